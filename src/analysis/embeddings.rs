@@ -1,4 +1,20 @@
 //! Embedding generation service using OpenAI
+//!
+//! [`EmbeddingService::local`] is a deterministic offline backend (feature
+//! hashing into [`LOCAL_DIMENSION`] dimensions) for CI and machines without
+//! an API key. Its vectors only compare meaningfully with other local ones.
+//!
+//! [`EmbeddingService::ollama`] runs a real embedding model on a local Ollama
+//! server; `from_env` picks it with `EMBEDDING_BACKEND=ollama`, and
+//! `OLLAMA_EMBED_MODEL` overrides the default `nomic-embed-text`.
+//!
+//! With [`EmbeddingService::with_cache_dir`] vectors are also kept on disk,
+//! one JSON file per sha256(model + text), so repeated texts ("+1", "спасибо")
+//! and re-indexing runs skip the API.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 
 use anyhow::Result;
 use async_openai::{
@@ -6,12 +22,103 @@ use async_openai::{
     types::{CreateEmbeddingRequestArgs, EmbeddingInput},
     Client as OpenAIClient,
 };
-use tracing::{debug, info};
+use futures::stream::{self, StreamExt};
+use sha2::{Digest, Sha256};
+use tracing::{debug, info, warn};
+
+use crate::integrations::ollama::{self, OllamaClient};
+
+/// Model name reported by the local backend
+pub const LOCAL_MODEL: &str = "local-hash";
+
+/// Dimension of local embeddings; matches `text-embedding-3-small` so local
+/// vectors fit the default Qdrant collection
+pub const LOCAL_DIMENSION: usize = 1536;
 
 /// Service for generating text embeddings
 pub struct EmbeddingService {
-    client: OpenAIClient<OpenAIConfig>,
+    backend: Backend,
     model: String,
+    cache: Option<EmbeddingCache>,
+}
+
+enum Backend {
+    OpenAI(OpenAIClient<OpenAIConfig>),
+    Ollama(OllamaClient),
+    Local,
+}
+
+/// Embedding cache hits and misses since the service was created
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// On-disk embedding cache; safe to share between concurrent batches
+struct EmbeddingCache {
+    dir: PathBuf,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl EmbeddingCache {
+    fn new(dir: PathBuf) -> Result<Self> {
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        })
+    }
+
+    fn path(&self, model: &str, text: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", cache_key(model, text)))
+    }
+
+    /// Cached vector, counting the lookup; unreadable entries count as misses
+    fn get(&self, model: &str, text: &str) -> Option<Vec<f32>> {
+        let cached = fs::read(self.path(model, text))
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<Vec<f32>>(&bytes).ok());
+        let counter = if cached.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        cached
+    }
+
+    /// Store a vector; written to a temp file and renamed so concurrent
+    /// readers never see a partial entry
+    fn put(&self, model: &str, text: &str, embedding: &[f32]) -> Result<()> {
+        let path = self.path(model, text);
+        let tmp = path.with_extension(format!("tmp-{}", uuid::Uuid::new_v4()));
+        fs::write(&tmp, serde_json::to_vec(embedding)?)?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Hex sha256 of the model name and the text as sent to the API
+fn cache_key(model: &str, text: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(model.as_bytes());
+    hasher.update([0u8]);
+    hasher.update(text.as_bytes());
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
 }
 
 impl EmbeddingService {
@@ -24,11 +131,67 @@ impl EmbeddingService {
         let client = OpenAIClient::with_config(config);
 
         Ok(Self {
-            client,
+            backend: Backend::OpenAI(client),
             model: "text-embedding-3-small".to_string(),
+            cache: None,
         })
     }
 
+    /// Deterministic local embeddings, no API key or network needed
+    pub fn local() -> Self {
+        Self {
+            backend: Backend::Local,
+            model: LOCAL_MODEL.to_string(),
+            cache: None,
+        }
+    }
+
+    /// Embeddings from a local Ollama server; `model` must be an embedding
+    /// model such as [`ollama::DEFAULT_EMBED_MODEL`]
+    pub fn ollama(client: OllamaClient, model: impl Into<String>) -> Self {
+        Self {
+            backend: Backend::Ollama(client),
+            model: model.into(),
+            cache: None,
+        }
+    }
+
+    /// Ollama when `EMBEDDING_BACKEND=ollama`, otherwise OpenAI when
+    /// `OPENAI_API_KEY` is set and local embeddings as the last resort
+    pub fn from_env() -> Self {
+        if std::env::var("EMBEDDING_BACKEND").is_ok_and(|b| b.eq_ignore_ascii_case("ollama")) {
+            let model = std::env::var("OLLAMA_EMBED_MODEL")
+                .ok()
+                .filter(|m| !m.trim().is_empty())
+                .unwrap_or_else(|| ollama::DEFAULT_EMBED_MODEL.to_string());
+            return Self::ollama(OllamaClient::new(), model);
+        }
+
+        Self::new().unwrap_or_else(|e| {
+            warn!("Using local embeddings ({})", e);
+            Self::local()
+        })
+    }
+
+    /// Whether this is the offline backend
+    pub fn is_local(&self) -> bool {
+        matches!(self.backend, Backend::Local)
+    }
+
+    /// Keep embeddings in `dir` (created if missing) and reuse them across runs
+    pub fn with_cache_dir(mut self, dir: impl AsRef<Path>) -> Result<Self> {
+        self.cache = Some(EmbeddingCache::new(dir.as_ref().to_path_buf())?);
+        Ok(self)
+    }
+
+    /// Cache hits and misses so far (zero without a cache dir)
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache
+            .as_ref()
+            .map(EmbeddingCache::stats)
+            .unwrap_or_default()
+    }
+
     /// Create with custom model
     pub fn with_model(model: impl Into<String>) -> Result<Self> {
         let mut service = Self::new()?;
@@ -53,30 +216,78 @@ impl EmbeddingService {
 
         debug!("Generating embeddings for {} texts", texts.len());
 
-        // Filter out empty texts and truncate long ones
-        let processed: Vec<String> = texts
-            .iter()
-            .map(|t| {
-                let trimmed = t.trim();
-                if trimmed.len() > 8000 {
-                    trimmed[..8000].to_string()
-                } else {
-                    trimmed.to_string()
-                }
-            })
-            .filter(|t| !t.is_empty())
-            .collect();
+        // Empty texts get empty vectors; cached ones are filled in right away
+        let mut result = vec![Vec::new(); texts.len()];
+        let mut pending: Vec<(usize, String)> = Vec::new();
+
+        for (idx, text) in texts.iter().enumerate() {
+            // Truncate long texts
+            let trimmed = text.trim();
+            let processed = if trimmed.len() > 8000 {
+                trimmed[..8000].to_string()
+            } else {
+                trimmed.to_string()
+            };
+            if processed.is_empty() {
+                continue;
+            }
 
-        if processed.is_empty() {
-            return Ok(vec![Vec::new(); texts.len()]);
+            match self
+                .cache
+                .as_ref()
+                .and_then(|cache| cache.get(&self.model, &processed))
+            {
+                Some(cached) => result[idx] = cached,
+                None => pending.push((idx, processed)),
+            }
+        }
+
+        if pending.is_empty() {
+            return Ok(result);
         }
 
+        let embeddings = match &self.backend {
+            Backend::OpenAI(client) => self.request_embeddings(client, &pending).await?,
+            Backend::Ollama(client) => {
+                // The embeddings endpoint takes one prompt per request
+                let mut embeddings = Vec::with_capacity(pending.len());
+                for (_, text) in &pending {
+                    embeddings.push(client.embed(text, &self.model).await?);
+                }
+                embeddings
+            }
+            Backend::Local => pending
+                .iter()
+                .map(|(_, text)| local_embedding(text))
+                .collect(),
+        };
+
+        // Map back to original indices
+        for ((idx, text), embedding) in pending.into_iter().zip(embeddings) {
+            if let Some(cache) = &self.cache {
+                if let Err(e) = cache.put(&self.model, &text, &embedding) {
+                    warn!("Failed to cache embedding: {}", e);
+                }
+            }
+            result[idx] = embedding;
+        }
+
+        Ok(result)
+    }
+
+    async fn request_embeddings(
+        &self,
+        client: &OpenAIClient<OpenAIConfig>,
+        pending: &[(usize, String)],
+    ) -> Result<Vec<Vec<f32>>> {
         let request = CreateEmbeddingRequestArgs::default()
             .model(&self.model)
-            .input(EmbeddingInput::StringArray(processed.clone()))
+            .input(EmbeddingInput::StringArray(
+                pending.iter().map(|(_, text)| text.clone()).collect(),
+            ))
             .build()?;
 
-        let response = self.client.embeddings().create(request).await?;
+        let response = client.embeddings().create(request).await?;
 
         info!(
             "Generated {} embeddings, tokens used: {}",
@@ -84,18 +295,33 @@ impl EmbeddingService {
             response.usage.total_tokens
         );
 
-        // Map back to original indices (empty texts get empty vectors)
-        let mut result = Vec::with_capacity(texts.len());
-        let mut embed_iter = response.data.into_iter();
+        Ok(response.data.into_iter().map(|e| e.embedding).collect())
+    }
 
-        for text in texts {
-            if text.trim().is_empty() {
-                result.push(Vec::new());
-            } else if let Some(embed) = embed_iter.next() {
-                result.push(embed.embedding);
-            }
-        }
+    /// Embed many texts in batches of `batch_size` with up to `concurrency`
+    /// requests in flight.
+    ///
+    /// Results keep the input order. `on_batch` receives the size of every
+    /// finished batch, for progress reporting.
+    pub async fn embed_all(
+        &self,
+        texts: &[String],
+        batch_size: usize,
+        concurrency: usize,
+        mut on_batch: impl FnMut(usize),
+    ) -> Result<Vec<Vec<f32>>> {
+        let mut batches = stream::iter(texts.chunks(batch_size.max(1)))
+            .map(|chunk| async move { (chunk.len(), self.embed_batch(chunk).await) })
+            .buffered(concurrency.max(1));
 
+        let mut result = Vec::with_capacity(texts.len());
+        while let Some((size, embeddings)) = batches.next().await {
+            let mut embeddings = embeddings?;
+            // Keep indices aligned even if the API returned fewer vectors
+            embeddings.resize(size, Vec::new());
+            result.extend(embeddings);
+            on_batch(size);
+        }
         Ok(result)
     }
 
@@ -105,11 +331,50 @@ impl EmbeddingService {
             "text-embedding-3-small" => 1536,
             "text-embedding-3-large" => 3072,
             "text-embedding-ada-002" => 1536,
+            "nomic-embed-text" => 768,
+            LOCAL_MODEL => LOCAL_DIMENSION,
             _ => 1536, // default
         }
     }
 }
 
+/// Feature-hashed bag of words and character trigrams, L2-normalized.
+///
+/// Trigrams let inflected forms ("индекс", "индексы") land close together.
+/// FNV-1a keeps the buckets stable across platforms and Rust versions.
+fn local_embedding(text: &str) -> Vec<f32> {
+    fn add(vector: &mut [f32], feature: &str, weight: f32) {
+        // FNV-1a
+        let hash = feature.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+        let bucket = (hash % LOCAL_DIMENSION as u64) as usize;
+        // The top bit picks the sign so collisions tend to cancel out
+        let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+        vector[bucket] += sign * weight;
+    }
+
+    let mut vector = vec![0.0f32; LOCAL_DIMENSION];
+    let lower = text.to_lowercase();
+    let words = lower
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty());
+    for word in words {
+        add(&mut vector, word, 1.0);
+
+        let chars: Vec<char> = format!("^{}$", word).chars().collect();
+        for trigram in chars.windows(3) {
+            add(&mut vector, &trigram.iter().collect::<String>(), 0.5);
+        }
+    }
+
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|x| *x /= norm);
+    }
+    vector
+}
+
 impl Default for EmbeddingService {
     fn default() -> Self {
         Self::new().expect("Failed to create embedding service")
@@ -123,11 +388,97 @@ mod tests {
     fn make_service(model: &str) -> EmbeddingService {
         let client = OpenAIClient::with_config(OpenAIConfig::new().with_api_key("test_key"));
         EmbeddingService {
-            client,
+            backend: Backend::OpenAI(client),
             model: model.to_string(),
+            cache: None,
         }
     }
 
+    #[tokio::test]
+    async fn local_embeddings_are_deterministic_and_offline() {
+        let service = EmbeddingService::local();
+        assert!(service.is_local());
+        assert_eq!(service.dimension(), LOCAL_DIMENSION);
+
+        let texts = vec![
+            "Индексация сообщений в Qdrant".to_string(),
+            " ".to_string(),
+            "индексация сообщения".to_string(),
+            "погода на выходных".to_string(),
+        ];
+        let embeddings = service.embed_batch(&texts).await.unwrap();
+        assert_eq!(embeddings.len(), 4);
+        assert!(embeddings[1].is_empty());
+        assert_eq!(embeddings[0].len(), LOCAL_DIMENSION);
+        assert_eq!(embeddings, service.embed_batch(&texts).await.unwrap());
+
+        let dot = |a: &[f32], b: &[f32]| a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>();
+        assert!((dot(&embeddings[0], &embeddings[0]) - 1.0).abs() < 1e-5);
+        assert!(dot(&embeddings[0], &embeddings[2]) > dot(&embeddings[0], &embeddings[3]));
+    }
+
+    #[tokio::test]
+    async fn cached_embeddings_skip_the_api() {
+        let dir = tempfile::tempdir().unwrap();
+        let service = make_service("text-embedding-3-small")
+            .with_cache_dir(dir.path())
+            .unwrap();
+        let cache = service.cache.as_ref().unwrap();
+        cache
+            .put("text-embedding-3-small", "+1", &[0.5, -0.25])
+            .unwrap();
+
+        // The test key would fail any real request
+        let embeddings = service
+            .embed_batch(&[" +1 ".to_string(), "".to_string(), "+1".to_string()])
+            .await
+            .unwrap();
+
+        assert_eq!(embeddings, vec![vec![0.5, -0.25], vec![], vec![0.5, -0.25]]);
+        assert_eq!(service.cache_stats(), CacheStats { hits: 2, misses: 0 });
+
+        // Keyed on the model too
+        assert!(cache.get("text-embedding-3-large", "+1").is_none());
+        assert_eq!(service.cache_stats().misses, 1);
+        assert_ne!(cache_key("a", "bc"), cache_key("ab", "c"));
+    }
+
+    #[tokio::test]
+    async fn ollama_backend_embeds_each_text_and_caches() {
+        use httpmock::prelude::*;
+        use serde_json::json;
+
+        let server = MockServer::start_async().await;
+        let embed_mock = server.mock(|when, then| {
+            when.method(POST).path("/api/embeddings").json_body(json!({
+                "model": "nomic-embed-text",
+                "prompt": "привет",
+                "keep_alive": -1
+            }));
+            then.status(200)
+                .json_body(json!({ "embedding": [0.1, 0.2] }));
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let service = EmbeddingService::ollama(
+            OllamaClient::with_url(&server.base_url()),
+            ollama::DEFAULT_EMBED_MODEL,
+        )
+        .with_cache_dir(dir.path())
+        .unwrap();
+        assert!(!service.is_local());
+        assert_eq!(service.dimension(), 768);
+
+        let texts = vec![" привет ".to_string(), "".to_string(), "привет".to_string()];
+        let embeddings = service.embed_batch(&texts).await.unwrap();
+        assert_eq!(embeddings, vec![vec![0.1, 0.2], vec![], vec![0.1, 0.2]]);
+        embed_mock.assert_calls(2);
+
+        // Second run is served from the cache
+        service.embed_batch(&texts).await.unwrap();
+        embed_mock.assert_calls(2);
+    }
+
     #[test]
     fn dimension_returns_expected_values() {
         let default = make_service("text-embedding-3-small");
@@ -180,6 +531,21 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn embed_all_keeps_order_and_reports_batches() {
+        let service = make_service("text-embedding-3-small");
+        let texts = vec![" ".to_string(); 5];
+        let mut batches = Vec::new();
+
+        let embeddings = service
+            .embed_all(&texts, 2, 3, |size| batches.push(size))
+            .await
+            .unwrap();
+
+        assert_eq!(embeddings.len(), 5);
+        assert_eq!(batches, vec![2, 2, 1]);
+    }
+
     #[test]
     fn dimension_unknown_model_returns_default() {
         let service = make_service("totally-unknown-model-xyz");
@@ -6,6 +6,31 @@ use tracing::{debug, info};
 
 use super::models::{AnalyzedMessage, ChatNode, MessageRelation, UserNode};
 
+/// One result row of an ad-hoc query, column name → value
+pub type GraphRow = serde_json::Map<String, serde_json::Value>;
+
+/// Users ranked by reply and reaction weight exchanged with `$user_id`, in
+/// either direction. Edges without a `weight` count as 1.
+const TOP_INTERACTIONS_QUERY: &str = "MATCH (u:User {user_id: $user_id})
+     CALL {
+         WITH u
+         MATCH (u)-[:SENT]->(:Message)-[r:REPLIES_TO]-(:Message)<-[:SENT]-(other:User)
+         RETURN other, coalesce(r.weight, 1.0) AS weight
+         UNION ALL
+         WITH u
+         MATCH (u)-[r:REACTED_TO]->(:Message)<-[:SENT]-(other:User)
+         RETURN other, coalesce(r.weight, 1.0) AS weight
+         UNION ALL
+         WITH u
+         MATCH (other:User)-[r:REACTED_TO]->(:Message)<-[:SENT]-(u)
+         RETURN other, coalesce(r.weight, 1.0) AS weight
+     }
+     WITH other, sum(weight) AS strength
+     WHERE other.user_id <> $user_id
+     RETURN other, strength
+     ORDER BY strength DESC
+     LIMIT $limit";
+
 /// Graph store backed by Neo4j
 pub struct GraphStore {
     graph: Graph,
@@ -105,12 +130,15 @@ impl GraphStore {
         Ok(())
     }
 
-    /// Create or update a message node with all relationships
+    /// Create or update a message node with all relationships.
+    ///
+    /// Nodes and edges are merged on the chat id/message id pair, so upserting
+    /// the same message again leaves the graph unchanged.
     pub async fn upsert_message(&self, msg: &AnalyzedMessage) -> Result<()> {
         // Create message node
         let q = query(
-            "MERGE (m:Message {uuid: $uuid})
-             SET m.telegram_id = $telegram_id,
+            "MERGE (m:Message {chat_id: $chat_id, telegram_id: $telegram_id})
+             SET m.uuid = $uuid,
                  m.text = $text,
                  m.timestamp = datetime($timestamp),
                  m.reaction_count = $reaction_count,
@@ -120,6 +148,7 @@ impl GraphStore {
                  m.sentiment = $sentiment",
         )
         .param("uuid", msg.id.to_string())
+        .param("chat_id", msg.chat_id)
         .param("telegram_id", msg.telegram_id)
         .param("text", msg.text.clone())
         .param("timestamp", msg.timestamp.to_rfc3339())
@@ -133,12 +162,13 @@ impl GraphStore {
 
         // Create SENT_BY relationship
         let sent_by = query(
-            "MATCH (m:Message {uuid: $uuid})
+            "MATCH (m:Message {chat_id: $chat_id, telegram_id: $telegram_id})
              MERGE (u:User {user_id: $sender_id})
              ON CREATE SET u.name = $sender_name
              MERGE (u)-[:SENT]->(m)",
         )
-        .param("uuid", msg.id.to_string())
+        .param("chat_id", msg.chat_id)
+        .param("telegram_id", msg.telegram_id)
         .param("sender_id", msg.sender_id)
         .param("sender_name", msg.sender_name.clone());
 
@@ -146,13 +176,13 @@ impl GraphStore {
 
         // Create IN_CHAT relationship
         let in_chat = query(
-            "MATCH (m:Message {uuid: $uuid})
+            "MATCH (m:Message {chat_id: $chat_id, telegram_id: $telegram_id})
              MERGE (c:Chat {chat_id: $chat_id})
              ON CREATE SET c.name = $chat_name
              MERGE (m)-[:IN_CHAT]->(c)",
         )
-        .param("uuid", msg.id.to_string())
         .param("chat_id", msg.chat_id)
+        .param("telegram_id", msg.telegram_id)
         .param("chat_name", msg.chat_name.clone());
 
         self.graph.run(in_chat).await?;
@@ -160,12 +190,11 @@ impl GraphStore {
         // Create REPLIES_TO relationship if applicable
         if let Some(reply_to_id) = msg.reply_to_id {
             let replies_to = query(
-                "MATCH (m:Message {uuid: $uuid})
-                 MATCH (target:Message {telegram_id: $reply_to_id})
-                 WHERE (target)-[:IN_CHAT]->(:Chat {chat_id: $chat_id})
+                "MATCH (m:Message {chat_id: $chat_id, telegram_id: $telegram_id})
+                 MATCH (target:Message {chat_id: $chat_id, telegram_id: $reply_to_id})
                  MERGE (m)-[:REPLIES_TO]->(target)",
             )
-            .param("uuid", msg.id.to_string())
+            .param("telegram_id", msg.telegram_id)
             .param("reply_to_id", reply_to_id)
             .param("chat_id", msg.chat_id);
 
@@ -187,25 +216,25 @@ impl GraphStore {
         Ok(count)
     }
 
-    /// Create a relationship between nodes
+    /// Create or strengthen a relationship between nodes.
+    ///
+    /// Endpoints are users (numeric ids) or messages (uuids). Each observation,
+    /// keyed by the endpoints and the `message_id` in its `properties`, adds
+    /// its weight to the edge once, so replaying the same relations leaves the
+    /// graph unchanged. Each edge remembers its last 1000 observation keys.
     pub async fn create_relation(&self, relation: &MessageRelation) -> Result<()> {
         let rel_type = relation.relation_type.as_str();
-
-        // Dynamic relationship type requires string interpolation
-        // This is safe because rel_type comes from our enum
-        let cypher = format!(
-            "MATCH (a {{uuid: $from_id}})
-             MATCH (b {{uuid: $to_id}})
-             MERGE (a)-[r:{}]->(b)
-             SET r.weight = $weight,
-                 r.properties = $properties",
-            rel_type
+        let cypher = relation_cypher(
+            rel_type,
+            endpoint_label(&relation.from_id),
+            endpoint_label(&relation.to_id),
         );
 
         let q = query(&cypher)
             .param("from_id", relation.from_id.clone())
             .param("to_id", relation.to_id.clone())
             .param("weight", relation.weight as f64)
+            .param("observation", observation_key(relation))
             .param("properties", relation.properties.to_string());
 
         self.graph.run(q).await?;
@@ -239,16 +268,32 @@ impl GraphStore {
 
         while let Some(row) = result.next().await? {
             if let Ok(node) = row.get::<Node>("other") {
-                let user = UserNode {
-                    user_id: node.get("user_id").unwrap_or(0),
-                    name: node.get("name").unwrap_or_default(),
-                    username: node.get("username").ok(),
-                    message_count: node.get::<i64>("message_count").unwrap_or(0) as u32,
-                    reactions_received: node.get::<i64>("reactions_received").unwrap_or(0) as u32,
-                    avg_sentiment: node.get("avg_sentiment").ok(),
-                    active_chats: Vec::new(),
-                };
-                users.push(user);
+                users.push(user_from_node(&node));
+            }
+        }
+
+        Ok(users)
+    }
+
+    /// Users with the strongest reply/reaction ties to `user_id`, strongest first.
+    ///
+    /// A user without such relationships (or unknown to the graph) gets an empty list.
+    pub async fn top_interactions(
+        &self,
+        user_id: i64,
+        limit: usize,
+    ) -> Result<Vec<(UserNode, f64)>> {
+        let q = query(TOP_INTERACTIONS_QUERY)
+            .param("user_id", user_id)
+            .param("limit", limit as i64);
+
+        let mut result = self.graph.execute(q).await?;
+        let mut users = Vec::new();
+
+        while let Some(row) = result.next().await? {
+            if let Ok(node) = row.get::<Node>("other") {
+                let strength = row.get::<f64>("strength").unwrap_or(0.0);
+                users.push((user_from_node(&node), strength));
             }
         }
 
@@ -314,6 +359,35 @@ impl GraphStore {
         Ok(messages)
     }
 
+    /// Run an arbitrary Cypher statement and return its rows as JSON objects
+    pub async fn query_rows(&self, cypher: &str) -> Result<Vec<GraphRow>> {
+        debug!("Running Cypher: {}", cypher);
+        let mut result = self.graph.execute(query(cypher)).await?;
+        let mut rows = Vec::new();
+
+        while let Some(row) = result.next().await? {
+            rows.push(row.to::<GraphRow>()?);
+        }
+
+        Ok(rows)
+    }
+
+    /// Like [`GraphStore::query_rows`], but inside a transaction that is always
+    /// rolled back, so nothing the statement does is ever persisted
+    pub async fn query_rows_read_only(&self, cypher: &str) -> Result<Vec<GraphRow>> {
+        debug!("Running read-only Cypher: {}", cypher);
+        let mut txn = self.graph.start_txn().await?;
+        let mut result = txn.execute(query(cypher)).await?;
+        let mut rows = Vec::new();
+
+        while let Some(row) = result.next(txn.handle()).await? {
+            rows.push(row.to::<GraphRow>()?);
+        }
+
+        txn.rollback().await?;
+        Ok(rows)
+    }
+
     /// Get graph statistics
     pub async fn stats(&self) -> Result<GraphStats> {
         let counts = query(
@@ -339,6 +413,71 @@ impl GraphStore {
     }
 }
 
+/// Observation keys remembered per edge for deduplication
+const MAX_OBSERVATIONS: usize = 1000;
+
+/// Node label of a relation endpoint: user ids are numeric, messages go by uuid
+fn endpoint_label(id: &str) -> &'static str {
+    if id.parse::<i64>().is_ok() {
+        "User"
+    } else {
+        "Message"
+    }
+}
+
+/// `MATCH` for one endpoint, on the label's indexed key
+fn endpoint_match(var: &str, label: &str, param: &str) -> String {
+    match label {
+        "User" => format!("MATCH ({}:User {{user_id: toInteger(${})}})", var, param),
+        _ => format!("MATCH ({}:Message {{uuid: ${}}})", var, param),
+    }
+}
+
+/// What makes an observation distinct: both endpoints and the message behind it
+fn observation_key(relation: &MessageRelation) -> String {
+    let message_id = match relation.properties.get("message_id") {
+        Some(serde_json::Value::String(id)) => id.clone(),
+        Some(id) => id.to_string(),
+        None => String::new(),
+    };
+    format!("{}|{}|{}", relation.from_id, relation.to_id, message_id)
+}
+
+/// Cypher merging one `rel_type` edge; see [`GraphStore::create_relation`]
+fn relation_cypher(rel_type: &str, from_label: &str, to_label: &str) -> String {
+    // Dynamic relationship type requires string interpolation
+    // This is safe because rel_type comes from our enum
+    // Edges merged elsewhere (REPLIES_TO from upsert_message) start without
+    // weight or observations, hence the coalesce
+    format!(
+        "{}
+         {}
+         MERGE (a)-[r:{}]->(b)
+         WITH r, coalesce(r.weight, 0.0) AS weight, coalesce(r.observations, []) AS seen
+         WITH r, weight, seen, $observation IN seen AS known
+         SET r.weight = CASE WHEN known THEN weight ELSE weight + $weight END,
+             r.observations = CASE WHEN known THEN seen
+                                   ELSE (seen + $observation)[-{}..] END,
+             r.properties = $properties",
+        endpoint_match("a", from_label, "from_id"),
+        endpoint_match("b", to_label, "to_id"),
+        rel_type,
+        MAX_OBSERVATIONS
+    )
+}
+
+fn user_from_node(node: &Node) -> UserNode {
+    UserNode {
+        user_id: node.get("user_id").unwrap_or(0),
+        name: node.get("name").unwrap_or_default(),
+        username: node.get("username").ok(),
+        message_count: node.get::<i64>("message_count").unwrap_or(0) as u32,
+        reactions_received: node.get::<i64>("reactions_received").unwrap_or(0) as u32,
+        avg_sentiment: node.get("avg_sentiment").ok(),
+        active_chats: Vec::new(),
+    }
+}
+
 /// Graph statistics
 #[derive(Debug, Default)]
 pub struct GraphStats {
@@ -351,6 +490,7 @@ pub struct GraphStats {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::analysis::models::RelationType;
 
     #[test]
     fn test_graph_stats_default() {
@@ -406,6 +546,52 @@ mod tests {
         assert!(stats.relation_count >= stats.message_count);
     }
 
+    #[test]
+    fn relation_writes_merge_and_count_each_observation_once() {
+        let cypher = relation_cypher(
+            RelationType::ReactedTo.as_str(),
+            endpoint_label("42"),
+            endpoint_label("5f0c1a9e-msg"),
+        );
+        assert!(cypher.contains("MATCH (a:User {user_id: toInteger($from_id)})"));
+        assert!(cypher.contains("MATCH (b:Message {uuid: $to_id})"));
+        assert!(cypher.contains("MERGE (a)-[r:REACTED_TO]->(b)"));
+        assert!(!cypher.contains("CREATE ("));
+        assert!(cypher.contains("$observation IN seen AS known"));
+        assert!(cypher.contains("CASE WHEN known THEN weight ELSE weight + $weight END"));
+        assert!(cypher.contains("(seen + $observation)[-1000..]"));
+    }
+
+    #[test]
+    fn observations_are_keyed_by_endpoints_and_message() {
+        use serde_json::json;
+
+        let relation = |properties: serde_json::Value| MessageRelation {
+            from_id: "42".to_string(),
+            to_id: "7".to_string(),
+            relation_type: RelationType::InteractsWith,
+            weight: 1.0,
+            properties,
+        };
+        let first = observation_key(&relation(json!({"message_id": 10, "emoji": "👍"})));
+        let replay = observation_key(&relation(json!({"emoji": "❤️", "message_id": 10})));
+        let other = observation_key(&relation(json!({"message_id": 11})));
+        assert_eq!(first, "42|7|10");
+        assert_eq!(first, replay);
+        assert_ne!(first, other);
+    }
+
+    #[test]
+    fn top_interactions_query_weighs_replies_and_reactions_both_ways() {
+        assert!(TOP_INTERACTIONS_QUERY.contains("-[r:REPLIES_TO]-(:Message)"));
+        assert_eq!(
+            TOP_INTERACTIONS_QUERY.matches("[r:REACTED_TO]->").count(),
+            2
+        );
+        assert!(TOP_INTERACTIONS_QUERY.contains("WHERE other.user_id <> $user_id"));
+        assert!(TOP_INTERACTIONS_QUERY.contains("ORDER BY strength DESC"));
+    }
+
     #[tokio::test]
     #[ignore] // Requires Neo4j connection
     async fn test_graph_store_from_env_requires_password() {
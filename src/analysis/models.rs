@@ -51,7 +51,7 @@ impl AnalyzedMessage {
         timestamp: DateTime<Utc>,
     ) -> Self {
         Self {
-            id: Uuid::new_v4(),
+            id: message_uuid(chat_id, telegram_id),
             telegram_id,
             chat_id,
             chat_name,
@@ -70,6 +70,12 @@ impl AnalyzedMessage {
     }
 }
 
+/// Stable id of a Telegram message, so re-indexing it updates the same
+/// Qdrant point and graph node instead of adding a copy
+pub fn message_uuid(chat_id: i64, telegram_id: i32) -> Uuid {
+    Uuid::from_u64_pair(chat_id as u64, telegram_id as u32 as u64)
+}
+
 /// Relationship between messages or users
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MessageRelation {
@@ -195,6 +201,27 @@ mod tests {
         assert_eq!(msg.timestamp, ts);
     }
 
+    #[test]
+    fn message_id_is_stable_per_chat_and_message() {
+        let ts = chrono::Utc::now();
+        let msg = |chat_id, telegram_id| {
+            AnalyzedMessage::new(
+                telegram_id,
+                chat_id,
+                String::new(),
+                1,
+                String::new(),
+                String::new(),
+                ts,
+            )
+            .id
+        };
+
+        assert_eq!(msg(-100, 5), msg(-100, 5));
+        assert_ne!(msg(-100, 5), msg(-100, 6));
+        assert_ne!(msg(-100, 5), msg(-200, 5));
+    }
+
     #[test]
     fn relation_type_as_str_matches_expected_values() {
         assert_eq!(RelationType::RepliesTo.as_str(), "REPLIES_TO");
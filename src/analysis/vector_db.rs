@@ -1,23 +1,32 @@
 //! Vector database integration with Qdrant
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
 use qdrant_client::qdrant::{
-    CreateCollectionBuilder, Distance, FieldCondition, Filter, Match, PointStruct,
+    CreateCollectionBuilder, Distance, FieldCondition, Filter, Match, PointStruct, Range,
     SearchPointsBuilder, UpsertPointsBuilder, Value as QdrantValue, VectorParamsBuilder,
 };
 use qdrant_client::Qdrant;
 use std::collections::HashMap;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 use uuid::Uuid;
 
 use super::models::{AnalyzedMessage, SearchResult};
 
 const COLLECTION_NAME: &str = "telegram_messages";
 
+/// Points sent per upsert request by default
+pub const DEFAULT_BATCH_SIZE: usize = 256;
+
+/// Log batch upsert progress every this many batches
+const PROGRESS_EVERY_BATCHES: usize = 10;
+
 /// Vector store backed by Qdrant
 pub struct VectorStore {
     client: Qdrant,
     dimension: usize,
+    /// Abort a batch upsert on the first failed batch instead of skipping it
+    stop_on_error: bool,
 }
 
 impl VectorStore {
@@ -28,6 +37,7 @@ impl VectorStore {
         Ok(Self {
             client,
             dimension: 1536, // text-embedding-3-small dimension
+            stop_on_error: true,
         })
     }
 
@@ -38,6 +48,12 @@ impl VectorStore {
         Ok(store)
     }
 
+    /// Whether a failed batch aborts the upsert (default) or is logged and skipped
+    pub fn stop_on_error(mut self, stop: bool) -> Self {
+        self.stop_on_error = stop;
+        self
+    }
+
     /// Initialize the collection if it doesn't exist
     pub async fn init_collection(&self) -> Result<()> {
         let collections = self.client.list_collections().await?;
@@ -76,38 +92,87 @@ impl VectorStore {
                     return None;
                 }
 
-                let mut payload: HashMap<String, QdrantValue> = HashMap::new();
-                payload.insert("telegram_id".into(), (msg.telegram_id as i64).into());
-                payload.insert("chat_id".into(), msg.chat_id.into());
-                payload.insert("chat_name".into(), msg.chat_name.clone().into());
-                payload.insert("sender_id".into(), msg.sender_id.into());
-                payload.insert("sender_name".into(), msg.sender_name.clone().into());
-                payload.insert("text".into(), msg.text.clone().into());
-                payload.insert("timestamp".into(), msg.timestamp.to_rfc3339().into());
-                payload.insert("reaction_count".into(), (msg.reaction_count as i64).into());
-                payload.insert("is_outgoing".into(), msg.is_outgoing.into());
-
                 Some(PointStruct::new(
                     msg.id.to_string(),
                     embedding.clone(),
-                    payload,
+                    message_payload(msg),
                 ))
             })
             .collect();
 
+        self.upsert_batch(points, DEFAULT_BATCH_SIZE).await
+    }
+
+    /// Upsert points in requests of at most `batch_size` points.
+    ///
+    /// Returns the number of points upserted. A failed batch aborts the upsert
+    /// or, with `stop_on_error(false)`, is logged and skipped.
+    pub async fn upsert_batch(&self, points: Vec<PointStruct>, batch_size: usize) -> Result<usize> {
         if points.is_empty() {
             return Ok(0);
         }
 
-        let count = points.len();
-        debug!("Upserting {} points to Qdrant", count);
+        let total = points.len();
+        let batch_size = batch_size.max(1);
+        let batches = total.div_ceil(batch_size);
+        debug!(
+            "Upserting {} points to Qdrant in {} batches of {}",
+            total, batches, batch_size
+        );
+
+        let mut points = points.into_iter();
+        let mut upserted = 0;
+        let mut failed = 0;
+
+        for index in 0..batches {
+            let batch: Vec<PointStruct> = points.by_ref().take(batch_size).collect();
+            let count = batch.len();
+
+            match self
+                .client
+                .upsert_points(UpsertPointsBuilder::new(COLLECTION_NAME, batch))
+                .await
+            {
+                Ok(_) => upserted += count,
+                Err(err) if self.stop_on_error => {
+                    return Err(err).with_context(|| {
+                        format!(
+                            "{} failed ({} points upserted before it)",
+                            batch_label(index, batch_size, total, batches),
+                            upserted
+                        )
+                    });
+                }
+                Err(err) => {
+                    warn!(
+                        "{} failed, skipping: {}",
+                        batch_label(index, batch_size, total, batches),
+                        err
+                    );
+                    failed += 1;
+                }
+            }
 
-        self.client
-            .upsert_points(UpsertPointsBuilder::new(COLLECTION_NAME, points))
-            .await?;
+            if (index + 1) % PROGRESS_EVERY_BATCHES == 0 && index + 1 < batches {
+                info!(
+                    "Upserted {}/{} points ({}/{} batches)",
+                    upserted,
+                    total,
+                    index + 1,
+                    batches
+                );
+            }
+        }
 
-        info!("Successfully upserted {} messages", count);
-        Ok(count)
+        if failed > 0 {
+            warn!(
+                "Upserted {}/{} points, {} of {} batches failed",
+                upserted, total, failed, batches
+            );
+        } else {
+            info!("Successfully upserted {} messages", upserted);
+        }
+        Ok(upserted)
     }
 
     /// Search for similar messages
@@ -205,6 +270,17 @@ impl VectorStore {
         Ok(search_results)
     }
 
+    /// Search within a chat, sender and/or date range; an empty filter searches everything
+    pub async fn search_filtered(
+        &self,
+        query_embedding: Vec<f32>,
+        filter: SearchFilter,
+        limit: u64,
+    ) -> Result<Vec<SearchResult>> {
+        let filter = (!filter.is_empty()).then_some(filter);
+        self.search(query_embedding, limit, filter).await
+    }
+
     /// Delete messages by chat ID
     pub async fn delete_by_chat(&self, chat_id: i64) -> Result<()> {
         use qdrant_client::qdrant::DeletePointsBuilder;
@@ -240,6 +316,36 @@ impl VectorStore {
     }
 }
 
+/// Point payload of a message. `chat_id`, `sender_id` and `timestamp_unix`
+/// back the [`SearchFilter`] conditions.
+fn message_payload(msg: &AnalyzedMessage) -> HashMap<String, QdrantValue> {
+    let mut payload: HashMap<String, QdrantValue> = HashMap::new();
+    payload.insert("telegram_id".into(), (msg.telegram_id as i64).into());
+    payload.insert("chat_id".into(), msg.chat_id.into());
+    payload.insert("chat_name".into(), msg.chat_name.clone().into());
+    payload.insert("sender_id".into(), msg.sender_id.into());
+    payload.insert("sender_name".into(), msg.sender_name.clone().into());
+    payload.insert("text".into(), msg.text.clone().into());
+    payload.insert("timestamp".into(), msg.timestamp.to_rfc3339().into());
+    payload.insert("timestamp_unix".into(), msg.timestamp.timestamp().into());
+    payload.insert("reaction_count".into(), (msg.reaction_count as i64).into());
+    payload.insert("is_outgoing".into(), msg.is_outgoing.into());
+    payload
+}
+
+/// `batch 3/10 (points 512..768)`, for progress and error messages
+fn batch_label(index: usize, batch_size: usize, total: usize, batches: usize) -> String {
+    let start = index * batch_size;
+    let end = (start + batch_size).min(total);
+    format!(
+        "batch {}/{} (points {}..{})",
+        index + 1,
+        batches,
+        start,
+        end
+    )
+}
+
 /// Filter for vector search
 #[derive(Debug, Default)]
 pub struct SearchFilter {
@@ -247,6 +353,10 @@ pub struct SearchFilter {
     pub sender_id: Option<i64>,
     pub is_outgoing: Option<bool>,
     pub min_reactions: Option<u32>,
+    /// Earliest message time, inclusive
+    pub date_from: Option<DateTime<Utc>>,
+    /// Latest message time, inclusive
+    pub date_to: Option<DateTime<Utc>>,
 }
 
 impl SearchFilter {
@@ -269,6 +379,25 @@ impl SearchFilter {
         self
     }
 
+    pub fn date_from(mut self, from: DateTime<Utc>) -> Self {
+        self.date_from = Some(from);
+        self
+    }
+
+    pub fn date_to(mut self, to: DateTime<Utc>) -> Self {
+        self.date_to = Some(to);
+        self
+    }
+
+    /// No condition set
+    pub fn is_empty(&self) -> bool {
+        self.chat_id.is_none()
+            && self.sender_id.is_none()
+            && self.is_outgoing.is_none()
+            && self.date_from.is_none()
+            && self.date_to.is_none()
+    }
+
     fn into_qdrant_filter(self) -> Filter {
         let mut conditions = Vec::new();
 
@@ -317,6 +446,21 @@ impl SearchFilter {
             );
         }
 
+        if self.date_from.is_some() || self.date_to.is_some() {
+            conditions.push(
+                FieldCondition {
+                    key: "timestamp_unix".to_string(),
+                    range: Some(Range {
+                        gte: self.date_from.map(|d| d.timestamp() as f64),
+                        lte: self.date_to.map(|d| d.timestamp() as f64),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }
+                .into(),
+            );
+        }
+
         Filter::must(conditions)
     }
 }
@@ -526,6 +670,56 @@ mod tests {
         assert_eq!(stats.dimension, 768);
     }
 
+    #[test]
+    fn date_range_maps_to_timestamp_range_condition() {
+        let from = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let to = DateTime::from_timestamp(1_700_086_400, 0).unwrap();
+
+        let filter = SearchFilter::new().chat(42).date_from(from).date_to(to);
+        assert!(!filter.is_empty());
+        let filter = filter.into_qdrant_filter();
+        assert_eq!(filter.must.len(), 2);
+
+        let field = extract_field(&filter.must[1]);
+        assert_eq!(field.key, "timestamp_unix");
+        let range = field.range.as_ref().expect("range");
+        assert_eq!(range.gte, Some(1_700_000_000.0));
+        assert_eq!(range.lte, Some(1_700_086_400.0));
+
+        let open_ended = SearchFilter::new().date_from(from).into_qdrant_filter();
+        let range = extract_field(&open_ended.must[0]).range.unwrap();
+        assert_eq!(range.lte, None);
+    }
+
+    #[test]
+    fn payload_carries_filter_fields() {
+        let msg = AnalyzedMessage::new(
+            1,
+            -100,
+            "chat".into(),
+            7,
+            "Alice".into(),
+            "hi".into(),
+            DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+        );
+        let payload = message_payload(&msg);
+
+        assert_eq!(payload["chat_id"].as_integer(), Some(-100));
+        assert_eq!(payload["sender_id"].as_integer(), Some(7));
+        assert_eq!(payload["timestamp_unix"].as_integer(), Some(1_700_000_000));
+        assert!(SearchFilter::new().is_empty());
+    }
+
+    #[test]
+    fn batch_label_names_the_point_range() {
+        assert_eq!(
+            batch_label(0, DEFAULT_BATCH_SIZE, 600, 3),
+            "batch 1/3 (points 0..256)"
+        );
+        assert_eq!(batch_label(2, 256, 600, 3), "batch 3/3 (points 512..600)");
+        assert_eq!(600usize.div_ceil(DEFAULT_BATCH_SIZE), 3);
+    }
+
     #[test]
     fn collection_name_constant() {
         assert_eq!(COLLECTION_NAME, "telegram_messages");
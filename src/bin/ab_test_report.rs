@@ -9,6 +9,7 @@ use dotenvy::dotenv;
 use mysql_async::Pool;
 use std::env;
 use telegram_reader::analytics::ab_testing::{fetch_ab_metrics, print_ab_report};
+use telegram_reader::db::wait_until_ready;
 
 #[derive(Parser, Debug)]
 #[command(name = "ab_test_report")]
@@ -47,6 +48,7 @@ async fn main() -> Result<()> {
     let args = Args::parse();
 
     let pool = Pool::new(build_mysql_url().as_str());
+    wait_until_ready(&pool).await?;
 
     match fetch_ab_metrics(&pool, &args.bot_name, &args.experiment, args.days).await {
         Ok(metrics) => {
@@ -38,7 +38,7 @@ async fn main() -> Result<()> {
     let args = Args::parse();
 
     // Acquire session lock
-    let _lock = SessionLock::acquire()?;
+    let _lock = SessionLock::acquire().await?;
 
     // Connect to Telegram
     let client = get_client().await?;
@@ -6,6 +6,6 @@ use telegram_reader::commands::autoanswer;
 async fn main() -> anyhow::Result<()> {
     dotenvy::dotenv().ok();
     tracing_subscriber::fmt::init();
-    autoanswer::run("gpt-4o-mini").await?;
+    autoanswer::run("gpt-4o-mini", None).await?;
     Ok(())
 }
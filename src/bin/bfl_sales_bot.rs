@@ -4,6 +4,7 @@
 //! и A/B тестированием промптов. Переписан с Python-версии `bfl_sales_bot.py`.
 
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::{anyhow, Context, Result};
 use dotenvy::dotenv;
@@ -11,6 +12,7 @@ use mysql_async::{prelude::*, Pool};
 use rand::distributions::WeightedIndex;
 use rand::prelude::*;
 use regex::Regex;
+use telegram_reader::db::wait_until_ready;
 use telegram_reader::integrations::openai::ChatMessage;
 use telegram_reader::integrations::OpenAIClient;
 use teloxide::dispatching::UpdateFilterExt;
@@ -20,6 +22,10 @@ use tracing::{error, info};
 
 const BOT_NAME: &str = "BFL_sales_bot";
 
+/// Retries of OpenAI 429/5xx responses, so a blip doesn't drop a reply mid-conversation
+const OPENAI_MAX_RETRIES: u32 = 3;
+const OPENAI_RETRY_DELAY: Duration = Duration::from_millis(500);
+
 const SALES_SYSTEM_PROMPT: &str = r#"Ты - профессиональный консультант по массажным креслам компании Relaxio.
 
 ТВОЯ ЦЕЛЬ: Помочь клиенту выбрать массажное кресло и довести до покупки.
@@ -116,7 +122,7 @@ async fn main() -> Result<()> {
         )
         .await?,
     );
-    let ai = OpenAIClient::from_env()?;
+    let ai = OpenAIClient::from_env()?.with_retry(OPENAI_MAX_RETRIES, OPENAI_RETRY_DELAY);
 
     let state = Arc::new(AppState {
         db,
@@ -354,6 +360,7 @@ impl MySqlLogger {
             .pass(Some(password));
 
         let pool = Pool::new(opts);
+        wait_until_ready(&pool).await?;
         let logger = Self {
             pool,
             bot_name: bot_name.to_string(),
@@ -11,6 +11,7 @@ use mysql_async::Pool;
 use std::env;
 use std::path::PathBuf;
 use telegram_reader::analytics::BotAnalytics;
+use telegram_reader::db::wait_until_ready;
 
 #[derive(Parser, Debug)]
 #[command(name = "bot_analytics")]
@@ -50,6 +51,7 @@ async fn main() -> Result<()> {
     let args = Args::parse();
 
     let pool = Pool::new(build_mysql_url().as_str());
+    wait_until_ready(&pool).await?;
     let analytics = BotAnalytics::new(pool.clone());
 
     let metrics = analytics.analyze(args.bots, args.days).await?;
@@ -171,7 +171,7 @@ async fn main() -> Result<()> {
         anyhow::bail!("Provide message ids/links or set --recent to react to latest messages");
     }
 
-    let _lock = SessionLock::acquire()?;
+    let _lock = SessionLock::acquire().await?;
     let client = get_client().await?;
 
     // Resolve chat using find_chat (handles config aliases, usernames, numeric ids)
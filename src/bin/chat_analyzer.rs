@@ -2,7 +2,10 @@
 
 use clap::Parser;
 use std::path::PathBuf;
-use telegram_reader::commands::chat_analyzer::{run, AnalyzerConfig, LlmProvider, OutputFormat};
+use telegram_reader::commands::chat_analyzer::{
+    run, AnalyzerConfig, LlmProvider, OutputFormat, SectionLimits,
+};
+use telegram_reader::engagement::EngagementWeights;
 
 #[derive(Parser)]
 #[command(name = "chat_analyzer")]
@@ -27,7 +30,7 @@ struct Args {
     #[arg(long, default_value = "30")]
     days: i64,
 
-    /// Output format: json | markdown | both
+    /// Output format: json | markdown | both | csv | none
     #[arg(long, default_value = "both")]
     output_format: String,
 
@@ -35,6 +38,10 @@ struct Args {
     #[arg(long, default_value = "analysis_results")]
     output_dir: PathBuf,
 
+    /// Also append each result as one JSON line to this file (time series across runs)
+    #[arg(long)]
+    append_to: Option<PathBuf>,
+
     /// Custom prompt file (Markdown)
     #[arg(long)]
     prompt: Option<PathBuf>,
@@ -62,6 +69,46 @@ struct Args {
     /// Max tokens for LLM response
     #[arg(long, default_value = "2000")]
     max_tokens: u32,
+
+    /// Important sender ids/usernames to always keep (comma-separated, adds to config.yml)
+    #[arg(long, value_delimiter = ',')]
+    important: Vec<String>,
+
+    /// Fail when the LLM output doesn't match the analysis schema
+    #[arg(long, default_value_t = false)]
+    strict: bool,
+
+    /// Only analyze messages newer than the last successful run
+    #[arg(long, default_value_t = false)]
+    since_last: bool,
+
+    /// Engagement score weights, e.g. messages=1,reactions=2,replies=1,recency=0.5,half_life=7
+    #[arg(long)]
+    engagement_weights: Option<String>,
+
+    /// Rank key participants by the local engagement score even if the LLM scores them
+    #[arg(long, default_value_t = false)]
+    local_engagement: bool,
+
+    /// Keep at most N topics in the report, by mentions (0 = all)
+    #[arg(long, default_value_t = 10)]
+    max_topics: usize,
+
+    /// Keep at most N key discussions, by message count (0 = all)
+    #[arg(long, default_value_t = 10)]
+    max_discussions: usize,
+
+    /// Keep at most N key participants, by engagement score (0 = all)
+    #[arg(long, default_value_t = 15)]
+    max_participants: usize,
+
+    /// Output language of free-text fields, e.g. English (category/sentiment stay canonical)
+    #[arg(long)]
+    language: Option<String>,
+
+    /// Attach recent photos to the prompt (claude/gemini; capped count and size)
+    #[arg(long, default_value_t = false)]
+    include_vision: bool,
 }
 
 #[tokio::main]
@@ -71,6 +118,11 @@ async fn main() -> anyhow::Result<()> {
 
     let args = Args::parse();
 
+    let engagement_weights = match args.engagement_weights.as_deref() {
+        Some(spec) => EngagementWeights::parse(spec)?,
+        None => EngagementWeights::default(),
+    };
+
     let cfg = AnalyzerConfig {
         message_limit: args.limit,
         days_back: args.days,
@@ -85,6 +137,19 @@ async fn main() -> anyhow::Result<()> {
         output_dir: args.output_dir,
         prompt_path: args.prompt,
         verbose: !args.quiet,
+        important_senders: args.important,
+        strict: args.strict,
+        since_last: args.since_last,
+        engagement_weights,
+        local_engagement: args.local_engagement,
+        section_limits: SectionLimits {
+            topics: args.max_topics,
+            discussions: args.max_discussions,
+            participants: args.max_participants,
+        },
+        append_to: args.append_to,
+        language: args.language,
+        include_vision: args.include_vision,
     };
 
     let result = run(&args.chat, cfg).await?;
@@ -7,6 +7,10 @@ use std::collections::HashMap;
 use telegram_reader::chat::resolve_chat;
 use telegram_reader::config::{ChatEntity, Config};
 use telegram_reader::get_client;
+use telegram_reader::links::{
+    domain_of, expand_client, expand_url, extract_urls, is_shortener, DomainReport,
+    DEFAULT_EXPAND_TIMEOUT,
+};
 use telegram_reader::session::SessionLock;
 
 #[derive(Parser)]
@@ -23,6 +27,14 @@ struct Cli {
     /// Show top N senders
     #[arg(short, long, default_value = "10")]
     top: usize,
+
+    /// Show top linked domains and who posted them
+    #[arg(long)]
+    domains: bool,
+
+    /// Expand shortened links (HEAD request) before counting domains
+    #[arg(long)]
+    expand_links: bool,
 }
 
 #[derive(Default)]
@@ -40,7 +52,7 @@ async fn main() -> Result<()> {
     dotenvy::dotenv().ok();
     let cli = Cli::parse();
 
-    let _lock = SessionLock::acquire()?;
+    let _lock = SessionLock::acquire().await?;
     let client = get_client().await?;
 
     let config = Config::new();
@@ -60,6 +72,8 @@ async fn main() -> Result<()> {
     let mut messages_with_media = 0;
     let mut first_msg_date: Option<DateTime<Utc>> = None;
     let mut last_msg_date: Option<DateTime<Utc>> = None;
+    // (sender, url) pairs for the domain report
+    let mut links: Vec<(String, String)> = Vec::new();
 
     let mut messages_iter = client.iter_messages(&peer);
     let mut count = 0;
@@ -85,6 +99,17 @@ async fn main() -> Result<()> {
             messages_with_media += 1;
         }
 
+        if cli.domains {
+            let sender_name = message
+                .sender()
+                .and_then(|s| s.name())
+                .unwrap_or("Unknown")
+                .to_string();
+            for url in extract_urls(message.text()) {
+                links.push((sender_name.clone(), url));
+            }
+        }
+
         if let Some(sender) = message.sender() {
             let sender_id: i64 = sender.id().to_string().parse().unwrap_or(0);
             let sender_name = sender.name().unwrap_or("Unknown").to_string();
@@ -156,9 +181,84 @@ async fn main() -> Result<()> {
         );
     }
 
+    if cli.domains {
+        let report = build_domain_report(links, cli.expand_links).await?;
+        print_domain_report(&report, cli.top);
+    }
+
     Ok(())
 }
 
+/// Count link domains, optionally resolving shorteners to their targets
+async fn build_domain_report(
+    links: Vec<(String, String)>,
+    expand_links: bool,
+) -> Result<DomainReport> {
+    let mut report = DomainReport::default();
+    let client = if expand_links {
+        Some(expand_client(DEFAULT_EXPAND_TIMEOUT)?)
+    } else {
+        None
+    };
+    let mut expanded: HashMap<String, String> = HashMap::new();
+
+    for (sender, url) in links {
+        let shortened = domain_of(&url).is_some_and(|d| is_shortener(&d));
+        let url = match &client {
+            Some(client) if shortened => {
+                if let Some(target) = expanded.get(&url) {
+                    target.clone()
+                } else {
+                    let target = expand_url(client, &url).await.unwrap_or_else(|e| {
+                        eprintln!("Failed to expand {}: {}", url, e);
+                        url.clone()
+                    });
+                    expanded.insert(url, target.clone());
+                    target
+                }
+            }
+            _ => url,
+        };
+        report.add_url(&sender, &url);
+    }
+
+    Ok(report)
+}
+
+fn print_domain_report(report: &DomainReport, top: usize) {
+    println!("\n=== Top {} Domains ===\n", top);
+    if report.is_empty() {
+        println!("No links found");
+        return;
+    }
+
+    println!("Total links: {}\n", report.total_links());
+    println!("{:<4} {:<30} {:>6}  Posted by", "#", "Domain", "Links");
+    println!("{}", "-".repeat(70));
+
+    for (i, stats) in report.top(top).iter().enumerate() {
+        let senders: Vec<String> = stats
+            .top_senders(3)
+            .iter()
+            .map(|(name, count)| format!("{} ({})", truncate(name, 20), count))
+            .collect();
+        let more = stats.senders.len().saturating_sub(3);
+        let suffix = if more > 0 {
+            format!(" +{} more", more)
+        } else {
+            String::new()
+        };
+        println!(
+            "{:<4} {:<30} {:>6}  {}{}",
+            i + 1,
+            truncate(&stats.domain, 29),
+            stats.count,
+            senders.join(", "),
+            suffix
+        );
+    }
+}
+
 fn truncate(s: &str, max_len: usize) -> String {
     if s.chars().count() <= max_len {
         s.to_string()
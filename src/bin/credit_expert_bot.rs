@@ -9,6 +9,7 @@ use anyhow::{Context, Result};
 use dotenvy::dotenv;
 use mysql_async::{prelude::*, Pool};
 use regex::Regex;
+use telegram_reader::db::wait_until_ready;
 use telegram_reader::integrations::openai::ChatMessage;
 use telegram_reader::integrations::OpenAIClient;
 use teloxide::dispatching::UpdateFilterExt;
@@ -393,6 +394,7 @@ async fn main() -> Result<()> {
 
     // Initialize MySQL pool
     let pool = Pool::new(build_mysql_url().as_str());
+    wait_until_ready(&pool).await?;
 
     // Initialize OpenAI client
     let ai = OpenAIClient::from_env()?;
@@ -31,7 +31,7 @@ async fn main() -> Result<()> {
     dotenvy::dotenv().ok();
     let cli = Cli::parse();
 
-    let _lock = SessionLock::acquire()?;
+    let _lock = SessionLock::acquire().await?;
     let client = get_client().await?;
 
     // Find chat by name, ID, or username
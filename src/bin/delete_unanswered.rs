@@ -44,7 +44,7 @@ async fn main() -> Result<()> {
     dotenvy::dotenv().ok();
     let cli = Cli::parse();
 
-    let _lock = SessionLock::acquire()?;
+    let _lock = SessionLock::acquire().await?;
     let client = get_client().await?;
     let config = Config::new();
 
@@ -6,10 +6,12 @@ use telegram_reader::commands::delete_zoom;
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     tracing_subscriber::fmt::init();
-    let args: Vec<String> = env::args().collect();
+    let args: Vec<String> = env::args().skip(1).collect();
+    let yes = args.iter().any(|arg| arg == "--yes" || arg == "-y");
     let username = args
-        .get(1)
-        .ok_or_else(|| anyhow::anyhow!("Usage: delete_zoom_messages <username>"))?;
-    delete_zoom::run(username, 3000).await?;
+        .iter()
+        .find(|arg| !arg.starts_with('-'))
+        .ok_or_else(|| anyhow::anyhow!("Usage: delete_zoom_messages <username> [--yes]"))?;
+    delete_zoom::run(username, 3000, None, yes).await?;
     Ok(())
 }
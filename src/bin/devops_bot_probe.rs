@@ -88,7 +88,7 @@ async fn main() -> Result<()> {
     let args = Args::parse();
     let timeout = Duration::from_secs(args.timeout_seconds);
 
-    let _lock = SessionLock::acquire()?;
+    let _lock = SessionLock::acquire().await?;
     let mut client = get_client().await?;
 
     let bot_peer = find_chat(&client, &args.bot).await?;
@@ -23,7 +23,7 @@ async fn main() -> anyhow::Result<()> {
     tracing_subscriber::fmt::init();
 
     let args = Args::parse();
-    let _lock = SessionLock::acquire()?;
+    let _lock = SessionLock::acquire().await?;
     let client = get_client().await?;
 
     let path = download_chat::download_chat(&client, args.chat_id, args.limit).await?;
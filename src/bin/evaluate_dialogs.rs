@@ -9,6 +9,7 @@ use dotenvy::dotenv;
 use mysql_async::Pool;
 use std::env;
 use telegram_reader::analytics::DialogEvaluator;
+use telegram_reader::db::wait_until_ready;
 
 #[derive(Parser, Debug)]
 #[command(name = "evaluate_dialogs")]
@@ -44,6 +45,7 @@ async fn main() -> Result<()> {
     let args = Args::parse();
 
     let pool = Pool::new(build_mysql_url().as_str());
+    wait_until_ready(&pool).await?;
     let evaluator = DialogEvaluator::new(pool.clone())?.with_model(args.model);
 
     let results = evaluator.evaluate_recent_sessions(args.limit).await?;
@@ -12,7 +12,7 @@ use telegram_reader::session::{get_client, SessionLock};
 
 /// Export chat by searching dialogs for name match
 async fn export_chat_by_name(chat_name: &str, limit: usize) -> Result<String> {
-    let _lock = SessionLock::acquire()?;
+    let _lock = SessionLock::acquire().await?;
     let client = get_client().await?;
 
     // Search through all dialogs
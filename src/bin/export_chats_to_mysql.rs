@@ -11,6 +11,7 @@ use mysql_async::{prelude::*, Pool};
 use std::env;
 use tracing::info;
 
+use telegram_reader::db::wait_until_ready;
 use telegram_reader::get_client;
 use telegram_reader::session::SessionLock;
 
@@ -70,6 +71,7 @@ async fn main() -> Result<()> {
     })?;
 
     let pool = Pool::new(mysql_url.as_str());
+    wait_until_ready(&pool).await?;
     let mut conn = pool.get_conn().await?;
 
     // Create table if not exists
@@ -97,7 +99,7 @@ async fn main() -> Result<()> {
     .await?;
 
     // Connect to Telegram
-    let _lock = SessionLock::acquire()?;
+    let _lock = SessionLock::acquire().await?;
     let client = get_client().await?;
 
     info!("Connected to Telegram");
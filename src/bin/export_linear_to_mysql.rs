@@ -9,6 +9,7 @@ use dotenvy::dotenv;
 use mysql_async::{prelude::*, Pool};
 use serde::Deserialize;
 use std::env;
+use telegram_reader::db::wait_until_ready;
 use tracing::info;
 
 const LINEAR_API_URL: &str = "https://api.linear.app/graphql";
@@ -228,6 +229,7 @@ async fn main() -> Result<()> {
     })?;
 
     let pool = Pool::new(mysql_url.as_str());
+    wait_until_ready(&pool).await?;
     let mut conn = pool.get_conn().await?;
 
     // Create table if not exists
@@ -1,10 +1,11 @@
 //! Get active chats binary.
 
 use telegram_reader::commands::active_chats;
+use telegram_reader::commands::list_chats::ChatsOutput;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     tracing_subscriber::fmt::init();
-    active_chats::run(20).await?;
+    active_chats::run(20, ChatsOutput::Table).await?;
     Ok(())
 }
@@ -1,10 +1,15 @@
 //! CLI tool for indexing Telegram messages to vector and graph databases
 
+use std::path::PathBuf;
+
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 use telegram_reader::commands::{
+    export::DateWindow,
     index::{index_all_chats, IndexConfig},
-    search::{find_contacts, get_stats, search_messages, SearchConfig},
+    search::{
+        find_collaborators, find_contacts, get_stats, search_local, search_messages, SearchConfig,
+    },
 };
 use tracing::info;
 use tracing_subscriber::EnvFilter;
@@ -40,6 +45,14 @@ enum Commands {
         /// Qdrant URL
         #[arg(long, env = "QDRANT_URL", default_value = "http://localhost:6333")]
         qdrant_url: String,
+
+        /// Embedding requests in flight at once
+        #[arg(long, default_value = "4")]
+        concurrency: usize,
+
+        /// Cache embeddings in this directory and reuse them across runs
+        #[arg(long, env = "EMBEDDING_CACHE_DIR")]
+        embedding_cache: Option<PathBuf>,
     },
 
     /// Search indexed messages semantically
@@ -62,6 +75,19 @@ enum Commands {
         /// Only show outgoing messages
         #[arg(long)]
         outgoing: bool,
+
+        /// Only messages from this day on (YYYY-MM-DD)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Only messages up to this day, inclusive (YYYY-MM-DD)
+        #[arg(long)]
+        until: Option<String>,
+
+        /// Search exported JSONL files under this directory instead of Qdrant
+        /// (local embeddings, no API key needed)
+        #[arg(long, value_name = "DIR")]
+        local: Option<PathBuf>,
     },
 
     /// Find users who interact most with a given user
@@ -74,6 +100,16 @@ enum Commands {
         limit: usize,
     },
 
+    /// Rank the users a given user replies to and reacts with most
+    Collaborators {
+        /// User ID to find collaborators for
+        user_id: i64,
+
+        /// Number of results
+        #[arg(short, long, default_value = "20")]
+        limit: usize,
+    },
+
     /// Show database statistics
     Stats,
 }
@@ -99,6 +135,8 @@ async fn main() -> Result<()> {
             no_graph,
             no_embeddings,
             qdrant_url,
+            concurrency,
+            embedding_cache,
         } => {
             let config = IndexConfig {
                 qdrant_url,
@@ -106,6 +144,8 @@ async fn main() -> Result<()> {
                 use_graph_db: !no_graph,
                 limit,
                 generate_embeddings: !no_embeddings,
+                concurrency,
+                embedding_cache,
             };
 
             info!("Starting indexing with config:");
@@ -113,6 +153,7 @@ async fn main() -> Result<()> {
             info!("  Vector DB: {}", config.use_vector_db);
             info!("  Graph DB: {}", config.use_graph_db);
             info!("  Embeddings: {}", config.generate_embeddings);
+            info!("  Concurrency: {}", config.concurrency);
 
             let results = index_all_chats(&config).await?;
 
@@ -136,18 +177,48 @@ async fn main() -> Result<()> {
             );
         }
 
+        Commands::Search {
+            query,
+            limit,
+            local: Some(dir),
+            ..
+        } => {
+            let matches = search_local(&dir, &query, limit as usize).await?;
+
+            println!("\n=== Local Search Results for '{}' ===\n", query);
+            for (i, m) in matches.iter().enumerate() {
+                println!(
+                    "{}. [Score: {:.3}] {} in {}:{}",
+                    i + 1,
+                    m.score,
+                    m.sender,
+                    m.file.display(),
+                    m.message_id
+                );
+                println!("   {}", m.date.format("%Y-%m-%d %H:%M"));
+                println!("   {}", truncate(&m.text, 100));
+                println!();
+            }
+        }
+
         Commands::Search {
             query,
             limit,
             chat_id,
             sender_id,
             outgoing,
+            since,
+            until,
+            local: None,
         } => {
+            let window = DateWindow::parse(since.as_deref(), until.as_deref())?;
             let config = SearchConfig {
                 limit,
                 chat_id,
                 sender_id,
                 outgoing_only: outgoing,
+                date_from: window.since,
+                date_to: window.until,
                 ..Default::default()
             };
 
@@ -183,6 +254,24 @@ async fn main() -> Result<()> {
             }
         }
 
+        Commands::Collaborators { user_id, limit } => {
+            let collaborators = find_collaborators(user_id, limit).await?;
+
+            println!("\n=== Closest collaborators of User {} ===\n", user_id);
+            if collaborators.is_empty() {
+                println!("No replies or reactions involving this user");
+            }
+            for (i, collaborator) in collaborators.iter().enumerate() {
+                println!(
+                    "{}. {} (ID: {}) - strength {:.1}",
+                    i + 1,
+                    collaborator.name,
+                    collaborator.user_id,
+                    collaborator.strength
+                );
+            }
+        }
+
         Commands::Stats => {
             let stats = get_stats().await?;
 
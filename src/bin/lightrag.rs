@@ -32,7 +32,7 @@ struct Cli {
     #[arg(long, default_value_t = 5)]
     results: usize,
 
-    /// Retrieval mode: hybrid | vector | graph | naive | local | global
+    /// Retrieval mode: hybrid | vector | graph | bm25 | naive | local | global
     #[arg(long, default_value = "hybrid")]
     mode: String,
 
@@ -105,9 +105,11 @@ fn print_results(query: &str, results: &[RetrievalResult]) {
 
     for (idx, res) in results.iter().enumerate() {
         println!(
-            "{}. score: {:.3} | source: {}",
+            "{}. score: {:.3} (vector {:.3}, bm25 {:.2}) | source: {}",
             idx + 1,
             res.score,
+            res.vector_score,
+            res.bm25_score,
             res.chunk.source
         );
 
@@ -1,8 +1,11 @@
 //! Linear bot - creates Linear issues from Telegram messages.
 //!
 //! Usage: linear_bot
+//!
+//! With `LINEAR_WATCH_CHAT` set, watches that chat for flagged messages
+//! (`LINEAR_TRIGGER_KEYWORDS`, default `/bug`); otherwise answers `!linear` commands.
 
-use telegram_reader::commands::linear_bot;
+use telegram_reader::commands::linear_bot::{self, LinearBotConfig};
 use telegram_reader::{get_client, SessionLock};
 
 #[tokio::main]
@@ -10,10 +13,15 @@ async fn main() -> anyhow::Result<()> {
     dotenvy::dotenv().ok();
     tracing_subscriber::fmt::init();
 
-    let _lock = SessionLock::acquire()?;
-    let telegram = get_client().await?;
+    let _lock = SessionLock::acquire().await?;
+    let mut telegram = get_client().await?;
 
-    linear_bot::run_linear_bot(telegram.client).await?;
+    let config = LinearBotConfig::from_env();
+    if config.chat.is_some() {
+        linear_bot::run(&mut telegram, config).await?;
+    } else {
+        linear_bot::run_linear_bot(&telegram).await?;
+    }
 
     Ok(())
 }
@@ -4,7 +4,8 @@
 //!   cargo run --bin n8n_backup -- backup
 //!   cargo run --bin n8n_backup -- list
 //!   cargo run --bin n8n_backup -- cleanup
-//!   cargo run --bin n8n_backup -- restore --file /path/to/backup.tar.gz
+//!   cargo run --bin n8n_backup -- verify --file /path/to/backup.tar.gz
+//!   cargo run --bin n8n_backup -- restore --file /path/to/backup.tar.gz [--dry-run]
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
@@ -29,11 +30,20 @@ enum Action {
         /// Path to backup file
         #[arg(long)]
         file: PathBuf,
+        /// List what would be created/updated without calling the API
+        #[arg(long)]
+        dry_run: bool,
     },
     /// List all available backups
     List,
     /// Remove old backups based on retention policy
     Cleanup,
+    /// Check a backup file against its .sha256 checksum
+    Verify {
+        /// Path to backup file
+        #[arg(long)]
+        file: PathBuf,
+    },
 }
 
 #[tokio::main]
@@ -49,8 +59,22 @@ async fn main() -> Result<()> {
             let archive = backup.create_backup().await?;
             println!("✅ Backup created: {}", archive.display());
         }
-        Action::Restore { file } => {
-            backup.restore_backup(&file).await?;
+        Action::Restore { file, dry_run } => {
+            let report = backup.restore_backup(&file, dry_run).await?;
+            if report.dry_run {
+                println!("🔍 Dry run, nothing was changed:");
+            }
+            for workflow in &report.workflows {
+                println!("  {}", workflow);
+            }
+            println!(
+                "📦 Workflows: {} ok, {} failed",
+                report.succeeded(),
+                report.failed()
+            );
+            if report.failed() > 0 {
+                anyhow::bail!("{} workflow(s) failed to restore", report.failed());
+            }
         }
         Action::List => {
             let backups = backup.list_backups().await?;
@@ -66,6 +90,10 @@ async fn main() -> Result<()> {
         Action::Cleanup => {
             backup.cleanup_old_backups().await?;
         }
+        Action::Verify { file } => {
+            backup.verify_backup(&file).await?;
+            println!("✓ {} matches its checksum", file.display());
+        }
     }
 
     Ok(())
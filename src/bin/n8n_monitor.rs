@@ -46,7 +46,11 @@ async fn main() -> Result<()> {
             }
         }
         Action::Check => {
-            let is_healthy = monitor.run_check().await;
+            let report = monitor.check_all().await;
+            for (name, healthy) in &report {
+                println!("{}: {}", name, if *healthy { "✅ up" } else { "❌ down" });
+            }
+            let is_healthy = report.iter().all(|(_, healthy)| *healthy);
             std::process::exit(if is_healthy { 0 } else { 1 });
         }
     }
@@ -9,6 +9,6 @@ async fn main() -> anyhow::Result<()> {
     let args: Vec<String> = env::args().collect();
     let chat = args.get(1).map(|s| s.as_str()).unwrap_or("chat_alpha");
     // By default, delete unengaged messages (like the Python version)
-    read::run(chat, None, true, false).await?;
+    read::run(chat, None, true, None).await?;
     Ok(())
 }
@@ -1,10 +1,13 @@
 //! Search messages in a chat by keyword or regex
 
+use std::io::IsTerminal;
+
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use clap::Parser;
 use regex::Regex;
 use telegram_reader::chat::resolve_chat;
+use telegram_reader::commands::search::{context_blocks, highlight, match_ranges, Highlight};
 use telegram_reader::config::{ChatEntity, Config};
 use telegram_reader::get_client;
 use telegram_reader::session::SessionLock;
@@ -34,6 +37,21 @@ struct Cli {
     /// Only show my own messages
     #[arg(long)]
     outgoing: bool,
+
+    /// Show N messages before and after each match (like grep -C)
+    #[arg(short = 'C', long, default_value = "0")]
+    context: usize,
+
+    /// Print Markdown (matches in **bold**) instead of terminal colors
+    #[arg(long)]
+    markdown: bool,
+}
+
+/// A message that passed the sender filters
+struct Candidate {
+    date: DateTime<Utc>,
+    sender: String,
+    text: String,
 }
 
 #[tokio::main]
@@ -41,7 +59,7 @@ async fn main() -> Result<()> {
     dotenvy::dotenv().ok();
     let cli = Cli::parse();
 
-    let _lock = SessionLock::acquire()?;
+    let _lock = SessionLock::acquire().await?;
     let client = get_client().await?;
 
     let config = Config::new();
@@ -62,9 +80,17 @@ async fn main() -> Result<()> {
 
     println!("Searching for '{}' in {}...\n", cli.query, cli.chat);
 
+    let style = if cli.markdown {
+        Highlight::Markdown
+    } else if std::io::stdout().is_terminal() {
+        Highlight::Ansi
+    } else {
+        Highlight::Plain
+    };
+
     let mut messages_iter = client.iter_messages(&peer);
     let mut count = 0;
-    let mut found = 0;
+    let mut candidates: Vec<Candidate> = Vec::new();
 
     while let Some(message) = messages_iter.next().await? {
         if count >= cli.limit {
@@ -94,27 +120,47 @@ async fn main() -> Result<()> {
             }
         }
 
-        if regex.is_match(text) {
-            found += 1;
-
-            let msg_date =
-                DateTime::from_timestamp(message.date().timestamp(), 0).unwrap_or_else(Utc::now);
-
-            let sender_name = message
-                .sender()
-                .map(|s| s.name().unwrap_or("Unknown").to_string())
-                .unwrap_or_else(|| "Unknown".to_string());
+        let date = DateTime::from_timestamp(message.date().timestamp(), 0).unwrap_or_else(Utc::now);
+        let sender = message
+            .sender()
+            .map(|s| s.name().unwrap_or("Unknown").to_string())
+            .unwrap_or_else(|| "Unknown".to_string());
+
+        candidates.push(Candidate {
+            date,
+            sender,
+            text: text.to_string(),
+        });
+    }
 
-            println!(
-                "--- #{} [{}] {} ---",
-                found,
-                msg_date.format("%Y-%m-%d %H:%M"),
-                sender_name
-            );
+    // Oldest first, so context reads like the chat
+    candidates.reverse();
+    let matches: Vec<usize> = candidates
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| regex.is_match(&c.text))
+        .map(|(i, _)| i)
+        .collect();
 
-            // Highlight matches
-            let highlighted = regex.replace_all(text, "\x1b[1;33m$0\x1b[0m");
-            println!("{}\n", highlighted);
+    let mut found = 0;
+    for (n, block) in context_blocks(candidates.len(), &matches, cli.context)
+        .iter()
+        .enumerate()
+    {
+        if n > 0 && cli.context > 0 {
+            println!("--\n");
+        }
+        let lines = candidates.iter().enumerate().take(block.end);
+        for (i, candidate) in lines.skip(block.start) {
+            let date = candidate.date.format("%Y-%m-%d %H:%M");
+            if block.matches.contains(&i) {
+                found += 1;
+                println!("--- #{} [{}] {} ---", found, date, candidate.sender);
+                let ranges = match_ranges(&regex, &candidate.text);
+                println!("{}\n", highlight(&candidate.text, &ranges, style));
+            } else {
+                println!("    [{}] {}: {}\n", date, candidate.sender, candidate.text);
+            }
         }
     }
 
@@ -2,27 +2,48 @@
 //!
 //! Usage:
 //!   cargo run --bin send_message -- <target> <message>
+//!   cargo run --bin send_message -- <target> --template message.txt [--strict]
+//!   cargo run --bin send_message -- chat_a,chat_b,@user "News for {chat}" [--delay-ms 3000]
 //!
 //! Target can be:
 //!   - User ID: 123456789
 //!   - Username: @username
 //!   - Chat name: chat_name (from config)
+//!   - A comma-separated list of the above
+//!
+//! Templates may use {{chat_title}}, {{date}} and {{first_name}} (DMs only).
+//! `{chat}` is replaced with the target as written in the list.
+
+use std::path::PathBuf;
+use std::time::Duration;
 
 use clap::Parser;
-use telegram_reader::commands::send_message;
-use telegram_reader::Result;
+use telegram_reader::commands::send_message::{self, MessageText};
+use telegram_reader::{Error, Result};
 
 #[derive(Parser)]
 #[command(name = "send_message")]
 #[command(about = "Send message to Telegram user or chat")]
 struct Args {
-    /// Target: user ID, @username, or chat name from config
+    /// Target: user ID, @username, chat name from config, or a comma-separated list
     #[arg(index = 1)]
     target: String,
 
     /// Message text to send
-    #[arg(index = 2)]
-    message: String,
+    #[arg(index = 2, required_unless_present = "template")]
+    message: Option<String>,
+
+    /// Template file rendered per recipient (instead of message text)
+    #[arg(long, conflicts_with = "message")]
+    template: Option<PathBuf>,
+
+    /// Fail on unknown template variables instead of rendering them empty
+    #[arg(long, default_value_t = false)]
+    strict: bool,
+
+    /// Pause between sends when there are several targets (ms)
+    #[arg(long, default_value_t = 3000)]
+    delay_ms: u64,
 }
 
 #[tokio::main]
@@ -33,5 +54,29 @@ async fn main() -> Result<()> {
         .with_env_filter("telegram_reader=info")
         .init();
 
-    send_message::run(&args.target, &args.message).await
+    let message = match (args.template, args.message) {
+        (Some(path), _) => MessageText::from_template_file(&path, args.strict)?,
+        (None, Some(text)) => MessageText::Plain(text),
+        (None, None) => unreachable!("clap requires message or --template"),
+    };
+
+    let targets = send_message::parse_targets(&args.target);
+    if let [target] = targets.as_slice() {
+        return send_message::run(target, &message).await;
+    }
+    if targets.is_empty() {
+        return Err(Error::InvalidArgument("No target given".to_string()));
+    }
+
+    let report =
+        send_message::run_many(&targets, &message, Duration::from_millis(args.delay_ms)).await?;
+    println!("{}", report);
+    if report.failed() > 0 {
+        return Err(Error::TelegramError(format!(
+            "{} of {} sends failed",
+            report.failed(),
+            report.outcomes.len()
+        )));
+    }
+    Ok(())
 }
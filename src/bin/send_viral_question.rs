@@ -6,6 +6,6 @@ use telegram_reader::commands::send_viral;
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     tracing_subscriber::fmt::init();
-    send_viral::run().await?;
+    send_viral::run(Default::default()).await?;
     Ok(())
 }
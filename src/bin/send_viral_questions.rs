@@ -9,7 +9,7 @@ use tokio::time::{sleep, Duration};
 
 /// Send message to chat by searching dialogs for matching name
 async fn send_to_chat_by_name(chat_name: &str, message: &str) -> Result<()> {
-    let _lock = SessionLock::acquire()?;
+    let _lock = SessionLock::acquire().await?;
     let client = get_client().await?;
 
     // Search through all dialogs
@@ -11,6 +11,7 @@ use rand::thread_rng;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::Duration;
+use telegram_reader::db::wait_until_ready;
 use teloxide::dispatching::UpdateFilterExt;
 use teloxide::prelude::*;
 use teloxide::types::{
@@ -853,6 +854,7 @@ impl MySqlLogger {
             .pass(Some(password));
 
         let pool = Pool::new(opts);
+        wait_until_ready(&pool).await?;
         Ok(Self {
             pool,
             bot_name: BOT_NAME.to_string(),
@@ -1,11 +1,21 @@
 //! Chat operations and entity resolution
 
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use futures::stream::{self, Stream, StreamExt};
 use grammers_client::types::peer::Peer;
+use grammers_client::types::Message;
 use grammers_client::Client;
+use grammers_tl_types as tl;
+use tracing::warn;
 
 use crate::config::ChatEntity;
 use crate::error::{Error, Result};
 
+/// How many consecutive FLOOD_WAIT errors [`fetch_messages`] sleeps through before giving up
+pub(crate) const MAX_FLOOD_RETRIES: usize = 3;
+
 /// Resolve a ChatEntity to an actual Peer
 pub async fn resolve_chat(client: &Client, entity: &ChatEntity) -> Result<Peer> {
     match entity {
@@ -148,9 +158,476 @@ pub async fn find_chat(client: &Client, name: &str) -> Result<Peer> {
         .ok_or_else(|| Error::ChatNotFound(format!("Chat '{}' not found", name)))
 }
 
+/// Convert a Peer to InputPeer for raw API calls
+pub fn input_peer(peer: &Peer) -> tl::enums::InputPeer {
+    match peer {
+        Peer::User(user) => {
+            let (user_id, access_hash) = match &user.raw {
+                tl::enums::User::User(u) => (u.id, u.access_hash.unwrap_or(0)),
+                tl::enums::User::Empty(u) => (u.id, 0),
+            };
+            tl::enums::InputPeer::User(tl::types::InputPeerUser {
+                user_id,
+                access_hash,
+            })
+        }
+        Peer::Channel(channel) => tl::enums::InputPeer::Channel(tl::types::InputPeerChannel {
+            channel_id: channel.raw.id,
+            access_hash: channel.raw.access_hash.unwrap_or(0),
+        }),
+        Peer::Group(group) => match &group.raw {
+            tl::enums::Chat::Chat(c) => {
+                tl::enums::InputPeer::Chat(tl::types::InputPeerChat { chat_id: c.id })
+            }
+            tl::enums::Chat::Channel(c) => {
+                tl::enums::InputPeer::Channel(tl::types::InputPeerChannel {
+                    channel_id: c.id,
+                    access_hash: c.access_hash.unwrap_or(0),
+                })
+            }
+            _ => tl::enums::InputPeer::Empty,
+        },
+    }
+}
+
+/// Fields of a message that [`fetch_messages`] relies on
+pub trait FetchedMessage {
+    fn date(&self) -> DateTime<Utc>;
+    fn text(&self) -> &str;
+}
+
+impl FetchedMessage for Message {
+    fn date(&self) -> DateTime<Utc> {
+        Message::date(self)
+    }
+
+    fn text(&self) -> &str {
+        Message::text(self)
+    }
+}
+
+/// Source of chat history, newest message first.
+///
+/// Implemented for any stream of messages so tests can feed a scripted history.
+pub trait MessageSource {
+    type Message: FetchedMessage;
+
+    fn next_message(
+        &mut self,
+    ) -> impl std::future::Future<Output = Option<Result<Self::Message>>> + Send;
+}
+
+impl<S, M> MessageSource for S
+where
+    S: Stream<Item = Result<M>> + Unpin + Send,
+    M: FetchedMessage + Send,
+{
+    type Message = M;
+
+    async fn next_message(&mut self) -> Option<Result<M>> {
+        self.next().await
+    }
+}
+
+/// Predicate applied to every message inside the date range
+pub type MessageFilter<M> = Box<dyn Fn(&M) -> bool + Send + Sync>;
+
+/// Options for [`fetch_messages`].
+///
+/// Whichever of `limit` and `since` is reached first stops the scan.
+pub struct FetchOptions<M = Message> {
+    /// Maximum number of messages to return (after filtering)
+    pub limit: Option<usize>,
+    /// Stop at the first message older than this
+    pub since: Option<DateTime<Utc>>,
+    /// Skip messages newer than this
+    pub until: Option<DateTime<Utc>>,
+    /// Keep only messages matching the predicate
+    pub filter: Option<MessageFilter<M>>,
+}
+
+impl<M> Default for FetchOptions<M> {
+    fn default() -> Self {
+        Self {
+            limit: None,
+            since: None,
+            until: None,
+            filter: None,
+        }
+    }
+}
+
+impl<M> FetchOptions<M> {
+    fn accepts(&self, msg: &M) -> bool {
+        self.filter.as_ref().is_none_or(|filter| filter(msg))
+    }
+}
+
+/// Wrap the grammers history iterator into a [`MessageSource`]
+pub fn history<'a>(
+    client: &'a Client,
+    peer: &'a Peer,
+) -> impl MessageSource<Message = Message> + 'a {
+    Box::pin(stream::unfold(
+        client.iter_messages(peer),
+        |mut iter| async move {
+            match iter.next().await {
+                Ok(Some(msg)) => Some((Ok(msg), iter)),
+                Ok(None) => None,
+                Err(e) => Some((Err(Error::from(e)), iter)),
+            }
+        },
+    ))
+}
+
+/// Fetch messages from a chat honoring limit, date range and filter
+pub async fn fetch_messages(
+    client: &Client,
+    peer: &Peer,
+    options: FetchOptions,
+) -> Result<Vec<Message>> {
+    collect_messages(history(client, peer), &options).await
+}
+
+/// Stream messages from a chat one by one, with the same rules as [`fetch_messages`]
+pub fn scan_messages<'a>(
+    client: &'a Client,
+    peer: &'a Peer,
+    options: FetchOptions,
+) -> MessageScan<impl MessageSource<Message = Message> + 'a> {
+    MessageScan::new(history(client, peer), options)
+}
+
+/// Streaming counterpart of [`fetch_messages`] for loops that act on each
+/// message as it arrives or stop on conditions of their own
+pub struct MessageScan<S: MessageSource> {
+    source: S,
+    options: FetchOptions<S::Message>,
+    returned: usize,
+    finished: bool,
+}
+
+impl<S: MessageSource> MessageScan<S> {
+    pub fn new(source: S, options: FetchOptions<S::Message>) -> Self {
+        Self {
+            source,
+            options,
+            returned: 0,
+            finished: false,
+        }
+    }
+
+    /// Next message matching the options; `None` once the history, the date
+    /// range or the limit is exhausted
+    pub async fn next(&mut self) -> Result<Option<S::Message>> {
+        if self.finished {
+            return Ok(None);
+        }
+
+        match next_in_range(&mut self.source, &self.options, self.returned).await? {
+            Some(msg) => {
+                self.returned += 1;
+                Ok(Some(msg))
+            }
+            None => {
+                self.finished = true;
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// Drain a [`MessageSource`] according to `options`, sleeping through flood waits
+pub async fn collect_messages<S>(
+    mut source: S,
+    options: &FetchOptions<S::Message>,
+) -> Result<Vec<S::Message>>
+where
+    S: MessageSource,
+{
+    let mut messages = Vec::new();
+    while let Some(msg) = next_in_range(&mut source, options, messages.len()).await? {
+        messages.push(msg);
+    }
+    Ok(messages)
+}
+
+/// Next message of `source` that `options` accept, given `returned` messages so far
+async fn next_in_range<S>(
+    source: &mut S,
+    options: &FetchOptions<S::Message>,
+    returned: usize,
+) -> Result<Option<S::Message>>
+where
+    S: MessageSource,
+{
+    if options.limit.is_some_and(|limit| returned >= limit) {
+        return Ok(None);
+    }
+
+    let mut flood_retries = 0;
+    while let Some(item) = source.next_message().await {
+        let msg = match item {
+            Ok(msg) => {
+                flood_retries = 0;
+                msg
+            }
+            Err(err) => {
+                let wait = flood_wait_seconds(&err.to_string());
+                match wait {
+                    Some(secs) if flood_retries < MAX_FLOOD_RETRIES => {
+                        flood_retries += 1;
+                        warn!("Flood wait {}s while fetching messages, retrying", secs);
+                        tokio::time::sleep(Duration::from_secs(secs)).await;
+                        continue;
+                    }
+                    _ => return Err(err),
+                }
+            }
+        };
+
+        let date = msg.date();
+        if options.since.is_some_and(|since| date < since) {
+            return Ok(None);
+        }
+        if options.until.is_some_and(|until| date > until) {
+            continue;
+        }
+        if !options.accepts(&msg) {
+            continue;
+        }
+
+        return Ok(Some(msg));
+    }
+
+    Ok(None)
+}
+
+/// Extract flood wait seconds from an error string (best-effort)
+pub fn flood_wait_seconds(error: &str) -> Option<u64> {
+    if let Some(idx) = error.find("FLOOD_WAIT_") {
+        let start = idx + "FLOOD_WAIT_".len();
+        let secs = error[start..]
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect::<String>();
+        if let Ok(v) = secs.parse::<u64>() {
+            return Some(v);
+        }
+    }
+
+    if let Some(idx) = error.find("value:") {
+        let start = idx + "value:".len();
+        let secs = error[start..]
+            .trim_start()
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect::<String>();
+        if let Ok(v) = secs.parse::<u64>() {
+            return Some(v);
+        }
+    }
+
+    None
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
     use crate::config::ChatEntity;
+    use chrono::TimeZone;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct FakeMessage {
+        date: DateTime<Utc>,
+        text: String,
+    }
+
+    impl FetchedMessage for FakeMessage {
+        fn date(&self) -> DateTime<Utc> {
+            self.date
+        }
+
+        fn text(&self) -> &str {
+            &self.text
+        }
+    }
+
+    /// History of `count` messages one hour apart, newest first
+    fn fake_history(count: usize) -> Vec<Result<FakeMessage>> {
+        let newest = Utc.with_ymd_and_hms(2024, 5, 10, 12, 0, 0).unwrap();
+        (0..count)
+            .map(|i| {
+                Ok(FakeMessage {
+                    date: newest - chrono::Duration::hours(i as i64),
+                    text: format!("message {}", i),
+                })
+            })
+            .collect()
+    }
+
+    fn texts(messages: &[FakeMessage]) -> Vec<&str> {
+        messages.iter().map(|m| m.text.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_fetch_without_options_returns_everything() {
+        let source = stream::iter(fake_history(5));
+        let messages = collect_messages(source, &FetchOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(messages.len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_limit_reached_before_cutoff() {
+        let newest = Utc.with_ymd_and_hms(2024, 5, 10, 12, 0, 0).unwrap();
+        let options = FetchOptions {
+            limit: Some(2),
+            since: Some(newest - chrono::Duration::hours(5)),
+            ..Default::default()
+        };
+        let messages = collect_messages(stream::iter(fake_history(10)), &options)
+            .await
+            .unwrap();
+        assert_eq!(texts(&messages), vec!["message 0", "message 1"]);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_cutoff_reached_before_limit() {
+        let newest = Utc.with_ymd_and_hms(2024, 5, 10, 12, 0, 0).unwrap();
+        let options = FetchOptions {
+            limit: Some(100),
+            since: Some(newest - chrono::Duration::hours(2)),
+            ..Default::default()
+        };
+        let messages = collect_messages(stream::iter(fake_history(10)), &options)
+            .await
+            .unwrap();
+        assert_eq!(
+            texts(&messages),
+            vec!["message 0", "message 1", "message 2"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch_until_skips_newer_messages() {
+        let newest = Utc.with_ymd_and_hms(2024, 5, 10, 12, 0, 0).unwrap();
+        let options = FetchOptions {
+            limit: Some(2),
+            until: Some(newest - chrono::Duration::hours(3)),
+            ..Default::default()
+        };
+        let messages = collect_messages(stream::iter(fake_history(10)), &options)
+            .await
+            .unwrap();
+        assert_eq!(texts(&messages), vec!["message 3", "message 4"]);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_limit_counts_filtered_messages_only() {
+        let options: FetchOptions<FakeMessage> = FetchOptions {
+            limit: Some(2),
+            filter: Some(Box::new(|m: &FakeMessage| {
+                m.text.ends_with(['1', '3', '5'])
+            })),
+            ..Default::default()
+        };
+        let messages = collect_messages(stream::iter(fake_history(10)), &options)
+            .await
+            .unwrap();
+        assert_eq!(texts(&messages), vec!["message 1", "message 3"]);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_zero_limit_returns_nothing() {
+        let options = FetchOptions {
+            limit: Some(0),
+            ..Default::default()
+        };
+        let messages = collect_messages(stream::iter(fake_history(3)), &options)
+            .await
+            .unwrap();
+        assert!(messages.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_retries_after_flood_wait() {
+        let mut history = fake_history(3);
+        history.insert(
+            1,
+            Err(Error::TelegramError(
+                "rpc error 420: FLOOD_WAIT_0".to_string(),
+            )),
+        );
+        let messages = collect_messages(stream::iter(history), &FetchOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(messages.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_gives_up_after_repeated_flood_waits() {
+        let mut history = fake_history(1);
+        for _ in 0..=MAX_FLOOD_RETRIES {
+            history.push(Err(Error::TelegramError("FLOOD_WAIT_0".to_string())));
+        }
+        let result = collect_messages(stream::iter(history), &FetchOptions::default()).await;
+        assert!(matches!(result, Err(Error::TelegramError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_propagates_other_errors() {
+        let mut history = fake_history(2);
+        history.insert(1, Err(Error::TelegramError("CHANNEL_PRIVATE".to_string())));
+        let result = collect_messages(stream::iter(history), &FetchOptions::default()).await;
+        assert!(matches!(result, Err(Error::TelegramError(ref e)) if e == "CHANNEL_PRIVATE"));
+    }
+
+    #[tokio::test]
+    async fn test_scan_streams_with_fetch_rules() {
+        let newest = Utc.with_ymd_and_hms(2024, 5, 10, 12, 0, 0).unwrap();
+        let mut history = fake_history(10);
+        history.insert(2, Err(Error::TelegramError("FLOOD_WAIT_0".to_string())));
+        let options = FetchOptions {
+            limit: Some(3),
+            since: Some(newest - chrono::Duration::hours(8)),
+            until: Some(newest - chrono::Duration::hours(1)),
+            filter: Some(Box::new(|m: &FakeMessage| !m.text.ends_with('2'))),
+        };
+
+        let mut scan = MessageScan::new(stream::iter(history), options);
+        let mut seen = Vec::new();
+        while let Some(msg) = scan.next().await.unwrap() {
+            seen.push(msg.text);
+        }
+        assert_eq!(seen, vec!["message 1", "message 3", "message 4"]);
+        // Exhausted scans stay exhausted
+        assert!(scan.next().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_scan_stops_at_cutoff() {
+        let newest = Utc.with_ymd_and_hms(2024, 5, 10, 12, 0, 0).unwrap();
+        let options = FetchOptions {
+            since: Some(newest - chrono::Duration::hours(1)),
+            ..Default::default()
+        };
+        let mut scan = MessageScan::new(stream::iter(fake_history(10)), options);
+        assert!(scan.next().await.unwrap().is_some());
+        assert!(scan.next().await.unwrap().is_some());
+        assert!(scan.next().await.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_flood_wait_seconds() {
+        assert_eq!(flood_wait_seconds("FLOOD_WAIT_42"), Some(42));
+        assert_eq!(
+            flood_wait_seconds("rpc error 420: FLOOD_WAIT (value: 17)"),
+            Some(17)
+        );
+        assert_eq!(flood_wait_seconds("CHANNEL_PRIVATE"), None);
+    }
 
     #[test]
     fn test_chat_entity_channel() {
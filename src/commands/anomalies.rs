@@ -0,0 +1,364 @@
+//! Message-frequency anomaly detection
+//!
+//! Builds a daily message-count series for a chat and flags days that deviate
+//! from the rolling mean by more than a configurable number of standard
+//! deviations. Useful for spotting raids, outages or viral moments.
+//!
+//! Only complete UTC days are analyzed: today is still in progress and would
+//! read as a dip on every run.
+
+use crate::chat::{fetch_messages, FetchOptions};
+use crate::error::{Error, Result};
+use crate::session::{get_client, SessionLock};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Standard deviation floor so that a perfectly flat window still reacts to a jump.
+const MIN_STD_DEV: f64 = 1.0;
+
+/// Anomaly detector configuration
+#[derive(Debug, Clone)]
+pub struct AnomalyConfig {
+    /// Days of history to fetch
+    pub days: i64,
+    /// Number of preceding days used for the rolling mean
+    pub window: usize,
+    /// Threshold in standard deviations
+    pub sigma: f64,
+}
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        Self {
+            days: 60,
+            window: 7,
+            sigma: 2.0,
+        }
+    }
+}
+
+/// Message count for a single day
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct DailyCount {
+    pub date: NaiveDate,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyKind {
+    Spike,
+    Dip,
+}
+
+/// Day whose message count deviates from the rolling mean
+#[derive(Debug, Clone, Serialize)]
+pub struct Anomaly {
+    pub date: NaiveDate,
+    pub count: usize,
+    pub mean: f64,
+    pub std_dev: f64,
+    pub z_score: f64,
+    pub kind: AnomalyKind,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum OutputFormat {
+    Table,
+    Json,
+}
+
+impl OutputFormat {
+    fn parse(raw: &str) -> Result<Self> {
+        match raw.to_ascii_lowercase().as_str() {
+            "table" | "pretty" => Ok(Self::Table),
+            "json" => Ok(Self::Json),
+            other => Err(Error::InvalidArgument(format!(
+                "Unsupported format '{}'. Use table|json",
+                other
+            ))),
+        }
+    }
+}
+
+/// Main entry point for the CLI.
+pub async fn run(chat_name: &str, config: AnomalyConfig, format: &str) -> Result<Vec<Anomaly>> {
+    let fmt = OutputFormat::parse(format)?;
+    if config.window == 0 {
+        return Err(Error::InvalidArgument(
+            "Window must be at least one day".to_string(),
+        ));
+    }
+
+    let _lock = SessionLock::acquire().await?;
+    let client = get_client().await?;
+    let chat = crate::chat::find_chat(&client, chat_name).await?;
+
+    let (first, last) = complete_days(Utc::now(), config.days);
+    let since = first.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+    let until = (last + Duration::days(1))
+        .and_hms_opt(0, 0, 0)
+        .unwrap_or_default()
+        .and_utc();
+
+    let options = FetchOptions {
+        since: Some(since),
+        until: Some(until),
+        ..Default::default()
+    };
+    let timestamps: Vec<DateTime<Utc>> = fetch_messages(&client, &chat, options)
+        .await?
+        .iter()
+        .map(|msg| msg.date())
+        .collect();
+
+    let series = daily_counts(&timestamps, first, last);
+    let anomalies = detect_anomalies(&series, config.window, config.sigma);
+
+    match fmt {
+        OutputFormat::Table => print_table(chat_name, &series, &anomalies),
+        OutputFormat::Json => {
+            let payload = serde_json::to_string_pretty(&anomalies)
+                .map_err(|e| Error::SerializationError(e.to_string()))?;
+            println!("{payload}");
+        }
+    }
+
+    Ok(anomalies)
+}
+
+/// The last `days` complete UTC days before `now`, as an inclusive range.
+///
+/// Today is excluded because it is still in progress; the window starts at
+/// midnight so its first day is fully covered too.
+pub fn complete_days(now: DateTime<Utc>, days: i64) -> (NaiveDate, NaiveDate) {
+    let last = now.date_naive() - Duration::days(1);
+    (last - Duration::days(days.max(1) - 1), last)
+}
+
+/// Buckets timestamps into a gap-free daily series covering `since..=until`.
+pub fn daily_counts(
+    timestamps: &[DateTime<Utc>],
+    since: NaiveDate,
+    until: NaiveDate,
+) -> Vec<DailyCount> {
+    let mut buckets: BTreeMap<NaiveDate, usize> = BTreeMap::new();
+    let mut day = since;
+    while day <= until {
+        buckets.insert(day, 0);
+        day += Duration::days(1);
+    }
+
+    for ts in timestamps {
+        if let Some(count) = buckets.get_mut(&ts.date_naive()) {
+            *count += 1;
+        }
+    }
+
+    buckets
+        .into_iter()
+        .map(|(date, count)| DailyCount { date, count })
+        .collect()
+}
+
+/// Flags days whose count is more than `sigma` standard deviations away from
+/// the mean of the preceding `window` days.
+///
+/// The first `window` days only seed the baseline and are never reported.
+pub fn detect_anomalies(series: &[DailyCount], window: usize, sigma: f64) -> Vec<Anomaly> {
+    if window == 0 || series.len() <= window {
+        return Vec::new();
+    }
+
+    series
+        .windows(window + 1)
+        .filter_map(|slice| {
+            let (history, current) = slice.split_at(window);
+            let current = current[0];
+
+            let mean = history.iter().map(|d| d.count as f64).sum::<f64>() / window as f64;
+            let variance = history
+                .iter()
+                .map(|d| (d.count as f64 - mean).powi(2))
+                .sum::<f64>()
+                / window as f64;
+            let std_dev = variance.sqrt();
+
+            let z_score = (current.count as f64 - mean) / std_dev.max(MIN_STD_DEV);
+            if z_score.abs() <= sigma {
+                return None;
+            }
+
+            Some(Anomaly {
+                date: current.date,
+                count: current.count,
+                mean,
+                std_dev,
+                z_score,
+                kind: if z_score > 0.0 {
+                    AnomalyKind::Spike
+                } else {
+                    AnomalyKind::Dip
+                },
+            })
+        })
+        .collect()
+}
+
+fn print_table(chat_name: &str, series: &[DailyCount], anomalies: &[Anomaly]) {
+    println!(
+        "\n📈 Activity anomalies for '{}' ({} days analyzed)",
+        chat_name,
+        series.len()
+    );
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+
+    if anomalies.is_empty() {
+        println!("No anomalies detected");
+        return;
+    }
+
+    println!(
+        "{:<12} {:<6} {:>8} {:>8} {:>8}",
+        "Date", "Kind", "Count", "Mean", "Z"
+    );
+    for anomaly in anomalies {
+        let kind = match anomaly.kind {
+            AnomalyKind::Spike => "spike",
+            AnomalyKind::Dip => "dip",
+        };
+        println!(
+            "{:<12} {:<6} {:>8} {:>8.1} {:>8.2}",
+            anomaly.date, kind, anomaly.count, anomaly.mean, anomaly.z_score
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn series(counts: &[usize]) -> Vec<DailyCount> {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        counts
+            .iter()
+            .enumerate()
+            .map(|(i, &count)| DailyCount {
+                date: start + Duration::days(i as i64),
+                count,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn detects_spike() {
+        let data = series(&[10, 12, 11, 9, 10, 11, 10, 60, 10]);
+        let anomalies = detect_anomalies(&data, 7, 2.0);
+
+        assert_eq!(anomalies.len(), 1);
+        assert_eq!(anomalies[0].kind, AnomalyKind::Spike);
+        assert_eq!(anomalies[0].count, 60);
+        assert_eq!(
+            anomalies[0].date,
+            NaiveDate::from_ymd_opt(2024, 1, 8).unwrap()
+        );
+        assert!(anomalies[0].z_score > 2.0);
+    }
+
+    #[test]
+    fn detects_dip() {
+        let data = series(&[50, 52, 48, 51, 49, 50, 50, 2]);
+        let anomalies = detect_anomalies(&data, 7, 2.0);
+
+        assert_eq!(anomalies.len(), 1);
+        assert_eq!(anomalies[0].kind, AnomalyKind::Dip);
+        assert!(anomalies[0].z_score < -2.0);
+    }
+
+    #[test]
+    fn stable_series_has_no_anomalies() {
+        let data = series(&[10, 11, 10, 9, 10, 11, 10, 11, 9, 10]);
+        assert!(detect_anomalies(&data, 7, 2.0).is_empty());
+    }
+
+    #[test]
+    fn flat_window_still_flags_jump() {
+        let data = series(&[5, 5, 5, 5, 8]);
+        let anomalies = detect_anomalies(&data, 4, 2.0);
+
+        assert_eq!(anomalies.len(), 1);
+        assert_eq!(anomalies[0].std_dev, 0.0);
+        assert_eq!(anomalies[0].z_score, 3.0);
+    }
+
+    #[test]
+    fn sigma_controls_sensitivity() {
+        let data = series(&[10, 12, 11, 9, 10, 11, 10, 14]);
+        assert!(detect_anomalies(&data, 7, 5.0).is_empty());
+        assert_eq!(detect_anomalies(&data, 7, 1.0).len(), 1);
+    }
+
+    #[test]
+    fn short_series_or_zero_window_is_empty() {
+        let data = series(&[1, 100]);
+        assert!(detect_anomalies(&data, 7, 2.0).is_empty());
+        assert!(detect_anomalies(&data, 0, 2.0).is_empty());
+    }
+
+    #[test]
+    fn daily_counts_fills_gaps() {
+        let since = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let until = NaiveDate::from_ymd_opt(2024, 1, 4).unwrap();
+        let timestamps = vec![
+            Utc.with_ymd_and_hms(2024, 1, 1, 10, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(2024, 1, 1, 23, 59, 0).unwrap(),
+            Utc.with_ymd_and_hms(2024, 1, 3, 0, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(2023, 12, 31, 12, 0, 0).unwrap(),
+        ];
+
+        let counts: Vec<usize> = daily_counts(&timestamps, since, until)
+            .iter()
+            .map(|d| d.count)
+            .collect();
+        assert_eq!(counts, vec![2, 0, 1, 0]);
+    }
+
+    #[test]
+    fn complete_days_skip_today_and_partial_start() {
+        let now = Utc.with_ymd_and_hms(2024, 1, 10, 15, 30, 0).unwrap();
+        let (first, last) = complete_days(now, 9);
+
+        assert_eq!(first, NaiveDate::from_ymd_opt(2024, 1, 1).unwrap());
+        assert_eq!(last, NaiveDate::from_ymd_opt(2024, 1, 9).unwrap());
+
+        // A quiet morning today is not bucketed, so it cannot show up as a dip
+        let mut timestamps = Vec::new();
+        for day in 1..=9 {
+            for hour in 0..10 {
+                timestamps.push(Utc.with_ymd_and_hms(2024, 1, day, hour, 0, 0).unwrap());
+            }
+        }
+        timestamps.push(Utc.with_ymd_and_hms(2024, 1, 10, 9, 0, 0).unwrap());
+
+        let series = daily_counts(&timestamps, first, last);
+        assert_eq!(series.len(), 9);
+        assert!(series.iter().all(|d| d.count == 10));
+        assert!(detect_anomalies(&series, 7, 2.0).is_empty());
+        assert_eq!(complete_days(now, 0), (last, last));
+    }
+
+    #[test]
+    fn parses_output_format() {
+        assert!(matches!(
+            OutputFormat::parse("JSON"),
+            Ok(OutputFormat::Json)
+        ));
+        assert!(matches!(
+            OutputFormat::parse("table"),
+            Ok(OutputFormat::Table)
+        ));
+        assert!(OutputFormat::parse("csv").is_err());
+    }
+}
@@ -2,8 +2,12 @@
 //!
 //! Equivalent to Python's autoanswer.py
 
+use std::collections::{HashSet, VecDeque};
+
 use crate::error::{Error, Result};
-use crate::session::{get_client, SessionLock};
+use crate::metrics;
+use crate::quiet_hours::QuietHours;
+use crate::session::{get_client, is_connection_error, SessionLock};
 use async_openai::{
     config::OpenAIConfig,
     types::{
@@ -12,6 +16,11 @@ use async_openai::{
     },
     Client as OpenAIClient,
 };
+use chrono::{DateTime, Utc};
+use grammers_client::types::peer::Peer;
+use grammers_client::types::Message;
+use grammers_session::defs::PeerRef;
+use grammers_tl_types as tl;
 use tokio::signal;
 
 const SYSTEM_INSTRUCTIONS: &str = r#"Ты - полезный ассистент, который отвечает на вопросы в Telegram-чате.
@@ -20,7 +29,139 @@ const SYSTEM_INSTRUCTIONS: &str = r#"Ты - полезный ассистент,
 Если пользователь задаёт технический вопрос, постарайся дать максимально понятный и точный ответ.
 Если пользователь не указал иное, отвечай на русском языке."#;
 
-pub async fn run(model: &str) -> Result<()> {
+/// How many processed (chat, message) ids are remembered
+const SEEN_CAPACITY: usize = 4096;
+
+/// Why a message is left unanswered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    /// Sent from this account (including other sessions)
+    Outgoing,
+    /// Sent or forwarded by ourselves, e.g. echoed by another bot
+    FromSelf,
+    /// Arrived before the bot was started
+    BeforeStartup,
+    /// Already processed, e.g. seen again after a reconnect
+    AlreadySeen,
+}
+
+/// Fields of an incoming message the reply guard looks at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IncomingMessage {
+    pub chat_id: i64,
+    pub message_id: i32,
+    pub sender_id: Option<i64>,
+    /// Original author of a forwarded message
+    pub forwarded_from: Option<i64>,
+    pub outgoing: bool,
+    pub date: DateTime<Utc>,
+}
+
+/// Bounded set of recently processed (chat, message) ids, oldest evicted first
+#[derive(Debug)]
+pub struct SeenIds {
+    capacity: usize,
+    order: VecDeque<(i64, i32)>,
+    ids: HashSet<(i64, i32)>,
+}
+
+impl SeenIds {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            order: VecDeque::new(),
+            ids: HashSet::new(),
+        }
+    }
+
+    /// Remember an id; returns `false` if it was already there
+    pub fn insert(&mut self, chat_id: i64, message_id: i32) -> bool {
+        let key = (chat_id, message_id);
+        if !self.ids.insert(key) {
+            return false;
+        }
+        self.order.push_back(key);
+        if self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.ids.remove(&oldest);
+            }
+        }
+        true
+    }
+}
+
+/// Guards against self-replies, stale history and double processing
+#[derive(Debug)]
+pub struct ReplyGuard {
+    my_id: i64,
+    started_at: DateTime<Utc>,
+    seen: SeenIds,
+}
+
+impl ReplyGuard {
+    pub fn new(my_id: i64, started_at: DateTime<Utc>) -> Self {
+        Self::with_capacity(my_id, started_at, SEEN_CAPACITY)
+    }
+
+    pub fn with_capacity(my_id: i64, started_at: DateTime<Utc>, capacity: usize) -> Self {
+        Self {
+            my_id,
+            started_at,
+            seen: SeenIds::new(capacity),
+        }
+    }
+
+    /// Decide whether to answer; accepted messages are marked as seen
+    pub fn check(&mut self, msg: &IncomingMessage) -> std::result::Result<(), SkipReason> {
+        if msg.outgoing {
+            return Err(SkipReason::Outgoing);
+        }
+        if msg.sender_id == Some(self.my_id) || msg.forwarded_from == Some(self.my_id) {
+            return Err(SkipReason::FromSelf);
+        }
+        if msg.date < self.started_at {
+            return Err(SkipReason::BeforeStartup);
+        }
+        if !self.seen.insert(msg.chat_id, msg.message_id) {
+            return Err(SkipReason::AlreadySeen);
+        }
+        Ok(())
+    }
+}
+
+/// Reply held back until quiet hours end; only ids are kept, the reply is
+/// sent through whatever connection is current by then
+struct DeferredReply {
+    chat: PeerRef,
+    message_id: i32,
+    text: String,
+}
+
+/// Guard view of a Telegram message
+fn incoming_message(chat: &Peer, msg: &Message) -> IncomingMessage {
+    let forwarded_from = match &msg.raw {
+        tl::enums::Message::Message(m) => match &m.fwd_from {
+            Some(tl::enums::MessageFwdHeader::Header(h)) => match &h.from_id {
+                Some(tl::enums::Peer::User(u)) => Some(u.user_id),
+                _ => None,
+            },
+            None => None,
+        },
+        _ => None,
+    };
+
+    IncomingMessage {
+        chat_id: chat.id().to_string().parse().unwrap_or(0),
+        message_id: msg.id(),
+        sender_id: msg.sender().and_then(|s| s.id().to_string().parse().ok()),
+        forwarded_from,
+        outgoing: msg.outgoing(),
+        date: msg.date(),
+    }
+}
+
+/// Run the auto-responder; with `quiet_hours` replies are queued until the window closes
+pub async fn run(model: &str, quiet_hours: Option<QuietHours>) -> Result<()> {
     // Get OpenAI API key from environment
     let api_key = std::env::var("OPENAI_API_KEY").map_err(|_| {
         Error::InvalidArgument("OPENAI_API_KEY environment variable not set".to_string())
@@ -30,20 +171,36 @@ pub async fn run(model: &str) -> Result<()> {
     let openai_client = OpenAIClient::with_config(openai_config);
 
     // Acquire session lock
-    let _lock = SessionLock::acquire()?;
+    let _lock = SessionLock::acquire().await?;
 
     // Connect to Telegram
     let client = get_client().await?;
 
+    // Не отвечаем себе и на историю до запуска
+    let me = client
+        .get_me()
+        .await
+        .map_err(|e| Error::TelegramError(e.to_string()))?;
+    let mut guard = ReplyGuard::new(me.raw.id(), Utc::now());
+
     println!("Бот запущен. Ожидаю сообщения...");
     println!("Нажмите Ctrl+C для остановки.");
+    if let Some(quiet) = &quiet_hours {
+        println!(
+            "🌙 Тихие часы {}-{} (UTC{}): ответы откладываются",
+            quiet.start.format("%H:%M"),
+            quiet.end.format("%H:%M"),
+            quiet.offset
+        );
+    }
 
     // Note: grammers 0.8 removed next_update() - need to use handle.step() pattern
     // For now, this is a placeholder implementation using polling
     // Real implementation would use client.handle.step() with proper update handling
 
     // Simple polling implementation - poll recent messages periodically
-    let mut last_seen_id: Option<i32> = None;
+    // Ответы, отложенные до конца тихих часов
+    let mut deferred: VecDeque<DeferredReply> = VecDeque::new();
 
     loop {
         tokio::select! {
@@ -52,44 +209,76 @@ pub async fn run(model: &str) -> Result<()> {
                 break;
             }
             _ = tokio::time::sleep(tokio::time::Duration::from_secs(2)) => {
+                let quiet_now = quiet_hours.is_some_and(|q| q.is_quiet(Utc::now()));
+                if !quiet_now {
+                    while let Some(reply) = deferred.pop_front() {
+                        let sent = client.reply(reply.chat, reply.message_id, reply.text).await;
+                        match sent {
+                            Ok(_) => {}
+                            Err(e @ Error::ConnectionError(_)) => {
+                                eprintln!(
+                                    "Связь с Telegram потеряна, не отправлено отложенных ответов: {}",
+                                    deferred.len() + 1
+                                );
+                                return Err(e);
+                            }
+                            Err(e) => eprintln!("Ошибка при отправке отложенного ответа: {}", e),
+                        }
+                    }
+                }
+
                 // Poll for new messages in all dialogs
                 // This is a simplified approach - real implementation would use update streaming
                 let mut dialogs = client.iter_dialogs();
+                let mut connection_lost = false;
 
                 while let Some(dialog) = dialogs.next().await.transpose() {
+                    if dialog.as_ref().is_err_and(is_connection_error) {
+                        connection_lost = true;
+                        break;
+                    }
                     if let Ok(dialog) = dialog {
                         let chat = &dialog.peer;
                         let mut messages = client.iter_messages(chat);
 
-                        if let Some(Ok(msg)) = messages.next().await.transpose() {
-                            // Check if this is a new message we haven't seen
-                            let msg_id = msg.id();
-                            if let Some(last_id) = last_seen_id {
-                                if msg_id <= last_id {
-                                    continue;
-                                }
-                            }
+                        let latest = match client.next_message(&mut messages).await {
+                            Ok(latest) => latest,
+                            Err(e @ Error::ConnectionError(_)) => return Err(e),
+                            Err(_) => None,
+                        };
 
-                            // Skip outgoing messages
-                            if msg.outgoing() {
-                                last_seen_id = Some(msg_id);
+                        if let Some(msg) = latest {
+                            // Skip own, stale and already processed messages
+                            if let Err(reason) = guard.check(&incoming_message(chat, &msg)) {
+                                tracing::debug!("Skipping message {}: {:?}", msg.id(), reason);
                                 continue;
                             }
 
                             let user_message = msg.text().trim().to_string();
                             if user_message.is_empty() {
-                                last_seen_id = Some(msg_id);
                                 continue;
                             }
 
                             println!("Получено сообщение: {}", user_message);
-                            last_seen_id = Some(msg_id);
 
                             // Generate AI response
                             match generate_response(&openai_client, model, &user_message).await {
+                                Ok(response) if quiet_now => {
+                                    deferred.push_back(DeferredReply {
+                                        chat: chat.into(),
+                                        message_id: msg.id(),
+                                        text: response,
+                                    });
+                                    println!(
+                                        "🌙 Тихие часы: ответ отложен (в очереди {})",
+                                        deferred.len()
+                                    );
+                                }
                                 Ok(response) => {
-                                    if let Err(e) = msg.reply(response).await {
-                                        eprintln!("Ошибка при отправке ответа: {}", e);
+                                    match client.reply(chat, msg.id(), response).await {
+                                        Ok(_) => {}
+                                        Err(e @ Error::ConnectionError(_)) => return Err(e),
+                                        Err(e) => eprintln!("Ошибка при отправке ответа: {}", e),
                                     }
                                 }
                                 Err(e) => {
@@ -100,6 +289,10 @@ pub async fn run(model: &str) -> Result<()> {
                         }
                     }
                 }
+
+                if connection_lost {
+                    client.reconnect().await?;
+                }
             }
         }
     }
@@ -141,6 +334,9 @@ async fn generate_response(
         .create(request)
         .await
         .map_err(|e| Error::OpenAiError(e.to_string()))?;
+    if let Some(usage) = &response.usage {
+        metrics::record_token_usage("openai", usage.prompt_tokens, usage.completion_tokens);
+    }
 
     let content = response
         .choices
@@ -188,4 +384,77 @@ mod tests {
             _ => panic!("expected user message"),
         }
     }
+
+    const ME: i64 = 1;
+
+    fn started() -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000, 0).unwrap()
+    }
+
+    fn incoming(chat_id: i64, message_id: i32, sender_id: i64) -> IncomingMessage {
+        IncomingMessage {
+            chat_id,
+            message_id,
+            sender_id: Some(sender_id),
+            forwarded_from: None,
+            outgoing: false,
+            date: started() + chrono::Duration::seconds(10),
+        }
+    }
+
+    #[test]
+    fn ignores_own_messages() {
+        let mut guard = ReplyGuard::new(ME, started());
+
+        assert_eq!(guard.check(&incoming(10, 1, ME)), Err(SkipReason::FromSelf));
+
+        let outgoing = IncomingMessage {
+            outgoing: true,
+            ..incoming(10, 2, 42)
+        };
+        assert_eq!(guard.check(&outgoing), Err(SkipReason::Outgoing));
+
+        // Our message echoed back by another bot
+        let echoed = IncomingMessage {
+            forwarded_from: Some(ME),
+            ..incoming(10, 3, 99)
+        };
+        assert_eq!(guard.check(&echoed), Err(SkipReason::FromSelf));
+
+        assert_eq!(guard.check(&incoming(10, 4, 42)), Ok(()));
+    }
+
+    #[test]
+    fn ignores_messages_before_startup() {
+        let mut guard = ReplyGuard::new(ME, started());
+        let stale = IncomingMessage {
+            date: started() - chrono::Duration::seconds(1),
+            ..incoming(10, 1, 42)
+        };
+        assert_eq!(guard.check(&stale), Err(SkipReason::BeforeStartup));
+    }
+
+    #[test]
+    fn dedups_messages_seen_again_after_reconnect() {
+        let mut guard = ReplyGuard::new(ME, started());
+        let msg = incoming(10, 7, 42);
+
+        assert_eq!(guard.check(&msg), Ok(()));
+        // The same update delivered again after a reconnect
+        assert_eq!(guard.check(&msg), Err(SkipReason::AlreadySeen));
+        // Message ids are per chat
+        assert_eq!(guard.check(&incoming(20, 7, 42)), Ok(()));
+    }
+
+    #[test]
+    fn seen_cache_is_bounded() {
+        let mut seen = SeenIds::new(2);
+        assert!(seen.insert(1, 1));
+        assert!(seen.insert(1, 2));
+        assert!(!seen.insert(1, 2));
+        assert!(seen.insert(1, 3));
+        // Oldest id was evicted
+        assert!(seen.insert(1, 1));
+        assert!(!seen.insert(1, 3));
+    }
 }
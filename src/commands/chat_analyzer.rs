@@ -3,25 +3,63 @@
 //! Features:
 //! - Fetch recent messages from a chat with basic filtering
 //! - Format data for LLM analysis (OpenAI/Claude/Gemini/Ollama)
-//! - Parse JSON response and save as JSON + Markdown reports
-
-use crate::chat::find_chat;
-use crate::integrations::{ClaudeClient, GeminiClient, OllamaClient, OpenAIClient};
+//! - Parse JSON response and save as JSON + Markdown reports (or flat CSV tables)
+
+use crate::chat::{find_chat, scan_messages, FetchOptions};
+use crate::engagement::{
+    aggregate_activity, rank_participants, ActivitySample, EngagementWeights, ParticipantActivity,
+};
+use crate::export::{write_records, CsvOptions};
+use crate::important::{trim_preserving, ImportantSenders, IMPORTANT_MARKER};
+use crate::integrations::{ClaudeClient, GeminiClient, ImageInput, OllamaClient, OpenAIClient};
+use crate::offline::{self, OfflineMessage};
 use crate::reactions::count_reactions;
+use crate::scan::{no_results_message, ScanStats};
+use crate::schema::{self, SchemaViolation, ViolationKind};
 use crate::session::{get_client, SessionLock};
+use crate::watermark::{FetchBounds, Watermark, WatermarkStore};
 use crate::{Error, Result};
 use chrono::{DateTime, Duration, Utc};
 use grammers_client::types::peer::Peer;
+use grammers_client::types::{Media, Message};
 use grammers_client::Client;
 use serde::Serialize;
 use serde_json::{json, Value};
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
+use std::io::Write;
 use std::path::{Path, PathBuf};
 use tracing::{info, warn};
 
+/// Watermark key for `--since-last` runs
+const WATERMARK_COMMAND: &str = "analyze";
+
+/// Photos attached with `--include-vision`, newest first
+const MAX_VISION_IMAGES: usize = 8;
+
+/// Total raw photo bytes per vision request (base64 adds a third on top)
+const MAX_VISION_BYTES: usize = 4 * 1024 * 1024;
+
+/// First backoff step for OpenAI 429/5xx retries
+const LLM_RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(1);
+
 const SYSTEM_MESSAGE: &str =
     "You are an expert Telegram chat analyzer. Always respond with valid JSON that matches the requested schema.";
 
+/// Analysis returned by [`LlmProvider::Stub`]; key participants are left to the local score
+const STUB_ANALYSIS: &str = r#"{
+  "category": "Offline",
+  "subcategories": ["stub"],
+  "sentiment": "neutral",
+  "activity_level": "unknown",
+  "professionalism": "unknown",
+  "topics": [{"name": "Offline analysis", "mentions": 0, "sentiment": "neutral", "key_message_ids": []}],
+  "discussions": [],
+  "key_participants": [],
+  "summary": "Canned analysis produced without network access.",
+  "insights": [],
+  "recommendations": []
+}"#;
+
 // Fallback prompt if prompts/chat_categorizer.md is missing.
 const FALLBACK_PROMPT: &str = r#"You are a chat analyzer. Analyze the provided Telegram chat messages and provide a comprehensive analysis.
 
@@ -69,6 +107,10 @@ pub enum OutputFormat {
     Json,
     Markdown,
     Both,
+    /// `_summary.csv`, `_topics.csv` and `_participants.csv`
+    Csv,
+    /// No per-run files (with `--append-to` only)
+    None,
 }
 
 impl OutputFormat {
@@ -76,6 +118,8 @@ impl OutputFormat {
         match value.to_lowercase().as_str() {
             "json" => OutputFormat::Json,
             "markdown" | "md" => OutputFormat::Markdown,
+            "csv" => OutputFormat::Csv,
+            "none" => OutputFormat::None,
             _ => OutputFormat::Both,
         }
     }
@@ -87,6 +131,8 @@ pub enum LlmProvider {
     Claude,
     Gemini,
     Ollama,
+    /// Canned response without network access (`--no-network`)
+    Stub,
 }
 
 impl LlmProvider {
@@ -95,6 +141,7 @@ impl LlmProvider {
             "claude" => LlmProvider::Claude,
             "gemini" => LlmProvider::Gemini,
             "ollama" => LlmProvider::Ollama,
+            "stub" => LlmProvider::Stub,
             _ => LlmProvider::OpenAI,
         }
     }
@@ -104,9 +151,15 @@ impl LlmProvider {
             LlmProvider::OpenAI => "gpt-4o-mini",
             LlmProvider::Claude => "claude-sonnet-4-5-20250929",
             LlmProvider::Gemini => "gemini-2.0-flash",
-            LlmProvider::Ollama => "qwen2.5:3b",
+            LlmProvider::Ollama => "llama3.1",
+            LlmProvider::Stub => "stub",
         }
     }
+
+    /// Whether photos can be sent along with the prompt (`--include-vision`)
+    pub fn supports_vision(&self) -> bool {
+        matches!(self, LlmProvider::Claude | LlmProvider::Gemini)
+    }
 }
 
 /// Analyzer configuration.
@@ -125,6 +178,43 @@ pub struct AnalyzerConfig {
     pub output_dir: PathBuf,
     pub prompt_path: Option<PathBuf>,
     pub verbose: bool,
+    /// Extra important sender ids/usernames (added to config.yml ones)
+    pub important_senders: Vec<String>,
+    /// Fail on LLM output that doesn't match the analysis schema instead of defaulting
+    pub strict: bool,
+    /// Only analyze messages newer than the previous successful run
+    pub since_last: bool,
+    /// Weights of the locally computed engagement score
+    pub engagement_weights: EngagementWeights,
+    /// Rank key participants by the local score even when the LLM provides scores
+    pub local_engagement: bool,
+    /// Caps on report sections; the rest is counted in `omitted`
+    pub section_limits: SectionLimits,
+    /// Running JSONL file each result is appended to as one line
+    pub append_to: Option<PathBuf>,
+    /// Language of the free-text fields (summary, topics, ...); `category` and
+    /// `sentiment` keep their canonical English values
+    pub language: Option<String>,
+    /// Attach recent photos to the prompt (Claude and Gemini only)
+    pub include_vision: bool,
+}
+
+/// Maximum number of entries kept per report section (0 = unlimited)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SectionLimits {
+    pub topics: usize,
+    pub discussions: usize,
+    pub participants: usize,
+}
+
+impl Default for SectionLimits {
+    fn default() -> Self {
+        Self {
+            topics: 10,
+            discussions: 10,
+            participants: 15,
+        }
+    }
 }
 
 impl Default for AnalyzerConfig {
@@ -154,6 +244,15 @@ impl Default for AnalyzerConfig {
             output_dir,
             prompt_path: None,
             verbose: true,
+            important_senders: Vec::new(),
+            strict: false,
+            since_last: false,
+            engagement_weights: EngagementWeights::default(),
+            local_engagement: false,
+            section_limits: SectionLimits::default(),
+            append_to: None,
+            language: None,
+            include_vision: false,
         }
     }
 }
@@ -173,8 +272,10 @@ struct FormattedMessage {
     sender_name: String,
     text: String,
     message_id: i32,
+    reply_to: Option<i32>,
     reactions_count: i32,
     has_media: bool,
+    important: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Default)]
@@ -198,11 +299,45 @@ pub struct Discussion {
     pub summary: String,
 }
 
+/// Where a participant's engagement score comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ScoreSource {
+    #[default]
+    Llm,
+    /// Computed from message, reaction, reply and recency aggregates
+    Local,
+}
+
+impl ScoreSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScoreSource::Llm => "llm",
+            ScoreSource::Local => "local",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Default)]
 pub struct KeyParticipant {
     pub name: String,
     pub message_count: i64,
     pub engagement_score: f32,
+    pub score_source: ScoreSource,
+}
+
+/// How many entries [`trim_sections`] dropped from each section
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Default)]
+pub struct OmittedSections {
+    pub topics: usize,
+    pub discussions: usize,
+    pub participants: usize,
+}
+
+impl OmittedSections {
+    pub fn is_empty(&self) -> bool {
+        self.topics == 0 && self.discussions == 0 && self.participants == 0
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Default)]
@@ -239,6 +374,9 @@ pub struct ChatAnalysisResult {
     pub insights: Vec<String>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub recommendations: Vec<String>,
+    /// Entries left out by the section limits
+    #[serde(skip_serializing_if = "OmittedSections::is_empty")]
+    pub omitted: OmittedSections,
 }
 
 impl ChatAnalysisResult {
@@ -251,6 +389,137 @@ impl ChatAnalysisResult {
         Ok(())
     }
 
+    /// Append the result as one JSON line, for tracking a chat over time.
+    ///
+    /// The line is written with a single `write` on a file opened in append
+    /// mode, so concurrent runs don't interleave partial lines.
+    pub fn append_jsonl(&self, path: &Path) -> Result<()> {
+        ensure_parent_dir(path)?;
+        let mut line = serde_json::to_string(self)
+            .map_err(|e| Error::InvalidArgument(format!("Failed to serialize JSON: {}", e)))?;
+        line.push('\n');
+
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| {
+                Error::InvalidArgument(format!("Failed to open {}: {}", path.display(), e))
+            })?;
+        file.write_all(line.as_bytes())
+            .map_err(|e| Error::InvalidArgument(format!("Failed to append JSONL: {}", e)))?;
+        Ok(())
+    }
+
+    /// Flat CSV tables as `(suffix, csv)`: one summary row with the activity
+    /// metrics, one row per topic and one row per key participant
+    pub fn csv_tables(&self, options: &CsvOptions) -> Result<Vec<(&'static str, String)>> {
+        let analyzed_at = self.analyzed_at.to_rfc3339();
+        let metrics = &self.activity_metrics;
+
+        let summary = write_records(
+            &[
+                "chat_name",
+                "analyzed_at",
+                "category",
+                "sentiment",
+                "activity_level",
+                "professionalism",
+                "total_messages",
+                "active_users",
+                "messages_per_day",
+                "avg_message_length",
+                "media_percentage",
+                "reactions_count",
+                "date_range_start",
+                "date_range_end",
+            ],
+            [[
+                self.chat_name.clone(),
+                analyzed_at.clone(),
+                self.category.clone(),
+                self.sentiment.clone(),
+                self.activity_level.clone(),
+                self.professionalism.clone(),
+                metrics.total_messages.to_string(),
+                metrics.active_users.to_string(),
+                format!("{:.2}", metrics.messages_per_day),
+                format!("{:.2}", metrics.avg_message_length),
+                format!("{:.2}", metrics.media_percentage),
+                metrics.reactions_count.to_string(),
+                self.date_range_start.clone().unwrap_or_default(),
+                self.date_range_end.clone().unwrap_or_default(),
+            ]],
+            options,
+        )?;
+
+        let topics = write_records(
+            &[
+                "chat_name",
+                "analyzed_at",
+                "topic",
+                "mentions",
+                "sentiment",
+                "key_message_ids",
+            ],
+            self.topics.iter().map(|topic| {
+                let ids: Vec<String> = topic.key_message_ids.iter().map(i32::to_string).collect();
+                [
+                    self.chat_name.clone(),
+                    analyzed_at.clone(),
+                    topic.name.clone(),
+                    topic.mentions.to_string(),
+                    topic.sentiment.clone(),
+                    ids.join("; "),
+                ]
+            }),
+            options,
+        )?;
+
+        let participants = write_records(
+            &[
+                "chat_name",
+                "analyzed_at",
+                "participant",
+                "message_count",
+                "engagement_score",
+                "score_source",
+            ],
+            self.key_participants.iter().map(|participant| {
+                [
+                    self.chat_name.clone(),
+                    analyzed_at.clone(),
+                    participant.name.clone(),
+                    participant.message_count.to_string(),
+                    format!("{:.2}", participant.engagement_score),
+                    participant.score_source.as_str().to_string(),
+                ]
+            }),
+            options,
+        )?;
+
+        Ok(vec![
+            ("summary", summary),
+            ("topics", topics),
+            ("participants", participants),
+        ])
+    }
+
+    /// Write `<base>_summary.csv`, `<base>_topics.csv` and `<base>_participants.csv`
+    pub fn save_csv(&self, base: &Path, options: &CsvOptions) -> Result<Vec<PathBuf>> {
+        ensure_parent_dir(base)?;
+        let mut paths = Vec::new();
+        for (suffix, csv) in self.csv_tables(options)? {
+            let mut path = base.as_os_str().to_owned();
+            path.push(format!("_{}.csv", suffix));
+            let path = PathBuf::from(path);
+            std::fs::write(&path, csv)
+                .map_err(|e| Error::InvalidArgument(format!("Failed to write CSV: {}", e)))?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+
     pub fn save_markdown(&self, path: &Path) -> Result<()> {
         ensure_parent_dir(path)?;
         std::fs::write(path, self.to_markdown())
@@ -333,6 +602,7 @@ impl ChatAnalysisResult {
                 }
                 lines.push(String::new());
             }
+            push_omitted(&mut lines, self.omitted.topics, "topics", "--max-topics");
         }
 
         if !self.discussions.is_empty() {
@@ -361,18 +631,37 @@ impl ChatAnalysisResult {
                 }
                 lines.push(String::new());
             }
+            push_omitted(
+                &mut lines,
+                self.omitted.discussions,
+                "discussions",
+                "--max-discussions",
+            );
         }
 
         if !self.key_participants.is_empty() {
             lines.push("## 👥 Key Participants".to_string());
             lines.push(String::new());
             for participant in &self.key_participants {
+                let source = match participant.score_source {
+                    ScoreSource::Llm => "",
+                    ScoreSource::Local => ", local",
+                };
                 lines.push(format!(
-                    "- **{}** — {} messages (engagement: {:.1}/10)",
-                    participant.name, participant.message_count, participant.engagement_score
+                    "- **{}** — {} messages (engagement: {:.1}/10{})",
+                    participant.name,
+                    participant.message_count,
+                    participant.engagement_score,
+                    source
                 ));
             }
             lines.push(String::new());
+            push_omitted(
+                &mut lines,
+                self.omitted.participants,
+                "participants",
+                "--max-participants",
+            );
         }
 
         if !self.insights.is_empty() {
@@ -397,17 +686,68 @@ impl ChatAnalysisResult {
     }
 }
 
+fn push_omitted(lines: &mut Vec<String>, omitted: usize, what: &str, flag: &str) {
+    if omitted > 0 {
+        lines.push(format!(
+            "_…and {} more {} omitted (raise {})_",
+            omitted, what, flag
+        ));
+        lines.push(String::new());
+    }
+}
+
 /// Analyze chat and write results to disk.
 pub async fn run(chat: &str, config: AnalyzerConfig) -> Result<ChatAnalysisResult> {
-    let _lock = SessionLock::acquire()?;
+    let _lock = SessionLock::acquire().await?;
     let client = get_client().await?;
     analyze_with_client(&client, chat, config).await
 }
 
+/// Analyze an exported JSONL history without Telegram access (`--no-network`)
+pub async fn run_offline(
+    chat: &str,
+    input: &Path,
+    config: AnalyzerConfig,
+) -> Result<ChatAnalysisResult> {
+    let records = offline::load_jsonl(input)?;
+    let scan = ScanStats::from_dates(records.iter().map(|record| record.date));
+    let messages = offline_messages(records, &config);
+    analyze_messages(chat, &messages, &scan, &[], &config).await
+}
+
 async fn analyze_with_client(
     client: &Client,
     chat: &str,
     config: AnalyzerConfig,
+) -> Result<ChatAnalysisResult> {
+    let watermarks = WatermarkStore::from_env();
+    let previous = if config.since_last {
+        watermarks.load(WATERMARK_COMMAND, chat)?
+    } else {
+        None
+    };
+
+    let (messages, scan, images) =
+        collect_messages(client, chat, &config, previous.as_ref()).await?;
+    let result = analyze_messages(chat, &messages, &scan, &images, &config).await?;
+
+    if config.since_last {
+        let processed = messages.iter().map(|m| (m.date, m.message_id));
+        if let Some(watermark) = Watermark::advance(previous, processed) {
+            watermarks.save(WATERMARK_COMMAND, chat, &watermark)?;
+        }
+    }
+
+    Ok(result)
+}
+
+/// Everything after fetching: prompt, LLM call, parsing and report files
+async fn analyze_messages(
+    chat: &str,
+    messages: &[FormattedMessage],
+    scan: &ScanStats,
+    images: &[ImageInput],
+    config: &AnalyzerConfig,
 ) -> Result<ChatAnalysisResult> {
     if config.verbose {
         info!(
@@ -416,31 +756,64 @@ async fn analyze_with_client(
         );
     }
 
-    let collected = collect_messages(client, chat, &config).await?;
-    if collected.messages.is_empty() {
+    if messages.is_empty() && images.is_empty() {
         return Err(Error::InvalidArgument(format!(
-            "No messages found in chat '{}'",
-            chat
+            "Nothing to analyze in chat '{}'. {}",
+            chat,
+            no_results_message(scan)
         )));
     }
 
-    let messages_text = format_messages_for_llm(&collected.messages);
-    let metadata = build_metadata(&collected.stats);
+    let (_, stats) = summarize_messages(messages);
+    let participants = participant_activity(messages);
+
+    let messages_text = format_messages_for_llm(messages);
+    let metadata = build_metadata(&stats);
     let prompt_template = load_prompt(config.prompt_path.as_deref());
-    let prompt = build_prompt(&prompt_template, &messages_text, &metadata, chat);
+    let mut prompt = build_prompt(
+        &prompt_template,
+        &messages_text,
+        &metadata,
+        chat,
+        config.language.as_deref(),
+    );
+    if !images.is_empty() {
+        prompt.push_str(&format!(
+            "\n\n{} recent photos from the chat are attached; take what they show into account.",
+            images.len()
+        ));
+    }
 
     let llm_raw = call_llm(
         config.llm_provider,
         &config.resolved_model(),
         &prompt,
+        images,
         config.temperature,
         config.max_tokens,
     )
     .await?;
 
-    let result = build_result(chat, &llm_raw, &collected.stats, &collected.sender_counts);
+    if config.strict {
+        let violations = validate_analysis(&llm_raw);
+        if !violations.is_empty() {
+            for violation in &violations {
+                warn!("LLM output schema violation: {}", violation);
+            }
+            return Err(Error::InvalidArgument(format!(
+                "LLM output failed schema validation: {}",
+                violations
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            )));
+        }
+    }
+
+    let result = build_result(chat, &llm_raw, &stats, &participants, config);
 
-    write_outputs(&result, &config)?;
+    write_outputs(&result, config)?;
 
     if config.verbose {
         info!("Analysis complete");
@@ -460,46 +833,58 @@ struct MessageStats {
     date_range: Option<(DateTime<Utc>, DateTime<Utc>)>,
 }
 
-struct CollectedMessages {
-    messages: Vec<FormattedMessage>,
-    sender_counts: HashMap<String, usize>,
-    stats: MessageStats,
-}
-
 async fn collect_messages(
     client: &Client,
     chat: &str,
     config: &AnalyzerConfig,
-) -> Result<CollectedMessages> {
+    watermark: Option<&Watermark>,
+) -> Result<(Vec<FormattedMessage>, ScanStats, Vec<ImageInput>)> {
     let peer = find_chat(client, chat).await?;
 
+    let vision = config.include_vision && config.llm_provider.supports_vision();
+    if config.include_vision && !vision {
+        warn!(
+            "{:?} has no vision support here; analyzing text only",
+            config.llm_provider
+        );
+    }
+
     let cutoff = if config.days_back > 0 {
         Some(Utc::now() - Duration::days(config.days_back))
     } else {
         None
     };
+    let bounds = FetchBounds::new(watermark, cutoff);
+
+    // Scan past the limit when important senders are configured so their
+    // messages can replace less relevant ones during trimming.
+    let important = ImportantSenders::from_config(&config.important_senders);
+    let scan_limit = important.scan_limit(config.message_limit);
 
     let mut messages = Vec::new();
-    let mut sender_counts: HashMap<String, usize> = HashMap::new();
-    let mut unique_senders: HashSet<String> = HashSet::new();
-    let mut total_reactions = 0;
-    let mut total_length: usize = 0;
-    let mut media_count = 0;
-    let mut earliest: Option<DateTime<Utc>> = None;
-    let mut latest: Option<DateTime<Utc>> = None;
+    let mut photos = Vec::new();
+    let mut scan = ScanStats::default();
 
-    let mut iter = client.iter_messages(&peer);
-    while let Some(msg) = iter.next().await.transpose() {
-        let msg = msg.map_err(|e| Error::TelegramError(e.to_string()))?;
+    let options = FetchOptions {
+        since: bounds.since,
+        ..Default::default()
+    };
+    let mut history = scan_messages(client, &peer, options);
+    while let Some(msg) = history.next().await? {
+        if messages.len() >= scan_limit {
+            break;
+        }
 
-        if messages.len() >= config.message_limit {
+        if !bounds.is_new(msg.id()) {
             break;
         }
+        scan.record(msg.date());
 
-        if let Some(cutoff) = cutoff {
-            if msg.date() < cutoff {
-                break;
-            }
+        if vision
+            && photos.len() < MAX_VISION_IMAGES
+            && matches!(msg.media(), Some(Media::Photo(_)))
+        {
+            photos.push(msg.clone());
         }
 
         let text = msg.text();
@@ -516,32 +901,176 @@ async fn collect_messages(
             continue;
         }
 
-        let sender_name = sender_name(&msg);
-        let reactions_count = count_reactions(&msg);
-
         messages.push(FormattedMessage {
             date: msg.date(),
-            sender_name: sender_name.clone(),
+            sender_name: sender_name(&msg),
             text: text.to_string(),
             message_id: msg.id(),
-            reactions_count,
+            reply_to: msg.reply_to_message_id(),
+            reactions_count: count_reactions(&msg),
             has_media,
+            important: important.matches_message(&msg),
         });
+    }
 
-        *sender_counts.entry(sender_name.clone()).or_insert(0) += 1;
-        unique_senders.insert(sender_name);
-        total_reactions += reactions_count;
-        total_length += text.chars().count();
-        if has_media {
-            media_count += 1;
+    let mut messages = trim_preserving(messages, config.message_limit, |m| m.important);
+
+    // Reverse to chronological order for better LLM context.
+    messages.reverse();
+
+    let images = if photos.is_empty() {
+        Vec::new()
+    } else {
+        download_photos(client, &photos).await
+    };
+
+    Ok((messages, scan, images))
+}
+
+/// How many photos and bytes a vision request may still take
+#[derive(Debug, Clone, Copy)]
+struct VisionBudget {
+    max_images: usize,
+    max_bytes: usize,
+    images: usize,
+    bytes: usize,
+}
+
+impl VisionBudget {
+    fn new(max_images: usize, max_bytes: usize) -> Self {
+        Self {
+            max_images,
+            max_bytes,
+            images: 0,
+            bytes: 0,
         }
+    }
 
-        earliest = Some(earliest.map_or(msg.date(), |d| d.min(msg.date())));
-        latest = Some(latest.map_or(msg.date(), |d| d.max(msg.date())));
+    fn is_full(&self) -> bool {
+        self.images >= self.max_images || self.bytes >= self.max_bytes
     }
 
-    // Reverse to chronological order for better LLM context.
+    fn remaining_bytes(&self) -> usize {
+        self.max_bytes.saturating_sub(self.bytes)
+    }
+
+    /// Take a photo of `size` bytes if it still fits
+    fn admit(&mut self, size: usize) -> bool {
+        if self.images >= self.max_images || size > self.remaining_bytes() {
+            return false;
+        }
+        self.images += 1;
+        self.bytes += size;
+        true
+    }
+}
+
+/// Download photos (newest first) until the vision budget runs out
+async fn download_photos(client: &Client, photos: &[Message]) -> Vec<ImageInput> {
+    let mut budget = VisionBudget::new(MAX_VISION_IMAGES, MAX_VISION_BYTES);
+    let mut images = Vec::new();
+
+    for msg in photos {
+        if budget.is_full() {
+            break;
+        }
+        let Some(media) = msg.media() else {
+            continue;
+        };
+        match download_photo(client, &media, budget.remaining_bytes()).await {
+            Ok(Some(data)) if budget.admit(data.len()) => images.push(ImageInput::jpeg(data)),
+            Ok(_) => warn!(
+                "Photo in message {} exceeds the vision size cap, skipped",
+                msg.id()
+            ),
+            Err(e) => warn!("Failed to download photo in message {}: {}", msg.id(), e),
+        }
+    }
+
+    info!(
+        "Attached {} photo(s), {} KB, for vision analysis",
+        images.len(),
+        budget.bytes / 1024
+    );
+    images
+}
+
+/// Photo bytes, or `None` as soon as they exceed `max_bytes`
+async fn download_photo(
+    client: &Client,
+    media: &Media,
+    max_bytes: usize,
+) -> Result<Option<Vec<u8>>> {
+    let mut data = Vec::new();
+    let mut download = client.iter_download(media);
+    while let Some(chunk) = download.next().await? {
+        data.extend_from_slice(&chunk);
+        if data.len() > max_bytes {
+            return Ok(None);
+        }
+    }
+    Ok(Some(data))
+}
+
+/// Apply the live collection filters to an offline history (newest first).
+///
+/// The `days` cutoff is not applied so fixtures stay valid over time.
+fn offline_messages(
+    records: Vec<OfflineMessage>,
+    config: &AnalyzerConfig,
+) -> Vec<FormattedMessage> {
+    let important = ImportantSenders::from_config(&config.important_senders);
+
+    let messages = records
+        .into_iter()
+        .filter(|record| record.text.chars().count() >= config.min_message_length)
+        .filter(|record| !(config.exclude_bots && record.is_bot))
+        .map(|record| FormattedMessage {
+            important: important.matches(record.sender_id, record.username()),
+            date: record.date,
+            sender_name: record.sender,
+            text: record.text,
+            message_id: record.id,
+            reply_to: record.reply_to,
+            reactions_count: record.reactions,
+            has_media: record.has_media,
+        })
+        .collect();
+
+    let mut messages = trim_preserving(messages, config.message_limit, |m| m.important);
     messages.reverse();
+    messages
+}
+
+fn participant_activity(messages: &[FormattedMessage]) -> Vec<ParticipantActivity> {
+    aggregate_activity(messages.iter().map(|msg| ActivitySample {
+        sender: &msg.sender_name,
+        message_id: msg.message_id,
+        reply_to: msg.reply_to,
+        reactions: msg.reactions_count.max(0) as usize,
+        date: msg.date,
+    }))
+}
+
+fn summarize_messages(messages: &[FormattedMessage]) -> (HashMap<String, usize>, MessageStats) {
+    let mut sender_counts: HashMap<String, usize> = HashMap::new();
+    let mut total_reactions = 0;
+    let mut total_length: usize = 0;
+    let mut media_count = 0;
+    let mut earliest: Option<DateTime<Utc>> = None;
+    let mut latest: Option<DateTime<Utc>> = None;
+
+    for msg in messages {
+        *sender_counts.entry(msg.sender_name.clone()).or_insert(0) += 1;
+        total_reactions += msg.reactions_count;
+        total_length += msg.text.chars().count();
+        if msg.has_media {
+            media_count += 1;
+        }
+
+        earliest = Some(earliest.map_or(msg.date, |d| d.min(msg.date)));
+        latest = Some(latest.map_or(msg.date, |d| d.max(msg.date)));
+    }
 
     let total_messages = messages.len();
     let avg_length = if total_messages > 0 {
@@ -567,7 +1096,7 @@ async fn collect_messages(
 
     let stats = MessageStats {
         total_messages,
-        unique_senders: unique_senders.len(),
+        unique_senders: sender_counts.len(),
         total_reactions,
         has_media: media_count > 0,
         avg_length,
@@ -576,11 +1105,7 @@ async fn collect_messages(
         date_range: earliest.zip(latest),
     };
 
-    Ok(CollectedMessages {
-        messages,
-        sender_counts,
-        stats,
-    })
+    (sender_counts, stats)
 }
 
 fn format_messages_for_llm(messages: &[FormattedMessage]) -> String {
@@ -592,9 +1117,15 @@ fn format_messages_for_llm(messages: &[FormattedMessage]) -> String {
             String::new()
         };
         let media_marker = if msg.has_media { " [media]" } else { "" };
+        let important_marker = if msg.important {
+            format!("{} ", IMPORTANT_MARKER)
+        } else {
+            String::new()
+        };
         lines.push(format!(
-            "[{}] {}: {}{}{}",
+            "[{}] {}{}: {}{}{}",
             msg.date.format("%d.%m.%Y %H:%M"),
+            important_marker,
             msg.sender_name,
             msg.text,
             reactions,
@@ -652,25 +1183,56 @@ fn prompt_dir() -> PathBuf {
     PathBuf::from("prompts")
 }
 
-fn build_prompt(template: &str, messages: &str, metadata: &Value, chat: &str) -> String {
+fn build_prompt(
+    template: &str,
+    messages: &str,
+    metadata: &Value,
+    chat: &str,
+    language: Option<&str>,
+) -> String {
+    let important_note = if messages.contains(IMPORTANT_MARKER) {
+        format!(
+            "\n\nMessages marked with {} come from key participants; make sure their points are reflected.",
+            IMPORTANT_MARKER
+        )
+    } else {
+        String::new()
+    };
+
+    let language_note = match language {
+        Some(language) => format!(
+            "\n\n{} Keep `category` and `sentiment` values exactly as listed above (English); translate only free-text fields.",
+            language_instruction(language)
+        ),
+        None => String::new(),
+    };
+
     format!(
-        "{template}\n\n## Chat Metadata\n{}\n\n## Chat Name\n{}\n\n## Messages\n{}\n\nProvide your analysis in JSON format as specified above.",
+        "{template}\n\n## Chat Metadata\n{}\n\n## Chat Name\n{}\n\n## Messages\n{}{}\n\nProvide your analysis in JSON format as specified above.{}",
         serde_json::to_string_pretty(metadata).unwrap_or_default(),
         chat,
-        messages
+        messages,
+        important_note,
+        language_note
     )
 }
 
+/// Explicit output language instruction for LLM prompts
+pub(crate) fn language_instruction(language: &str) -> String {
+    format!("Respond in {}.", language.trim())
+}
+
 async fn call_llm(
     provider: LlmProvider,
     model: &str,
     prompt: &str,
+    images: &[ImageInput],
     temperature: f32,
     max_tokens: u32,
 ) -> Result<String> {
     match provider {
         LlmProvider::OpenAI => {
-            let client = OpenAIClient::from_env()?;
+            let client = OpenAIClient::from_env()?.with_retry(3, LLM_RETRY_DELAY);
             let messages = vec![
                 crate::integrations::openai::ChatMessage {
                     role: "system".to_string(),
@@ -687,11 +1249,23 @@ async fn call_llm(
         }
         LlmProvider::Claude => {
             let client = ClaudeClient::from_env()?.with_model(model);
-            client.chat_with_system(prompt, Some(SYSTEM_MESSAGE)).await
+            if images.is_empty() {
+                client.chat_with_system(prompt, Some(SYSTEM_MESSAGE)).await
+            } else {
+                client
+                    .chat_with_images(prompt, Some(SYSTEM_MESSAGE), images)
+                    .await
+            }
         }
         LlmProvider::Gemini => {
             let client = GeminiClient::from_env()?.with_model(model);
-            client.chat_with_system(prompt, Some(SYSTEM_MESSAGE)).await
+            if images.is_empty() {
+                client.chat_with_system(prompt, Some(SYSTEM_MESSAGE)).await
+            } else {
+                client
+                    .chat_with_images(prompt, Some(SYSTEM_MESSAGE), images)
+                    .await
+            }
         }
         LlmProvider::Ollama => {
             let client = std::env::var("OLLAMA_BASE_URL")
@@ -703,6 +1277,7 @@ async fn call_llm(
                 .generate(prompt, model, Some(SYSTEM_MESSAGE), temperature, max_tokens)
                 .await
         }
+        LlmProvider::Stub => Ok(STUB_ANALYSIS.to_string()),
     }
 }
 
@@ -710,7 +1285,8 @@ fn build_result(
     chat: &str,
     llm_raw: &str,
     stats: &MessageStats,
-    sender_counts: &HashMap<String, usize>,
+    participants: &[ParticipantActivity],
+    config: &AnalyzerConfig,
 ) -> ChatAnalysisResult {
     let parsed = parse_llm_json(llm_raw);
 
@@ -745,12 +1321,21 @@ fn build_result(
         .unwrap_or("unknown")
         .to_string();
 
-    let topics = parse_topics(&parsed);
-    let discussions = parse_discussions(&parsed);
+    let mut topics = parse_topics(&parsed);
+    let mut discussions = parse_discussions(&parsed);
+    let weights = &config.engagement_weights;
     let mut key_participants = parse_participants(&parsed);
-    if key_participants.is_empty() {
-        key_participants = fallback_participants(sender_counts);
+    if key_participants.is_empty() || config.local_engagement {
+        key_participants = fallback_participants(participants, weights);
+    } else {
+        fill_missing_scores(&mut key_participants, participants, weights);
     }
+    let omitted = trim_sections(
+        &mut topics,
+        &mut discussions,
+        &mut key_participants,
+        &config.section_limits,
+    );
 
     let date_range_start = stats.date_range.map(|(start, _)| start.to_rfc3339());
     let date_range_end = stats.date_range.map(|(_, end)| end.to_rfc3339());
@@ -807,9 +1392,47 @@ fn build_result(
         summary,
         insights,
         recommendations,
+        omitted,
     }
 }
 
+/// Keep the top entries of each section: topics by mentions, discussions by
+/// message count, participants by engagement score. Sections within their
+/// limit keep the LLM's order.
+pub fn trim_sections(
+    topics: &mut Vec<Topic>,
+    discussions: &mut Vec<Discussion>,
+    participants: &mut Vec<KeyParticipant>,
+    limits: &SectionLimits,
+) -> OmittedSections {
+    OmittedSections {
+        topics: keep_top(topics, limits.topics, |a, b| b.mentions.cmp(&a.mentions)),
+        discussions: keep_top(discussions, limits.discussions, |a, b| {
+            b.messages_count.cmp(&a.messages_count)
+        }),
+        participants: keep_top(participants, limits.participants, |a, b| {
+            b.engagement_score
+                .total_cmp(&a.engagement_score)
+                .then(b.message_count.cmp(&a.message_count))
+        }),
+    }
+}
+
+/// Stable-sort by `order` and truncate to `max` (0 = unlimited); returns how many were dropped
+fn keep_top<T>(
+    items: &mut Vec<T>,
+    max: usize,
+    order: impl FnMut(&T, &T) -> std::cmp::Ordering,
+) -> usize {
+    if max == 0 || items.len() <= max {
+        return 0;
+    }
+    items.sort_by(order);
+    let omitted = items.len() - max;
+    items.truncate(max);
+    omitted
+}
+
 fn parse_topics(parsed: &Value) -> Vec<Topic> {
     parsed
         .get("topics")
@@ -905,36 +1528,182 @@ fn parse_participants(parsed: &Value) -> Vec<KeyParticipant> {
                         .get("engagement_score")
                         .and_then(Value::as_f64)
                         .unwrap_or(0.0) as f32,
+                    score_source: if item.get("engagement_score").is_some_and(Value::is_number) {
+                        ScoreSource::Llm
+                    } else {
+                        ScoreSource::Local
+                    },
                 })
                 .collect()
         })
         .unwrap_or_default()
 }
 
-fn fallback_participants(sender_counts: &HashMap<String, usize>) -> Vec<KeyParticipant> {
-    let mut counts: Vec<(&String, &usize)> = sender_counts.iter().collect();
-    counts.sort_by(|a, b| b.1.cmp(a.1));
-
-    counts
+/// Top participants ranked by the local engagement score
+fn fallback_participants(
+    participants: &[ParticipantActivity],
+    weights: &EngagementWeights,
+) -> Vec<KeyParticipant> {
+    rank_participants(participants, weights)
         .into_iter()
         .take(5)
-        .map(|(name, count)| KeyParticipant {
-            name: name.clone(),
-            message_count: *count as i64,
-            engagement_score: 5.0,
+        .map(|(participant, score)| KeyParticipant {
+            name: participant.name.clone(),
+            message_count: participant.messages as i64,
+            engagement_score: score,
+            score_source: ScoreSource::Local,
         })
         .collect()
 }
 
+/// Score LLM-listed participants that came without an engagement score
+fn fill_missing_scores(
+    key_participants: &mut [KeyParticipant],
+    participants: &[ParticipantActivity],
+    weights: &EngagementWeights,
+) {
+    let scores: HashMap<&str, f32> = rank_participants(participants, weights)
+        .into_iter()
+        .map(|(participant, score)| (participant.name.as_str(), score))
+        .collect();
+
+    for participant in key_participants
+        .iter_mut()
+        .filter(|p| p.score_source == ScoreSource::Local)
+    {
+        participant.engagement_score = scores
+            .get(participant.name.as_str())
+            .copied()
+            .unwrap_or(0.0);
+    }
+}
+
+/// JSON Schema of the analysis object the prompt asks the LLM for
+fn analysis_schema() -> Value {
+    let string_list = json!({"type": "array", "items": {"type": "string"}});
+    json!({
+        "type": "object",
+        "required": [
+            "category", "subcategories", "sentiment", "activity_level", "professionalism",
+            "topics", "discussions", "key_participants", "summary", "insights", "recommendations"
+        ],
+        "properties": {
+            "category": {"type": "string"},
+            "subcategories": string_list,
+            "sentiment": {"type": "string"},
+            "activity_level": {"type": "string"},
+            "professionalism": {"type": "string"},
+            "topics": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": ["name", "mentions", "sentiment"],
+                    "properties": {
+                        "name": {"type": "string"},
+                        "mentions": {"type": "integer"},
+                        "sentiment": {"type": "string"},
+                        "key_message_ids": {"type": "array", "items": {"type": "integer"}}
+                    }
+                }
+            },
+            "discussions": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": ["title", "messages_count"],
+                    "properties": {
+                        "title": {"type": "string"},
+                        "date": {"type": ["string", "null"]},
+                        "participants": string_list,
+                        "messages_count": {"type": "integer"},
+                        "summary": {"type": "string"}
+                    }
+                }
+            },
+            "key_participants": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": ["name", "message_count", "engagement_score"],
+                    "properties": {
+                        "name": {"type": "string"},
+                        "message_count": {"type": "integer"},
+                        "engagement_score": {"type": "number"}
+                    }
+                }
+            },
+            "summary": {"type": "string"},
+            "insights": string_list,
+            "recommendations": string_list
+        }
+    })
+}
+
+/// Validate raw LLM output against [`analysis_schema`] (strict mode)
+pub fn validate_analysis(raw: &str) -> Vec<SchemaViolation> {
+    match parse_json_lenient(raw) {
+        Ok(value) => schema::validate(&value, &analysis_schema()),
+        Err(e) => vec![SchemaViolation {
+            path: "$".to_string(),
+            kind: ViolationKind::WrongType {
+                expected: "JSON object".to_string(),
+                found: format!("unparseable output ({})", e),
+            },
+        }],
+    }
+}
+
 fn parse_llm_json(raw: &str) -> Value {
-    let cleaned = strip_code_fences(raw.trim());
-    serde_json::from_str(&cleaned).unwrap_or_else(|e| {
+    parse_json_lenient(raw).unwrap_or_else(|e| {
         warn!("Failed to parse LLM JSON: {}", e);
         json!({})
     })
 }
 
-fn strip_code_fences(text: &str) -> String {
+/// Parse LLM output, falling back to the first `{...}` object in it
+///
+/// Local models often wrap the JSON in prose ("Here is the analysis: ...").
+fn parse_json_lenient(raw: &str) -> serde_json::Result<Value> {
+    let cleaned = strip_code_fences(raw.trim());
+    serde_json::from_str(&cleaned).or_else(|e| match extract_json_object(&cleaned) {
+        Some(object) => serde_json::from_str(object),
+        None => Err(e),
+    })
+}
+
+/// The first balanced `{...}` in `text`, ignoring braces inside strings
+fn extract_json_object(text: &str) -> Option<&str> {
+    let start = text.find('{')?;
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (offset, ch) in text[start..].char_indices() {
+        if in_string {
+            match ch {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match ch {
+            '"' => in_string = true,
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(&text[start..start + offset + 1]);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+pub(crate) fn strip_code_fences(text: &str) -> String {
     let mut trimmed = text.trim().to_string();
     if trimmed.starts_with("```json") {
         trimmed = trimmed.trim_start_matches("```json").to_string();
@@ -948,7 +1717,9 @@ fn strip_code_fences(text: &str) -> String {
 }
 
 fn write_outputs(result: &ChatAnalysisResult, config: &AnalyzerConfig) -> Result<()> {
-    ensure_dir(&config.output_dir)?;
+    if config.output_format != OutputFormat::None {
+        ensure_dir(&config.output_dir)?;
+    }
     let safe_chat = sanitize_filename(&result.chat_name);
     let timestamp = result.analyzed_at.format("%Y%m%d_%H%M%S");
     let base = format!("{}_{}", safe_chat, timestamp);
@@ -978,6 +1749,23 @@ fn write_outputs(result: &ChatAnalysisResult, config: &AnalyzerConfig) -> Result
                 info!("Saved Markdown: {}", md_path.display());
             }
         }
+        OutputFormat::Csv => {
+            let base = config.output_dir.join(&base);
+            let paths = result.save_csv(&base, &CsvOptions::default())?;
+            if config.verbose {
+                for path in paths {
+                    info!("Saved CSV: {}", path.display());
+                }
+            }
+        }
+        OutputFormat::None => {}
+    }
+
+    if let Some(path) = &config.append_to {
+        result.append_jsonl(path)?;
+        if config.verbose {
+            info!("Appended to {}", path.display());
+        }
     }
 
     Ok(())
@@ -1002,7 +1790,7 @@ fn ensure_dir(path: &Path) -> Result<()> {
     })
 }
 
-fn sender_name(msg: &grammers_client::types::Message) -> String {
+pub(crate) fn sender_name(msg: &grammers_client::types::Message) -> String {
     if let Some(sender) = msg.sender() {
         match sender {
             Peer::User(u) => u
@@ -1064,6 +1852,35 @@ mod tests {
         assert!(parsed.is_object());
     }
 
+    #[test]
+    fn vision_budget_caps_images_and_bytes() {
+        let mut budget = VisionBudget::new(2, 100);
+        assert!(budget.admit(60));
+        assert!(!budget.admit(50), "over the byte cap");
+        assert_eq!(budget.remaining_bytes(), 40);
+        assert!(budget.admit(40));
+        assert!(budget.is_full());
+        assert!(!budget.admit(0), "over the image cap");
+
+        assert!(!LlmProvider::OpenAI.supports_vision());
+        assert!(LlmProvider::Claude.supports_vision());
+        assert!(LlmProvider::Gemini.supports_vision());
+    }
+
+    #[test]
+    fn extracts_json_wrapped_in_prose() {
+        let raw = r#"Sure! Here is the analysis:
+{"category": "Tech", "summary": "Talk about {braces} and \"quotes\"", "topics": [{"name": "Rust"}]}
+Let me know if you need anything else {or more}."#;
+
+        let parsed = parse_llm_json(raw);
+        assert_eq!(parsed["category"], "Tech");
+        assert_eq!(parsed["summary"], "Talk about {braces} and \"quotes\"");
+        assert_eq!(parsed["topics"][0]["name"], "Rust");
+
+        assert_eq!(parse_llm_json("no object {here"), json!({}));
+    }
+
     #[test]
     fn sanitizes_filename() {
         assert_eq!(sanitize_filename("chat@name"), "chat_name");
@@ -1083,6 +1900,63 @@ mod tests {
         assert_eq!(topics[0].mentions, 5);
     }
 
+    fn formatted(sender: &str, text: &str, important: bool) -> FormattedMessage {
+        FormattedMessage {
+            date: chrono::Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap(),
+            sender_name: sender.to_string(),
+            text: text.to_string(),
+            message_id: 1,
+            reply_to: None,
+            reactions_count: 0,
+            has_media: false,
+            important,
+        }
+    }
+
+    #[test]
+    fn important_messages_are_marked_in_prompt() {
+        let messages = vec![
+            formatted("Boss", "Ship it on Friday", true),
+            formatted("Dev", "ok", false),
+        ];
+
+        let formatted = format_messages_for_llm(&messages);
+        assert!(formatted.contains("⭐ Boss: Ship it on Friday"));
+        assert!(formatted.contains("] Dev: ok"));
+
+        let prompt = build_prompt("T", &formatted, &json!({}), "chat", None);
+        assert!(prompt.contains("key participants"));
+        let plain = build_prompt("T", "] Dev: ok", &json!({}), "chat", None);
+        assert!(!plain.contains("key participants"));
+    }
+
+    #[test]
+    fn language_instruction_is_appended_when_set() {
+        let prompt = build_prompt("T", "] Dev: ok", &json!({}), "chat", Some("English"));
+        assert!(prompt.contains("Respond in English."));
+        assert!(prompt.contains("translate only free-text fields"));
+
+        let plain = build_prompt("T", "] Dev: ok", &json!({}), "chat", None);
+        assert!(!plain.contains("Respond in"));
+    }
+
+    #[test]
+    fn important_messages_survive_message_limit() {
+        // Newest first, as collected from Telegram
+        let mut messages: Vec<FormattedMessage> = (0..10)
+            .map(|i| formatted("Dev", &format!("chatter {i}"), false))
+            .collect();
+        messages.push(formatted("Boss", "decision from last week", true));
+
+        let kept = trim_preserving(messages, 3, |m| m.important);
+        assert_eq!(kept.len(), 3);
+        assert!(kept.iter().any(|m| m.text == "decision from last week"));
+
+        let (sender_counts, stats) = summarize_messages(&kept);
+        assert_eq!(stats.total_messages, 3);
+        assert_eq!(sender_counts.get("Boss"), Some(&1));
+    }
+
     #[test]
     fn formats_messages_for_llm_includes_reactions_and_media() {
         let dt = chrono::Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
@@ -1091,8 +1965,10 @@ mod tests {
             sender_name: "Alice".to_string(),
             text: "Hello world".to_string(),
             message_id: 1,
+            reply_to: None,
             reactions_count: 3,
             has_media: true,
+            important: false,
         }];
 
         let formatted = format_messages_for_llm(&messages);
@@ -1127,26 +2003,302 @@ mod tests {
             .contains("2024-01-01"));
     }
 
+    fn activity(name: &str, messages: usize, reactions: usize) -> ParticipantActivity {
+        ParticipantActivity {
+            name: name.to_string(),
+            messages,
+            reactions_received: reactions,
+            replies_received: 0,
+            last_active: chrono::Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap(),
+        }
+    }
+
     #[test]
     fn fallback_participants_returns_sorted_top5() {
-        let mut counts = std::collections::HashMap::new();
-        for (name, count) in [
+        let counts: Vec<ParticipantActivity> = [
             ("Alice", 10usize),
             ("Bob", 3),
             ("Carol", 7),
             ("Dave", 2),
             ("Eve", 5),
             ("Frank", 1),
-        ] {
-            counts.insert(name.to_string(), count);
-        }
+        ]
+        .into_iter()
+        .map(|(name, count)| activity(name, count, 0))
+        .collect();
 
-        let participants = fallback_participants(&counts);
+        let participants = fallback_participants(&counts, &EngagementWeights::default());
         assert_eq!(participants.len(), 5);
         assert_eq!(participants[0].name, "Alice");
         assert_eq!(participants[0].message_count, 10);
         // Ensure sorted descending
         let counts_sorted: Vec<i64> = participants.iter().map(|p| p.message_count).collect();
         assert!(counts_sorted.windows(2).all(|w| w[0] >= w[1]));
+        assert!(participants
+            .iter()
+            .all(|p| p.score_source == ScoreSource::Local));
+    }
+
+    #[test]
+    fn local_scores_fill_in_missing_llm_scores() {
+        let activity = vec![activity("Alice", 10, 0), activity("Bob", 5, 20)];
+        let parsed = json!({
+            "key_participants": [
+                {"name": "Alice", "message_count": 10, "engagement_score": 9.0},
+                {"name": "Bob", "message_count": 5}
+            ]
+        });
+
+        let mut participants = parse_participants(&parsed);
+        fill_missing_scores(&mut participants, &activity, &EngagementWeights::default());
+
+        assert_eq!(participants[0].engagement_score, 9.0);
+        assert_eq!(participants[0].score_source, ScoreSource::Llm);
+        assert_eq!(participants[1].score_source, ScoreSource::Local);
+        assert!(participants[1].engagement_score > 0.0);
+
+        // Reactions can outrank a higher message count
+        let ranked = fallback_participants(&activity, &EngagementWeights::default());
+        assert_eq!(ranked[0].name, "Bob");
+    }
+
+    fn conformant_analysis() -> Value {
+        json!({
+            "category": "IT",
+            "subcategories": ["Rust"],
+            "sentiment": "positive",
+            "activity_level": "high",
+            "professionalism": "professional",
+            "topics": [
+                {"name": "AI", "mentions": 5, "sentiment": "positive", "key_message_ids": [1, 2]}
+            ],
+            "discussions": [
+                {"title": "Release", "date": "2025-11-24", "participants": ["A"], "messages_count": 3, "summary": "s"}
+            ],
+            "key_participants": [
+                {"name": "Alice", "message_count": 50, "engagement_score": 8.5}
+            ],
+            "summary": "Busy chat",
+            "insights": ["i"],
+            "recommendations": ["r"]
+        })
+    }
+
+    #[test]
+    fn strict_validation_accepts_conformant_output() {
+        let raw = format!("```json\n{}\n```", conformant_analysis());
+        assert!(validate_analysis(&raw).is_empty());
+    }
+
+    #[test]
+    fn strict_validation_reports_missing_and_wrong_fields() {
+        let mut value = conformant_analysis();
+        let obj = value.as_object_mut().unwrap();
+        obj.remove("summary");
+        obj.remove("topics");
+        obj.insert(
+            "key_participants".into(),
+            json!([{"name": "Bob", "message_count": "many"}]),
+        );
+
+        let violations: Vec<String> = validate_analysis(&value.to_string())
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+
+        assert!(violations.contains(&"$.summary: missing required field".to_string()));
+        assert!(violations.contains(&"$.topics: missing required field".to_string()));
+        assert!(violations.contains(
+            &"$.key_participants[0].message_count: expected integer, found string".to_string()
+        ));
+        assert!(violations.contains(
+            &"$.key_participants[0].engagement_score: missing required field".to_string()
+        ));
+        assert_eq!(violations.len(), 4);
+    }
+
+    #[test]
+    fn strict_validation_rejects_non_json() {
+        let violations = validate_analysis("Sorry, I can't do that");
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].path, "$");
+    }
+
+    fn topic(name: &str, mentions: i64) -> Topic {
+        Topic {
+            name: name.to_string(),
+            mentions,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn trimming_keeps_highest_mention_topics() {
+        let mut topics = vec![
+            topic("Hiring", 3),
+            topic("Rust", 12),
+            topic("Memes", 1),
+            topic("Release", 12),
+            topic("Infra", 7),
+        ];
+        let mut discussions = vec![Discussion::default(); 2];
+        let mut participants = vec![KeyParticipant::default(); 4];
+        let limits = SectionLimits {
+            topics: 3,
+            discussions: 5,
+            participants: 0,
+        };
+
+        let omitted = trim_sections(&mut topics, &mut discussions, &mut participants, &limits);
+
+        let names: Vec<&str> = topics.iter().map(|t| t.name.as_str()).collect();
+        // Ties keep the LLM's order
+        assert_eq!(names, vec!["Rust", "Release", "Infra"]);
+        assert_eq!(
+            omitted,
+            OmittedSections {
+                topics: 2,
+                discussions: 0,
+                participants: 0,
+            }
+        );
+        // Within the limit and unlimited (0) sections are untouched
+        assert_eq!(discussions.len(), 2);
+        assert_eq!(participants.len(), 4);
+    }
+
+    #[test]
+    fn report_notes_omitted_sections() {
+        let parsed = json!({
+            "topics": [
+                {"name": "A", "mentions": 1, "sentiment": "neutral"},
+                {"name": "B", "mentions": 5, "sentiment": "neutral"},
+                {"name": "C", "mentions": 3, "sentiment": "neutral"}
+            ],
+            "key_participants": [
+                {"name": "Low", "message_count": 2, "engagement_score": 1.0},
+                {"name": "High", "message_count": 9, "engagement_score": 8.0}
+            ]
+        });
+        let stats = MessageStats {
+            total_messages: 10,
+            unique_senders: 2,
+            total_reactions: 0,
+            has_media: false,
+            avg_length: 20.0,
+            media_percentage: 0.0,
+            messages_per_day: 10.0,
+            date_range: None,
+        };
+        let config = AnalyzerConfig {
+            section_limits: SectionLimits {
+                topics: 1,
+                discussions: 10,
+                participants: 1,
+            },
+            ..Default::default()
+        };
+
+        let result = build_result("chat", &parsed.to_string(), &stats, &[], &config);
+
+        assert_eq!(result.topics[0].name, "B");
+        assert_eq!(result.key_participants[0].name, "High");
+        assert_eq!(result.omitted.topics, 2);
+        assert_eq!(result.omitted.participants, 1);
+
+        let markdown = result.to_markdown();
+        assert!(markdown.contains("_…and 2 more topics omitted (raise --max-topics)_"));
+        assert!(markdown.contains("_…and 1 more participants omitted (raise --max-participants)_"));
+        assert!(!markdown.contains("more discussions"));
+
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["omitted"]["topics"], 2);
+    }
+
+    #[test]
+    fn csv_tables_flatten_summary_topics_and_participants() {
+        let result = ChatAnalysisResult {
+            chat_name: "rust_chat".to_string(),
+            analyzed_at: chrono::Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap(),
+            category: "Tech".to_string(),
+            subcategories: vec![],
+            sentiment: "positive".to_string(),
+            activity_level: "high".to_string(),
+            professionalism: "high".to_string(),
+            topics: vec![Topic {
+                name: "Async, traits".to_string(),
+                mentions: 7,
+                sentiment: "neutral".to_string(),
+                key_message_ids: vec![10, 12],
+            }],
+            discussions: vec![],
+            key_participants: vec![KeyParticipant {
+                name: "@alice".to_string(),
+                message_count: 42,
+                engagement_score: 0.875,
+                score_source: ScoreSource::Local,
+            }],
+            activity_metrics: ActivityMetrics {
+                total_messages: 120,
+                active_users: 9,
+                messages_per_day: 4.0,
+                avg_message_length: 55.5,
+                media_percentage: 2.5,
+                reactions_count: 30,
+            },
+            date_range_start: Some("2024-04-01T00:00:00+00:00".to_string()),
+            date_range_end: None,
+            summary: String::new(),
+            insights: vec![],
+            recommendations: vec![],
+            omitted: OmittedSections::default(),
+        };
+        let options = CsvOptions {
+            bom: false,
+            ..Default::default()
+        };
+
+        let tables = result.csv_tables(&options).unwrap();
+        let names: Vec<&str> = tables.iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, vec!["summary", "topics", "participants"]);
+
+        let lines = |csv: &str| csv.lines().map(str::to_string).collect::<Vec<_>>();
+        assert_eq!(
+            lines(&tables[0].1),
+            vec![
+                "chat_name,analyzed_at,category,sentiment,activity_level,professionalism,\
+                 total_messages,active_users,messages_per_day,avg_message_length,\
+                 media_percentage,reactions_count,date_range_start,date_range_end",
+                "rust_chat,2024-05-01T12:00:00+00:00,Tech,positive,high,high,120,9,4.00,55.50,\
+                 2.50,30,2024-04-01T00:00:00+00:00,",
+            ]
+        );
+        assert_eq!(
+            lines(&tables[1].1),
+            vec![
+                "chat_name,analyzed_at,topic,mentions,sentiment,key_message_ids",
+                "rust_chat,2024-05-01T12:00:00+00:00,\"Async, traits\",7,neutral,10; 12",
+            ]
+        );
+        assert_eq!(
+            lines(&tables[2].1),
+            vec![
+                "chat_name,analyzed_at,participant,message_count,engagement_score,score_source",
+                "rust_chat,2024-05-01T12:00:00+00:00,@alice,42,0.88,local",
+            ]
+        );
+    }
+
+    #[test]
+    fn stub_analysis_passes_strict_validation() {
+        assert!(validate_analysis(STUB_ANALYSIS).is_empty());
+        assert_eq!(LlmProvider::parse("stub"), LlmProvider::Stub);
+    }
+
+    #[test]
+    fn ollama_provider_defaults_to_llama() {
+        let provider = LlmProvider::parse("Ollama");
+        assert_eq!(provider, LlmProvider::Ollama);
+        assert_eq!(provider.default_model(), "llama3.1");
     }
 }
@@ -3,7 +3,12 @@
 //! Based on the CRM idea from example_channel chat - automatically parse conversations
 //! to extract business information
 
+use crate::chat::{scan_messages, FetchOptions};
 use crate::error::{Error, Result};
+use crate::export::{write_records, CsvOptions};
+use crate::metrics;
+use crate::resolve::{resolve_users, UserCache};
+use crate::scan::ScanStats;
 use crate::session::{get_client, SessionLock};
 use async_openai::{
     config::OpenAIConfig,
@@ -14,7 +19,10 @@ use async_openai::{
     Client as OpenAIClient,
 };
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::path::Path;
 
 const CRM_EXTRACTION_PROMPT: &str = r#"Ты — эксперт по CRM и продажам. Проанализируй переписку и извлеки структурированные данные.
 
@@ -36,6 +44,8 @@ const CRM_EXTRACTION_PROMPT: &str = r#"Ты — эксперт по CRM и пр
       "description": "Описание что обсуждается",
       "stage": "один из: lead|qualification|proposal|negotiation|closed_won|closed_lost",
       "estimated_value": "сумма если упоминается",
+      "amount": 150000,
+      "currency": "RUB|USD|EUR",
       "next_action": "следующий шаг",
       "deadline": "дедлайн если упоминается"
     }
@@ -62,6 +72,9 @@ pub struct Contact {
     pub phone: Option<String>,
     pub email: Option<String>,
     pub telegram: Option<String>,
+    /// Telegram user id, filled in by resolving `telegram`
+    #[serde(default)]
+    pub telegram_id: Option<i64>,
 }
 
 /// Deal/opportunity information
@@ -71,10 +84,153 @@ pub struct Deal {
     pub description: Option<String>,
     pub stage: Option<String>,
     pub estimated_value: Option<String>,
+    /// Normalized amount (models sometimes send it as text, e.g. "1,5 млн")
+    #[serde(default, deserialize_with = "lenient_amount")]
+    pub amount: Option<f64>,
+    /// ISO code: RUB, USD or EUR
+    #[serde(default)]
+    pub currency: Option<String>,
     pub next_action: Option<String>,
     pub deadline: Option<String>,
 }
 
+/// Money mentions: `150 тыс руб`, `1,5 млн ₽`, `300$`, `$10000`, `2 000 eur`
+static AMOUNT_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?i)(?:(\d[\d\s.,]*?)\s?(тыс\.?|млн\.?|k|к)?\s?(руб\w*|₽|\$|€|usd|eur)|([$€])\s?(\d[\d\s.,]*\d|\d)\s?(тыс\.?|млн\.?|k|к)?)",
+    )
+    .expect("valid amount regex")
+});
+
+/// Number with spaces, thousands separators or a decimal comma as `f64`.
+///
+/// A lone `,` or `.` followed by exactly three digits is a thousands separator
+/// ("10,000"); otherwise the last separator is the decimal point ("1,5").
+fn parse_number(raw: &str) -> Option<f64> {
+    let digits: String = raw.chars().filter(|c| !c.is_whitespace()).collect();
+    let digits = digits.trim_end_matches(['.', ',']);
+    let separators: Vec<(usize, char)> = digits
+        .char_indices()
+        .filter(|(_, c)| *c == '.' || *c == ',')
+        .collect();
+
+    let normalized = match separators.as_slice() {
+        [] => digits.to_string(),
+        [(i, _)] if digits.len() - i - 1 == 3 => digits.replace([',', '.'], ""),
+        [.., (last, _)] if separators.iter().any(|(_, c)| *c != separators[0].1) => {
+            let (int, frac) = digits.split_at(*last);
+            format!("{}.{}", int.replace([',', '.'], ""), &frac[1..])
+        }
+        [(i, _)] => format!("{}.{}", &digits[..*i], &digits[i + 1..]),
+        _ => digits.replace([',', '.'], ""),
+    };
+    normalized.parse().ok()
+}
+
+fn currency_code(raw: &str) -> Option<&'static str> {
+    let raw = raw.to_lowercase();
+    match raw.as_str() {
+        "₽" => Some("RUB"),
+        "$" | "usd" => Some("USD"),
+        "€" | "eur" => Some("EUR"),
+        _ if raw.starts_with("руб") => Some("RUB"),
+        _ => None,
+    }
+}
+
+fn multiplier(raw: &str) -> f64 {
+    let raw = raw.to_lowercase();
+    if raw.starts_with("млн") {
+        1_000_000.0
+    } else if raw.starts_with("тыс") || raw == "k" || raw == "к" {
+        1_000.0
+    } else {
+        1.0
+    }
+}
+
+/// First money mention in `text` as (amount, ISO currency)
+pub fn parse_amount(text: &str) -> Option<(f64, String)> {
+    AMOUNT_RE.captures_iter(text).find_map(|caps| {
+        let (number, mult, currency) = match caps.get(1) {
+            Some(number) => (number, caps.get(2), caps.get(3)?),
+            None => (caps.get(5)?, caps.get(6), caps.get(4)?),
+        };
+        let amount = parse_number(number.as_str())? * mult.map_or(1.0, |m| multiplier(m.as_str()));
+        Some((amount, currency_code(currency.as_str())?.to_string()))
+    })
+}
+
+/// Accept the amount as a JSON number or as text like "150 тыс"
+fn lenient_amount<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Option<f64>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Amount {
+        Number(f64),
+        Text(String),
+    }
+
+    Ok(match Option::<Amount>::deserialize(deserializer)? {
+        Some(Amount::Number(n)) => Some(n),
+        Some(Amount::Text(text)) => {
+            let text = text.trim().trim_start_matches(['$', '€', '₽']);
+            let split = text
+                .find(|c: char| !(c.is_ascii_digit() || c.is_whitespace() || c == '.' || c == ','))
+                .unwrap_or(text.len());
+            let (number, rest) = text.split_at(split);
+            parse_number(number).map(|n| n * multiplier(rest.trim()))
+        }
+        None => None,
+    })
+}
+
+impl Deal {
+    /// Fill a missing amount/currency from the deal's own text
+    fn fill_amount(&mut self) {
+        if self.amount.is_some() && self.currency.is_some() {
+            return;
+        }
+        let found = [&self.estimated_value, &self.description, &self.title]
+            .into_iter()
+            .flatten()
+            .find_map(|text| parse_amount(text));
+        if let Some((amount, currency)) = found {
+            self.amount.get_or_insert(amount);
+            self.currency.get_or_insert(currency);
+        }
+    }
+}
+
+/// Regex fallback for deal amounts the model left out.
+///
+/// Deals without an amount take the first one in their own text; if the model
+/// returned no deals at all, every message line mentioning money becomes a
+/// `lead` deal.
+pub fn fill_deal_amounts(extraction: &mut CrmExtraction, conversation: &str) {
+    for deal in &mut extraction.deals {
+        deal.fill_amount();
+    }
+    if !extraction.deals.is_empty() {
+        return;
+    }
+
+    extraction.deals = conversation
+        .lines()
+        .filter_map(|line| {
+            let (amount, currency) = parse_amount(line)?;
+            Some(Deal {
+                description: Some(line.chars().take(200).collect()),
+                stage: Some("lead".to_string()),
+                amount: Some(amount),
+                currency: Some(currency),
+                ..Default::default()
+            })
+        })
+        .collect();
+}
+
 /// Action item from conversation
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ActionItem {
@@ -91,6 +247,19 @@ pub struct CrmExtraction {
     pub action_items: Vec<ActionItem>,
     pub sentiment: Option<String>,
     pub summary: Option<String>,
+    /// Messages read from the chat (not part of the LLM output)
+    #[serde(skip)]
+    pub scan: ScanStats,
+}
+
+impl CrmExtraction {
+    /// Nothing worth reporting was extracted
+    pub fn is_empty(&self) -> bool {
+        self.contacts.is_empty()
+            && self.deals.is_empty()
+            && self.action_items.is_empty()
+            && self.summary.as_deref().is_none_or(|s| s.trim().is_empty())
+    }
 }
 
 /// CRM parser configuration
@@ -118,7 +287,7 @@ pub async fn parse_chat(chat_name: &str, config: CrmConfig) -> Result<CrmExtract
     let openai_config = OpenAIConfig::new().with_api_key(api_key);
     let openai_client = OpenAIClient::with_config(openai_config);
 
-    let _lock = SessionLock::acquire()?;
+    let _lock = SessionLock::acquire().await?;
     let client = get_client().await?;
 
     println!("🔍 Анализирую чат '{}' для CRM...", chat_name);
@@ -127,42 +296,43 @@ pub async fn parse_chat(chat_name: &str, config: CrmConfig) -> Result<CrmExtract
 
     // Collect messages
     let mut messages: Vec<(String, String, DateTime<Utc>)> = Vec::new();
-    let mut iter = client.iter_messages(&chat);
+    let mut scan = ScanStats::default();
+    let mut history = scan_messages(&client, &chat, FetchOptions::default());
 
-    while let Some(msg_result) = iter.next().await.transpose() {
+    while let Some(msg) = history.next().await? {
         if messages.len() >= config.max_messages {
             break;
         }
 
-        if let Ok(msg) = msg_result {
-            let text = msg.text().trim().to_string();
-            if text.is_empty() {
-                continue;
-            }
+        scan.record(msg.date());
+        let text = msg.text().trim().to_string();
+        if text.is_empty() {
+            continue;
+        }
 
-            let sender = if let Some(sender) = msg.sender() {
-                match sender {
-                    grammers_client::types::Peer::User(u) => u
-                        .username()
-                        .map(|s| format!("@{}", s))
-                        .unwrap_or_else(|| u.full_name()),
+        let sender = if let Some(sender) = msg.sender() {
+            match sender {
+                grammers_client::types::Peer::User(u) => u
+                    .username()
+                    .map(|s| format!("@{}", s))
+                    .unwrap_or_else(|| u.full_name()),
 
-                    grammers_client::types::Peer::Channel(c) => c.title().to_string(),
-                    grammers_client::types::Peer::Group(g) => {
-                        g.title().unwrap_or("Group").to_string()
-                    }
-                }
-            } else {
-                "Unknown".to_string()
-            };
+                grammers_client::types::Peer::Channel(c) => c.title().to_string(),
+                grammers_client::types::Peer::Group(g) => g.title().unwrap_or("Group").to_string(),
+            }
+        } else {
+            "Unknown".to_string()
+        };
 
-            let timestamp: DateTime<Utc> = msg.date();
-            messages.push((sender, text, timestamp));
-        }
+        let timestamp: DateTime<Utc> = msg.date();
+        messages.push((sender, text, timestamp));
     }
 
     if messages.is_empty() {
-        return Ok(CrmExtraction::default());
+        return Ok(CrmExtraction {
+            scan,
+            ..Default::default()
+        });
     }
 
     // Reverse to chronological order
@@ -176,11 +346,33 @@ pub async fn parse_chat(chat_name: &str, config: CrmConfig) -> Result<CrmExtract
         .join("\n");
 
     // Extract CRM data with AI
-    let extraction = extract_crm_data(&openai_client, &config.model, &conversation).await?;
+    let mut extraction = extract_crm_data(&openai_client, &config.model, &conversation).await?;
+    fill_deal_amounts(&mut extraction, &conversation);
+    resolve_contacts(&client, &mut extraction).await;
+    extraction.scan = scan;
 
     Ok(extraction)
 }
 
+/// Attach Telegram ids to contacts with a username (unknown ones are only reported)
+async fn resolve_contacts(client: &grammers_client::Client, extraction: &mut CrmExtraction) {
+    let usernames: Vec<&str> = extraction
+        .contacts
+        .iter()
+        .filter_map(|c| c.telegram.as_deref())
+        .filter(|t| t.trim().starts_with('@'))
+        .collect();
+    if usernames.is_empty() {
+        return;
+    }
+
+    let report = resolve_users(client, &usernames, &mut UserCache::default()).await;
+    report.print_unresolved();
+    for contact in &mut extraction.contacts {
+        contact.telegram_id = contact.telegram.as_deref().and_then(|t| report.id(t));
+    }
+}
+
 async fn extract_crm_data(
     client: &OpenAIClient<OpenAIConfig>,
     model: &str,
@@ -212,6 +404,9 @@ async fn extract_crm_data(
         .create(request)
         .await
         .map_err(|e| Error::OpenAiError(e.to_string()))?;
+    if let Some(usage) = &response.usage {
+        metrics::record_token_usage("openai", usage.prompt_tokens, usage.completion_tokens);
+    }
 
     let content = response
         .choices
@@ -230,42 +425,214 @@ async fn extract_crm_data(
     Ok(extraction)
 }
 
-/// Export CRM data to CSV
-pub fn export_contacts_csv(extraction: &CrmExtraction) -> String {
-    let mut csv = String::from("name,company,role,phone,email,telegram\n");
+/// Column order of the contacts CSV
+const CONTACT_HEADERS: [&str; 7] = [
+    "name",
+    "company",
+    "role",
+    "phone",
+    "email",
+    "telegram",
+    "telegram_id",
+];
+
+/// Phone without spaces and dashes, for matching
+fn normalize_phone(phone: &str) -> String {
+    phone
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .collect()
+}
+
+/// Lowercase username without `@`, for matching
+fn normalize_username(username: &str) -> String {
+    username.trim().trim_start_matches('@').to_lowercase()
+}
+
+fn normalized(field: &Option<String>, normalize: fn(&str) -> String) -> Option<String> {
+    field
+        .as_deref()
+        .map(normalize)
+        .filter(|value| !value.is_empty())
+}
+
+/// Same person: equal normalized phone or equal normalized username
+fn same_contact(a: &Contact, b: &Contact) -> bool {
+    let phone = |c: &Contact| normalized(&c.phone, normalize_phone);
+    let username = |c: &Contact| normalized(&c.telegram, normalize_username);
+    (phone(a).is_some() && phone(a) == phone(b))
+        || (username(a).is_some() && username(a) == username(b))
+}
 
-    for contact in &extraction.contacts {
-        csv.push_str(&format!(
-            "\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\"\n",
-            contact.name.as_deref().unwrap_or(""),
-            contact.company.as_deref().unwrap_or(""),
-            contact.role.as_deref().unwrap_or(""),
-            contact.phone.as_deref().unwrap_or(""),
-            contact.email.as_deref().unwrap_or(""),
-            contact.telegram.as_deref().unwrap_or(""),
-        ));
+/// Merge freshly extracted contacts into existing ones.
+///
+/// A match (by phone or username) takes the fresh name and any fresh non-empty
+/// fields; everything else is appended. Contacts without a phone or username
+/// can't be matched and are always appended.
+pub fn merge_contacts(mut existing: Vec<Contact>, fresh: &[Contact]) -> Vec<Contact> {
+    for contact in fresh {
+        match existing.iter_mut().find(|c| same_contact(c, contact)) {
+            Some(known) => {
+                let fields = [
+                    (&mut known.name, &contact.name),
+                    (&mut known.company, &contact.company),
+                    (&mut known.role, &contact.role),
+                    (&mut known.phone, &contact.phone),
+                    (&mut known.email, &contact.email),
+                    (&mut known.telegram, &contact.telegram),
+                ];
+                for (old, new) in fields {
+                    if new.as_deref().is_some_and(|v| !v.trim().is_empty()) {
+                        old.clone_from(new);
+                    }
+                }
+                known.telegram_id = contact.telegram_id.or(known.telegram_id);
+            }
+            None => existing.push(contact.clone()),
+        }
     }
+    existing
+}
+
+/// Parse a contacts CSV written by [`export_contacts_csv`]; columns are matched by header
+pub fn parse_contacts_csv(content: &str, options: &CsvOptions) -> Result<Vec<Contact>> {
+    let csv_error = |e: csv::Error| Error::SerializationError(format!("CSV error: {}", e));
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(options.delimiter)
+        .from_reader(content.trim_start_matches('\u{feff}').as_bytes());
+
+    let headers = reader.headers().map_err(csv_error)?.clone();
+    let column = |name: &str| headers.iter().position(|h| h.trim() == name);
+    let columns = CONTACT_HEADERS.map(column);
+
+    reader
+        .records()
+        .map(|record| {
+            let record = record.map_err(csv_error)?;
+            let [name, company, role, phone, email, telegram, telegram_id] = columns.map(|i| {
+                i.and_then(|i| record.get(i))
+                    .map(str::trim)
+                    .filter(|v| !v.is_empty())
+                    .map(String::from)
+            });
+            Ok(Contact {
+                name,
+                company,
+                role,
+                phone,
+                email,
+                telegram,
+                telegram_id: telegram_id.and_then(|id| id.parse().ok()),
+            })
+        })
+        .collect()
+}
+
+/// Contacts from an earlier export; a missing file means none yet
+pub fn read_contacts_csv(path: &Path, options: &CsvOptions) -> Result<Vec<Contact>> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => parse_contacts_csv(&content, options),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
 
-    csv
+/// Export CRM data to CSV, merged into the contacts of `existing` when given
+pub fn export_contacts_csv(
+    extraction: &CrmExtraction,
+    existing: Option<&Path>,
+    options: &CsvOptions,
+) -> Result<String> {
+    let contacts = match existing {
+        Some(path) => merge_contacts(read_contacts_csv(path, options)?, &extraction.contacts),
+        None => extraction.contacts.clone(),
+    };
+
+    let rows = contacts.iter().map(|contact| {
+        let telegram_id = contact
+            .telegram_id
+            .map(|id| id.to_string())
+            .unwrap_or_default();
+        let fields = [
+            &contact.name,
+            &contact.company,
+            &contact.role,
+            &contact.phone,
+            &contact.email,
+            &contact.telegram,
+        ]
+        .map(|field| field.as_deref().unwrap_or("").to_string());
+        fields.into_iter().chain([telegram_id]).collect::<Vec<_>>()
+    });
+
+    write_records(&CONTACT_HEADERS, rows, options)
 }
 
 /// Export deals to CSV
-pub fn export_deals_csv(extraction: &CrmExtraction) -> String {
-    let mut csv = String::from("title,description,stage,value,next_action,deadline\n");
-
-    for deal in &extraction.deals {
-        csv.push_str(&format!(
-            "\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\"\n",
-            deal.title.as_deref().unwrap_or(""),
-            deal.description.as_deref().unwrap_or(""),
-            deal.stage.as_deref().unwrap_or(""),
-            deal.estimated_value.as_deref().unwrap_or(""),
-            deal.next_action.as_deref().unwrap_or(""),
-            deal.deadline.as_deref().unwrap_or(""),
-        ));
-    }
-
-    csv
+pub fn export_deals_csv(extraction: &CrmExtraction, options: &CsvOptions) -> Result<String> {
+    let rows = extraction.deals.iter().map(|deal| {
+        let amount = deal.amount.map(|a| a.to_string()).unwrap_or_default();
+        let [title, description, stage, value, currency, next_action, deadline] = [
+            &deal.title,
+            &deal.description,
+            &deal.stage,
+            &deal.estimated_value,
+            &deal.currency,
+            &deal.next_action,
+            &deal.deadline,
+        ]
+        .map(|field| field.as_deref().unwrap_or("").to_string());
+        [
+            title,
+            description,
+            stage,
+            value,
+            amount,
+            currency,
+            next_action,
+            deadline,
+        ]
+    });
+
+    write_records(
+        &[
+            "title",
+            "description",
+            "stage",
+            "value",
+            "amount",
+            "currency",
+            "next_action",
+            "deadline",
+        ],
+        rows,
+        options,
+    )
+}
+
+/// Amount with thousands separated by spaces, e.g. `1 500 000` or `99.5`
+fn format_amount(amount: f64) -> String {
+    let whole = amount.trunc() as i64;
+    let digits = whole.abs().to_string();
+    let mut grouped = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(' ');
+        }
+        grouped.push(c);
+    }
+    if whole < 0 {
+        grouped.insert(0, '-');
+    }
+
+    let cents = ((amount - amount.trunc()).abs() * 100.0).round() as i64;
+    if cents == 0 {
+        grouped
+    } else {
+        format!("{}.{:02}", grouped, cents)
+            .trim_end_matches('0')
+            .to_string()
+    }
 }
 
 /// Print CRM extraction in human-readable format
@@ -300,21 +667,34 @@ pub fn print_extraction(extraction: &CrmExtraction) {
             if let Some(phone) = &contact.phone {
                 println!("    📞 {}", phone);
             }
+            if let Some(telegram) = &contact.telegram {
+                match contact.telegram_id {
+                    Some(id) => println!("    ✈️ {} (id {})", telegram, id),
+                    None => println!("    ✈️ {}", telegram),
+                }
+            }
         }
         println!();
     }
 
     if !extraction.deals.is_empty() {
         println!("💼 Deals ({}):", extraction.deals.len());
+        println!("  Stage                Amount Cur   Description");
         for deal in &extraction.deals {
+            let description = deal
+                .title
+                .as_deref()
+                .or(deal.description.as_deref())
+                .unwrap_or("Untitled");
             println!(
-                "  • {} [{}]",
-                deal.title.as_deref().unwrap_or("Untitled"),
-                deal.stage.as_deref().unwrap_or("unknown")
+                "  {:<12} {:>14} {:<4}  {}",
+                deal.stage.as_deref().unwrap_or("unknown"),
+                deal.amount
+                    .map(format_amount)
+                    .unwrap_or_else(|| "-".to_string()),
+                deal.currency.as_deref().unwrap_or(""),
+                description.chars().take(60).collect::<String>()
             );
-            if let Some(value) = &deal.estimated_value {
-                println!("    💰 {}", value);
-            }
             if let Some(action) = &deal.next_action {
                 println!("    ➡️ Next: {}", action);
             }
@@ -348,14 +728,83 @@ mod tests {
                 email: Some("john@acme.com".to_string()),
                 phone: None,
                 telegram: Some("@johndoe".to_string()),
+                telegram_id: Some(42),
             }],
             ..Default::default()
         };
 
-        let csv = export_contacts_csv(&extraction);
+        let csv = export_contacts_csv(&extraction, None, &CsvOptions::default()).unwrap();
         assert!(csv.contains("John Doe"));
         assert!(csv.contains("Acme Corp"));
         assert!(csv.contains("john@acme.com"));
+        assert!(csv.contains("@johndoe,42"));
+    }
+
+    fn contact(name: &str, phone: Option<&str>, telegram: Option<&str>) -> Contact {
+        Contact {
+            name: Some(name.to_string()),
+            phone: phone.map(String::from),
+            telegram: telegram.map(String::from),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_merge_contacts_yields_union() {
+        let existing = vec![
+            contact("Ivan", Some("+7 999 123-45-67"), None),
+            contact("Anna", None, Some("@anna_dev")),
+        ];
+        let fresh = vec![
+            contact("Ivan Petrov", Some("+79991234567"), Some("@ivanp")),
+            contact("Oleg", None, Some("@oleg")),
+        ];
+
+        let merged = merge_contacts(existing, &fresh);
+        let names: Vec<_> = merged.iter().map(|c| c.name.as_deref().unwrap()).collect();
+        assert_eq!(names, vec!["Ivan Petrov", "Anna", "Oleg"]);
+        assert_eq!(merged[0].telegram.as_deref(), Some("@ivanp"));
+
+        // Usernames match case-insensitively, with or without `@`
+        let again = merge_contacts(merged, &[contact("Anna K", None, Some("ANNA_DEV"))]);
+        assert_eq!(again.len(), 3);
+        assert_eq!(again[1].name.as_deref(), Some("Anna K"));
+    }
+
+    #[test]
+    fn test_export_contacts_csv_merges_into_existing_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("contacts.csv");
+        let options = CsvOptions::default();
+
+        let first = CrmExtraction {
+            contacts: vec![contact("Ivan", Some("+7 999 123-45-67"), None)],
+            ..Default::default()
+        };
+        std::fs::write(
+            &path,
+            export_contacts_csv(&first, Some(&path), &options).unwrap(),
+        )
+        .unwrap();
+
+        let second = CrmExtraction {
+            contacts: vec![
+                contact("Ivan Petrov", Some("+79991234567"), None),
+                contact("Oleg", None, Some("@oleg")),
+            ],
+            ..Default::default()
+        };
+        std::fs::write(
+            &path,
+            export_contacts_csv(&second, Some(&path), &options).unwrap(),
+        )
+        .unwrap();
+
+        let contacts = read_contacts_csv(&path, &options).unwrap();
+        assert_eq!(contacts.len(), 2);
+        assert_eq!(contacts[0].name.as_deref(), Some("Ivan Petrov"));
+        assert_eq!(contacts[1].telegram.as_deref(), Some("@oleg"));
+        assert_eq!(contacts[1].phone, None);
     }
 
     #[test]
@@ -370,9 +819,58 @@ mod tests {
             ..Default::default()
         };
 
-        let csv = export_deals_csv(&extraction);
+        let csv = export_deals_csv(&extraction, &CsvOptions::default()).unwrap();
         assert!(csv.contains("Big Deal"));
         assert!(csv.contains("negotiation"));
         assert!(csv.contains("$10000"));
     }
+
+    #[test]
+    fn test_parse_amount_handles_multipliers_and_currencies() {
+        let cases = [
+            ("бюджет 150 тыс руб", 150_000.0, "RUB"),
+            ("около 1,5 млн ₽ в месяц", 1_500_000.0, "RUB"),
+            ("2 000 000 рублей", 2_000_000.0, "RUB"),
+            ("ставка 300$ в час", 300.0, "USD"),
+            ("$10000 upfront", 10_000.0, "USD"),
+            ("10,000 USD", 10_000.0, "USD"),
+            ("€ 2.5k", 2_500.0, "EUR"),
+            ("99.90 eur", 99.9, "EUR"),
+            ("50к ₽", 50_000.0, "RUB"),
+        ];
+        for (text, amount, currency) in cases {
+            let (parsed, code) = parse_amount(text).unwrap_or_else(|| panic!("{}", text));
+            assert!((parsed - amount).abs() < 1e-6, "{}: {}", text, parsed);
+            assert_eq!(code, currency, "{}", text);
+        }
+        assert_eq!(parse_amount("встреча в 15:00, 3 человека"), None);
+    }
+
+    #[test]
+    fn test_deal_amounts_from_model_or_fallback() {
+        let json = r#"{"contacts": [], "deals": [
+            {"title": "Сайт", "stage": "proposal", "amount": "1,2 млн", "currency": "RUB"},
+            {"title": "Поддержка", "stage": "lead", "estimated_value": "80 тыс руб"}
+        ], "action_items": []}"#;
+        let mut extraction: CrmExtraction = serde_json::from_str(json).unwrap();
+        fill_deal_amounts(&mut extraction, "");
+        assert_eq!(extraction.deals[0].amount, Some(1_200_000.0));
+        assert_eq!(extraction.deals[1].amount, Some(80_000.0));
+        assert_eq!(extraction.deals[1].currency.as_deref(), Some("RUB"));
+
+        // No deals from the model: money mentions in the conversation become leads
+        let mut empty = CrmExtraction::default();
+        fill_deal_amounts(
+            &mut empty,
+            "[01.05 10:00] @anna: привет\n[01.05 10:05] @ivan: готовы за 500$ в месяц",
+        );
+        assert_eq!(empty.deals.len(), 1);
+        assert_eq!(empty.deals[0].amount, Some(500.0));
+        assert_eq!(empty.deals[0].stage.as_deref(), Some("lead"));
+
+        let csv = export_deals_csv(&extraction, &CsvOptions::default()).unwrap();
+        assert!(csv.contains("1200000,RUB"), "{}", csv);
+        assert_eq!(format_amount(1_500_000.0), "1 500 000");
+        assert_eq!(format_amount(99.5), "99.5");
+    }
 }
@@ -1,14 +1,37 @@
 //! Delete Zoom messages command
 //!
 //! Equivalent to Python's delete_zoom_messages.py
+//!
+//! `--dry-run` lists what would be deleted, with identical snippets grouped
+//! and counted, so the link heuristic can be checked before anything is removed.
+//! A real run asks to confirm the resolved chat first unless `--yes` is given.
 
+use crate::chat::{fetch_messages, FetchOptions};
+use crate::confirm::confirm_chat;
+use crate::dry_run::{ActionKind, DryRunPlan, PlanFormat};
 use crate::error::{Error, Result};
 use crate::session::{get_client, SessionLock};
 
-const ZOOM_URL_PREFIX: &str = "https://us06web.zoom.us/";
+const ZOOM_DOMAIN: &str = "zoom.us";
+
+/// Whether `word` is a link to zoom.us or one of its subdomains (`us06web.zoom.us`)
+fn is_zoom_link(word: &str) -> bool {
+    let lower = word.to_lowercase();
+    let rest = lower
+        .strip_prefix("https://")
+        .or_else(|| lower.strip_prefix("http://"))
+        .unwrap_or(&lower);
+    let host = rest
+        .split(['/', '?', '#', ':'])
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches('.');
+    host == ZOOM_DOMAIN || host.ends_with(&format!(".{}", ZOOM_DOMAIN))
+}
 
 fn contains_zoom_link(text: &str) -> bool {
-    text.contains(ZOOM_URL_PREFIX)
+    text.split(|c: char| c.is_whitespace() || matches!(c, '(' | ')' | '<' | '>' | '"'))
+        .any(is_zoom_link)
 }
 
 fn preview_text(text: &str, max_chars: usize) -> String {
@@ -19,9 +42,37 @@ fn preview_text(text: &str, max_chars: usize) -> String {
     preview
 }
 
-pub async fn run(username: &str, limit: usize) -> Result<()> {
+/// Matched messages sharing the same snippet
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct MatchGroup {
+    preview: String,
+    ids: Vec<i32>,
+}
+
+/// Group matches by snippet, largest groups first (ties keep scan order)
+fn group_matches(matches: &[(i32, String)]) -> Vec<MatchGroup> {
+    let mut groups: Vec<MatchGroup> = Vec::new();
+    for (id, preview) in matches {
+        match groups.iter_mut().find(|g| &g.preview == preview) {
+            Some(group) => group.ids.push(*id),
+            None => groups.push(MatchGroup {
+                preview: preview.clone(),
+                ids: vec![*id],
+            }),
+        }
+    }
+    groups.sort_by_key(|g| std::cmp::Reverse(g.ids.len()));
+    groups
+}
+
+pub async fn run(
+    username: &str,
+    limit: usize,
+    dry_run: Option<PlanFormat>,
+    yes: bool,
+) -> Result<()> {
     // Acquire session lock
-    let _lock = SessionLock::acquire()?;
+    let _lock = SessionLock::acquire().await?;
 
     // Connect to Telegram
     let client = get_client().await?;
@@ -42,35 +93,66 @@ pub async fn run(username: &str, limit: usize) -> Result<()> {
     println!("Поиск сообщений с Zoom ссылками в чате с @{}", username);
 
     // Collect messages
-    let mut messages = Vec::new();
-    let mut iter = client.iter_messages(&chat);
-
-    while let Some(msg) = iter.next().await.transpose() {
-        let msg = msg.map_err(|e| Error::TelegramError(e.to_string()))?;
-        messages.push(msg);
-        if messages.len() >= limit {
-            break;
+    let options = FetchOptions {
+        limit: Some(limit),
+        ..Default::default()
+    };
+    let messages = fetch_messages(&client, &chat, options).await?;
+
+    let matched: Vec<_> = messages
+        .iter()
+        .filter(|msg| contains_zoom_link(msg.text()))
+        .collect();
+    println!(
+        "Найдено {} сообщений с Zoom ссылками из {} просмотренных",
+        matched.len(),
+        messages.len()
+    );
+
+    if let Some(format) = dry_run {
+        let mut plan = DryRunPlan::new("delete_zoom");
+        let previews: Vec<(i32, String)> = matched
+            .iter()
+            .map(|msg| (msg.id(), preview_text(msg.text(), 50)))
+            .collect();
+        for group in group_matches(&previews) {
+            let ids: Vec<String> = group.ids.iter().map(|id| id.to_string()).collect();
+            println!(
+                "Будет удалено ×{} [{}]: {}",
+                group.ids.len(),
+                ids.join(", "),
+                group.preview
+            );
         }
+        for (id, preview) in previews {
+            plan.record(
+                ActionKind::Delete,
+                format!("@{}", username),
+                Some(id),
+                preview,
+            );
+        }
+        return plan.print(format);
     }
 
-    let mut deleted_count = 0;
-
-    for msg in &messages {
-        let text = msg.text();
-        if contains_zoom_link(text) {
-            let timestamp = msg.date().format("%d.%m.%Y %H:%M:%S").to_string();
-            let sender = if msg.outgoing() { "Я" } else { &name };
-
-            let preview = preview_text(text, 50);
-
-            println!("Удаляю: {} {}: {}", timestamp, sender, preview);
+    if !matched.is_empty() {
+        let action = format!("delete {} Zoom message(s)", matched.len());
+        confirm_chat(&chat, &action, yes)?;
+    }
 
-            // Delete message for both users (revoke)
-            if let Err(e) = client.delete_messages(&chat, &[msg.id()]).await {
-                eprintln!("Failed to delete message: {}", e);
-            } else {
-                deleted_count += 1;
-            }
+    let mut deleted_count = 0;
+    for msg in matched {
+        let timestamp = msg.date().format("%d.%m.%Y %H:%M:%S").to_string();
+        let sender = if msg.outgoing() { "Я" } else { &name };
+        let preview = preview_text(msg.text(), 50);
+
+        println!("Удаляю: {} {}: {}", timestamp, sender, preview);
+
+        // Delete message for both users (revoke)
+        if let Err(e) = client.delete_messages(&chat, &[msg.id()]).await {
+            eprintln!("Failed to delete message: {}", e);
+        } else {
+            deleted_count += 1;
         }
     }
 
@@ -89,6 +171,49 @@ mod tests {
         assert!(!contains_zoom_link("https://example.com/zoom"));
     }
 
+    #[test]
+    fn contains_zoom_link_covers_zoom_subdomains() {
+        assert!(contains_zoom_link("https://zoom.us/j/123?pwd=abc"));
+        assert!(contains_zoom_link(
+            "Созвон: https://company.zoom.us/my/room"
+        ));
+        assert!(contains_zoom_link("ссылка (http://US02WEB.ZOOM.US/j/9)"));
+        assert!(contains_zoom_link("us05web.zoom.us/j/77"));
+        assert!(contains_zoom_link("https://zoom.us"));
+
+        assert!(!contains_zoom_link("https://notzoom.us/j/1"));
+        assert!(!contains_zoom_link("https://zoom.us.evil.com/j/1"));
+        assert!(!contains_zoom_link("https://meet.google.com/abc"));
+        assert!(!contains_zoom_link("обсудим zoom завтра"));
+    }
+
+    #[test]
+    fn group_matches_counts_identical_snippets() {
+        let matches = vec![
+            (1, "Zoom A".to_string()),
+            (2, "Zoom B".to_string()),
+            (3, "Zoom B".to_string()),
+            (4, "Zoom C".to_string()),
+        ];
+        assert_eq!(
+            group_matches(&matches),
+            vec![
+                MatchGroup {
+                    preview: "Zoom B".to_string(),
+                    ids: vec![2, 3]
+                },
+                MatchGroup {
+                    preview: "Zoom A".to_string(),
+                    ids: vec![1]
+                },
+                MatchGroup {
+                    preview: "Zoom C".to_string(),
+                    ids: vec![4]
+                },
+            ]
+        );
+    }
+
     #[test]
     fn preview_text_returns_full_text_when_short() {
         assert_eq!(preview_text("hello", 50), "hello");
@@ -12,13 +12,18 @@ use crate::session::{get_client, SessionLock};
 use chrono::{DateTime, Utc};
 use futures::stream::{self, StreamExt};
 use grammers_client::types::peer::Peer;
+use grammers_client::types::Dialog;
 use grammers_client::Client;
+use grammers_tl_types as tl;
 use serde::Serialize;
 
 const DEFAULT_PARALLEL_FETCH: usize = 8;
 const _: () = assert!(DEFAULT_PARALLEL_FETCH > 0);
 
-#[derive(Debug, Clone, Serialize)]
+/// Папка архива в Telegram.
+const ARCHIVE_FOLDER_ID: i32 = 1;
+
+#[derive(Debug, Clone, Default, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub struct DialogInfo {
     pub title: String,
@@ -27,6 +32,112 @@ pub struct DialogInfo {
     pub unread: i32,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub last_message: Option<DateTime<Utc>>,
+    pub pinned: bool,
+    pub archived: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub folder_id: Option<i32>,
+    pub muted: bool,
+    /// Конец временного мьюта (`None` — не замьючен или навсегда).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub muted_until: Option<DateTime<Utc>>,
+}
+
+/// Адаптер над диалогом grammers: всё, что нужно для строки [`DialogInfo`].
+pub trait DialogSource {
+    fn title(&self) -> String;
+    fn id(&self) -> i64;
+    /// `None` для диалогов, которые не показываем (боты).
+    fn chat_type(&self) -> Option<&'static str>;
+    fn unread(&self) -> i32;
+    fn pinned(&self) -> bool;
+    fn folder_id(&self) -> Option<i32>;
+    /// Unix-время окончания мьюта из настроек уведомлений.
+    fn mute_until(&self) -> Option<i32>;
+    fn last_message_date(&self) -> Option<DateTime<Utc>>;
+}
+
+impl DialogSource for Dialog {
+    fn title(&self) -> String {
+        chat_title(&self.peer)
+    }
+
+    fn id(&self) -> i64 {
+        peer_id(&self.peer)
+    }
+
+    fn chat_type(&self) -> Option<&'static str> {
+        classify_peer(&self.peer)
+    }
+
+    fn unread(&self) -> i32 {
+        extract_unread_count(self)
+    }
+
+    fn pinned(&self) -> bool {
+        match &self.raw {
+            tl::enums::Dialog::Dialog(d) => d.pinned,
+            tl::enums::Dialog::Folder(f) => f.pinned,
+        }
+    }
+
+    fn folder_id(&self) -> Option<i32> {
+        match &self.raw {
+            tl::enums::Dialog::Dialog(d) => d.folder_id,
+            tl::enums::Dialog::Folder(_) => None,
+        }
+    }
+
+    fn mute_until(&self) -> Option<i32> {
+        match &self.raw {
+            tl::enums::Dialog::Dialog(d) => {
+                let tl::enums::PeerNotifySettings::Settings(settings) = &d.notify_settings;
+                settings.mute_until
+            }
+            tl::enums::Dialog::Folder(_) => None,
+        }
+    }
+
+    fn last_message_date(&self) -> Option<DateTime<Utc>> {
+        self.last_message.as_ref().map(|m| m.date())
+    }
+}
+
+/// Собрать строку диалога; `None` для ботов.
+pub fn dialog_row(dialog: &impl DialogSource, now: DateTime<Utc>) -> Option<DialogInfo> {
+    let chat_type = dialog.chat_type()?;
+
+    let mute_until = dialog
+        .mute_until()
+        .filter(|&until| i64::from(until) > now.timestamp());
+    let folder_id = dialog.folder_id();
+
+    Some(DialogInfo {
+        title: dialog.title(),
+        id: dialog.id(),
+        chat_type: chat_type.to_string(),
+        unread: dialog.unread(),
+        last_message: dialog.last_message_date(),
+        pinned: dialog.pinned(),
+        archived: folder_id == Some(ARCHIVE_FOLDER_ID),
+        folder_id,
+        muted: mute_until.is_some(),
+        muted_until: mute_until
+            .filter(|&until| until != i32::MAX)
+            .and_then(|until| DateTime::from_timestamp(i64::from(until), 0)),
+    })
+}
+
+/// Флаги для таблицы: 📌 закреплён, 🔕 без звука, 🗄 в архиве.
+fn flags(dialog: &DialogInfo) -> String {
+    [
+        (dialog.pinned, "📌"),
+        (dialog.muted, "🔕"),
+        (dialog.archived, "🗄"),
+    ]
+    .iter()
+    .filter(|(on, _)| *on)
+    .map(|(_, icon)| *icon)
+    .collect()
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -50,24 +161,47 @@ impl OutputFormat {
     }
 }
 
+/// Порядок диалогов в выводе; при равенстве — по ID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DialogSort {
+    /// Сначала свежие (по дате последнего сообщения)
+    Recent,
+    /// Сначала с большим числом непрочитанных
+    Unread,
+    /// По названию, по алфавиту
+    Name,
+}
+
+impl DialogSort {
+    pub fn parse(raw: &str) -> Result<Self> {
+        match raw.to_ascii_lowercase().as_str() {
+            "recent" => Ok(Self::Recent),
+            "unread" => Ok(Self::Unread),
+            "name" | "title" => Ok(Self::Name),
+            other => Err(Error::InvalidArgument(format!(
+                "Unsupported sort '{}'. Use recent|unread|name",
+                other
+            ))),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 struct PendingChat {
-    title: String,
-    id: i64,
-    unread: i32,
-    chat_type: String,
+    info: DialogInfo,
     peer: Peer,
 }
 
 /// Основная точка входа для CLI.
-pub async fn run(limit: usize, format: &str, output: Option<PathBuf>) -> Result<()> {
+pub async fn run(limit: usize, format: &str, sort: &str, output: Option<PathBuf>) -> Result<()> {
     let fmt = OutputFormat::parse(format)?;
+    let sort = DialogSort::parse(sort)?;
 
-    let _lock = SessionLock::acquire()?;
+    let _lock = SessionLock::acquire().await?;
     let client = get_client().await?;
 
     let mut dialogs = fetch_dialogs(&client, limit).await?;
-    sort_dialogs(&mut dialogs);
+    sort_dialogs(&mut dialogs, sort);
 
     match fmt {
         OutputFormat::Table => print_table(&dialogs),
@@ -83,20 +217,27 @@ pub async fn run(limit: usize, format: &str, output: Option<PathBuf>) -> Result<
     Ok(())
 }
 
-fn sort_dialogs(dialogs: &mut [DialogInfo]) {
-    dialogs.sort_by(|a, b| match (&a.last_message, &b.last_message) {
-        (Some(a_ts), Some(b_ts)) => b_ts.cmp(a_ts),
-        (Some(_), None) => Ordering::Less,
-        (None, Some(_)) => Ordering::Greater,
-        (None, None) => Ordering::Equal,
+fn sort_dialogs(dialogs: &mut [DialogInfo], sort: DialogSort) {
+    dialogs.sort_by(|a, b| {
+        let order = match sort {
+            DialogSort::Recent => match (&a.last_message, &b.last_message) {
+                (Some(a_ts), Some(b_ts)) => b_ts.cmp(a_ts),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            },
+            DialogSort::Unread => b.unread.cmp(&a.unread),
+            DialogSort::Name => a.title.to_lowercase().cmp(&b.title.to_lowercase()),
+        };
+        order.then(a.id.cmp(&b.id))
     });
 }
 
 fn print_table(dialogs: &[DialogInfo]) {
     println!("Диалоги: {}\n", dialogs.len());
     println!(
-        "{:<4} {:<16} {:<9} {:<12} Последнее сообщение | Заголовок",
-        "#", "ID", "Тип", "Непрочитано"
+        "{:<4} {:<16} {:<9} {:<12} {:<6} Последнее сообщение | Заголовок",
+        "#", "ID", "Тип", "Непрочитано", "Флаги"
     );
     println!("{}", "-".repeat(80));
 
@@ -107,11 +248,12 @@ fn print_table(dialogs: &[DialogInfo]) {
             .unwrap_or_else(|| "-".to_string());
 
         println!(
-            "{:<4} {:<16} {:<9} {:<12} {} | {}",
+            "{:<4} {:<16} {:<9} {:<12} {:<6} {} | {}",
             idx + 1,
             dialog.id,
             dialog.chat_type,
             dialog.unread,
+            flags(dialog),
             ts,
             dialog.title
         );
@@ -153,8 +295,8 @@ fn persist(path: &Path, fmt: OutputFormat, dialogs: &[DialogInfo]) -> Result<()>
 fn render_table(dialogs: &[DialogInfo]) -> String {
     let mut out = String::new();
     out.push_str(&format!(
-        "{:<4} {:<16} {:<9} {:<12} {}\n",
-        "#", "ID", "Тип", "Непрочитано", "Последнее сообщение | Заголовок"
+        "{:<4} {:<16} {:<9} {:<12} {:<6} {}\n",
+        "#", "ID", "Тип", "Непрочитано", "Флаги", "Последнее сообщение | Заголовок"
     ));
     out.push_str(&"-".repeat(80));
     out.push('\n');
@@ -166,11 +308,12 @@ fn render_table(dialogs: &[DialogInfo]) -> String {
             .unwrap_or_else(|| "-".to_string());
 
         out.push_str(&format!(
-            "{:<4} {:<16} {:<9} {:<12} {} | {}\n",
+            "{:<4} {:<16} {:<9} {:<12} {:<6} {} | {}\n",
             idx + 1,
             dialog.id,
             dialog.chat_type,
             dialog.unread,
+            flags(dialog),
             ts,
             dialog.title
         ));
@@ -184,32 +327,19 @@ async fn fetch_dialogs(client: &Client, limit: usize) -> Result<Vec<DialogInfo>>
     let mut pending: Vec<PendingChat> = Vec::new();
     let mut dialogs = client.iter_dialogs();
 
+    let now = Utc::now();
     let mut count = 0;
     while let Some(dialog) = dialogs.next().await? {
-        let peer = dialog.peer.clone();
-        let Some(chat_type) = classify_peer(&peer) else {
+        let Some(info) = dialog_row(&dialog, now) else {
             continue;
         };
 
-        let title = chat_title(&peer);
-        let id = peer_id(&peer);
-        let unread = extract_unread_count(&dialog);
-
-        if let Some(last_message) = dialog.last_message.as_ref() {
-            chat_activity.push(DialogInfo {
-                title,
-                id,
-                last_message: Some(last_message.date()),
-                unread,
-                chat_type: chat_type.to_string(),
-            });
+        if info.last_message.is_some() {
+            chat_activity.push(info);
         } else {
             pending.push(PendingChat {
-                title,
-                id,
-                unread,
-                chat_type: chat_type.to_string(),
-                peer,
+                info,
+                peer: dialog.peer.clone(),
             });
         }
 
@@ -268,7 +398,7 @@ fn peer_id(chat: &Peer) -> i64 {
     }
 }
 
-fn extract_unread_count(dialog: &grammers_client::types::Dialog) -> i32 {
+fn extract_unread_count(dialog: &Dialog) -> i32 {
     match &dialog.raw {
         grammers_tl_types::enums::Dialog::Dialog(d) => d.unread_count,
         grammers_tl_types::enums::Dialog::Folder(folder) => {
@@ -290,16 +420,13 @@ async fn fetch_missing_last_messages(
             let mut messages = client.iter_messages(&chat.peer);
             match messages.next().await.transpose() {
                 Some(Ok(msg)) => Some(DialogInfo {
-                    title: chat.title,
-                    id: chat.id,
                     last_message: Some(msg.date()),
-                    unread: chat.unread,
-                    chat_type: chat.chat_type,
+                    ..chat.info
                 }),
                 Some(Err(err)) => {
                     eprintln!(
                         "Не удалось загрузить последнее сообщение для {}: {}",
-                        chat.title, err
+                        chat.info.title, err
                     );
                     None
                 }
@@ -376,6 +503,7 @@ mod tests {
                 chat_type: "user".to_string(),
                 unread: 0,
                 last_message: Some(t2),
+                ..Default::default()
             },
             DialogInfo {
                 title: "Newer".to_string(),
@@ -383,6 +511,7 @@ mod tests {
                 chat_type: "user".to_string(),
                 unread: 0,
                 last_message: Some(t1),
+                ..Default::default()
             },
             DialogInfo {
                 title: "NoMessage".to_string(),
@@ -390,16 +519,82 @@ mod tests {
                 chat_type: "user".to_string(),
                 unread: 0,
                 last_message: None,
+                ..Default::default()
             },
         ];
 
-        sort_dialogs(&mut dialogs);
+        sort_dialogs(&mut dialogs, DialogSort::Recent);
 
         assert_eq!(dialogs[0].title, "Newer");
         assert_eq!(dialogs[1].title, "Older");
         assert_eq!(dialogs[2].title, "NoMessage");
     }
 
+    fn dialog(id: i64, title: &str, unread: i32) -> DialogInfo {
+        DialogInfo {
+            title: title.to_string(),
+            id,
+            chat_type: "group".to_string(),
+            unread,
+            ..Default::default()
+        }
+    }
+
+    fn ids(dialogs: &[DialogInfo]) -> Vec<i64> {
+        dialogs.iter().map(|d| d.id).collect()
+    }
+
+    #[test]
+    fn sort_parse_accepts_known_orders() {
+        assert_eq!(DialogSort::parse("recent").unwrap(), DialogSort::Recent);
+        assert_eq!(DialogSort::parse("UNREAD").unwrap(), DialogSort::Unread);
+        assert_eq!(DialogSort::parse("name").unwrap(), DialogSort::Name);
+        assert!(DialogSort::parse("size").is_err());
+    }
+
+    #[test]
+    fn sort_by_unread_then_id() {
+        let mut dialogs = vec![
+            dialog(7, "b", 2),
+            dialog(3, "a", 10),
+            dialog(5, "c", 2),
+            dialog(1, "d", 0),
+        ];
+        sort_dialogs(&mut dialogs, DialogSort::Unread);
+        assert_eq!(ids(&dialogs), vec![3, 5, 7, 1]);
+    }
+
+    #[test]
+    fn sort_by_name_then_id() {
+        let mut dialogs = vec![
+            dialog(4, "beta", 0),
+            dialog(9, "Alpha", 0),
+            dialog(2, "alpha", 0),
+            dialog(1, "Гамма", 0),
+        ];
+        sort_dialogs(&mut dialogs, DialogSort::Name);
+        assert_eq!(ids(&dialogs), vec![2, 9, 4, 1]);
+    }
+
+    #[test]
+    fn sort_recent_breaks_ties_by_id() {
+        let at = Utc::now();
+        let mut dialogs = vec![
+            DialogInfo {
+                last_message: Some(at),
+                ..dialog(8, "x", 0)
+            },
+            dialog(6, "no messages", 0),
+            DialogInfo {
+                last_message: Some(at),
+                ..dialog(2, "y", 0)
+            },
+            dialog(4, "no messages either", 0),
+        ];
+        sort_dialogs(&mut dialogs, DialogSort::Recent);
+        assert_eq!(ids(&dialogs), vec![2, 8, 4, 6]);
+    }
+
     #[test]
     fn render_table_contains_headers() {
         let dialogs = vec![DialogInfo {
@@ -408,6 +603,7 @@ mod tests {
             chat_type: "group".to_string(),
             unread: 5,
             last_message: Some(Utc::now()),
+            ..Default::default()
         }];
 
         let table = render_table(&dialogs);
@@ -417,6 +613,113 @@ mod tests {
         assert!(table.contains("123"));
     }
 
+    struct FakeDialog {
+        chat_type: Option<&'static str>,
+        pinned: bool,
+        folder_id: Option<i32>,
+        mute_until: Option<i32>,
+    }
+
+    impl Default for FakeDialog {
+        fn default() -> Self {
+            Self {
+                chat_type: Some("group"),
+                pinned: false,
+                folder_id: None,
+                mute_until: None,
+            }
+        }
+    }
+
+    impl DialogSource for FakeDialog {
+        fn title(&self) -> String {
+            "Team".to_string()
+        }
+        fn id(&self) -> i64 {
+            42
+        }
+        fn chat_type(&self) -> Option<&'static str> {
+            self.chat_type
+        }
+        fn unread(&self) -> i32 {
+            3
+        }
+        fn pinned(&self) -> bool {
+            self.pinned
+        }
+        fn folder_id(&self) -> Option<i32> {
+            self.folder_id
+        }
+        fn mute_until(&self) -> Option<i32> {
+            self.mute_until
+        }
+        fn last_message_date(&self) -> Option<DateTime<Utc>> {
+            None
+        }
+    }
+
+    fn now() -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000, 0).unwrap()
+    }
+
+    #[test]
+    fn dialog_row_maps_pinned_muted_and_archived() {
+        let fake = FakeDialog {
+            pinned: true,
+            folder_id: Some(ARCHIVE_FOLDER_ID),
+            mute_until: Some(1_700_003_600),
+            ..Default::default()
+        };
+
+        let row = dialog_row(&fake, now()).unwrap();
+        assert_eq!(row.title, "Team");
+        assert_eq!(row.id, 42);
+        assert_eq!(row.unread, 3);
+        assert!(row.pinned);
+        assert!(row.archived);
+        assert_eq!(row.folder_id, Some(1));
+        assert!(row.muted);
+        assert_eq!(row.muted_until, DateTime::from_timestamp(1_700_003_600, 0));
+        assert_eq!(flags(&row), "📌🔕🗄");
+    }
+
+    #[test]
+    fn dialog_row_treats_expired_mute_as_unmuted() {
+        let fake = FakeDialog {
+            mute_until: Some(1_600_000_000),
+            folder_id: Some(5),
+            ..Default::default()
+        };
+
+        let row = dialog_row(&fake, now()).unwrap();
+        assert!(!row.muted);
+        assert_eq!(row.muted_until, None);
+        assert!(!row.archived);
+        assert_eq!(row.folder_id, Some(5));
+        assert_eq!(flags(&row), "");
+    }
+
+    #[test]
+    fn dialog_row_muted_forever_has_no_end_date() {
+        let fake = FakeDialog {
+            mute_until: Some(i32::MAX),
+            ..Default::default()
+        };
+
+        let row = dialog_row(&fake, now()).unwrap();
+        assert!(row.muted);
+        assert_eq!(row.muted_until, None);
+    }
+
+    #[test]
+    fn dialog_row_skips_bots() {
+        let fake = FakeDialog {
+            chat_type: None,
+            ..Default::default()
+        };
+        assert!(dialog_row(&fake, now()).is_none());
+    }
+
     #[test]
     fn dialog_info_serialization() {
         let dialog = DialogInfo {
@@ -425,6 +728,7 @@ mod tests {
             chat_type: "channel".to_string(),
             unread: 10,
             last_message: None,
+            ..Default::default()
         };
 
         let json = serde_json::to_string(&dialog).unwrap();
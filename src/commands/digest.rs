@@ -2,17 +2,26 @@
 //!
 //! Generates AI-powered summaries of chat discussions for stories/reports
 
+use crate::chat::{scan_messages, FetchOptions};
+use crate::commands::chat_analyzer::{language_instruction, strip_code_fences};
+use crate::commands::send_message::{self, split_message, TELEGRAM_MESSAGE_LIMIT};
 use crate::error::{Error, Result};
-use crate::session::{get_client, SessionLock};
+use crate::important::{trim_preserving, ImportantSenders, IMPORTANT_MARKER};
+use crate::metrics;
+use crate::session::{get_client, SessionLock, TelegramClient};
+use crate::watermark::{FetchBounds, Watermark, WatermarkStore};
 use async_openai::{
     config::OpenAIConfig,
     types::{
         ChatCompletionRequestMessage, ChatCompletionRequestSystemMessage,
-        ChatCompletionRequestUserMessage, CreateChatCompletionRequest,
+        ChatCompletionRequestUserMessage, CreateChatCompletionRequest, ResponseFormat,
     },
     Client as OpenAIClient,
 };
 use chrono::{DateTime, Duration, Utc};
+use grammers_client::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 const DIGEST_SYSTEM_PROMPT: &str = r#"Ты — эксперт по анализу чатов. Твоя задача — создать краткий дайджест обсуждений.
 
@@ -39,8 +48,26 @@ const DIGEST_SYSTEM_PROMPT: &str = r#"Ты — эксперт по анализ
 - Активных участников: N
 - Самая обсуждаемая тема: X
 
+Сообщения с пометкой ⭐ написаны ключевыми участниками — обязательно отрази их позицию.
+
 Пиши кратко, по делу, с эмодзи. Максимум 500 слов."#;
 
+const DIGEST_STRUCTURED_PROMPT: &str = r#"Ты — эксперт по анализу чатов. Твоя задача — создать краткий дайджест обсуждений.
+
+Верни ТОЛЬКО JSON-объект следующего вида:
+{
+  "summary": "2-3 предложения о главном за период",
+  "highlights": ["ключевая тема или инсайт", "..."],
+  "action_items": ["кто и что должен сделать", "..."],
+  "decisions": ["принятое решение", "..."]
+}
+
+Сообщения с пометкой ⭐ написаны ключевыми участниками — обязательно отрази их позицию.
+Если раздел пуст — верни пустой массив. Пиши кратко и по делу."#;
+
+/// Watermark key for `--since-last` runs
+const WATERMARK_COMMAND: &str = "digest";
+
 /// Digest configuration
 pub struct DigestConfig {
     /// Time period for digest (hours)
@@ -51,6 +78,14 @@ pub struct DigestConfig {
     pub model: String,
     /// Output format (markdown, text, html)
     pub format: DigestFormat,
+    /// Extra important sender ids/usernames (added to config.yml ones)
+    pub important_senders: Vec<String>,
+    /// Only process messages newer than the previous successful run
+    pub since_last: bool,
+    /// Also send the digest to this chat (alias, @username or user id)
+    pub post_to: Option<String>,
+    /// Write the digest in this language instead of the chat's own
+    pub language: Option<String>,
 }
 
 impl Default for DigestConfig {
@@ -60,6 +95,10 @@ impl Default for DigestConfig {
             max_messages: 500,
             model: "gpt-4o-mini".to_string(),
             format: DigestFormat::Markdown,
+            important_senders: Vec::new(),
+            since_last: false,
+            post_to: None,
+            language: None,
         }
     }
 }
@@ -71,107 +110,305 @@ pub enum DigestFormat {
     Html,
 }
 
+/// Structured digest for automations
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Digest {
+    pub summary: String,
+    pub highlights: Vec<String>,
+    pub action_items: Vec<String>,
+    pub decisions: Vec<String>,
+}
+
+impl Digest {
+    /// Render the digest as Markdown prose
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::from("## 📊 Дайджест чата\n");
+
+        if !self.summary.is_empty() {
+            out.push_str(&format!("\n{}\n", self.summary));
+        }
+
+        let sections = [
+            ("🔥 Главное", &self.highlights),
+            ("✅ Задачи", &self.action_items),
+            ("🤝 Решения", &self.decisions),
+        ];
+        for (title, items) in sections {
+            if items.is_empty() {
+                continue;
+            }
+            out.push_str(&format!("\n### {}\n", title));
+            for item in items {
+                out.push_str(&format!("- {}\n", item));
+            }
+        }
+
+        out
+    }
+}
+
 /// Message data for digest
 struct MessageData {
+    id: i32,
     sender: String,
     text: String,
     timestamp: DateTime<Utc>,
     reactions: i32,
+    important: bool,
+}
+
+/// Generate a digest for one or more chats over a single Telegram connection
+///
+/// With several chats the report gets a section per chat, and a chat that
+/// fails (not found, no access) is reported in its section instead of
+/// aborting the whole batch.
+pub async fn run(chats: &[String], config: DigestConfig) -> Result<String> {
+    let openai_client = openai_client()?;
+    let watermarks = WatermarkStore::from_env();
+
+    // Acquire session lock once for the whole batch, posting included
+    let _lock = SessionLock::acquire().await?;
+    let client = get_client().await?;
+
+    let mut sections: Vec<(&str, String)> = Vec::new();
+    let mut pending: Vec<(&str, Watermark)> = Vec::new();
+    for chat_name in chats {
+        match digest_chat(&client, &openai_client, &watermarks, chat_name, &config).await {
+            Ok((digest, watermark)) => {
+                sections.push((chat_name.as_str(), digest));
+                pending.extend(watermark.map(|w| (chat_name.as_str(), w)));
+            }
+            Err(e) if chats.len() > 1 => {
+                eprintln!("⚠️ {}: {}", chat_name, e);
+                sections.push((
+                    chat_name.as_str(),
+                    format!("⚠️ Не удалось построить дайджест: {}", e),
+                ));
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    let report = combine_sections(&sections);
+    // Watermarks move only once every part of the report is posted
+    post_digest(&client, &config, &report).await?;
+    for (chat_name, watermark) in pending {
+        watermarks.save(WATERMARK_COMMAND, chat_name, &watermark)?;
+    }
+
+    Ok(report)
 }
 
-/// Generate chat digest
-pub async fn run(chat_name: &str, config: DigestConfig) -> Result<String> {
-    // Get OpenAI API key
+/// Digest one chat; returns the text and the watermark to save once it is delivered
+async fn digest_chat(
+    client: &Client,
+    openai_client: &OpenAIClient<OpenAIConfig>,
+    watermarks: &WatermarkStore,
+    chat_name: &str,
+    config: &DigestConfig,
+) -> Result<(String, Option<Watermark>)> {
+    let previous = load_watermark(watermarks, chat_name, config)?;
+    let messages = collect_messages(client, chat_name, config, previous.as_ref()).await?;
+
+    if messages.is_empty() {
+        return Ok(("📭 Нет сообщений за указанный период".to_string(), None));
+    }
+
+    // Prepare chat content for AI
+    let chat_content = prepare_chat_content(&messages);
+
+    // Generate digest with AI
+    let digest = generate_digest(openai_client, config, &chat_content).await?;
+
+    // Add statistics
+    let stats = format!(
+        "\n\n---\n*Проанализировано {} сообщений от {} участников*",
+        messages.len(),
+        count_unique_senders(&messages)
+    );
+
+    Ok((
+        format!("{}{}", digest, stats),
+        next_watermark(config, previous, &messages),
+    ))
+}
+
+/// One chat's digest as is, several as `# 💬 <chat>` sections
+fn combine_sections(sections: &[(&str, String)]) -> String {
+    match sections {
+        [(_, digest)] => digest.clone(),
+        _ => sections
+            .iter()
+            .map(|(chat_name, digest)| format!("# 💬 {}\n\n{}", chat_name, digest))
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+    }
+}
+
+/// Generate a structured digest (summary, highlights, action items, decisions)
+pub async fn run_structured(chat_name: &str, config: DigestConfig) -> Result<Digest> {
+    let openai_client = openai_client()?;
+    let watermarks = WatermarkStore::from_env();
+    let previous = load_watermark(&watermarks, chat_name, &config)?;
+    let _lock = SessionLock::acquire().await?;
+    let client = get_client().await?;
+    let messages = collect_messages(&client, chat_name, &config, previous.as_ref()).await?;
+
+    if messages.is_empty() {
+        return Ok(Digest {
+            summary: "Нет сообщений за указанный период".to_string(),
+            ..Default::default()
+        });
+    }
+
+    let chat_content = prepare_chat_content(&messages);
+    let raw = complete(
+        &openai_client,
+        &config.model,
+        DIGEST_STRUCTURED_PROMPT,
+        digest_user_prompt(&chat_content, config.hours, config.language.as_deref()),
+        true,
+    )
+    .await?;
+
+    let digest = parse_digest(&raw)?;
+    post_digest(&client, &config, &digest.to_markdown()).await?;
+    if let Some(watermark) = next_watermark(&config, previous, &messages) {
+        watermarks.save(WATERMARK_COMMAND, chat_name, &watermark)?;
+    }
+
+    Ok(digest)
+}
+
+/// Send the digest to `--post-to`, split into Telegram-sized messages
+///
+/// The parts go out as plain text over the caller's connection: model output
+/// is not a template, and a digest is not held back by quiet hours.
+async fn post_digest(client: &TelegramClient, config: &DigestConfig, digest: &str) -> Result<()> {
+    let Some(target) = config.post_to.as_deref() else {
+        return Ok(());
+    };
+    let parts = split_message(digest, TELEGRAM_MESSAGE_LIMIT);
+    send_message::deliver_parts(client, target, &parts).await
+}
+
+fn load_watermark(
+    store: &WatermarkStore,
+    chat_name: &str,
+    config: &DigestConfig,
+) -> Result<Option<Watermark>> {
+    if config.since_last {
+        store.load(WATERMARK_COMMAND, chat_name)
+    } else {
+        Ok(None)
+    }
+}
+
+/// The `--since-last` watermark past the processed messages
+fn next_watermark(
+    config: &DigestConfig,
+    previous: Option<Watermark>,
+    messages: &[MessageData],
+) -> Option<Watermark> {
+    if !config.since_last {
+        return None;
+    }
+    Watermark::advance(previous, messages.iter().map(|m| (m.timestamp, m.id)))
+}
+
+fn openai_client() -> Result<OpenAIClient<OpenAIConfig>> {
     let api_key = std::env::var("OPENAI_API_KEY")
         .map_err(|_| Error::InvalidArgument("OPENAI_API_KEY not set".to_string()))?;
 
     let openai_config = OpenAIConfig::new().with_api_key(api_key);
-    let openai_client = OpenAIClient::with_config(openai_config);
-
-    // Acquire session lock
-    let _lock = SessionLock::acquire()?;
-    let client = get_client().await?;
+    Ok(OpenAIClient::with_config(openai_config))
+}
 
-    println!(
-        "📊 Генерирую дайджест чата '{}' за {} часов...",
-        chat_name, config.hours
-    );
+/// Collect recent messages in chronological order
+async fn collect_messages(
+    client: &Client,
+    chat_name: &str,
+    config: &DigestConfig,
+    watermark: Option<&Watermark>,
+) -> Result<Vec<MessageData>> {
+    match watermark {
+        Some(w) => eprintln!(
+            "📊 Генерирую дайджест чата '{}' с {}...",
+            chat_name,
+            w.last_date.format("%Y-%m-%d %H:%M")
+        ),
+        None => eprintln!(
+            "📊 Генерирую дайджест чата '{}' за {} часов...",
+            chat_name, config.hours
+        ),
+    }
 
     // Find chat
-    let chat = crate::chat::find_chat(&client, chat_name).await?;
+    let chat = crate::chat::find_chat(client, chat_name).await?;
 
-    // Calculate time cutoff
-    let cutoff = Utc::now() - Duration::hours(config.hours);
+    // Calculate time cutoff (the watermark wins when present)
+    let bounds = FetchBounds::new(watermark, Some(Utc::now() - Duration::hours(config.hours)));
+
+    // Scan past the budget when important senders are configured so their
+    // messages can replace less relevant ones during trimming
+    let important = ImportantSenders::from_config(&config.important_senders);
+    let scan_limit = important.scan_limit(config.max_messages);
 
     // Collect messages
     let mut messages: Vec<MessageData> = Vec::new();
-    let mut iter = client.iter_messages(&chat);
+    let options = FetchOptions {
+        since: bounds.since,
+        ..Default::default()
+    };
+    let mut history = scan_messages(client, &chat, options);
 
-    while let Some(msg_result) = iter.next().await.transpose() {
-        if messages.len() >= config.max_messages {
+    while let Some(msg) = history.next().await? {
+        if messages.len() >= scan_limit {
             break;
         }
 
-        if let Ok(msg) = msg_result {
-            let msg_time: DateTime<Utc> = msg.date();
-            if msg_time < cutoff {
-                break;
-            }
+        let msg_time: DateTime<Utc> = msg.date();
+        if !bounds.is_new(msg.id()) {
+            break;
+        }
 
-            let text = msg.text().trim().to_string();
-            if text.is_empty() {
-                continue;
+        let text = msg.text().trim().to_string();
+        if text.is_empty() {
+            continue;
+        }
+
+        let sender = if let Some(sender) = msg.sender() {
+            match sender {
+                grammers_client::types::Peer::User(u) => u
+                    .username()
+                    .map(|s| format!("@{}", s))
+                    .unwrap_or_else(|| u.full_name()),
+
+                grammers_client::types::Peer::Channel(c) => c.title().to_string(),
+                grammers_client::types::Peer::Group(g) => g.title().unwrap_or("Group").to_string(),
             }
+        } else {
+            "Unknown".to_string()
+        };
 
-            let sender = if let Some(sender) = msg.sender() {
-                match sender {
-                    grammers_client::types::Peer::User(u) => u
-                        .username()
-                        .map(|s| format!("@{}", s))
-                        .unwrap_or_else(|| u.full_name()),
-
-                    grammers_client::types::Peer::Channel(c) => c.title().to_string(),
-                    grammers_client::types::Peer::Group(g) => {
-                        g.title().unwrap_or("Group").to_string()
-                    }
-                }
-            } else {
-                "Unknown".to_string()
-            };
-
-            let reactions = crate::reactions::count_reactions(&msg);
-
-            messages.push(MessageData {
-                sender,
-                text,
-                timestamp: msg_time,
-                reactions,
-            });
-        }
-    }
+        let reactions = crate::reactions::count_reactions(&msg);
 
-    if messages.is_empty() {
-        return Ok("📭 Нет сообщений за указанный период".to_string());
+        messages.push(MessageData {
+            id: msg.id(),
+            sender,
+            text,
+            timestamp: msg_time,
+            reactions,
+            important: important.matches_message(&msg),
+        });
     }
 
+    let mut messages = trim_preserving(messages, config.max_messages, |m| m.important);
+
     // Reverse to chronological order
     messages.reverse();
 
-    // Prepare chat content for AI
-    let chat_content = prepare_chat_content(&messages);
-
-    // Generate digest with AI
-    let digest =
-        generate_digest(&openai_client, &config.model, &chat_content, config.hours).await?;
-
-    // Add statistics
-    let stats = format!(
-        "\n\n---\n*Проанализировано {} сообщений от {} участников*",
-        messages.len(),
-        count_unique_senders(&messages)
-    );
-
-    Ok(format!("{}{}", digest, stats))
+    Ok(messages)
 }
 
 fn prepare_chat_content(messages: &[MessageData]) -> String {
@@ -184,9 +421,16 @@ fn prepare_chat_content(messages: &[MessageData]) -> String {
             String::new()
         };
 
+        let marker = if msg.important {
+            format!("{} ", IMPORTANT_MARKER)
+        } else {
+            String::new()
+        };
+
         content.push_str(&format!(
-            "{} {}: {}{}\n",
+            "{} {}{}: {}{}\n",
             msg.timestamp.format("%H:%M"),
+            marker,
             msg.sender,
             msg.text.chars().take(500).collect::<String>(),
             reactions_str
@@ -201,23 +445,51 @@ fn count_unique_senders(messages: &[MessageData]) -> usize {
     senders.len()
 }
 
+fn digest_user_prompt(chat_content: &str, hours: i64, language: Option<&str>) -> String {
+    let mut prompt = format!(
+        "Проанализируй этот чат за последние {} часов и создай дайджест:\n\n{}",
+        hours, chat_content
+    );
+    if let Some(language) = language {
+        prompt.push_str(&format!("\n\n{}", language_instruction(language)));
+    }
+    prompt
+}
+
 async fn generate_digest(
     client: &OpenAIClient<OpenAIConfig>,
-    model: &str,
+    config: &DigestConfig,
     chat_content: &str,
-    hours: i64,
 ) -> Result<String> {
-    let user_prompt = format!(
-        "Проанализируй этот чат за последние {} часов и создай дайджест:\n\n{}",
-        hours, chat_content
-    );
+    let content = complete(
+        client,
+        &config.model,
+        DIGEST_SYSTEM_PROMPT,
+        digest_user_prompt(chat_content, config.hours, config.language.as_deref()),
+        false,
+    )
+    .await?;
+
+    if content.is_empty() {
+        return Ok("Не удалось сгенерировать дайджест".to_string());
+    }
 
+    Ok(content)
+}
+
+async fn complete(
+    client: &OpenAIClient<OpenAIConfig>,
+    model: &str,
+    system_prompt: &str,
+    user_prompt: String,
+    json_mode: bool,
+) -> Result<String> {
     let request = CreateChatCompletionRequest {
         model: model.to_string(),
         messages: vec![
             ChatCompletionRequestMessage::System(ChatCompletionRequestSystemMessage {
                 content: async_openai::types::ChatCompletionRequestSystemMessageContent::Text(
-                    DIGEST_SYSTEM_PROMPT.to_string(),
+                    system_prompt.to_string(),
                 ),
                 name: None,
             }),
@@ -230,6 +502,7 @@ async fn generate_digest(
         ],
         temperature: Some(0.7),
         max_completion_tokens: Some(1500),
+        response_format: json_mode.then_some(ResponseFormat::JsonObject),
         ..Default::default()
     };
 
@@ -238,17 +511,55 @@ async fn generate_digest(
         .create(request)
         .await
         .map_err(|e| Error::OpenAiError(e.to_string()))?;
+    if let Some(usage) = &response.usage {
+        metrics::record_token_usage("openai", usage.prompt_tokens, usage.completion_tokens);
+    }
 
     let content = response
         .choices
         .first()
         .and_then(|c| c.message.content.as_ref())
         .map(|s| s.trim().to_string())
-        .unwrap_or_else(|| "Не удалось сгенерировать дайджест".to_string());
+        .unwrap_or_default();
 
     Ok(content)
 }
 
+/// Parse a structured LLM response, defaulting missing or malformed sections
+pub fn parse_digest(raw: &str) -> Result<Digest> {
+    let value: Value = serde_json::from_str(&strip_code_fences(raw))?;
+
+    let summary = value
+        .get("summary")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .trim()
+        .to_string();
+
+    Ok(Digest {
+        summary,
+        highlights: string_list(&value, "highlights"),
+        action_items: string_list(&value, "action_items"),
+        decisions: string_list(&value, "decisions"),
+    })
+}
+
+fn string_list(value: &Value, key: &str) -> Vec<String> {
+    value
+        .get(key)
+        .and_then(Value::as_array)
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(Value::as_str)
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -257,22 +568,28 @@ mod tests {
     fn test_count_unique_senders() {
         let messages = vec![
             MessageData {
+                id: 1,
                 sender: "@user1".to_string(),
                 text: "Hello".to_string(),
                 timestamp: Utc::now(),
                 reactions: 0,
+                important: false,
             },
             MessageData {
+                id: 2,
                 sender: "@user2".to_string(),
                 text: "Hi".to_string(),
                 timestamp: Utc::now(),
                 reactions: 0,
+                important: false,
             },
             MessageData {
+                id: 3,
                 sender: "@user1".to_string(),
                 text: "Bye".to_string(),
                 timestamp: Utc::now(),
                 reactions: 0,
+                important: false,
             },
         ];
 
@@ -282,15 +599,117 @@ mod tests {
     #[test]
     fn test_prepare_chat_content() {
         let messages = vec![MessageData {
+            id: 1,
             sender: "@test".to_string(),
             text: "Test message".to_string(),
             timestamp: Utc::now(),
             reactions: 5,
+            important: false,
         }];
 
         let content = prepare_chat_content(&messages);
         assert!(content.contains("@test"));
         assert!(content.contains("Test message"));
         assert!(content.contains("[5❤]"));
+        assert!(!content.contains(IMPORTANT_MARKER));
+    }
+
+    #[test]
+    fn test_prepare_chat_content_marks_important_senders() {
+        let messages = vec![MessageData {
+            id: 1,
+            sender: "@boss".to_string(),
+            text: "Deadline is Friday".to_string(),
+            timestamp: Utc::now(),
+            reactions: 0,
+            important: true,
+        }];
+
+        let content = prepare_chat_content(&messages);
+        assert!(content.contains("⭐ @boss: Deadline is Friday"));
+    }
+
+    #[test]
+    fn test_digest_user_prompt_language() {
+        let prompt = digest_user_prompt("10:00 @a: hi\n", 24, Some("English"));
+        assert!(prompt.ends_with("Respond in English."));
+        assert!(!digest_user_prompt("10:00 @a: hi\n", 24, None).contains("Respond in"));
+    }
+
+    #[test]
+    fn test_combine_sections() {
+        let single = [("chat_alpha", "Дайджест".to_string())];
+        assert_eq!(combine_sections(&single), "Дайджест");
+
+        let batch = [
+            ("chat_alpha", "Дайджест".to_string()),
+            (
+                "chat_beta",
+                "⚠️ Не удалось построить дайджест: not found".to_string(),
+            ),
+        ];
+        assert_eq!(
+            combine_sections(&batch),
+            "# 💬 chat_alpha\n\nДайджест\n\n# 💬 chat_beta\n\n⚠️ Не удалось построить дайджест: not found"
+        );
+    }
+
+    #[test]
+    fn test_parse_digest_full() {
+        let raw = r#"```json
+{
+  "summary": "Обсудили релиз",
+  "highlights": ["Новая версия API", "  "],
+  "action_items": ["@alice готовит changelog"],
+  "decisions": ["Релиз в пятницу"]
+}
+```"#;
+
+        let digest = parse_digest(raw).unwrap();
+        assert_eq!(digest.summary, "Обсудили релиз");
+        assert_eq!(digest.highlights, vec!["Новая версия API"]);
+        assert_eq!(digest.action_items, vec!["@alice готовит changelog"]);
+        assert_eq!(digest.decisions, vec!["Релиз в пятницу"]);
+    }
+
+    #[test]
+    fn test_parse_digest_missing_sections_default() {
+        let digest = parse_digest(r#"{"summary": "Тихий день", "decisions": null}"#).unwrap();
+        assert_eq!(digest.summary, "Тихий день");
+        assert!(digest.highlights.is_empty());
+        assert!(digest.action_items.is_empty());
+        assert!(digest.decisions.is_empty());
+    }
+
+    #[test]
+    fn test_parse_digest_invalid_json() {
+        assert!(parse_digest("not json").is_err());
+    }
+
+    #[test]
+    fn test_digest_to_markdown_skips_empty_sections() {
+        let digest = Digest {
+            summary: "Итоги".to_string(),
+            highlights: vec!["Тема".to_string()],
+            action_items: vec![],
+            decisions: vec!["Решение".to_string()],
+        };
+
+        let md = digest.to_markdown();
+        assert!(md.contains("Итоги"));
+        assert!(md.contains("- Тема"));
+        assert!(md.contains("### 🤝 Решения"));
+        assert!(!md.contains("Задачи"));
+    }
+
+    #[test]
+    fn test_digest_serializes_to_json() {
+        let digest = Digest {
+            summary: "s".to_string(),
+            ..Default::default()
+        };
+        let json = serde_json::to_value(&digest).unwrap();
+        assert_eq!(json["summary"], "s");
+        assert!(json["action_items"].as_array().unwrap().is_empty());
     }
 }
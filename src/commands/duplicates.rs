@@ -0,0 +1,456 @@
+//! Near-duplicate message detection
+//!
+//! Groups messages with the same or nearly the same text to surface copypasta,
+//! coordinated spam and bot networks. The `hash` mode compares normalized text
+//! (case, punctuation, links and extra whitespace ignored) and needs no API;
+//! the `embedding` mode clusters OpenAI embeddings by cosine similarity.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::analysis::embeddings::EmbeddingService;
+use crate::chat::{fetch_messages, find_chat, FetchOptions};
+use crate::commands::chat_analyzer::sender_name;
+use crate::error::{Error, Result};
+use crate::session::{get_client, SessionLock};
+
+/// Messages with less normalized text than this ("ok", "+1") are never clustered
+pub const MIN_TEXT_CHARS: usize = 10;
+
+/// Characters of message text shown in the report
+const PREVIEW_CHARS: usize = 80;
+
+/// How messages are compared
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateMode {
+    /// Identical text after normalization
+    Hash,
+    /// Embedding cosine similarity above the threshold
+    Embedding,
+}
+
+impl DuplicateMode {
+    pub fn parse(raw: &str) -> Result<Self> {
+        match raw.to_ascii_lowercase().as_str() {
+            "hash" => Ok(Self::Hash),
+            "embedding" | "embeddings" => Ok(Self::Embedding),
+            other => Err(Error::InvalidArgument(format!(
+                "Unsupported mode '{}'. Use hash|embedding",
+                other
+            ))),
+        }
+    }
+}
+
+/// Duplicate finder configuration
+#[derive(Debug, Clone)]
+pub struct DuplicatesConfig {
+    /// Number of recent messages to scan
+    pub limit: usize,
+    /// Minimum cosine similarity in embedding mode
+    pub threshold: f32,
+    pub mode: DuplicateMode,
+}
+
+impl Default for DuplicatesConfig {
+    fn default() -> Self {
+        Self {
+            limit: 1000,
+            threshold: 0.92,
+            mode: DuplicateMode::Hash,
+        }
+    }
+}
+
+/// Message as seen by the clustering functions
+#[derive(Debug, Clone, PartialEq)]
+pub struct MessageText {
+    pub id: i32,
+    pub sender: String,
+    pub text: String,
+}
+
+/// Group of near-identical messages
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DuplicateCluster {
+    /// Message ids, oldest first
+    pub message_ids: Vec<i32>,
+    /// Distinct senders, sorted
+    pub senders: Vec<String>,
+    /// Text of the first message, shortened
+    pub preview: String,
+    /// Lowest similarity among the links that formed the cluster (1.0 in hash mode)
+    pub min_similarity: f32,
+}
+
+impl DuplicateCluster {
+    pub fn size(&self) -> usize {
+        self.message_ids.len()
+    }
+}
+
+/// Lowercase text without links, punctuation and repeated whitespace
+pub fn normalize_text(text: &str) -> String {
+    text.split_whitespace()
+        .filter(|token| {
+            let token = token.to_lowercase();
+            !(token.starts_with("http://")
+                || token.starts_with("https://")
+                || token.starts_with("www."))
+        })
+        .map(|token| {
+            token
+                .chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        })
+        .filter(|token| !token.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Cluster messages whose normalized text is identical
+pub fn hash_clusters(messages: &[MessageText]) -> Vec<DuplicateCluster> {
+    let mut groups: HashMap<String, Vec<usize>> = HashMap::new();
+    for (i, msg) in messages.iter().enumerate() {
+        let key = normalize_text(&msg.text);
+        if key.chars().count() >= MIN_TEXT_CHARS {
+            groups.entry(key).or_default().push(i);
+        }
+    }
+
+    let clusters = groups
+        .into_values()
+        .filter(|members| members.len() > 1)
+        .map(|members| build_cluster(messages, &members, 1.0))
+        .collect();
+    sort_clusters(clusters)
+}
+
+/// Cluster messages whose embeddings are at least `threshold` cosine-similar.
+///
+/// Similarity is transitive within a cluster: A≈B and B≈C put all three
+/// together. `embeddings[i]` belongs to `messages[i]`; empty vectors are skipped.
+pub fn embedding_clusters(
+    messages: &[MessageText],
+    embeddings: &[Vec<f32>],
+    threshold: f32,
+) -> Vec<DuplicateCluster> {
+    let n = messages.len().min(embeddings.len());
+    let eligible: Vec<usize> = (0..n)
+        .filter(|&i| {
+            !embeddings[i].is_empty()
+                && normalize_text(&messages[i].text).chars().count() >= MIN_TEXT_CHARS
+        })
+        .collect();
+
+    let mut parent: Vec<usize> = (0..n).collect();
+    let mut min_link: Vec<f32> = vec![1.0; n];
+    for (pos, &a) in eligible.iter().enumerate() {
+        for &b in &eligible[pos + 1..] {
+            let similarity = cosine_similarity(&embeddings[a], &embeddings[b]);
+            if similarity < threshold {
+                continue;
+            }
+            let (root_a, root_b) = (find_root(&mut parent, a), find_root(&mut parent, b));
+            if root_a != root_b {
+                parent[root_b] = root_a;
+                min_link[root_a] = similarity.min(min_link[root_a]).min(min_link[root_b]);
+            }
+        }
+    }
+
+    let mut groups: HashMap<usize, Vec<usize>> = HashMap::new();
+    for &i in &eligible {
+        let root = find_root(&mut parent, i);
+        groups.entry(root).or_default().push(i);
+    }
+
+    let clusters = groups
+        .into_iter()
+        .filter(|(_, members)| members.len() > 1)
+        .map(|(root, members)| build_cluster(messages, &members, min_link[root]))
+        .collect();
+    sort_clusters(clusters)
+}
+
+/// Cosine similarity of two vectors (0.0 for mismatched or zero vectors)
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+fn find_root(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
+
+fn build_cluster(
+    messages: &[MessageText],
+    members: &[usize],
+    min_similarity: f32,
+) -> DuplicateCluster {
+    let mut members: Vec<&MessageText> = members.iter().map(|&i| &messages[i]).collect();
+    members.sort_by_key(|msg| msg.id);
+
+    let mut senders: Vec<String> = members.iter().map(|msg| msg.sender.clone()).collect();
+    senders.sort();
+    senders.dedup();
+
+    DuplicateCluster {
+        message_ids: members.iter().map(|msg| msg.id).collect(),
+        senders,
+        preview: preview(&members[0].text),
+        min_similarity,
+    }
+}
+
+/// Largest clusters first, then by the oldest message
+fn sort_clusters(mut clusters: Vec<DuplicateCluster>) -> Vec<DuplicateCluster> {
+    clusters.sort_by(|a, b| {
+        b.size()
+            .cmp(&a.size())
+            .then(a.message_ids[0].cmp(&b.message_ids[0]))
+    });
+    clusters
+}
+
+fn preview(text: &str) -> String {
+    let single_line = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if single_line.chars().count() > PREVIEW_CHARS {
+        let cut: String = single_line.chars().take(PREVIEW_CHARS).collect();
+        format!("{}…", cut)
+    } else {
+        single_line
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum OutputFormat {
+    Table,
+    Json,
+}
+
+impl OutputFormat {
+    fn parse(raw: &str) -> Result<Self> {
+        match raw.to_ascii_lowercase().as_str() {
+            "table" | "pretty" => Ok(Self::Table),
+            "json" => Ok(Self::Json),
+            other => Err(Error::InvalidArgument(format!(
+                "Unsupported format '{}'. Use table|json",
+                other
+            ))),
+        }
+    }
+}
+
+/// Main entry point for the CLI.
+pub async fn run(
+    chat_name: &str,
+    config: DuplicatesConfig,
+    format: &str,
+) -> Result<Vec<DuplicateCluster>> {
+    let fmt = OutputFormat::parse(format)?;
+    if !(0.0..=1.0).contains(&config.threshold) {
+        return Err(Error::InvalidArgument(
+            "Threshold must be between 0.0 and 1.0".to_string(),
+        ));
+    }
+
+    let _lock = SessionLock::acquire().await?;
+    let client = get_client().await?;
+    let chat = find_chat(&client, chat_name).await?;
+
+    let options = FetchOptions {
+        limit: Some(config.limit),
+        filter: Some(Box::new(|msg: &grammers_client::types::Message| {
+            !msg.text().trim().is_empty()
+        })),
+        ..Default::default()
+    };
+    let messages: Vec<MessageText> = fetch_messages(&client, &chat, options)
+        .await?
+        .iter()
+        .map(|msg| MessageText {
+            id: msg.id(),
+            sender: sender_name(msg),
+            text: msg.text().to_string(),
+        })
+        .collect();
+
+    let clusters = match config.mode {
+        DuplicateMode::Hash => hash_clusters(&messages),
+        DuplicateMode::Embedding => {
+            let service = EmbeddingService::new().map_err(|e| Error::OpenAiError(e.to_string()))?;
+            let texts: Vec<String> = messages.iter().map(|msg| msg.text.clone()).collect();
+            let embeddings = service
+                .embed_batch(&texts)
+                .await
+                .map_err(|e| Error::OpenAiError(e.to_string()))?;
+            embedding_clusters(&messages, &embeddings, config.threshold)
+        }
+    };
+
+    match fmt {
+        OutputFormat::Table => print_table(chat_name, messages.len(), &clusters),
+        OutputFormat::Json => {
+            let payload = serde_json::to_string_pretty(&clusters)
+                .map_err(|e| Error::SerializationError(e.to_string()))?;
+            println!("{payload}");
+        }
+    }
+
+    Ok(clusters)
+}
+
+fn print_table(chat_name: &str, scanned: usize, clusters: &[DuplicateCluster]) {
+    println!(
+        "\n🔁 Duplicate messages in '{}' ({} messages scanned)",
+        chat_name, scanned
+    );
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+
+    if clusters.is_empty() {
+        println!("No duplicates found");
+        return;
+    }
+
+    for (i, cluster) in clusters.iter().enumerate() {
+        println!(
+            "\n#{} — {} messages from {} sender(s), similarity ≥ {:.2}",
+            i + 1,
+            cluster.size(),
+            cluster.senders.len(),
+            cluster.min_similarity
+        );
+        println!("   \"{}\"", cluster.preview);
+        println!("   Senders: {}", cluster.senders.join(", "));
+        let ids: Vec<String> = cluster.message_ids.iter().map(i32::to_string).collect();
+        println!("   Message ids: {}", ids.join(", "));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn msg(id: i32, sender: &str, text: &str) -> MessageText {
+        MessageText {
+            id,
+            sender: sender.to_string(),
+            text: text.to_string(),
+        }
+    }
+
+    fn spam_feed() -> Vec<MessageText> {
+        vec![
+            msg(10, "@bot_a", "Earn $500 a day! Join https://t.me/scam1 now"),
+            msg(11, "@alice", "Has anyone tried the new release yet?"),
+            msg(
+                12,
+                "@bot_b",
+                "EARN 500 a day!!! join   now https://t.me/scam2",
+            ),
+            msg(13, "@bob", "ok"),
+            msg(14, "@carol", "ok"),
+            msg(15, "@bot_c", "earn 500 a day join now"),
+            msg(16, "@dave", "Good morning everyone, have a nice day"),
+            msg(17, "@erin", "good morning everyone — have a nice day!"),
+        ]
+    }
+
+    #[test]
+    fn normalizes_case_punctuation_and_links() {
+        assert_eq!(
+            normalize_text("  EARN $500 a day!!! https://t.me/x  Join   now "),
+            "earn 500 a day join now"
+        );
+        assert_eq!(normalize_text("www.spam.com ..."), "");
+    }
+
+    #[test]
+    fn hash_mode_groups_copypasta() {
+        let clusters = hash_clusters(&spam_feed());
+
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters[0].message_ids, vec![10, 12, 15]);
+        assert_eq!(clusters[0].senders, vec!["@bot_a", "@bot_b", "@bot_c"]);
+        assert_eq!(clusters[0].min_similarity, 1.0);
+        assert_eq!(clusters[1].message_ids, vec![16, 17]);
+        // Short replies like "ok" are ignored even though they repeat
+        assert!(clusters.iter().all(|c| !c.message_ids.contains(&13)));
+    }
+
+    #[test]
+    fn embedding_mode_clusters_transitively_above_threshold() {
+        let messages = vec![
+            msg(1, "@a", "buy followers cheap today"),
+            msg(2, "@b", "buy followers cheaply today"),
+            msg(3, "@c", "cheap followers for sale today"),
+            msg(4, "@d", "what time is the meetup tomorrow"),
+            msg(5, "@e", "short"),
+        ];
+        let embeddings = vec![
+            vec![1.0, 0.0, 0.0],
+            vec![0.96, 0.28, 0.0],
+            vec![0.8, 0.6, 0.0],
+            vec![0.0, 0.0, 1.0],
+            vec![1.0, 0.0, 0.0],
+        ];
+
+        // 1≈2 (0.96), 2≈3 (≈0.94), but 1 and 3 only 0.8 apart
+        let clusters = embedding_clusters(&messages, &embeddings, 0.9);
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].message_ids, vec![1, 2, 3]);
+        assert!((clusters[0].min_similarity - 0.936).abs() < 1e-3);
+
+        // A stricter threshold splits the chain
+        let strict = embedding_clusters(&messages, &embeddings, 0.95);
+        assert_eq!(strict.len(), 1);
+        assert_eq!(strict[0].message_ids, vec![1, 2]);
+
+        assert!(embedding_clusters(&messages, &embeddings, 0.99).is_empty());
+    }
+
+    #[test]
+    fn embedding_mode_skips_empty_vectors() {
+        let messages = vec![
+            msg(1, "@a", "identical long message"),
+            msg(2, "@b", "identical long message"),
+        ];
+        let embeddings = vec![vec![1.0, 0.0], Vec::new()];
+        assert!(embedding_clusters(&messages, &embeddings, 0.5).is_empty());
+    }
+
+    #[test]
+    fn cosine_similarity_handles_edge_cases() {
+        assert_eq!(cosine_similarity(&[1.0, 0.0], &[2.0, 0.0]), 1.0);
+        assert_eq!(cosine_similarity(&[1.0, 0.0], &[0.0, 1.0]), 0.0);
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 0.0]), 0.0);
+        assert_eq!(cosine_similarity(&[1.0], &[1.0, 0.0]), 0.0);
+    }
+
+    #[test]
+    fn parses_mode() {
+        assert_eq!(DuplicateMode::parse("HASH").unwrap(), DuplicateMode::Hash);
+        assert_eq!(
+            DuplicateMode::parse("embedding").unwrap(),
+            DuplicateMode::Embedding
+        );
+        assert!(DuplicateMode::parse("fuzzy").is_err());
+    }
+}
@@ -0,0 +1,618 @@
+//! Meeting and event mentions → calendar
+//!
+//! Chats are full of "созвон завтра в 15:00" and "meetup on Friday at 7pm".
+//! The LLM only reads the day and time as written (an absolute date or a word
+//! like `tomorrow`/`friday`); resolving them against the date of the message
+//! that mentions them happens here, so "tomorrow" in a message from last week
+//! lands on the right day. Events are exported as an ICS calendar and as JSON
+//! with RFC3339 timestamps.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use async_openai::{
+    config::OpenAIConfig,
+    types::{
+        ChatCompletionRequestMessage, ChatCompletionRequestSystemMessage,
+        ChatCompletionRequestUserMessage, CreateChatCompletionRequest,
+    },
+    Client as OpenAIClient,
+};
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, NaiveDate, NaiveTime, TimeZone, Utc, Weekday,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::chat::{fetch_messages, find_chat, peer_name, FetchOptions};
+use crate::error::{Error, Result};
+use crate::metrics;
+use crate::scan::ScanStats;
+use crate::session::{get_client, SessionLock};
+
+const EVENTS_EXTRACTION_PROMPT: &str = r#"Ты извлекаешь из переписки упоминания встреч, созвонов и мероприятий с датой или временем.
+
+Каждое сообщение начинается с [id=N дата время]. Верни JSON:
+{
+  "events": [
+    {
+      "message_id": 123,
+      "title": "Краткое название события",
+      "day": "дата как в сообщении",
+      "time": "HH:MM или null",
+      "duration_minutes": 60,
+      "location": "место или ссылка, если есть"
+    }
+  ]
+}
+
+Поле "day": YYYY-MM-DD, если дата указана явно; иначе одно из
+today | tomorrow | day_after_tomorrow | monday | tuesday | wednesday | thursday | friday | saturday | sunday | +N (через N дней).
+НЕ вычисляй относительные даты сам. "time" — в 24-часовом формате, как написано в сообщении.
+Пропускай сообщения без конкретного дня. duration_minutes и location — null, если не указаны.
+
+Отвечай ТОЛЬКО валидным JSON без дополнительного текста."#;
+
+/// Events without an explicit duration last an hour
+const DEFAULT_DURATION_MINUTES: i64 = 60;
+
+/// Characters of the source message kept in the event description
+const QUOTE_CHARS: usize = 300;
+
+/// The day of an event as written in the message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DaySpec {
+    Date(NaiveDate),
+    /// Days after the message date (`today` = 0, `tomorrow` = 1)
+    InDays(i64),
+    /// The next such weekday, the message date included
+    Weekday(Weekday),
+}
+
+/// Parse the LLM's `day` field: `YYYY-MM-DD`, `today`/`tomorrow`/
+/// `day_after_tomorrow` (or Russian), `+N`, or a weekday name
+pub fn parse_day(day: &str) -> Option<DaySpec> {
+    let day = day.trim().to_lowercase();
+    if let Ok(date) = NaiveDate::parse_from_str(&day, "%Y-%m-%d") {
+        return Some(DaySpec::Date(date));
+    }
+    if let Some(n) = day.strip_prefix('+') {
+        return n.trim().parse().ok().map(DaySpec::InDays);
+    }
+
+    let spec = match day.as_str() {
+        "today" | "сегодня" => DaySpec::InDays(0),
+        "tomorrow" | "завтра" => DaySpec::InDays(1),
+        "day_after_tomorrow" | "послезавтра" => DaySpec::InDays(2),
+        "понедельник" => DaySpec::Weekday(Weekday::Mon),
+        "вторник" => DaySpec::Weekday(Weekday::Tue),
+        "среда" | "среду" => DaySpec::Weekday(Weekday::Wed),
+        "четверг" => DaySpec::Weekday(Weekday::Thu),
+        "пятница" | "пятницу" => DaySpec::Weekday(Weekday::Fri),
+        "суббота" | "субботу" => DaySpec::Weekday(Weekday::Sat),
+        "воскресенье" => DaySpec::Weekday(Weekday::Sun),
+        other => DaySpec::Weekday(other.parse().ok()?),
+    };
+    Some(spec)
+}
+
+/// Parse `HH:MM`, `HH.MM` or a bare hour
+pub fn parse_time(time: &str) -> Option<NaiveTime> {
+    let time = time.trim();
+    ["%H:%M", "%H.%M", "%H:%M:%S"]
+        .iter()
+        .find_map(|format| NaiveTime::parse_from_str(time, format).ok())
+        .or_else(|| {
+            let hour: u32 = time.parse().ok()?;
+            NaiveTime::from_hms_opt(hour, 0, 0)
+        })
+}
+
+/// Calendar date of `spec` for a message written on `message_day`
+pub fn resolve_day(spec: DaySpec, message_day: NaiveDate) -> NaiveDate {
+    match spec {
+        DaySpec::Date(date) => date,
+        DaySpec::InDays(days) => message_day + Duration::days(days),
+        DaySpec::Weekday(weekday) => {
+            let ahead = (weekday.num_days_from_monday() + 7
+                - message_day.weekday().num_days_from_monday())
+                % 7;
+            message_day + Duration::days(ahead as i64)
+        }
+    }
+}
+
+/// An event mention as returned by the LLM
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RawEvent {
+    pub message_id: i32,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub day: String,
+    pub time: Option<String>,
+    pub duration_minutes: Option<i64>,
+    pub location: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RawEvents {
+    #[serde(default)]
+    events: Vec<RawEvent>,
+}
+
+/// An event with its time resolved against the source message
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Event {
+    pub title: String,
+    /// RFC3339 in the requested timezone; midnight for all-day events
+    pub start: DateTime<FixedOffset>,
+    pub end: DateTime<FixedOffset>,
+    /// No time was mentioned, only the day
+    pub all_day: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    pub message_id: i32,
+    /// Start of the message the event was found in
+    pub quote: String,
+}
+
+/// Resolve a raw mention against the date of its message.
+///
+/// Relative days count from the message's local date in `offset`, so a
+/// message sent at 23:30 UTC on Monday is Tuesday in `+03:00`. `None` when
+/// the day or time can't be understood.
+pub fn resolve_event(
+    raw: &RawEvent,
+    message_date: DateTime<Utc>,
+    offset: FixedOffset,
+    quote: &str,
+) -> Option<Event> {
+    let message_day = message_date.with_timezone(&offset).date_naive();
+    let day = resolve_day(parse_day(&raw.day)?, message_day);
+
+    let time = match raw.time.as_deref().map(str::trim) {
+        Some(time) if !time.is_empty() && time != "null" => Some(parse_time(time)?),
+        _ => None,
+    };
+    let start = offset
+        .from_local_datetime(&day.and_time(time.unwrap_or(NaiveTime::MIN)))
+        .single()?;
+    let end = match time {
+        Some(_) => {
+            let minutes = raw
+                .duration_minutes
+                .filter(|m| *m > 0)
+                .unwrap_or(DEFAULT_DURATION_MINUTES);
+            start + Duration::minutes(minutes)
+        }
+        None => start + Duration::days(1),
+    };
+
+    let title = raw.title.trim();
+    Some(Event {
+        title: if title.is_empty() {
+            "Event".to_string()
+        } else {
+            title.to_string()
+        },
+        start,
+        end,
+        all_day: time.is_none(),
+        location: raw
+            .location
+            .as_deref()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .map(str::to_string),
+        message_id: raw.message_id,
+        quote: quote.chars().take(QUOTE_CHARS).collect(),
+    })
+}
+
+/// Escape a TEXT value (RFC 5545 §3.3.11)
+fn escape_ics(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '\\' => out.push_str("\\\\"),
+            ';' => out.push_str("\\;"),
+            ',' => out.push_str("\\,"),
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+/// Fold a content line at 75 octets without splitting a UTF-8 character
+fn fold_ics_line(line: &str, out: &mut String) {
+    const LIMIT: usize = 75;
+    let mut width = 0;
+    for ch in line.chars() {
+        if width + ch.len_utf8() > LIMIT {
+            out.push_str("\r\n ");
+            // The leading space counts towards the next line
+            width = 1;
+        }
+        out.push(ch);
+        width += ch.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+/// Identifier safe for UIDs: ASCII alphanumerics, everything else as `-`
+fn uid_slug(name: &str) -> String {
+    let slug: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let slug = slug.trim_matches('-');
+    if slug.is_empty() {
+        "chat".to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+/// Serialize events as an iCalendar (RFC 5545) document.
+///
+/// Timed events are written in UTC, all-day ones as dates. UIDs are stable
+/// per chat, message and position so re-imports update instead of duplicate.
+pub fn to_ics(chat_name: &str, events: &[Event], stamp: DateTime<Utc>) -> String {
+    const UTC_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//telegram_reader//events//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        format!("X-WR-CALNAME:{}", escape_ics(chat_name)),
+    ];
+
+    let slug = uid_slug(chat_name);
+    let mut seen: HashMap<i32, usize> = HashMap::new();
+    for event in events {
+        let n = seen.entry(event.message_id).or_default();
+        *n += 1;
+
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!(
+            "UID:{}-{}-{}@telegram_reader",
+            slug, event.message_id, n
+        ));
+        lines.push(format!("DTSTAMP:{}", stamp.format(UTC_FORMAT)));
+        if event.all_day {
+            lines.push(format!(
+                "DTSTART;VALUE=DATE:{}",
+                event.start.date_naive().format("%Y%m%d")
+            ));
+            lines.push(format!(
+                "DTEND;VALUE=DATE:{}",
+                event.end.date_naive().format("%Y%m%d")
+            ));
+        } else {
+            let utc = |date: &DateTime<FixedOffset>| date.with_timezone(&Utc).format(UTC_FORMAT);
+            lines.push(format!("DTSTART:{}", utc(&event.start)));
+            lines.push(format!("DTEND:{}", utc(&event.end)));
+        }
+        lines.push(format!("SUMMARY:{}", escape_ics(&event.title)));
+        if let Some(location) = &event.location {
+            lines.push(format!("LOCATION:{}", escape_ics(location)));
+        }
+        if !event.quote.is_empty() {
+            lines.push(format!("DESCRIPTION:{}", escape_ics(&event.quote)));
+        }
+        lines.push("END:VEVENT".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut out = String::new();
+    for line in &lines {
+        fold_ics_line(line, &mut out);
+    }
+    out
+}
+
+/// Events command configuration
+pub struct EventsConfig {
+    /// Days of history to scan
+    pub days: i64,
+    /// Maximum messages to send to the LLM
+    pub limit: usize,
+    /// OpenAI model to use
+    pub model: String,
+    /// Timezone relative days and times are read in
+    pub offset: FixedOffset,
+}
+
+/// Events found in a chat and what was scanned
+#[derive(Debug, Clone, Default)]
+pub struct EventsReport {
+    pub events: Vec<Event>,
+    pub scan: ScanStats,
+}
+
+/// Extract event mentions from the last `days` of a chat
+pub async fn run(chat_name: &str, config: EventsConfig) -> Result<EventsReport> {
+    let api_key = std::env::var("OPENAI_API_KEY")
+        .map_err(|_| Error::InvalidArgument("OPENAI_API_KEY not set".to_string()))?;
+    let openai_client = OpenAIClient::with_config(OpenAIConfig::new().with_api_key(api_key));
+
+    let _lock = SessionLock::acquire().await?;
+    let client = get_client().await?;
+    let chat = find_chat(&client, chat_name).await?;
+
+    println!("📅 Ищу события в чате '{}'...", chat_name);
+
+    let options = FetchOptions {
+        since: Some(Utc::now() - Duration::days(config.days.max(1))),
+        limit: Some(config.limit),
+        ..Default::default()
+    };
+    let mut messages = fetch_messages(&client, &chat, options).await?;
+    let scan = ScanStats::from_dates(messages.iter().map(|m| m.date()));
+    messages.retain(|m| !m.text().trim().is_empty());
+    if messages.is_empty() {
+        return Ok(EventsReport {
+            scan,
+            ..Default::default()
+        });
+    }
+    messages.reverse();
+
+    let sources: HashMap<i32, (DateTime<Utc>, String)> = messages
+        .iter()
+        .map(|m| (m.id(), (m.date(), m.text().trim().to_string())))
+        .collect();
+    let conversation = messages
+        .iter()
+        .map(|m| {
+            let sender = m
+                .sender()
+                .map(peer_name)
+                .unwrap_or_else(|| "Unknown".to_string());
+            format!(
+                "[id={} {}] {}: {}",
+                m.id(),
+                m.date()
+                    .with_timezone(&config.offset)
+                    .format("%Y-%m-%d %a %H:%M"),
+                sender,
+                m.text().trim()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let raw = extract_events(&openai_client, &config.model, &conversation).await?;
+    let mut events: Vec<Event> = raw
+        .iter()
+        .filter_map(|raw| {
+            let Some((date, text)) = sources.get(&raw.message_id) else {
+                eprintln!(
+                    "⚠️ Пропускаю событие '{}': неизвестное сообщение {}",
+                    raw.title, raw.message_id
+                );
+                return None;
+            };
+            let event = resolve_event(raw, *date, config.offset, text);
+            if event.is_none() {
+                eprintln!(
+                    "⚠️ Не удалось разобрать дату '{}' {} (сообщение {})",
+                    raw.day,
+                    raw.time.as_deref().unwrap_or(""),
+                    raw.message_id
+                );
+            }
+            event
+        })
+        .collect();
+    events.sort_by_key(|e| e.start);
+
+    Ok(EventsReport { events, scan })
+}
+
+async fn extract_events(
+    client: &OpenAIClient<OpenAIConfig>,
+    model: &str,
+    conversation: &str,
+) -> Result<Vec<RawEvent>> {
+    let request = CreateChatCompletionRequest {
+        model: model.to_string(),
+        messages: vec![
+            ChatCompletionRequestMessage::System(ChatCompletionRequestSystemMessage {
+                content: async_openai::types::ChatCompletionRequestSystemMessageContent::Text(
+                    EVENTS_EXTRACTION_PROMPT.to_string(),
+                ),
+                name: None,
+            }),
+            ChatCompletionRequestMessage::User(ChatCompletionRequestUserMessage {
+                content: async_openai::types::ChatCompletionRequestUserMessageContent::Text(
+                    format!("Найди события в этой переписке:\n\n{}", conversation),
+                ),
+                name: None,
+            }),
+        ],
+        temperature: Some(0.2),
+        max_completion_tokens: Some(2000),
+        ..Default::default()
+    };
+
+    let response = client
+        .chat()
+        .create(request)
+        .await
+        .map_err(|e| Error::OpenAiError(e.to_string()))?;
+    if let Some(usage) = &response.usage {
+        metrics::record_token_usage("openai", usage.prompt_tokens, usage.completion_tokens);
+    }
+
+    let content = response
+        .choices
+        .first()
+        .and_then(|c| c.message.content.as_ref())
+        .map(|s| s.trim().to_string())
+        .unwrap_or_default();
+
+    let parsed: RawEvents = serde_json::from_str(&content).unwrap_or_else(|e| {
+        eprintln!("⚠️ Ошибка парсинга JSON: {}", e);
+        eprintln!("Ответ AI: {}", content);
+        RawEvents::default()
+    });
+
+    Ok(parsed.events)
+}
+
+/// Print events in chronological order
+pub fn print_events(events: &[Event]) {
+    println!("\n📅 Найдено событий: {}\n", events.len());
+    for event in events {
+        let when = if event.all_day {
+            event.start.format("%Y-%m-%d (весь день)").to_string()
+        } else {
+            event.start.format("%Y-%m-%d %H:%M %:z").to_string()
+        };
+        println!("  {} — {}", when, event.title);
+        if let Some(location) = &event.location {
+            println!("     📍 {}", location);
+        }
+    }
+}
+
+/// Write `<stem>.ics` and `<stem>.json`
+pub fn export(chat_name: &str, events: &[Event], stem: &Path) -> Result<()> {
+    // Appended rather than `with_extension`, so a stem like `v1.5_events` keeps its dot
+    let with_suffix = |suffix: &str| {
+        let mut path = stem.as_os_str().to_owned();
+        path.push(suffix);
+        PathBuf::from(path)
+    };
+
+    let ics_path = with_suffix(".ics");
+    std::fs::write(&ics_path, to_ics(chat_name, events, Utc::now()))?;
+
+    let json_path = with_suffix(".json");
+    std::fs::write(&json_path, serde_json::to_string_pretty(events)?)?;
+
+    println!(
+        "\n📁 Events saved to {} and {}",
+        ics_path.display(),
+        json_path.display()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn msk() -> FixedOffset {
+        FixedOffset::east_opt(3 * 3600).unwrap()
+    }
+
+    fn raw(day: &str, time: Option<&str>) -> RawEvent {
+        RawEvent {
+            message_id: 7,
+            title: "Созвон".to_string(),
+            day: day.to_string(),
+            time: time.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn relative_days_resolve_against_the_message_date() {
+        // Friday, 31 May 2024
+        let friday = NaiveDate::from_ymd_opt(2024, 5, 31).unwrap();
+        let date = |m, d| NaiveDate::from_ymd_opt(2024, m, d).unwrap();
+
+        let resolve = |day: &str| resolve_day(parse_day(day).unwrap(), friday);
+        assert_eq!(resolve("today"), friday);
+        assert_eq!(resolve("завтра"), date(6, 1));
+        assert_eq!(resolve("day_after_tomorrow"), date(6, 2));
+        assert_eq!(resolve("+10"), date(6, 10));
+        assert_eq!(resolve("Monday"), date(6, 3));
+        assert_eq!(resolve("пятницу"), friday);
+        assert_eq!(resolve("thu"), date(6, 6));
+        assert_eq!(resolve("2024-07-01"), date(7, 1));
+        assert_eq!(parse_day("someday"), None);
+    }
+
+    #[test]
+    fn resolves_times_in_the_local_timezone() {
+        // 22:30 UTC on Monday is already Tuesday in Moscow
+        let sent = Utc.with_ymd_and_hms(2024, 6, 3, 22, 30, 0).unwrap();
+        let event = resolve_event(&raw("tomorrow", Some("15.00")), sent, msk(), "hi").unwrap();
+
+        assert_eq!(event.start.to_rfc3339(), "2024-06-05T15:00:00+03:00");
+        assert_eq!(event.end.to_rfc3339(), "2024-06-05T16:00:00+03:00");
+        assert!(!event.all_day);
+
+        let all_day = resolve_event(&raw("tomorrow", None), sent, msk(), "").unwrap();
+        assert!(all_day.all_day);
+        assert_eq!(all_day.start.to_rfc3339(), "2024-06-05T00:00:00+03:00");
+
+        assert!(resolve_event(&raw("tomorrow", Some("25:99")), sent, msk(), "").is_none());
+    }
+
+    #[test]
+    fn serializes_events_as_ics() {
+        let sent = Utc.with_ymd_and_hms(2024, 6, 3, 9, 0, 0).unwrap();
+        let mut timed = raw("2024-06-04", Some("15:00"));
+        timed.duration_minutes = Some(90);
+        timed.location = Some("Zoom, room 1; bring notes".to_string());
+        let events = [
+            resolve_event(&timed, sent, msk(), "Созвон завтра в 15:00\nне опаздывать").unwrap(),
+            resolve_event(&raw("friday", None), sent, msk(), "").unwrap(),
+        ];
+
+        let ics = to_ics("Rust Team", &events, sent);
+        assert_eq!(
+            ics,
+            "BEGIN:VCALENDAR\r\n\
+             VERSION:2.0\r\n\
+             PRODID:-//telegram_reader//events//EN\r\n\
+             CALSCALE:GREGORIAN\r\n\
+             X-WR-CALNAME:Rust Team\r\n\
+             BEGIN:VEVENT\r\n\
+             UID:rust-team-7-1@telegram_reader\r\n\
+             DTSTAMP:20240603T090000Z\r\n\
+             DTSTART:20240604T120000Z\r\n\
+             DTEND:20240604T133000Z\r\n\
+             SUMMARY:Созвон\r\n\
+             LOCATION:Zoom\\, room 1\\; bring notes\r\n\
+             DESCRIPTION:Созвон завтра в 15:00\\nне опаздывать\r\n\
+             END:VEVENT\r\n\
+             BEGIN:VEVENT\r\n\
+             UID:rust-team-7-2@telegram_reader\r\n\
+             DTSTAMP:20240603T090000Z\r\n\
+             DTSTART;VALUE=DATE:20240607\r\n\
+             DTEND;VALUE=DATE:20240608\r\n\
+             SUMMARY:Созвон\r\n\
+             END:VEVENT\r\n\
+             END:VCALENDAR\r\n"
+        );
+    }
+
+    #[test]
+    fn folds_long_lines_on_character_boundaries() {
+        let mut out = String::new();
+        fold_ics_line(&format!("SUMMARY:{}", "я".repeat(60)), &mut out);
+
+        let lines: Vec<&str> = out.trim_end_matches("\r\n").split("\r\n").collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|l| l.len() <= 75));
+        assert!(lines[1].starts_with(' '));
+        assert_eq!(
+            lines.concat().replacen(" ", "", 1),
+            format!("SUMMARY:{}", "я".repeat(60))
+        );
+    }
+}
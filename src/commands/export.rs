@@ -1,16 +1,276 @@
 //! Export chat command
 //!
 //! Equivalent to Python's export_chat.py
+//!
+//! With `--resume` the export keeps a `<output>.cursor` sidecar holding the id
+//! of the last message written and the output length at that point. Messages
+//! are then written as they are fetched (newest first, so a resumable Markdown
+//! export is in the opposite order to a plain one); a restart cuts the output
+//! back to the checkpoint, skips everything newer than the cursor and appends
+//! the rest. The cursor is removed once the export completes.
 
-use std::fs::File;
-use std::io::Write;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
 
+use crate::chat::{fetch_messages, scan_messages, FetchOptions, MessageFilter};
+use crate::commands::chat_analyzer::sender_name;
 use crate::error::{Error, Result};
+use crate::markdown::message_markdown;
+use crate::reactions::{count_reactions, fetch_reactors, Reactor};
 use crate::session::{get_client, SessionLock};
+use chrono::{DateTime, NaiveDate, Utc};
+use grammers_client::types::{peer::Peer, Message};
+use grammers_client::Client;
+use grammers_tl_types as tl;
+use serde::Serialize;
+use tracing::warn;
+
+/// How many messages are written between cursor updates
+const CURSOR_EVERY: usize = 100;
+
+/// Reactors of a single exported message
+#[derive(Debug, Serialize)]
+struct MessageReactors {
+    message_id: i32,
+    date: String,
+    reactors: Vec<Reactor>,
+}
+
+/// `--since`/`--until` bounds of an export, both inclusive
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DateWindow {
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl DateWindow {
+    /// Parse `YYYY-MM-DD` bounds; `until` covers its whole day
+    pub fn parse(since: Option<&str>, until: Option<&str>) -> Result<Self> {
+        let since = since
+            .map(|s| parse_date(s, "since"))
+            .transpose()?
+            .map(|date| date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc());
+        let until = until
+            .map(|s| parse_date(s, "until"))
+            .transpose()?
+            .map(|date| date.and_hms_opt(23, 59, 59).unwrap_or_default().and_utc());
+
+        if let (Some(since), Some(until)) = (since, until) {
+            if since > until {
+                return Err(Error::InvalidArgument(format!(
+                    "--since ({}) is after --until ({})",
+                    since.format("%Y-%m-%d"),
+                    until.format("%Y-%m-%d")
+                )));
+            }
+        }
+
+        Ok(Self { since, until })
+    }
+
+    /// Message is older than the window, so the (newest first) scan can stop
+    pub fn is_past(&self, date: DateTime<Utc>) -> bool {
+        self.since.is_some_and(|since| date < since)
+    }
+
+    /// Message is newer than the window and should be skipped
+    pub fn is_ahead(&self, date: DateTime<Utc>) -> bool {
+        self.until.is_some_and(|until| date > until)
+    }
+}
+
+fn parse_date(value: &str, flag: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d").map_err(|_| {
+        Error::InvalidArgument(format!(
+            "--{} expects a date as YYYY-MM-DD, got '{}'",
+            flag, value
+        ))
+    })
+}
+
+/// Output format of the export file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Human-readable chat log, oldest first
+    Markdown,
+    /// One JSON object per message, written as fetched (newest first)
+    Jsonl,
+}
+
+impl ExportFormat {
+    pub fn parse(raw: &str) -> Result<Self> {
+        match raw.to_ascii_lowercase().as_str() {
+            "markdown" | "md" => Ok(Self::Markdown),
+            "jsonl" => Ok(Self::Jsonl),
+            other => Err(Error::InvalidArgument(format!(
+                "Unsupported format '{}'. Use markdown|jsonl",
+                other
+            ))),
+        }
+    }
+
+    /// Extension of the default output file
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            Self::Jsonl => "jsonl",
+        }
+    }
+}
+
+/// Lets clap reject an unknown `--format` before anything connects
+impl std::str::FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(raw: &str) -> std::result::Result<Self, Self::Err> {
+        Self::parse(raw).map_err(|e| e.to_string())
+    }
+}
+
+/// A message as written to a JSONL export
+///
+/// Readable back as `--no-network --input` history (see [`crate::offline`]).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct JsonlMessage {
+    pub id: i32,
+    /// RFC 3339
+    pub date: String,
+    pub sender_id: Option<i64>,
+    /// `@username` or display name
+    pub sender_name: String,
+    pub text: String,
+    pub reply_to: Option<i32>,
+    /// Total reaction count
+    pub reactions: i32,
+    pub has_media: bool,
+}
+
+impl JsonlMessage {
+    pub fn from_message(msg: &Message) -> Self {
+        Self {
+            id: msg.id(),
+            date: msg.date().to_rfc3339(),
+            sender_id: msg.sender().map(peer_id),
+            sender_name: sender_name(msg),
+            text: msg.text().to_string(),
+            reply_to: msg.reply_to_message_id(),
+            reactions: count_reactions(msg),
+            has_media: msg.media().is_some(),
+        }
+    }
+}
+
+fn peer_id(peer: &Peer) -> i64 {
+    match peer {
+        Peer::Channel(c) => c.raw.id,
+        Peer::Group(g) => match &g.raw {
+            tl::enums::Chat::Empty(c) => c.id,
+            tl::enums::Chat::Chat(c) => c.id,
+            tl::enums::Chat::Forbidden(c) => c.id,
+            tl::enums::Chat::Channel(c) => c.id,
+            tl::enums::Chat::ChannelForbidden(c) => c.id,
+        },
+        Peer::User(u) => u.raw.id(),
+    }
+}
+
+/// Sidecar file holding the resume cursor for `output`
+pub fn cursor_path(output: &str) -> PathBuf {
+    PathBuf::from(format!("{}.cursor", output))
+}
+
+/// Resume checkpoint: the last message written and the output length after it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cursor {
+    pub message_id: i32,
+    pub offset: u64,
+}
+
+/// Parse cursor file contents (`<message id> <offset>`); `None` when unusable
+pub fn parse_cursor(contents: &str) -> Option<Cursor> {
+    let mut fields = contents.split_whitespace();
+    let message_id = fields.next()?.parse().ok().filter(|id| *id > 0)?;
+    let offset = fields.next()?.parse().ok()?;
+    if fields.next().is_some() {
+        return None;
+    }
+    Some(Cursor { message_id, offset })
+}
+
+/// Read the cursor, warning and returning `None` when the file is unusable
+pub fn read_cursor(path: &Path) -> Option<Cursor> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+        Err(e) => {
+            warn!(
+                "Cannot read cursor {}: {}, doing a full export",
+                path.display(),
+                e
+            );
+            return None;
+        }
+    };
+
+    let cursor = parse_cursor(&contents);
+    if cursor.is_none() {
+        warn!(
+            "Cursor {} is empty or corrupt, doing a full export",
+            path.display()
+        );
+    }
+    cursor
+}
+
+/// Persist the checkpoint of the last exported message
+pub fn write_cursor(path: &Path, cursor: Cursor) -> Result<()> {
+    std::fs::write(path, format!("{} {}\n", cursor.message_id, cursor.offset))?;
+    Ok(())
+}
+
+/// Format one message as an export line; `None` for messages with nothing to show
+fn message_line(msg: &Message, name: &str) -> Option<String> {
+    let timestamp = msg.date().format("%d.%m.%Y %H:%M:%S").to_string();
+    let sender = if msg.outgoing() { "Я" } else { name };
+
+    let text = message_markdown(msg);
+    if !text.is_empty() {
+        Some(format!("{} {}: {}", timestamp, sender, text))
+    } else if msg.media().is_some() {
+        Some(format!("{} {}: [Media]", timestamp, sender))
+    } else {
+        None
+    }
+}
 
 pub async fn run(username: &str, output: Option<&str>, limit: usize) -> Result<()> {
+    run_with_reactors(
+        username,
+        output,
+        limit,
+        DateWindow::default(),
+        ExportFormat::Markdown,
+        None,
+        false,
+    )
+    .await
+}
+
+/// Export a chat and optionally write who reacted with what to a JSON file
+///
+/// Only messages inside `window` are exported; `limit` caps how many of them.
+pub async fn run_with_reactors(
+    username: &str,
+    output: Option<&str>,
+    limit: usize,
+    window: DateWindow,
+    format: ExportFormat,
+    reactors_output: Option<&str>,
+    resume: bool,
+) -> Result<()> {
     // Acquire session lock
-    let _lock = SessionLock::acquire()?;
+    let _lock = SessionLock::acquire().await?;
 
     // Connect to Telegram
     let client = get_client().await?;
@@ -23,54 +283,328 @@ pub async fn run(username: &str, output: Option<&str>, limit: usize) -> Result<(
         .ok_or_else(|| Error::ChatNotFound(format!("Username @{} not found", username)))?;
 
     let name = match &chat {
-        grammers_client::types::peer::Peer::User(u) => u.full_name(),
-        grammers_client::types::peer::Peer::Group(g) => g.title().unwrap_or("Group").to_string(),
-        grammers_client::types::peer::Peer::Channel(c) => c.title().to_string(),
+        Peer::User(u) => u.full_name(),
+        Peer::Group(g) => g.title().unwrap_or("Group").to_string(),
+        Peer::Channel(c) => c.title().to_string(),
     };
 
     println!("Экспортирую чат: {} (@{})", name, username);
 
-    // Collect messages
-    let mut messages = Vec::new();
-    let mut iter = client.iter_messages(&chat);
+    let output_file = output
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| format!("{}.{}", username, format.extension()));
 
-    while let Some(msg) = iter.next().await.transpose() {
-        let msg = msg.map_err(|e| Error::TelegramError(e.to_string()))?;
-        messages.push(msg);
-        if messages.len() >= limit {
-            break;
+    let target = ExportTarget {
+        username,
+        name: &name,
+        output_file: &output_file,
+        limit,
+        window,
+        format,
+    };
+    let exported = if resume || format == ExportFormat::Jsonl {
+        export_streaming(&client, &chat, &target, resume, reactors_output.is_some()).await?
+    } else {
+        export_all(&client, &chat, &target).await?
+    };
+
+    println!(
+        "Экспортировано {} сообщений в {}",
+        exported.count, output_file
+    );
+
+    if let Some(path) = reactors_output {
+        let mut entries = Vec::new();
+        let mut hidden = 0;
+
+        for msg in &exported.messages {
+            if count_reactions(msg) == 0 {
+                continue;
+            }
+            match fetch_reactors(&client, &chat, msg).await? {
+                Some(reactors) => entries.push(MessageReactors {
+                    message_id: msg.id(),
+                    date: msg.date().to_rfc3339(),
+                    reactors,
+                }),
+                None => hidden += 1,
+            }
+        }
+
+        let payload = serde_json::to_string_pretty(&entries)?;
+        std::fs::write(path, payload)?;
+        println!(
+            "Реакции: {} сообщений сохранено в {} (список скрыт у {})",
+            entries.len(),
+            path,
+            hidden
+        );
+    }
+
+    Ok(())
+}
+
+/// What to export and where
+struct ExportTarget<'a> {
+    username: &'a str,
+    name: &'a str,
+    output_file: &'a str,
+    limit: usize,
+    window: DateWindow,
+    format: ExportFormat,
+}
+
+impl ExportTarget<'_> {
+    fn header(&self) -> Option<String> {
+        match self.format {
+            ExportFormat::Markdown => Some(format!("# Чат с @{}\n", self.username)),
+            ExportFormat::Jsonl => None,
+        }
+    }
+
+    /// Fetch rules of this export; with a resume `cursor` only messages older
+    /// than it are returned
+    fn fetch_options(&self, cursor: Option<i32>) -> FetchOptions {
+        FetchOptions {
+            limit: Some(self.limit),
+            since: self.window.since,
+            until: self.window.until,
+            filter: cursor
+                .map(|id| Box::new(move |msg: &Message| msg.id() < id) as MessageFilter<Message>),
         }
     }
 
+    fn line(&self, msg: &Message) -> Result<Option<String>> {
+        match self.format {
+            ExportFormat::Markdown => Ok(message_line(msg, self.name)),
+            ExportFormat::Jsonl => Ok(Some(serde_json::to_string(&JsonlMessage::from_message(
+                msg,
+            ))?)),
+        }
+    }
+}
+
+/// Exported message count, plus the messages themselves when still needed
+struct Exported {
+    count: usize,
+    messages: Vec<Message>,
+}
+
+/// Fetch up to `limit` messages and write them oldest first
+async fn export_all(client: &Client, chat: &Peer, target: &ExportTarget<'_>) -> Result<Exported> {
+    // Collect messages
+    let mut messages = fetch_messages(client, chat, target.fetch_options(None)).await?;
+
     // Reverse for chronological order
     messages.reverse();
 
-    // Create output file
-    let output_file = output
-        .map(|s| s.to_string())
-        .unwrap_or_else(|| format!("{}.md", username));
-
-    let mut file = File::create(&output_file)?;
-    writeln!(file, "# Чат с @{}\n", username)?;
+    let mut file = File::create(target.output_file)?;
+    if let Some(header) = target.header() {
+        writeln!(file, "{}", header)?;
+    }
 
     for msg in &messages {
-        let timestamp = msg.date().format("%d.%m.%Y %H:%M:%S").to_string();
-        let is_outgoing = msg.outgoing();
-        let sender = if is_outgoing { "Я" } else { &name };
-
-        let text = msg.text();
-        if !text.is_empty() {
-            writeln!(file, "{} {}: {}", timestamp, sender, text)?;
-        } else if msg.media().is_some() {
-            writeln!(file, "{} {}: [Media]", timestamp, sender)?;
+        if let Some(line) = target.line(msg)? {
+            writeln!(file, "{}", line)?;
         }
     }
 
-    println!(
-        "Экспортировано {} сообщений в {}",
-        messages.len(),
-        output_file
+    Ok(Exported {
+        count: messages.len(),
+        messages,
+    })
+}
+
+/// Write messages newest first as they arrive
+///
+/// With `resume` the cursor is checkpointed every [`CURSOR_EVERY`] messages,
+/// right after a flush. Whatever reached the file after the last checkpoint is
+/// cut off on restart and fetched again, so nothing is lost or repeated.
+/// Messages are only kept in memory when `keep` is set.
+async fn export_streaming(
+    client: &Client,
+    chat: &Peer,
+    target: &ExportTarget<'_>,
+    resume: bool,
+    keep: bool,
+) -> Result<Exported> {
+    let cursor_file = cursor_path(target.output_file);
+    let cursor = if resume && Path::new(target.output_file).exists() {
+        read_cursor(&cursor_file)
+    } else {
+        None
+    };
+
+    let mut file = match cursor {
+        Some(cursor) => {
+            println!("Продолжаю экспорт с сообщения {}", cursor.message_id);
+            let mut file = OpenOptions::new().write(true).open(target.output_file)?;
+            file.set_len(cursor.offset)?;
+            file.seek(SeekFrom::End(0))?;
+            BufWriter::new(file)
+        }
+        None => {
+            let mut file = BufWriter::new(File::create(target.output_file)?);
+            if let Some(header) = target.header() {
+                writeln!(file, "{}", header)?;
+            }
+            file
+        }
+    };
+
+    let mut count = 0;
+    let mut messages = Vec::new();
+    let mut history = scan_messages(
+        client,
+        chat,
+        target.fetch_options(cursor.map(|c| c.message_id)),
     );
 
-    Ok(())
+    while let Some(msg) = history.next().await? {
+        if let Some(line) = target.line(&msg)? {
+            writeln!(file, "{}", line)?;
+        }
+        count += 1;
+
+        if resume && count % CURSOR_EVERY == 0 {
+            file.flush()?;
+            let offset = file.get_ref().metadata()?.len();
+            write_cursor(
+                &cursor_file,
+                Cursor {
+                    message_id: msg.id(),
+                    offset,
+                },
+            )?;
+        }
+        if keep {
+            messages.push(msg);
+        }
+    }
+
+    file.flush()?;
+    if resume && cursor_file.exists() {
+        std::fs::remove_file(&cursor_file)?;
+    }
+
+    Ok(Exported { count, messages })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use tempfile::TempDir;
+
+    #[test]
+    fn parses_export_format() {
+        assert_eq!(
+            ExportFormat::parse("markdown").unwrap(),
+            ExportFormat::Markdown
+        );
+        assert_eq!(ExportFormat::parse("JSONL").unwrap(), ExportFormat::Jsonl);
+        assert_eq!(ExportFormat::Jsonl.extension(), "jsonl");
+        assert!(ExportFormat::parse("csv").is_err());
+        assert_eq!("md".parse::<ExportFormat>(), Ok(ExportFormat::Markdown));
+        assert!("csv"
+            .parse::<ExportFormat>()
+            .unwrap_err()
+            .contains("markdown|jsonl"));
+    }
+
+    #[test]
+    fn jsonl_line_has_all_fields_and_reads_back_offline() {
+        let message = JsonlMessage {
+            id: 42,
+            date: "2024-01-05T10:00:00+00:00".to_string(),
+            sender_id: Some(1001),
+            sender_name: "@alice".to_string(),
+            text: "line one\nline two".to_string(),
+            reply_to: None,
+            reactions: 3,
+            has_media: false,
+        };
+
+        let line = serde_json::to_string(&message).unwrap();
+        assert!(!line.contains('\n'));
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&line).unwrap(),
+            serde_json::json!({
+                "id": 42,
+                "date": "2024-01-05T10:00:00+00:00",
+                "sender_id": 1001,
+                "sender_name": "@alice",
+                "text": "line one\nline two",
+                "reply_to": null,
+                "reactions": 3,
+                "has_media": false
+            })
+        );
+
+        let offline = crate::offline::parse_jsonl(&line).unwrap();
+        assert_eq!(offline[0].username(), Some("alice"));
+        assert_eq!(offline[0].reactions, 3);
+    }
+
+    #[test]
+    fn date_window_covers_whole_days() {
+        let window = DateWindow::parse(Some("2024-01-01"), Some("2024-01-31")).unwrap();
+        let at = |d, h, m, s| Utc.with_ymd_and_hms(2024, 1, d, h, m, s).unwrap();
+
+        assert!(!window.is_past(at(1, 0, 0, 0)));
+        assert!(window.is_past(Utc.with_ymd_and_hms(2023, 12, 31, 23, 59, 59).unwrap()));
+        assert!(!window.is_ahead(at(31, 23, 59, 59)));
+        assert!(window.is_ahead(Utc.with_ymd_and_hms(2024, 2, 1, 0, 0, 0).unwrap()));
+
+        let open = DateWindow::parse(None, None).unwrap();
+        assert!(!open.is_past(at(1, 0, 0, 0)) && !open.is_ahead(at(1, 0, 0, 0)));
+    }
+
+    #[test]
+    fn date_window_rejects_bad_input() {
+        assert!(DateWindow::parse(Some("2024-02-01"), Some("2024-01-01")).is_err());
+        assert!(DateWindow::parse(Some("2024-01-01"), Some("2024-01-01")).is_ok());
+        match DateWindow::parse(Some("01.02.2024"), None) {
+            Err(Error::InvalidArgument(msg)) => assert!(msg.contains("--since"), "{}", msg),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn cursor_sits_next_to_the_output() {
+        assert_eq!(cursor_path("chat.md"), PathBuf::from("chat.md.cursor"));
+    }
+
+    #[test]
+    fn cursor_round_trips_through_the_sidecar() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("chat.md.cursor");
+
+        assert_eq!(read_cursor(&path), None);
+        let cursor = Cursor {
+            message_id: 48213,
+            offset: 1024,
+        };
+        write_cursor(&path, cursor).unwrap();
+        assert_eq!(read_cursor(&path), Some(cursor));
+    }
+
+    #[test]
+    fn empty_or_corrupt_cursor_means_full_export() {
+        for contents in ["", "\n", "abc", "-5 10", "0 10", "77", "12 -3", "12 34 56"] {
+            assert_eq!(parse_cursor(contents), None, "{:?}", contents);
+        }
+        assert_eq!(
+            parse_cursor(" 77 512\n"),
+            Some(Cursor {
+                message_id: 77,
+                offset: 512
+            })
+        );
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("chat.md.cursor");
+        std::fs::write(&path, "garbage").unwrap();
+        assert_eq!(read_cursor(&path), None);
+    }
 }
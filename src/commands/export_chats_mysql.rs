@@ -32,7 +32,7 @@ pub async fn run(max_dialogs: usize) -> Result<()> {
     // Ensure environment is loaded for MySQL credentials.
     let _ = dotenvy::dotenv();
 
-    let _lock = SessionLock::acquire()?;
+    let _lock = SessionLock::acquire().await?;
     let client = get_client().await?;
 
     let pool = mysql_pool_from_env()?;
@@ -0,0 +1,439 @@
+//! Run Cypher against the message graph
+//!
+//! Lets users explore the Neo4j graph built by `index_messages` without a
+//! separate client. Queries are read-only by default: anything with a write
+//! clause or a procedure outside a short allowlist is rejected before it
+//! reaches the database unless `--allow-write` is given, and what does run is
+//! executed in a transaction that is rolled back afterwards.
+
+use std::future::Future;
+
+use anyhow::{bail, Result};
+use serde_json::Value;
+
+use crate::analysis::graph_db::{GraphRow, GraphStore};
+
+/// Clauses that modify the graph
+const WRITE_CLAUSES: &[&str] = &[
+    "CREATE", "MERGE", "DELETE", "DETACH", "SET", "REMOVE", "DROP", "FOREACH", "LOAD",
+];
+
+/// Procedures that only read the schema
+const READ_ONLY_PROCEDURES: &[&str] = &["db.labels", "db.relationshipTypes", "db.propertyKeys"];
+
+/// Namespace of read-only schema procedures (`CALL db.schema.visualization()`)
+const READ_ONLY_PROCEDURE_PREFIX: &str = "db.schema.";
+
+/// Widest table cell before values are shortened
+const MAX_CELL_CHARS: usize = 40;
+
+/// Runs a Cypher statement and returns its rows.
+///
+/// Without `allow_write` the statement must not persist anything.
+/// Implemented for [`GraphStore`]; tests plug in a scripted runner.
+pub trait CypherRunner {
+    fn run_cypher(
+        &self,
+        cypher: &str,
+        allow_write: bool,
+    ) -> impl Future<Output = Result<Vec<GraphRow>>> + Send;
+}
+
+impl CypherRunner for GraphStore {
+    async fn run_cypher(&self, cypher: &str, allow_write: bool) -> Result<Vec<GraphRow>> {
+        if allow_write {
+            self.query_rows(cypher).await
+        } else {
+            self.query_rows_read_only(cypher).await
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Table,
+    Json,
+}
+
+impl OutputFormat {
+    pub fn parse(raw: &str) -> Result<Self> {
+        match raw.to_ascii_lowercase().as_str() {
+            "table" | "pretty" => Ok(Self::Table),
+            "json" => Ok(Self::Json),
+            other => bail!("Unsupported format '{}'. Use table|json", other),
+        }
+    }
+}
+
+/// Reject statements that could modify the graph.
+///
+/// Keywords inside string literals, backtick-quoted names and comments don't
+/// count. `CALL` is only allowed for read-only procedures, and only a single
+/// statement is accepted.
+pub fn validate_cypher(cypher: &str, allow_write: bool) -> Result<()> {
+    let tokens = cypher_tokens(cypher);
+    if tokens.is_empty() {
+        bail!("Cypher query is empty");
+    }
+
+    let statements = tokens
+        .iter()
+        .position(|t| t == ";")
+        .map(|pos| tokens[pos + 1..].iter().any(|t| t != ";"))
+        .unwrap_or(false);
+    if statements {
+        bail!("Only a single Cypher statement is allowed");
+    }
+
+    if allow_write {
+        return Ok(());
+    }
+
+    for (i, token) in tokens.iter().enumerate() {
+        let upper = token.to_ascii_uppercase();
+        if WRITE_CLAUSES.contains(&upper.as_str()) {
+            bail!(
+                "Write clause {} is not allowed in a read-only query (use --allow-write)",
+                upper
+            );
+        }
+        if upper == "CALL" {
+            let procedure = tokens.get(i + 1).map(String::as_str).unwrap_or_default();
+            if !is_read_only_procedure(procedure) {
+                bail!(
+                    "CALL {} is not allowed in a read-only query (use --allow-write)",
+                    if procedure.is_empty() {
+                        "{...}"
+                    } else {
+                        procedure
+                    }
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+fn is_read_only_procedure(procedure: &str) -> bool {
+    READ_ONLY_PROCEDURES.contains(&procedure)
+        || procedure
+            .strip_prefix(READ_ONLY_PROCEDURE_PREFIX)
+            .is_some_and(|name| !name.is_empty())
+}
+
+/// Words (with dots, for procedure names) and `;`, outside literals and comments
+fn cypher_tokens(cypher: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    let mut chars = cypher.chars().peekable();
+
+    let flush = |word: &mut String, tokens: &mut Vec<String>| {
+        if !word.is_empty() {
+            tokens.push(std::mem::take(word));
+        }
+    };
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' | '"' | '`' => {
+                flush(&mut word, &mut tokens);
+                let mut escaped = false;
+                for next in chars.by_ref() {
+                    if escaped {
+                        escaped = false;
+                    } else if next == '\\' && c != '`' {
+                        escaped = true;
+                    } else if next == c {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                flush(&mut word, &mut tokens);
+                for next in chars.by_ref() {
+                    if next == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                flush(&mut word, &mut tokens);
+                chars.next();
+                let mut star = false;
+                for next in chars.by_ref() {
+                    if star && next == '/' {
+                        break;
+                    }
+                    star = next == '*';
+                }
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '.' => word.push(c),
+            ';' => {
+                flush(&mut word, &mut tokens);
+                tokens.push(";".to_string());
+            }
+            _ => flush(&mut word, &mut tokens),
+        }
+    }
+    flush(&mut word, &mut tokens);
+    tokens
+}
+
+/// Validate and run a query
+pub async fn execute_query<R: CypherRunner>(
+    runner: &R,
+    cypher: &str,
+    allow_write: bool,
+) -> Result<Vec<GraphRow>> {
+    validate_cypher(cypher, allow_write)?;
+    runner.run_cypher(cypher, allow_write).await
+}
+
+/// Rows as an aligned text table (columns in first-seen order) or pretty JSON
+pub fn format_rows(rows: &[GraphRow], format: OutputFormat) -> Result<String> {
+    if format == OutputFormat::Json {
+        return Ok(serde_json::to_string_pretty(rows)?);
+    }
+    if rows.is_empty() {
+        return Ok("(no rows)".to_string());
+    }
+
+    let mut columns: Vec<&str> = Vec::new();
+    for row in rows {
+        for key in row.keys() {
+            if !columns.contains(&key.as_str()) {
+                columns.push(key);
+            }
+        }
+    }
+
+    let cells: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| {
+            columns
+                .iter()
+                .map(|column| cell(row.get(*column).unwrap_or(&Value::Null)))
+                .collect()
+        })
+        .collect();
+    let widths: Vec<usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            cells
+                .iter()
+                .map(|row| row[i].chars().count())
+                .chain([column.chars().count()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let line = |values: Vec<&str>| {
+        // Missing trailing cells are left out rather than padded
+        let filled = values
+            .iter()
+            .rposition(|v| !v.is_empty())
+            .map_or(0, |i| i + 1);
+        values[..filled]
+            .iter()
+            .zip(&widths)
+            .map(|(value, width)| format!("{:<width$}", value, width = width))
+            .collect::<Vec<_>>()
+            .join(" | ")
+            .trim_end()
+            .to_string()
+    };
+
+    let mut out = vec![line(columns.clone())];
+    out.push(
+        widths
+            .iter()
+            .map(|w| "-".repeat(*w))
+            .collect::<Vec<_>>()
+            .join("-+-"),
+    );
+    for row in &cells {
+        out.push(line(row.iter().map(String::as_str).collect()));
+    }
+    out.push(format!(
+        "({} row{})",
+        rows.len(),
+        if rows.len() == 1 { "" } else { "s" }
+    ));
+    Ok(out.join("\n"))
+}
+
+fn cell(value: &Value) -> String {
+    let text = match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    let text = text.replace('\n', " ");
+    if text.chars().count() > MAX_CELL_CHARS {
+        let cut: String = text.chars().take(MAX_CELL_CHARS - 1).collect();
+        format!("{}…", cut)
+    } else {
+        text
+    }
+}
+
+/// Main entry point for the CLI: connect with `NEO4J_*` settings and print the rows.
+pub async fn run(cypher: &str, format: &str, allow_write: bool) -> Result<Vec<GraphRow>> {
+    let format = OutputFormat::parse(format)?;
+    // Validate before connecting so a rejected query fails fast
+    validate_cypher(cypher, allow_write)?;
+
+    let store = GraphStore::from_env().await?;
+    let rows = execute_query(&store, cypher, allow_write).await?;
+    println!("{}", format_rows(&rows, format)?);
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct MockRunner {
+        rows: Vec<GraphRow>,
+        calls: Mutex<Vec<(String, bool)>>,
+    }
+
+    impl CypherRunner for MockRunner {
+        async fn run_cypher(&self, cypher: &str, allow_write: bool) -> Result<Vec<GraphRow>> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push((cypher.to_string(), allow_write));
+            Ok(self.rows.clone())
+        }
+    }
+
+    fn row(value: Value) -> GraphRow {
+        value.as_object().cloned().unwrap()
+    }
+
+    #[test]
+    fn accepts_read_queries() {
+        for cypher in [
+            "MATCH (u:User) RETURN u.name, u.message_count ORDER BY u.message_count DESC LIMIT 10",
+            "MATCH (m:Message) WHERE m.text CONTAINS 'CREATE table' RETURN count(m);",
+            "MATCH (n:`DELETE me`) RETURN n // SET is fine in a comment",
+            "CALL db.labels()",
+            "MATCH (u:User) WITH u.created_at AS created RETURN created",
+        ] {
+            assert!(validate_cypher(cypher, false).is_ok(), "{}", cypher);
+        }
+    }
+
+    #[test]
+    fn rejects_write_clauses() {
+        for (cypher, clause) in [
+            ("CREATE (u:User {name: 'x'})", "CREATE"),
+            ("match (u:User) detach delete u", "DETACH"),
+            ("MERGE (c:Chat {chat_id: 1})", "MERGE"),
+            ("MATCH (u:User) SET u.banned = true", "SET"),
+            ("MATCH (u) /* harmless */ REMOVE u.name", "REMOVE"),
+            ("LOAD CSV FROM 'file:///x.csv' AS row RETURN row", "LOAD"),
+        ] {
+            let err = validate_cypher(cypher, false).unwrap_err().to_string();
+            assert!(err.contains(clause), "{}: {}", cypher, err);
+            assert!(err.contains("--allow-write"));
+            assert!(validate_cypher(cypher, true).is_ok());
+        }
+
+        assert!(validate_cypher("CALL apoc.create.node(['X'], {})", false).is_err());
+        assert!(validate_cypher("CALL { CREATE (n) }", false).is_err());
+    }
+
+    #[test]
+    fn only_schema_procedures_are_read_only() {
+        for cypher in [
+            "CALL db.labels()",
+            "CALL db.relationshipTypes()",
+            "CALL db.propertyKeys()",
+            "CALL db.schema.visualization()",
+        ] {
+            assert!(validate_cypher(cypher, false).is_ok(), "{}", cypher);
+        }
+
+        for cypher in [
+            "CALL db.createLabel('X')",
+            "CALL db.createProperty('x')",
+            "CALL db.clearQueryCaches()",
+            "CALL db.labelsAndMore()",
+            "CALL db.schema.()",
+            "CALL dbms.components()",
+        ] {
+            let err = validate_cypher(cypher, false).unwrap_err().to_string();
+            assert!(err.contains("--allow-write"), "{}: {}", cypher, err);
+        }
+        assert!(validate_cypher("CALL db.createLabel('X')", true).is_ok());
+    }
+
+    #[test]
+    fn rejects_empty_and_multiple_statements() {
+        assert!(validate_cypher("  // nothing\n", false).is_err());
+        assert!(validate_cypher("MATCH (n) RETURN n; MATCH (m) DETACH DELETE m", true).is_err());
+        assert!(validate_cypher("MATCH (n) RETURN n;;", false).is_ok());
+    }
+
+    #[tokio::test]
+    async fn rejected_queries_never_reach_the_database() {
+        let runner = MockRunner::default();
+        assert!(execute_query(&runner, "MATCH (n) DELETE n", false)
+            .await
+            .is_err());
+        assert!(runner.calls.lock().unwrap().is_empty());
+
+        execute_query(&runner, "MATCH (n) RETURN n", false)
+            .await
+            .unwrap();
+        // Read-only queries are run without write access
+        assert_eq!(
+            *runner.calls.lock().unwrap(),
+            vec![("MATCH (n) RETURN n".to_string(), false)]
+        );
+    }
+
+    #[tokio::test]
+    async fn formats_rows_as_table_and_json() {
+        let runner = MockRunner {
+            rows: vec![
+                row(json!({"name": "Alice", "messages": 42})),
+                row(json!({"name": "Bob", "messages": 7, "username": null})),
+            ],
+            ..Default::default()
+        };
+        let rows = execute_query(&runner, "MATCH (u:User) RETURN u.name AS name", false)
+            .await
+            .unwrap();
+
+        let table = format_rows(&rows, OutputFormat::Table).unwrap();
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines[0], "messages | name  | username");
+        assert_eq!(lines[1], "---------+-------+---------");
+        assert_eq!(lines[2], "42       | Alice");
+        assert_eq!(lines[3], "7        | Bob");
+        assert_eq!(lines[4], "(2 rows)");
+
+        let json: Value =
+            serde_json::from_str(&format_rows(&rows, OutputFormat::Json).unwrap()).unwrap();
+        assert_eq!(json[0]["name"], "Alice");
+        assert_eq!(json[1]["messages"], 7);
+
+        assert_eq!(format_rows(&[], OutputFormat::Table).unwrap(), "(no rows)");
+    }
+
+    #[test]
+    fn long_cells_are_shortened() {
+        let rows = vec![row(json!({"text": "x".repeat(100)}))];
+        let table = format_rows(&rows, OutputFormat::Table).unwrap();
+        assert!(table.lines().nth(2).unwrap().ends_with('…'));
+        assert!(OutputFormat::parse("csv").is_err());
+    }
+}
@@ -2,12 +2,16 @@
 //!
 //! Search for potential candidates based on message content, activity, interests
 
-use crate::error::Result;
+use crate::chat::{scan_messages, FetchOptions};
+use crate::error::{Error, Result};
+use crate::export::{write_records, CsvOptions};
+use crate::resolve::{resolve_users, UserCache};
+use crate::scan::ScanStats;
 use crate::session::{get_client, SessionLock};
-use chrono::{DateTime, Duration, Utc};
-use regex::Regex;
+use chrono::{DateTime, Duration, Timelike, Utc};
+use regex::{Regex, RegexBuilder};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 /// Criteria for hunting/filtering users
 #[derive(Debug, Clone, Default)]
@@ -26,6 +30,85 @@ pub struct HuntCriteria {
     pub patterns: Vec<String>,
     /// Only users with bio containing keywords
     pub bio_keywords: Vec<String>,
+    /// Usernames to skip (admins, bots, colleagues)
+    pub exclude_users: Vec<String>,
+    /// Treat keywords, required and exclude lists as case-insensitive regexes
+    pub regex: bool,
+}
+
+/// A keyword as it is matched against message text
+#[derive(Debug, Clone)]
+enum Matcher {
+    /// Case-insensitive substring (the default)
+    Substring { keyword: String, lower: String },
+    /// Case-insensitive regex (`--regex`)
+    Regex { keyword: String, regex: Regex },
+}
+
+impl Matcher {
+    fn keyword(&self) -> &str {
+        match self {
+            Matcher::Substring { keyword, .. } | Matcher::Regex { keyword, .. } => keyword,
+        }
+    }
+
+    fn is_match(&self, text: &str, text_lower: &str) -> bool {
+        match self {
+            Matcher::Substring { lower, .. } => text_lower.contains(lower.as_str()),
+            Matcher::Regex { regex, .. } => regex.is_match(text),
+        }
+    }
+}
+
+/// Keyword, required and exclude lists compiled once before scanning
+#[derive(Debug, Clone)]
+pub struct KeywordMatchers {
+    keywords: Vec<Matcher>,
+    required: Vec<Matcher>,
+    exclude: Vec<Matcher>,
+}
+
+impl KeywordMatchers {
+    /// Compile the criteria's keyword lists; an invalid regex names the pattern
+    pub fn new(criteria: &HuntCriteria) -> Result<Self> {
+        let compile = |keywords: &[String]| -> Result<Vec<Matcher>> {
+            keywords
+                .iter()
+                .map(|keyword| {
+                    if !criteria.regex {
+                        return Ok(Matcher::Substring {
+                            keyword: keyword.clone(),
+                            lower: keyword.to_lowercase(),
+                        });
+                    }
+                    let regex = RegexBuilder::new(keyword)
+                        .case_insensitive(true)
+                        .build()
+                        .map_err(|e| {
+                            Error::InvalidArgument(format!("Invalid regex '{}': {}", keyword, e))
+                        })?;
+                    Ok(Matcher::Regex {
+                        keyword: keyword.clone(),
+                        regex,
+                    })
+                })
+                .collect()
+        };
+
+        Ok(Self {
+            keywords: compile(&criteria.keywords)?,
+            required: compile(&criteria.required_keywords)?,
+            exclude: compile(&criteria.exclude_keywords)?,
+        })
+    }
+
+    /// Message mentions one of the excluded keywords
+    fn is_excluded(&self, text: &str) -> bool {
+        let text_lower = text.to_lowercase();
+        self.exclude
+            .iter()
+            .any(|matcher| matcher.is_match(text, &text_lower))
+    }
 }
 
 /// Information about a found user
@@ -39,6 +122,16 @@ pub struct HuntResult {
     pub keywords_found: Vec<String>,
     pub last_active: DateTime<Utc>,
     pub score: f64,
+    /// Matched messages per hour of day (UTC), index 0 = 00:00–00:59
+    #[serde(default)]
+    pub hourly_activity: [u32; 24],
+}
+
+/// Found users along with what was scanned to find them
+#[derive(Debug, Clone, Default)]
+pub struct HuntReport {
+    pub results: Vec<HuntResult>,
+    pub scan: ScanStats,
 }
 
 /// Hunt for users in a chat matching criteria
@@ -46,8 +139,26 @@ pub async fn hunt_users(
     chat_name: &str,
     criteria: HuntCriteria,
     max_messages: usize,
-) -> Result<Vec<HuntResult>> {
-    let _lock = SessionLock::acquire()?;
+) -> Result<HuntReport> {
+    let matchers = KeywordMatchers::new(&criteria)?;
+    hunt_users_cached(
+        chat_name,
+        criteria,
+        &matchers,
+        max_messages,
+        &mut UserCache::default(),
+    )
+    .await
+}
+
+async fn hunt_users_cached(
+    chat_name: &str,
+    criteria: HuntCriteria,
+    matchers: &KeywordMatchers,
+    max_messages: usize,
+    user_cache: &mut UserCache,
+) -> Result<HuntReport> {
+    let _lock = SessionLock::acquire().await?;
     let client = get_client().await?;
 
     println!("🎯 Hunting users in '{}' with criteria:", chat_name);
@@ -60,9 +171,20 @@ pub async fn hunt_users(
     if !criteria.exclude_keywords.is_empty() {
         println!("   Exclude: {:?}", criteria.exclude_keywords);
     }
+    if criteria.regex {
+        println!("   Mode: regex");
+    }
 
     let chat = crate::chat::find_chat(&client, chat_name).await?;
 
+    let excluded_ids: HashSet<i64> = if criteria.exclude_users.is_empty() {
+        HashSet::new()
+    } else {
+        let report = resolve_users(&client, &criteria.exclude_users, user_cache).await;
+        report.print_unresolved();
+        report.users.values().filter_map(|u| u.id).collect()
+    };
+
     // Compile regex patterns
     let patterns: Vec<Regex> = criteria
         .patterns
@@ -75,79 +197,75 @@ pub async fn hunt_users(
 
     // Collect user data
     let mut user_data: HashMap<i64, UserData> = HashMap::new();
-    let mut iter = client.iter_messages(&chat);
-    let mut count = 0;
-
-    while let Some(msg_result) = iter.next().await.transpose() {
-        if count >= max_messages {
-            break;
+    let mut scan = ScanStats::default();
+    let options = FetchOptions {
+        limit: Some(max_messages),
+        since: Some(cutoff),
+        ..Default::default()
+    };
+    let mut messages = scan_messages(&client, &chat, options);
+
+    while let Some(msg) = messages.next().await? {
+        let msg_time: DateTime<Utc> = msg.date();
+        scan.record(msg_time);
+
+        let text = msg.text().trim().to_string();
+        if text.is_empty() {
+            continue;
         }
-        count += 1;
-
-        if let Ok(msg) = msg_result {
-            let msg_time: DateTime<Utc> = msg.date();
-            if msg_time < cutoff {
-                break;
-            }
-
-            let text = msg.text().trim().to_string();
-            if text.is_empty() {
-                continue;
-            }
 
-            // Get sender info
-            let (user_id, username, full_name) = if let Some(sender) = msg.sender() {
-                match sender {
-                    grammers_client::types::Peer::User(u) => {
-                        let user_id = match &u.raw {
-                            grammers_tl_types::enums::User::User(user) => user.id,
-                            grammers_tl_types::enums::User::Empty(empty) => empty.id,
-                        };
-                        (user_id, u.username().map(String::from), u.full_name())
-                    }
-                    _ => continue, // Skip non-user senders
+        // Get sender info
+        let (user_id, username, full_name) = if let Some(sender) = msg.sender() {
+            match sender {
+                grammers_client::types::Peer::User(u) => {
+                    let user_id = match &u.raw {
+                        grammers_tl_types::enums::User::User(user) => user.id,
+                        grammers_tl_types::enums::User::Empty(empty) => empty.id,
+                    };
+                    (user_id, u.username().map(String::from), u.full_name())
                 }
-            } else {
-                continue;
-            };
-
-            // Check if message matches criteria
-            let matches = check_message_match(&text, &criteria, &patterns);
-            if matches.is_empty() && criteria.keywords.is_empty() && criteria.patterns.is_empty() {
-                // If no keywords specified, collect all active users
-            } else if matches.is_empty() {
-                continue;
+                _ => continue, // Skip non-user senders
             }
+        } else {
+            continue;
+        };
+        if excluded_ids.contains(&user_id) {
+            continue;
+        }
 
-            // Check exclusion keywords
-            let text_lower = text.to_lowercase();
-            if criteria
-                .exclude_keywords
-                .iter()
-                .any(|k| text_lower.contains(&k.to_lowercase()))
-            {
-                continue;
-            }
+        // Check if message matches criteria
+        let matches = check_message_match(&text, matchers, &patterns);
+        if matches.is_empty() && criteria.keywords.is_empty() && criteria.patterns.is_empty() {
+            // If no keywords specified, collect all active users
+        } else if matches.is_empty() {
+            continue;
+        }
 
-            // Add to user data
-            let entry = user_data.entry(user_id).or_insert_with(|| UserData {
-                user_id,
-                username: username.clone(),
-                full_name: full_name.clone(),
-                messages: Vec::new(),
-                keywords_found: Vec::new(),
-                last_active: msg_time,
-            });
-
-            if msg_time > entry.last_active {
-                entry.last_active = msg_time;
-            }
+        // Check exclusion keywords
+        if matchers.is_excluded(&text) {
+            continue;
+        }
 
-            // Store matching message (truncated)
-            let truncated: String = text.chars().take(200).collect();
-            entry.messages.push(truncated);
-            entry.keywords_found.extend(matches);
+        // Add to user data
+        let entry = user_data.entry(user_id).or_insert_with(|| UserData {
+            user_id,
+            username: username.clone(),
+            full_name: full_name.clone(),
+            messages: Vec::new(),
+            keywords_found: Vec::new(),
+            last_active: msg_time,
+            hourly_activity: [0; 24],
+        });
+
+        if msg_time > entry.last_active {
+            entry.last_active = msg_time;
         }
+
+        // Store matching message (truncated)
+        let truncated: String = text.chars().take(200).collect();
+        entry.messages.push(truncated);
+        entry.keywords_found.extend(matches);
+        entry.hourly_activity[msg_time.hour() as usize] += 1;
     }
 
     // Convert to results and filter by min_messages
@@ -178,6 +296,7 @@ pub async fn hunt_users(
                     .collect(),
                 last_active: u.last_active,
                 score,
+                hourly_activity: u.hourly_activity,
             }
         })
         .collect();
@@ -190,7 +309,7 @@ pub async fn hunt_users(
     });
 
     println!("✅ Found {} matching users", results.len());
-    Ok(results)
+    Ok(HuntReport { results, scan })
 }
 
 struct UserData {
@@ -200,23 +319,17 @@ struct UserData {
     messages: Vec<String>,
     keywords_found: Vec<String>,
     last_active: DateTime<Utc>,
+    hourly_activity: [u32; 24],
 }
 
-fn check_message_match(text: &str, criteria: &HuntCriteria, patterns: &[Regex]) -> Vec<String> {
+fn check_message_match(text: &str, matchers: &KeywordMatchers, patterns: &[Regex]) -> Vec<String> {
     let mut matches = Vec::new();
     let text_lower = text.to_lowercase();
 
-    // Check keywords
-    for keyword in &criteria.keywords {
-        if text_lower.contains(&keyword.to_lowercase()) {
-            matches.push(keyword.clone());
-        }
-    }
-
-    // Check required keywords
-    for keyword in &criteria.required_keywords {
-        if text_lower.contains(&keyword.to_lowercase()) {
-            matches.push(keyword.clone());
+    // Check keywords and required keywords
+    for matcher in matchers.keywords.iter().chain(&matchers.required) {
+        if matcher.is_match(text, &text_lower) {
+            matches.push(matcher.keyword().to_string());
         }
     }
 
@@ -290,6 +403,10 @@ pub fn print_results(results: &[HuntResult], limit: usize) {
             "   🕐 Last active: {}",
             user.last_active.format("%d.%m.%Y %H:%M")
         );
+        println!(
+            "   ⏰ Hours (UTC 00–23): {}",
+            sparkline(&user.hourly_activity)
+        );
 
         if !user.matching_messages.is_empty() {
             println!("   💬 Sample message:");
@@ -305,25 +422,54 @@ pub fn print_results(results: &[HuntResult], limit: usize) {
     }
 }
 
+/// One bar per hour, scaled to the busiest hour; `·` marks hours without messages
+pub fn sparkline(hours: &[u32; 24]) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let max = hours.iter().copied().max().unwrap_or(0);
+
+    hours
+        .iter()
+        .map(|&count| {
+            if count == 0 {
+                return '·';
+            }
+            let level = (count as u64 * BARS.len() as u64).div_ceil(max as u64);
+            BARS[(level as usize).clamp(1, BARS.len()) - 1]
+        })
+        .collect()
+}
+
 /// Export results to CSV
-pub fn export_csv(results: &[HuntResult]) -> String {
-    let mut csv =
-        String::from("user_id,username,full_name,message_count,score,last_active,keywords\n");
-
-    for result in results {
-        csv.push_str(&format!(
-            "{},\"{}\",\"{}\",{},{:.1},{},\"{}\"\n",
-            result.user_id,
-            result.username.as_deref().unwrap_or(""),
-            result.full_name.replace('"', "'"),
-            result.message_count,
-            result.score,
-            result.last_active.format("%Y-%m-%d %H:%M"),
-            result.keywords_found.join("; ")
-        ));
-    }
+pub fn export_csv(results: &[HuntResult], options: &CsvOptions) -> Result<String> {
+    let mut headers: Vec<String> = [
+        "user_id",
+        "username",
+        "full_name",
+        "message_count",
+        "score",
+        "last_active",
+        "keywords",
+    ]
+    .iter()
+    .map(|h| h.to_string())
+    .collect();
+    headers.extend((0..24).map(|hour| format!("hour_{:02}", hour)));
+
+    let rows = results.iter().map(|result| {
+        let mut row = vec![
+            result.user_id.to_string(),
+            result.username.clone().unwrap_or_default(),
+            result.full_name.clone(),
+            result.message_count.to_string(),
+            format!("{:.1}", result.score),
+            result.last_active.format("%Y-%m-%d %H:%M").to_string(),
+            result.keywords_found.join("; "),
+        ];
+        row.extend(result.hourly_activity.iter().map(|n| n.to_string()));
+        row
+    });
 
-    csv
+    write_records(&headers, rows, options)
 }
 
 /// Search multiple chats for users matching criteria
@@ -331,14 +477,26 @@ pub async fn hunt_multiple_chats(
     chat_names: &[&str],
     criteria: HuntCriteria,
     max_messages_per_chat: usize,
-) -> Result<Vec<HuntResult>> {
+) -> Result<HuntReport> {
+    // Fail on a bad pattern before any chat is scanned
+    let matchers = KeywordMatchers::new(&criteria)?;
     let mut all_results: HashMap<i64, HuntResult> = HashMap::new();
+    let mut user_cache = UserCache::default();
+    let mut scan_stats = ScanStats::default();
 
     for chat_name in chat_names {
         println!("\n📡 Scanning chat: {}", chat_name);
-        match hunt_users(chat_name, criteria.clone(), max_messages_per_chat).await {
-            Ok(results) => {
-                for result in results {
+        let scan = hunt_users_cached(
+            chat_name,
+            criteria.clone(),
+            &matchers,
+            max_messages_per_chat,
+            &mut user_cache,
+        );
+        match scan.await {
+            Ok(report) => {
+                scan_stats.merge(&report.scan);
+                for result in report.results {
                     // Merge results for same user
                     let entry = all_results.entry(result.user_id).or_insert(result.clone());
                     if result.score > entry.score {
@@ -359,7 +517,10 @@ pub async fn hunt_multiple_chats(
             .unwrap_or(std::cmp::Ordering::Equal)
     });
 
-    Ok(results)
+    Ok(HuntReport {
+        results,
+        scan: scan_stats,
+    })
 }
 
 #[cfg(test)]
@@ -373,10 +534,11 @@ mod tests {
             ..Default::default()
         };
 
-        let matches = check_message_match("Ищу работу курьером", &criteria, &[]);
+        let matchers = KeywordMatchers::new(&criteria).unwrap();
+        let matches = check_message_match("Ищу работу курьером", &matchers, &[]);
         assert!(matches.contains(&"курьер".to_string()));
 
-        let matches = check_message_match("Привет мир", &criteria, &[]);
+        let matches = check_message_match("Привет мир", &matchers, &[]);
         assert!(matches.is_empty());
     }
 
@@ -385,10 +547,59 @@ mod tests {
         let criteria = HuntCriteria::default();
         let patterns = vec![Regex::new(r"\d{3,}[₽$€]").unwrap()];
 
-        let matches = check_message_match("Зарплата 50000₽", &criteria, &patterns);
+        let matchers = KeywordMatchers::new(&criteria).unwrap();
+        let matches = check_message_match("Зарплата 50000₽", &matchers, &patterns);
         assert!(!matches.is_empty());
     }
 
+    #[test]
+    fn test_regex_mode_matches_whole_words_case_insensitively() {
+        let criteria = HuntCriteria {
+            keywords: vec![r"\bart\b".to_string()],
+            exclude_keywords: vec![r"^spam".to_string()],
+            regex: true,
+            ..Default::default()
+        };
+        let matchers = KeywordMatchers::new(&criteria).unwrap();
+
+        assert_eq!(
+            check_message_match("Love ART and design", &matchers, &[]),
+            vec![r"\bart\b".to_string()]
+        );
+        assert!(check_message_match("Let's start", &matchers, &[]).is_empty());
+        assert!(matchers.is_excluded("SPAM: buy now"));
+        assert!(!matchers.is_excluded("no spam here"));
+
+        // Substring mode stays the default
+        let substring = KeywordMatchers::new(&HuntCriteria {
+            keywords: vec!["art".to_string()],
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(!check_message_match("Let's start", &substring, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_invalid_regex_names_the_pattern() {
+        let criteria = HuntCriteria {
+            keywords: vec!["ok".to_string()],
+            required_keywords: vec!["rust(".to_string()],
+            regex: true,
+            ..Default::default()
+        };
+        match KeywordMatchers::new(&criteria) {
+            Err(Error::InvalidArgument(msg)) => assert!(msg.contains("'rust('"), "{}", msg),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        // Not a regex in substring mode
+        assert!(KeywordMatchers::new(&HuntCriteria {
+            regex: false,
+            ..criteria
+        })
+        .is_ok());
+    }
+
     #[test]
     fn test_export_csv() {
         let results = vec![HuntResult {
@@ -400,10 +611,69 @@ mod tests {
             keywords_found: vec!["keyword".to_string()],
             last_active: Utc::now(),
             score: 50.0,
+            hourly_activity: [0; 24],
         }];
 
-        let csv = export_csv(&results);
+        let csv = export_csv(&results, &CsvOptions::default()).unwrap();
         assert!(csv.contains("testuser"));
         assert!(csv.contains("Test User"));
     }
+
+    #[test]
+    fn test_export_csv_keeps_quotes_and_commas() {
+        let results = vec![HuntResult {
+            user_id: 1,
+            username: None,
+            full_name: "Ivan \"The Dev\", PM".to_string(),
+            message_count: 1,
+            matching_messages: Vec::new(),
+            keywords_found: vec!["rust".to_string(), "go, python".to_string()],
+            last_active: Utc::now(),
+            score: 1.0,
+            hourly_activity: [0; 24],
+        }];
+
+        let csv = export_csv(&results, &CsvOptions::default()).unwrap();
+        let mut reader = csv::Reader::from_reader(csv.as_bytes());
+        let record = reader.records().next().unwrap().unwrap();
+        assert_eq!(&record[2], "Ivan \"The Dev\", PM");
+        assert_eq!(&record[6], "rust; go, python");
+    }
+
+    #[test]
+    fn test_hourly_activity_in_csv_and_sparkline() {
+        let mut hours = [0; 24];
+        hours[9] = 4;
+        hours[10] = 8;
+        hours[23] = 1;
+        let results = vec![HuntResult {
+            user_id: 7,
+            username: None,
+            full_name: "Night Owl".to_string(),
+            message_count: 13,
+            matching_messages: Vec::new(),
+            keywords_found: Vec::new(),
+            last_active: Utc::now(),
+            score: 1.0,
+            hourly_activity: hours,
+        }];
+
+        let csv = export_csv(&results, &CsvOptions::default()).unwrap();
+        let mut reader = csv::Reader::from_reader(csv.as_bytes());
+        let headers = reader.headers().unwrap().clone();
+        assert_eq!(headers.len(), 7 + 24);
+        assert_eq!(&headers[7], "hour_00");
+        assert_eq!(&headers[30], "hour_23");
+        let record = reader.records().next().unwrap().unwrap();
+        assert_eq!(&record[7 + 10], "8");
+        assert_eq!(&record[7 + 23], "1");
+
+        let line = sparkline(&hours);
+        assert_eq!(line.chars().count(), 24);
+        assert_eq!(line.chars().nth(10), Some('█'));
+        assert_eq!(line.chars().nth(9), Some('▄'));
+        assert_eq!(line.chars().nth(23), Some('▁'));
+        assert_eq!(line.chars().next(), Some('·'));
+        assert_eq!(sparkline(&[0; 24]), "·".repeat(24));
+    }
 }
@@ -1,5 +1,7 @@
 //! Index messages to vector and graph databases
 
+use std::path::PathBuf;
+
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use grammers_client::types::Message;
@@ -14,6 +16,7 @@ use crate::analysis::{
 };
 use crate::chat::resolve_chat;
 use crate::config::{ChatEntity, Config};
+use crate::progress::Progress;
 use crate::session::SessionLock;
 use crate::{get_client, KNOWN_SENDERS};
 
@@ -29,6 +32,10 @@ pub struct IndexConfig {
     pub limit: usize,
     /// Generate embeddings
     pub generate_embeddings: bool,
+    /// Embedding requests in flight at once
+    pub concurrency: usize,
+    /// Directory for cached embeddings (no cache when unset)
+    pub embedding_cache: Option<PathBuf>,
 }
 
 impl Default for IndexConfig {
@@ -40,6 +47,8 @@ impl Default for IndexConfig {
             use_graph_db: true,
             limit: 1000,
             generate_embeddings: true,
+            concurrency: 4,
+            embedding_cache: None,
         }
     }
 }
@@ -62,6 +71,7 @@ pub async fn index_chat(
     let mut count = 0;
     let mut user_stats: std::collections::HashMap<i64, UserStats> =
         std::collections::HashMap::new();
+    let mut progress = Progress::new(format!("Collecting {}", chat_name), config.limit as u64);
 
     while let Some(message) = messages_iter.next().await? {
         if count >= config.limit {
@@ -82,8 +92,10 @@ pub async fn index_chat(
 
             analyzed_messages.push(analyzed);
             count += 1;
+            progress.inc(1);
         }
     }
+    progress.finish();
 
     info!(
         "Collected {} messages from {}",
@@ -101,7 +113,7 @@ pub async fn index_chat(
 
     // Generate embeddings if enabled
     if config.generate_embeddings && !analyzed_messages.is_empty() {
-        match generate_embeddings(&mut analyzed_messages).await {
+        match generate_embeddings(&mut analyzed_messages, config).await {
             Ok(count) => {
                 result.embeddings_generated = count;
                 info!("Generated {} embeddings", count);
@@ -265,26 +277,41 @@ fn estimate_sentiment(text: &str) -> f32 {
     score.clamp(-1.0, 1.0)
 }
 
-/// Generate embeddings for messages
-async fn generate_embeddings(messages: &mut [AnalyzedMessage]) -> Result<usize> {
-    let embedding_service = EmbeddingService::new()?;
+/// Generate embeddings for messages, `concurrency` batches of 100 at a time
+async fn generate_embeddings(
+    messages: &mut [AnalyzedMessage],
+    config: &IndexConfig,
+) -> Result<usize> {
+    let mut embedding_service = EmbeddingService::from_env();
+    if let Some(dir) = &config.embedding_cache {
+        embedding_service = embedding_service.with_cache_dir(dir)?;
+    }
 
     // Batch texts for embedding
     let texts: Vec<String> = messages.iter().map(|m| m.text.clone()).collect();
 
-    // Process in batches of 100
-    let mut count = 0;
-    for (chunk_idx, chunk) in texts.chunks(100).enumerate() {
-        debug!("Processing embedding batch {}", chunk_idx + 1);
-
-        let embeddings = embedding_service.embed_batch(chunk).await?;
+    let mut progress = Progress::new("Embedding", texts.len() as u64);
+    let embeddings = embedding_service
+        .embed_all(&texts, 100, config.concurrency, |size| {
+            debug!("Finished embedding batch of {}", size);
+            progress.inc(size as u64);
+        })
+        .await?;
+    progress.finish();
+
+    if config.embedding_cache.is_some() {
+        let stats = embedding_service.cache_stats();
+        info!(
+            "Embedding cache: {} hits, {} misses",
+            stats.hits, stats.misses
+        );
+    }
 
-        for (i, embedding) in embeddings.into_iter().enumerate() {
-            let msg_idx = chunk_idx * 100 + i;
-            if msg_idx < messages.len() && !embedding.is_empty() {
-                messages[msg_idx].embedding = Some(embedding);
-                count += 1;
-            }
+    let mut count = 0;
+    for (message, embedding) in messages.iter_mut().zip(embeddings) {
+        if !embedding.is_empty() {
+            message.embedding = Some(embedding);
+            count += 1;
         }
     }
 
@@ -293,7 +320,11 @@ async fn generate_embeddings(messages: &mut [AnalyzedMessage]) -> Result<usize>
 
 /// Index messages to Qdrant
 async fn index_to_vector_db(url: &str, messages: &[AnalyzedMessage]) -> Result<usize> {
-    let store = VectorStore::new(url).await?;
+    // Size a new collection for the backend in use (768 for nomic-embed-text)
+    let store = match messages.iter().find_map(|m| m.embedding.as_ref()) {
+        Some(embedding) => VectorStore::with_dimension(url, embedding.len()).await?,
+        None => VectorStore::new(url).await?,
+    };
     store.init_collection().await?;
     store.upsert_messages(messages).await
 }
@@ -354,7 +385,7 @@ pub struct IndexResult {
 /// Index all configured chats
 pub async fn index_all_chats(config: &IndexConfig) -> Result<Vec<IndexResult>> {
     let app_config = Config::new();
-    let _lock = SessionLock::acquire()?;
+    let _lock = SessionLock::acquire().await?;
     let client = get_client().await?;
 
     let mut results = Vec::new();
@@ -376,7 +407,7 @@ pub async fn index_all_chats(config: &IndexConfig) -> Result<Vec<IndexResult>> {
 
 /// Search similar messages using vector DB
 pub async fn search_similar(query: &str, limit: u64) -> Result<Vec<AnalyzedMessage>> {
-    let embedding_service = EmbeddingService::new()?;
+    let embedding_service = EmbeddingService::from_env();
     let query_embedding = embedding_service.embed(query).await?;
 
     let qdrant_url =
@@ -399,6 +430,7 @@ mod tests {
         assert!(config.use_graph_db);
         assert_eq!(config.limit, 1000);
         assert!(config.generate_embeddings);
+        assert_eq!(config.concurrency, 4);
     }
 
     #[test]
@@ -6,10 +6,11 @@ use std::io::{self, Write};
 
 use crate::config::Config;
 use crate::error::{Error, Result};
-use crate::session::get_client_for_init;
+use crate::session::{get_client_for_init, session_file};
 
 pub async fn run() -> Result<()> {
     let config = Config::new();
+    let session_file = session_file();
 
     println!(
         r#"
@@ -18,7 +19,7 @@ pub async fn run() -> Result<()> {
 ╚═══════════════════════════════════════════════════════════════╝
 
 ⚠️  КРИТИЧЕСКОЕ ПРЕДУПРЕЖДЕНИЕ:
-   Этот скрипт создаст НОВУЮ сессию для номера {}
+   Этот скрипт создаст НОВУЮ сессию {} для номера {}
 
    ЭТО ПРИВЕДЁТ К:
    - Выходу из Telegram на всех других устройствах
@@ -27,7 +28,7 @@ pub async fn run() -> Result<()> {
    Вы УВЕРЕНЫ, что хотите продолжить?
 
    Введите 'YES' (заглавными) для подтверждения: "#,
-        config.phone
+        session_file, config.phone
     );
 
     io::stdout().flush()?;
@@ -77,17 +78,19 @@ pub async fn run() -> Result<()> {
   Имя: {}
   Username: @{}
 
-Файл сессии: telegram_session.session
+Файл сессии: {}
 
 Теперь вы можете:
 1. Запускать команды (read, tg, list-chats и т.д.)
 2. Скрипты будут использовать эту сессию автоматически
 3. НИКОГДА больше не запускайте init-session!
 
-⚠️  ВАЖНО: Сделайте резервную копию файла telegram_session.session
+⚠️  ВАЖНО: Сделайте резервную копию файла {}
 "#,
         user.full_name(),
         user.username().unwrap_or("не указан"),
+        session_file,
+        session_file,
     );
 
     Ok(())
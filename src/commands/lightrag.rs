@@ -50,6 +50,7 @@ pub fn mode_from_str(value: &str) -> RetrievalMode {
     match value.to_lowercase().as_str() {
         "vector" | "naive" => RetrievalMode::VectorOnly,
         "graph" | "local" => RetrievalMode::GraphOnly,
+        "bm25" | "keyword" => RetrievalMode::Bm25Hybrid,
         _ => RetrievalMode::Hybrid, // hybrid / global
     }
 }
@@ -368,6 +369,7 @@ mod tests {
         assert_eq!(mode_from_str("graph"), RetrievalMode::GraphOnly);
         assert_eq!(mode_from_str("local"), RetrievalMode::GraphOnly);
         assert_eq!(mode_from_str("hybrid"), RetrievalMode::Hybrid);
+        assert_eq!(mode_from_str("BM25"), RetrievalMode::Bm25Hybrid);
         assert_eq!(mode_from_str(""), RetrievalMode::Hybrid);
     }
 
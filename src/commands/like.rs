@@ -13,8 +13,11 @@ use rand::Rng;
 use tokio::time::sleep;
 use tracing::{info, warn};
 
-use crate::chat::find_chat;
+use crate::chat::{find_chat, scan_messages, FetchOptions};
+use crate::confirm::confirm_chat;
+use crate::dry_run::{ActionKind, DryRunPlan, PlanFormat};
 use crate::error::{Error, Result};
+use crate::reactions::{count_reactions, FloodRetry};
 use crate::session::{get_client, SessionLock};
 
 /// Configuration for the like command
@@ -26,6 +29,14 @@ pub struct LikeConfig {
     pub limit: usize,
     /// Delay between reactions in milliseconds (to avoid rate limits)
     pub delay_ms: u64,
+    /// Collect planned reactions instead of sending them
+    pub dry_run: bool,
+    /// Skip the confirmation of the resolved chat
+    pub yes: bool,
+    /// Only react to messages with at least this many reactions (0 = all)
+    pub min_reactions: u32,
+    /// Stop after this many reactions in one run (anti-spam cap)
+    pub max_per_run: Option<usize>,
 }
 
 impl Default for LikeConfig {
@@ -34,10 +45,26 @@ impl Default for LikeConfig {
             emoji: "❤️".to_string(),
             limit: 500,
             delay_ms: 1500,
+            dry_run: false,
+            yes: false,
+            min_reactions: 0,
+            max_per_run: None,
         }
     }
 }
 
+impl LikeConfig {
+    /// Whether a message with `count` reactions is popular enough to amplify
+    pub fn meets_threshold(&self, count: i32) -> bool {
+        i64::from(count) >= i64::from(self.min_reactions)
+    }
+
+    /// Whether `sent` reactions (or planned ones in a dry run) hit `max_per_run`
+    pub fn cap_reached(&self, sent: usize) -> bool {
+        self.max_per_run.is_some_and(|max| sent >= max)
+    }
+}
+
 /// Result of like operation
 #[derive(Debug, Default)]
 pub struct LikeResult {
@@ -46,6 +73,12 @@ pub struct LikeResult {
     pub already_reacted_count: usize,
     pub error_count: usize,
     pub messages_scanned: usize,
+    /// Messages from the user with fewer than `min_reactions` reactions
+    pub below_threshold_count: usize,
+    /// FLOOD_WAIT pauses sat through while reacting
+    pub flood_wait_count: usize,
+    /// Reactions skipped by a dry run
+    pub plan: DryRunPlan,
 }
 
 /// Target user matcher: supports numeric IDs, @usernames and name substrings.
@@ -219,34 +252,6 @@ fn already_reacted_with_emoji(message: &Message, emoji: &str) -> bool {
         .unwrap_or(false)
 }
 
-/// Extract flood wait seconds from an error string (best-effort)
-fn parse_flood_wait_seconds(error: &str) -> Option<u64> {
-    if let Some(idx) = error.find("FLOOD_WAIT_") {
-        let start = idx + "FLOOD_WAIT_".len();
-        let secs = error[start..]
-            .chars()
-            .take_while(|c| c.is_ascii_digit())
-            .collect::<String>();
-        if let Ok(v) = secs.parse::<u64>() {
-            return Some(v);
-        }
-    }
-
-    if let Some(idx) = error.find("value:") {
-        let start = idx + "value:".len();
-        let secs = error[start..]
-            .trim_start()
-            .chars()
-            .take_while(|c| c.is_ascii_digit())
-            .collect::<String>();
-        if let Ok(v) = secs.parse::<u64>() {
-            return Some(v);
-        }
-    }
-
-    None
-}
-
 /// Human-like delay generator to avoid predictable timing
 #[derive(Debug)]
 struct HumanDelayStrategy {
@@ -306,9 +311,10 @@ pub async fn like_user_messages(
     let mut rng = rand::thread_rng();
     let mut delay_strategy = HumanDelayStrategy::new(config.delay_ms);
     let target = SenderMatcher::from_input(user_name);
+    let mut flood = FloodRetry::default();
 
     // Acquire session lock
-    let _lock = SessionLock::acquire()?;
+    let _lock = SessionLock::acquire().await?;
 
     // Connect to Telegram
     let client = get_client().await?;
@@ -324,8 +330,15 @@ pub async fn like_user_messages(
             find_chat_by_name(&client, chat_name).await?
         }
     };
+    if !config.dry_run {
+        let action = format!("react {} to messages", config.emoji);
+        confirm_chat(&chat, &action, config.yes)?;
+    }
 
-    let mut result = LikeResult::default();
+    let mut result = LikeResult {
+        plan: DryRunPlan::new("like"),
+        ..Default::default()
+    };
 
     info!(
         "Scanning messages in chat, looking for sender '{}'",
@@ -333,16 +346,25 @@ pub async fn like_user_messages(
     );
 
     // Iterate messages
-    let mut iter = client.iter_messages(&chat);
-
-    while let Some(msg) = iter.next().await.transpose() {
-        let msg = msg.map_err(|e| Error::TelegramError(e.to_string()))?;
-        result.messages_scanned += 1;
+    let options = FetchOptions {
+        limit: Some(config.limit),
+        ..Default::default()
+    };
+    let mut history = scan_messages(&client, &chat, options);
 
-        if result.messages_scanned > config.limit {
+    while let Some(msg) = history.next().await? {
+        let sent = if config.dry_run {
+            result.plan.actions().len()
+        } else {
+            result.liked_count
+        };
+        if config.cap_reached(sent) {
+            info!("Reached --max-per-run limit of {} reactions", sent);
             break;
         }
 
+        result.messages_scanned += 1;
+
         // Check sender
         if let Some(sender) = msg.sender() {
             let sender_info = extract_sender_info(sender);
@@ -356,11 +378,35 @@ pub async fn like_user_messages(
                 continue;
             }
 
-            // Try to send reaction
-            match client
-                .send_reactions(&chat, msg.id(), InputReactions::emoticon(&config.emoji))
-                .await
-            {
+            if !config.meets_threshold(count_reactions(&msg)) {
+                result.below_threshold_count += 1;
+                continue;
+            }
+
+            if config.dry_run {
+                result
+                    .plan
+                    .record(ActionKind::React, chat_name, Some(msg.id()), &config.emoji);
+                continue;
+            }
+
+            // Send the reaction, sleeping through FLOOD_WAIT
+            let flood_waits_before = flood.flood_waits;
+            let emoji = config.emoji.as_str();
+            let sent = flood
+                .run(|| async {
+                    client
+                        .send_reactions(&chat, msg.id(), InputReactions::emoticon(emoji))
+                        .await
+                        .map_err(|e| Error::TelegramError(e.to_string()))
+                })
+                .await;
+            if flood.flood_waits > flood_waits_before {
+                // Increase delay to reduce future flood waits and reset burst counter
+                delay_strategy.register_flood_wait();
+            }
+
+            match sent {
                 Ok(()) => {
                     result.liked_count += 1;
                     let text_preview = msg
@@ -381,16 +427,7 @@ pub async fn like_user_messages(
                     sleep(Duration::from_millis(delay_ms)).await;
                 }
                 Err(e) => {
-                    let err_str = e.to_string();
-                    if let Some(wait_secs) = parse_flood_wait_seconds(&err_str) {
-                        let wait = wait_secs + 1;
-                        warn!("Rate limit hit, waiting {}s: {}", wait, err_str);
-                        sleep(Duration::from_secs(wait)).await;
-                        // Increase delay to reduce future flood waits and reset burst counter
-                        delay_strategy.register_flood_wait();
-                        continue;
-                    }
-                    warn!("Error on message {}: {}", msg.id(), err_str);
+                    warn!("Error on message {}: {}", msg.id(), e);
                     result.error_count += 1;
                 }
             }
@@ -399,25 +436,43 @@ pub async fn like_user_messages(
         }
     }
 
+    result.flood_wait_count = flood.flood_waits;
+    info!(
+        "Like summary: scanned {} messages, reacted to {} ({} planned), {} below the reaction threshold, {} flood waits",
+        result.messages_scanned,
+        result.liked_count,
+        result.plan.actions().len(),
+        result.below_threshold_count,
+        result.flood_wait_count
+    );
+
     Ok(result)
 }
 
 /// Main entry point for the like command
-pub async fn run(chat: &str, user: &str, emoji: Option<&str>, limit: usize) -> Result<()> {
-    let config = LikeConfig {
-        emoji: emoji.unwrap_or("❤️").to_string(),
-        limit,
-        ..Default::default()
-    };
+pub async fn run(
+    chat: &str,
+    user: &str,
+    mut config: LikeConfig,
+    dry_run: Option<PlanFormat>,
+) -> Result<()> {
+    config.dry_run = dry_run.is_some();
 
     let matcher = SenderMatcher::from_input(user);
     println!("🔍 Ищу чат '{}'...", chat);
     println!("👤 Ищу сообщения от '{}'...", matcher.description());
     println!("💝 Буду ставить реакцию: {}", config.emoji);
+    if config.min_reactions > 0 {
+        println!("📈 Только сообщения с {}+ реакциями", config.min_reactions);
+    }
     println!();
 
     let result = like_user_messages(chat, user, config).await?;
 
+    if let Some(format) = dry_run {
+        return result.plan.print(format);
+    }
+
     println!();
     println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
     println!("📊 Результат:");
@@ -428,6 +483,8 @@ pub async fn run(chat: &str, user: &str, emoji: Option<&str>, limit: usize) -> R
         result.skipped_count
     );
     println!("   Уже была наша реакция: {}", result.already_reacted_count);
+    println!("   Ниже порога реакций: {}", result.below_threshold_count);
+    println!("   Пауз FLOOD_WAIT: {}", result.flood_wait_count);
     println!("   Ошибок: {}", result.error_count);
 
     Ok(())
@@ -452,6 +509,10 @@ mod tests {
             emoji: "🔥".to_string(),
             limit: 100,
             delay_ms: 1000,
+            dry_run: false,
+            yes: false,
+            min_reactions: 3,
+            max_per_run: Some(20),
         };
         assert_eq!(config.emoji, "🔥");
         assert_eq!(config.limit, 100);
@@ -484,6 +545,40 @@ mod tests {
         assert_eq!(result.already_reacted_count, 3);
     }
 
+    #[test]
+    fn min_reactions_threshold() {
+        let all = LikeConfig::default();
+        assert!(all.meets_threshold(0));
+
+        let popular = LikeConfig {
+            min_reactions: 5,
+            ..Default::default()
+        };
+        assert!(!popular.meets_threshold(0));
+        assert!(!popular.meets_threshold(4));
+        assert!(popular.meets_threshold(5));
+        assert!(popular.meets_threshold(12));
+    }
+
+    #[test]
+    fn max_per_run_cap() {
+        let unlimited = LikeConfig::default();
+        assert!(!unlimited.cap_reached(10_000));
+
+        let capped = LikeConfig {
+            max_per_run: Some(3),
+            ..Default::default()
+        };
+        assert!(!capped.cap_reached(2));
+        assert!(capped.cap_reached(3));
+
+        let none = LikeConfig {
+            max_per_run: Some(0),
+            ..Default::default()
+        };
+        assert!(none.cap_reached(0));
+    }
+
     #[test]
     fn test_config_clone() {
         let config = LikeConfig::default();
@@ -1,7 +1,15 @@
-//! Create Linear issues via CLI.
+//! Create and update Linear issues via CLI.
+//!
+//! The issue is created first; an optional comment and file attachments follow.
+//! If any of those fail the issue still exists, so the failure is logged as a
+//! warning and the created issue is returned anyway.
+
+use std::path::PathBuf;
+
+use tracing::warn;
 
 use crate::error::{Error, Result};
-use crate::linear::{CreateIssueInput, LinearClient};
+use crate::linear::{CreateIssueInput, CreatedIssue, IssueChanges, LinearClient};
 
 #[derive(Debug, Clone)]
 pub struct LinearArgs {
@@ -13,9 +21,13 @@ pub struct LinearArgs {
     pub priority: i32,
     pub assignee: Option<String>,
     pub labels: Vec<String>,
+    /// Follow-up comment posted after creation
+    pub comment: Option<String>,
+    /// Files uploaded and attached to the issue
+    pub attach_files: Vec<PathBuf>,
 }
 
-pub async fn run(args: LinearArgs) -> Result<()> {
+pub async fn run(args: LinearArgs) -> Result<CreatedIssue> {
     let client = LinearClient::from_optional_key(args.api_key)?;
 
     let team_key = args
@@ -46,19 +58,82 @@ pub async fn run(args: LinearArgs) -> Result<()> {
         })
         .await?;
 
+    if let Some(comment) = args.comment.as_deref().filter(|c| !c.trim().is_empty()) {
+        if let Err(e) = client.create_comment(&issue.id, comment).await {
+            warn!("Задача создана, но комментарий не добавлен: {}", e);
+        }
+    }
+
+    for path in &args.attach_files {
+        if let Err(e) = client.attach_file(&issue.id, path).await {
+            warn!(
+                "Задача создана, но файл {} не прикреплён: {}",
+                path.display(),
+                e
+            );
+        }
+    }
+
     println!(
         "Создана задача {} {}",
         issue.identifier.as_deref().unwrap_or(""),
         issue.url.as_deref().unwrap_or("")
     );
 
-    Ok(())
+    Ok(issue)
+}
+
+#[derive(Debug, Clone)]
+pub struct LinearUpdateArgs {
+    pub api_key: Option<String>,
+    /// Issue identifier, e.g. `ENG-123`
+    pub issue: String,
+    pub changes: IssueChanges,
+    /// Move the issue to its team's completed state
+    pub close: bool,
+}
+
+pub async fn update(args: LinearUpdateArgs) -> Result<CreatedIssue> {
+    if args.close && args.changes.state.is_some() {
+        return Err(Error::InvalidArgument(
+            "--close и --state нельзя указывать вместе".to_string(),
+        ));
+    }
+    if !args.close && args.changes.is_empty() {
+        return Err(Error::InvalidArgument(
+            "Не указано ни одного изменения задачи".to_string(),
+        ));
+    }
+
+    let client = LinearClient::from_optional_key(args.api_key)?;
+
+    let issue = if args.close {
+        if !args.changes.is_empty() {
+            client.update_issue(&args.issue, args.changes).await?;
+        }
+        client.close_issue(&args.issue).await?
+    } else {
+        client.update_issue(&args.issue, args.changes).await?
+    };
+
+    println!(
+        "Обновлена задача {} {}",
+        issue.identifier.as_deref().unwrap_or(&args.issue),
+        issue.url.as_deref().unwrap_or("")
+    );
+
+    Ok(issue)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use httpmock::prelude::*;
+    use std::sync::LazyLock;
+
+    /// Tests pointing `LINEAR_API_URL` at their own mock server run one at a time
+    static ENV_LOCK: LazyLock<tokio::sync::Mutex<()>> =
+        LazyLock::new(|| tokio::sync::Mutex::new(()));
 
     struct EnvGuard {
         key: &'static str,
@@ -90,6 +165,7 @@ mod tests {
 
     #[tokio::test]
     async fn run_clamps_priority_and_trims_labels() {
+        let _lock = ENV_LOCK.lock().await;
         let server = MockServer::start_async().await;
         let _url_guard = EnvGuard::set("LINEAR_API_URL", &server.url("/graphql"));
 
@@ -132,6 +208,8 @@ mod tests {
             priority: 7,
             assignee: None,
             labels: vec!["  l1 ".into(), "".into()],
+            comment: None,
+            attach_files: vec![],
         })
         .await
         .expect("run");
@@ -140,6 +218,87 @@ mod tests {
         issue_mock.assert_calls(1);
     }
 
+    #[tokio::test]
+    async fn run_returns_issue_when_comment_fails() {
+        let _lock = ENV_LOCK.lock().await;
+        let server = MockServer::start_async().await;
+        let _url_guard = EnvGuard::set("LINEAR_API_URL", &server.url("/graphql"));
+
+        server.mock(|when, then| {
+            when.method(POST)
+                .path("/graphql")
+                .body_includes("query TeamId");
+            then.status(200).json_body(serde_json::json!({
+                "data": { "team": { "id": "team-123", "key": "OPS" } }
+            }));
+        });
+        server.mock(|when, then| {
+            when.method(POST)
+                .path("/graphql")
+                .body_includes("mutation IssueCreate");
+            then.status(200).json_body(serde_json::json!({
+                "data": {
+                    "issueCreate": {
+                        "success": true,
+                        "issue": {
+                            "id": "issue-2",
+                            "identifier": "OPS-2",
+                            "title": "Title",
+                            "url": "https://linear.app/ops/issue/OPS-2/title"
+                        }
+                    }
+                }
+            }));
+        });
+        let comment_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/graphql")
+                .body_includes("mutation CommentCreate")
+                .body_includes("\"issueId\":\"issue-2\"");
+            then.status(200).json_body(serde_json::json!({
+                "errors": [ { "message": "rate limited" } ]
+            }));
+        });
+
+        let issue = run(LinearArgs {
+            api_key: Some("test-key".into()),
+            team: Some("OPS".into()),
+            title: "Title".into(),
+            description: None,
+            project: None,
+            priority: 1,
+            assignee: None,
+            labels: vec![],
+            comment: Some("Logs attached".into()),
+            attach_files: vec![PathBuf::from("/nonexistent/trace.log")],
+        })
+        .await
+        .expect("issue is returned despite the failed comment");
+
+        assert_eq!(issue.identifier.as_deref(), Some("OPS-2"));
+        assert_eq!(
+            issue.url.as_deref(),
+            Some("https://linear.app/ops/issue/OPS-2/title")
+        );
+        comment_mock.assert_calls(1);
+    }
+
+    #[tokio::test]
+    async fn update_rejects_empty_changes_before_any_request() {
+        let err = update(LinearUpdateArgs {
+            api_key: Some("key".into()),
+            issue: "ENG-1".into(),
+            changes: IssueChanges::default(),
+            close: false,
+        })
+        .await
+        .unwrap_err();
+
+        assert!(
+            matches!(err, Error::InvalidArgument(msg) if msg.contains("Не указано ни одного изменения"))
+        );
+    }
+
     #[tokio::test]
     async fn run_errors_when_team_missing() {
         let _team_guard = EnvGuard::remove("LINEAR_TEAM_KEY");
@@ -153,6 +312,8 @@ mod tests {
             priority: 1,
             assignee: None,
             labels: vec![],
+            comment: None,
+            attach_files: vec![],
         })
         .await
         .unwrap_err();
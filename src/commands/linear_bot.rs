@@ -1,16 +1,30 @@
 //! Linear bot - Telegram bot for creating Linear issues.
+//!
+//! Two modes:
+//! - command mode: `!linear <title> | <description>` in any dialog;
+//! - watch mode (`LINEAR_WATCH_CHAT`): every message in one chat that matches a
+//!   trigger (`/bug ...` or a configured keyword) is filed as an issue with a
+//!   link back to the message. Edits are watched too, but a message is filed once.
 
 use std::collections::HashSet;
 use std::env;
 
+use grammers_client::client::UpdatesConfiguration;
 use grammers_client::types::peer::Peer;
+use grammers_client::types::update::Update;
 use grammers_client::types::Message;
 use grammers_client::Client;
 use regex::Regex;
 use tokio::signal;
 
+use crate::chat::find_chat;
+use crate::commands::linear::LinearArgs;
 use crate::error::{Error, Result};
 use crate::linear::{CreateIssueInput, LinearClient};
+use crate::session::{is_connection_error, TelegramClient};
+
+/// Longest issue title taken from a flagged message
+const MAX_TITLE_CHARS: usize = 80;
 
 /// Linear bot configuration.
 #[derive(Debug, Clone)]
@@ -20,6 +34,10 @@ pub struct LinearBotConfig {
     pub project_id: Option<String>,
     pub default_priority: i32,
     pub allowed_sender_ids: HashSet<i64>,
+    /// Watch-mode triggers: `/command` at the start of a message, or a word anywhere in it
+    pub trigger_keywords: Vec<String>,
+    /// Chat to watch for flagged messages (`LINEAR_WATCH_CHAT`)
+    pub chat: Option<String>,
 }
 
 impl Default for LinearBotConfig {
@@ -30,6 +48,8 @@ impl Default for LinearBotConfig {
             project_id: None,
             default_priority: 1,
             allowed_sender_ids: HashSet::new(),
+            trigger_keywords: vec!["/bug".to_string()],
+            chat: None,
         }
     }
 }
@@ -56,6 +76,13 @@ impl LinearBotConfig {
             .filter_map(|s| s.trim().parse::<i64>().ok())
             .collect();
 
+        let trigger_keywords: Vec<String> = env::var("LINEAR_TRIGGER_KEYWORDS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+
         Self {
             command_prefix: if command_prefix.is_empty() {
                 "!linear".to_string()
@@ -66,7 +93,86 @@ impl LinearBotConfig {
             project_id,
             default_priority,
             allowed_sender_ids,
+            trigger_keywords: if trigger_keywords.is_empty() {
+                vec!["/bug".to_string()]
+            } else {
+                trigger_keywords
+            },
+            chat: env::var("LINEAR_WATCH_CHAT")
+                .ok()
+                .filter(|s| !s.trim().is_empty()),
+        }
+    }
+
+    /// Issue text of a flagged message, or `None` when no trigger matches.
+    ///
+    /// A `/command` trigger must open the message and is stripped from the
+    /// result; other keywords match anywhere, case-insensitively, and the
+    /// whole message is kept.
+    pub fn flagged_text(&self, text: &str) -> Option<String> {
+        let trimmed = text.trim();
+        let lowered = trimmed.to_lowercase();
+
+        for keyword in &self.trigger_keywords {
+            let keyword = keyword.to_lowercase();
+            if keyword.starts_with('/') {
+                let Some(rest) = lowered.strip_prefix(&keyword) else {
+                    continue;
+                };
+                // `/bug` must not match `/bugfix`; `/bug@my_bot` is fine
+                if rest.is_empty() || rest.starts_with(char::is_whitespace) || rest.starts_with('@')
+                {
+                    let skip = trimmed
+                        .char_indices()
+                        .nth(keyword.chars().count())
+                        .map_or(trimmed.len(), |(i, _)| i);
+                    let body = trimmed[skip..].trim_start();
+                    let body = match body.strip_prefix('@') {
+                        Some(mention) => mention
+                            .split_once(char::is_whitespace)
+                            .map_or("", |(_, rest)| rest),
+                        None => body,
+                    };
+                    return Some(body.trim().to_string());
+                }
+            } else if lowered.contains(&keyword) {
+                return Some(trimmed.to_string());
+            }
         }
+        None
+    }
+}
+
+/// Title and description of an issue filed from a flagged message.
+///
+/// The title is the first non-empty line, cut at [`MAX_TITLE_CHARS`]; the
+/// description is the full text plus the link back to Telegram.
+pub fn flagged_issue(text: &str, link: Option<&str>) -> Option<(String, String)> {
+    let first_line = text.lines().map(str::trim).find(|l| !l.is_empty())?;
+    let title = if first_line.chars().count() > MAX_TITLE_CHARS {
+        let cut: String = first_line.chars().take(MAX_TITLE_CHARS - 1).collect();
+        format!("{}…", cut.trim_end())
+    } else {
+        first_line.to_string()
+    };
+
+    let description = match link {
+        Some(link) => format!("{}\n\nИсходное сообщение в Telegram: {}", text.trim(), link),
+        None => text.trim().to_string(),
+    };
+    Some((title, description))
+}
+
+/// Public link to a message (`t.me/<username>/<id>` or `t.me/c/<id>/<id>`).
+///
+/// Only channels and supergroups have message links.
+pub fn message_link(chat: &Peer, message_id: i32) -> Option<String> {
+    match chat {
+        Peer::Channel(c) => Some(match c.username() {
+            Some(username) => format!("https://t.me/{}/{}", username, message_id),
+            None => format!("https://t.me/c/{}/{}", c.raw.id, message_id),
+        }),
+        _ => None,
     }
 }
 
@@ -219,7 +325,9 @@ impl LinearBot {
 }
 
 /// Run Linear bot (polling version for grammers 0.8).
-pub async fn run_linear_bot(client: Client) -> Result<()> {
+///
+/// A dropped connection is re-established before the next poll.
+pub async fn run_linear_bot(client: &TelegramClient) -> Result<()> {
     let config = LinearBotConfig::from_env();
     let bot = LinearBot::new(config.clone())?;
 
@@ -253,8 +361,13 @@ pub async fn run_linear_bot(client: Client) -> Result<()> {
             _ = tokio::time::sleep(tokio::time::Duration::from_secs(2)) => {
                 // Poll dialogs for new messages with command
                 let mut dialogs = client.iter_dialogs();
+                let mut connection_lost = false;
 
                 while let Some(dialog) = dialogs.next().await.transpose() {
+                    if dialog.as_ref().is_err_and(is_connection_error) {
+                        connection_lost = true;
+                        break;
+                    }
                     if let Ok(dialog) = dialog {
                         let chat = &dialog.peer;
                         let mut messages = client.iter_messages(chat);
@@ -274,15 +387,15 @@ pub async fn run_linear_bot(client: Client) -> Result<()> {
 
                             let text = msg.text();
                             if bot.matches(text).is_some() {
-                                match bot.handle_message(&client, &msg, None).await {
+                                match bot.handle_message(client, &msg, None).await {
                                     Ok(response) => {
-                                        if let Err(e) = msg.reply(response).await {
+                                        if let Err(e) = client.reply(chat, msg_id, response).await {
                                             tracing::error!("Failed to send reply: {}", e);
                                         }
                                     }
                                     Err(e) => {
                                         let error_msg = format!("Ошибка: {}", e);
-                                        if let Err(e) = msg.reply(error_msg).await {
+                                        if let Err(e) = client.reply(chat, msg_id, error_msg).await {
                                             tracing::error!("Failed to send error reply: {}", e);
                                         }
                                     }
@@ -293,6 +406,11 @@ pub async fn run_linear_bot(client: Client) -> Result<()> {
                         }
                     }
                 }
+
+                drop(dialogs);
+                if connection_lost {
+                    client.reconnect().await?;
+                }
             }
         }
     }
@@ -300,6 +418,132 @@ pub async fn run_linear_bot(client: Client) -> Result<()> {
     Ok(())
 }
 
+/// Watch `config.chat` and file a Linear issue for every flagged message.
+pub async fn run(telegram: &mut TelegramClient, config: LinearBotConfig) -> Result<()> {
+    let chat_name = config
+        .chat
+        .clone()
+        .ok_or_else(|| Error::InvalidArgument("LINEAR_WATCH_CHAT не задан".to_string()))?;
+    let chat = find_chat(telegram, &chat_name).await?;
+    let target_peer_id = chat.id();
+
+    let updates_rx = telegram
+        .take_updates()
+        .ok_or_else(|| Error::TelegramError("Канал обновлений Telegram недоступен".to_string()))?;
+    let mut updates = telegram.stream_updates(
+        updates_rx,
+        UpdatesConfiguration {
+            catch_up: false,
+            ..Default::default()
+        },
+    );
+
+    tracing::info!(
+        "Linear bot следит за '{}'. Триггеры: {}",
+        chat_name,
+        config.trigger_keywords.join(", ")
+    );
+
+    // Message ids already filed, so edits don't create duplicates
+    let mut filed: HashSet<i32> = HashSet::new();
+
+    loop {
+        tokio::select! {
+            _ = signal::ctrl_c() => {
+                tracing::info!("Остановка бота...");
+                break;
+            }
+            update = updates.next() => {
+                let msg = match update {
+                    Ok(Update::NewMessage(msg)) | Ok(Update::MessageEdited(msg)) => msg,
+                    Ok(_) => continue,
+                    Err(e) if is_connection_error(&e) => {
+                        // The stream outlives the drop and fetches the missed
+                        // difference on its next poll
+                        telegram.reconnect().await?;
+                        continue;
+                    }
+                    Err(e) => {
+                        tracing::error!("Ошибка при получении обновлений: {}", e);
+                        break;
+                    }
+                };
+                if msg.peer_id() != target_peer_id {
+                    continue;
+                }
+
+                if let Err(e) = file_flagged(telegram, &config, &chat, &msg, &mut filed).await {
+                    tracing::error!("Не удалось создать задачу из сообщения {}: {}", msg.id(), e);
+                }
+            }
+        }
+    }
+
+    updates.sync_update_state();
+    Ok(())
+}
+
+/// File one message as an issue if it is flagged and not filed yet.
+async fn file_flagged(
+    telegram: &TelegramClient,
+    config: &LinearBotConfig,
+    chat: &Peer,
+    msg: &Message,
+    filed: &mut HashSet<i32>,
+) -> Result<()> {
+    let Some(text) = config.flagged_text(msg.text()) else {
+        return Ok(());
+    };
+    if filed.contains(&msg.id()) {
+        return Ok(());
+    }
+    let sender_id = get_sender_id(msg.sender());
+    if !config.allowed_sender_ids.is_empty() && !config.allowed_sender_ids.contains(&sender_id) {
+        return Ok(());
+    }
+    let Some((title, description)) = flagged_issue(&text, message_link(chat, msg.id()).as_deref())
+    else {
+        return Ok(());
+    };
+
+    filed.insert(msg.id());
+    let issue = crate::commands::linear::run(LinearArgs {
+        api_key: None,
+        team: config.team_key.clone(),
+        title,
+        description: Some(description),
+        project: config.project_id.clone(),
+        priority: config.default_priority,
+        assignee: None,
+        labels: vec![],
+        comment: None,
+        attach_files: vec![],
+    })
+    .await;
+
+    let issue = match issue {
+        Ok(issue) => issue,
+        Err(e) => {
+            // Let a later edit retry
+            filed.remove(&msg.id());
+            return Err(e);
+        }
+    };
+
+    let reply = format!(
+        "Создана задача {} {}",
+        issue.identifier.as_deref().unwrap_or(""),
+        issue.url.as_deref().unwrap_or("")
+    );
+    if let Err(e) = telegram
+        .reply(chat, msg.id(), reply.trim().to_string())
+        .await
+    {
+        tracing::error!("Failed to send reply: {}", e);
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -441,6 +685,64 @@ mod tests {
         assert_eq!(bot.matches("prefix !linear hi"), None);
     }
 
+    #[test]
+    fn flagged_text_matches_commands_and_keywords() {
+        let config = LinearBotConfig {
+            trigger_keywords: vec!["/bug".to_string(), "Сломалось".to_string()],
+            ..LinearBotConfig::default()
+        };
+
+        assert_eq!(
+            config.flagged_text("/bug Login fails on Safari"),
+            Some("Login fails on Safari".to_string())
+        );
+        assert_eq!(
+            config.flagged_text("/BUG@team_bot Crash on start"),
+            Some("Crash on start".to_string())
+        );
+        assert_eq!(
+            config.flagged_text("Опять всё сломалось после деплоя"),
+            Some("Опять всё сломалось после деплоя".to_string())
+        );
+        assert_eq!(config.flagged_text("/bugfix merged"), None);
+        assert_eq!(config.flagged_text("see /bug in the docs"), None);
+        assert_eq!(config.flagged_text("all good"), None);
+    }
+
+    #[test]
+    fn flagged_issue_uses_first_line_and_links_back() {
+        let (title, description) = flagged_issue(
+            "\nLogin fails on Safari\nSteps: open /login",
+            Some("https://t.me/team_chat/42"),
+        )
+        .unwrap();
+        assert_eq!(title, "Login fails on Safari");
+        assert_eq!(
+            description,
+            "Login fails on Safari\nSteps: open /login\n\nИсходное сообщение в Telegram: https://t.me/team_chat/42"
+        );
+
+        let (title, _) = flagged_issue(&"x".repeat(200), None).unwrap();
+        assert_eq!(title.chars().count(), MAX_TITLE_CHARS);
+        assert!(title.ends_with('…'));
+
+        assert_eq!(flagged_issue("   ", None), None);
+    }
+
+    #[test]
+    fn config_from_env_reads_watch_settings() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        let _keywords = EnvGuard::set("LINEAR_TRIGGER_KEYWORDS", " /bug, urgent ,");
+        let _chat = EnvGuard::set("LINEAR_WATCH_CHAT", "@team_chat");
+
+        let config = LinearBotConfig::from_env();
+        assert_eq!(config.trigger_keywords, vec!["/bug", "urgent"]);
+        assert_eq!(config.chat.as_deref(), Some("@team_chat"));
+
+        let _keywords = EnvGuard::remove("LINEAR_TRIGGER_KEYWORDS");
+        assert_eq!(LinearBotConfig::from_env().trigger_keywords, vec!["/bug"]);
+    }
+
     #[test]
     fn is_sender_allowed_allows_everyone_when_list_empty() {
         let bot = build_bot(LinearBotConfig::default());
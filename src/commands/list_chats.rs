@@ -1,7 +1,11 @@
 //! List chats command
 //!
 //! Equivalent to Python's list_chats.py
+//!
+//! `--output json` prints [`ChatInfo`] rows instead of the table; `active-chats`
+//! uses the same struct so both commands share one JSON schema.
 
+use std::collections::HashSet;
 use std::fs::{self, File};
 use std::io::ErrorKind;
 use std::io::Write;
@@ -14,6 +18,7 @@ use chrono::{DateTime, Duration as ChronoDuration, Utc};
 use futures::stream::{self, StreamExt};
 use grammers_client::types::peer::Peer;
 use grammers_client::types::Dialog;
+use grammers_tl_types as tl;
 use serde::{Deserialize, Serialize};
 
 const DEFAULT_CACHE_TTL_SECS: u64 = 300; // 5 minutes
@@ -21,33 +26,221 @@ const DEFAULT_PARALLEL_FETCH: usize = 8;
 const DEFAULT_MAX_DIALOGS: usize = 200;
 const DEFAULT_CACHE_PATH: &str = ".cache/list_chats_cache.json";
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct ChatInfo {
-    title: String,
-    id: i64,
-    last_message: DateTime<Utc>,
-    unread: i32,
-    chat_type: String,
+/// Chat metadata shown by `list-chats` and `active-chats`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatInfo {
+    pub id: i64,
+    pub title: String,
+    /// `user`, `group` or `channel`
+    pub chat_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    pub unread: i32,
+    pub last_message: DateTime<Utc>,
+}
+
+/// How chat lists are printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChatsOutput {
+    Table,
+    Json,
+}
+
+impl ChatsOutput {
+    pub fn parse(raw: &str) -> Result<Self> {
+        match raw.to_ascii_lowercase().as_str() {
+            "table" | "pretty" => Ok(Self::Table),
+            "json" => Ok(Self::Json),
+            other => Err(Error::InvalidArgument(format!(
+                "Unsupported output '{}'. Use table|json",
+                other
+            ))),
+        }
+    }
+}
+
+/// Print the first `limit` chats as a table or a JSON array
+pub fn print_chats(chats: &[ChatInfo], limit: usize, output: ChatsOutput) -> Result<()> {
+    let chats = &chats[..limit.min(chats.len())];
+    match output {
+        ChatsOutput::Table => {
+            println!("Наиболее активные чаты:\n");
+            for (i, chat) in chats.iter().enumerate() {
+                println!("{}. {}", i + 1, chat.title);
+                println!(
+                    "   ID: {} | Тип: {} | Непрочитано: {}",
+                    chat.id, chat.chat_type, chat.unread
+                );
+                println!(
+                    "   Последнее сообщение: {}",
+                    chat.last_message.format("%d.%m.%Y %H:%M")
+                );
+                println!();
+            }
+        }
+        ChatsOutput::Json => {
+            let payload = serde_json::to_string_pretty(chats)
+                .map_err(|e| Error::SerializationError(e.to_string()))?;
+            println!("{payload}");
+        }
+    }
+    Ok(())
 }
 
 /// Filter for chat types
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ChatFilter {
     All,
     Users,
     Groups,
     Channels,
+    /// Chats with unread messages
+    Unread,
+    /// Chats in the Telegram folder with this title
+    Folder(String),
 }
 
 impl ChatFilter {
+    /// Parse the `--filter` value: all, users, groups, channels, unread, folder:<name>
+    pub fn parse(raw: &str) -> Self {
+        if let Some(name) = raw.strip_prefix("folder:") {
+            return ChatFilter::Folder(name.trim().to_string());
+        }
+        match raw.to_lowercase().as_str() {
+            "users" | "user" => ChatFilter::Users,
+            "groups" | "group" => ChatFilter::Groups,
+            "channels" | "channel" => ChatFilter::Channels,
+            "unread" => ChatFilter::Unread,
+            _ => ChatFilter::All,
+        }
+    }
+
     fn matches(&self, chat_type: &str) -> bool {
         match self {
-            ChatFilter::All => true,
+            ChatFilter::All | ChatFilter::Unread | ChatFilter::Folder(_) => true,
             ChatFilter::Users => chat_type == "user",
             ChatFilter::Groups => chat_type == "group",
             ChatFilter::Channels => chat_type == "channel",
         }
     }
+
+    fn matches_chat(&self, chat: &ChatInfo) -> bool {
+        match self {
+            ChatFilter::Unread => chat.unread > 0,
+            _ => self.matches(&chat.chat_type),
+        }
+    }
+}
+
+/// Which chats a Telegram folder (dialog filter) contains
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct FolderRule {
+    include_ids: HashSet<i64>,
+    exclude_ids: HashSet<i64>,
+    users: bool,
+    groups: bool,
+    channels: bool,
+    exclude_read: bool,
+}
+
+impl FolderRule {
+    fn from_filter(filter: &tl::enums::DialogFilter) -> Option<(String, Self)> {
+        match filter {
+            tl::enums::DialogFilter::Filter(f) => {
+                let tl::enums::TextWithEntities::Entities(title) = &f.title;
+                Some((
+                    title.text.clone(),
+                    Self {
+                        include_ids: input_peer_ids(f.pinned_peers.iter().chain(&f.include_peers)),
+                        exclude_ids: input_peer_ids(&f.exclude_peers),
+                        users: f.contacts || f.non_contacts,
+                        groups: f.groups,
+                        channels: f.broadcasts,
+                        exclude_read: f.exclude_read,
+                    },
+                ))
+            }
+            tl::enums::DialogFilter::Chatlist(f) => {
+                let tl::enums::TextWithEntities::Entities(title) = &f.title;
+                Some((
+                    title.text.clone(),
+                    Self {
+                        include_ids: input_peer_ids(f.pinned_peers.iter().chain(&f.include_peers)),
+                        ..Default::default()
+                    },
+                ))
+            }
+            // The built-in "All chats" entry has no title
+            tl::enums::DialogFilter::Default => None,
+        }
+    }
+
+    fn matches(&self, chat: &ChatInfo) -> bool {
+        if self.exclude_ids.contains(&chat.id) {
+            return false;
+        }
+        if self.include_ids.contains(&chat.id) {
+            return true;
+        }
+        let by_type = match chat.chat_type.as_str() {
+            "user" => self.users,
+            "group" => self.groups,
+            "channel" => self.channels,
+            _ => false,
+        };
+        by_type && !(self.exclude_read && chat.unread == 0)
+    }
+}
+
+fn input_peer_ids<'a>(peers: impl IntoIterator<Item = &'a tl::enums::InputPeer>) -> HashSet<i64> {
+    peers
+        .into_iter()
+        .filter_map(|peer| match peer {
+            tl::enums::InputPeer::User(p) => Some(p.user_id),
+            tl::enums::InputPeer::Chat(p) => Some(p.chat_id),
+            tl::enums::InputPeer::Channel(p) => Some(p.channel_id),
+            tl::enums::InputPeer::UserFromMessage(p) => Some(p.user_id),
+            tl::enums::InputPeer::ChannelFromMessage(p) => Some(p.channel_id),
+            tl::enums::InputPeer::Empty | tl::enums::InputPeer::PeerSelf => None,
+        })
+        .collect()
+}
+
+/// Pick the folder titled `name` (case-insensitive) from the account's folders
+fn find_folder(folders: Vec<(String, FolderRule)>, name: &str) -> Result<FolderRule> {
+    if folders.is_empty() {
+        return Err(Error::InvalidArgument(format!(
+            "Папка '{}' не найдена: в аккаунте нет папок",
+            name
+        )));
+    }
+    let wanted = name.to_lowercase();
+    let titles: Vec<String> = folders.iter().map(|(title, _)| title.clone()).collect();
+    folders
+        .into_iter()
+        .find(|(title, _)| title.to_lowercase() == wanted)
+        .map(|(_, rule)| rule)
+        .ok_or_else(|| {
+            Error::InvalidArgument(format!(
+                "Папка '{}' не найдена. Доступные папки: {}",
+                name,
+                titles.join(", ")
+            ))
+        })
+}
+
+/// Resolve a folder by title via `messages.getDialogFilters`
+async fn resolve_folder(client: &grammers_client::Client, name: &str) -> Result<FolderRule> {
+    let tl::enums::messages::DialogFilters::Filters(response) = client
+        .invoke(&tl::functions::messages::GetDialogFilters {})
+        .await
+        .map_err(|e| Error::TelegramError(e.to_string()))?;
+    let folders = response
+        .filters
+        .iter()
+        .filter_map(FolderRule::from_filter)
+        .collect();
+    find_folder(folders, name)
 }
 
 #[derive(Debug)]
@@ -89,21 +282,22 @@ struct CachedDialogs {
 struct PendingChat {
     title: String,
     id: i64,
+    username: Option<String>,
     unread: i32,
     chat_type: String,
     peer: Peer,
 }
 
 pub async fn run(limit: usize) -> Result<()> {
-    run_with_filter(limit, ChatFilter::All).await
+    run_with_filter(limit, ChatFilter::All, ChatsOutput::Table).await
 }
 
 /// Run with specific chat type filter
-pub async fn run_with_filter(limit: usize, filter: ChatFilter) -> Result<()> {
+pub async fn run_with_filter(limit: usize, filter: ChatFilter, output: ChatsOutput) -> Result<()> {
     let settings = ListChatsSettings::from_env();
 
     // Acquire session lock
-    let _lock = SessionLock::acquire()?;
+    let _lock = SessionLock::acquire().await?;
 
     // Connect to Telegram
     let client = get_client().await?;
@@ -112,7 +306,7 @@ pub async fn run_with_filter(limit: usize, filter: ChatFilter) -> Result<()> {
         match load_cache(&settings.cache_path, settings.cache_ttl) {
             Ok(Some(cache)) => {
                 let age = Utc::now() - cache.generated_at;
-                println!(
+                eprintln!(
                     "Использую кэш диалогов ({} сек назад, {} чатов)",
                     age.num_seconds(),
                     cache.chats.len()
@@ -141,40 +335,33 @@ pub async fn run_with_filter(limit: usize, filter: ChatFilter) -> Result<()> {
         fresh_dialogs
     };
 
-    chat_activity = filter_chats(chat_activity, filter);
+    chat_activity = filter_chats(chat_activity, &filter);
+    if let ChatFilter::Folder(name) = &filter {
+        let folder = resolve_folder(&client, name).await?;
+        chat_activity.retain(|chat| folder.matches(chat));
+    }
 
     // Sort by last message date (newest first)
     chat_activity.sort_by(|a, b| b.last_message.cmp(&a.last_message));
 
-    println!("Наиболее активные чаты:\n");
+    print_chats(&chat_activity, limit, output)?;
 
-    for (i, chat) in chat_activity.iter().take(limit).enumerate() {
-        println!("{}. {}", i + 1, chat.title);
-        println!(
-            "   ID: {} | Тип: {} | Непрочитано: {}",
-            chat.id, chat.chat_type, chat.unread
-        );
+    write_yaml(&chat_activity)?;
+
+    if output == ChatsOutput::Table {
         println!(
-            "   Последнее сообщение: {}",
-            chat.last_message.format("%d.%m.%Y %H:%M")
+            "\nИнформация сохранена в chats.yml ({} чатов)",
+            chat_activity.len()
         );
-        println!();
     }
 
-    write_yaml(&chat_activity)?;
-
-    println!(
-        "\nИнформация сохранена в chats.yml ({} чатов)",
-        chat_activity.len()
-    );
-
     Ok(())
 }
 
-fn filter_chats(chats: Vec<ChatInfo>, filter: ChatFilter) -> Vec<ChatInfo> {
+fn filter_chats(chats: Vec<ChatInfo>, filter: &ChatFilter) -> Vec<ChatInfo> {
     chats
         .into_iter()
-        .filter(|chat| filter.matches(&chat.chat_type))
+        .filter(|chat| filter.matches_chat(chat))
         .collect()
 }
 
@@ -257,20 +444,23 @@ async fn fetch_dialogs(
 
         let title = chat_title(&peer);
         let id = peer_id(&peer);
+        let username = peer_username(&peer);
         let unread = extract_unread_count(&dialog);
 
         if let Some(last_message) = dialog.last_message.as_ref() {
             chat_activity.push(ChatInfo {
-                title,
                 id,
-                last_message: last_message.date(),
-                unread,
+                title,
                 chat_type: chat_type.to_string(),
+                username,
+                unread,
+                last_message: last_message.date(),
             });
         } else {
             pending.push(PendingChat {
                 title,
                 id,
+                username,
                 unread,
                 chat_type: chat_type.to_string(),
                 peer,
@@ -310,7 +500,7 @@ fn classify_peer(peer: &Peer) -> Option<&'static str> {
     }
 }
 
-fn chat_title(chat: &Peer) -> String {
+pub(crate) fn chat_title(chat: &Peer) -> String {
     match chat {
         Peer::Channel(c) => c.title().to_string(),
         Peer::Group(g) => g.title().unwrap_or("Group").to_string(),
@@ -318,7 +508,17 @@ fn chat_title(chat: &Peer) -> String {
     }
 }
 
-fn peer_id(chat: &Peer) -> i64 {
+/// Public @username of a user, group or channel
+pub(crate) fn peer_username(chat: &Peer) -> Option<String> {
+    match chat {
+        Peer::User(u) => u.username(),
+        Peer::Group(g) => g.username(),
+        Peer::Channel(c) => c.username(),
+    }
+    .map(str::to_string)
+}
+
+pub(crate) fn peer_id(chat: &Peer) -> i64 {
     match chat {
         Peer::Channel(c) => c.raw.id,
         Peer::Group(g) => match &g.raw {
@@ -332,7 +532,7 @@ fn peer_id(chat: &Peer) -> i64 {
     }
 }
 
-fn extract_unread_count(dialog: &Dialog) -> i32 {
+pub(crate) fn extract_unread_count(dialog: &Dialog) -> i32 {
     match &dialog.raw {
         grammers_tl_types::enums::Dialog::Dialog(d) => d.unread_count,
         grammers_tl_types::enums::Dialog::Folder(folder) => {
@@ -354,11 +554,12 @@ async fn fetch_missing_last_messages(
             let mut messages = client.iter_messages(&chat.peer);
             match messages.next().await.transpose() {
                 Some(Ok(msg)) => Some(ChatInfo {
-                    title: chat.title,
                     id: chat.id,
-                    last_message: msg.date(),
-                    unread: chat.unread,
+                    title: chat.title,
                     chat_type: chat.chat_type,
+                    username: chat.username,
+                    unread: chat.unread,
+                    last_message: msg.date(),
                 }),
                 Some(Err(err)) => {
                     eprintln!(
@@ -455,6 +656,7 @@ mod tests {
                 last_message: Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
                 unread: 0,
                 chat_type: "user".to_string(),
+                username: None,
             },
             ChatInfo {
                 title: "Group1".to_string(),
@@ -462,6 +664,7 @@ mod tests {
                 last_message: Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
                 unread: 5,
                 chat_type: "group".to_string(),
+                username: None,
             },
             ChatInfo {
                 title: "Channel1".to_string(),
@@ -469,25 +672,153 @@ mod tests {
                 last_message: Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
                 unread: 10,
                 chat_type: "channel".to_string(),
+                username: None,
             },
         ];
 
-        let users = filter_chats(chats.clone(), ChatFilter::Users);
+        let users = filter_chats(chats.clone(), &ChatFilter::Users);
         assert_eq!(users.len(), 1);
         assert_eq!(users[0].chat_type, "user");
 
-        let groups = filter_chats(chats.clone(), ChatFilter::Groups);
+        let groups = filter_chats(chats.clone(), &ChatFilter::Groups);
         assert_eq!(groups.len(), 1);
         assert_eq!(groups[0].chat_type, "group");
 
-        let channels = filter_chats(chats.clone(), ChatFilter::Channels);
+        let channels = filter_chats(chats.clone(), &ChatFilter::Channels);
         assert_eq!(channels.len(), 1);
         assert_eq!(channels[0].chat_type, "channel");
 
-        let all = filter_chats(chats, ChatFilter::All);
+        let all = filter_chats(chats, &ChatFilter::All);
         assert_eq!(all.len(), 3);
     }
 
+    fn chat(id: i64, chat_type: &str, unread: i32) -> ChatInfo {
+        ChatInfo {
+            id,
+            title: format!("Chat {}", id),
+            chat_type: chat_type.to_string(),
+            username: None,
+            unread,
+            last_message: Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn chat_filter_parses_unread_and_folders() {
+        assert_eq!(ChatFilter::parse("unread"), ChatFilter::Unread);
+        assert_eq!(ChatFilter::parse("Channels"), ChatFilter::Channels);
+        assert_eq!(
+            ChatFilter::parse("folder:Работа"),
+            ChatFilter::Folder("Работа".to_string())
+        );
+        assert_eq!(ChatFilter::parse("whatever"), ChatFilter::All);
+    }
+
+    #[test]
+    fn unread_filter_keeps_chats_with_unread_messages() {
+        let chats = vec![
+            chat(1, "user", 0),
+            chat(2, "group", 4),
+            chat(3, "channel", 1),
+        ];
+        let unread = filter_chats(chats, &ChatFilter::Unread);
+        let ids: Vec<i64> = unread.iter().map(|c| c.id).collect();
+        assert_eq!(ids, vec![2, 3]);
+    }
+
+    #[test]
+    fn folder_rule_combines_peers_and_categories() {
+        let rule = FolderRule {
+            include_ids: HashSet::from([1]),
+            exclude_ids: HashSet::from([3]),
+            groups: true,
+            exclude_read: true,
+            ..Default::default()
+        };
+        assert!(rule.matches(&chat(1, "user", 0))); // explicitly included
+        assert!(rule.matches(&chat(2, "group", 5)));
+        assert!(!rule.matches(&chat(3, "group", 5))); // explicitly excluded
+        assert!(!rule.matches(&chat(4, "group", 0))); // read, excluded by flag
+        assert!(!rule.matches(&chat(5, "channel", 2)));
+    }
+
+    #[test]
+    fn find_folder_by_title_or_explain() {
+        let work = FolderRule {
+            channels: true,
+            ..Default::default()
+        };
+        let folders = vec![
+            ("Работа".to_string(), work.clone()),
+            ("Friends".to_string(), FolderRule::default()),
+        ];
+        assert_eq!(find_folder(folders.clone(), "работа").unwrap(), work);
+
+        let err = find_folder(folders, "News").unwrap_err().to_string();
+        assert!(err.contains("Доступные папки: Работа, Friends"), "{}", err);
+
+        let err = find_folder(Vec::new(), "News").unwrap_err().to_string();
+        assert!(err.contains("в аккаунте нет папок"), "{}", err);
+    }
+
+    #[test]
+    fn chats_output_parses_table_and_json() {
+        assert_eq!(ChatsOutput::parse("table").unwrap(), ChatsOutput::Table);
+        assert_eq!(ChatsOutput::parse("JSON").unwrap(), ChatsOutput::Json);
+        assert!(ChatsOutput::parse("csv").is_err());
+    }
+
+    #[test]
+    fn chat_info_json_schema() {
+        let chat = ChatInfo {
+            id: 42,
+            title: "Rust".to_string(),
+            chat_type: "channel".to_string(),
+            username: Some("rustlang".to_string()),
+            unread: 3,
+            last_message: Utc.with_ymd_and_hms(2024, 12, 15, 10, 30, 0).unwrap(),
+        };
+        assert_eq!(
+            serde_json::to_value(&chat).unwrap(),
+            serde_json::json!({
+                "id": 42,
+                "title": "Rust",
+                "chat_type": "channel",
+                "username": "rustlang",
+                "unread": 3,
+                "last_message": "2024-12-15T10:30:00Z"
+            })
+        );
+
+        let private = ChatInfo {
+            username: None,
+            ..chat
+        };
+        let json = serde_json::to_value(&private).unwrap();
+        assert!(json.get("username").is_none());
+    }
+
+    #[test]
+    fn cache_without_usernames_still_loads() {
+        let temp_file = tempfile::NamedTempFile::new().unwrap();
+        let cache = serde_json::json!({
+            "generated_at": Utc::now(),
+            "chats": [{
+                "title": "Old",
+                "id": 1,
+                "last_message": "2024-01-01T00:00:00Z",
+                "unread": 0,
+                "chat_type": "group"
+            }]
+        });
+        std::fs::write(temp_file.path(), cache.to_string()).unwrap();
+
+        let loaded = load_cache(temp_file.path(), Duration::from_secs(3600))
+            .unwrap()
+            .unwrap();
+        assert_eq!(loaded.chats[0].username, None);
+    }
+
     #[test]
     fn test_parse_env_usize_default() {
         let _lock = ENV_LOCK.lock().unwrap();
@@ -577,6 +908,7 @@ mod tests {
             last_message: Utc.with_ymd_and_hms(2024, 12, 15, 10, 30, 0).unwrap(),
             unread: 5,
             chat_type: "group".to_string(),
+            username: None,
         }];
 
         let temp_file = NamedTempFile::new().unwrap();
@@ -607,6 +939,7 @@ mod tests {
             last_message: Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
             unread: 0,
             chat_type: "user".to_string(),
+            username: None,
         }];
 
         let temp_file = NamedTempFile::new().unwrap();
@@ -665,6 +998,7 @@ mod tests {
             last_message: Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
             unread: 0,
             chat_type: "group".to_string(),
+            username: None,
         }];
 
         save_cache(&cache_path, &chats).unwrap();
@@ -4,6 +4,7 @@
 //! Each module corresponds to a subcommand in the CLI.
 
 pub mod active_chats;
+pub mod anomalies;
 pub mod autoanswer;
 pub mod chat_analyzer;
 pub mod crm;
@@ -12,8 +13,11 @@ pub mod dialogs;
 pub mod digest;
 pub mod download_chat;
 pub mod download_user_chat;
+pub mod duplicates;
+pub mod events;
 pub mod export;
 pub mod export_chats_mysql;
+pub mod graph_query;
 pub mod hunt;
 pub mod index;
 pub mod init_session;
@@ -25,12 +29,14 @@ pub mod list_chats;
 pub mod moderate;
 pub mod monitor;
 pub mod n8n;
+pub mod pin_candidates;
 pub mod react;
 pub mod read;
 pub mod search;
 pub mod send_message;
 pub mod send_viral;
 pub mod tg;
+pub mod validate;
 
 // Re-export commonly used types
 pub use active_chats::run as active_chats_run;
@@ -39,9 +45,9 @@ pub use chat_analyzer::{run as chat_analyzer_run, AnalyzerConfig as ChatAnalyzer
 pub use crm::{parse_chat as crm_parse, CrmConfig};
 pub use dialogs::run as dialogs_run;
 pub use digest::{run as digest_run, DigestConfig};
-pub use hunt::{hunt_users, HuntCriteria};
+pub use hunt::{hunt_users, HuntCriteria, HuntReport};
 pub use like::run as like_run;
-pub use linear::{run as linear_run, LinearArgs};
+pub use linear::{run as linear_run, update as linear_update, LinearArgs, LinearUpdateArgs};
 pub use list_chats::run as list_chats_run;
 pub use moderate::{run as moderate_run, ModerateConfig};
 pub use react::run as react_run;
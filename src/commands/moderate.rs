@@ -2,10 +2,24 @@
 //!
 //! Monitors chat for profanity, spam, and inappropriate content
 
-use crate::error::Result;
+use crate::chat::input_peer;
+use crate::confirm::confirm_chat;
+use crate::dry_run::{ActionKind, DryRunPlan, PlanFormat};
+use crate::error::{Error, Result};
+use crate::export::{write_records, CsvOptions};
+use crate::polling::PollingConfig;
+use crate::scan::ScanStats;
 use crate::session::{get_client, SessionLock};
+use chrono::NaiveDate;
+use grammers_client::types::peer::Peer;
+use grammers_client::types::Message;
+use grammers_client::{Client, InvocationError};
+use grammers_tl_types as tl;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use regex::Regex;
-use std::collections::HashSet;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
 use tokio::signal;
 
 /// Russian profanity patterns (censored for safety)
@@ -25,6 +39,12 @@ const PROFANITY_PATTERNS: &[&str] = &[
 /// Replacement word for profanity
 const REPLACEMENT: &str = "хулиган";
 
+/// Example message ids kept per sender in the breakdown
+const MAX_EXAMPLE_IDS: usize = 5;
+
+/// Most recent days shown in the daily violations table
+const MAX_TABLE_DAYS: usize = 31;
+
 /// Spam/advertising patterns
 const SPAM_PATTERNS: &[&str] = &[
     // Crypto scams
@@ -77,10 +97,23 @@ pub struct ModerateConfig {
     pub replacement: String,
     /// Additional banned words
     pub banned_words: HashSet<String>,
+    /// Custom terms (e.g. from `--wordlist`) flagged as whole words
+    pub extra_words: Vec<String>,
     /// Enable spam detection
     pub detect_spam: bool,
     /// Flag suspicious URLs (don't delete, just log)
     pub flag_suspicious_urls: bool,
+    /// Polling cadence for new messages
+    pub polling: PollingConfig,
+    /// Record deletions and warnings instead of performing them
+    pub dry_run: Option<PlanFormat>,
+    /// Skip the confirmation of the resolved chat
+    pub yes: bool,
+    /// Users whose messages are never warned about or deleted
+    pub exempt_user_ids: Vec<i64>,
+    /// Also exempt the chat's admins (fetched once at startup) and anonymous
+    /// admins posting as the chat
+    pub exempt_admins: bool,
 }
 
 impl Default for ModerateConfig {
@@ -91,8 +124,14 @@ impl Default for ModerateConfig {
             send_warning: true,
             replacement: REPLACEMENT.to_string(),
             banned_words: HashSet::new(),
+            extra_words: Vec::new(),
             detect_spam: true,
             flag_suspicious_urls: true,
+            polling: PollingConfig::default(),
+            dry_run: None,
+            yes: false,
+            exempt_user_ids: Vec::new(),
+            exempt_admins: false,
         }
     }
 }
@@ -235,10 +274,23 @@ pub struct ProfanityFilter {
 
 impl ProfanityFilter {
     pub fn new(replacement: &str) -> Self {
-        let patterns: Vec<Regex> = PROFANITY_PATTERNS
+        Self::with_extra_words(replacement, &[])
+    }
+
+    /// Default patterns plus custom terms, matched case-insensitively as whole
+    /// words (so "ass" doesn't flag "class")
+    pub fn with_extra_words(replacement: &str, extra_words: &[String]) -> Self {
+        let mut patterns: Vec<Regex> = PROFANITY_PATTERNS
             .iter()
             .filter_map(|p| Regex::new(p).ok())
             .collect();
+        patterns.extend(
+            extra_words
+                .iter()
+                .map(|w| w.trim())
+                .filter(|w| !w.is_empty())
+                .filter_map(|w| Regex::new(&word_pattern(w)).ok()),
+        );
 
         Self {
             patterns,
@@ -272,18 +324,158 @@ impl ProfanityFilter {
     }
 }
 
+/// Case-insensitive pattern for a literal term, bounded on the sides that
+/// start or end with a word character (`\b` never matches next to `c++`'s pluses)
+fn word_pattern(word: &str) -> String {
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+    let start = if is_word(word.chars().next()) {
+        r"\b"
+    } else {
+        ""
+    };
+    let end = if is_word(word.chars().last()) {
+        r"\b"
+    } else {
+        ""
+    };
+    format!("(?i){}{}{}", start, regex::escape(word), end)
+}
+
+/// Terms from a wordlist: one per line, blank lines and `#` comments ignored
+pub fn parse_wordlist(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect()
+}
+
+/// Load a `--wordlist` file
+pub fn load_wordlist(path: &Path) -> Result<Vec<String>> {
+    let content = std::fs::read_to_string(path).map_err(|e| {
+        Error::InvalidArgument(format!("Cannot read wordlist {}: {}", path.display(), e))
+    })?;
+    Ok(parse_wordlist(&content))
+}
+
+/// Admins and the creator among a basic group's participants
+fn chat_admin_ids(participants: &[tl::enums::ChatParticipant]) -> Vec<i64> {
+    participants
+        .iter()
+        .filter_map(|p| match p {
+            tl::enums::ChatParticipant::Creator(c) => Some(c.user_id),
+            tl::enums::ChatParticipant::Admin(a) => Some(a.user_id),
+            tl::enums::ChatParticipant::Participant(_) => None,
+        })
+        .collect()
+}
+
+/// Ids of the chat's admins; needs the right to view the member list
+async fn fetch_admin_ids(client: &Client, chat: &Peer) -> Result<Vec<i64>> {
+    match input_peer(chat) {
+        tl::enums::InputPeer::Channel(channel) => {
+            let request = tl::functions::channels::GetParticipants {
+                channel: tl::enums::InputChannel::Channel(tl::types::InputChannel {
+                    channel_id: channel.channel_id,
+                    access_hash: channel.access_hash,
+                }),
+                filter: tl::enums::ChannelParticipantsFilter::ChannelParticipantsAdmins,
+                offset: 0,
+                limit: 200,
+                hash: 0,
+            };
+            match client.invoke(&request).await? {
+                tl::enums::channels::ChannelParticipants::Participants(page) => Ok(page
+                    .participants
+                    .iter()
+                    .filter_map(|p| match p {
+                        tl::enums::ChannelParticipant::Creator(c) => Some(c.user_id),
+                        tl::enums::ChannelParticipant::Admin(a) => Some(a.user_id),
+                        _ => None,
+                    })
+                    .collect()),
+                tl::enums::channels::ChannelParticipants::NotModified => Ok(Vec::new()),
+            }
+        }
+        tl::enums::InputPeer::Chat(group) => {
+            let request = tl::functions::messages::GetFullChat {
+                chat_id: group.chat_id,
+            };
+            let tl::enums::messages::ChatFull::Full(full) = client.invoke(&request).await?;
+            match full.full_chat {
+                tl::enums::ChatFull::Full(chat_full) => match chat_full.participants {
+                    tl::enums::ChatParticipants::Participants(p) => {
+                        Ok(chat_admin_ids(&p.participants))
+                    }
+                    tl::enums::ChatParticipants::Forbidden(_) => Err(Error::TelegramError(
+                        "member list is not available".to_string(),
+                    )),
+                },
+                tl::enums::ChatFull::ChannelFull(_) => Ok(Vec::new()),
+            }
+        }
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// Telegram user id of a message's sender (`None` for channels and anonymous admins)
+fn sender_user_id(msg: &grammers_client::types::Message) -> Option<i64> {
+    match msg.sender()? {
+        Peer::User(u) => Some(u.raw.id()),
+        _ => None,
+    }
+}
+
+/// Whether the message is posted as the moderated chat itself, i.e. by an anonymous admin
+fn sent_as_chat(msg: &grammers_client::types::Message, chat: &Peer) -> bool {
+    match (msg.sender(), chat) {
+        (Some(Peer::Channel(sender)), Peer::Channel(chat)) => sender.raw.id == chat.raw.id,
+        _ => false,
+    }
+}
+
 /// Run moderation bot
 pub async fn run(chat_name: &str, config: ModerateConfig) -> Result<()> {
-    let _lock = SessionLock::acquire()?;
+    let _lock = SessionLock::acquire().await?;
     let client = get_client().await?;
 
-    let filter = ProfanityFilter::new(&config.replacement);
+    let filter = ProfanityFilter::with_extra_words(&config.replacement, &config.extra_words);
 
     println!("🛡️ Модератор запущен для чата '{}'", chat_name);
+    if !config.extra_words.is_empty() {
+        println!(
+            "📝 Дополнительных запрещённых слов: {}",
+            config.extra_words.len()
+        );
+    }
+    if config.dry_run.is_some() {
+        println!("🧪 Пробный режим: сообщения не удаляются, предупреждения не отправляются.");
+    }
     println!("Нажмите Ctrl+C для остановки.");
 
     let chat = crate::chat::find_chat(&client, chat_name).await?;
+    if config.dry_run.is_none() && (config.delete_profanity || config.send_warning) {
+        confirm_chat(&chat, "moderate messages", config.yes)?;
+    }
+
+    let mut exempt: HashSet<i64> = config.exempt_user_ids.iter().copied().collect();
+    if config.exempt_admins {
+        match fetch_admin_ids(&client, &chat).await {
+            Ok(admins) => {
+                println!("👮 Админы не модерируются: {}", admins.len());
+                exempt.extend(admins);
+            }
+            Err(e) => eprintln!(
+                "⚠️ Не удалось получить список админов ({}), модерирую всех",
+                e
+            ),
+        }
+    }
+
+    let mut plan = DryRunPlan::new("moderate");
     let mut last_seen_id: Option<i32> = None;
+    let mut interval = config.polling.base;
 
     loop {
         tokio::select! {
@@ -291,10 +483,25 @@ pub async fn run(chat_name: &str, config: ModerateConfig) -> Result<()> {
                 println!("\n🛑 Останавливаю модератора...");
                 break;
             }
-            _ = tokio::time::sleep(tokio::time::Duration::from_secs(1)) => {
-                let mut messages = client.iter_messages(&chat);
+            _ = tokio::time::sleep(interval) => {
+                // Dropped connections are re-established; giving up stops the bot
+                let fetched = client.with_reconnect(|client| fetch_latest(client, &chat)).await;
+                let latest = match fetched {
+                    Ok(latest) => latest,
+                    Err(e @ Error::ConnectionError(_)) => return Err(e),
+                    Err(e) => {
+                        eprintln!("⚠️ Не удалось получить сообщения: {}", e);
+                        None
+                    }
+                };
+
+                let has_new = matches!(
+                    &latest,
+                    Some(msg) if last_seen_id.is_none_or(|last_id| msg.id() > last_id)
+                );
+                interval = config.polling.next_interval(interval, has_new);
 
-                if let Some(Ok(msg)) = messages.next().await.transpose() {
+                if let Some(msg) = latest {
                     let msg_id = msg.id();
 
                     // Skip already seen messages
@@ -305,8 +512,11 @@ pub async fn run(chat_name: &str, config: ModerateConfig) -> Result<()> {
                     }
                     last_seen_id = Some(msg_id);
 
-                    // Skip outgoing messages
-                    if msg.outgoing() {
+                    // Skip outgoing messages and exempt users
+                    if msg.outgoing()
+                        || sender_user_id(&msg).is_some_and(|id| exempt.contains(&id))
+                        || (config.exempt_admins && sent_as_chat(&msg, &chat))
+                    {
                         continue;
                     }
 
@@ -332,19 +542,42 @@ pub async fn run(chat_name: &str, config: ModerateConfig) -> Result<()> {
                         let found = filter.find_profanity(text);
                         println!("⚠️ Обнаружен мат от {}: {:?}", sender, found);
 
-                        if config.delete_profanity {
-                            // Note: Deleting messages requires admin rights
-                            println!("🗑️ Удаление сообщения (требуются права админа)");
-                        }
-
-                        if config.send_warning {
+                        // Warn first: the reply needs the message to still exist
+                        if config.send_warning && config.dry_run.is_some() {
+                            plan.record(
+                                ActionKind::Reply,
+                                chat_name,
+                                Some(msg_id),
+                                format!("warning to {}", sender),
+                            );
+                        } else if config.send_warning {
                             let censored = filter.censor(text);
                             let warning = format!(
                                 "⚠️ {}, пожалуйста, общайтесь культурно!\n\nВаше сообщение:\n{}",
                                 sender, censored
                             );
-                            if let Err(e) = msg.reply(warning).await {
-                                eprintln!("Ошибка отправки предупреждения: {}", e);
+                            match client.reply(&chat, msg_id, warning).await {
+                                Ok(_) => {}
+                                Err(e @ Error::ConnectionError(_)) => return Err(e),
+                                Err(e) => eprintln!("Ошибка отправки предупреждения: {}", e),
+                            }
+                        }
+
+                        if config.delete_profanity {
+                            if config.dry_run.is_some() {
+                                plan.record(
+                                    ActionKind::Delete,
+                                    chat_name,
+                                    Some(msg_id),
+                                    found.join(", "),
+                                );
+                            } else {
+                                // Deleting other members' messages requires admin rights
+                                match client.delete_messages(&chat, &[msg_id]).await {
+                                    Ok(_) => println!("🗑️ Сообщение удалено"),
+                                    Err(e @ Error::ConnectionError(_)) => return Err(e),
+                                    Err(e) => eprintln!("Ошибка удаления сообщения: {}", e),
+                                }
                             }
                         }
                     }
@@ -353,67 +586,246 @@ pub async fn run(chat_name: &str, config: ModerateConfig) -> Result<()> {
         }
     }
 
+    if let Some(format) = config.dry_run {
+        plan.print(format)?;
+    }
+
     Ok(())
 }
 
+/// Newest message of `chat`, if any
+async fn fetch_latest(
+    client: Client,
+    chat: &Peer,
+) -> std::result::Result<Option<Message>, InvocationError> {
+    client.iter_messages(chat).next().await
+}
+
+/// Which messages `profanity-stats` analyzes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatsMode {
+    /// The `limit` most recent messages
+    Recent { limit: usize },
+    /// A uniform random sample of `size` messages from the whole history
+    Sample { size: usize, seed: u64 },
+}
+
+/// Uniform sample of `size` items from `items` (reservoir sampling, algorithm R).
+///
+/// Returns the sample and the number of items seen. The same seed and input
+/// always give the same sample; if there are no more than `size` items, all of
+/// them are returned in order.
+pub fn reservoir_sample<T, I>(items: I, size: usize, seed: u64) -> (Vec<T>, usize)
+where
+    I: IntoIterator<Item = T>,
+{
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut sample = Vec::with_capacity(size.min(1024));
+    let mut seen = 0;
+
+    for item in items {
+        if sample.len() < size {
+            sample.push(item);
+        } else if size > 0 {
+            let slot = rng.gen_range(0..=seen);
+            if slot < size {
+                sample[slot] = item;
+            }
+        }
+        seen += 1;
+    }
+
+    (sample, seen)
+}
+
+fn sender_name(msg: &grammers_client::types::Message) -> String {
+    match msg.sender() {
+        Some(grammers_client::types::Peer::User(u)) => u
+            .username()
+            .map(|s| format!("@{}", s))
+            .unwrap_or_else(|| u.full_name()),
+        _ => "Unknown".to_string(),
+    }
+}
+
 /// Analyze chat for profanity statistics
-pub async fn analyze(chat_name: &str, limit: usize) -> Result<ProfanityStats> {
-    let _lock = SessionLock::acquire()?;
+pub async fn analyze(chat_name: &str, mode: StatsMode) -> Result<ProfanityStats> {
+    let _lock = SessionLock::acquire().await?;
     let client = get_client().await?;
 
     let filter = ProfanityFilter::new(REPLACEMENT);
     let chat = crate::chat::find_chat(&client, chat_name).await?;
 
-    let mut stats = ProfanityStats::default();
-    let mut messages = client.iter_messages(&chat);
-    let mut count = 0;
-
-    while let Some(msg_result) = messages.next().await.transpose() {
-        if count >= limit {
-            break;
+    let limit = match mode {
+        StatsMode::Recent { limit } => Some(limit),
+        StatsMode::Sample { .. } => None,
+    };
+    let options = crate::chat::FetchOptions {
+        limit,
+        ..Default::default()
+    };
+    let messages = crate::chat::fetch_messages(&client, &chat, options).await?;
+    let scan = ScanStats::from_dates(messages.iter().map(|msg| msg.date()));
+    let records = messages.iter().map(|msg| {
+        (
+            msg.id(),
+            sender_name(msg),
+            msg.text().trim().to_string(),
+            msg.date().date_naive(),
+        )
+    });
+
+    let mut stats = ProfanityStats {
+        scan,
+        ..Default::default()
+    };
+    let records: Vec<(i32, String, String, NaiveDate)> = match mode {
+        StatsMode::Recent { .. } => records.collect(),
+        StatsMode::Sample { size, seed } => {
+            let (sample, seen) = reservoir_sample(records, size, seed);
+            stats.sampled = true;
+            stats.seed = Some(seed);
+            stats.population = seen;
+            sample
         }
-        count += 1;
-
-        if let Ok(msg) = msg_result {
-            let text = msg.text().trim();
-            if filter.contains_profanity(text) {
-                stats.messages_with_profanity += 1;
-
-                let sender = if let Some(sender) = msg.sender() {
-                    match sender {
-                        grammers_client::types::Peer::User(u) => u
-                            .username()
-                            .map(|s| format!("@{}", s))
-                            .unwrap_or_else(|| u.full_name()),
-                        _ => "Unknown".to_string(),
-                    }
-                } else {
-                    "Unknown".to_string()
-                };
-
-                *stats.offenders.entry(sender).or_insert(0) += 1;
+    };
+    if !stats.sampled {
+        stats.population = records.len();
+    }
 
-                for word in filter.find_profanity(text) {
-                    *stats.word_frequency.entry(word.to_lowercase()).or_insert(0) += 1;
-                }
-            }
-        }
-        stats.total_messages += 1;
+    for (id, sender, text, day) in &records {
+        stats.record_on(&filter, *id, sender, text, *day);
     }
 
     Ok(stats)
 }
 
 /// Profanity statistics
-#[derive(Default)]
+#[derive(Debug, Default, Serialize)]
 pub struct ProfanityStats {
+    /// Messages analyzed (the effective sample size when sampled)
     pub total_messages: usize,
     pub messages_with_profanity: usize,
     pub offenders: std::collections::HashMap<String, usize>,
     pub word_frequency: std::collections::HashMap<String, usize>,
+    /// Whether the messages are a random sample of the history
+    pub sampled: bool,
+    /// Messages scanned to draw the sample (equals `total_messages` when not sampled)
+    pub population: usize,
+    /// Seed that reproduces the sample
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// Breakdown by sender, including senders without violations
+    pub per_sender: BTreeMap<String, SenderStats>,
+    /// Messages with profanity per day (UTC); days without violations are absent
+    pub by_day: BTreeMap<NaiveDate, u32>,
+    /// Messages fetched from the chat
+    #[serde(skip)]
+    pub scan: ScanStats,
+}
+
+/// Per-sender counts for targeted moderation
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct SenderStats {
+    /// Messages analyzed from this sender
+    pub total: usize,
+    /// Messages with profanity
+    pub profane: usize,
+    /// Share of profane messages, %
+    pub rate: f64,
+    /// First few profane message ids, as evidence (at most `MAX_EXAMPLE_IDS`)
+    pub example_ids: Vec<i32>,
+}
+
+impl SenderStats {
+    fn record(&mut self, id: i32, profane: bool) {
+        self.total += 1;
+        if profane {
+            self.profane += 1;
+            // Service messages and placeholders have no linkable id
+            if id > 0 && self.example_ids.len() < MAX_EXAMPLE_IDS && !self.example_ids.contains(&id)
+            {
+                self.example_ids.push(id);
+            }
+        }
+        self.rate = self.profane as f64 / self.total as f64 * 100.0;
+    }
+}
+
+/// Sender name as a single clean line (names may contain newlines or control characters)
+fn sanitize_sender(sender: &str) -> String {
+    let name = sender
+        .split(|c: char| c.is_whitespace() || c.is_control())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    if name.is_empty() {
+        "Unknown".to_string()
+    } else {
+        name
+    }
 }
 
 impl ProfanityStats {
+    /// Count one analyzed message
+    pub fn record(&mut self, filter: &ProfanityFilter, id: i32, sender: &str, text: &str) {
+        let sender = sanitize_sender(sender);
+        let profane = filter.contains_profanity(text);
+        self.total_messages += 1;
+        self.per_sender
+            .entry(sender.clone())
+            .or_default()
+            .record(id, profane);
+        if !profane {
+            return;
+        }
+
+        self.messages_with_profanity += 1;
+        *self.offenders.entry(sender).or_insert(0) += 1;
+        for word in filter.find_profanity(text) {
+            *self.word_frequency.entry(word.to_lowercase()).or_insert(0) += 1;
+        }
+    }
+
+    /// Count one analyzed message posted on `day`
+    pub fn record_on(
+        &mut self,
+        filter: &ProfanityFilter,
+        id: i32,
+        sender: &str,
+        text: &str,
+        day: NaiveDate,
+    ) {
+        let before = self.messages_with_profanity;
+        self.record(filter, id, sender, text);
+        if self.messages_with_profanity > before {
+            *self.by_day.entry(day).or_insert(0) += 1;
+        }
+    }
+
+    /// Daily violations as a text table with bars, the last `MAX_TABLE_DAYS`
+    /// days up to the latest violation (quiet days in between show as 0)
+    pub fn daily_table(&self) -> String {
+        let (Some(first), Some(last)) = (
+            self.by_day.keys().next().copied(),
+            self.by_day.keys().next_back().copied(),
+        ) else {
+            return String::new();
+        };
+        let max = self.by_day.values().copied().max().unwrap_or(0).max(1);
+
+        let days: Vec<NaiveDate> = first.iter_days().take_while(|d| *d <= last).collect();
+        let shown = &days[days.len().saturating_sub(MAX_TABLE_DAYS)..];
+
+        let mut table = String::new();
+        for day in shown {
+            let count = self.by_day.get(day).copied().unwrap_or(0);
+            let bar = "█".repeat((count as usize * 20).div_ceil(max as usize));
+            table.push_str(&format!("  {}  {:>4}  {}\n", day, count, bar));
+        }
+        table
+    }
+
     pub fn profanity_rate(&self) -> f64 {
         if self.total_messages == 0 {
             return 0.0;
@@ -426,6 +838,56 @@ impl ProfanityStats {
         offenders.sort_by(|a, b| b.1.cmp(a.1));
         offenders.into_iter().take(n).collect()
     }
+
+    /// Senders ordered by violations, then by rate
+    fn ranked_senders(&self) -> Vec<(&String, &SenderStats)> {
+        let mut senders: Vec<_> = self.per_sender.iter().collect();
+        senders.sort_by(|a, b| {
+            b.1.profane
+                .cmp(&a.1.profane)
+                .then(b.1.rate.total_cmp(&a.1.rate))
+                .then(a.0.cmp(b.0))
+        });
+        senders
+    }
+
+    /// Per-sender breakdown as CSV, worst offenders first
+    pub fn per_sender_csv(&self, options: &CsvOptions) -> Result<String> {
+        let rows = self.ranked_senders().into_iter().map(|(sender, stats)| {
+            [
+                sender.clone(),
+                stats.total.to_string(),
+                stats.profane.to_string(),
+                format!("{:.1}", stats.rate),
+                stats
+                    .example_ids
+                    .iter()
+                    .map(|id| id.to_string())
+                    .collect::<Vec<_>>()
+                    .join("; "),
+            ]
+        });
+
+        write_records(
+            &["sender", "total", "profane", "rate", "example_ids"],
+            rows,
+            options,
+        )
+    }
+
+    /// Write the per-sender breakdown to `path`: CSV for `.csv`, JSON otherwise
+    pub fn export_per_sender(&self, path: &Path, options: &CsvOptions) -> Result<()> {
+        let is_csv = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+        let content = if is_csv {
+            self.per_sender_csv(options)?
+        } else {
+            serde_json::to_string_pretty(&self.per_sender)?
+        };
+        std::fs::write(path, content)?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -459,6 +921,52 @@ mod tests {
         assert_eq!(found.len(), 2);
     }
 
+    #[test]
+    fn test_wordlist_terms_match_whole_words() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("wordlist.txt");
+        std::fs::write(
+            &path,
+            "# community bans\n\nass\n  Scammer  \n# c++ is fine\nc++\n",
+        )
+        .unwrap();
+
+        let words = load_wordlist(&path).unwrap();
+        assert_eq!(words, vec!["ass", "Scammer", "c++"]);
+
+        let filter = ProfanityFilter::with_extra_words("***", &words);
+        assert!(filter.contains_profanity("what an ass"));
+        assert!(filter.contains_profanity("ASS!"));
+        assert!(filter.contains_profanity("he is a scammer"));
+        assert!(!filter.contains_profanity("first class seats"));
+        assert!(!filter.contains_profanity("scammers")); // whole word only
+        assert!(filter.contains_profanity("I write c++ daily"));
+        assert_eq!(filter.censor("a scammer here"), "a *** here");
+
+        // The default patterns still apply
+        assert!(filter.contains_profanity("блядь"));
+        assert!(load_wordlist(&dir.path().join("missing.txt")).is_err());
+    }
+
+    #[test]
+    fn test_chat_admin_ids_keeps_creator_and_admins() {
+        let participants = vec![
+            tl::enums::ChatParticipant::Participant(tl::types::ChatParticipant {
+                user_id: 1,
+                inviter_id: 2,
+                date: 0,
+            }),
+            tl::enums::ChatParticipant::Creator(tl::types::ChatParticipantCreator { user_id: 2 }),
+            tl::enums::ChatParticipant::Admin(tl::types::ChatParticipantAdmin {
+                user_id: 3,
+                inviter_id: 2,
+                date: 0,
+            }),
+        ];
+        assert_eq!(chat_admin_ids(&participants), vec![2, 3]);
+        assert!(chat_admin_ids(&[]).is_empty());
+    }
+
     #[test]
     fn test_case_insensitive() {
         let filter = ProfanityFilter::new("***");
@@ -559,4 +1067,145 @@ mod tests {
             filter.find_spam_patterns("Bybit - лучшая криптобиржа! Получите бонус до 1000 USDT");
         assert!(!patterns.is_empty());
     }
+
+    #[test]
+    fn test_reservoir_sample_is_deterministic() {
+        let (first, seen) = reservoir_sample(0..1000, 10, 42);
+        let (second, _) = reservoir_sample(0..1000, 10, 42);
+        let (other, _) = reservoir_sample(0..1000, 10, 7);
+
+        assert_eq!(seen, 1000);
+        assert_eq!(first.len(), 10);
+        assert_eq!(first, second);
+        assert_ne!(first, other);
+
+        let unique: HashSet<_> = first.iter().collect();
+        assert_eq!(unique.len(), 10);
+        assert!(first.iter().all(|&i| i < 1000));
+    }
+
+    #[test]
+    fn test_reservoir_sample_small_population() {
+        assert_eq!(reservoir_sample(0..5, 10, 1), ((0..5).collect(), 5));
+        assert_eq!(reservoir_sample(0..5, 0, 1), (Vec::new(), 5));
+    }
+
+    #[test]
+    fn test_reservoir_sample_is_uniform() {
+        // Every item should land in a 2-of-10 sample about 20% of the time
+        let mut hits = [0usize; 10];
+        for seed in 0..2000 {
+            for i in reservoir_sample(0..10, 2, seed).0 {
+                hits[i] += 1;
+            }
+        }
+        for count in hits {
+            assert!((300..500).contains(&count), "{:?}", hits);
+        }
+    }
+
+    #[test]
+    fn test_stats_record() {
+        let filter = ProfanityFilter::new("***");
+        let mut stats = ProfanityStats::default();
+        stats.record(&filter, 1, "@alice", "Блядь, опять");
+        stats.record(&filter, 2, "@bob", "Привет");
+
+        assert_eq!(stats.total_messages, 2);
+        assert_eq!(stats.messages_with_profanity, 1);
+        assert_eq!(stats.offenders["@alice"], 1);
+        assert_eq!(stats.word_frequency["блядь"], 1);
+        assert_eq!(stats.profanity_rate(), 50.0);
+    }
+
+    #[test]
+    fn test_stats_by_day() {
+        let filter = ProfanityFilter::new("***");
+        let mut stats = ProfanityStats::default();
+        let day = |d| NaiveDate::from_ymd_opt(2024, 5, d).unwrap();
+
+        stats.record_on(&filter, 1, "@alice", "Блядь", day(1));
+        stats.record_on(&filter, 2, "@bob", "Привет", day(1));
+        stats.record_on(&filter, 3, "@alice", "Сука", day(3));
+        stats.record_on(&filter, 4, "@troll", "Хуйня", day(3));
+
+        assert_eq!(stats.by_day.len(), 2);
+        assert_eq!(stats.by_day[&day(1)], 1);
+        assert_eq!(stats.by_day[&day(3)], 2);
+
+        let table = stats.daily_table();
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("  2024-05-01     1  "), "{}", lines[0]);
+        assert_eq!(lines[1].trim_end(), "  2024-05-02     0");
+        assert!(lines[2].ends_with(&"█".repeat(20)));
+        assert_eq!(ProfanityStats::default().daily_table(), "");
+
+        let json = serde_json::to_value(&stats).unwrap();
+        assert_eq!(json["by_day"]["2024-05-03"], 2);
+    }
+
+    fn synthetic_stats() -> ProfanityStats {
+        let filter = ProfanityFilter::new("***");
+        let mut stats = ProfanityStats::default();
+        let messages = [
+            (1, "@alice", "Блядь, опять сломалось"),
+            (2, "@alice", "Всё, починила"),
+            (3, "@bob", "Привет всем"),
+            (4, "@alice", "Сука, снова"),
+            (5, "Иван\nПетров", "Хуйня какая-то"),
+            (6, "@bob", "Добрый день"),
+        ];
+        for (id, sender, text) in messages {
+            stats.record(&filter, id, sender, text);
+        }
+        stats
+    }
+
+    #[test]
+    fn test_per_sender_breakdown() {
+        let stats = synthetic_stats();
+
+        let alice = &stats.per_sender["@alice"];
+        assert_eq!((alice.total, alice.profane), (3, 2));
+        assert!((alice.rate - 66.666).abs() < 0.01);
+        assert_eq!(alice.example_ids, vec![1, 4]);
+
+        let bob = &stats.per_sender["@bob"];
+        assert_eq!((bob.total, bob.profane, bob.rate), (2, 0, 0.0));
+        assert!(bob.example_ids.is_empty());
+
+        // Control characters in names don't leak into keys
+        assert_eq!(stats.per_sender["Иван Петров"].rate, 100.0);
+        assert_eq!(stats.offenders["Иван Петров"], 1);
+    }
+
+    #[test]
+    fn test_per_sender_example_ids_are_capped() {
+        let filter = ProfanityFilter::new("***");
+        let mut stats = ProfanityStats::default();
+        for id in [0, -3, 7, 7, 8, 9, 10, 11, 12, 13] {
+            stats.record(&filter, id, "@troll", "блядь");
+        }
+
+        let troll = &stats.per_sender["@troll"];
+        assert_eq!(troll.profane, 10);
+        assert_eq!(troll.example_ids, vec![7, 8, 9, 10, 11]);
+    }
+
+    #[test]
+    fn test_per_sender_csv() {
+        let stats = synthetic_stats();
+        let options = CsvOptions {
+            bom: false,
+            ..Default::default()
+        };
+        let csv = stats.per_sender_csv(&options).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(lines[0], "sender,total,profane,rate,example_ids");
+        assert_eq!(lines[1], "@alice,3,2,66.7,1; 4");
+        assert_eq!(lines[2], "Иван Петров,1,1,100.0,5");
+        assert_eq!(lines[3], "@bob,2,0,0.0,");
+    }
 }
@@ -0,0 +1,409 @@
+//! Reaction-driven pin candidates
+//!
+//! Ranks recent messages by engagement (reactions weighted by sentiment,
+//! replies and views) so community managers can pick what to pin, and
+//! optionally pins the top candidates.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+use grammers_client::types::peer::Peer;
+use grammers_client::Client;
+use grammers_tl_types as tl;
+use tracing::warn;
+
+use crate::chat::{fetch_messages, find_chat, input_peer, FetchOptions};
+use crate::confirm::confirm_chat;
+use crate::error::{Error, Result};
+use crate::reactions::{reaction_breakdown, reaction_sentiment, ReactionSentiment};
+use crate::session::{get_client, SessionLock};
+
+/// Characters of message text shown in the report
+const PREVIEW_CHARS: usize = 80;
+
+/// Pin candidates configuration
+#[derive(Debug, Clone)]
+pub struct PinCandidatesConfig {
+    /// Days of history to scan
+    pub days: i64,
+    /// Number of candidates to report
+    pub top: usize,
+    /// Pin the reported candidates
+    pub pin: bool,
+    /// Preview pinning without changing the chat
+    pub dry_run: bool,
+    /// Skip the confirmation of the resolved chat before pinning
+    pub yes: bool,
+    pub weights: ScoreWeights,
+}
+
+impl Default for PinCandidatesConfig {
+    fn default() -> Self {
+        Self {
+            days: 7,
+            top: 5,
+            pin: false,
+            dry_run: false,
+            yes: false,
+            weights: ScoreWeights::default(),
+        }
+    }
+}
+
+/// Weights of the engagement score
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScoreWeights {
+    pub positive_reaction: f64,
+    pub neutral_reaction: f64,
+    pub negative_reaction: f64,
+    pub reply: f64,
+    /// Weight per 1000 views
+    pub views_per_thousand: f64,
+}
+
+impl Default for ScoreWeights {
+    fn default() -> Self {
+        Self {
+            positive_reaction: 2.0,
+            neutral_reaction: 1.0,
+            negative_reaction: -1.0,
+            reply: 3.0,
+            views_per_thousand: 1.0,
+        }
+    }
+}
+
+/// Engagement fields of a single message
+#[derive(Debug, Clone, PartialEq)]
+pub struct Engagement {
+    pub message_id: i32,
+    pub date: DateTime<Utc>,
+    pub preview: String,
+    /// Reaction label → count
+    pub reactions: Vec<(String, i32)>,
+    pub replies: i32,
+    pub views: i32,
+}
+
+/// Ranked message with its score
+#[derive(Debug, Clone, PartialEq)]
+pub struct PinCandidate {
+    pub engagement: Engagement,
+    pub score: f64,
+}
+
+impl ScoreWeights {
+    /// Combined engagement score of a message
+    pub fn score(&self, engagement: &Engagement) -> f64 {
+        let reactions: f64 = engagement
+            .reactions
+            .iter()
+            .map(|(label, count)| {
+                let weight = match reaction_sentiment(label) {
+                    ReactionSentiment::Positive => self.positive_reaction,
+                    ReactionSentiment::Neutral => self.neutral_reaction,
+                    ReactionSentiment::Negative => self.negative_reaction,
+                };
+                weight * f64::from(*count)
+            })
+            .sum();
+
+        reactions
+            + self.reply * f64::from(engagement.replies)
+            + self.views_per_thousand * f64::from(engagement.views) / 1000.0
+    }
+}
+
+/// Rank messages by score, highest first (newer messages win ties).
+///
+/// Messages without positive score are never suggested.
+pub fn rank_candidates(
+    messages: Vec<Engagement>,
+    weights: &ScoreWeights,
+    top: usize,
+) -> Vec<PinCandidate> {
+    let mut ranked: Vec<PinCandidate> = messages
+        .into_iter()
+        .map(|engagement| PinCandidate {
+            score: weights.score(&engagement),
+            engagement,
+        })
+        .filter(|candidate| candidate.score > 0.0)
+        .collect();
+
+    ranked.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then(b.engagement.message_id.cmp(&a.engagement.message_id))
+    });
+    ranked.truncate(top);
+    ranked
+}
+
+/// Public link to a message: `t.me/<username>/<id>` or `t.me/c/<channel>/<id>`.
+///
+/// Basic groups and private chats have no permalinks.
+pub fn permalink(
+    username: Option<&str>,
+    channel_id: Option<i64>,
+    message_id: i32,
+) -> Option<String> {
+    match (username, channel_id) {
+        (Some(username), _) => Some(format!("https://t.me/{}/{}", username, message_id)),
+        (None, Some(channel_id)) => Some(format!("https://t.me/c/{}/{}", channel_id, message_id)),
+        (None, None) => None,
+    }
+}
+
+/// Main entry point for the CLI.
+pub async fn run(chat_name: &str, config: PinCandidatesConfig) -> Result<Vec<PinCandidate>> {
+    let _lock = SessionLock::acquire().await?;
+    let client = get_client().await?;
+    let chat = find_chat(&client, chat_name).await?;
+
+    // Fail before scanning history if we couldn't pin anyway
+    if config.pin && !config.dry_run && !can_pin(&chat) {
+        return Err(Error::InvalidArgument(format!(
+            "Pinning in '{}' requires admin rights with the pin permission",
+            chat_name
+        )));
+    }
+
+    let options = FetchOptions {
+        since: Some(Utc::now() - Duration::days(config.days.max(1))),
+        ..Default::default()
+    };
+    let messages = fetch_messages(&client, &chat, options).await?;
+
+    // Replies inside the scanned window, for chats without comment counters
+    let mut reply_counts: HashMap<i32, i32> = HashMap::new();
+    for msg in &messages {
+        if let Some(reply_to) = msg.reply_to_message_id() {
+            *reply_counts.entry(reply_to).or_default() += 1;
+        }
+    }
+
+    let engagement: Vec<Engagement> = messages
+        .iter()
+        .filter_map(|msg| {
+            let tl::enums::Message::Message(raw) = &msg.raw else {
+                return None;
+            };
+            let comments = match &raw.replies {
+                Some(tl::enums::MessageReplies::Replies(r)) => r.replies,
+                None => 0,
+            };
+            let in_chat = reply_counts.get(&msg.id()).copied().unwrap_or(0);
+
+            Some(Engagement {
+                message_id: msg.id(),
+                date: msg.date(),
+                preview: msg.text().chars().take(PREVIEW_CHARS).collect(),
+                reactions: reaction_breakdown(raw.reactions.as_ref()),
+                replies: comments.max(in_chat),
+                views: raw.views.unwrap_or(0),
+            })
+        })
+        .collect();
+
+    let candidates = rank_candidates(engagement, &config.weights, config.top);
+    let (username, channel_id) = link_target(&chat);
+    print_report(chat_name, &candidates, |id| {
+        permalink(username.as_deref(), channel_id, id)
+    });
+
+    if config.pin {
+        if !config.dry_run && !candidates.is_empty() {
+            let action = format!("pin {} message(s)", candidates.len());
+            confirm_chat(&chat, &action, config.yes)?;
+        }
+        pin_candidates(&client, &chat, &candidates, config.dry_run).await?;
+    }
+
+    Ok(candidates)
+}
+
+/// Whether we may pin messages in this chat
+fn can_pin(peer: &Peer) -> bool {
+    fn rights_allow_pin(creator: bool, rights: Option<&tl::enums::ChatAdminRights>) -> bool {
+        creator || matches!(rights, Some(tl::enums::ChatAdminRights::Rights(r)) if r.pin_messages)
+    }
+
+    match peer {
+        Peer::Channel(c) => rights_allow_pin(c.raw.creator, c.raw.admin_rights.as_ref()),
+        Peer::Group(g) => match &g.raw {
+            tl::enums::Chat::Chat(c) => rights_allow_pin(c.creator, c.admin_rights.as_ref()),
+            tl::enums::Chat::Channel(c) => rights_allow_pin(c.creator, c.admin_rights.as_ref()),
+            _ => false,
+        },
+        Peer::User(_) => true,
+    }
+}
+
+/// Username and channel id used to build permalinks
+fn link_target(peer: &Peer) -> (Option<String>, Option<i64>) {
+    match peer {
+        Peer::Channel(c) => (c.raw.username.clone(), Some(c.raw.id)),
+        Peer::Group(g) => match &g.raw {
+            tl::enums::Chat::Channel(c) => (c.username.clone(), Some(c.id)),
+            _ => (None, None),
+        },
+        Peer::User(_) => (None, None),
+    }
+}
+
+/// Pin candidates lowest score first so the best one ends up on top
+async fn pin_candidates(
+    client: &Client,
+    chat: &Peer,
+    candidates: &[PinCandidate],
+    dry_run: bool,
+) -> Result<()> {
+    if dry_run {
+        println!("\nDry run: messages will not be pinned.");
+    }
+
+    let peer = input_peer(chat);
+    for candidate in candidates.iter().rev() {
+        let id = candidate.engagement.message_id;
+        println!("📌 {}", id);
+
+        if dry_run {
+            continue;
+        }
+
+        let request = tl::functions::messages::UpdatePinnedMessage {
+            silent: true,
+            unpin: false,
+            pm_oneside: false,
+            peer: peer.clone(),
+            id,
+        };
+        if let Err(e) = client.invoke(&request).await {
+            warn!("Failed to pin message {}: {}", id, e);
+        }
+    }
+
+    Ok(())
+}
+
+fn print_report(
+    chat_name: &str,
+    candidates: &[PinCandidate],
+    link: impl Fn(i32) -> Option<String>,
+) {
+    println!("\n📌 Pin candidates for '{}'", chat_name);
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+
+    if candidates.is_empty() {
+        println!("No engaging messages found");
+        return;
+    }
+
+    for (rank, candidate) in candidates.iter().enumerate() {
+        let e = &candidate.engagement;
+        let reactions: String = e
+            .reactions
+            .iter()
+            .map(|(label, count)| format!("{}{} ", label, count))
+            .collect();
+        println!(
+            "{}. score {:.1} | {}replies {} | views {} | {}",
+            rank + 1,
+            candidate.score,
+            reactions,
+            e.replies,
+            e.views,
+            e.date.format("%Y-%m-%d %H:%M")
+        );
+        println!(
+            "   {}",
+            link(e.message_id).unwrap_or_else(|| format!("message #{}", e.message_id))
+        );
+        if !e.preview.is_empty() {
+            println!("   {}", e.preview.replace('\n', " "));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(id: i32, reactions: &[(&str, i32)], replies: i32, views: i32) -> Engagement {
+        Engagement {
+            message_id: id,
+            date: Utc::now(),
+            preview: String::new(),
+            reactions: reactions
+                .iter()
+                .map(|(label, count)| (label.to_string(), *count))
+                .collect(),
+            replies,
+            views,
+        }
+    }
+
+    fn ranked_ids(messages: Vec<Engagement>, top: usize) -> Vec<i32> {
+        rank_candidates(messages, &ScoreWeights::default(), top)
+            .iter()
+            .map(|c| c.engagement.message_id)
+            .collect()
+    }
+
+    #[test]
+    fn score_combines_reactions_replies_and_views() {
+        let msg = message(1, &[("🔥", 3), ("🤔", 2), ("👎", 1)], 2, 1500);
+        // 3*2 + 2*1 - 1 + 2*3 + 1.5
+        assert_eq!(ScoreWeights::default().score(&msg), 14.5);
+    }
+
+    #[test]
+    fn ranks_by_combined_score() {
+        let messages = vec![
+            message(1, &[("👍", 1)], 0, 0),
+            message(2, &[("🔥", 5)], 1, 0),
+            message(3, &[], 4, 2000),
+            message(4, &[("❤", 2)], 0, 0),
+        ];
+        assert_eq!(ranked_ids(messages, 10), vec![3, 2, 4, 1]);
+    }
+
+    #[test]
+    fn negative_reactions_push_messages_down() {
+        let messages = vec![
+            message(1, &[("👎", 10), ("🔥", 2)], 0, 0),
+            message(2, &[("👍", 2)], 0, 0),
+        ];
+        assert_eq!(ranked_ids(messages, 10), vec![2]);
+    }
+
+    #[test]
+    fn ties_prefer_newer_messages_and_top_limits() {
+        let messages = vec![
+            message(10, &[("🔥", 1)], 0, 0),
+            message(12, &[("🔥", 1)], 0, 0),
+            message(11, &[("🔥", 1)], 0, 0),
+        ];
+        assert_eq!(ranked_ids(messages, 2), vec![12, 11]);
+    }
+
+    #[test]
+    fn unengaged_messages_are_not_candidates() {
+        let messages = vec![message(1, &[], 0, 0), message(2, &[("🤔", 1)], 0, 0)];
+        assert_eq!(ranked_ids(messages, 5), vec![2]);
+    }
+
+    #[test]
+    fn builds_permalinks() {
+        assert_eq!(
+            permalink(Some("rust_ru"), Some(1), 42).as_deref(),
+            Some("https://t.me/rust_ru/42")
+        );
+        assert_eq!(
+            permalink(None, Some(1234567), 42).as_deref(),
+            Some("https://t.me/c/1234567/42")
+        );
+        assert_eq!(permalink(None, None, 42), None);
+    }
+}
@@ -1,61 +1,101 @@
-//! Bulk reactions helper (send the same emoji to multiple messages).
+//! Bulk reactions helper (send emoji reactions to multiple messages).
 //!
 //! Supports direct message ids, t.me links, ids from file, or the latest N messages.
+//! The emoji can be a weighted list (`❤️:5,🔥:2,👍`); each message then gets a
+//! weighted-random pick so bulk reactions look less uniform. `--remove` clears
+//! reactions on the same targets instead. FLOOD_WAIT errors are slept through
+//! and retried; `--delay-ms` stays the pause between reactions.
 
 use std::path::Path;
 use std::time::Duration;
 
 use grammers_client::types::peer::Peer;
+use grammers_client::types::Message;
 use grammers_client::Client;
 use grammers_tl_types as tl;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::Rng;
 use tokio::time::sleep;
 use tracing::warn;
 
-use crate::chat::find_chat;
+use crate::chat::{fetch_messages, find_chat, input_peer, FetchOptions, MessageFilter};
+use crate::confirm::confirm_chat;
+use crate::dry_run::{ActionKind, DryRunPlan, PlanFormat};
 use crate::error::{Error, Result};
+use crate::reactions::FloodRetry;
 use crate::session::{get_client, SessionLock};
 
 /// Arguments for bulk reactions.
 pub struct ReactArgs {
     pub chat: String,
+    /// One emoji or a weighted list, e.g. `❤️:5,🔥:2,👍:1`
     pub emoji: String,
     pub ids: Vec<String>,
     pub file: Option<std::path::PathBuf>,
     pub recent: usize,
     pub user_id: Option<i64>,
     pub delay_ms: u64,
-    pub dry_run: bool,
+    /// Remove reactions from the targeted messages instead of setting `emoji`
+    pub remove: bool,
+    /// Record planned reactions instead of sending them
+    pub dry_run: Option<PlanFormat>,
+    /// Skip the confirmation of the resolved chat
+    pub yes: bool,
 }
 
-/// Convert a Peer to InputPeer for API calls.
-fn peer_to_input(peer: &Peer) -> tl::enums::InputPeer {
-    match peer {
-        Peer::User(user) => {
-            let (user_id, access_hash) = match &user.raw {
-                tl::enums::User::User(u) => (u.id, u.access_hash.unwrap_or(0)),
-                tl::enums::User::Empty(u) => (u.id, 0),
-            };
-            tl::enums::InputPeer::User(tl::types::InputPeerUser {
-                user_id,
-                access_hash,
-            })
-        }
-        Peer::Channel(channel) => tl::enums::InputPeer::Channel(tl::types::InputPeerChannel {
-            channel_id: channel.raw.id,
-            access_hash: channel.raw.access_hash.unwrap_or(0),
-        }),
-        Peer::Group(group) => match &group.raw {
-            tl::enums::Chat::Chat(c) => {
-                tl::enums::InputPeer::Chat(tl::types::InputPeerChat { chat_id: c.id })
+/// Parse an emoji spec: `emoji[:weight]` entries separated by commas.
+///
+/// Entries without a weight count as 1, so a plain list is picked uniformly.
+pub fn parse_emoji_spec(spec: &str) -> Result<Vec<(String, u32)>> {
+    let mut choices = Vec::new();
+    for entry in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let (emoji, weight) = match entry.rsplit_once(':') {
+            Some((emoji, weight)) => {
+                let weight = weight
+                    .trim()
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|w| *w > 0)
+                    .ok_or_else(|| {
+                        Error::InvalidArgument(format!(
+                            "Emoji weight must be a positive integer: '{}'",
+                            entry
+                        ))
+                    })?;
+                (emoji.trim(), weight)
             }
-            tl::enums::Chat::Channel(c) => {
-                tl::enums::InputPeer::Channel(tl::types::InputPeerChannel {
-                    channel_id: c.id,
-                    access_hash: c.access_hash.unwrap_or(0),
-                })
-            }
-            _ => tl::enums::InputPeer::Empty,
-        },
+            None => (entry, 1),
+        };
+        if emoji.is_empty() {
+            return Err(Error::InvalidArgument(format!(
+                "Missing emoji in '{}'",
+                entry
+            )));
+        }
+        choices.push((emoji.to_string(), weight));
+    }
+
+    if choices.is_empty() {
+        return Err(Error::InvalidArgument("No emoji given".to_string()));
+    }
+    Ok(choices)
+}
+
+/// Weighted-random emoji choice for each message.
+pub struct EmojiPicker {
+    choices: Vec<(String, u32)>,
+    index: WeightedIndex<u32>,
+}
+
+impl EmojiPicker {
+    pub fn new(choices: Vec<(String, u32)>) -> Result<Self> {
+        let index = WeightedIndex::new(choices.iter().map(|(_, weight)| *weight))
+            .map_err(|e| Error::InvalidArgument(format!("Invalid emoji weights: {}", e)))?;
+        Ok(Self { choices, index })
+    }
+
+    pub fn pick(&self, rng: &mut impl Rng) -> &str {
+        &self.choices[self.index.sample(rng)].0
     }
 }
 
@@ -130,52 +170,56 @@ async fn fetch_recent_ids(
     limit: usize,
     user_id: Option<i64>,
 ) -> Result<Vec<i32>> {
-    let mut result = Vec::new();
-    let mut iter = client.iter_messages(chat);
-
-    while let Some(msg) = iter.next().await.transpose() {
-        let msg = msg.map_err(|e| Error::TelegramError(e.to_string()))?;
-        if result.len() >= limit {
-            break;
-        }
+    let options = FetchOptions {
+        limit: Some(limit),
+        filter: user_id.map(|expected| {
+            Box::new(move |msg: &Message| sender_id(msg) == expected) as MessageFilter<Message>
+        }),
+        ..Default::default()
+    };
+    let messages = fetch_messages(client, chat, options).await?;
 
-        if let Some(expected) = user_id {
-            let sender_id = match msg.sender() {
-                Some(Peer::User(u)) => u.raw.id(),
-                Some(Peer::Channel(c)) => c.raw.id,
-                Some(Peer::Group(g)) => match &g.raw {
-                    tl::enums::Chat::Chat(ch) => ch.id,
-                    tl::enums::Chat::Channel(ch) => ch.id,
-                    tl::enums::Chat::Forbidden(ch) => ch.id,
-                    tl::enums::Chat::ChannelForbidden(ch) => ch.id,
-                    tl::enums::Chat::Empty(ch) => ch.id,
-                },
-                None => 0,
-            };
-            if sender_id != expected {
-                continue;
-            }
-        }
+    Ok(messages.iter().map(Message::id).collect())
+}
 
-        result.push(msg.id());
+/// Raw id of the message sender (0 when unknown)
+fn sender_id(msg: &Message) -> i64 {
+    match msg.sender() {
+        Some(Peer::User(u)) => u.raw.id(),
+        Some(Peer::Channel(c)) => c.raw.id,
+        Some(Peer::Group(g)) => match &g.raw {
+            tl::enums::Chat::Chat(ch) => ch.id,
+            tl::enums::Chat::Channel(ch) => ch.id,
+            tl::enums::Chat::Forbidden(ch) => ch.id,
+            tl::enums::Chat::ChannelForbidden(ch) => ch.id,
+            tl::enums::Chat::Empty(ch) => ch.id,
+        },
+        None => 0,
     }
+}
 
-    Ok(result)
+/// Errors Telegram returns when there is no reaction to remove
+fn is_nothing_to_remove(error: &str) -> bool {
+    ["MESSAGE_NOT_MODIFIED", "REACTION_EMPTY"]
+        .iter()
+        .any(|code| error.contains(code))
 }
 
-/// Send reaction to a single message id.
+/// Send reaction to a single message id (`None` removes our reactions).
 async fn send_reaction(
     client: &Client,
     peer: &tl::enums::InputPeer,
     msg_id: i32,
-    emoji: &str,
+    emoji: Option<&str>,
 ) -> Result<()> {
     let request = tl::functions::messages::SendReaction {
         peer: peer.clone(),
         msg_id,
-        reaction: Some(vec![tl::enums::Reaction::Emoji(tl::types::ReactionEmoji {
-            emoticon: emoji.to_string(),
-        })]),
+        reaction: emoji.map(|emoji| {
+            vec![tl::enums::Reaction::Emoji(tl::types::ReactionEmoji {
+                emoticon: emoji.to_string(),
+            })]
+        }),
         big: false,
         add_to_recent: false,
     };
@@ -190,11 +234,14 @@ async fn send_reaction(
 
 /// Execute bulk reactions.
 pub async fn run(args: ReactArgs) -> Result<()> {
-    let _lock = SessionLock::acquire()?;
+    let picker = EmojiPicker::new(parse_emoji_spec(&args.emoji)?)?;
+    let mut rng = rand::thread_rng();
+
+    let _lock = SessionLock::acquire().await?;
     let client = get_client().await?;
 
     let chat = find_chat(&client, &args.chat).await?;
-    let input_peer = peer_to_input(&chat);
+    let input_peer = input_peer(&chat);
 
     let mut ids = collect_message_ids(&args.ids, args.file.as_deref())?;
     if args.recent > 0 {
@@ -218,43 +265,81 @@ pub async fn run(args: ReactArgs) -> Result<()> {
     }
 
     println!("Chat: {}", args.chat);
-    println!("Emoji: {}", args.emoji);
-    println!("Messages to react: {}", unique.len());
-    if args.dry_run {
+    if args.remove {
+        println!("Mode: remove reactions");
+        println!("Messages to clear: {}", unique.len());
+    } else {
+        println!("Emoji: {}", args.emoji);
+        println!("Messages to react: {}", unique.len());
+    }
+    if args.dry_run.is_some() {
         println!("Dry run: reactions will not be sent.");
+    } else {
+        let action = if args.remove {
+            format!("remove reactions from {} message(s)", unique.len())
+        } else {
+            format!("react {} to {} message(s)", args.emoji, unique.len())
+        };
+        confirm_chat(&chat, &action, args.yes)?;
     }
     println!();
 
+    let mut plan = DryRunPlan::new("react");
     let mut sent = 0usize;
+    let mut unchanged = 0usize;
     let mut errors = 0usize;
+    let mut flood = FloodRetry::default();
 
     for msg_id in unique {
-        println!("{} -> {}", args.emoji, msg_id);
+        let emoji = (!args.remove).then(|| picker.pick(&mut rng));
+        match emoji {
+            Some(emoji) => println!("{} -> {}", emoji, msg_id),
+            None if args.dry_run.is_some() => println!("would remove reactions from {}", msg_id),
+            None => println!("remove reactions -> {}", msg_id),
+        }
 
-        if args.dry_run {
+        if args.dry_run.is_some() {
+            match emoji {
+                Some(emoji) => plan.record(ActionKind::React, &args.chat, Some(msg_id), emoji),
+                None => plan.record(ActionKind::Unreact, &args.chat, Some(msg_id), ""),
+            }
             continue;
         }
 
-        match send_reaction(&client, &input_peer, msg_id, &args.emoji).await {
+        match flood
+            .run(|| send_reaction(&client, &input_peer, msg_id, emoji))
+            .await
+        {
             Ok(()) => {
                 sent += 1;
                 sleep(Duration::from_millis(args.delay_ms)).await;
             }
+            Err(e) if args.remove && is_nothing_to_remove(&e.to_string()) => {
+                unchanged += 1;
+            }
             Err(e) => {
                 errors += 1;
                 let err_str = e.to_string();
                 warn!("Error on {}: {}", msg_id, err_str);
                 if err_str.contains("FLOOD") || err_str.contains("wait") {
-                    warn!("Rate limit detected, stopping early.");
+                    warn!("Rate limit persists after retries, stopping early.");
                     break;
                 }
             }
         }
     }
 
+    if let Some(format) = args.dry_run {
+        return plan.print(format);
+    }
+
     println!();
     println!("Done.");
     println!("Sent: {}", sent);
+    if args.remove {
+        println!("Nothing to remove: {}", unchanged);
+    }
+    println!("Flood waits: {}", flood.flood_waits);
     println!("Errors: {}", errors);
 
     Ok(())
@@ -288,6 +373,50 @@ mod tests {
         assert_eq!(parse_message_token("abc"), None);
     }
 
+    #[test]
+    fn emoji_spec_parses_weights_and_defaults_to_uniform() {
+        assert_eq!(
+            parse_emoji_spec("❤️:5, 🔥:2,👍:1").unwrap(),
+            vec![
+                ("❤️".to_string(), 5),
+                ("🔥".to_string(), 2),
+                ("👍".to_string(), 1)
+            ]
+        );
+        assert_eq!(
+            parse_emoji_spec("❤️,🔥").unwrap(),
+            vec![("❤️".to_string(), 1), ("🔥".to_string(), 1)]
+        );
+        assert_eq!(parse_emoji_spec("❤️").unwrap(), vec![("❤️".to_string(), 1)]);
+
+        for bad in ["❤️:0", "❤️:-1", "❤️:lots", ":3", "", " , "] {
+            assert!(parse_emoji_spec(bad).is_err(), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn picker_follows_weights() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let picker = EmojiPicker::new(parse_emoji_spec("❤️:9,🔥:1").unwrap()).unwrap();
+        let mut rng = StdRng::seed_from_u64(1);
+        let hearts = (0..1000).filter(|_| picker.pick(&mut rng) == "❤️").count();
+        assert!((850..=950).contains(&hearts), "{}", hearts);
+
+        let single = EmojiPicker::new(parse_emoji_spec("👍").unwrap()).unwrap();
+        assert!((0..10).all(|_| single.pick(&mut rng) == "👍"));
+    }
+
+    #[test]
+    fn removing_a_missing_reaction_is_not_an_error() {
+        assert!(is_nothing_to_remove(
+            "Telegram API error: rpc error 400: MESSAGE_NOT_MODIFIED caused by messages.sendReaction"
+        ));
+        assert!(is_nothing_to_remove("rpc error 400: REACTION_EMPTY"));
+        assert!(!is_nothing_to_remove("rpc error 420: FLOOD_WAIT_30"));
+        assert!(!is_nothing_to_remove("rpc error 400: MSG_ID_INVALID"));
+    }
+
     #[test]
     fn collect_ids_dedupes() {
         let ids = collect_message_ids(
@@ -3,36 +3,72 @@
 //! Equivalent to Python's read.py
 
 use std::collections::HashSet;
+use std::path::PathBuf;
 
 use crate::chat::resolve_chat;
 use crate::config::ChatEntity;
-use crate::config::{Config, MEDIA_REACTION_THRESHOLD};
+use crate::config::Config;
 use crate::error::Result;
 use crate::export::{create_media_dir, ExportWriter};
-use crate::session::{get_client, SessionLock, TelegramClient};
+use crate::polling::PollingConfig;
+use crate::rolling::{RollingJsonl, DEFAULT_KEEP};
+use crate::session::{get_client, is_connection_error, SessionLock, TelegramClient};
 use grammers_client::client::UpdatesConfiguration;
+use grammers_client::types::peer::Peer;
 use grammers_client::types::update::Update;
 use grammers_client::types::Message;
-use grammers_session::defs::PeerId;
+use grammers_client::{Client, InvocationError};
+use serde::Serialize;
 use tokio::signal;
 use tracing::info;
 
+/// Maximum number of new messages picked up by a single poll
+const WATCH_POLL_BATCH: usize = 100;
+
+/// Watch mode settings
+#[derive(Debug, Clone, Default)]
+pub struct WatchOptions {
+    /// Poll history instead of subscribing to updates
+    pub force_poll: bool,
+    /// Adaptive polling cadence (also used when updates are unavailable)
+    pub polling: PollingConfig,
+    /// Also append new messages as JSONL to this file
+    pub out: Option<PathBuf>,
+    /// Rotate the JSONL file to `file.1`, `file.2`, ... past this size
+    pub out_max_bytes: u64,
+}
+
+/// One line of the `--watch-out` capture
+#[derive(Debug, Serialize)]
+struct WatchedMessage<'a> {
+    id: i32,
+    chat: &'a str,
+    date: chrono::DateTime<chrono::Utc>,
+    sender_id: i64,
+    sender: &'a str,
+    text: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reply_to: Option<i32>,
+    has_media: bool,
+}
+
 pub async fn run(
     chat_name: &str,
     limit: Option<usize>,
     delete_unengaged: bool,
-    watch: bool,
+    watch: Option<WatchOptions>,
 ) -> Result<()> {
     let config = Config::new();
     let my_user_id = config.my_user_id;
     let limit = limit.unwrap_or_else(|| config.get_limit());
+    let media_threshold = config.reaction_threshold(chat_name);
 
     println!("LIMIT={}", limit);
 
     let (primary_entity, fallback_entity) = parse_chat_entity(chat_name, &config);
 
     // Acquire session lock
-    let _lock = SessionLock::acquire()?;
+    let _lock = SessionLock::acquire().await?;
 
     // Connect to Telegram
     let mut client = get_client().await?;
@@ -48,7 +84,6 @@ pub async fn run(
             }
         }
     };
-    let target_peer_id = chat.id();
 
     info!("Reading messages from: {}", chat_name);
 
@@ -56,8 +91,7 @@ pub async fn run(
     let mut messages: Vec<Message> = Vec::new();
     let mut iter = client.iter_messages(&chat);
 
-    while let Some(msg) = iter.next().await.transpose() {
-        let msg = msg.map_err(|e| crate::error::Error::TelegramError(e.to_string()))?;
+    while let Some(msg) = client.next_message(&mut iter).await? {
         messages.push(msg);
         if messages.len() >= limit {
             break;
@@ -127,7 +161,7 @@ pub async fn run(
 
         // Handle media
         if msg.media().is_some() {
-            if reactions >= MEDIA_REACTION_THRESHOLD && !Config::is_github_actions() {
+            if reactions >= media_threshold && !Config::is_github_actions() {
                 create_media_dir(chat_name)?;
                 // Download media
                 let file_path = format!("{}/media_{}.bin", chat_name, msg.id());
@@ -146,15 +180,39 @@ pub async fn run(
         }
     }
 
-    if watch {
-        watch_chat(
-            &mut client,
-            target_peer_id,
-            chat_name,
-            &mut writer,
-            last_seen_id,
-        )
-        .await?;
+    if let Some(options) = watch {
+        let mut capture = options
+            .out
+            .as_ref()
+            .map(|path| RollingJsonl::open(path, options.out_max_bytes, DEFAULT_KEEP))
+            .transpose()?;
+        if let Some(capture) = &capture {
+            println!("📝 Новые сообщения пишутся в {}", capture.path().display());
+        }
+
+        if options.force_poll {
+            poll_chat(
+                &client,
+                &chat,
+                chat_name,
+                &mut writer,
+                &mut capture,
+                last_seen_id,
+                options.polling,
+            )
+            .await?;
+        } else {
+            watch_chat(
+                &mut client,
+                &chat,
+                chat_name,
+                &mut writer,
+                &mut capture,
+                last_seen_id,
+                options.polling,
+            )
+            .await?;
+        }
     }
 
     writer.finish()?;
@@ -189,16 +247,28 @@ fn extract_sender_id(msg: &Message) -> i64 {
 
 async fn watch_chat(
     client: &mut TelegramClient,
-    target_peer_id: PeerId,
+    chat: &Peer,
     chat_name: &str,
     writer: &mut ExportWriter,
+    capture: &mut Option<RollingJsonl>,
     mut last_seen_id: i32,
+    polling: PollingConfig,
 ) -> Result<()> {
+    let target_peer_id = chat.id();
     let updates_rx = match client.take_updates() {
         Some(rx) => rx,
         None => {
-            println!("⚠️ Watch режим недоступен: не удалось получить канал обновлений. Попробуйте перезапустить команду.");
-            return Ok(());
+            println!("⚠️ Канал обновлений недоступен, переключаюсь на опрос истории.");
+            return poll_chat(
+                client,
+                chat,
+                chat_name,
+                writer,
+                capture,
+                last_seen_id,
+                polling,
+            )
+            .await;
         }
     };
 
@@ -234,24 +304,14 @@ async fn watch_chat(
                         }
                         last_seen_id = msg_id;
 
-                        let sender_id = extract_sender_id(&msg);
-                        let sender_name = writer.get_sender_name(sender_id, &msg);
-                        let mut text = msg.text().to_string();
-                        if text.is_empty() && msg.media().is_some() {
-                            text = "[Media]".to_string();
-                        }
-
-                        let timestamp = msg.date();
-                        writer.write_message(&sender_name, &text, "", Some(timestamp), None)?;
-
-                        println!(
-                            "[{}] {}: {}",
-                            timestamp.format("%H:%M:%S"),
-                            sender_name,
-                            text
-                        );
+                        log_watched_message(writer, capture, chat_name, &msg)?;
                     }
                     Ok(_) => {}
+                    Err(err) if is_connection_error(&err) => {
+                        // The stream outlives the drop and fetches the missed
+                        // difference on its next poll
+                        client.reconnect().await?;
+                    }
                     Err(err) => {
                         eprintln!("⚠️ Ошибка при получении обновлений: {}", err);
                         break;
@@ -265,6 +325,105 @@ async fn watch_chat(
     Ok(())
 }
 
+/// Watch a chat by polling its history with an adaptive interval
+async fn poll_chat(
+    client: &TelegramClient,
+    chat: &Peer,
+    chat_name: &str,
+    writer: &mut ExportWriter,
+    capture: &mut Option<RollingJsonl>,
+    mut last_seen_id: i32,
+    polling: PollingConfig,
+) -> Result<()> {
+    println!(
+        "👀 Watch режим (опрос) включен для '{}'. Нажмите Ctrl+C для остановки.",
+        chat_name
+    );
+
+    let mut interval = polling.base;
+
+    loop {
+        tokio::select! {
+            _ = signal::ctrl_c() => {
+                println!("\nОстанавливаю watch режим...");
+                break;
+            }
+            _ = tokio::time::sleep(interval) => {
+                let fresh = client
+                    .with_reconnect(|client| fetch_newer(client, chat, last_seen_id))
+                    .await?;
+
+                interval = polling.next_interval(interval, !fresh.is_empty());
+
+                for msg in fresh.iter().rev() {
+                    last_seen_id = last_seen_id.max(msg.id());
+                    log_watched_message(writer, capture, chat_name, msg)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Messages newer than `last_seen_id`, newest first, at most one batch
+async fn fetch_newer(
+    client: Client,
+    chat: &Peer,
+    last_seen_id: i32,
+) -> std::result::Result<Vec<Message>, InvocationError> {
+    let mut fresh = Vec::new();
+    let mut iter = client.iter_messages(chat);
+    while let Some(msg) = iter.next().await? {
+        if msg.id() <= last_seen_id {
+            break;
+        }
+        fresh.push(msg);
+        if fresh.len() >= WATCH_POLL_BATCH {
+            break;
+        }
+    }
+    Ok(fresh)
+}
+
+fn log_watched_message(
+    writer: &mut ExportWriter,
+    capture: &mut Option<RollingJsonl>,
+    chat_name: &str,
+    msg: &Message,
+) -> Result<()> {
+    let sender_id = extract_sender_id(msg);
+    let sender_name = writer.get_sender_name(sender_id, msg);
+    let mut text = msg.text().to_string();
+    if text.is_empty() && msg.media().is_some() {
+        text = "[Media]".to_string();
+    }
+
+    let timestamp = msg.date();
+    writer.write_message(&sender_name, &text, "", Some(timestamp), None)?;
+
+    if let Some(capture) = capture {
+        capture.append(&WatchedMessage {
+            id: msg.id(),
+            chat: chat_name,
+            date: timestamp,
+            sender_id,
+            sender: &sender_name,
+            text: msg.text(),
+            reply_to: msg.reply_to_message_id(),
+            has_media: msg.media().is_some(),
+        })?;
+    }
+
+    println!(
+        "[{}] {}: {}",
+        timestamp.format("%H:%M:%S"),
+        sender_name,
+        text
+    );
+    Ok(())
+}
+
 /// Resolve chat input into a ChatEntity and optional fallback.
 /// - Config name wins
 /// - Numeric strings are treated as channel IDs with group fallback
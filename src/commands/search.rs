@@ -1,7 +1,16 @@
-//! Semantic search in indexed messages
+//! Semantic search in indexed messages, plus the grep-style context and
+//! highlighting used to print keyword matches
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 
 use anyhow::Result;
-use tracing::info;
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use tracing::{info, warn};
+use walkdir::WalkDir;
 
 use crate::analysis::{
     embeddings::EmbeddingService,
@@ -9,6 +18,10 @@ use crate::analysis::{
     models::AnalyzedMessage,
     vector_db::{SearchFilter, VectorStore},
 };
+use crate::offline::OfflineMessage;
+
+/// Messages embedded at once by the local search
+const LOCAL_BATCH_SIZE: usize = 256;
 
 /// Search configuration
 pub struct SearchConfig {
@@ -22,6 +35,10 @@ pub struct SearchConfig {
     pub sender_id: Option<i64>,
     /// Filter to only outgoing messages
     pub outgoing_only: bool,
+    /// Only messages sent at or after this time
+    pub date_from: Option<DateTime<Utc>>,
+    /// Only messages sent at or before this time
+    pub date_to: Option<DateTime<Utc>>,
 }
 
 impl Default for SearchConfig {
@@ -33,6 +50,8 @@ impl Default for SearchConfig {
             chat_id: None,
             sender_id: None,
             outgoing_only: false,
+            date_from: None,
+            date_to: None,
         }
     }
 }
@@ -42,7 +61,7 @@ pub async fn search_messages(query: &str, config: &SearchConfig) -> Result<Vec<S
     info!("Searching for: '{}'", query);
 
     // Generate embedding for query
-    let embedding_service = EmbeddingService::new()?;
+    let embedding_service = EmbeddingService::from_env();
     let query_embedding = embedding_service.embed(query).await?;
 
     // Build filter
@@ -56,18 +75,17 @@ pub async fn search_messages(query: &str, config: &SearchConfig) -> Result<Vec<S
     if config.outgoing_only {
         filter = filter.outgoing(true);
     }
-
-    let filter_option =
-        if config.chat_id.is_some() || config.sender_id.is_some() || config.outgoing_only {
-            Some(filter)
-        } else {
-            None
-        };
+    if let Some(from) = config.date_from {
+        filter = filter.date_from(from);
+    }
+    if let Some(to) = config.date_to {
+        filter = filter.date_to(to);
+    }
 
     // Search vector DB
     let store = VectorStore::new(&config.qdrant_url).await?;
     let results = store
-        .search(query_embedding, config.limit, filter_option)
+        .search_filtered(query_embedding, filter, config.limit)
         .await?;
 
     info!("Found {} results", results.len());
@@ -81,6 +99,127 @@ pub async fn search_messages(query: &str, config: &SearchConfig) -> Result<Vec<S
         .collect())
 }
 
+/// A message from an exported JSONL file matching a local search
+#[derive(Debug, Clone)]
+pub struct LocalMatch {
+    pub file: PathBuf,
+    pub message_id: i32,
+    pub sender: String,
+    pub date: DateTime<Utc>,
+    pub text: String,
+    pub score: f32,
+}
+
+/// The `limit` best matches seen so far, best first
+#[derive(Debug)]
+struct TopK {
+    limit: usize,
+    items: Vec<LocalMatch>,
+}
+
+impl TopK {
+    fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            items: Vec::with_capacity(limit),
+        }
+    }
+
+    fn push(&mut self, item: LocalMatch) {
+        let worst = self.items.last().map(|last| last.score);
+        let full = self.items.len() >= self.limit;
+        if full && !worst.is_some_and(|worst| worst < item.score) {
+            return;
+        }
+        let pos = self.items.partition_point(|m| m.score >= item.score);
+        self.items.insert(pos, item);
+        self.items.truncate(self.limit);
+    }
+}
+
+/// Semantic search over exported JSONL chats in `dir`, without Qdrant.
+///
+/// Files are streamed line by line and embedded in batches with the local
+/// embedding backend; only the `limit` best matches are kept in memory.
+pub async fn search_local(dir: &Path, query: &str, limit: usize) -> Result<Vec<LocalMatch>> {
+    info!("Searching {} for: '{}'", dir.display(), query);
+
+    let service = EmbeddingService::local();
+    let query_embedding = service.embed(query).await?;
+    let mut top = TopK::new(limit);
+    let mut scanned = 0;
+
+    let files = WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry.file_type().is_file()
+                && entry.path().extension().is_some_and(|ext| ext == "jsonl")
+        });
+
+    for entry in files {
+        let path = entry.path();
+        let mut batch: Vec<OfflineMessage> = Vec::with_capacity(LOCAL_BATCH_SIZE);
+
+        for (i, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<OfflineMessage>(&line) {
+                Ok(msg) if !msg.text.trim().is_empty() => batch.push(msg),
+                Ok(_) => {}
+                Err(e) => warn!(
+                    "{}:{}: skipping invalid message: {}",
+                    path.display(),
+                    i + 1,
+                    e
+                ),
+            }
+
+            if batch.len() == LOCAL_BATCH_SIZE {
+                scanned += batch.len();
+                score_batch(&service, &query_embedding, path, &mut batch, &mut top).await?;
+            }
+        }
+        scanned += batch.len();
+        score_batch(&service, &query_embedding, path, &mut batch, &mut top).await?;
+    }
+
+    info!("Scanned {} messages", scanned);
+    Ok(top.items)
+}
+
+/// Embed and score a batch of messages, draining it into `top`
+async fn score_batch(
+    service: &EmbeddingService,
+    query: &[f32],
+    file: &Path,
+    batch: &mut Vec<OfflineMessage>,
+    top: &mut TopK,
+) -> Result<()> {
+    if batch.is_empty() {
+        return Ok(());
+    }
+
+    let texts: Vec<String> = batch.iter().map(|m| m.text.clone()).collect();
+    let embeddings = service.embed_batch(&texts).await?;
+
+    for (msg, embedding) in batch.drain(..).zip(embeddings) {
+        // Local embeddings are unit length, so the dot product is the cosine
+        let score = query.iter().zip(&embedding).map(|(a, b)| a * b).sum();
+        top.push(LocalMatch {
+            file: file.to_path_buf(),
+            message_id: msg.id,
+            sender: msg.sender,
+            date: msg.date,
+            text: msg.text,
+            score,
+        });
+    }
+    Ok(())
+}
+
 /// Find conversation context for a message
 pub async fn find_conversation(message_uuid: &str, depth: usize) -> Result<Vec<AnalyzedMessage>> {
     info!("Finding conversation for message: {}", message_uuid);
@@ -110,6 +249,23 @@ pub async fn find_contacts(user_id: i64, limit: usize) -> Result<Vec<ContactResu
         .collect())
 }
 
+/// Closest collaborators of a user, by reply/reaction strength
+pub async fn find_collaborators(user_id: i64, limit: usize) -> Result<Vec<CollaboratorResult>> {
+    info!("Finding collaborators for user: {}", user_id);
+
+    let graph = GraphStore::from_env().await?;
+    let users = graph.top_interactions(user_id, limit).await?;
+
+    Ok(users
+        .into_iter()
+        .map(|(u, strength)| CollaboratorResult {
+            user_id: u.user_id,
+            name: u.name,
+            strength,
+        })
+        .collect())
+}
+
 /// Search result with score
 #[derive(Debug)]
 pub struct SearchResult {
@@ -125,6 +281,14 @@ pub struct ContactResult {
     pub message_count: u32,
 }
 
+/// Collaborator with the aggregated interaction weight
+#[derive(Debug)]
+pub struct CollaboratorResult {
+    pub user_id: i64,
+    pub name: String,
+    pub strength: f64,
+}
+
 /// Get database statistics
 pub async fn get_stats() -> Result<Stats> {
     let qdrant_url =
@@ -159,6 +323,126 @@ pub struct Stats {
     pub graph_relations: Option<u64>,
 }
 
+/// How matched terms are marked in printed results
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Highlight {
+    /// Bold yellow ANSI escapes for a terminal
+    Ansi,
+    /// `**bold**` for Markdown output
+    Markdown,
+    /// No markers (output piped to a file or another tool)
+    Plain,
+}
+
+impl Highlight {
+    fn markers(self) -> (&'static str, &'static str) {
+        match self {
+            Highlight::Ansi => ("\x1b[1;33m", "\x1b[0m"),
+            Highlight::Markdown => ("**", "**"),
+            Highlight::Plain => ("", ""),
+        }
+    }
+}
+
+/// Consecutive lines shown around one or more matches, like a `grep -C` group
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContextBlock {
+    /// Lines `start..end` of the input
+    pub start: usize,
+    pub end: usize,
+    /// Matching line indices inside the block
+    pub matches: Vec<usize>,
+}
+
+/// Group matching lines with `context` lines around each.
+///
+/// Windows are clamped to `0..total` and merged when they overlap or touch,
+/// so every line is printed at most once.
+pub fn context_blocks(total: usize, matches: &[usize], context: usize) -> Vec<ContextBlock> {
+    let mut matches: Vec<usize> = matches.iter().copied().filter(|&m| m < total).collect();
+    matches.sort_unstable();
+    matches.dedup();
+
+    let mut blocks: Vec<ContextBlock> = Vec::new();
+    for m in matches {
+        let start = m.saturating_sub(context);
+        let end = (m + context + 1).min(total);
+        match blocks.last_mut() {
+            Some(last) if start <= last.end => {
+                last.end = last.end.max(end);
+                last.matches.push(m);
+            }
+            _ => blocks.push(ContextBlock {
+                start,
+                end,
+                matches: vec![m],
+            }),
+        }
+    }
+    blocks
+}
+
+/// Byte ranges of all non-empty matches of `regex` in `text`
+pub fn match_ranges(regex: &Regex, text: &str) -> Vec<Range<usize>> {
+    regex
+        .find_iter(text)
+        .filter(|m| !m.is_empty())
+        .map(|m| m.range())
+        .collect()
+}
+
+/// Wrap the `ranges` of `text` in highlight markers.
+///
+/// Overlapping ranges are merged. For Markdown, whitespace at the edges of a
+/// match stays outside the markers (`** foo**` isn't bold).
+pub fn highlight(text: &str, ranges: &[Range<usize>], style: Highlight) -> String {
+    let (open, close) = style.markers();
+    let mut ranges: Vec<Range<usize>> = ranges
+        .iter()
+        .filter(|r| r.start < r.end && r.end <= text.len())
+        .cloned()
+        .collect();
+    ranges.sort_by_key(|r| r.start);
+
+    let mut out = String::with_capacity(text.len() + ranges.len() * 8);
+    let mut pos = 0;
+    let mut i = 0;
+    while i < ranges.len() {
+        let start = ranges[i].start.max(pos);
+        let mut end = ranges[i].end;
+        while i + 1 < ranges.len() && ranges[i + 1].start <= end {
+            i += 1;
+            end = end.max(ranges[i].end);
+        }
+        i += 1;
+        if start >= end {
+            continue;
+        }
+
+        let matched = &text[start..end];
+        let (lead, core, trail) = if style == Highlight::Markdown {
+            let trimmed_start = matched.trim_start();
+            let core = trimmed_start.trim_end();
+            let lead = matched.len() - trimmed_start.len();
+            (&matched[..lead], core, &trimmed_start[core.len()..])
+        } else {
+            ("", matched, "")
+        };
+
+        out.push_str(&text[pos..start]);
+        out.push_str(lead);
+        if !core.is_empty() {
+            out.push_str(open);
+            out.push_str(core);
+            out.push_str(close);
+        }
+        out.push_str(trail);
+        pos = end;
+    }
+    out.push_str(&text[pos..]);
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -180,6 +464,8 @@ mod tests {
             chat_id: Some(123),
             sender_id: Some(456),
             outgoing_only: true,
+            date_from: None,
+            date_to: None,
         };
         assert_eq!(config.qdrant_url, "http://custom:6333");
         assert_eq!(config.limit, 50);
@@ -188,6 +474,35 @@ mod tests {
         assert!(config.outgoing_only);
     }
 
+    #[tokio::test]
+    async fn local_search_ranks_exported_messages() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("nested")).unwrap();
+        std::fs::write(
+            dir.path().join("a.jsonl"),
+            r#"{"id": 1, "date": "2024-05-01T10:00:00Z", "sender": "@alice", "text": "Deploy the release on Friday"}
+{"id": 2, "date": "2024-05-01T10:05:00Z", "sender": "@bob", "text": "Lunch at noon?"}
+not json
+"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("nested/b.jsonl"),
+            r#"{"id": 7, "date": "2024-05-02T09:00:00Z", "sender": "@carol", "text": "release deploy failed"}"#,
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "release deploy").unwrap();
+
+        let matches = search_local(dir.path(), "deploy release", 2).await.unwrap();
+
+        assert_eq!(matches.len(), 2);
+        let mut ids: Vec<i32> = matches.iter().map(|m| m.message_id).collect();
+        ids.sort();
+        assert_eq!(ids, vec![1, 7]);
+        assert!(matches[0].score >= matches[1].score);
+        assert!(matches.iter().any(|m| m.file.ends_with("nested/b.jsonl")));
+    }
+
     #[test]
     fn test_search_result_debug() {
         let msg = AnalyzedMessage::default();
@@ -236,4 +551,85 @@ mod tests {
         };
         assert!(format!("{:?}", stats).contains("None"));
     }
+
+    #[test]
+    fn test_context_blocks_clamp_at_bounds() {
+        // First and last line of a 10-line input with 2 lines of context
+        assert_eq!(
+            context_blocks(10, &[0, 9], 2),
+            vec![
+                ContextBlock {
+                    start: 0,
+                    end: 3,
+                    matches: vec![0]
+                },
+                ContextBlock {
+                    start: 7,
+                    end: 10,
+                    matches: vec![9]
+                },
+            ]
+        );
+        // Out-of-range matches are ignored
+        assert!(context_blocks(3, &[5], 1).is_empty());
+        assert!(context_blocks(0, &[0], 1).is_empty());
+    }
+
+    #[test]
+    fn test_context_blocks_merge_overlapping_windows() {
+        // 2 and 5 touch with 1 line of context (1..4 and 4..7), 9 is separate
+        assert_eq!(
+            context_blocks(20, &[5, 2, 9, 5], 1),
+            vec![
+                ContextBlock {
+                    start: 1,
+                    end: 7,
+                    matches: vec![2, 5]
+                },
+                ContextBlock {
+                    start: 8,
+                    end: 11,
+                    matches: vec![9]
+                },
+            ]
+        );
+        // No context: adjacent matches still form one block
+        assert_eq!(context_blocks(5, &[1, 2], 0).len(), 1);
+        assert_eq!(context_blocks(5, &[1, 3], 0).len(), 2);
+    }
+
+    #[test]
+    fn test_highlight_places_markers_on_matches() {
+        let regex = Regex::new("(?i)deploy").unwrap();
+        let text = "Deploy failed, redeploy now";
+        let ranges = match_ranges(&regex, text);
+        assert_eq!(ranges, vec![0..6, 17..23]);
+
+        assert_eq!(
+            highlight(text, &ranges, Highlight::Markdown),
+            "**Deploy** failed, re**deploy** now"
+        );
+        assert_eq!(
+            highlight(text, &ranges, Highlight::Ansi),
+            "\x1b[1;33mDeploy\x1b[0m failed, re\x1b[1;33mdeploy\x1b[0m now"
+        );
+        assert_eq!(highlight(text, &ranges, Highlight::Plain), text);
+    }
+
+    #[test]
+    fn test_highlight_merges_overlaps_and_trims_whitespace() {
+        assert_eq!(
+            highlight("abcdef", &[1..3, 2..5], Highlight::Markdown),
+            "a**bcde**f"
+        );
+        assert_eq!(
+            highlight("see the docs  ok", &[3..8, 12..16], Highlight::Markdown),
+            "see **the** docs  **ok**"
+        );
+        // Multi-byte text and out-of-range ranges
+        assert_eq!(
+            highlight("Привет мир", &[13..19, 40..50], Highlight::Markdown),
+            "Привет **мир**"
+        );
+    }
 }
@@ -1,12 +1,210 @@
 //! Send message to Telegram user or chat
 //!
-//! Отправка сообщений в Telegram
+//! Отправка сообщений в Telegram. Получателей можно перечислить через запятую:
+//! `{chat}` в тексте заменяется на алиас текущего чата, отправки разнесены
+//! паузой, а ошибка в одном чате не прерывает остальные.
 
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::time::Duration;
+
+use chrono::{NaiveDate, Utc};
 use grammers_client::types::peer::Peer;
+use tokio::time::sleep;
 
+use crate::chat::peer_name;
 use crate::config::{ChatEntity, Config};
 use crate::error::{Error, Result};
-use crate::session::{get_client, SessionLock};
+use crate::prompts::render_template;
+use crate::quiet_hours::QuietHours;
+use crate::send_limit::DailySendLimiter;
+use crate::session::{get_client, HeldSession, SessionLock, TelegramClient};
+
+/// Текст сообщения: готовый или шаблон с переменными получателя
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MessageText {
+    Plain(String),
+    /// Шаблон с `{{chat_title}}`, `{{date}}`, `{{first_name}}` (только для личных чатов)
+    /// и `{chat}` — алиасом получателя
+    Template {
+        template: String,
+        strict: bool,
+    },
+}
+
+impl MessageText {
+    /// Загрузить шаблон из файла
+    pub fn from_template_file(path: &Path, strict: bool) -> Result<Self> {
+        let template = std::fs::read_to_string(path).map_err(|e| {
+            Error::InvalidArgument(format!(
+                "Не удалось загрузить шаблон {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        Ok(Self::Template { template, strict })
+    }
+
+    /// Подставить `{chat}` — алиас получателя, как он указан в списке
+    pub fn with_chat(&self, chat: &str) -> Self {
+        match self {
+            Self::Plain(text) => Self::Plain(text.replace("{chat}", chat)),
+            Self::Template { template, strict } => Self::Template {
+                template: template.replace("{chat}", chat),
+                strict: *strict,
+            },
+        }
+    }
+
+    /// Текст для конкретного получателя
+    pub fn render_for(&self, peer: &Peer) -> Result<String> {
+        match self {
+            Self::Plain(text) => Ok(text.clone()),
+            Self::Template { template, strict } => {
+                let first_name = match peer {
+                    Peer::User(u) => match &u.raw {
+                        grammers_tl_types::enums::User::User(user) => user.first_name.as_deref(),
+                        grammers_tl_types::enums::User::Empty(_) => None,
+                    },
+                    _ => None,
+                };
+                let vars = template_vars(&peer_name(peer), first_name, Utc::now().date_naive());
+                render_template(template, &vars, *strict)
+            }
+        }
+    }
+}
+
+/// Переменные шаблона для получателя; `first_name` есть только у пользователей
+pub fn template_vars(
+    chat_title: &str,
+    first_name: Option<&str>,
+    date: NaiveDate,
+) -> HashMap<&'static str, String> {
+    let mut vars = HashMap::from([
+        ("chat_title", chat_title.to_string()),
+        ("date", date.format("%d.%m.%Y").to_string()),
+    ]);
+    if let Some(first_name) = first_name {
+        vars.insert("first_name", first_name.to_string());
+    }
+    vars
+}
+
+/// Максимальная длина одного сообщения Telegram (в UTF-16 символах)
+pub const TELEGRAM_MESSAGE_LIMIT: usize = 4096;
+
+fn utf16_len(text: &str) -> usize {
+    text.encode_utf16().count()
+}
+
+/// Разбить длинный текст на сообщения не длиннее `limit`.
+///
+/// Режет по абзацам (`\n\n`), слишком длинный абзац — по строкам, а слишком
+/// длинную строку — по символам.
+pub fn split_message(text: &str, limit: usize) -> Vec<String> {
+    let limit = limit.max(1);
+    let mut parts = Vec::new();
+    let mut current = String::new();
+
+    let mut push = |piece: &str, separator: &str, current: &mut String| {
+        if current.is_empty() {
+            current.push_str(piece);
+        } else if utf16_len(current) + utf16_len(separator) + utf16_len(piece) <= limit {
+            current.push_str(separator);
+            current.push_str(piece);
+        } else {
+            parts.push(std::mem::take(current));
+            current.push_str(piece);
+        }
+    };
+
+    for paragraph in text.split("\n\n").filter(|p| !p.trim().is_empty()) {
+        if utf16_len(paragraph) <= limit {
+            push(paragraph, "\n\n", &mut current);
+            continue;
+        }
+        for (i, line) in paragraph.lines().enumerate() {
+            let separator = if i == 0 { "\n\n" } else { "\n" };
+            if utf16_len(line) <= limit {
+                push(line, separator, &mut current);
+                continue;
+            }
+            let mut chunk = String::new();
+            for c in line.chars() {
+                if utf16_len(&chunk) + c.len_utf16() > limit {
+                    push(&std::mem::take(&mut chunk), separator, &mut current);
+                }
+                chunk.push(c);
+            }
+            push(&chunk, separator, &mut current);
+        }
+    }
+
+    if !current.is_empty() {
+        parts.push(current);
+    }
+    parts
+}
+
+/// Разобрать список получателей через запятую (пустые и повторы отбрасываются)
+pub fn parse_targets(spec: &str) -> Vec<String> {
+    let mut targets: Vec<String> = Vec::new();
+    for target in spec.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+        if !targets.iter().any(|t| t == target) {
+            targets.push(target.to_string());
+        }
+    }
+    targets
+}
+
+/// Итог отправки одному получателю
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SendOutcome {
+    pub target: String,
+    pub error: Option<String>,
+}
+
+/// Отчёт о рассылке по нескольким чатам
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SendReport {
+    pub outcomes: Vec<SendOutcome>,
+}
+
+impl SendReport {
+    pub fn record(&mut self, target: &str, result: Result<()>) {
+        self.outcomes.push(SendOutcome {
+            target: target.to_string(),
+            error: result.err().map(|e| e.to_string()),
+        });
+    }
+
+    pub fn succeeded(&self) -> usize {
+        self.outcomes.iter().filter(|o| o.error.is_none()).count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.outcomes.len() - self.succeeded()
+    }
+}
+
+impl fmt::Display for SendReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for outcome in &self.outcomes {
+            match &outcome.error {
+                None => writeln!(f, "✓ {}", outcome.target)?,
+                Some(error) => writeln!(f, "✗ {}: {}", outcome.target, error)?,
+            }
+        }
+        write!(
+            f,
+            "Отправлено: {}, ошибок: {}",
+            self.succeeded(),
+            self.failed()
+        )
+    }
+}
 
 #[derive(Debug, PartialEq, Eq)]
 enum Target<'a> {
@@ -39,23 +237,32 @@ fn get_peer_id(peer: &Peer) -> i64 {
 }
 
 /// Send a message to a user by ID
-pub async fn send_to_user(user_id: i64, message: &str) -> Result<()> {
-    let _lock = SessionLock::acquire()?;
+pub async fn send_to_user(user_id: i64, message: &MessageText) -> Result<()> {
+    let _lock = SessionLock::acquire().await?;
     let client = get_client().await?;
+    deliver_to_user(&client, user_id, message).await
+}
 
-    // Find user in dialogs
+async fn deliver_to_user(
+    client: &TelegramClient,
+    user_id: i64,
+    message: &MessageText,
+) -> Result<()> {
+    let peer = find_user(client, user_id).await?;
+    client
+        .send_message(&peer, message.render_for(&peer)?)
+        .await?;
+    println!("✓ Сообщение отправлено пользователю {}", user_id);
+    Ok(())
+}
+
+/// Find a user among the dialogs by ID
+async fn find_user(client: &TelegramClient, user_id: i64) -> Result<Peer> {
     let mut dialogs = client.iter_dialogs();
-    while let Some(dialog) = dialogs.next().await.transpose() {
-        if let Ok(dialog) = dialog {
-            if let Peer::User(_) = &dialog.peer {
-                if get_peer_id(&dialog.peer) == user_id {
-                    client
-                        .send_message(&dialog.peer, message)
-                        .await
-                        .map_err(|e| Error::TelegramError(e.to_string()))?;
-                    println!("✓ Сообщение отправлено пользователю {}", user_id);
-                    return Ok(());
-                }
+    while let Some(dialog) = client.next_dialog(&mut dialogs).await? {
+        if let Peer::User(_) = &dialog.peer {
+            if get_peer_id(&dialog.peer) == user_id {
+                return Ok(dialog.peer);
             }
         }
     }
@@ -67,91 +274,194 @@ pub async fn send_to_user(user_id: i64, message: &str) -> Result<()> {
 }
 
 /// Send a message to a chat by name (from config)
-pub async fn send_to_chat(chat_name: &str, message: &str) -> Result<()> {
+pub async fn send_to_chat(chat_name: &str, message: &MessageText) -> Result<()> {
     let config = Config::new();
+    let _lock = SessionLock::acquire().await?;
+    let client = get_client().await?;
+    deliver_to_chat(&client, &config, chat_name, message).await
+}
+
+async fn deliver_to_chat(
+    client: &TelegramClient,
+    config: &Config,
+    chat_name: &str,
+    message: &MessageText,
+) -> Result<()> {
+    let peer = find_configured_chat(client, config, chat_name).await?;
+    client
+        .send_message(&peer, message.render_for(&peer)?)
+        .await?;
+    println!("✓ Сообщение отправлено в {}", chat_name);
+    Ok(())
+}
+
+/// Find a chat by its alias from config
+async fn find_configured_chat(
+    client: &TelegramClient,
+    config: &Config,
+    chat_name: &str,
+) -> Result<Peer> {
     let chat_entity = config
         .get_chat(chat_name)
         .ok_or_else(|| Error::InvalidArgument(format!("Чат '{}' не найден в конфиге", chat_name)))?
         .clone();
 
-    let _lock = SessionLock::acquire()?;
-    let client = get_client().await?;
-
     match chat_entity {
         ChatEntity::Channel(id) | ChatEntity::Chat(id) => {
             // Find channel/chat in dialogs
             let mut dialogs = client.iter_dialogs();
-            while let Some(dialog) = dialogs.next().await.transpose() {
-                if let Ok(dialog) = dialog {
-                    let is_channel_or_group =
-                        matches!(&dialog.peer, Peer::Channel(_) | Peer::Group(_));
-                    if is_channel_or_group && get_peer_id(&dialog.peer) == id {
-                        client
-                            .send_message(&dialog.peer, message)
-                            .await
-                            .map_err(|e| Error::TelegramError(e.to_string()))?;
-                        println!("✓ Сообщение отправлено в {}", chat_name);
-                        return Ok(());
-                    }
+            while let Some(dialog) = client.next_dialog(&mut dialogs).await? {
+                let is_channel_or_group = matches!(&dialog.peer, Peer::Channel(_) | Peer::Group(_));
+                if is_channel_or_group && get_peer_id(&dialog.peer) == id {
+                    return Ok(dialog.peer);
                 }
             }
             Err(Error::InvalidArgument(format!("Чат {} не найден", id)))
         }
-        ChatEntity::Username(username) => {
-            let entity = client
-                .resolve_username(&username)
-                .await
-                .map_err(|e| Error::TelegramError(e.to_string()))?
-                .ok_or_else(|| {
-                    Error::InvalidArgument(format!("Username @{} не найден", username))
-                })?;
-
-            client
-                .send_message(&entity, message)
-                .await
-                .map_err(|e| Error::TelegramError(e.to_string()))?;
-            println!("✓ Сообщение отправлено @{}", username);
-            Ok(())
-        }
-        ChatEntity::UserId(id) => {
-            // Need to drop lock before recursive call
-            drop(_lock);
-            send_to_user(id, message).await
-        }
+        ChatEntity::Username(username) => find_username(client, &username).await,
+        ChatEntity::UserId(id) => find_user(client, id).await,
     }
 }
 
 /// Send a message to username directly
-pub async fn send_to_username(username: &str, message: &str) -> Result<()> {
-    let _lock = SessionLock::acquire()?;
+pub async fn send_to_username(username: &str, message: &MessageText) -> Result<()> {
+    let _lock = SessionLock::acquire().await?;
     let client = get_client().await?;
+    deliver_to_username(&client, username, message).await
+}
 
-    let username = username.strip_prefix('@').unwrap_or(username);
-
-    let entity = client
-        .resolve_username(username)
-        .await
-        .map_err(|e| Error::TelegramError(e.to_string()))?
-        .ok_or_else(|| Error::InvalidArgument(format!("Username @{} не найден", username)))?;
+async fn deliver_to_username(
+    client: &TelegramClient,
+    username: &str,
+    message: &MessageText,
+) -> Result<()> {
+    let peer = find_username(client, username).await?;
+    client
+        .send_message(&peer, message.render_for(&peer)?)
+        .await?;
+    println!(
+        "✓ Сообщение отправлено @{}",
+        username.strip_prefix('@').unwrap_or(username)
+    );
+    Ok(())
+}
 
+/// Resolve `@username` (the `@` is optional)
+async fn find_username(client: &TelegramClient, username: &str) -> Result<Peer> {
+    let username = username.strip_prefix('@').unwrap_or(username);
     client
-        .send_message(&entity, message)
-        .await
-        .map_err(|e| Error::TelegramError(e.to_string()))?;
+        .with_reconnect(|client| async move { client.resolve_username(username).await })
+        .await?
+        .ok_or_else(|| Error::InvalidArgument(format!("Username @{} не найден", username)))
+}
+
+/// Send to one target of any kind over an open session, substituting `{chat}`
+async fn deliver(
+    client: &TelegramClient,
+    config: &Config,
+    target: &str,
+    message: &MessageText,
+) -> Result<()> {
+    let message = message.with_chat(target);
+    match parse_target(target) {
+        Target::Username(username) => deliver_to_username(client, username, &message).await,
+        Target::UserId(user_id) => deliver_to_user(client, user_id, &message).await,
+        Target::ChatName(chat_name) => deliver_to_chat(client, config, chat_name, &message).await,
+    }
+}
 
-    println!("✓ Сообщение отправлено @{}", username);
+/// Send ready-made parts to one target over an open session, in order.
+///
+/// The text goes out as is: no `{chat}` substitution, quiet hours or send cap.
+pub async fn deliver_parts(client: &TelegramClient, target: &str, parts: &[String]) -> Result<()> {
+    let config = Config::new();
+    let peer = match parse_target(target) {
+        Target::Username(username) => find_username(client, username).await?,
+        Target::UserId(user_id) => find_user(client, user_id).await?,
+        Target::ChatName(chat_name) => find_configured_chat(client, &config, chat_name).await?,
+    };
+    for part in parts {
+        client.send_message(&peer, part.as_str()).await?;
+    }
     Ok(())
 }
 
-/// CLI entry point
-pub async fn run(target: &str, message: &str) -> Result<()> {
-    match parse_target(target) {
-        Target::Username(username) => send_to_username(username, message).await,
-        Target::UserId(user_id) => send_to_user(user_id, message).await,
-        Target::ChatName(chat_name) => send_to_chat(chat_name, message).await,
+/// Тихие часы откладывают отправку, а не отменяют её
+async fn wait_for_quiet_hours(quiet: Option<&QuietHours>) {
+    if let Some(quiet) = quiet {
+        let now = Utc::now();
+        if quiet.is_quiet(now) {
+            println!(
+                "🌙 Тихие часы: отправка отложена до {}",
+                quiet.format_next_allowed(now)
+            );
+            quiet.wait().await;
+        }
     }
 }
 
+/// CLI entry point for a one-off send.
+///
+/// Manual sends (and `digest --post-to`) are not counted towards the daily
+/// send cap; only `run_many` and other bulk commands are.
+pub async fn run(target: &str, message: &MessageText) -> Result<()> {
+    wait_for_quiet_hours(QuietHours::from_env()?.as_ref()).await;
+
+    let config = Config::new();
+    let _lock = SessionLock::acquire().await?;
+    let client = get_client().await?;
+    deliver(&client, &config, target, message).await
+}
+
+/// Send the same message to several targets, `delay` apart.
+///
+/// The session is locked and connected once for the whole run and let go
+/// while waiting out quiet hours. A failed send is recorded in the report and
+/// the rest still go out; only the daily cap stops the run, leaving the
+/// remaining targets queued.
+pub async fn run_many(
+    targets: &[String],
+    message: &MessageText,
+    delay: Duration,
+) -> Result<SendReport> {
+    let mut limiter = DailySendLimiter::from_env(None)?;
+    let quiet = QuietHours::from_env()?;
+    let config = Config::new();
+    let mut report = SendReport::default();
+
+    let mut session = HeldSession::default();
+
+    for (idx, target) in targets.iter().enumerate() {
+        if idx > 0 {
+            sleep(delay).await;
+        }
+        if quiet.as_ref().is_some_and(|q| q.is_quiet(Utc::now())) {
+            // Other scripts may use the session while we wait
+            session.release();
+            wait_for_quiet_hours(quiet.as_ref()).await;
+        }
+        let client = session.client().await?;
+
+        // Claimed right before the send, so parallel runs share the cap
+        if !limiter.try_reserve()? {
+            let queued = targets.len() - idx;
+            limiter.record_queued(queued)?;
+            for target in &targets[idx..] {
+                report.record(target, Err(limiter.cap_reached(queued)));
+            }
+            break;
+        }
+
+        let result = deliver(client, &config, target, message).await;
+        if result.is_err() {
+            limiter.release()?;
+        }
+        report.record(target, result);
+    }
+
+    Ok(report)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -173,4 +483,127 @@ mod tests {
     fn parse_target_falls_back_to_chat_name() {
         assert_eq!(parse_target("chat_alpha"), Target::ChatName("chat_alpha"));
     }
+
+    #[test]
+    fn parse_targets_splits_on_commas() {
+        assert_eq!(
+            parse_targets("chat_alpha, @bob,123,,chat_alpha "),
+            vec!["chat_alpha", "@bob", "123"]
+        );
+        assert!(parse_targets(" , ").is_empty());
+    }
+
+    #[test]
+    fn chat_placeholder_is_substituted_per_target() {
+        let plain = MessageText::Plain("Анонс для {chat}: встреча в {chat}".to_string());
+        assert_eq!(
+            plain.with_chat("chat_alpha"),
+            MessageText::Plain("Анонс для chat_alpha: встреча в chat_alpha".to_string())
+        );
+
+        let template = MessageText::Template {
+            template: "{chat} / {{chat_title}}".to_string(),
+            strict: true,
+        };
+        assert_eq!(
+            template.with_chat("@bob"),
+            MessageText::Template {
+                template: "@bob / {{chat_title}}".to_string(),
+                strict: true
+            }
+        );
+    }
+
+    #[test]
+    fn report_lists_each_chat() {
+        let mut report = SendReport::default();
+        report.record("chat_alpha", Ok(()));
+        report.record(
+            "chat_beta",
+            Err(Error::InvalidArgument(
+                "Чат 'chat_beta' не найден в конфиге".to_string(),
+            )),
+        );
+        report.record("@bob", Ok(()));
+
+        assert_eq!(report.succeeded(), 2);
+        assert_eq!(report.failed(), 1);
+        assert_eq!(
+            report.to_string(),
+            "✓ chat_alpha\n✗ chat_beta: Invalid argument: Чат 'chat_beta' не найден в конфиге\n✓ @bob\nОтправлено: 2, ошибок: 1"
+        );
+    }
+
+    #[test]
+    fn split_message_keeps_short_text_whole() {
+        assert_eq!(split_message("Привет\n\nмир", 4096), vec!["Привет\n\nмир"]);
+        assert!(split_message("", 4096).is_empty());
+    }
+
+    #[test]
+    fn split_message_breaks_on_paragraphs() {
+        let text = format!(
+            "{}\n\n{}\n\n{}",
+            "a".repeat(6),
+            "b".repeat(6),
+            "c".repeat(3)
+        );
+        assert_eq!(
+            split_message(&text, 12),
+            vec!["aaaaaa".to_string(), "bbbbbb\n\nccc".to_string()]
+        );
+    }
+
+    #[test]
+    fn split_message_falls_back_to_lines_and_chars() {
+        let text = format!("{}\n{}", "x".repeat(5), "y".repeat(12));
+        let parts = split_message(&text, 8);
+        assert_eq!(parts, vec!["xxxxx", "yyyyyyyy", "yyyy"]);
+
+        // Emoji take two UTF-16 units, as Telegram counts them
+        let parts = split_message(&"🔥".repeat(5), 4);
+        assert_eq!(parts, vec!["🔥🔥", "🔥🔥", "🔥"]);
+        assert!(parts.iter().all(|p| utf16_len(p) <= 4));
+    }
+
+    fn render(template: &str, first_name: Option<&str>, strict: bool) -> Result<String> {
+        let date = NaiveDate::from_ymd_opt(2024, 12, 31).unwrap();
+        let vars = template_vars("Rust Chat", first_name, date);
+        render_template(template, &vars, strict)
+    }
+
+    #[test]
+    fn template_vars_fill_recipient_fields() {
+        let text = render(
+            "{{first_name}}, итоги {{date}} в {{chat_title}}",
+            Some("Анна"),
+            true,
+        )
+        .unwrap();
+        assert_eq!(text, "Анна, итоги 31.12.2024 в Rust Chat");
+    }
+
+    #[test]
+    fn first_name_is_missing_for_groups() {
+        let template = "Привет, {{first_name}}!";
+        assert_eq!(render(template, None, false).unwrap(), "Привет, !");
+        assert!(render(template, None, true).is_err());
+    }
+
+    #[test]
+    fn template_file_is_loaded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("msg.txt");
+        std::fs::write(&path, "Hi {{chat_title}}").unwrap();
+
+        let message = MessageText::from_template_file(&path, true).unwrap();
+        assert_eq!(
+            message,
+            MessageText::Template {
+                template: "Hi {{chat_title}}".to_string(),
+                strict: true
+            }
+        );
+        assert!(MessageText::from_template_file(&dir.path().join("none"), true).is_err());
+    }
 }
@@ -1,35 +1,146 @@
 //! Отправка заранее подготовленных вопросов в несколько чатов.
 //! Порт Python-скрипта `send_viral_question.py`.
+//!
+//! Вопросы можно загрузить из YAML/JSON файла (`--questions`):
+//!
+//! ```yaml
+//! - question: "Какой язык учить первым?"
+//!   chats: ["Golang GO", "вайбкодеры"]
+//!   weight: 3
+//! ```
+//!
+//! Вопросы с большим весом отправляются первыми — важно, когда дневной лимит
+//! обрывает рассылку. Без файла используется встроенный набор.
 
+use std::collections::HashSet;
+use std::path::Path;
 use std::time::Duration;
 
+use chrono::Utc;
 use grammers_client::types::peer::Peer;
+use serde::Deserialize;
 use tokio::time::sleep;
 
+use crate::commands::send_message::MessageText;
+use crate::dry_run::{ActionKind, DryRunPlan, PlanFormat};
 use crate::error::{Error, Result};
-use crate::session::{get_client, SessionLock};
+use crate::quiet_hours::QuietHours;
+use crate::send_limit::DailySendLimiter;
+use crate::session::HeldSession;
 
-/// Вопрос с указанием подстроки, по которой ищем чат.
-struct ViralQuestion {
+/// Вопрос для рассылки: текст, подстроки названий чатов и вес.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ViralQuestion {
+    pub question: String,
+    /// Подстроки названий чатов, в которые отправляется вопрос
+    pub chats: Vec<String>,
+    /// Чем больше вес, тем раньше вопрос отправляется
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+impl ViralQuestion {
+    fn validate(&self, index: usize) -> Result<()> {
+        let invalid =
+            |reason: &str| Error::InvalidArgument(format!("Вопрос #{}: {}", index + 1, reason));
+        if self.question.trim().is_empty() {
+            return Err(invalid("пустой текст вопроса"));
+        }
+        if self.chats.is_empty() {
+            return Err(invalid("не указаны чаты (chats)"));
+        }
+        if self.chats.iter().any(|chat| chat.trim().is_empty()) {
+            return Err(invalid("пустое название чата"));
+        }
+        if self.weight == 0 {
+            return Err(invalid("вес должен быть положительным"));
+        }
+        Ok(())
+    }
+}
+
+/// Загрузить вопросы из YAML или JSON (по расширению `.json`) и проверить их
+pub fn load_questions(path: &Path) -> Result<Vec<ViralQuestion>> {
+    let content = std::fs::read_to_string(path).map_err(|e| {
+        Error::InvalidArgument(format!(
+            "Не удалось прочитать вопросы {}: {}",
+            path.display(),
+            e
+        ))
+    })?;
+    let is_json = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    let parsed = if is_json {
+        serde_json::from_str(&content).map_err(|e| e.to_string())
+    } else {
+        serde_yaml::from_str(&content).map_err(|e| e.to_string())
+    };
+    let questions = parsed.map_err(|e| {
+        Error::InvalidArgument(format!(
+            "Некорректный файл вопросов {}: {}",
+            path.display(),
+            e
+        ))
+    })?;
+    validate_questions(questions).map_err(|e| match e {
+        Error::InvalidArgument(msg) => {
+            Error::InvalidArgument(format!("{}: {}", path.display(), msg))
+        }
+        other => other,
+    })
+}
+
+/// Проверить вопросы и упорядочить по весу (при равном весе — как в файле)
+pub fn validate_questions(mut questions: Vec<ViralQuestion>) -> Result<Vec<ViralQuestion>> {
+    if questions.is_empty() {
+        return Err(Error::InvalidArgument("нет ни одного вопроса".to_string()));
+    }
+    for (index, question) in questions.iter().enumerate() {
+        question.validate(index)?;
+    }
+    questions.sort_by_key(|q| std::cmp::Reverse(q.weight));
+    Ok(questions)
+}
+
+/// Встроенный набор вопросов
+pub fn builtin_questions() -> Vec<ViralQuestion> {
+    QUESTIONS
+        .iter()
+        .map(|q| ViralQuestion {
+            question: q.question.to_string(),
+            chats: vec![q.chat_match.to_string()],
+            weight: default_weight(),
+        })
+        .collect()
+}
+
+/// Встроенный вопрос с указанием подстроки, по которой ищем чат.
+struct BuiltinQuestion {
     chat_match: &'static str,
     question: &'static str,
 }
 
-const QUESTIONS: &[ViralQuestion] = &[
-    ViralQuestion {
+const QUESTIONS: &[BuiltinQuestion] = &[
+    BuiltinQuestion {
         chat_match: "Golang GO",
         question: "Реально ли попасть в Яндекс/Авито на Go без олимпиадных регалий в 2025?\n\n\
         Или там только ICPC финалисты?\n\n\
         Кто проходил собесы недавно — что спрашивали, сколько этапов, какие алгоритмы?",
     },
-    ViralQuestion {
+    BuiltinQuestion {
         chat_match: "вайбкодеры",
         question: "Claude Haiku 4.5 vs GPT-4.5-mini: кто реально выиграл?\n\n\
         Anthropic говорят что \"лучше всех на рынке\", OpenAI молчит. \
         Кто тестил обе модели на реальных задачах (не бенчмарки)? \
         Поделитесь примерами где одна слила другую.",
     },
-    ViralQuestion {
+    BuiltinQuestion {
         chat_match: "Хара",
         question: "Какая самая безумная синхрония случалась в вашей жизни?\n\n\
         У меня: читала книгу про лотерею → получила 'случайные' числа → поставила → \
@@ -38,6 +149,21 @@ const QUESTIONS: &[ViralQuestion] = &[
     },
 ];
 
+/// Параметры рассылки.
+#[derive(Debug, Clone, Default)]
+pub struct ViralOptions {
+    /// Дневной лимит сообщений на аккаунт (по умолчанию `DAILY_SEND_CAP`).
+    pub daily_cap: Option<u32>,
+    /// Вопросы из файла (`--questions`); без них — встроенный набор.
+    pub questions: Option<Vec<ViralQuestion>>,
+    /// Шаблон вместо заготовленных вопросов, рендерится для каждого чата.
+    pub template: Option<MessageText>,
+    /// Пробный запуск: собрать план отправки, ничего не отправляя.
+    pub dry_run: Option<PlanFormat>,
+    /// Тихие часы (по умолчанию `QUIET_HOURS`/`QUIET_HOURS_TZ`).
+    pub quiet_hours: Option<QuietHours>,
+}
+
 #[derive(Clone)]
 struct AvailableChat {
     title: String,
@@ -54,37 +180,111 @@ fn chat_title(peer: &Peer) -> String {
 }
 
 /// Отправляет вопросы в чаты, найденные по подстроке.
-pub async fn run() -> Result<()> {
+pub async fn run(options: ViralOptions) -> Result<()> {
     println!("📤 Отправка виральных вопросов...");
 
-    // Блокируем сессию на время отправки.
-    let _lock = SessionLock::acquire()?;
-    let client = get_client().await?;
+    // Общий для всех рассылок дневной лимит, переживает перезапуски.
+    let mut limiter = DailySendLimiter::from_env(options.daily_cap)?;
+    println!(
+        "🛡 Лимит на сегодня: осталось {} из {}",
+        limiter.remaining(),
+        limiter.cap()
+    );
+
+    let quiet_hours = match options.quiet_hours {
+        Some(quiet) => Some(quiet),
+        None => QuietHours::from_env()?,
+    };
+
+    // Блокируем сессию на время отправки (кроме ожидания тихих часов).
+    let mut session = HeldSession::default();
 
     // Считаем диалоги заранее, чтобы не итерировать несколько раз.
-    let mut dialogs = client.iter_dialogs();
     let mut chats: Vec<AvailableChat> = Vec::new();
-
-    while let Some(dialog) = dialogs.next().await.transpose() {
-        let dialog = dialog.map_err(|e| Error::TelegramError(e.to_string()))?;
-        let title = chat_title(&dialog.peer);
-        chats.push(AvailableChat {
-            title_lower: title.to_lowercase(),
-            title,
-            peer: dialog.peer,
-        });
+    {
+        let client = session.client().await?;
+        let mut dialogs = client.iter_dialogs();
+        while let Some(dialog) = client.next_dialog(&mut dialogs).await? {
+            let title = chat_title(&dialog.peer);
+            chats.push(AvailableChat {
+                title_lower: title.to_lowercase(),
+                title,
+                peer: dialog.peer,
+            });
+        }
     }
 
+    let mut plan = DryRunPlan::new("send_viral");
+
+    // Каждая пара «вопрос — чат» отправляется отдельно.
+    let questions = options.questions.unwrap_or_else(builtin_questions);
+    let sends: Vec<(&ViralQuestion, &str)> = questions
+        .iter()
+        .flat_map(|q| q.chats.iter().map(move |chat| (q, chat.as_str())))
+        .collect();
+
+    // С шаблоном текст не зависит от вопроса: чат из нескольких вопросов
+    // получает его один раз.
+    let mut templated: HashSet<usize> = HashSet::new();
+
     // Проходим по списку вопросов и отправляем.
-    for (idx, question) in QUESTIONS.iter().enumerate() {
-        let needle = question.chat_match.to_lowercase();
-        let target = chats.iter().find(|c| c.title_lower.contains(&needle));
+    for (idx, (question, chat_match)) in sends.iter().enumerate() {
+        let needle = chat_match.to_lowercase();
+        let target = chats.iter().position(|c| c.title_lower.contains(&needle));
+
+        if let Some(chat_idx) = target {
+            if options.template.is_some() && !templated.insert(chat_idx) {
+                println!(
+                    "↪️ [{}] '{}' уже получил шаблон, пропускаю",
+                    idx + 1,
+                    chats[chat_idx].title
+                );
+                continue;
+            }
+            let chat = &chats[chat_idx];
+            let text = match &options.template {
+                Some(template) => template.render_for(&chat.peer)?,
+                None => question.question.clone(),
+            };
 
-        if let Some(chat) = target {
-            client
-                .send_message(&chat.peer, question.question)
-                .await
-                .map_err(|e| Error::TelegramError(e.to_string()))?;
+            if options.dry_run.is_some() {
+                let preview: String = text.chars().take(50).collect();
+                plan.record(ActionKind::SendMessage, &chat.title, None, preview);
+                println!("🧪 [{}] Будет отправлено в '{}'", idx + 1, chat.title);
+                continue;
+            }
+
+            // В тихие часы ждём окончания окна, вопросы остаются в очереди.
+            // Сессию и соединение на это время отпускаем.
+            if let Some(quiet) = &quiet_hours {
+                let now = Utc::now();
+                if quiet.is_quiet(now) {
+                    println!(
+                        "🌙 Тихие часы: отправка в '{}' отложена до {}",
+                        chat.title,
+                        quiet.format_next_allowed(now)
+                    );
+                    session.release();
+                    quiet.wait().await;
+                }
+            }
+            let client = session.client().await?;
+
+            // Слот под лимит занимаем прямо перед отправкой: счётчик общий
+            // для всех запущенных рассылок.
+            if !limiter.try_reserve()? {
+                let queued = sends.len() - idx;
+                limiter.record_queued(queued)?;
+                eprintln!(
+                    "⛔ Дневной лимит исчерпан, в очереди осталось {} вопрос(ов)",
+                    queued
+                );
+                return Err(limiter.cap_reached(queued));
+            }
+            if let Err(e) = client.send_message(&chat.peer, text).await {
+                limiter.release()?;
+                return Err(e);
+            }
 
             println!("✅ [{}] Отправлено в '{}'", idx + 1, chat.title);
             // Лёгкая задержка как в Python-версии.
@@ -92,11 +292,15 @@ pub async fn run() -> Result<()> {
         } else {
             eprintln!(
                 "❌ Чат '{}' не найден среди диалогов, пропускаю",
-                question.chat_match
+                chat_match
             );
         }
     }
 
+    if let Some(format) = options.dry_run {
+        return plan.print(format);
+    }
+
     println!("\n✅ Все вопросы обработаны!");
     println!("📊 Отслеживайте реакции в первые минуты после отправки.");
 
@@ -125,4 +329,81 @@ mod tests {
         // Note: We can't fully test this without a real Peer, but we can verify the struct exists
         assert!(QUESTIONS.len() >= 2);
     }
+
+    fn write(dir: &tempfile::TempDir, name: &str, content: &str) -> std::path::PathBuf {
+        let path = dir.path().join(name);
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn builtin_questions_match_one_chat_each() {
+        let questions = builtin_questions();
+        assert_eq!(questions.len(), QUESTIONS.len());
+        assert!(questions
+            .iter()
+            .all(|q| q.chats.len() == 1 && q.weight == 1));
+        assert!(validate_questions(questions).is_ok());
+    }
+
+    #[test]
+    fn loads_yaml_and_orders_by_weight() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write(
+            &dir,
+            "questions.yaml",
+            "- question: Первый\n  chats: [Golang GO]\n\
+             - question: Важный\n  chats: [Хара, вайбкодеры]\n  weight: 5\n",
+        );
+
+        let questions = load_questions(&path).unwrap();
+        assert_eq!(questions[0].question, "Важный");
+        assert_eq!(questions[0].chats, vec!["Хара", "вайбкодеры"]);
+        assert_eq!(questions[1].question, "Первый");
+        assert_eq!(questions[1].weight, 1);
+    }
+
+    #[test]
+    fn loads_json_by_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write(
+            &dir,
+            "questions.json",
+            r#"[{"question": "Q", "chats": ["Golang GO"], "weight": 2}]"#,
+        );
+        let questions = load_questions(&path).unwrap();
+        assert_eq!(questions.len(), 1);
+        assert_eq!(questions[0].weight, 2);
+    }
+
+    #[test]
+    fn malformed_entries_are_rejected_with_their_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let cases = [
+            ("- question: Q\n  chats: []\n", "Вопрос #1: не указаны чаты"),
+            (
+                "- question: Q\n  chats: [a]\n- question: \"  \"\n  chats: [b]\n",
+                "Вопрос #2: пустой текст вопроса",
+            ),
+            (
+                "- question: Q\n  chats: [a]\n  weight: 0\n",
+                "вес должен быть",
+            ),
+            ("- question: Q\n  chats: [\"\"]\n", "пустое название чата"),
+            ("[]\n", "нет ни одного вопроса"),
+            ("- question: Q\n", "missing field `chats`"),
+            (
+                "- question: Q\n  chats: [a]\n  wieght: 2\n",
+                "unknown field `wieght`",
+            ),
+        ];
+        for (content, expected) in cases {
+            let path = write(&dir, "q.yaml", content);
+            let err = load_questions(&path).unwrap_err().to_string();
+            assert!(err.contains(expected), "{:?}: {}", content, err);
+            assert!(err.contains("q.yaml"), "{}", err);
+        }
+
+        assert!(load_questions(&dir.path().join("missing.yaml")).is_err());
+    }
 }
@@ -3,7 +3,7 @@
 //! Equivalent to Python's tg.py
 
 use crate::chat::resolve_chat;
-use crate::config::{ChatEntity, Config, MEDIA_REACTION_THRESHOLD_TG};
+use crate::config::{ChatEntity, Config};
 use crate::error::Result;
 use crate::export::{create_media_dir, ExportWriter};
 use crate::session::{get_client, SessionLock};
@@ -15,9 +15,10 @@ pub async fn run(chat_name: &str, limit: usize) -> Result<()> {
 
     // Support direct ID input or config lookup
     let chat_entity = resolve_chat_entity(chat_name, &config)?;
+    let media_threshold = config.reaction_threshold_tg(chat_name);
 
     // Acquire session lock
-    let _lock = SessionLock::acquire()?;
+    let _lock = SessionLock::acquire().await?;
 
     // Connect to Telegram
     let client = get_client().await?;
@@ -72,7 +73,7 @@ pub async fn run(chat_name: &str, limit: usize) -> Result<()> {
 
         // Handle media
         if msg.media().is_some() {
-            if reactions >= MEDIA_REACTION_THRESHOLD_TG {
+            if reactions >= media_threshold {
                 create_media_dir(chat_name)?;
                 let file_path = format!("{}/media_{}.bin", chat_name, msg.id());
                 println!(
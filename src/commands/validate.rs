@@ -0,0 +1,28 @@
+//! Config validation command
+//!
+//! Checks config.yml and the environment before anything touches the network.
+
+use crate::config::Config;
+use crate::error::{Error, Result};
+
+pub fn run() -> Result<()> {
+    let config =
+        Config::try_new().map_err(|e| Error::InvalidArgument(format!("config.yml: {}", e)))?;
+
+    let problems = config.validate();
+    if problems.is_empty() {
+        println!("config.yml OK: {} chat(s) configured", config.chats.len());
+        return Ok(());
+    }
+
+    for problem in &problems {
+        println!("{}", problem);
+    }
+
+    if config.has_fatal_problems() {
+        return Err(Error::InvalidArgument(
+            "config.yml has fatal problems".to_string(),
+        ));
+    }
+    Ok(())
+}
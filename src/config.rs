@@ -7,6 +7,7 @@ use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 use std::sync::LazyLock;
+use tracing::warn;
 
 /// Default constants (fallback if config.yml not found)
 pub const SESSION_NAME: &str = "telegram_session";
@@ -59,6 +60,8 @@ struct YamlConfig {
     limits: Option<LimitsConfig>,
     chats: Option<HashMap<String, ChatConfig>>,
     openai: Option<OpenAIConfig>,
+    #[serde(default)]
+    important_senders: Vec<serde_yaml::Value>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -97,7 +100,6 @@ where
 }
 
 #[derive(Debug, Deserialize)]
-#[allow(dead_code)]
 struct LimitsConfig {
     default: Option<usize>,
     ci: Option<usize>,
@@ -110,9 +112,13 @@ struct LimitsConfig {
 struct ChatConfig {
     #[serde(rename = "type")]
     chat_type: String,
-    id: Option<i64>,
+    /// Number or `${VAR}` placeholder
+    #[serde(default, deserialize_with = "deserialize_string_or_number")]
+    id: Option<String>,
     username: Option<String>,
     title: Option<String>,
+    /// Overrides the global media-reaction thresholds for this chat
+    media_reaction_threshold: Option<i32>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -139,10 +145,20 @@ pub struct Config {
     pub my_user_id: i64,
     pub default_limit: usize,
     pub ci_limit: usize,
+    /// Reactions a media message needs before `read` downloads it
+    pub media_reaction_threshold: i32,
+    /// Same threshold for the `tg` export
+    pub media_reaction_threshold_tg: i32,
     pub chats: HashMap<String, ChatEntity>,
+    /// Per-chat threshold overrides (chat alias -> reactions)
+    pub reaction_thresholds: HashMap<String, i32>,
+    /// Chats left out of `chats` because a `${VAR}` did not resolve, with the reason
+    pub skipped_chats: Vec<String>,
     pub openai_model: String,
     pub openai_max_tokens: u32,
     pub openai_temperature: f32,
+    /// Sender ids/usernames whose messages digest and analysis always keep
+    pub important_senders: Vec<String>,
 }
 
 impl Default for Config {
@@ -155,9 +171,12 @@ impl Config {
     /// Load configuration from config.yml or use defaults
     /// Environment variables take precedence over config.yml values
     pub fn new() -> Self {
-        Self::load_from_file("config.yml")
-            .or_else(|_| Self::load_from_file("../config.yml"))
-            .unwrap_or_else(|_| Self::defaults())
+        Self::try_new().unwrap_or_else(|_| Self::defaults())
+    }
+
+    /// Like [`Config::new`], but reports a missing or unparsable config.yml
+    pub fn try_new() -> Result<Self, String> {
+        Self::load_from_file("config.yml").or_else(|_| Self::load_from_file("../config.yml"))
     }
 
     /// Resolve a value: prefer env var if config value looks like ${VAR}
@@ -231,6 +250,31 @@ impl Config {
         0
     }
 
+    /// Expand a `${VAR}` chat field; a missing variable is an error so the
+    /// chat is skipped instead of pointing at a bogus id
+    fn interpolate_env(value: Option<String>) -> Result<Option<String>, String> {
+        match value {
+            Some(v) if v.starts_with("${") && v.ends_with('}') => {
+                let var_name = &v[2..v.len() - 1];
+                std::env::var(var_name)
+                    .map(Some)
+                    .map_err(|_| format!("environment variable {} is not set", var_name))
+            }
+            other => Ok(other),
+        }
+    }
+
+    /// Chat id after `${VAR}` expansion
+    fn resolve_chat_id(value: Option<String>) -> Result<Option<i64>, String> {
+        Self::interpolate_env(value)?
+            .map(|raw| {
+                raw.trim()
+                    .parse::<i64>()
+                    .map_err(|_| format!("id {:?} is not a number", raw))
+            })
+            .transpose()
+    }
+
     /// Load .env file into environment variables using dotenvy
     fn load_dotenv() {
         // Try to load from current directory first, then parent
@@ -277,32 +321,44 @@ impl Config {
 
         // Parse chats
         let mut chats = HashMap::new();
+        let mut reaction_thresholds = HashMap::new();
+        let mut skipped_chats = Vec::new();
         if let Some(yaml_chats) = yaml.chats {
             for (name, chat_config) in yaml_chats {
+                let resolved = Self::resolve_chat_id(chat_config.id)
+                    .and_then(|id| Ok((id, Self::interpolate_env(chat_config.username)?)));
+                let (id, username) = match resolved {
+                    Ok(values) => values,
+                    Err(problem) => {
+                        warn!("Skipping chat '{}': {}", name, problem);
+                        skipped_chats.push(format!("chat '{}': {}", name, problem));
+                        continue;
+                    }
+                };
                 let entity = match chat_config.chat_type.as_str() {
                     "channel" => {
-                        if let Some(id) = chat_config.id {
+                        if let Some(id) = id {
                             ChatEntity::Channel(id)
                         } else {
                             continue;
                         }
                     }
                     "group" => {
-                        if let Some(id) = chat_config.id {
+                        if let Some(id) = id {
                             ChatEntity::Chat(id)
                         } else {
                             continue;
                         }
                     }
                     "user" => {
-                        if let Some(id) = chat_config.id {
+                        if let Some(id) = id {
                             ChatEntity::UserId(id)
                         } else {
                             continue;
                         }
                     }
                     "username" => {
-                        if let Some(username) = chat_config.username {
+                        if let Some(username) = username {
                             ChatEntity::Username(username)
                         } else {
                             continue;
@@ -310,10 +366,23 @@ impl Config {
                     }
                     _ => continue,
                 };
+                if let Some(threshold) = chat_config.media_reaction_threshold {
+                    reaction_thresholds.insert(name.clone(), threshold);
+                }
                 chats.insert(name, entity);
             }
         }
 
+        let important_senders = yaml
+            .important_senders
+            .into_iter()
+            .filter_map(|value| match value {
+                serde_yaml::Value::String(s) => Some(s),
+                serde_yaml::Value::Number(n) => Some(n.to_string()),
+                _ => None,
+            })
+            .collect();
+
         // Resolve values with env var precedence
         let api_id = Self::resolve_env_i32(telegram.api_id, "TELEGRAM_API_ID");
         let api_hash = Self::resolve_env_string(telegram.api_hash, "TELEGRAM_API_HASH");
@@ -331,10 +400,19 @@ impl Config {
             my_user_id,
             default_limit: limits.default.unwrap_or(DEFAULT_LIMIT),
             ci_limit: limits.ci.unwrap_or(CI_LIMIT),
+            media_reaction_threshold: limits
+                .media_reaction_threshold
+                .unwrap_or(MEDIA_REACTION_THRESHOLD),
+            media_reaction_threshold_tg: limits
+                .media_reaction_threshold_tg
+                .unwrap_or(MEDIA_REACTION_THRESHOLD_TG),
             chats,
+            reaction_thresholds,
+            skipped_chats,
             openai_model: openai.model.unwrap_or_else(|| "gpt-4o-mini".to_string()),
             openai_max_tokens: openai.max_tokens.unwrap_or(150),
             openai_temperature: openai.temperature.unwrap_or(0.7),
+            important_senders,
         })
     }
 
@@ -350,10 +428,15 @@ impl Config {
             my_user_id: 0,
             default_limit: DEFAULT_LIMIT,
             ci_limit: CI_LIMIT,
+            media_reaction_threshold: MEDIA_REACTION_THRESHOLD,
+            media_reaction_threshold_tg: MEDIA_REACTION_THRESHOLD_TG,
             chats: HashMap::new(),
+            reaction_thresholds: HashMap::new(),
+            skipped_chats: Vec::new(),
             openai_model: "gpt-4o-mini".to_string(),
             openai_max_tokens: 150,
             openai_temperature: 0.7,
+            important_senders: Vec::new(),
         }
     }
 
@@ -362,6 +445,58 @@ impl Config {
         self.chats.get(name)
     }
 
+    /// Media-reaction threshold for `read`: the chat's own value, else the global one
+    pub fn reaction_threshold(&self, chat_name: &str) -> i32 {
+        self.reaction_thresholds
+            .get(chat_name)
+            .copied()
+            .unwrap_or(self.media_reaction_threshold)
+    }
+
+    /// Media-reaction threshold for `tg`: the chat's own value, else the global one
+    pub fn reaction_threshold_tg(&self, chat_name: &str) -> i32 {
+        self.reaction_thresholds
+            .get(chat_name)
+            .copied()
+            .unwrap_or(self.media_reaction_threshold_tg)
+    }
+
+    /// Problems that would make commands fail later, fatal ones (`error: `) first
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems: Vec<String> = self
+            .fatal_problems()
+            .into_iter()
+            .map(|p| format!("error: {}", p))
+            .collect();
+        if self.chats.is_empty() {
+            problems.push(
+                "warning: no chats configured; commands take raw ids or @usernames only"
+                    .to_string(),
+            );
+        }
+        problems
+    }
+
+    /// Whether [`Config::validate`] found anything that stops commands from working
+    pub fn has_fatal_problems(&self) -> bool {
+        !self.fatal_problems().is_empty()
+    }
+
+    fn fatal_problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.api_id == 0 {
+            problems.push("telegram.api_id is 0 (set it or TELEGRAM_API_ID)".to_string());
+        }
+        if self.api_hash.trim().is_empty() {
+            problems.push("telegram.api_hash is empty (set it or TELEGRAM_API_HASH)".to_string());
+        }
+        if self.phone.trim().is_empty() {
+            problems.push("telegram.phone is missing (set it or TELEGRAM_PHONE)".to_string());
+        }
+        problems.extend(self.skipped_chats.iter().cloned());
+        problems
+    }
+
     /// Check if running in GitHub Actions
     pub fn is_github_actions() -> bool {
         std::env::var("GITHUB_ACTIONS")
@@ -767,6 +902,125 @@ chats:
         std::fs::remove_file(temp_file).ok();
     }
 
+    #[test]
+    fn per_chat_reaction_threshold_overrides_global() {
+        let yaml = r#"
+limits:
+  media_reaction_threshold: 5000
+chats:
+  big_channel:
+    type: channel
+    id: 1
+  small_group:
+    type: group
+    id: 2
+    media_reaction_threshold: 10
+"#;
+        let temp_file = std::env::temp_dir().join("config_reaction_threshold.yml");
+        std::fs::write(&temp_file, yaml).unwrap();
+
+        let config = Config::load_from_file(&temp_file).unwrap();
+
+        assert_eq!(config.reaction_threshold("small_group"), 10);
+        assert_eq!(config.reaction_threshold("big_channel"), 5000);
+        assert_eq!(config.reaction_threshold("unknown"), 5000);
+        assert_eq!(config.reaction_threshold_tg("small_group"), 10);
+        assert_eq!(
+            config.reaction_threshold_tg("big_channel"),
+            MEDIA_REACTION_THRESHOLD_TG
+        );
+
+        std::fs::remove_file(temp_file).ok();
+    }
+
+    #[test]
+    fn reaction_threshold_defaults_to_constants() {
+        let config = Config::defaults();
+        assert_eq!(config.reaction_threshold("any"), MEDIA_REACTION_THRESHOLD);
+        assert_eq!(
+            config.reaction_threshold_tg("any"),
+            MEDIA_REACTION_THRESHOLD_TG
+        );
+    }
+
+    #[test]
+    fn chat_ids_and_usernames_are_interpolated_from_env() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        let yaml = r#"
+chats:
+  alpha:
+    type: group
+    id: "${PYTHORUST_TEST_ALPHA_ID}"
+  support:
+    type: username
+    username: "${PYTHORUST_TEST_SUPPORT}"
+  missing:
+    type: channel
+    id: "${PYTHORUST_TEST_UNSET_ID}"
+  not_a_number:
+    type: channel
+    id: "${PYTHORUST_TEST_SUPPORT}"
+"#;
+        let temp_file = std::env::temp_dir().join("config_chat_env.yml");
+        std::fs::write(&temp_file, yaml).unwrap();
+
+        let _guards = set_envs(&[
+            ("PYTHORUST_TEST_ALPHA_ID", "-100500"),
+            ("PYTHORUST_TEST_SUPPORT", "support_bot"),
+        ]);
+        std::env::remove_var("PYTHORUST_TEST_UNSET_ID");
+
+        let config = Config::load_from_file(&temp_file).unwrap();
+
+        assert!(matches!(
+            config.chats.get("alpha"),
+            Some(ChatEntity::Chat(-100500))
+        ));
+        assert!(matches!(
+            config.chats.get("support"),
+            Some(ChatEntity::Username(name)) if name == "support_bot"
+        ));
+        assert!(!config.chats.contains_key("missing"));
+        assert!(!config.chats.contains_key("not_a_number"));
+        assert_eq!(config.chats.len(), 2);
+        assert_eq!(config.skipped_chats.len(), 2);
+
+        std::fs::remove_file(temp_file).ok();
+    }
+
+    #[test]
+    fn validate_reports_missing_credentials() {
+        let config = Config::defaults();
+        let problems = config.validate();
+
+        assert!(config.has_fatal_problems());
+        assert!(problems.iter().any(|p| p.contains("api_id")));
+        assert!(problems.iter().any(|p| p.contains("api_hash")));
+        assert!(problems.iter().any(|p| p.contains("phone")));
+        assert!(problems[0].starts_with("error: "));
+        assert!(problems.last().unwrap().starts_with("warning: "));
+    }
+
+    #[test]
+    fn validate_reports_chats_with_undefined_env_vars() {
+        let mut config = Config::defaults();
+        config.api_id = 1;
+        config.api_hash = "hash".into();
+        config.phone = "+1000".into();
+        config.chats.insert("alpha".into(), ChatEntity::Chat(1));
+        assert!(config.validate().is_empty());
+        assert!(!config.has_fatal_problems());
+
+        config
+            .skipped_chats
+            .push("chat 'beta': environment variable BETA_ID is not set".into());
+        assert_eq!(
+            config.validate(),
+            vec!["error: chat 'beta': environment variable BETA_ID is not set"]
+        );
+        assert!(config.has_fatal_problems());
+    }
+
     #[test]
     fn load_from_file_fails_on_missing_file() {
         let result = Config::load_from_file("/nonexistent/path/config.yml");
@@ -783,5 +1037,31 @@ chats:
 
         std::fs::remove_file(temp_file).ok();
     }
-}
 
+    #[test]
+    fn parses_important_senders() {
+        let yaml = r#"
+telegram:
+  api_id: 111
+  api_hash: "hash"
+important_senders:
+  - "@boss"
+  - 424242
+  - pm_anna
+"#;
+        let temp_file = std::env::temp_dir().join("config_important_senders.yml");
+        std::fs::write(&temp_file, yaml).unwrap();
+
+        let config = Config::load_from_file(&temp_file).unwrap();
+        assert_eq!(
+            config.important_senders,
+            vec![
+                "@boss".to_string(),
+                "424242".to_string(),
+                "pm_anna".to_string()
+            ]
+        );
+
+        std::fs::remove_file(temp_file).ok();
+    }
+}
@@ -0,0 +1,147 @@
+//! Confirmation before destructive actions on a resolved chat
+//!
+//! Chats are looked up by alias, username or partial title, so a short name
+//! can resolve to a chat the user didn't mean. Before reacting, deleting,
+//! pinning or replying, commands show the resolved chat (title, type, id) and
+//! ask for confirmation when stdin is a terminal. `--yes` skips the prompt;
+//! non-interactive runs (cron, pipes) proceed as before.
+
+use std::fmt;
+use std::io::{BufRead, IsTerminal, Write};
+
+use grammers_client::types::peer::Peer;
+
+use crate::chat::peer_name;
+use crate::error::{Error, Result};
+
+/// The chat a destructive command is about to act on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChatSummary {
+    pub title: String,
+    /// `user`, `group` or `channel`
+    pub kind: &'static str,
+    pub id: i64,
+}
+
+impl ChatSummary {
+    pub fn from_peer(peer: &Peer) -> Self {
+        let kind = match peer {
+            Peer::User(_) => "user",
+            Peer::Group(_) => "group",
+            Peer::Channel(_) => "channel",
+        };
+        Self {
+            title: peer_name(peer),
+            kind,
+            id: peer.id().bot_api_dialog_id(),
+        }
+    }
+}
+
+impl fmt::Display for ChatSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "'{}' ({}, id {})", self.title, self.kind, self.id)
+    }
+}
+
+/// Whether to prompt before acting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Confirmation {
+    Proceed,
+    Ask,
+}
+
+/// Prompt only on a terminal and only without `--yes`
+pub fn confirmation(interactive: bool, yes: bool) -> Confirmation {
+    if interactive && !yes {
+        Confirmation::Ask
+    } else {
+        Confirmation::Proceed
+    }
+}
+
+/// `y`/`yes` (or `д`/`да`) in any case; anything else, including EOF, is a no
+pub fn is_yes(answer: &str) -> bool {
+    matches!(
+        answer.trim().to_lowercase().as_str(),
+        "y" | "yes" | "д" | "да"
+    )
+}
+
+/// Show the resolved chat and read a yes/no answer
+pub fn ask<R: BufRead, W: Write>(
+    chat: &ChatSummary,
+    action: &str,
+    mut input: R,
+    mut output: W,
+) -> Result<bool> {
+    write!(output, "About to {} in {}. Continue? [y/N] ", action, chat)?;
+    output.flush()?;
+
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+    Ok(is_yes(&answer))
+}
+
+/// Confirm `action` on `peer` on the terminal; errors if the user declines
+pub fn confirm_chat(peer: &Peer, action: &str, yes: bool) -> Result<()> {
+    let chat = ChatSummary::from_peer(peer);
+    if confirmation(std::io::stdin().is_terminal(), yes) == Confirmation::Proceed {
+        return Ok(());
+    }
+
+    if ask(&chat, action, std::io::stdin().lock(), std::io::stderr())? {
+        Ok(())
+    } else {
+        Err(Error::InvalidArgument(format!(
+            "Aborted: not confirmed to {} in {}",
+            action, chat
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn chat() -> ChatSummary {
+        ChatSummary {
+            title: "Rust Beginners".to_string(),
+            kind: "group",
+            id: 4242,
+        }
+    }
+
+    #[test]
+    fn yes_or_non_interactive_proceeds() {
+        assert_eq!(confirmation(true, true), Confirmation::Proceed);
+        assert_eq!(confirmation(false, true), Confirmation::Proceed);
+        assert_eq!(confirmation(false, false), Confirmation::Proceed);
+        assert_eq!(confirmation(true, false), Confirmation::Ask);
+    }
+
+    #[test]
+    fn prompt_shows_title_type_and_id() {
+        let mut output = Vec::new();
+        assert!(ask(&chat(), "like messages", Cursor::new("y\n"), &mut output).unwrap());
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "About to like messages in 'Rust Beginners' (group, id 4242). Continue? [y/N] "
+        );
+    }
+
+    #[test]
+    fn anything_but_yes_aborts() {
+        for answer in ["n\n", "\n", "", "yep\n"] {
+            assert!(
+                !ask(&chat(), "react", Cursor::new(answer), Vec::new()).unwrap(),
+                "{:?}",
+                answer
+            );
+        }
+        for answer in ["YES\n", " y \n", "да\n"] {
+            assert!(ask(&chat(), "react", Cursor::new(answer), Vec::new()).unwrap());
+        }
+    }
+}
@@ -0,0 +1,164 @@
+//! MySQL startup readiness
+//!
+//! In docker-compose the bots and analytics tools often start a few seconds
+//! before MySQL accepts connections. Instead of failing on the first query,
+//! they ping the pool with exponential backoff until it answers or the startup
+//! timeout (`MYSQL_STARTUP_TIMEOUT_SECS`, 30s by default) runs out.
+
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use mysql_async::{prelude::*, Pool};
+use tracing::{info, warn};
+
+use crate::error::{Error, Result};
+
+/// Backoff settings of [`wait_until_ready`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadinessConfig {
+    /// Give up when the next attempt would start after this long (zero = ping once)
+    pub timeout: Duration,
+    /// Delay after the first failed ping, doubled after each further failure
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for ReadinessConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(5),
+        }
+    }
+}
+
+impl ReadinessConfig {
+    /// Defaults with the timeout overridden by `MYSQL_STARTUP_TIMEOUT_SECS`
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        if let Some(secs) = std::env::var("MYSQL_STARTUP_TIMEOUT_SECS")
+            .ok()
+            .and_then(|v| v.trim().parse::<u64>().ok())
+        {
+            config.timeout = Duration::from_secs(secs);
+        }
+        config
+    }
+}
+
+/// Retry `ping` with backoff until it succeeds; returns the number of attempts
+pub async fn wait_until_ready_with<F, Fut>(mut ping: F, config: &ReadinessConfig) -> Result<u32>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<()>>,
+{
+    let started = Instant::now();
+    let mut delay = config.initial_delay;
+    let mut attempts = 0;
+
+    loop {
+        attempts += 1;
+        let err = match ping().await {
+            Ok(()) => return Ok(attempts),
+            Err(err) => err,
+        };
+
+        if started.elapsed() + delay > config.timeout {
+            return Err(Error::ConnectionError(format!(
+                "MySQL is not ready after {} attempt(s) in {:.1}s: {}",
+                attempts,
+                started.elapsed().as_secs_f64(),
+                err
+            )));
+        }
+
+        warn!(
+            "MySQL is not ready yet (attempt {}): {}; retrying in {:?}",
+            attempts, err, delay
+        );
+        tokio::time::sleep(delay).await;
+        delay = (delay * 2).min(config.max_delay);
+    }
+}
+
+/// Block until `pool` answers a ping, using [`ReadinessConfig::from_env`]
+pub async fn wait_until_ready(pool: &Pool) -> Result<()> {
+    let attempts = wait_until_ready_with(
+        || async {
+            let mut conn = pool.get_conn().await?;
+            conn.ping().await?;
+            Ok(())
+        },
+        &ReadinessConfig::from_env(),
+    )
+    .await?;
+
+    if attempts > 1 {
+        info!("MySQL is ready after {} attempts", attempts);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn fast_config(timeout_ms: u64) -> ReadinessConfig {
+        ReadinessConfig {
+            timeout: Duration::from_millis(timeout_ms),
+            initial_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(4),
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_until_ready() {
+        let calls = AtomicU32::new(0);
+        let ping = || async {
+            if calls.fetch_add(1, Ordering::SeqCst) < 2 {
+                Err(Error::MySqlError("Connection refused".to_string()))
+            } else {
+                Ok(())
+            }
+        };
+
+        let attempts = wait_until_ready_with(ping, &fast_config(5_000))
+            .await
+            .unwrap();
+        assert_eq!(attempts, 3);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_timeout() {
+        let calls = AtomicU32::new(0);
+        let ping = || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(Error::MySqlError("Connection refused".to_string()))
+        };
+
+        let started = Instant::now();
+        let err = wait_until_ready_with(ping, &fast_config(30))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, Error::ConnectionError(_)));
+        assert!(err.to_string().contains("Connection refused"), "{}", err);
+        assert!(calls.load(Ordering::SeqCst) > 1);
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn zero_timeout_pings_once() {
+        let calls = AtomicU32::new(0);
+        let ping = || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(Error::MySqlError("down".to_string()))
+        };
+
+        assert!(wait_until_ready_with(ping, &fast_config(0)).await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,239 @@
+//! Structured dry-run plans for mutating commands
+//!
+//! Commands that react to, delete or send messages record what they would do
+//! into a [`DryRunPlan`] instead of touching the chat, and print one summary
+//! in a common format at the end (text or JSON).
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::error::Result;
+
+/// Kind of change a command would make
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionKind {
+    React,
+    Unreact,
+    Delete,
+    SendMessage,
+    Reply,
+}
+
+impl ActionKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ActionKind::React => "react",
+            ActionKind::Unreact => "unreact",
+            ActionKind::Delete => "delete",
+            ActionKind::SendMessage => "send_message",
+            ActionKind::Reply => "reply",
+        }
+    }
+}
+
+/// How the plan summary is printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PlanFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl PlanFormat {
+    /// Dry-run mode from the CLI flags (`None` means a real run)
+    pub fn from_flags(dry_run: bool, json: bool) -> Option<Self> {
+        match (dry_run, json) {
+            (false, _) => None,
+            (true, false) => Some(PlanFormat::Text),
+            (true, true) => Some(PlanFormat::Json),
+        }
+    }
+}
+
+/// A single change that was skipped because of the dry run
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlannedAction {
+    pub kind: ActionKind,
+    pub chat: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<i32>,
+    /// Emoji, message preview, etc.
+    pub detail: String,
+}
+
+/// Planned actions aggregated by type and chat
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlanSummary {
+    pub command: String,
+    pub total: usize,
+    pub by_kind: BTreeMap<ActionKind, usize>,
+    pub by_chat: BTreeMap<String, usize>,
+}
+
+/// Everything a dry run would have done
+#[derive(Debug, Clone, Default)]
+pub struct DryRunPlan {
+    command: String,
+    actions: Vec<PlannedAction>,
+}
+
+#[derive(Serialize)]
+struct PlanReport<'a> {
+    #[serde(flatten)]
+    summary: PlanSummary,
+    actions: &'a [PlannedAction],
+}
+
+impl DryRunPlan {
+    pub fn new(command: impl Into<String>) -> Self {
+        Self {
+            command: command.into(),
+            actions: Vec::new(),
+        }
+    }
+
+    /// Record an action the command skipped
+    pub fn record(
+        &mut self,
+        kind: ActionKind,
+        chat: impl Into<String>,
+        message_id: Option<i32>,
+        detail: impl Into<String>,
+    ) {
+        self.actions.push(PlannedAction {
+            kind,
+            chat: chat.into(),
+            message_id,
+            detail: detail.into(),
+        });
+    }
+
+    pub fn actions(&self) -> &[PlannedAction] {
+        &self.actions
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    /// Counts of planned actions by type and by chat
+    pub fn summary(&self) -> PlanSummary {
+        let mut by_kind = BTreeMap::new();
+        let mut by_chat = BTreeMap::new();
+        for action in &self.actions {
+            *by_kind.entry(action.kind).or_insert(0) += 1;
+            *by_chat.entry(action.chat.clone()).or_insert(0) += 1;
+        }
+
+        PlanSummary {
+            command: self.command.clone(),
+            total: self.actions.len(),
+            by_kind,
+            by_chat,
+        }
+    }
+
+    /// Summary plus the individual actions as pretty JSON
+    pub fn to_json(&self) -> Result<String> {
+        let report = PlanReport {
+            summary: self.summary(),
+            actions: &self.actions,
+        };
+        Ok(serde_json::to_string_pretty(&report)?)
+    }
+
+    /// Human-readable summary
+    pub fn render_text(&self) -> String {
+        let summary = self.summary();
+        let mut out = format!(
+            "🧪 Dry run ({}): {} planned action(s), nothing was changed\n",
+            summary.command, summary.total
+        );
+        for (kind, count) in &summary.by_kind {
+            out.push_str(&format!("  {}: {}\n", kind.label(), count));
+        }
+        if summary.by_chat.len() > 1 {
+            out.push_str("  by chat:\n");
+            for (chat, count) in &summary.by_chat {
+                out.push_str(&format!("    {}: {}\n", chat, count));
+            }
+        }
+        out
+    }
+
+    /// Print the summary in the requested format
+    pub fn print(&self, format: PlanFormat) -> Result<()> {
+        match format {
+            PlanFormat::Text => print!("\n{}", self.render_text()),
+            PlanFormat::Json => println!("{}", self.to_json()?),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_plan() -> DryRunPlan {
+        let mut plan = DryRunPlan::new("moderate");
+        plan.record(ActionKind::Delete, "chat_a", Some(10), "spam");
+        plan.record(ActionKind::Reply, "chat_a", Some(10), "warning");
+        plan.record(ActionKind::Delete, "chat_b", Some(3), "spam");
+        plan.record(ActionKind::Delete, "chat_a", Some(11), "profanity");
+        plan
+    }
+
+    #[test]
+    fn empty_plan_has_no_actions() {
+        let summary = DryRunPlan::new("react").summary();
+        assert_eq!(summary.total, 0);
+        assert!(summary.by_kind.is_empty());
+        assert!(summary.by_chat.is_empty());
+    }
+
+    #[test]
+    fn aggregates_by_kind_and_chat() {
+        let summary = sample_plan().summary();
+
+        assert_eq!(summary.command, "moderate");
+        assert_eq!(summary.total, 4);
+        assert_eq!(
+            summary.by_kind.into_iter().collect::<Vec<_>>(),
+            vec![(ActionKind::Delete, 3), (ActionKind::Reply, 1)]
+        );
+        assert_eq!(summary.by_chat.get("chat_a"), Some(&3));
+        assert_eq!(summary.by_chat.get("chat_b"), Some(&1));
+    }
+
+    #[test]
+    fn json_contains_counts_and_actions() {
+        let value: serde_json::Value =
+            serde_json::from_str(&sample_plan().to_json().unwrap()).unwrap();
+
+        assert_eq!(value["command"], "moderate");
+        assert_eq!(value["total"], 4);
+        assert_eq!(value["by_kind"]["delete"], 3);
+        assert_eq!(value["by_kind"]["reply"], 1);
+        assert_eq!(value["actions"][0]["kind"], "delete");
+        assert_eq!(value["actions"][0]["message_id"], 10);
+    }
+
+    #[test]
+    fn text_summary_lists_counts() {
+        let text = sample_plan().render_text();
+        assert!(text.contains("Dry run (moderate): 4 planned action(s)"));
+        assert!(text.contains("delete: 3"));
+        assert!(text.contains("reply: 1"));
+        assert!(text.contains("chat_b: 1"));
+    }
+
+    #[test]
+    fn format_from_flags() {
+        assert_eq!(PlanFormat::from_flags(false, true), None);
+        assert_eq!(PlanFormat::from_flags(true, false), Some(PlanFormat::Text));
+        assert_eq!(PlanFormat::from_flags(true, true), Some(PlanFormat::Json));
+    }
+}
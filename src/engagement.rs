@@ -0,0 +1,363 @@
+//! Deterministic engagement score for chat participants
+//!
+//! Ranks participants from locally computed aggregates instead of opaque LLM
+//! numbers. Each signal (messages sent, reactions and replies received,
+//! recency) is normalized against the most active participant, weighted, and
+//! scaled to 0–10 — the same range the analysis prompt uses.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+
+use crate::error::{Error, Result};
+
+/// Per-participant aggregates over the analyzed messages
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParticipantActivity {
+    pub name: String,
+    pub messages: usize,
+    pub reactions_received: usize,
+    pub replies_received: usize,
+    pub last_active: DateTime<Utc>,
+}
+
+/// One analyzed message as seen by [`aggregate_activity`]
+#[derive(Debug, Clone, Copy)]
+pub struct ActivitySample<'a> {
+    pub sender: &'a str,
+    pub message_id: i32,
+    pub reply_to: Option<i32>,
+    pub reactions: usize,
+    pub date: DateTime<Utc>,
+}
+
+/// Collect per-sender aggregates; replies count for the author of the replied-to message
+pub fn aggregate_activity<'a>(
+    samples: impl IntoIterator<Item = ActivitySample<'a>>,
+) -> Vec<ParticipantActivity> {
+    let samples: Vec<ActivitySample> = samples.into_iter().collect();
+    let authors: HashMap<i32, &str> = samples.iter().map(|s| (s.message_id, s.sender)).collect();
+
+    let mut activity: HashMap<&str, ParticipantActivity> = HashMap::new();
+    for sample in &samples {
+        let entry = activity
+            .entry(sample.sender)
+            .or_insert_with(|| ParticipantActivity {
+                name: sample.sender.to_string(),
+                messages: 0,
+                reactions_received: 0,
+                replies_received: 0,
+                last_active: sample.date,
+            });
+        entry.messages += 1;
+        entry.reactions_received += sample.reactions;
+        entry.last_active = entry.last_active.max(sample.date);
+    }
+
+    for sample in &samples {
+        let Some(author) = sample.reply_to.and_then(|id| authors.get(&id)) else {
+            continue;
+        };
+        // Replying to yourself isn't engagement
+        if *author == sample.sender {
+            continue;
+        }
+        if let Some(entry) = activity.get_mut(author) {
+            entry.replies_received += 1;
+        }
+    }
+
+    let mut participants: Vec<ParticipantActivity> = activity.into_values().collect();
+    participants.sort_by(|a, b| a.name.cmp(&b.name));
+    participants
+}
+
+/// Relative weights of the score components (need not sum to 1)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EngagementWeights {
+    pub messages: f32,
+    pub reactions: f32,
+    pub replies: f32,
+    pub recency: f32,
+    /// Days of inactivity after which the recency component halves
+    pub recency_half_life_days: f32,
+}
+
+impl Default for EngagementWeights {
+    fn default() -> Self {
+        Self {
+            messages: 0.4,
+            reactions: 0.25,
+            replies: 0.25,
+            recency: 0.1,
+            recency_half_life_days: 7.0,
+        }
+    }
+}
+
+impl EngagementWeights {
+    /// Parse `messages=1,reactions=2,replies=1,recency=0.5,half_life=3`; omitted keys keep defaults
+    pub fn parse(spec: &str) -> Result<Self> {
+        let mut weights = Self::default();
+        for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let invalid = || {
+                Error::InvalidArgument(format!(
+                    "Invalid engagement weight '{}', expected key=number",
+                    part
+                ))
+            };
+            let (key, value) = part.split_once('=').ok_or_else(invalid)?;
+            let value: f32 = value.trim().parse().map_err(|_| invalid())?;
+            if !value.is_finite() || value < 0.0 {
+                return Err(invalid());
+            }
+            match key.trim() {
+                "messages" => weights.messages = value,
+                "reactions" => weights.reactions = value,
+                "replies" => weights.replies = value,
+                "recency" => weights.recency = value,
+                "half_life" => weights.recency_half_life_days = value,
+                other => {
+                    return Err(Error::InvalidArgument(format!(
+                        "Unknown engagement weight '{}' (messages, reactions, replies, recency, half_life)",
+                        other
+                    )))
+                }
+            }
+        }
+
+        if weights.total() == 0.0 {
+            return Err(Error::InvalidArgument(
+                "At least one engagement weight must be positive".to_string(),
+            ));
+        }
+        Ok(weights)
+    }
+
+    fn total(&self) -> f32 {
+        self.messages + self.reactions + self.replies + self.recency
+    }
+}
+
+/// Score of one participant (0–10) against the maxima of the group.
+///
+/// `now` is the reference point for recency; pass the newest analyzed message
+/// date to keep results reproducible.
+pub fn engagement_score(
+    participant: &ParticipantActivity,
+    group: &[ParticipantActivity],
+    weights: &EngagementWeights,
+    now: DateTime<Utc>,
+) -> f32 {
+    let ratio = |value: usize, max: usize| {
+        if max == 0 {
+            0.0
+        } else {
+            value as f32 / max as f32
+        }
+    };
+    let max_of = |f: fn(&ParticipantActivity) -> usize| group.iter().map(f).max().unwrap_or(0);
+
+    let messages = ratio(participant.messages, max_of(|p| p.messages));
+    let reactions = ratio(
+        participant.reactions_received,
+        max_of(|p| p.reactions_received),
+    );
+    let replies = ratio(participant.replies_received, max_of(|p| p.replies_received));
+
+    let idle_days = (now - participant.last_active).num_seconds().max(0) as f32 / 86_400.0;
+    let recency = if weights.recency_half_life_days > 0.0 {
+        0.5f32.powf(idle_days / weights.recency_half_life_days)
+    } else {
+        1.0
+    };
+
+    let total = weights.total();
+    if total == 0.0 {
+        return 0.0;
+    }
+    let weighted = weights.messages * messages
+        + weights.reactions * reactions
+        + weights.replies * replies
+        + weights.recency * recency;
+    (weighted / total * 10.0 * 100.0).round() / 100.0
+}
+
+/// Participants with their scores, best first (ties by message count, then name)
+pub fn rank_participants<'a>(
+    group: &'a [ParticipantActivity],
+    weights: &EngagementWeights,
+) -> Vec<(&'a ParticipantActivity, f32)> {
+    let now = group
+        .iter()
+        .map(|p| p.last_active)
+        .max()
+        .unwrap_or_else(Utc::now);
+
+    let mut ranked: Vec<(&ParticipantActivity, f32)> = group
+        .iter()
+        .map(|p| (p, engagement_score(p, group, weights, now)))
+        .collect();
+    ranked.sort_by(|a, b| {
+        b.1.total_cmp(&a.1)
+            .then(b.0.messages.cmp(&a.0.messages))
+            .then(a.0.name.cmp(&b.0.name))
+    });
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    fn base() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, 10, 12, 0, 0).unwrap()
+    }
+
+    fn participant(
+        name: &str,
+        messages: usize,
+        reactions: usize,
+        replies: usize,
+        idle_days: i64,
+    ) -> ParticipantActivity {
+        ParticipantActivity {
+            name: name.to_string(),
+            messages,
+            reactions_received: reactions,
+            replies_received: replies,
+            last_active: base() - Duration::days(idle_days),
+        }
+    }
+
+    fn group() -> Vec<ParticipantActivity> {
+        vec![
+            // Talks a lot, nobody reacts
+            participant("chatty", 50, 0, 1, 0),
+            // Few messages that everyone reacts and replies to
+            participant("expert", 5, 40, 12, 0),
+            // Used to be active, silent for a month
+            participant("ghost", 30, 10, 5, 30),
+        ]
+    }
+
+    fn order(weights: &EngagementWeights) -> Vec<String> {
+        let group = group();
+        rank_participants(&group, weights)
+            .into_iter()
+            .map(|(p, _)| p.name.clone())
+            .collect()
+    }
+
+    fn only(messages: f32, reactions: f32, replies: f32, recency: f32) -> EngagementWeights {
+        EngagementWeights {
+            messages,
+            reactions,
+            replies,
+            recency,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn ordering_follows_weights() {
+        assert_eq!(
+            order(&only(1.0, 0.0, 0.0, 0.0)),
+            vec!["chatty", "ghost", "expert"]
+        );
+        assert_eq!(
+            order(&only(0.0, 1.0, 1.0, 0.0)),
+            vec!["expert", "ghost", "chatty"]
+        );
+        // Recency alone: ties between the two active ones break by message count
+        assert_eq!(
+            order(&only(0.0, 0.0, 0.0, 1.0)),
+            vec!["chatty", "expert", "ghost"]
+        );
+        assert_eq!(
+            order(&EngagementWeights::default()),
+            vec!["expert", "chatty", "ghost"]
+        );
+    }
+
+    #[test]
+    fn scores_are_scaled_to_ten() {
+        let group = vec![participant("a", 10, 4, 2, 0), participant("b", 5, 2, 1, 0)];
+        let weights = only(1.0, 1.0, 1.0, 1.0);
+
+        assert_eq!(engagement_score(&group[0], &group, &weights, base()), 10.0);
+        // Half of every maximum plus full recency: (0.5 * 3 + 1) / 4
+        assert_eq!(engagement_score(&group[1], &group, &weights, base()), 6.25);
+    }
+
+    #[test]
+    fn recency_halves_per_half_life() {
+        let group = vec![participant("old", 1, 0, 0, 7)];
+        let weights = EngagementWeights {
+            recency_half_life_days: 7.0,
+            ..only(0.0, 0.0, 0.0, 1.0)
+        };
+        assert_eq!(engagement_score(&group[0], &group, &weights, base()), 5.0);
+    }
+
+    #[test]
+    fn aggregates_replies_for_the_replied_author() {
+        let at = |h| base() + Duration::hours(h);
+        let samples = [
+            ActivitySample {
+                sender: "alice",
+                message_id: 1,
+                reply_to: None,
+                reactions: 3,
+                date: at(0),
+            },
+            ActivitySample {
+                sender: "bob",
+                message_id: 2,
+                reply_to: Some(1),
+                reactions: 0,
+                date: at(1),
+            },
+            ActivitySample {
+                sender: "alice",
+                message_id: 3,
+                reply_to: Some(1),
+                reactions: 1,
+                date: at(2),
+            },
+            ActivitySample {
+                sender: "bob",
+                message_id: 4,
+                reply_to: Some(99),
+                reactions: 0,
+                date: at(3),
+            },
+        ];
+
+        let activity = aggregate_activity(samples);
+        assert_eq!(activity.len(), 2);
+        let alice = &activity[0];
+        assert_eq!(alice.name, "alice");
+        assert_eq!(alice.messages, 2);
+        assert_eq!(alice.reactions_received, 4);
+        // Own reply and the reply to an unknown message don't count
+        assert_eq!(alice.replies_received, 1);
+        assert_eq!(alice.last_active, at(2));
+        assert_eq!(activity[1].replies_received, 0);
+    }
+
+    #[test]
+    fn parses_weights() {
+        let weights = EngagementWeights::parse("messages=2, replies=0.5,half_life=3").unwrap();
+        assert_eq!(weights.messages, 2.0);
+        assert_eq!(weights.replies, 0.5);
+        assert_eq!(weights.reactions, EngagementWeights::default().reactions);
+        assert_eq!(weights.recency_half_life_days, 3.0);
+
+        assert!(EngagementWeights::parse("likes=1").is_err());
+        assert!(EngagementWeights::parse("messages=-1").is_err());
+        assert!(EngagementWeights::parse("messages").is_err());
+        assert!(EngagementWeights::parse("messages=0,reactions=0,replies=0,recency=0").is_err());
+    }
+}
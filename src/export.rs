@@ -10,7 +10,7 @@ use grammers_client::types::peer::Peer;
 use grammers_client::types::Message;
 
 use crate::config::KNOWN_SENDERS;
-use crate::error::Result;
+use crate::error::{Error, Result};
 
 /// Export context for writing messages to a file
 pub struct ExportWriter {
@@ -117,6 +117,76 @@ pub fn media_dir_exists(chat_name: &str) -> bool {
     Path::new(chat_name).is_dir()
 }
 
+/// UTF-8 byte order mark, lets Excel detect the encoding
+const UTF8_BOM: &str = "\u{feff}";
+
+/// CSV output settings shared by all exporters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CsvOptions {
+    /// Prepend a UTF-8 BOM (Excel on Windows needs it for Cyrillic/emoji)
+    pub bom: bool,
+    /// Field delimiter: `,`, `;` or tab
+    pub delimiter: u8,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            bom: true,
+            delimiter: b',',
+        }
+    }
+}
+
+impl CsvOptions {
+    /// Options from CLI flags; the delimiter accepts `,`, `;`, `\t` or `tab`
+    pub fn new(bom: bool, delimiter: &str) -> Result<Self> {
+        let delimiter = match delimiter {
+            "," => b',',
+            ";" => b';',
+            "\t" | "\\t" | "tab" => b'\t',
+            other => {
+                return Err(Error::InvalidArgument(format!(
+                    "Unsupported CSV delimiter '{}'. Use , ; or tab",
+                    other
+                )))
+            }
+        };
+        Ok(Self { bom, delimiter })
+    }
+}
+
+/// Render a header row and records as CSV.
+///
+/// Fields containing commas, quotes or newlines are quoted per RFC 4180, so
+/// free-form text (names, messages, LLM output) can't break the columns.
+pub fn write_records<H, R, F>(
+    headers: &[H],
+    rows: impl IntoIterator<Item = R>,
+    options: &CsvOptions,
+) -> Result<String>
+where
+    H: AsRef<[u8]>,
+    R: IntoIterator<Item = F>,
+    F: AsRef<[u8]>,
+{
+    let csv_error = |e: csv::Error| Error::SerializationError(format!("CSV error: {}", e));
+
+    let prefix = if options.bom { UTF8_BOM } else { "" };
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(options.delimiter)
+        .from_writer(prefix.as_bytes().to_vec());
+    writer.write_record(headers).map_err(csv_error)?;
+    for row in rows {
+        writer.write_record(row).map_err(csv_error)?;
+    }
+
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| Error::SerializationError(format!("CSV error: {}", e)))?;
+    String::from_utf8(bytes).map_err(|e| Error::SerializationError(e.to_string()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -245,6 +315,90 @@ mod tests {
         Ok(())
     }
 
+    fn plain_csv() -> CsvOptions {
+        CsvOptions {
+            bom: false,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn write_records_quotes_special_fields() -> crate::error::Result<()> {
+        let rows = vec![
+            vec!["plain", "with, comma"],
+            vec!["say \"hi\"", "multi\nline"],
+        ];
+        let output = write_records(&["a", "b"], rows, &plain_csv())?;
+
+        assert_eq!(
+            output,
+            "a,b\nplain,\"with, comma\"\n\"say \"\"hi\"\"\",\"multi\nline\"\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn write_records_round_trips_through_reader() -> crate::error::Result<()> {
+        let rows = vec![
+            vec!["Doe, John".to_string(), "O\"Brien\" & Co".to_string()],
+            vec!["line1\nline2\r\nline3".to_string(), String::new()],
+        ];
+        let output = write_records(&["name", "company"], rows.clone(), &plain_csv())?;
+
+        let mut reader = csv::Reader::from_reader(output.as_bytes());
+        let headers: Vec<String> = reader.headers().unwrap().iter().map(String::from).collect();
+        assert_eq!(headers, vec!["name", "company"]);
+
+        let parsed: Vec<Vec<String>> = reader
+            .records()
+            .map(|r| r.unwrap().iter().map(String::from).collect())
+            .collect();
+        assert_eq!(parsed, rows);
+        Ok(())
+    }
+
+    #[test]
+    fn write_records_rejects_ragged_rows() {
+        let rows = vec![vec!["only one"]];
+        assert!(write_records(&["a", "b"], rows, &plain_csv()).is_err());
+    }
+
+    #[test]
+    fn write_records_prepends_bom_by_default() -> crate::error::Result<()> {
+        let output = write_records(&["имя"], vec![vec!["Анна 🔥"]], &CsvOptions::default())?;
+        assert!(output.starts_with('\u{feff}'));
+        assert_eq!(output.trim_start_matches('\u{feff}'), "имя\nАнна 🔥\n");
+
+        let output = write_records(&["имя"], vec![vec!["Анна"]], &plain_csv())?;
+        assert!(!output.starts_with('\u{feff}'));
+        Ok(())
+    }
+
+    #[test]
+    fn write_records_honors_delimiter() -> crate::error::Result<()> {
+        let semicolon = CsvOptions::new(false, ";")?;
+        let output = write_records(&["a", "b"], vec![vec!["1,5", "x;y"]], &semicolon)?;
+        assert_eq!(output, "a;b\n1,5;\"x;y\"\n");
+
+        let tab = CsvOptions::new(false, "tab")?;
+        let output = write_records(&["a", "b"], vec![vec!["1", "2"]], &tab)?;
+        assert_eq!(output, "a\tb\n1\t2\n");
+
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(b'\t')
+            .from_reader(output.as_bytes());
+        let record = reader.records().next().unwrap().unwrap();
+        assert_eq!(&record[1], "2");
+        Ok(())
+    }
+
+    #[test]
+    fn csv_options_reject_unknown_delimiter() {
+        assert_eq!(CsvOptions::new(true, ",").unwrap(), CsvOptions::default());
+        assert_eq!(CsvOptions::new(true, "\\t").unwrap().delimiter, b'\t');
+        assert!(CsvOptions::new(true, "|").is_err());
+    }
+
     #[test]
     fn write_message_trims_excess_whitespace() -> crate::error::Result<()> {
         let _lock = WORKDIR_LOCK.lock().unwrap();
@@ -0,0 +1,204 @@
+//! Important senders (boss, PM, ...) whose messages must reach the LLM
+//!
+//! Digest and analysis trim large chats down to a message budget; messages
+//! from the senders listed here are kept first and marked in the prompt.
+
+use std::collections::HashSet;
+
+use grammers_client::types::peer::Peer;
+use grammers_client::types::Message;
+
+/// How many extra messages to scan per budgeted one when important senders are configured
+pub const IMPORTANT_SCAN_FACTOR: usize = 5;
+
+/// Marker prepended to important senders in LLM prompts
+pub const IMPORTANT_MARKER: &str = "⭐";
+
+/// Set of important sender ids and usernames
+#[derive(Debug, Clone, Default)]
+pub struct ImportantSenders {
+    ids: HashSet<i64>,
+    usernames: HashSet<String>,
+}
+
+impl ImportantSenders {
+    /// Parse entries that are either numeric ids or usernames (with or without @)
+    pub fn new<S: AsRef<str>>(entries: &[S]) -> Self {
+        let mut senders = Self::default();
+        for entry in entries {
+            let entry = entry.as_ref().trim();
+            if entry.is_empty() {
+                continue;
+            }
+            match entry.parse::<i64>() {
+                Ok(id) => {
+                    senders.ids.insert(id);
+                }
+                Err(_) => {
+                    senders
+                        .usernames
+                        .insert(entry.trim_start_matches('@').to_lowercase());
+                }
+            }
+        }
+        senders
+    }
+
+    /// Important senders from config.yml plus extra CLI entries
+    pub fn from_config<S: AsRef<str>>(extra: &[S]) -> Self {
+        let config = crate::config::Config::new();
+        let mut senders = Self::new(&config.important_senders);
+        let extra = Self::new(extra);
+        senders.ids.extend(extra.ids);
+        senders.usernames.extend(extra.usernames);
+        senders
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty() && self.usernames.is_empty()
+    }
+
+    /// Whether a sender with this id/username is important
+    pub fn matches(&self, id: Option<i64>, username: Option<&str>) -> bool {
+        id.is_some_and(|id| self.ids.contains(&id))
+            || username.is_some_and(|u| {
+                self.usernames
+                    .contains(&u.trim_start_matches('@').to_lowercase())
+            })
+    }
+
+    /// Whether the message was sent by an important sender
+    pub fn matches_message(&self, msg: &Message) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+        match msg.sender() {
+            Some(Peer::User(u)) => self.matches(Some(u.raw.id()), u.username()),
+            Some(Peer::Channel(c)) => self.matches(Some(c.raw.id), c.username()),
+            _ => false,
+        }
+    }
+
+    /// Number of messages to scan so important ones outside the budget can still be found
+    pub fn scan_limit(&self, budget: usize) -> usize {
+        if self.is_empty() {
+            budget
+        } else {
+            budget.saturating_mul(IMPORTANT_SCAN_FACTOR)
+        }
+    }
+}
+
+/// Trim `items` to `limit`, keeping important items first.
+///
+/// Items are expected newest first. Important items are kept (newest first if
+/// they alone exceed the limit), remaining slots go to the newest other items.
+/// The original order is preserved in the result.
+pub fn trim_preserving<T>(
+    items: Vec<T>,
+    limit: usize,
+    is_important: impl Fn(&T) -> bool,
+) -> Vec<T> {
+    if items.len() <= limit {
+        return items;
+    }
+
+    let mut keep = vec![false; items.len()];
+    let mut kept = 0;
+
+    for (idx, item) in items.iter().enumerate() {
+        if kept >= limit {
+            break;
+        }
+        if is_important(item) {
+            keep[idx] = true;
+            kept += 1;
+        }
+    }
+
+    for flag in keep.iter_mut() {
+        if kept >= limit {
+            break;
+        }
+        if !*flag {
+            *flag = true;
+            kept += 1;
+        }
+    }
+
+    items
+        .into_iter()
+        .zip(keep)
+        .filter_map(|(item, keep)| keep.then_some(item))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Msg {
+        id: i32,
+        important: bool,
+    }
+
+    fn msgs(important_ids: &[i32], count: i32) -> Vec<Msg> {
+        (0..count)
+            .map(|id| Msg {
+                id,
+                important: important_ids.contains(&id),
+            })
+            .collect()
+    }
+
+    fn ids(items: &[Msg]) -> Vec<i32> {
+        items.iter().map(|m| m.id).collect()
+    }
+
+    #[test]
+    fn important_messages_survive_trimming() {
+        let trimmed = trim_preserving(msgs(&[7, 9], 10), 4, |m| m.important);
+        assert_eq!(ids(&trimmed), vec![0, 1, 7, 9]);
+    }
+
+    #[test]
+    fn trimming_keeps_order_and_limit() {
+        let trimmed = trim_preserving(msgs(&[], 10), 3, |m| m.important);
+        assert_eq!(ids(&trimmed), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn too_many_important_keeps_newest_important() {
+        let trimmed = trim_preserving(msgs(&[2, 4, 6, 8], 10), 3, |m| m.important);
+        assert_eq!(ids(&trimmed), vec![2, 4, 6]);
+    }
+
+    #[test]
+    fn under_limit_is_untouched() {
+        let trimmed = trim_preserving(msgs(&[1], 3), 5, |m| m.important);
+        assert_eq!(ids(&trimmed), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn zero_limit_drops_everything() {
+        assert!(trim_preserving(msgs(&[1], 3), 0, |m| m.important).is_empty());
+    }
+
+    #[test]
+    fn parses_ids_and_usernames() {
+        let senders = ImportantSenders::new(&["12345", "@Boss", " pm_anna ", ""]);
+        assert!(senders.matches(Some(12345), None));
+        assert!(senders.matches(None, Some("boss")));
+        assert!(senders.matches(Some(1), Some("@PM_Anna")));
+        assert!(!senders.matches(Some(1), Some("intern")));
+        assert!(!senders.matches(None, None));
+    }
+
+    #[test]
+    fn scan_limit_expands_only_when_configured() {
+        assert_eq!(ImportantSenders::default().scan_limit(100), 100);
+        let senders = ImportantSenders::new(&["boss"]);
+        assert_eq!(senders.scan_limit(100), 100 * IMPORTANT_SCAN_FACTOR);
+    }
+}
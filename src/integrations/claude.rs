@@ -4,13 +4,16 @@
 //! - Claude 3.5/4 Sonnet, Opus, Haiku
 //! - Потоковые ответы
 //! - Vision (изображения)
+//! - Кэширование системного промпта (`with_prompt_caching`)
 
 use std::env;
+use std::time::Instant;
 
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 
-use crate::{Error, Result};
+use super::{Completion, ImageInput};
+use crate::{metrics, Error, Result};
 
 const CLAUDE_API_URL: &str = "https://api.anthropic.com/v1";
 const ANTHROPIC_VERSION: &str = "2023-06-01";
@@ -22,6 +25,7 @@ pub struct ClaudeClient {
     api_key: String,
     base_url: String,
     model: String,
+    prompt_caching: bool,
 }
 
 impl ClaudeClient {
@@ -51,6 +55,7 @@ impl ClaudeClient {
             api_key,
             base_url: CLAUDE_API_URL.to_string(),
             model: model.to_string(),
+            prompt_caching: false,
         })
     }
 
@@ -60,6 +65,16 @@ impl ClaudeClient {
         self
     }
 
+    /// Помечать системный промпт как кэшируемый (`cache_control`), чтобы
+    /// повторные запросы с тем же промптом читали префикс из кэша.
+    ///
+    /// API кэширует только промпты длиннее ~1024 токенов (2048 для Haiku),
+    /// короткие отправляются как обычно.
+    pub fn with_prompt_caching(mut self, enabled: bool) -> Self {
+        self.prompt_caching = enabled;
+        self
+    }
+
     /// Простой чат - отправить сообщение и получить ответ.
     pub async fn chat(&self, message: &str) -> Result<String> {
         self.chat_with_system(message, None).await
@@ -67,6 +82,17 @@ impl ClaudeClient {
 
     /// Чат с системным промптом.
     pub async fn chat_with_system(&self, message: &str, system: Option<&str>) -> Result<String> {
+        self.chat_completion_with_usage(message, system)
+            .await
+            .map(|completion| completion.text)
+    }
+
+    /// Чат с системным промптом и расходом токенов из ответа API.
+    pub async fn chat_completion_with_usage(
+        &self,
+        message: &str,
+        system: Option<&str>,
+    ) -> Result<Completion> {
         let mut payload = ClaudeRequest {
             model: self.model.clone(),
             max_tokens: 4096,
@@ -79,16 +105,74 @@ impl ClaudeClient {
         };
 
         if let Some(sys) = system {
-            payload.system = Some(sys.to_string());
+            payload.system = Some(self.system_prompt(sys));
+        }
+
+        self.send(&payload).await
+    }
+
+    /// Чат с изображениями (vision): картинки идут перед текстом в одном сообщении.
+    pub async fn chat_with_images(
+        &self,
+        message: &str,
+        system: Option<&str>,
+        images: &[ImageInput],
+    ) -> Result<String> {
+        let mut parts: Vec<ContentPart> = images
+            .iter()
+            .map(|image| ContentPart::Image {
+                r#type: "image".to_string(),
+                source: ImageSource {
+                    r#type: "base64".to_string(),
+                    media_type: image.mime_type.clone(),
+                    data: Some(image.base64()),
+                    url: None,
+                },
+            })
+            .collect();
+        parts.push(ContentPart::Text {
+            r#type: "text".to_string(),
+            text: message.to_string(),
+        });
+
+        let payload = ClaudeRequest {
+            model: self.model.clone(),
+            max_tokens: 4096,
+            temperature: 0.7,
+            messages: vec![Message {
+                role: "user".to_string(),
+                content: MessageContent::Parts(parts),
+            }],
+            system: system.map(|sys| self.system_prompt(sys)),
+        };
+
+        self.send(&payload).await.map(|completion| completion.text)
+    }
+
+    /// Системный промпт строкой или, с кэшированием, блоком с `cache_control`.
+    fn system_prompt(&self, system: &str) -> SystemPrompt {
+        if !self.prompt_caching {
+            return SystemPrompt::Text(system.to_string());
         }
 
+        SystemPrompt::Blocks(vec![SystemBlock {
+            r#type: "text".to_string(),
+            text: system.to_string(),
+            cache_control: Some(CacheControl {
+                r#type: "ephemeral".to_string(),
+            }),
+        }])
+    }
+
+    async fn send(&self, payload: &ClaudeRequest) -> Result<Completion> {
+        let started = Instant::now();
         let response = self
             .http
             .post(format!("{}/messages", self.base_url))
             .header("x-api-key", &self.api_key)
             .header("anthropic-version", ANTHROPIC_VERSION)
             .header("content-type", "application/json")
-            .json(&payload)
+            .json(payload)
             .send()
             .await
             .map_err(|e| Error::InvalidArgument(format!("Claude request failed: {}", e)))?;
@@ -98,6 +182,7 @@ impl ClaudeClient {
             .text()
             .await
             .map_err(|e| Error::InvalidArgument(format!("Failed to read response: {}", e)))?;
+        metrics::record_llm_duration("claude", started.elapsed());
 
         if !status.is_success() {
             return Err(Error::InvalidArgument(format!(
@@ -110,14 +195,30 @@ impl ClaudeClient {
             Error::InvalidArgument(format!("Invalid Claude response: {} - {}", e, text))
         })?;
 
-        claude_response
+        let usage = claude_response.usage.unwrap_or_default();
+        metrics::record_token_usage("claude", usage.input_tokens, usage.output_tokens);
+        if usage.cache_creation_input_tokens > 0 || usage.cache_read_input_tokens > 0 {
+            tracing::debug!(
+                "Claude prompt cache: {} tokens written, {} read",
+                usage.cache_creation_input_tokens,
+                usage.cache_read_input_tokens
+            );
+        }
+
+        let text = claude_response
             .content
             .first()
             .and_then(|c| match c {
                 ContentBlock::Text { text } => Some(text.clone()),
                 ContentBlock::Image { .. } => None,
             })
-            .ok_or_else(|| Error::InvalidArgument("Empty response from Claude".to_string()))
+            .ok_or_else(|| Error::InvalidArgument("Empty response from Claude".to_string()))?;
+
+        Ok(Completion {
+            text,
+            prompt_tokens: usage.input_tokens,
+            completion_tokens: usage.output_tokens,
+        })
     }
 
     /// Анализ изображения.
@@ -167,6 +268,7 @@ impl ClaudeClient {
             system: None,
         };
 
+        let started = Instant::now();
         let response = self
             .http
             .post(format!("{}/messages", self.base_url))
@@ -183,6 +285,7 @@ impl ClaudeClient {
             .text()
             .await
             .map_err(|e| Error::InvalidArgument(format!("Failed to read response: {}", e)))?;
+        metrics::record_llm_duration("claude", started.elapsed());
 
         if !status.is_success() {
             return Err(Error::InvalidArgument(format!(
@@ -228,7 +331,27 @@ struct ClaudeRequest {
     temperature: f32,
     messages: Vec<Message>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    system: Option<String>,
+    system: Option<SystemPrompt>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum SystemPrompt {
+    Text(String),
+    Blocks(Vec<SystemBlock>),
+}
+
+#[derive(Debug, Serialize)]
+struct SystemBlock {
+    r#type: String,
+    text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cache_control: Option<CacheControl>,
+}
+
+#[derive(Debug, Serialize)]
+struct CacheControl {
+    r#type: String,
 }
 
 #[derive(Debug, Serialize)]
@@ -267,6 +390,20 @@ struct ImageSource {
 #[derive(Debug, Deserialize)]
 struct ClaudeResponse {
     content: Vec<ContentBlock>,
+    #[serde(default)]
+    usage: Option<ClaudeUsage>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ClaudeUsage {
+    #[serde(default)]
+    input_tokens: u32,
+    #[serde(default)]
+    output_tokens: u32,
+    #[serde(default)]
+    cache_creation_input_tokens: u32,
+    #[serde(default)]
+    cache_read_input_tokens: u32,
 }
 
 #[derive(Debug, Deserialize)]
@@ -343,6 +480,111 @@ mod tests {
         chat_mock.assert_calls(1);
     }
 
+    #[tokio::test]
+    async fn prompt_caching_marks_system_prompt_cacheable() {
+        let server = MockServer::start_async().await;
+
+        let chat_mock = server.mock(|when, then| {
+            when.method(POST).path("/messages").json_body_includes(
+                json!({
+                    "system": [{
+                        "type": "text",
+                        "text": "LONG_SYSTEM_PROMPT",
+                        "cache_control": { "type": "ephemeral" }
+                    }]
+                })
+                .to_string(),
+            );
+            then.status(200).json_body(json!({
+                "content": [{ "type": "text", "text": "cached" }],
+                "usage": {
+                    "input_tokens": 5,
+                    "output_tokens": 2,
+                    "cache_read_input_tokens": 2048
+                }
+            }));
+        });
+
+        let completion = client(&server)
+            .with_prompt_caching(true)
+            .chat_completion_with_usage("Hi", Some("LONG_SYSTEM_PROMPT"))
+            .await
+            .unwrap();
+
+        assert_eq!(completion.text, "cached");
+        assert_eq!(completion.prompt_tokens, 5);
+        chat_mock.assert_calls(1);
+    }
+
+    #[tokio::test]
+    async fn system_prompt_is_plain_text_without_caching() {
+        let server = MockServer::start_async().await;
+
+        let chat_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/messages")
+                .json_body_includes(json!({ "system": "SYS_PROMPT" }).to_string())
+                .is_true(|req| {
+                    let body = String::from_utf8_lossy(req.body().as_ref());
+                    !body.contains("cache_control")
+                });
+            then.status(200).json_body(json!({
+                "content": [{ "type": "text", "text": "plain" }]
+            }));
+        });
+
+        let reply = client(&server)
+            .chat_with_system("Hi", Some("SYS_PROMPT"))
+            .await
+            .unwrap();
+
+        assert_eq!(reply, "plain");
+        chat_mock.assert_calls(1);
+    }
+
+    #[test]
+    fn cached_system_prompt_serializes_as_block() {
+        let client = ClaudeClient::new("test_key", "claude-3-haiku-20240307")
+            .unwrap()
+            .with_prompt_caching(true);
+        let payload = ClaudeRequest {
+            model: client.model.clone(),
+            max_tokens: 16,
+            temperature: 0.0,
+            messages: vec![],
+            system: Some(client.system_prompt("SYS")),
+        };
+
+        assert_eq!(
+            serde_json::to_value(&payload).unwrap()["system"],
+            json!([{ "type": "text", "text": "SYS", "cache_control": { "type": "ephemeral" } }])
+        );
+    }
+
+    #[tokio::test]
+    async fn chat_completion_with_usage_parses_token_counts() {
+        let server = MockServer::start_async().await;
+
+        server.mock(|when, then| {
+            when.method(POST).path("/messages");
+            then.status(200).json_body(json!({
+                "content": [
+                    { "type": "text", "text": "Hello from Claude" }
+                ],
+                "usage": { "input_tokens": 25, "output_tokens": 9 }
+            }));
+        });
+
+        let completion = client(&server)
+            .chat_completion_with_usage("Hi", None)
+            .await
+            .unwrap();
+
+        assert_eq!(completion.text, "Hello from Claude");
+        assert_eq!(completion.prompt_tokens, 25);
+        assert_eq!(completion.completion_tokens, 9);
+    }
+
     #[tokio::test]
     async fn chat_returns_error_on_non_success_status() {
         let server = MockServer::start_async().await;
@@ -411,6 +653,38 @@ mod tests {
         vision_mock.assert_calls(1);
     }
 
+    #[tokio::test]
+    async fn chat_with_images_sends_base64_images_before_text() {
+        let server = MockServer::start_async().await;
+
+        let vision_mock = server.mock(|when, then| {
+            when.method(POST).path("/messages").is_true(|req| {
+                let body = String::from_utf8_lossy(req.body().as_ref());
+                body.contains("\"data\":\"AQID\"")
+                    && body.contains("image/jpeg")
+                    && body.contains("SYS_PROMPT")
+                    && body.find("AQID") < body.find("Analyze")
+            });
+            then.status(200).json_body(json!({
+                "content": [
+                    { "type": "text", "text": "{}" }
+                ]
+            }));
+        });
+
+        let reply = client(&server)
+            .chat_with_images(
+                "Analyze",
+                Some("SYS_PROMPT"),
+                &[ImageInput::jpeg(vec![1, 2, 3])],
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(reply, "{}");
+        vision_mock.assert_calls(1);
+    }
+
     #[test]
     fn claude_models_not_empty() {
         assert!(!CLAUDE_MODELS.is_empty());
@@ -6,11 +6,13 @@
 //! - Vision (изображения)
 
 use std::env;
+use std::time::Instant;
 
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 
-use crate::{Error, Result};
+use super::{Completion, ImageInput};
+use crate::{metrics, Error, Result};
 
 const GEMINI_API_URL: &str = "https://generativelanguage.googleapis.com/v1beta";
 
@@ -64,6 +66,17 @@ impl GeminiClient {
 
     /// Чат с системным промптом.
     pub async fn chat_with_system(&self, message: &str, system: Option<&str>) -> Result<String> {
+        self.chat_completion_with_usage(message, system)
+            .await
+            .map(|completion| completion.text)
+    }
+
+    /// Чат с системным промптом и расходом токенов из ответа API.
+    pub async fn chat_completion_with_usage(
+        &self,
+        message: &str,
+        system: Option<&str>,
+    ) -> Result<Completion> {
         let mut payload = GeminiRequest {
             contents: vec![Content {
                 role: "user".to_string(),
@@ -86,15 +99,59 @@ impl GeminiClient {
             });
         }
 
+        self.send(&payload).await
+    }
+
+    /// Чат с изображениями (vision): картинки идут перед текстом в одном сообщении.
+    pub async fn chat_with_images(
+        &self,
+        message: &str,
+        system: Option<&str>,
+        images: &[ImageInput],
+    ) -> Result<String> {
+        let mut parts: Vec<Part> = images
+            .iter()
+            .map(|image| Part::InlineData {
+                inline_data: InlineData {
+                    mime_type: image.mime_type.clone(),
+                    data: image.base64(),
+                },
+            })
+            .collect();
+        parts.push(Part::Text {
+            text: message.to_string(),
+        });
+
+        let payload = GeminiRequest {
+            contents: vec![Content {
+                role: "user".to_string(),
+                parts,
+            }],
+            generation_config: Some(GenerationConfig {
+                temperature: 0.7,
+                max_output_tokens: 4096,
+            }),
+            system_instruction: system.map(|sys| SystemInstruction {
+                parts: vec![Part::Text {
+                    text: sys.to_string(),
+                }],
+            }),
+        };
+
+        self.send(&payload).await.map(|completion| completion.text)
+    }
+
+    async fn send(&self, payload: &GeminiRequest) -> Result<Completion> {
         let url = format!(
             "{}/models/{}:generateContent?key={}",
             self.base_url, self.model, self.api_key
         );
 
+        let started = Instant::now();
         let response = self
             .http
             .post(&url)
-            .json(&payload)
+            .json(payload)
             .send()
             .await
             .map_err(|e| Error::InvalidArgument(format!("Gemini request failed: {}", e)))?;
@@ -104,6 +161,7 @@ impl GeminiClient {
             .text()
             .await
             .map_err(|e| Error::InvalidArgument(format!("Failed to read response: {}", e)))?;
+        metrics::record_llm_duration("gemini", started.elapsed());
 
         if !status.is_success() {
             return Err(Error::InvalidArgument(format!(
@@ -116,7 +174,14 @@ impl GeminiClient {
             Error::InvalidArgument(format!("Invalid Gemini response: {} - {}", e, text))
         })?;
 
-        gemini_response
+        let usage = gemini_response.usage_metadata.unwrap_or_default();
+        metrics::record_token_usage(
+            "gemini",
+            usage.prompt_token_count,
+            usage.candidates_token_count,
+        );
+
+        let text = gemini_response
             .candidates
             .first()
             .and_then(|c| c.content.parts.first())
@@ -124,7 +189,13 @@ impl GeminiClient {
                 Part::Text { text } => Some(text.clone()),
                 Part::InlineData { .. } => None,
             })
-            .ok_or_else(|| Error::InvalidArgument("Empty response from Gemini".to_string()))
+            .ok_or_else(|| Error::InvalidArgument("Empty response from Gemini".to_string()))?;
+
+        Ok(Completion {
+            text,
+            prompt_tokens: usage.prompt_token_count,
+            completion_tokens: usage.candidates_token_count,
+        })
     }
 
     /// Анализ изображения.
@@ -164,6 +235,7 @@ impl GeminiClient {
             self.base_url, self.model, self.api_key
         );
 
+        let started = Instant::now();
         let response = self
             .http
             .post(&url)
@@ -177,6 +249,7 @@ impl GeminiClient {
             .text()
             .await
             .map_err(|e| Error::InvalidArgument(format!("Failed to read response: {}", e)))?;
+        metrics::record_llm_duration("gemini", started.elapsed());
 
         if !status.is_success() {
             return Err(Error::InvalidArgument(format!(
@@ -262,6 +335,16 @@ struct SystemInstruction {
 #[derive(Debug, Deserialize)]
 struct GeminiResponse {
     candidates: Vec<Candidate>,
+    #[serde(default, rename = "usageMetadata")]
+    usage_metadata: Option<UsageMetadata>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct UsageMetadata {
+    #[serde(default, rename = "promptTokenCount")]
+    prompt_token_count: u32,
+    #[serde(default, rename = "candidatesTokenCount")]
+    candidates_token_count: u32,
 }
 
 #[derive(Debug, Deserialize)]
@@ -339,6 +422,35 @@ mod tests {
         chat_mock.assert_calls(1);
     }
 
+    #[tokio::test]
+    async fn chat_completion_with_usage_parses_token_counts() {
+        let server = MockServer::start_async().await;
+
+        server.mock(|when, then| {
+            when.method(POST)
+                .path("/models/gemini-2.0-flash:generateContent");
+            then.status(200).json_body(json!({
+                "candidates": [
+                    { "content": { "role": "model", "parts": [ { "text": "Hi" } ] } }
+                ],
+                "usageMetadata": {
+                    "promptTokenCount": 31,
+                    "candidatesTokenCount": 4,
+                    "totalTokenCount": 35
+                }
+            }));
+        });
+
+        let completion = client(&server)
+            .chat_completion_with_usage("Hi", None)
+            .await
+            .unwrap();
+
+        assert_eq!(completion.text, "Hi");
+        assert_eq!(completion.prompt_tokens, 31);
+        assert_eq!(completion.completion_tokens, 4);
+    }
+
     #[tokio::test]
     async fn chat_returns_error_on_non_success_status() {
         let server = MockServer::start_async().await;
@@ -404,6 +516,39 @@ mod tests {
         vision_mock.assert_calls(1);
     }
 
+    #[tokio::test]
+    async fn chat_with_images_sends_inline_images_before_text() {
+        let server = MockServer::start_async().await;
+
+        let vision_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/models/gemini-2.0-flash:generateContent")
+                .is_true(|req| {
+                    let body = String::from_utf8_lossy(req.body().as_ref());
+                    body.contains("image/jpeg")
+                        && body.contains("systemInstruction")
+                        && body.find("AQID") < body.find("Analyze")
+                });
+            then.status(200).json_body(json!({
+                "candidates": [
+                    { "content": { "role": "model", "parts": [ { "text": "{}" } ] } }
+                ]
+            }));
+        });
+
+        let reply = client(&server)
+            .chat_with_images(
+                "Analyze",
+                Some("SYS_PROMPT"),
+                &[ImageInput::jpeg(vec![1, 2, 3])],
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(reply, "{}");
+        vision_mock.assert_calls(1);
+    }
+
     #[test]
     fn gemini_models_not_empty() {
         assert!(!GEMINI_MODELS.is_empty());
@@ -0,0 +1,223 @@
+//! Rotation across several API keys of one provider.
+//!
+//! Requests stay on the current key until it hits a rate limit or quota
+//! error; that key then cools down and the next available one takes over.
+//! The pool itself is plain bookkeeping over `Instant`s so it can be tested
+//! without HTTP.
+
+use std::env;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::{Error, Result};
+
+/// How long a rate-limited key is skipped when the response has no `Retry-After`
+pub const DEFAULT_KEY_COOLDOWN: Duration = Duration::from_secs(60);
+
+/// Every key in the pool is cooling down
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeysExhausted {
+    pub keys: usize,
+    /// Time until the first key becomes available again
+    pub retry_in: Duration,
+}
+
+impl fmt::Display for KeysExhausted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "all {} API key(s) are rate limited, next one is available in {}s",
+            self.keys,
+            self.retry_in.as_secs_f64().ceil().max(1.0)
+        )
+    }
+}
+
+/// A key handed out by [`KeyPool::acquire`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyLease {
+    pub index: usize,
+    pub key: String,
+}
+
+/// API keys with per-key cooldowns
+#[derive(Debug, Clone)]
+pub struct KeyPool {
+    keys: Vec<String>,
+    cooldown_until: Vec<Option<Instant>>,
+    current: usize,
+}
+
+impl KeyPool {
+    /// Pool from a list of keys (blank and duplicate entries are dropped)
+    pub fn new<I, S>(keys: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut unique: Vec<String> = Vec::new();
+        for key in keys {
+            let key = key.as_ref().trim();
+            if !key.is_empty() && !unique.iter().any(|k| k == key) {
+                unique.push(key.to_string());
+            }
+        }
+        if unique.is_empty() {
+            return Err(Error::InvalidArgument("API key list is empty".to_string()));
+        }
+
+        Ok(Self {
+            cooldown_until: vec![None; unique.len()],
+            keys: unique,
+            current: 0,
+        })
+    }
+
+    /// Keys from a comma-separated `list_var`, falling back to the single-key `single_var`
+    pub fn from_env(list_var: &str, single_var: &str) -> Option<Result<Self>> {
+        match env::var(list_var) {
+            Ok(list) if !list.trim().is_empty() => Some(Self::new(list.split(','))),
+            _ => env::var(single_var).ok().map(|key| Self::new([key])),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// The current key if usable at `now`, otherwise the next one that isn't cooling down
+    pub fn acquire(&mut self, now: Instant) -> std::result::Result<KeyLease, KeysExhausted> {
+        let n = self.keys.len();
+        for offset in 0..n {
+            let index = (self.current + offset) % n;
+            if self.is_available(index, now) {
+                self.current = index;
+                return Ok(KeyLease {
+                    index,
+                    key: self.keys[index].clone(),
+                });
+            }
+        }
+
+        let retry_in = self
+            .cooldown_until
+            .iter()
+            .flatten()
+            .map(|until| until.saturating_duration_since(now))
+            .min()
+            .unwrap_or_default();
+        Err(KeysExhausted { keys: n, retry_in })
+    }
+
+    /// Put a key on cooldown after a 429/quota error and move on to the next one
+    pub fn mark_rate_limited(&mut self, index: usize, now: Instant, cooldown: Duration) {
+        if let Some(slot) = self.cooldown_until.get_mut(index) {
+            *slot = Some(now + cooldown);
+            if self.current == index {
+                self.current = (index + 1) % self.keys.len();
+            }
+        }
+    }
+
+    fn is_available(&self, index: usize, now: Instant) -> bool {
+        self.cooldown_until[index].is_none_or(|until| until <= now)
+    }
+}
+
+/// Whether an error response means "slow down / out of quota" rather than a bad request
+pub fn is_rate_limited(status: u16, body: &str) -> bool {
+    status == 429
+        || body.contains("rate_limit_exceeded")
+        || body.contains("insufficient_quota")
+        || body.contains("RESOURCE_EXHAUSTED")
+}
+
+/// Cooldown from a `Retry-After` header in seconds, if present and sane
+pub fn retry_after(header: Option<&str>) -> Option<Duration> {
+    header
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(keys: &[&str]) -> KeyPool {
+        KeyPool::new(keys.iter().copied()).unwrap()
+    }
+
+    #[test]
+    fn stays_on_a_working_key() {
+        let mut pool = pool(&["a", "b"]);
+        let now = Instant::now();
+        assert_eq!(pool.acquire(now).unwrap().key, "a");
+        assert_eq!(pool.acquire(now).unwrap().key, "a");
+    }
+
+    #[test]
+    fn rate_limit_on_a_rotates_to_b() {
+        let mut pool = pool(&["a", "b", "c"]);
+        let now = Instant::now();
+
+        let lease = pool.acquire(now).unwrap();
+        pool.mark_rate_limited(lease.index, now, Duration::from_secs(30));
+        assert_eq!(pool.acquire(now).unwrap().key, "b");
+
+        // Once its cooldown is over, "a" is used again after "b" fails
+        let later = now + Duration::from_secs(31);
+        pool.mark_rate_limited(1, later, Duration::from_secs(30));
+        assert_eq!(pool.acquire(later).unwrap().key, "c");
+        pool.mark_rate_limited(2, later, Duration::from_secs(30));
+        assert_eq!(pool.acquire(later).unwrap().key, "a");
+    }
+
+    #[test]
+    fn exhausted_keys_report_the_earliest_retry() {
+        let mut pool = pool(&["a", "b"]);
+        let now = Instant::now();
+        pool.mark_rate_limited(0, now, Duration::from_secs(60));
+        pool.mark_rate_limited(1, now, Duration::from_secs(10));
+
+        let err = pool.acquire(now).unwrap_err();
+        assert_eq!(
+            err,
+            KeysExhausted {
+                keys: 2,
+                retry_in: Duration::from_secs(10)
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "all 2 API key(s) are rate limited, next one is available in 10s"
+        );
+        assert_eq!(
+            pool.acquire(now + Duration::from_secs(10)).unwrap().key,
+            "b"
+        );
+    }
+
+    #[test]
+    fn drops_blank_and_duplicate_keys() {
+        let pool = KeyPool::new(" a , ,b,a".split(',')).unwrap();
+        assert_eq!(pool.len(), 2);
+        assert!(KeyPool::new([" ", ""]).is_err());
+    }
+
+    #[test]
+    fn detects_rate_limit_responses() {
+        assert!(is_rate_limited(429, ""));
+        assert!(is_rate_limited(
+            403,
+            r#"{"error": {"code": "insufficient_quota"}}"#
+        ));
+        assert!(!is_rate_limited(400, "invalid model"));
+        assert_eq!(retry_after(Some("12")), Some(Duration::from_secs(12)));
+        assert_eq!(retry_after(Some("soon")), None);
+        assert_eq!(retry_after(None), None);
+    }
+}
@@ -6,9 +6,15 @@
 //! - Anthropic Claude (chat, vision)
 //! - Yandex SpeechKit (TTS, STT)
 //! - Ollama (local LLM)
+//! - API key rotation on rate limits ([`key_pool`])
+//!
+//! Chat clients also return token usage ([`Completion`]) and count it in
+//! [`crate::metrics::record_token_usage`]; call latency goes to
+//! [`crate::metrics::record_llm_duration`].
 
 pub mod claude;
 pub mod gemini;
+pub mod key_pool;
 pub mod ollama;
 pub mod openai;
 pub mod yandex_tts;
@@ -18,3 +24,33 @@ pub use gemini::GeminiClient;
 pub use ollama::OllamaClient;
 pub use openai::OpenAIClient;
 pub use yandex_tts::YandexTTSClient;
+
+/// Text of an LLM reply with the tokens it cost
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Completion {
+    pub text: String,
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+}
+
+/// An image attached to a multimodal (vision) request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageInput {
+    pub mime_type: String,
+    pub data: Vec<u8>,
+}
+
+impl ImageInput {
+    /// Telegram photos are always JPEG
+    pub fn jpeg(data: Vec<u8>) -> Self {
+        Self {
+            mime_type: "image/jpeg".to_string(),
+            data,
+        }
+    }
+
+    pub fn base64(&self) -> String {
+        use base64::Engine;
+        base64::engine::general_purpose::STANDARD.encode(&self.data)
+    }
+}
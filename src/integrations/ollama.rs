@@ -1,19 +1,38 @@
 //! Ollama Client for local LLM inference.
+//!
+//! The server defaults to `localhost:11434`; set `OLLAMA_HOST` (same format
+//! as the `ollama` CLI, e.g. `gpu-box:11434`) to use a remote machine.
 
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 
-use crate::{Error, Result};
+use crate::{metrics, Error, Result};
 
 const OLLAMA_URL: &str = "http://localhost:11434";
 
+/// Env var with the server address.
+pub const OLLAMA_HOST_ENV: &str = "OLLAMA_HOST";
+
+/// Port used when `OLLAMA_HOST` has none.
+const OLLAMA_PORT: u16 = 11434;
+
+/// Keep the model loaded indefinitely, so interactive bots skip reloads.
+pub const DEFAULT_KEEP_ALIVE: i64 = -1;
+
+/// How long [`OllamaClient::ping`] waits for an answer.
+const PING_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Default embedding model (768 dimensions).
+pub const DEFAULT_EMBED_MODEL: &str = "nomic-embed-text";
+
 /// Ollama client for local LLM.
 #[derive(Debug, Clone)]
 pub struct OllamaClient {
     http: Client,
     base_url: String,
+    keep_alive: i64,
 }
 
 impl Default for OllamaClient {
@@ -23,9 +42,12 @@ impl Default for OllamaClient {
 }
 
 impl OllamaClient {
-    /// Create new client with default URL.
+    /// Create client for `OLLAMA_HOST`, or the default URL when unset.
     pub fn new() -> Self {
-        Self::with_url(OLLAMA_URL)
+        match std::env::var(OLLAMA_HOST_ENV) {
+            Ok(host) if !host.trim().is_empty() => Self::with_url(&normalize_host(&host)),
+            _ => Self::with_url(OLLAMA_URL),
+        }
     }
 
     /// Create client with custom URL.
@@ -38,9 +60,39 @@ impl OllamaClient {
         Self {
             http,
             base_url: base_url.to_string(),
+            keep_alive: DEFAULT_KEEP_ALIVE,
         }
     }
 
+    /// Point the client at another server, e.g. `http://gpu-box:11434`.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Seconds the model stays loaded after a request; negative keeps it
+    /// loaded, 0 unloads it right away.
+    pub fn with_keep_alive(mut self, seconds: i64) -> Self {
+        self.keep_alive = seconds;
+        self
+    }
+
+    /// Server address in use.
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Whether the server answers at all; a quick check for remote hosts.
+    pub async fn ping(&self) -> bool {
+        self.http
+            .get(format!("{}/api/version", self.base_url))
+            .timeout(PING_TIMEOUT)
+            .send()
+            .await
+            .map(|r| r.status().is_success())
+            .unwrap_or(false)
+    }
+
     /// Check if Ollama server is running.
     pub async fn is_running(&self) -> bool {
         self.http
@@ -83,12 +135,14 @@ impl OllamaClient {
             prompt: prompt.to_string(),
             system: system.map(|s| s.to_string()),
             stream: false,
+            keep_alive: self.keep_alive,
             options: GenerateOptions {
                 temperature,
                 num_predict: max_tokens,
             },
         };
 
+        let started = Instant::now();
         let response = self
             .http
             .post(format!("{}/api/generate", self.base_url))
@@ -96,6 +150,7 @@ impl OllamaClient {
             .send()
             .await
             .map_err(|e| Error::InvalidArgument(format!("Ollama request failed: {}", e)))?;
+        metrics::record_llm_duration("ollama", started.elapsed());
 
         let status = response.status();
         if !status.is_success() {
@@ -125,9 +180,11 @@ impl OllamaClient {
             model: model.to_string(),
             messages,
             stream: false,
+            keep_alive: self.keep_alive,
             options: ChatOptions { temperature },
         };
 
+        let started = Instant::now();
         let response = self
             .http
             .post(format!("{}/api/chat", self.base_url))
@@ -135,6 +192,7 @@ impl OllamaClient {
             .send()
             .await
             .map_err(|e| Error::InvalidArgument(format!("Ollama request failed: {}", e)))?;
+        metrics::record_llm_duration("ollama", started.elapsed());
 
         let status = response.status();
         if !status.is_success() {
@@ -153,6 +211,49 @@ impl OllamaClient {
         Ok(result.message.content)
     }
 
+    /// Embed `text` with an embedding model such as [`DEFAULT_EMBED_MODEL`].
+    pub async fn embed(&self, text: &str, model: &str) -> Result<Vec<f32>> {
+        let request = EmbeddingsRequest {
+            model: model.to_string(),
+            prompt: text.to_string(),
+            keep_alive: self.keep_alive,
+        };
+
+        let started = Instant::now();
+        let response = self
+            .http
+            .post(format!("{}/api/embeddings", self.base_url))
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| Error::InvalidArgument(format!("Ollama request failed: {}", e)))?;
+        metrics::record_llm_duration("ollama", started.elapsed());
+
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            return Err(Error::InvalidArgument(format!(
+                "Ollama error {}: {}",
+                status, text
+            )));
+        }
+
+        let result: EmbeddingsResponse = response
+            .json()
+            .await
+            .map_err(|e| Error::InvalidArgument(format!("Invalid response: {}", e)))?;
+
+        // Ollama answers 200 with an empty vector for non-embedding models
+        if result.embedding.is_empty() {
+            return Err(Error::InvalidArgument(format!(
+                "Ollama returned an empty embedding; is {} an embedding model?",
+                model
+            )));
+        }
+
+        Ok(result.embedding)
+    }
+
     /// Sales agent response.
     pub async fn sales_agent_response(
         &self,
@@ -221,6 +322,7 @@ struct GenerateRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     system: Option<String>,
     stream: bool,
+    keep_alive: i64,
     options: GenerateOptions,
 }
 
@@ -240,6 +342,7 @@ struct ChatRequest {
     model: String,
     messages: Vec<ChatMessage>,
     stream: bool,
+    keep_alive: i64,
     options: ChatOptions,
 }
 
@@ -253,11 +356,44 @@ struct ChatResponse {
     message: ChatMessage,
 }
 
+#[derive(Debug, Serialize)]
+struct EmbeddingsRequest {
+    model: String,
+    prompt: String,
+    keep_alive: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingsResponse {
+    embedding: Vec<f32>,
+}
+
 #[derive(Debug, Serialize)]
 struct PullRequest {
     name: String,
 }
 
+/// Turn an `OLLAMA_HOST` value (`host`, `host:port` or a full URL) into a
+/// base URL.
+fn normalize_host(host: &str) -> String {
+    let host = host.trim().trim_end_matches('/');
+    let url = if host.contains("://") {
+        host.to_string()
+    } else {
+        format!("http://{}", host)
+    };
+
+    let authority = url.split("://").nth(1).unwrap_or_default();
+    let has_port = authority
+        .rsplit_once(':')
+        .is_some_and(|(_, port)| !port.is_empty() && port.bytes().all(|b| b.is_ascii_digit()));
+    if has_port {
+        url
+    } else {
+        format!("{}:{}", url, OLLAMA_PORT)
+    }
+}
+
 /// Recommended models.
 pub const RECOMMENDED_MODELS: &[(&str, &str)] = &[
     ("qwen2.5:3b", "1.5GB, быстрая"),
@@ -487,6 +623,134 @@ mod tests {
         assert_eq!(OLLAMA_URL, "http://localhost:11434");
     }
 
+    #[tokio::test]
+    async fn embed_returns_vector() {
+        let server = MockServer::start_async().await;
+
+        let embed_mock = server.mock(|when, then| {
+            when.method(POST).path("/api/embeddings").json_body(json!({
+                "model": DEFAULT_EMBED_MODEL,
+                "prompt": "привет",
+                "keep_alive": -1
+            }));
+            then.status(200)
+                .json_body(json!({ "embedding": [0.5, -0.25, 1.0] }));
+        });
+
+        let embedding = client(&server)
+            .embed("привет", DEFAULT_EMBED_MODEL)
+            .await
+            .unwrap();
+
+        assert_eq!(embedding, vec![0.5, -0.25, 1.0]);
+        embed_mock.assert_calls(1);
+    }
+
+    #[tokio::test]
+    async fn embed_rejects_empty_vector() {
+        let server = MockServer::start_async().await;
+
+        server.mock(|when, then| {
+            when.method(POST).path("/api/embeddings");
+            then.status(200).json_body(json!({ "embedding": [] }));
+        });
+
+        let err = client(&server).embed("hi", "llama3").await.unwrap_err();
+        assert!(format!("{err}").contains("empty embedding"));
+    }
+
+    #[tokio::test]
+    async fn embed_reports_missing_model() {
+        let server = MockServer::start_async().await;
+
+        server.mock(|when, then| {
+            when.method(POST).path("/api/embeddings");
+            then.status(404)
+                .body(r#"{"error":"model \"nomic-embed-text\" not found"}"#);
+        });
+
+        let err = client(&server)
+            .embed("hi", DEFAULT_EMBED_MODEL)
+            .await
+            .unwrap_err();
+        let msg = format!("{err}");
+        assert!(msg.contains("Ollama error 404"));
+        assert!(msg.contains("not found"));
+    }
+
+    #[test]
+    fn with_base_url_overrides_host() {
+        let client = OllamaClient::with_url(OLLAMA_URL).with_base_url("http://gpu-box:11434/");
+        assert_eq!(client.base_url(), "http://gpu-box:11434");
+        assert_eq!(client.keep_alive, DEFAULT_KEEP_ALIVE);
+    }
+
+    #[test]
+    fn normalize_host_accepts_cli_formats() {
+        assert_eq!(normalize_host("gpu-box"), "http://gpu-box:11434");
+        assert_eq!(normalize_host("10.0.0.5:8080"), "http://10.0.0.5:8080");
+        assert_eq!(
+            normalize_host(" https://ollama.lan/ "),
+            "https://ollama.lan:11434"
+        );
+        assert_eq!(
+            normalize_host("http://gpu-box:11434"),
+            "http://gpu-box:11434"
+        );
+    }
+
+    #[tokio::test]
+    async fn generate_sends_keep_alive() {
+        let server = MockServer::start_async().await;
+
+        let gen_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/api/generate")
+                .body_includes("\"keep_alive\":300");
+            then.status(200).json_body(json!({ "response": "ok" }));
+        });
+
+        let reply = client(&server)
+            .with_keep_alive(300)
+            .generate("hi", "llama3", None, 0.2, 64)
+            .await
+            .unwrap();
+
+        assert_eq!(reply, "ok");
+        gen_mock.assert_calls(1);
+    }
+
+    #[tokio::test]
+    async fn chat_keeps_model_loaded_by_default() {
+        let server = MockServer::start_async().await;
+
+        let chat_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/api/chat")
+                .body_includes("\"keep_alive\":-1");
+            then.status(200)
+                .json_body(json!({ "message": { "role": "assistant", "content": "hey" } }));
+        });
+
+        let reply = client(&server).chat(vec![], "llama3", 0.5).await.unwrap();
+
+        assert_eq!(reply, "hey");
+        chat_mock.assert_calls(1);
+    }
+
+    #[tokio::test]
+    async fn ping_reports_reachability() {
+        let server = MockServer::start_async().await;
+        server.mock(|when, then| {
+            when.method(GET).path("/api/version");
+            then.status(200).json_body(json!({ "version": "0.5.7" }));
+        });
+        assert!(client(&server).ping().await);
+
+        // Nothing listens on port 9
+        assert!(!OllamaClient::with_url("http://127.0.0.1:9").ping().await);
+    }
+
     #[tokio::test]
     async fn pull_model_handles_success() {
         let server = MockServer::start_async().await;
@@ -1,29 +1,68 @@
 //! OpenAI API Client for voice AI salesman and other AI tasks.
 
-use std::env;
 use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
 
-use reqwest::Client;
+use reqwest::{Client, RequestBuilder, Response};
 use serde::{Deserialize, Serialize};
 
-use crate::{Error, Result};
+use super::key_pool::{self, KeyPool, DEFAULT_KEY_COOLDOWN};
+use super::Completion;
+use crate::{metrics, Error, Result};
 
 const OPENAI_API_URL: &str = "https://api.openai.com/v1";
 
+/// Longest wait between retries, whatever the attempt number
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Retries of transient failures (429 and 500/502/503)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// No retries: transient errors are returned right away
+    pub const NONE: Self = Self {
+        max_retries: 0,
+        base_delay: Duration::ZERO,
+    };
+
+    /// Delay before retry number `retry` (from 0): `base_delay * 2^retry`,
+    /// scaled into its upper half by `jitter` in `[0, 1)` and capped at a minute
+    pub fn backoff(&self, retry: u32, jitter: f64) -> Duration {
+        let exponential = self.base_delay.saturating_mul(1 << retry.min(16));
+        exponential
+            .min(MAX_BACKOFF)
+            .mul_f64(0.5 + jitter.clamp(0.0, 1.0) / 2.0)
+    }
+}
+
+/// 5xx statuses worth retrying on the same key
+fn is_transient(status: u16) -> bool {
+    matches!(status, 500 | 502 | 503)
+}
+
 /// OpenAI client.
+///
+/// Clones share one key pool, so a rate-limited key is skipped by all of them.
 #[derive(Debug, Clone)]
 pub struct OpenAIClient {
     http: Client,
-    api_key: String,
+    keys: Arc<Mutex<KeyPool>>,
     base_url: String,
+    retry: RetryPolicy,
 }
 
 impl OpenAIClient {
-    /// Create client from environment variable.
+    /// Create client from `OPENAI_API_KEYS` (comma-separated) or `OPENAI_API_KEY`.
     pub fn from_env() -> Result<Self> {
-        let api_key = env::var("OPENAI_API_KEY")
-            .map_err(|_| Error::InvalidArgument("OPENAI_API_KEY не установлен".to_string()))?;
-        Self::new(api_key)
+        let keys = KeyPool::from_env("OPENAI_API_KEYS", "OPENAI_API_KEY")
+            .ok_or_else(|| Error::InvalidArgument("OPENAI_API_KEY не установлен".to_string()))?
+            .map_err(|_| Error::InvalidArgument("OPENAI_API_KEY пустой".to_string()))?;
+        Self::with_keys(keys)
     }
 
     /// Create client with API key.
@@ -32,7 +71,11 @@ impl OpenAIClient {
         if api_key.trim().is_empty() {
             return Err(Error::InvalidArgument("OPENAI_API_KEY пустой".to_string()));
         }
+        Self::with_keys(KeyPool::new([api_key])?)
+    }
 
+    /// Create client rotating over several API keys.
+    pub fn with_keys(keys: KeyPool) -> Result<Self> {
         let http = Client::builder()
             .user_agent("telegram_reader/0.1.0")
             .build()
@@ -40,11 +83,132 @@ impl OpenAIClient {
 
         Ok(Self {
             http,
-            api_key,
+            keys: Arc::new(Mutex::new(keys)),
             base_url: OPENAI_API_URL.to_string(),
+            retry: RetryPolicy::NONE,
         })
     }
 
+    /// Retry 429 and 500/502/503 responses up to `max_retries` times with
+    /// jittered exponential backoff, waiting for `Retry-After` when the API sends it.
+    pub fn with_retry(mut self, max_retries: u32, base_delay: Duration) -> Self {
+        self.retry = RetryPolicy {
+            max_retries,
+            base_delay,
+        };
+        self
+    }
+
+    fn key_pool(&self) -> MutexGuard<'_, KeyPool> {
+        self.keys
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Error after the last attempt; with retries on it says how many were made
+    fn failure(&self, error: String, attempts: u32) -> Error {
+        if self.retry.max_retries == 0 {
+            return Error::InvalidArgument(error);
+        }
+        Error::InvalidArgument(format!(
+            "{} (after {} attempt{})",
+            error,
+            attempts,
+            if attempts == 1 { "" } else { "s" }
+        ))
+    }
+
+    /// Send a request built for the current key; on 429/quota errors the key
+    /// cools down and the request is retried with the next one. A lone key
+    /// only cools down for an explicit Retry-After. With a [`RetryPolicy`],
+    /// exhausted keys and 500/502/503 are waited out and retried.
+    async fn send<F>(&self, what: &str, build: F) -> Result<Response>
+    where
+        F: Fn() -> RequestBuilder,
+    {
+        let mut last_error: Option<String> = None;
+        let mut attempts = 0;
+        let mut retries = 0;
+        loop {
+            let acquired = self.key_pool().acquire(Instant::now());
+            let lease = match acquired {
+                Ok(lease) => lease,
+                Err(exhausted) if retries < self.retry.max_retries => {
+                    retries += 1;
+                    tokio::time::sleep(exhausted.retry_in).await;
+                    continue;
+                }
+                Err(exhausted) => {
+                    let error = match last_error {
+                        Some(error) => format!("{} ({})", error, exhausted),
+                        None => exhausted.to_string(),
+                    };
+                    return Err(self.failure(error, attempts));
+                }
+            };
+
+            attempts += 1;
+            let response = build()
+                .header("Authorization", format!("Bearer {}", lease.key))
+                .send()
+                .await
+                .map_err(|e| Error::InvalidArgument(format!("{} request failed: {}", what, e)))?;
+
+            let status = response.status();
+            if status.is_success() {
+                return Ok(response);
+            }
+
+            let retry_after = key_pool::retry_after(
+                response
+                    .headers()
+                    .get("retry-after")
+                    .and_then(|v| v.to_str().ok()),
+            );
+            let text = response.text().await.unwrap_or_default();
+            let error = format!("{} error {}: {}", what, status, text);
+
+            let rate_limited = key_pool::is_rate_limited(status.as_u16(), &text);
+            let single_key = self.key_pool().len() == 1;
+            if rate_limited && single_key && retry_after.is_none() {
+                // A single key has nothing to rotate to: cooling it down would
+                // only block the next calls, so back off or fail right away
+                if retries < self.retry.max_retries {
+                    let delay = self.retry.backoff(retries, rand::random());
+                    retries += 1;
+                    tokio::time::sleep(delay).await;
+                    continue;
+                }
+                return Err(self.failure(error, attempts));
+            }
+
+            if rate_limited {
+                // Without Retry-After a retrying client cools the key down for
+                // the backoff, not the full default, and waits for it above
+                let cooldown = retry_after.unwrap_or_else(|| {
+                    if self.retry.max_retries > 0 {
+                        self.retry.backoff(retries, rand::random())
+                    } else {
+                        DEFAULT_KEY_COOLDOWN
+                    }
+                });
+                self.key_pool()
+                    .mark_rate_limited(lease.index, Instant::now(), cooldown);
+                last_error = Some(error);
+                continue;
+            }
+
+            if is_transient(status.as_u16()) && retries < self.retry.max_retries {
+                let delay =
+                    retry_after.unwrap_or_else(|| self.retry.backoff(retries, rand::random()));
+                retries += 1;
+                tokio::time::sleep(delay).await;
+                continue;
+            }
+            return Err(self.failure(error, attempts));
+        }
+    }
+
     /// Chat completion.
     pub async fn chat_completion(
         &self,
@@ -53,6 +217,19 @@ impl OpenAIClient {
         temperature: f32,
         max_tokens: u32,
     ) -> Result<String> {
+        self.chat_completion_with_usage(messages, model, temperature, max_tokens)
+            .await
+            .map(|completion| completion.text)
+    }
+
+    /// Chat completion with the token usage reported by the API.
+    pub async fn chat_completion_with_usage(
+        &self,
+        messages: Vec<ChatMessage>,
+        model: &str,
+        temperature: f32,
+        max_tokens: u32,
+    ) -> Result<Completion> {
         let request = ChatRequest {
             model: model.to_string(),
             messages,
@@ -60,36 +237,36 @@ impl OpenAIClient {
             max_tokens,
         };
 
+        let url = format!("{}/chat/completions", self.base_url);
+        let started = Instant::now();
         let response = self
-            .http
-            .post(format!("{}/chat/completions", self.base_url))
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .json(&request)
-            .send()
-            .await
-            .map_err(|e| Error::InvalidArgument(format!("OpenAI request failed: {}", e)))?;
+            .send("OpenAI", || self.http.post(&url).json(&request))
+            .await?;
 
-        let status = response.status();
         let text = response
             .text()
             .await
             .map_err(|e| Error::InvalidArgument(format!("Failed to read response: {}", e)))?;
-
-        if !status.is_success() {
-            return Err(Error::InvalidArgument(format!(
-                "OpenAI error {}: {}",
-                status, text
-            )));
-        }
+        metrics::record_llm_duration("openai", started.elapsed());
 
         let chat_response: ChatResponse = serde_json::from_str(&text)
             .map_err(|e| Error::InvalidArgument(format!("Invalid response: {}", e)))?;
 
-        chat_response
+        let usage = chat_response.usage.unwrap_or_default();
+        metrics::record_token_usage("openai", usage.prompt_tokens, usage.completion_tokens);
+
+        let text = chat_response
             .choices
-            .first()
-            .and_then(|c| c.message.content.clone())
-            .ok_or_else(|| Error::InvalidArgument("Empty response from OpenAI".to_string()))
+            .into_iter()
+            .next()
+            .and_then(|c| c.message.content)
+            .ok_or_else(|| Error::InvalidArgument("Empty response from OpenAI".to_string()))?;
+
+        Ok(Completion {
+            text,
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+        })
     }
 
     /// Продающий агент (использует промпт из файла).
@@ -128,36 +305,28 @@ impl OpenAIClient {
             .and_then(|n| n.to_str())
             .unwrap_or("audio.ogg");
 
-        let form = reqwest::multipart::Form::new()
-            .text("model", "whisper-1")
-            .text("language", language.to_string())
-            .part(
-                "file",
-                reqwest::multipart::Part::bytes(file_bytes).file_name(file_name.to_string()),
-            );
+        // A multipart form can't be reused, so every attempt builds its own
+        let form = || {
+            reqwest::multipart::Form::new()
+                .text("model", "whisper-1")
+                .text("language", language.to_string())
+                .part(
+                    "file",
+                    reqwest::multipart::Part::bytes(file_bytes.clone())
+                        .file_name(file_name.to_string()),
+                )
+        };
 
+        let url = format!("{}/audio/transcriptions", self.base_url);
         let response = self
-            .http
-            .post(format!("{}/audio/transcriptions", self.base_url))
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .multipart(form)
-            .send()
-            .await
-            .map_err(|e| Error::InvalidArgument(format!("Whisper request failed: {}", e)))?;
+            .send("Whisper", || self.http.post(&url).multipart(form()))
+            .await?;
 
-        let status = response.status();
         let text = response
             .text()
             .await
             .map_err(|e| Error::InvalidArgument(format!("Failed to read response: {}", e)))?;
 
-        if !status.is_success() {
-            return Err(Error::InvalidArgument(format!(
-                "Whisper error {}: {}",
-                status, text
-            )));
-        }
-
         let transcription: TranscriptionResponse = serde_json::from_str(&text).map_err(|e| {
             Error::InvalidArgument(format!("Invalid transcription response: {}", e))
         })?;
@@ -173,23 +342,10 @@ impl OpenAIClient {
             input: text.to_string(),
         };
 
+        let url = format!("{}/audio/speech", self.base_url);
         let response = self
-            .http
-            .post(format!("{}/audio/speech", self.base_url))
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .json(&request)
-            .send()
-            .await
-            .map_err(|e| Error::InvalidArgument(format!("TTS request failed: {}", e)))?;
-
-        let status = response.status();
-        if !status.is_success() {
-            let text = response.text().await.unwrap_or_default();
-            return Err(Error::InvalidArgument(format!(
-                "TTS error {}: {}",
-                status, text
-            )));
-        }
+            .send("TTS", || self.http.post(&url).json(&request))
+            .await?;
 
         let bytes = response
             .bytes()
@@ -223,6 +379,16 @@ struct ChatRequest {
 #[derive(Debug, Deserialize)]
 struct ChatResponse {
     choices: Vec<ChatChoice>,
+    #[serde(default)]
+    usage: Option<Usage>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Usage {
+    #[serde(default)]
+    prompt_tokens: u32,
+    #[serde(default)]
+    completion_tokens: u32,
 }
 
 #[derive(Debug, Deserialize)]
@@ -264,6 +430,190 @@ mod tests {
         client
     }
 
+    fn rotating_client(server: &MockServer, keys: &[&str]) -> OpenAIClient {
+        let pool = KeyPool::new(keys.iter().copied()).expect("pool");
+        let mut client = OpenAIClient::with_keys(pool).expect("client");
+        client.base_url = server.base_url();
+        client
+    }
+
+    fn current_key(client: &OpenAIClient) -> String {
+        client.key_pool().acquire(Instant::now()).unwrap().key
+    }
+
+    #[tokio::test]
+    async fn chat_completion_rotates_to_next_key_on_429() {
+        let server = MockServer::start_async().await;
+
+        let limited = server.mock(|when, then| {
+            when.method(POST)
+                .path("/chat/completions")
+                .header("Authorization", "Bearer key_a");
+            then.status(429).body("rate limited");
+        });
+        let ok = server.mock(|when, then| {
+            when.method(POST)
+                .path("/chat/completions")
+                .header("Authorization", "Bearer key_b");
+            then.status(200).json_body(json!({
+                "choices": [
+                    { "message": { "role": "assistant", "content": "from b" } }
+                ]
+            }));
+        });
+
+        let client = rotating_client(&server, &["key_a", "key_b"]);
+        for _ in 0..2 {
+            let reply = client
+                .chat_completion(vec![], "gpt-4o-mini", 0.2, 32)
+                .await
+                .unwrap();
+            assert_eq!(reply, "from b");
+        }
+
+        // key_a stays on cooldown, so the second request goes straight to key_b
+        limited.assert_calls(1);
+        ok.assert_calls(2);
+        assert_eq!(current_key(&client), "key_b");
+    }
+
+    fn completion(content: &str) -> serde_json::Value {
+        json!({
+            "choices": [
+                { "message": { "role": "assistant", "content": content } }
+            ]
+        })
+    }
+
+    #[tokio::test]
+    async fn chat_completion_retries_429_with_backoff() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let server = MockServer::start_async().await;
+
+        // Mocks are matched in creation order: the first two requests get a 429
+        let seen = Arc::new(AtomicUsize::new(0));
+        let counter = seen.clone();
+        let limited = server.mock(|when, then| {
+            when.method(POST)
+                .path("/chat/completions")
+                .is_true(move |_| counter.fetch_add(1, Ordering::SeqCst) < 2);
+            then.status(429).body("rate limited");
+        });
+        let ok = server.mock(|when, then| {
+            when.method(POST).path("/chat/completions");
+            then.status(200).json_body(completion("finally"));
+        });
+
+        let reply = client(&server)
+            .with_retry(3, Duration::from_millis(10))
+            .chat_completion(vec![], "gpt-4o-mini", 0.2, 32)
+            .await
+            .unwrap();
+
+        assert_eq!(reply, "finally");
+        limited.assert_calls(2);
+        ok.assert_calls(1);
+    }
+
+    #[tokio::test]
+    async fn chat_completion_reports_attempts_after_exhausted_retries() {
+        let server = MockServer::start_async().await;
+
+        let unavailable = server.mock(|when, then| {
+            when.method(POST).path("/chat/completions");
+            then.status(503).body("overloaded");
+        });
+
+        let err = client(&server)
+            .with_retry(2, Duration::from_millis(1))
+            .chat_completion(vec![], "gpt-4o-mini", 0.2, 32)
+            .await
+            .unwrap_err();
+
+        let msg = err.to_string();
+        assert!(msg.contains("OpenAI error 503"), "{}", msg);
+        assert!(msg.contains("(after 3 attempts)"), "{}", msg);
+        unavailable.assert_calls(3);
+    }
+
+    #[tokio::test]
+    async fn chat_completion_does_not_retry_client_errors() {
+        let server = MockServer::start_async().await;
+
+        let unauthorized = server.mock(|when, then| {
+            when.method(POST).path("/chat/completions");
+            then.status(401).body("invalid api key");
+        });
+
+        let err = client(&server)
+            .with_retry(3, Duration::from_millis(1))
+            .chat_completion(vec![], "gpt-4o-mini", 0.2, 32)
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().contains("(after 1 attempt)"), "{}", err);
+        unauthorized.assert_calls(1);
+    }
+
+    #[test]
+    fn backoff_doubles_with_jitter_and_cap() {
+        let policy = RetryPolicy {
+            max_retries: 5,
+            base_delay: Duration::from_millis(100),
+        };
+        assert_eq!(policy.backoff(0, 0.0), Duration::from_millis(50));
+        assert_eq!(policy.backoff(0, 1.0), Duration::from_millis(100));
+        assert_eq!(policy.backoff(3, 1.0), Duration::from_millis(800));
+        assert_eq!(policy.backoff(3, 0.5), Duration::from_millis(600));
+        assert_eq!(policy.backoff(30, 1.0), MAX_BACKOFF);
+        assert_eq!(RetryPolicy::NONE.backoff(2, 0.7), Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn chat_completion_reports_exhausted_keys() {
+        let server = MockServer::start_async().await;
+
+        let limited = server.mock(|when, then| {
+            when.method(POST).path("/chat/completions");
+            then.status(429)
+                .header("Retry-After", "20")
+                .body(r#"{"error": {"code": "rate_limit_exceeded"}}"#);
+        });
+
+        let err = rotating_client(&server, &["key_a", "key_b"])
+            .chat_completion(vec![], "gpt-4o-mini", 0.2, 32)
+            .await
+            .unwrap_err();
+
+        let msg = err.to_string();
+        assert!(msg.contains("OpenAI error 429"), "{}", msg);
+        assert!(
+            msg.contains("all 2 API key(s) are rate limited, next one is available in 20s"),
+            "{}",
+            msg
+        );
+        limited.assert_calls(2);
+    }
+
+    #[tokio::test]
+    async fn chat_completion_does_not_rotate_on_other_errors() {
+        let server = MockServer::start_async().await;
+
+        let bad = server.mock(|when, then| {
+            when.method(POST).path("/chat/completions");
+            then.status(400).body("invalid model");
+        });
+
+        let err = rotating_client(&server, &["key_a", "key_b"])
+            .chat_completion(vec![], "gpt-4o-mini", 0.2, 32)
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().contains("OpenAI error 400"));
+        bad.assert_calls(1);
+    }
+
     #[tokio::test]
     async fn chat_completion_returns_first_choice_content() {
         let server = MockServer::start_async().await;
@@ -316,6 +666,55 @@ mod tests {
         completion_mock.assert_calls(1);
     }
 
+    #[tokio::test]
+    async fn a_single_rate_limited_key_is_not_put_on_cooldown() {
+        let server = MockServer::start_async().await;
+
+        let limited = server.mock(|when, then| {
+            when.method(POST).path("/chat/completions");
+            then.status(429).body("rate limited");
+        });
+
+        let client = client(&server);
+        for _ in 0..2 {
+            let err = client
+                .chat_completion(vec![], "gpt-4o-mini", 0.2, 32)
+                .await
+                .unwrap_err();
+            assert!(err.to_string().contains("OpenAI error 429"));
+        }
+        limited.assert_calls(2);
+    }
+
+    #[tokio::test]
+    async fn chat_completion_with_usage_parses_token_counts() {
+        let server = MockServer::start_async().await;
+
+        server.mock(|when, then| {
+            when.method(POST).path("/chat/completions");
+            then.status(200).json_body(json!({
+                "choices": [
+                    { "message": { "role": "assistant", "content": "Hello!" } }
+                ],
+                "usage": { "prompt_tokens": 42, "completion_tokens": 7, "total_tokens": 49 }
+            }));
+        });
+
+        let completion = client(&server)
+            .chat_completion_with_usage(vec![], "gpt-4o-mini", 0.2, 32)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            completion,
+            Completion {
+                text: "Hello!".to_string(),
+                prompt_tokens: 42,
+                completion_tokens: 7,
+            }
+        );
+    }
+
     #[tokio::test]
     async fn chat_completion_returns_error_on_invalid_json() {
         let server = MockServer::start_async().await;
@@ -577,7 +976,7 @@ mod tests {
         let client = OpenAIClient::new("key123").unwrap();
         let cloned = client.clone();
         
-        assert_eq!(cloned.api_key, "key123");
+        assert_eq!(current_key(&cloned), "key123");
     }
 
     #[test]
@@ -588,7 +987,7 @@ mod tests {
     #[test]
     fn openai_client_stores_api_key() {
         let client = OpenAIClient::new("my_secret_key").unwrap();
-        assert_eq!(client.api_key, "my_secret_key");
+        assert_eq!(current_key(&client), "my_secret_key");
     }
 
     #[test]
@@ -1,4 +1,6 @@
 //! Yandex SpeechKit TTS (Text-to-Speech) Client.
+//!
+//! Also recognizes speech (STT), e.g. Telegram voice notes via [`YandexTTSClient::transcribe`].
 
 use std::collections::HashMap;
 use std::env;
@@ -14,6 +16,19 @@ use crate::{Error, Result};
 const TTS_URL: &str = "https://tts.api.cloud.yandex.net/speech/v1/tts:synthesize";
 const STT_URL: &str = "https://stt.api.cloud.yandex.net/speech/v1/stt:recognize";
 
+/// Speech rate range accepted by SpeechKit.
+pub const MIN_SPEED: f32 = 0.1;
+pub const MAX_SPEED: f32 = 3.0;
+
+/// Largest audio accepted by synchronous recognition (about 30 s of speech).
+pub const STT_MAX_AUDIO_BYTES: usize = 1024 * 1024;
+
+/// Sample rate declared for LPCM audio sent to recognition.
+pub const STT_LPCM_SAMPLE_RATE: u32 = 48_000;
+
+/// Voice used when none is chosen with [`YandexTTSClient::with_voice`].
+pub const DEFAULT_VOICE: &str = "alena";
+
 /// Available Russian voices.
 pub const VOICES_RU: &[(&str, &str)] = &[
     ("alena", "Алёна (нейтральный женский)"),
@@ -99,6 +114,10 @@ pub struct YandexTTSClient {
     folder_id: String,
     tts_url: String,
     stt_url: String,
+    voice: String,
+    emotion: Emotion,
+    speed: f32,
+    format: AudioFormat,
 }
 
 impl YandexTTSClient {
@@ -126,6 +145,10 @@ impl YandexTTSClient {
             folder_id,
             tts_url: TTS_URL.to_string(),
             stt_url: STT_URL.to_string(),
+            voice: DEFAULT_VOICE.to_string(),
+            emotion: Emotion::Neutral,
+            speed: 1.0,
+            format: AudioFormat::Mp3,
         })
     }
 
@@ -152,6 +175,10 @@ impl YandexTTSClient {
             folder_id,
             tts_url: TTS_URL.to_string(),
             stt_url: STT_URL.to_string(),
+            voice: DEFAULT_VOICE.to_string(),
+            emotion: Emotion::Neutral,
+            speed: 1.0,
+            format: AudioFormat::Mp3,
         })
     }
 
@@ -166,6 +193,30 @@ impl YandexTTSClient {
         self
     }
 
+    /// Voice used by `synthesize`/`synthesize_ssml`, see [`VOICES_RU`].
+    pub fn with_voice(mut self, voice: impl Into<String>) -> Self {
+        self.voice = voice.into();
+        self
+    }
+
+    /// Emotion (intonation) of the synthesized speech.
+    pub fn with_emotion(mut self, emotion: Emotion) -> Self {
+        self.emotion = emotion;
+        self
+    }
+
+    /// Speech rate, 1.0 is normal; must be within 0.1..=3.0.
+    pub fn with_speed(mut self, speed: f32) -> Result<Self> {
+        self.speed = validate_speed(speed)?;
+        Ok(self)
+    }
+
+    /// Output audio format (mp3 by default).
+    pub fn with_format(mut self, format: AudioFormat) -> Self {
+        self.format = format;
+        self
+    }
+
     fn get_auth_header(&self) -> String {
         if let Some(ref token) = self.iam_token {
             format!("Bearer {}", token)
@@ -191,15 +242,48 @@ impl YandexTTSClient {
         params.insert("lang", "ru-RU".to_string());
         params.insert("voice", voice.to_string());
         params.insert("emotion", emotion.as_str().to_string());
-        params.insert("speed", speed.to_string());
+        params.insert("speed", validate_speed(speed)?.to_string());
         params.insert("format", format.as_str().to_string());
         params.insert("folderId", self.folder_id.clone());
 
+        let bytes = self.synthesize_form(&params).await?;
+        write_audio(output_path, &bytes).await
+    }
+
+    /// Synthesize plain text with the client's voice, emotion, speed and format.
+    pub async fn synthesize(&self, text: &str) -> Result<Vec<u8>> {
+        self.synthesize_with("text", text).await
+    }
+
+    /// Synthesize SSML (`<speak>...</speak>`) with the client's voice, emotion, speed and format.
+    pub async fn synthesize_ssml(&self, ssml: &str) -> Result<Vec<u8>> {
+        if !ssml.trim_start().starts_with("<speak") {
+            return Err(Error::InvalidArgument(
+                "SSML должен начинаться с <speak>".to_string(),
+            ));
+        }
+        self.synthesize_with("ssml", ssml).await
+    }
+
+    async fn synthesize_with(&self, input_kind: &'static str, input: &str) -> Result<Vec<u8>> {
+        let mut params = HashMap::new();
+        params.insert(input_kind, input.to_string());
+        params.insert("lang", "ru-RU".to_string());
+        params.insert("voice", self.voice.clone());
+        params.insert("emotion", self.emotion.as_str().to_string());
+        params.insert("speed", self.speed.to_string());
+        params.insert("format", self.format.as_str().to_string());
+        params.insert("folderId", self.folder_id.clone());
+
+        self.synthesize_form(&params).await
+    }
+
+    async fn synthesize_form(&self, params: &HashMap<&str, String>) -> Result<Vec<u8>> {
         let response = self
             .http
             .post(&self.tts_url)
             .header("Authorization", self.get_auth_header())
-            .form(&params)
+            .form(params)
             .send()
             .await
             .map_err(|e| Error::InvalidArgument(format!("Yandex TTS request failed: {}", e)))?;
@@ -218,15 +302,7 @@ impl YandexTTSClient {
             .await
             .map_err(|e| Error::InvalidArgument(format!("Failed to read audio: {}", e)))?;
 
-        let mut file = File::create(output_path)
-            .await
-            .map_err(|e| Error::InvalidArgument(format!("Failed to create file: {}", e)))?;
-
-        file.write_all(&bytes)
-            .await
-            .map_err(|e| Error::InvalidArgument(format!("Failed to write file: {}", e)))?;
-
-        Ok(())
+        Ok(bytes.to_vec())
     }
 
     /// Recognize speech.
@@ -273,58 +349,129 @@ impl YandexTTSClient {
         Ok(result.result.unwrap_or_default())
     }
 
-    /// Synthesize speech with SSML.
-    pub async fn text_to_speech_ssml(
-        &self,
-        ssml: &str,
-        output_path: &Path,
-        voice: &str,
-    ) -> Result<()> {
-        let mut params = HashMap::new();
-        params.insert("ssml", ssml.to_string());
-        params.insert("lang", "ru-RU".to_string());
-        params.insert("voice", voice.to_string());
-        params.insert("format", "mp3".to_string());
-        params.insert("folderId", self.folder_id.clone());
+    /// Transcribe in-memory audio, e.g. a downloaded Telegram voice note (OGG/Opus).
+    ///
+    /// Only `OggOpus` and `Lpcm` (48 kHz) are accepted by recognition; audio
+    /// over [`STT_MAX_AUDIO_BYTES`] must go through async recognition instead.
+    pub async fn transcribe(&self, audio: &[u8], format: AudioFormat) -> Result<String> {
+        if audio.is_empty() {
+            return Err(Error::InvalidArgument("Пустое аудио".to_string()));
+        }
+        if audio.len() > STT_MAX_AUDIO_BYTES {
+            return Err(Error::InvalidArgument(format!(
+                "Аудио {} байт больше лимита распознавания {} байт",
+                audio.len(),
+                STT_MAX_AUDIO_BYTES
+            )));
+        }
+
+        let mut query = vec![
+            ("lang", "ru-RU".to_string()),
+            ("topic", STTTopic::General.as_str().to_string()),
+            ("folderId", self.folder_id.clone()),
+            ("format", format.as_str().to_string()),
+        ];
+        match format {
+            AudioFormat::OggOpus => {}
+            AudioFormat::Lpcm => query.push(("sampleRateHertz", STT_LPCM_SAMPLE_RATE.to_string())),
+            AudioFormat::Mp3 => {
+                return Err(Error::InvalidArgument(
+                    "Yandex STT не принимает mp3, используйте oggopus или lpcm".to_string(),
+                ))
+            }
+        }
 
         let response = self
             .http
-            .post(&self.tts_url)
+            .post(&self.stt_url)
             .header("Authorization", self.get_auth_header())
-            .form(&params)
+            .query(&query)
+            .body(audio.to_vec())
             .send()
             .await
-            .map_err(|e| Error::InvalidArgument(format!("Yandex TTS request failed: {}", e)))?;
+            .map_err(|e| Error::InvalidArgument(format!("Yandex STT request failed: {}", e)))?;
 
         let status = response.status();
+        let text = response
+            .text()
+            .await
+            .map_err(|e| Error::InvalidArgument(format!("Failed to read response: {}", e)))?;
+
+        let parsed = serde_json::from_str::<STTResponse>(&text);
+        if let Ok(STTResponse {
+            error_code: Some(code),
+            error_message,
+            ..
+        }) = &parsed
+        {
+            return Err(Error::InvalidArgument(format!(
+                "Yandex STT error {} ({}): {}",
+                status,
+                code,
+                error_message.as_deref().unwrap_or("no details")
+            )));
+        }
         if !status.is_success() {
-            let text = response.text().await.unwrap_or_default();
             return Err(Error::InvalidArgument(format!(
-                "Yandex TTS error {}: {}",
+                "Yandex STT error {}: {}",
                 status, text
             )));
         }
 
-        let bytes = response
-            .bytes()
-            .await
-            .map_err(|e| Error::InvalidArgument(format!("Failed to read audio: {}", e)))?;
+        let result =
+            parsed.map_err(|e| Error::InvalidArgument(format!("Invalid STT response: {}", e)))?;
+        Ok(result.result.unwrap_or_default())
+    }
 
-        let mut file = File::create(output_path)
-            .await
-            .map_err(|e| Error::InvalidArgument(format!("Failed to create file: {}", e)))?;
+    /// Synthesize speech with SSML.
+    pub async fn text_to_speech_ssml(
+        &self,
+        ssml: &str,
+        output_path: &Path,
+        voice: &str,
+    ) -> Result<()> {
+        let mut params = HashMap::new();
+        params.insert("ssml", ssml.to_string());
+        params.insert("lang", "ru-RU".to_string());
+        params.insert("voice", voice.to_string());
+        params.insert("format", "mp3".to_string());
+        params.insert("folderId", self.folder_id.clone());
 
-        file.write_all(&bytes)
-            .await
-            .map_err(|e| Error::InvalidArgument(format!("Failed to write file: {}", e)))?;
+        let bytes = self.synthesize_form(&params).await?;
+        write_audio(output_path, &bytes).await
+    }
+}
 
-        Ok(())
+/// Check a speech rate against SpeechKit's 0.1..=3.0 range.
+fn validate_speed(speed: f32) -> Result<f32> {
+    if (MIN_SPEED..=MAX_SPEED).contains(&speed) {
+        Ok(speed)
+    } else {
+        Err(Error::InvalidArgument(format!(
+            "Скорость речи {} вне диапазона {}..{}",
+            speed, MIN_SPEED, MAX_SPEED
+        )))
     }
 }
 
+async fn write_audio(output_path: &Path, bytes: &[u8]) -> Result<()> {
+    let mut file = File::create(output_path)
+        .await
+        .map_err(|e| Error::InvalidArgument(format!("Failed to create file: {}", e)))?;
+
+    file.write_all(bytes)
+        .await
+        .map_err(|e| Error::InvalidArgument(format!("Failed to write file: {}", e)))?;
+
+    Ok(())
+}
+
 #[derive(Debug, Deserialize)]
 struct STTResponse {
     result: Option<String>,
+    /// Set instead of `result` when recognition fails
+    error_code: Option<String>,
+    error_message: Option<String>,
 }
 
 /// List available voices.
@@ -657,6 +804,178 @@ mod tests {
         assert!(format!("{err}").contains("boom"));
     }
 
+    #[test]
+    fn with_speed_rejects_values_outside_yandex_range() {
+        let client =
+            YandexTTSClient::new(Some("key".to_string()), None, "folder".to_string()).unwrap();
+
+        for speed in [0.1, 1.0, 3.0] {
+            assert_eq!(client.clone().with_speed(speed).unwrap().speed, speed);
+        }
+        for speed in [0.0, 0.09, 3.01, -1.0, f32::NAN] {
+            assert!(matches!(
+                client.clone().with_speed(speed),
+                Err(Error::InvalidArgument(_))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn synthesize_ssml_sends_voice_emotion_and_speed() {
+        let server = MockServer::start_async().await;
+        let synth_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/tts")
+                .header("Authorization", "Api-Key key")
+                .is_true(|req| {
+                    let body = String::from_utf8_lossy(req.body().as_ref());
+                    body.contains("ssml=%3Cspeak%3Ehi%3C%2Fspeak%3E")
+                        && !body.contains("text=")
+                        && body.contains("voice=jane")
+                        && body.contains("emotion=good")
+                        && body.contains("speed=1.3")
+                        && body.contains("format=oggopus")
+                        && body.contains("folderId=folder")
+                });
+            then.status(200).body("voice");
+        });
+
+        let client = YandexTTSClient::new(Some("key".to_string()), None, "folder".to_string())
+            .unwrap()
+            .with_urls(server.url("/tts"), server.url("/stt"))
+            .with_voice("jane")
+            .with_emotion(Emotion::Good)
+            .with_format(AudioFormat::OggOpus)
+            .with_speed(1.3)
+            .unwrap();
+
+        let audio = client.synthesize_ssml("<speak>hi</speak>").await.unwrap();
+        assert_eq!(audio, b"voice");
+        synth_mock.assert_calls(1);
+    }
+
+    #[tokio::test]
+    async fn synthesize_uses_defaults_and_rejects_non_ssml() {
+        let server = MockServer::start_async().await;
+        let synth_mock = server.mock(|when, then| {
+            when.method(POST).path("/tts").is_true(|req| {
+                let body = String::from_utf8_lossy(req.body().as_ref());
+                body.contains("text=Hello")
+                    && body.contains("voice=alena")
+                    && body.contains("emotion=neutral")
+                    && body.contains("speed=1")
+                    && body.contains("format=mp3")
+            });
+            then.status(200).body("mp3");
+        });
+
+        let client = YandexTTSClient::new(Some("key".to_string()), None, "folder".to_string())
+            .unwrap()
+            .with_urls(server.url("/tts"), server.url("/stt"));
+
+        assert_eq!(client.synthesize("Hello").await.unwrap(), b"mp3");
+        synth_mock.assert_calls(1);
+
+        let err = client.synthesize_ssml("plain text").await.unwrap_err();
+        assert!(matches!(err, Error::InvalidArgument(_)));
+    }
+
+    #[tokio::test]
+    async fn transcribe_sends_oggopus_voice_note() {
+        let server = MockServer::start_async().await;
+        let stt_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/stt")
+                .header("Authorization", "Api-Key key")
+                .query_param("format", "oggopus")
+                .query_param("lang", "ru-RU")
+                .query_param("folderId", "folder")
+                .body("OggS-voice");
+            then.status(200).json_body(json!({ "result": "привет" }));
+        });
+
+        let client = YandexTTSClient::new(Some("key".to_string()), None, "folder".to_string())
+            .unwrap()
+            .with_urls(server.url("/tts"), server.url("/stt"));
+
+        let text = client
+            .transcribe(b"OggS-voice", AudioFormat::OggOpus)
+            .await
+            .unwrap();
+        assert_eq!(text, "привет");
+        stt_mock.assert_calls(1);
+    }
+
+    #[tokio::test]
+    async fn transcribe_declares_lpcm_sample_rate() {
+        let server = MockServer::start_async().await;
+        let stt_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/stt")
+                .query_param("format", "lpcm")
+                .query_param("sampleRateHertz", "48000");
+            then.status(200).json_body(json!({ "result": "ok" }));
+        });
+
+        let client = YandexTTSClient::new(Some("key".to_string()), None, "folder".to_string())
+            .unwrap()
+            .with_urls(server.url("/tts"), server.url("/stt"));
+
+        let text = client.transcribe(b"pcm", AudioFormat::Lpcm).await.unwrap();
+        assert_eq!(text, "ok");
+        stt_mock.assert_calls(1);
+    }
+
+    #[tokio::test]
+    async fn transcribe_surfaces_recognition_errors() {
+        let server = MockServer::start_async().await;
+        server.mock(|when, then| {
+            when.method(POST).path("/stt");
+            then.status(400).json_body(json!({
+                "error_code": "BAD_REQUEST",
+                "error_message": "audio should be less than 30s"
+            }));
+        });
+
+        let client = YandexTTSClient::new(Some("key".to_string()), None, "folder".to_string())
+            .unwrap()
+            .with_urls(server.url("/tts"), server.url("/stt"));
+
+        let err = client
+            .transcribe(b"OggS", AudioFormat::OggOpus)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("BAD_REQUEST"));
+        assert!(err.contains("audio should be less than 30s"));
+    }
+
+    #[tokio::test]
+    async fn transcribe_rejects_unsupported_input_without_calling_api() {
+        let server = MockServer::start_async().await;
+        let stt_mock = server.mock(|when, then| {
+            when.method(POST).path("/stt");
+            then.status(200).json_body(json!({ "result": "x" }));
+        });
+
+        let client = YandexTTSClient::new(Some("key".to_string()), None, "folder".to_string())
+            .unwrap()
+            .with_urls(server.url("/tts"), server.url("/stt"));
+
+        let too_big = vec![0u8; STT_MAX_AUDIO_BYTES + 1];
+        for (audio, format) in [
+            (&b"mp3"[..], AudioFormat::Mp3),
+            (&b""[..], AudioFormat::OggOpus),
+            (&too_big[..], AudioFormat::OggOpus),
+        ] {
+            assert!(matches!(
+                client.transcribe(audio, format).await,
+                Err(Error::InvalidArgument(_))
+            ));
+        }
+        stt_mock.assert_calls(0);
+    }
+
     #[tokio::test]
     async fn text_to_speech_ssml_writes_bytes_to_output_file() {
         let server = MockServer::start_async().await;
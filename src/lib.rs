@@ -13,17 +13,35 @@
 pub mod analysis;
 pub mod analytics;
 pub mod chat;
+pub mod db;
+pub mod dry_run;
+pub mod engagement;
 pub mod config;
+pub mod confirm;
 pub mod error;
 pub mod export;
+pub mod important;
+pub mod links;
+pub mod markdown;
 pub mod integrations;
 pub mod lightrag;
 pub mod linear;
 pub mod metrics;
 pub mod n8n;
+pub mod offline;
+pub mod output;
+pub mod polling;
+pub mod progress;
 pub mod prompts;
+pub mod quiet_hours;
 pub mod reactions;
+pub mod resolve;
+pub mod rolling;
+pub mod scan;
+pub mod schema;
+pub mod send_limit;
 pub mod session;
+pub mod watermark;
 
 // Re-export common types
 pub use config::{ChatEntity, Config, KNOWN_SENDERS};
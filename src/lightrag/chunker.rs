@@ -1,15 +1,15 @@
-use uuid::Uuid;
+use sha2::{Digest, Sha256};
 
 /// Text chunk produced by the chunker.
 #[derive(Debug, Clone)]
 pub struct Chunk {
-    /// Unique chunk id
-    pub id: Uuid,
+    /// Stable chunk id: sha256 of the normalized text, so re-ingested text keeps its id
+    pub id: String,
     /// Raw text of the chunk
     pub text: String,
-    /// Word index of the first token (for reference)
+    /// Word index of the first token (char offset for sentence chunks)
     pub start: usize,
-    /// Word index after the last token (for reference)
+    /// Word index after the last token (char offset for sentence chunks)
     pub end: usize,
     /// Optional source label (chat, document, etc.)
     pub source: String,
@@ -18,7 +18,7 @@ pub struct Chunk {
 impl Chunk {
     pub fn new(text: String, start: usize, end: usize, source: impl Into<String>) -> Self {
         Self {
-            id: Uuid::new_v4(),
+            id: chunk_id(&text),
             text,
             start,
             end,
@@ -27,13 +27,36 @@ impl Chunk {
     }
 }
 
+/// Content id of a chunk text: hex sha256 over the lowercased text with
+/// whitespace collapsed, so formatting-only differences map to the same id.
+pub fn chunk_id(text: &str) -> String {
+    let normalized = text
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    Sha256::digest(normalized.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
 /// Chunking strategy.
 #[derive(Debug, Clone, Copy)]
 pub enum ChunkingStrategy {
     /// Split by words with overlap (default)
     Words,
+    /// Pack whole sentences into chunks of at most `max_chars` characters,
+    /// repeating trailing sentences of up to `overlap` characters in the next chunk
+    Sentence { max_chars: usize, overlap: usize },
 }
 
+/// Sentence terminators (ASCII and the ellipsis character).
+const TERMINATORS: [char; 4] = ['.', '!', '?', '…'];
+
+/// Closing quotes/brackets that stay with the sentence they end.
+const CLOSERS: [char; 6] = ['"', '\'', ')', '»', '”', '’'];
+
 /// Simple chunker with word-level overlap.
 #[derive(Debug, Clone)]
 pub struct Chunker {
@@ -61,10 +84,22 @@ impl Chunker {
         }
     }
 
+    /// Sentence chunker with at most `max_chars` per chunk and a character overlap.
+    pub fn sentences(max_chars: usize, overlap: usize) -> Self {
+        Self::with_strategy(
+            max_chars,
+            overlap,
+            ChunkingStrategy::Sentence { max_chars, overlap },
+        )
+    }
+
     /// Split text into overlapping chunks.
     pub fn chunk(&self, text: &str, source: impl Into<String>) -> Vec<Chunk> {
         match self.strategy {
             ChunkingStrategy::Words => self.chunk_words(text, source),
+            ChunkingStrategy::Sentence { max_chars, overlap } => {
+                chunk_sentences(text, max_chars.max(1), overlap, source.into())
+            }
         }
     }
 
@@ -94,6 +129,138 @@ impl Chunker {
     }
 }
 
+/// Pack sentences into chunks, carrying whole trailing sentences as overlap.
+fn chunk_sentences(text: &str, max_chars: usize, overlap: usize, source: String) -> Vec<Chunk> {
+    let units: Vec<(usize, usize)> = split_sentences(text)
+        .into_iter()
+        .flat_map(|span| hard_split(text, span, max_chars))
+        .collect();
+
+    let char_len = |start: usize, end: usize| text[start..end].chars().count();
+    let mut chunks = Vec::new();
+    let mut current: Vec<(usize, usize)> = Vec::new();
+
+    for unit in units {
+        if let Some(first) = current.first() {
+            if char_len(first.0, unit.1) > max_chars {
+                chunks.push(sentence_chunk(text, &current, &source));
+
+                // Trailing sentences (never the whole chunk) that fit the overlap
+                // and still leave room for the next sentence
+                let mut keep = current.len();
+                while keep > 1 {
+                    let start = current[keep - 1].0;
+                    if char_len(start, current[current.len() - 1].1) > overlap
+                        || char_len(start, unit.1) > max_chars
+                    {
+                        break;
+                    }
+                    keep -= 1;
+                }
+                current.drain(..keep);
+            }
+        }
+        current.push(unit);
+    }
+
+    if !current.is_empty() {
+        chunks.push(sentence_chunk(text, &current, &source));
+    }
+    chunks
+}
+
+fn sentence_chunk(text: &str, spans: &[(usize, usize)], source: &str) -> Chunk {
+    let start = spans[0].0;
+    let end = spans[spans.len() - 1].1;
+    Chunk::new(
+        text[start..end].to_string(),
+        text[..start].chars().count(),
+        text[..end].chars().count(),
+        source,
+    )
+}
+
+/// Byte spans of the sentences in `text`, trimmed of surrounding whitespace.
+///
+/// A sentence ends at a run of terminators (plus closing quotes) followed by
+/// whitespace. Single-letter words before a period — initials and Russian
+/// abbreviations like `т.е.`, `т.д.`, `А. С. Пушкин` — don't end a sentence.
+fn split_sentences(text: &str) -> Vec<(usize, usize)> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut spans = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let (_, ch) = chars[i];
+        if !TERMINATORS.contains(&ch) {
+            i += 1;
+            continue;
+        }
+
+        let mut end = i + 1;
+        while end < chars.len()
+            && (TERMINATORS.contains(&chars[end].1) || CLOSERS.contains(&chars[end].1))
+        {
+            end += 1;
+        }
+        let at_boundary = end == chars.len() || chars[end].1.is_whitespace();
+
+        if at_boundary && !(ch == '.' && end == i + 1 && is_single_letter_before(&chars, i)) {
+            let end_byte = chars.get(end).map_or(text.len(), |&(b, _)| b);
+            push_trimmed(text, start, end_byte, &mut spans);
+            start = end_byte;
+        }
+        i = end;
+    }
+
+    push_trimmed(text, start, text.len(), &mut spans);
+    spans
+}
+
+/// Whether the word right before position `i` is a single letter.
+fn is_single_letter_before(chars: &[(usize, char)], i: usize) -> bool {
+    match i.checked_sub(1).map(|j| chars[j].1) {
+        Some(letter) if letter.is_alphabetic() => {}
+        _ => return false,
+    }
+    let before = i.checked_sub(2).map(|j| chars[j].1);
+    before.is_none_or(|c| !c.is_alphanumeric())
+}
+
+fn push_trimmed(text: &str, start: usize, end: usize, spans: &mut Vec<(usize, usize)>) {
+    let slice = &text[start..end];
+    let trimmed = slice.trim();
+    if !trimmed.is_empty() {
+        let offset = start + (slice.len() - slice.trim_start().len());
+        spans.push((offset, offset + trimmed.len()));
+    }
+}
+
+/// Split a sentence longer than `max_chars`, at the last space when there is one.
+fn hard_split(text: &str, span: (usize, usize), max_chars: usize) -> Vec<(usize, usize)> {
+    let mut pieces = Vec::new();
+    let (mut start, end) = span;
+
+    while text[start..end].chars().count() > max_chars {
+        let limit = text[start..end]
+            .char_indices()
+            .nth(max_chars)
+            .map_or(end, |(b, _)| start + b);
+        let cut = text[start..limit]
+            .rfind(char::is_whitespace)
+            .filter(|&b| b > 0)
+            .map_or(limit, |b| start + b);
+
+        push_trimmed(text, start, cut, &mut pieces);
+        start = cut;
+        start += text[start..end].len() - text[start..end].trim_start().len();
+    }
+
+    push_trimmed(text, start, end, &mut pieces);
+    pieces
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -176,6 +343,15 @@ mod tests {
         assert_ne!(c1.id, c2.id);
     }
 
+    #[test]
+    fn chunk_id_is_stable_over_formatting() {
+        let c1 = Chunk::new("Hello  World\n".into(), 0, 2, "a");
+        let c2 = Chunk::new("hello world".into(), 5, 7, "b");
+        assert_eq!(c1.id, c2.id);
+        assert_eq!(c1.id.len(), 64);
+        assert_ne!(c1.id, chunk_id("hello worlds"));
+    }
+
     #[test]
     fn chunk_stores_source() {
         let chunk = Chunk::new("text".into(), 0, 1, "my_source");
@@ -296,6 +472,56 @@ mod tests {
         assert_eq!(chunks[0].text, text);
     }
 
+    #[test]
+    fn sentence_chunks_overlap_without_mid_sentence_cuts() {
+        let text = "Мы запускаем бота. Он отвечает на вопросы! Работает ли он ночью? \
+                    Да, т.е. круглосуточно… Спасибо, А. С. Пушкин.";
+        let chunker = Chunker::sentences(50, 30);
+        let chunks = chunker.chunk(text, "test");
+
+        let texts: Vec<&str> = chunks.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            vec![
+                "Мы запускаем бота. Он отвечает на вопросы!",
+                "Он отвечает на вопросы! Работает ли он ночью?",
+                "Работает ли он ночью? Да, т.е. круглосуточно…",
+                "Да, т.е. круглосуточно… Спасибо, А. С. Пушкин.",
+            ]
+        );
+        for chunk in &chunks {
+            assert!(chunk.text.chars().count() <= 50);
+            let ends_sentence = chunk.text.ends_with(['.', '!', '?', '…']);
+            assert!(ends_sentence, "{:?}", chunk.text);
+            assert_eq!(
+                text.chars()
+                    .skip(chunk.start)
+                    .take(chunk.end - chunk.start)
+                    .collect::<String>(),
+                chunk.text
+            );
+        }
+    }
+
+    #[test]
+    fn sentence_chunks_hard_split_long_sentences() {
+        let chunker = Chunker::sentences(10, 0);
+        let chunks = chunker.chunk("Short one. averyveryverylongword and more words here", "t");
+
+        let texts: Vec<&str> = chunks.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            vec![
+                "Short one.",
+                "averyveryv",
+                "erylongwor",
+                "d and more",
+                "words here"
+            ]
+        );
+        assert!(chunks.iter().all(|c| c.text.chars().count() <= 10));
+    }
+
     #[test]
     fn chunker_mixed_content() {
         let chunker = Chunker::new(4, 1);
@@ -10,11 +10,26 @@ pub struct Entity {
     /// Lowercased normalized form (for matching)
     pub normalized: String,
     /// Chunk where the entity was found
-    pub chunk_id: uuid::Uuid,
+    pub chunk_id: String,
     /// Word position inside chunk
     pub position: usize,
+    /// Heuristic type, one of [`ENTITY_TYPES`]
+    pub entity_type: String,
 }
 
+/// Entity types the heuristics can tell apart.
+pub const ENTITY_TYPES: &[&str] = &[
+    "Person", "Company", "Product", "Location", "Topic", "Number", "Name",
+];
+
+/// Legal-form words next to a company name.
+const COMPANY_MARKERS: &[&str] = &[
+    "inc", "llc", "ltd", "corp", "gmbh", "ооо", "оао", "зао", "пао", "ао", "ип",
+];
+
+/// Prepositions that put the next capitalized word in a place.
+const LOCATION_PREPOSITIONS: &[&str] = &["in", "at", "from", "в", "во", "из", "на"];
+
 /// Relation between entities (co-occurrence for now).
 #[derive(Debug, Clone, PartialEq)]
 pub struct Relation {
@@ -28,6 +43,8 @@ pub struct Relation {
 #[derive(Debug, Default, Clone)]
 pub struct EntityExtractor {
     stopwords: HashSet<String>,
+    /// Lowercased types to keep; `None` keeps everything
+    types: Option<HashSet<String>>,
 }
 
 impl EntityExtractor {
@@ -39,7 +56,27 @@ impl EntityExtractor {
         ] {
             stopwords.insert(w.to_string());
         }
-        Self { stopwords }
+        Self {
+            stopwords,
+            types: None,
+        }
+    }
+
+    /// Keep only entities of these types (case-insensitive, see [`ENTITY_TYPES`]);
+    /// an empty list keeps every type.
+    pub fn with_types(mut self, types: Vec<String>) -> Self {
+        self.types = if types.is_empty() {
+            None
+        } else {
+            Some(types.iter().map(|t| t.trim().to_lowercase()).collect())
+        };
+        self
+    }
+
+    fn accepts(&self, entity_type: &str) -> bool {
+        self.types
+            .as_ref()
+            .is_none_or(|types| types.contains(&entity_type.to_lowercase()))
     }
 
     /// Extract entities and relations from a chunk.
@@ -48,9 +85,13 @@ impl EntityExtractor {
         let mut seen = HashSet::new();
         let mut relations = Vec::new();
 
-        for (idx, raw_token) in chunk.text.split_whitespace().enumerate() {
-            let token =
-                raw_token.trim_matches(|c: char| !c.is_alphanumeric() && c != '@' && c != '#');
+        let tokens: Vec<&str> = chunk
+            .text
+            .split_whitespace()
+            .map(|raw| raw.trim_matches(|c: char| !c.is_alphanumeric() && c != '@' && c != '#'))
+            .collect();
+
+        for (idx, &token) in tokens.iter().enumerate() {
             if token.len() < 3 {
                 continue;
             }
@@ -73,12 +114,18 @@ impl EntityExtractor {
                 continue;
             }
 
+            let entity_type = classify(&tokens, idx);
+            if !self.accepts(entity_type) {
+                continue;
+            }
+
             if seen.insert(normalized.clone()) {
                 entities.push(Entity {
                     name: token.to_string(),
                     normalized: normalized.clone(),
-                    chunk_id: chunk.id,
+                    chunk_id: chunk.id.clone(),
                     position: idx,
+                    entity_type: entity_type.to_string(),
                 });
             }
         }
@@ -116,6 +163,35 @@ impl EntityExtractor {
     }
 }
 
+/// Guess the type of the candidate at `idx` from its shape and neighbours.
+fn classify(tokens: &[&str], idx: usize) -> &'static str {
+    let token = tokens[idx];
+    let neighbour = |offset: isize| {
+        idx.checked_add_signed(offset)
+            .and_then(|i| tokens.get(i))
+            .map(|t| t.to_lowercase())
+            .unwrap_or_default()
+    };
+
+    if token.starts_with('@') {
+        "Person"
+    } else if token.starts_with('#') {
+        "Topic"
+    } else if token.chars().all(|c| c.is_numeric()) {
+        "Number"
+    } else if token.chars().any(|c| c.is_numeric()) {
+        "Product"
+    } else if COMPANY_MARKERS.contains(&neighbour(-1).as_str())
+        || COMPANY_MARKERS.contains(&neighbour(1).as_str())
+    {
+        "Company"
+    } else if LOCATION_PREPOSITIONS.contains(&neighbour(-1).as_str()) {
+        "Location"
+    } else {
+        "Name"
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -137,6 +213,42 @@ mod tests {
         assert!(!relations.is_empty());
     }
 
+    #[test]
+    fn types_entities_and_filters_by_schema() {
+        let chunk = Chunk::new(
+            "ООО Ромашка ships X200 to Alice in Berlin, see #sales and @bob since 2024".to_string(),
+            0,
+            14,
+            "test",
+        );
+
+        let (all, _) = EntityExtractor::new().extract(&chunk);
+        let typed: Vec<(&str, &str)> = all
+            .iter()
+            .map(|e| (e.name.as_str(), e.entity_type.as_str()))
+            .collect();
+        assert!(typed.contains(&("Ромашка", "Company")));
+        assert!(typed.contains(&("X200", "Product")));
+        assert!(typed.contains(&("Alice", "Name")));
+        assert!(typed.contains(&("Berlin", "Location")));
+        assert!(typed.contains(&("#sales", "Topic")));
+        assert!(typed.contains(&("@bob", "Person")));
+        assert!(typed.contains(&("2024", "Number")));
+
+        let extractor =
+            EntityExtractor::new().with_types(vec!["company".to_string(), "Product".to_string()]);
+        let (entities, relations) = extractor.extract(&chunk);
+        let names: Vec<&str> = entities.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["Ромашка", "X200"]);
+        assert_eq!(relations.len(), 1);
+
+        // An empty schema keeps the open-ended behaviour
+        let (open, _) = EntityExtractor::new()
+            .with_types(Vec::new())
+            .extract(&chunk);
+        assert_eq!(open.len(), all.len());
+    }
+
     #[test]
     fn ignores_stopwords() {
         let extractor = EntityExtractor::new();
@@ -281,8 +393,9 @@ mod tests {
         let entity = Entity {
             name: "Test".to_string(),
             normalized: "test".to_string(),
-            chunk_id: uuid::Uuid::new_v4(),
+            chunk_id: "chunk".to_string(),
             position: 0,
+            entity_type: "Name".to_string(),
         };
         
         let debug_str = format!("{:?}", entity);
@@ -295,8 +408,9 @@ mod tests {
         let entity = Entity {
             name: "Original".to_string(),
             normalized: "original".to_string(),
-            chunk_id: uuid::Uuid::new_v4(),
+            chunk_id: "chunk".to_string(),
             position: 5,
+            entity_type: "Name".to_string(),
         };
         
         let cloned = entity.clone();
@@ -307,18 +421,20 @@ mod tests {
 
     #[test]
     fn entity_struct_equality() {
-        let chunk_id = uuid::Uuid::new_v4();
+        let chunk_id = "chunk".to_string();
         let entity1 = Entity {
             name: "Test".to_string(),
             normalized: "test".to_string(),
-            chunk_id,
+            chunk_id: chunk_id.clone(),
             position: 0,
+            entity_type: "Name".to_string(),
         };
         let entity2 = Entity {
             name: "Test".to_string(),
             normalized: "test".to_string(),
             chunk_id,
             position: 0,
+            entity_type: "Name".to_string(),
         };
         
         assert_eq!(entity1, entity2);
@@ -331,8 +447,9 @@ mod tests {
         let entity = Entity {
             name: "Test".to_string(),
             normalized: "test".to_string(),
-            chunk_id: uuid::Uuid::new_v4(),
+            chunk_id: "chunk".to_string(),
             position: 0,
+            entity_type: "Name".to_string(),
         };
         
         let mut set = HashSet::new();
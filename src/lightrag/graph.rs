@@ -1,14 +1,13 @@
 use std::collections::{HashMap, HashSet};
 
 use super::entity_extractor::{Entity, Relation};
-use uuid::Uuid;
 
 /// Graph node representing an entity.
 #[derive(Debug, Clone)]
 pub struct Node {
     pub name: String,
     pub occurrences: usize,
-    pub chunks: HashSet<Uuid>,
+    pub chunks: HashSet<String>,
 }
 
 /// Graph edge representing relationship between entities.
@@ -43,7 +42,7 @@ impl KnowledgeGraph {
                     chunks: HashSet::new(),
                 });
             entry.occurrences += 1;
-            entry.chunks.insert(entity.chunk_id);
+            entry.chunks.insert(entity.chunk_id.clone());
         }
     }
 
@@ -115,19 +114,21 @@ mod tests {
     fn stores_entities_and_relations() {
         let mut graph = KnowledgeGraph::new();
 
-        let chunk_id = Uuid::new_v4();
+        let chunk_id = "chunk".to_string();
         let entities = vec![
             Entity {
                 name: "Alice".to_string(),
                 normalized: "alice".to_string(),
-                chunk_id,
+                chunk_id: chunk_id.clone(),
                 position: 0,
+                entity_type: "Name".to_string(),
             },
             Entity {
                 name: "Bob".to_string(),
                 normalized: "bob".to_string(),
                 chunk_id,
                 position: 1,
+                entity_type: "Name".to_string(),
             },
         ];
 
@@ -167,8 +168,8 @@ mod tests {
     #[test]
     fn entity_occurrences_incremented() {
         let mut graph = KnowledgeGraph::new();
-        let chunk_id1 = Uuid::new_v4();
-        let chunk_id2 = Uuid::new_v4();
+        let chunk_id1 = "chunk-1".to_string();
+        let chunk_id2 = "chunk-2".to_string();
 
         let entities = vec![
             Entity {
@@ -176,12 +177,14 @@ mod tests {
                 normalized: "alice".to_string(),
                 chunk_id: chunk_id1,
                 position: 0,
+                entity_type: "Name".to_string(),
             },
             Entity {
                 name: "Alice".to_string(),
                 normalized: "alice".to_string(),
                 chunk_id: chunk_id2,
                 position: 0,
+                entity_type: "Name".to_string(),
             },
         ];
 
@@ -290,20 +293,22 @@ mod tests {
     #[test]
     fn neighbors_for_entities_aggregates_scores() {
         let mut graph = KnowledgeGraph::new();
-        let chunk_id = Uuid::new_v4();
+        let chunk_id = "chunk".to_string();
 
         let entities = vec![
             Entity {
                 name: "Alice".to_string(),
                 normalized: "alice".to_string(),
-                chunk_id,
+                chunk_id: chunk_id.clone(),
                 position: 0,
+                entity_type: "Name".to_string(),
             },
             Entity {
                 name: "Bob".to_string(),
                 normalized: "bob".to_string(),
                 chunk_id,
                 position: 1,
+                entity_type: "Name".to_string(),
             },
         ];
 
@@ -354,7 +359,7 @@ mod tests {
         let node = Node {
             name: "Test".to_string(),
             occurrences: 5,
-            chunks: HashSet::from([Uuid::new_v4()]),
+            chunks: HashSet::from(["chunk".to_string()]),
         };
 
         assert_eq!(node.name, "Test");
@@ -382,7 +387,7 @@ mod tests {
         let node = Node {
             name: "Test".to_string(),
             occurrences: 3,
-            chunks: HashSet::from([Uuid::new_v4()]),
+            chunks: HashSet::from(["chunk".to_string()]),
         };
 
         let cloned = node.clone();
@@ -1,9 +1,10 @@
 use anyhow::{Context, Result};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Mutex;
 
 use tracing::{debug, info, warn};
 
-use super::chunker::{Chunk, Chunker};
+use super::chunker::{Chunk, Chunker, ChunkingStrategy};
 use super::entity_extractor::{Entity, EntityExtractor};
 use super::graph::KnowledgeGraph;
 use crate::analysis::embeddings::EmbeddingService;
@@ -17,13 +18,24 @@ pub enum RetrievalMode {
     VectorOnly,
     /// Only graph/entity matching
     GraphOnly,
+    /// Vector similarity blended with BM25 keyword scoring (exact terms like SKUs)
+    Bm25Hybrid,
 }
 
+/// BM25 term-frequency saturation.
+const BM25_K1: f32 = 1.2;
+/// BM25 document-length normalization.
+const BM25_B: f32 = 0.75;
+
 /// Result of a LightRAG retrieval.
 #[derive(Debug, Clone)]
 pub struct RetrievalResult {
     pub chunk: Chunk,
     pub score: f32,
+    /// Cosine similarity to the query (0 in graph-only mode)
+    pub vector_score: f32,
+    /// Raw BM25 score; [`RetrievalMode::Bm25Hybrid`] scales it by the best match before blending
+    pub bm25_score: f32,
     pub matched_entities: Vec<String>,
     pub related_entities: Vec<String>,
 }
@@ -35,12 +47,20 @@ pub struct LightRAGConfig {
     pub chunk_size: usize,
     /// Overlap between chunks (words)
     pub chunk_overlap: usize,
+    /// How text is split; `Sentence` carries its own size and overlap in characters
+    pub chunking: ChunkingStrategy,
     /// Top-K vector results
     pub vector_top_k: usize,
     /// Max related entities to return
     pub graph_depth: usize,
     /// Embedding dimension for local embeddings
     pub embedding_dim: usize,
+    /// Retrievals kept in the LRU query cache (0 disables it)
+    pub cache_capacity: usize,
+    /// Weight of vector similarity in [`RetrievalMode::Bm25Hybrid`] (the rest goes to BM25)
+    pub bm25_alpha: f32,
+    /// Entity types kept in the graph, e.g. `["Company", "Product"]` (empty keeps all)
+    pub entity_types: Vec<String>,
 }
 
 impl Default for LightRAGConfig {
@@ -48,9 +68,13 @@ impl Default for LightRAGConfig {
         Self {
             chunk_size: 128,
             chunk_overlap: 16,
+            chunking: ChunkingStrategy::Words,
             vector_top_k: 8,
             graph_depth: 4,
             embedding_dim: 256,
+            cache_capacity: 128,
+            bm25_alpha: 0.5,
+            entity_types: Vec::new(),
         }
     }
 }
@@ -61,6 +85,9 @@ struct IndexedChunk {
     chunk: Chunk,
     embedding: Vec<f32>,
     entities: Vec<Entity>,
+    /// BM25 term frequencies, computed at ingestion
+    term_freqs: HashMap<String, u32>,
+    term_count: usize,
 }
 
 #[allow(clippy::large_enum_variant)]
@@ -117,6 +144,72 @@ impl LocalEmbedder {
     }
 }
 
+/// Cache key: normalized query plus everything else that shapes the results.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CacheKey {
+    query: String,
+    limit: usize,
+    mode: RetrievalMode,
+}
+
+impl CacheKey {
+    fn new(query: &str, limit: usize, mode: RetrievalMode) -> Self {
+        Self {
+            query: normalize_query(query),
+            limit,
+            mode,
+        }
+    }
+}
+
+/// Lowercased query with collapsed whitespace, so trivial variants share an entry.
+fn normalize_query(query: &str) -> String {
+    query
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Least-recently-used cache of retrieval results (most recent at the back).
+#[derive(Debug, Default)]
+struct RetrievalCache {
+    capacity: usize,
+    entries: VecDeque<(CacheKey, Vec<RetrievalResult>)>,
+}
+
+impl RetrievalCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: VecDeque::new(),
+        }
+    }
+
+    fn get(&mut self, key: &CacheKey) -> Option<Vec<RetrievalResult>> {
+        let pos = self.entries.iter().position(|(k, _)| k == key)?;
+        let entry = self.entries.remove(pos)?;
+        let results = entry.1.clone();
+        self.entries.push_back(entry);
+        Some(results)
+    }
+
+    fn put(&mut self, key: CacheKey, results: Vec<RetrievalResult>) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.entries.retain(|(k, _)| k != &key);
+        while self.entries.len() >= self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((key, results));
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
 /// Main LightRAG retriever.
 pub struct LightRAGRetriever {
     config: LightRAGConfig,
@@ -125,6 +218,13 @@ pub struct LightRAGRetriever {
     graph: KnowledgeGraph,
     backend: EmbedBackend,
     index: Vec<IndexedChunk>,
+    /// Content ids of indexed chunks, so re-ingested text is skipped
+    chunk_ids: HashSet<String>,
+    /// Chunks containing each term (BM25 document frequency)
+    doc_freqs: HashMap<String, usize>,
+    /// Terms across all chunks, for the average chunk length
+    total_terms: usize,
+    cache: Mutex<RetrievalCache>,
 }
 
 impl LightRAGRetriever {
@@ -152,15 +252,40 @@ impl LightRAGRetriever {
 
     fn new_with_backend(config: LightRAGConfig, backend: EmbedBackend) -> Self {
         Self {
-            chunker: Chunker::new(config.chunk_size, config.chunk_overlap),
-            extractor: EntityExtractor::new(),
+            chunker: Chunker::with_strategy(
+                config.chunk_size,
+                config.chunk_overlap,
+                config.chunking,
+            ),
+            extractor: EntityExtractor::new().with_types(config.entity_types.clone()),
             graph: KnowledgeGraph::new(),
+            cache: Mutex::new(RetrievalCache::new(config.cache_capacity)),
             config,
             backend,
             index: Vec::new(),
+            chunk_ids: HashSet::new(),
+            doc_freqs: HashMap::new(),
+            total_terms: 0,
         }
     }
 
+    /// Keep the last `capacity` retrievals in the query cache (0 disables it).
+    pub fn with_cache(mut self, capacity: usize) -> Self {
+        self.config.cache_capacity = capacity;
+        self.cache = Mutex::new(RetrievalCache::new(capacity));
+        self
+    }
+
+    /// Drop all cached retrievals.
+    pub fn clear_cache(&self) {
+        self.lock_cache().clear();
+    }
+
+    fn lock_cache(&self) -> std::sync::MutexGuard<'_, RetrievalCache> {
+        // A panic mid-update can only leave stale entries behind; keep going
+        self.cache.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
     /// Number of indexed chunks.
     pub fn len(&self) -> usize {
         self.index.len()
@@ -171,6 +296,11 @@ impl LightRAGRetriever {
         self.index.is_empty()
     }
 
+    /// Whether a chunk with this content id (see [`Chunk::id`]) is already indexed.
+    pub fn contains(&self, chunk_id: &str) -> bool {
+        self.chunk_ids.contains(chunk_id)
+    }
+
     /// Ingest text into LightRAG (chunk -> extract -> embed -> graph).
     pub async fn ingest(&mut self, source: &str, text: &str) -> Result<usize> {
         if text.trim().is_empty() {
@@ -182,6 +312,9 @@ impl LightRAGRetriever {
     }
 
     /// Retrieve relevant chunks with optional graph boost.
+    ///
+    /// Repeated queries (same normalized text, limit and mode) are served from
+    /// the LRU cache until the next ingestion.
     pub async fn retrieve(
         &self,
         query: &str,
@@ -192,6 +325,23 @@ impl LightRAGRetriever {
             return Ok(Vec::new());
         }
 
+        let key = CacheKey::new(query, limit, mode);
+        if let Some(cached) = self.lock_cache().get(&key) {
+            debug!("LightRAG cache hit for '{}'", key.query);
+            return Ok(cached);
+        }
+
+        let results = self.retrieve_uncached(query, limit, mode).await?;
+        self.lock_cache().put(key, results.clone());
+        Ok(results)
+    }
+
+    async fn retrieve_uncached(
+        &self,
+        query: &str,
+        limit: usize,
+        mode: RetrievalMode,
+    ) -> Result<Vec<RetrievalResult>> {
         let query_embedding = if mode == RetrievalMode::GraphOnly {
             vec![0.0; self.backend.dimension()]
         } else {
@@ -206,9 +356,18 @@ impl LightRAGRetriever {
         let query_entities: HashSet<String> =
             self.extractor.extract_keywords(query).into_iter().collect();
 
+        let query_terms: HashSet<String> = tokenize(query).into_iter().collect();
+        let bm25_scores: Vec<f32> = self
+            .index
+            .iter()
+            .map(|entry| self.bm25_score(&query_terms, entry))
+            .collect();
+        let best_bm25 = bm25_scores.iter().copied().fold(0.0f32, f32::max);
+        let alpha = self.config.bm25_alpha.clamp(0.0, 1.0);
+
         let mut scored = Vec::new();
 
-        for entry in &self.index {
+        for (entry, &bm25_score) in self.index.iter().zip(&bm25_scores) {
             let vector_score = if mode == RetrievalMode::GraphOnly {
                 0.0
             } else {
@@ -241,11 +400,21 @@ impl LightRAGRetriever {
                 RetrievalMode::VectorOnly => vector_score,
                 RetrievalMode::GraphOnly => graph_score,
                 RetrievalMode::Hybrid => vector_score + graph_score,
+                RetrievalMode::Bm25Hybrid => {
+                    let bm25_norm = if best_bm25 > 0.0 {
+                        bm25_score / best_bm25
+                    } else {
+                        0.0
+                    };
+                    alpha * vector_score + (1.0 - alpha) * bm25_norm
+                }
             };
 
             scored.push(RetrievalResult {
                 chunk: entry.chunk.clone(),
                 score,
+                vector_score,
+                bm25_score,
                 matched_entities,
                 related_entities,
             });
@@ -270,6 +439,7 @@ impl LightRAGRetriever {
         }
 
         let mut chunk_entities = Vec::new();
+        let mut batch_ids = HashSet::new();
 
         for (source, text) in docs {
             if text.trim().is_empty() {
@@ -282,15 +452,17 @@ impl LightRAGRetriever {
             }
 
             for chunk in chunks {
+                // Already indexed (or repeated in this batch): re-ingesting is a no-op
+                if self.chunk_ids.contains(&chunk.id) || !batch_ids.insert(chunk.id.clone()) {
+                    continue;
+                }
                 let (entities, relations) = self.extractor.extract(&chunk);
-                self.graph.add_entities(&entities);
-                self.graph.add_relations(&relations);
-                chunk_entities.push((chunk, entities));
+                chunk_entities.push((chunk, entities, relations));
             }
         }
 
         if chunk_entities.is_empty() {
-            return Ok(0);
+            return Ok(self.index.len());
         }
 
         let embeddings = self
@@ -298,22 +470,69 @@ impl LightRAGRetriever {
             .embed(
                 &chunk_entities
                     .iter()
-                    .map(|(chunk, _)| chunk.text.clone())
+                    .map(|(chunk, _, _)| chunk.text.clone())
                     .collect::<Vec<_>>(),
             )
             .await
             .context("failed to embed chunks")?;
 
-        for ((chunk, entities), embedding) in chunk_entities.into_iter().zip(embeddings) {
+        // The graph is only updated once embedding succeeded, so a failed batch
+        // can be retried without counting its relations twice
+        for ((chunk, entities, relations), embedding) in chunk_entities.into_iter().zip(embeddings)
+        {
+            self.graph.add_entities(&entities);
+            self.graph.add_relations(&relations);
+            let mut term_freqs: HashMap<String, u32> = HashMap::new();
+            let terms = tokenize(&chunk.text);
+            for term in &terms {
+                *term_freqs.entry(term.clone()).or_insert(0) += 1;
+            }
+            for term in term_freqs.keys() {
+                *self.doc_freqs.entry(term.clone()).or_insert(0) += 1;
+            }
+            self.total_terms += terms.len();
+
+            self.chunk_ids.insert(chunk.id.clone());
             self.index.push(IndexedChunk {
                 chunk,
                 embedding,
                 entities,
+                term_freqs,
+                term_count: terms.len(),
             });
         }
 
+        // New chunks can change any ranking
+        self.clear_cache();
+
         Ok(self.index.len())
     }
+
+    /// Okapi BM25 of one chunk against the query terms.
+    fn bm25_score(&self, query_terms: &HashSet<String>, entry: &IndexedChunk) -> f32 {
+        let chunks = self.index.len() as f32;
+        let avg_len = (self.total_terms as f32 / chunks.max(1.0)).max(1.0);
+        let length_norm = 1.0 - BM25_B + BM25_B * entry.term_count as f32 / avg_len;
+
+        query_terms
+            .iter()
+            .filter_map(|term| {
+                let tf = *entry.term_freqs.get(term)? as f32;
+                let df = self.doc_freqs.get(term).copied().unwrap_or(0) as f32;
+                let idf = ((chunks - df + 0.5) / (df + 0.5) + 1.0).ln();
+                Some(idf * tf * (BM25_K1 + 1.0) / (tf + BM25_K1 * length_norm))
+            })
+            .sum()
+    }
+}
+
+/// Lowercased BM25 terms; `-` and `_` stay inside tokens so codes like `SKU-4471` match whole.
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric() && c != '-' && c != '_')
+        .map(|token| token.trim_matches(|c| c == '-' || c == '_'))
+        .filter(|token| !token.is_empty())
+        .map(str::to_lowercase)
+        .collect()
 }
 
 fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
@@ -428,6 +647,30 @@ mod tests {
         assert_eq!(sources.iter().filter(|s| s.as_str() == "doc2").count(), 1);
     }
 
+    #[tokio::test]
+    async fn reingesting_the_same_input_is_a_no_op() {
+        let mut rag = LightRAGRetriever::with_local(LightRAGConfig {
+            chunk_size: 4,
+            chunk_overlap: 0,
+            ..Default::default()
+        });
+        let docs = vec![
+            ("doc1".to_string(), "Alice meets Bob in Berlin".to_string()),
+            ("doc2".to_string(), "alice  meets bob in berlin".to_string()),
+        ];
+
+        let indexed = rag.ingest_documents(&docs).await.unwrap();
+        let weights = rag.graph.related_entities("alice", 5);
+        assert!(!weights.is_empty());
+        let chunk_id = rag.index[0].chunk.id.clone();
+        assert!(rag.contains(&chunk_id));
+        assert!(!rag.contains("missing"));
+
+        assert_eq!(rag.ingest_documents(&docs).await.unwrap(), indexed);
+        assert_eq!(rag.len(), indexed);
+        assert_eq!(rag.graph.related_entities("alice", 5), weights);
+    }
+
     #[tokio::test]
     async fn ingest_ignores_whitespace_only_texts() {
         let mut rag = LightRAGRetriever::with_local(LightRAGConfig::default());
@@ -486,6 +729,100 @@ mod tests {
         assert_eq!(config.vector_top_k, 8);
         assert_eq!(config.graph_depth, 4);
         assert_eq!(config.embedding_dim, 256);
+        assert_eq!(config.cache_capacity, 128);
+        assert_eq!(config.bm25_alpha, 0.5);
+    }
+
+    #[test]
+    fn tokenize_keeps_codes_whole() {
+        assert_eq!(
+            tokenize("Order SKU-4471, (shipped) -today- snake_case!"),
+            vec!["order", "sku-4471", "shipped", "today", "snake_case"]
+        );
+    }
+
+    #[tokio::test]
+    async fn bm25_hybrid_ranks_exact_terms_and_exposes_sub_scores() {
+        let mut rag = LightRAGRetriever::with_local(LightRAGConfig {
+            chunk_size: 16,
+            bm25_alpha: 0.0,
+            ..Default::default()
+        });
+
+        let docs = vec![
+            ("a".to_string(), "Order queue order status".to_string()),
+            ("b".to_string(), "Order SKU-4471 shipped today".to_string()),
+            ("c".to_string(), "Order SKU-9000 is delayed".to_string()),
+        ];
+        rag.ingest_documents(&docs).await.unwrap();
+
+        let results = rag
+            .retrieve("order sku-4471", 3, RetrievalMode::Bm25Hybrid)
+            .await
+            .unwrap();
+
+        assert_eq!(results[0].chunk.source, "b");
+        assert!((results[0].score - 1.0).abs() < 1e-6);
+        assert!(results[0].bm25_score > results[1].bm25_score);
+        assert!(results.iter().all(|r| r.vector_score > 0.0));
+    }
+
+    #[tokio::test]
+    async fn cache_serves_repeated_queries_until_ingest() {
+        let mut rag = LightRAGRetriever::with_local(LightRAGConfig {
+            chunk_size: 8,
+            ..Default::default()
+        })
+        .with_cache(2);
+
+        rag.ingest("doc1", "Alice loves Rust programming")
+            .await
+            .unwrap();
+        let first = rag
+            .retrieve("Rust  programming", 3, RetrievalMode::Hybrid)
+            .await
+            .unwrap();
+        assert_eq!(rag.lock_cache().entries.len(), 1);
+
+        // Normalized query hits the same entry
+        let again = rag
+            .retrieve(" rust programming ", 3, RetrievalMode::Hybrid)
+            .await
+            .unwrap();
+        assert_eq!(again.len(), first.len());
+        assert_eq!(rag.lock_cache().entries.len(), 1);
+
+        // A different limit or mode is a different entry; the oldest is evicted
+        rag.retrieve("rust programming", 1, RetrievalMode::Hybrid)
+            .await
+            .unwrap();
+        rag.retrieve("rust programming", 3, RetrievalMode::VectorOnly)
+            .await
+            .unwrap();
+        let keys: Vec<(usize, RetrievalMode)> = rag
+            .lock_cache()
+            .entries
+            .iter()
+            .map(|(k, _)| (k.limit, k.mode))
+            .collect();
+        assert_eq!(
+            keys,
+            vec![(1, RetrievalMode::Hybrid), (3, RetrievalMode::VectorOnly)]
+        );
+
+        // Ingesting invalidates everything
+        rag.ingest("doc2", "Bob writes Rust programming guides")
+            .await
+            .unwrap();
+        assert!(rag.lock_cache().entries.is_empty());
+        let fresh = rag
+            .retrieve("rust programming", 3, RetrievalMode::Hybrid)
+            .await
+            .unwrap();
+        assert_eq!(fresh.len(), 2);
+
+        rag.clear_cache();
+        assert!(rag.lock_cache().entries.is_empty());
     }
 
     #[test]
@@ -573,6 +910,8 @@ mod tests {
         let result = RetrievalResult {
             chunk,
             score: 0.95,
+            vector_score: 0.9,
+            bm25_score: 0.0,
             matched_entities: vec!["entity1".to_string()],
             related_entities: vec!["entity2".to_string(), "entity3".to_string()],
         };
@@ -1,6 +1,7 @@
-//! Minimal Linear GraphQL client (issue creation).
+//! Minimal Linear GraphQL client (issue creation and updates, comments, file attachments).
 
 use std::collections::HashMap;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
 
 use reqwest::{Client, StatusCode};
@@ -174,6 +175,231 @@ impl LinearClient {
 
         Ok(issue)
     }
+
+    /// Look up an issue by identifier (`ENG-123`) or internal id.
+    pub async fn resolve_issue(&self, issue: &str) -> Result<IssueRef> {
+        let data: IssueLookupResponse = self
+            .post(
+                ISSUE_LOOKUP_QUERY,
+                IssueLookupVariables { id: issue.trim() },
+            )
+            .await?;
+        data.issue
+            .ok_or_else(|| Error::LinearError(format!("Задача '{}' не найдена в Linear", issue)))
+    }
+
+    /// Workflow states of a team.
+    async fn team_states(&self, team_id: &str) -> Result<Vec<WorkflowState>> {
+        let data: WorkflowStatesResponse = self
+            .post(WORKFLOW_STATES_QUERY, WorkflowStatesVariables { team_id })
+            .await?;
+        Ok(data.workflow_states.nodes)
+    }
+
+    /// Change fields of an existing issue (`issue` is an identifier like `ENG-123`).
+    ///
+    /// `changes.state` is a workflow state name of the issue's team, matched
+    /// case-insensitively.
+    pub async fn update_issue(&self, issue: &str, changes: IssueChanges) -> Result<CreatedIssue> {
+        if changes.is_empty() {
+            return Err(Error::InvalidArgument(
+                "Не указано ни одного изменения задачи".to_string(),
+            ));
+        }
+        if changes
+            .title
+            .as_deref()
+            .is_some_and(|t| t.trim().is_empty())
+        {
+            return Err(Error::InvalidArgument(
+                "Заголовок задачи пустой".to_string(),
+            ));
+        }
+
+        let issue = self.resolve_issue(issue).await?;
+        let state_id = match changes.state.as_deref() {
+            Some(name) => {
+                let states = self.team_states(&issue.team.id).await?;
+                let state = states
+                    .iter()
+                    .find(|s| s.name.eq_ignore_ascii_case(name.trim()))
+                    .ok_or_else(|| {
+                        let known: Vec<&str> = states.iter().map(|s| s.name.as_str()).collect();
+                        Error::InvalidArgument(format!(
+                            "Статус '{}' не найден (доступны: {})",
+                            name,
+                            known.join(", ")
+                        ))
+                    })?;
+                Some(state.id.clone())
+            }
+            None => None,
+        };
+
+        self.apply_update(
+            &issue.id,
+            IssueUpdateInputPayload {
+                title: changes.title.map(|t| t.trim().to_string()),
+                description: changes.description,
+                state_id,
+                priority: changes.priority.map(|p| p.clamp(0, 4)),
+                assignee_id: changes.assignee_id,
+            },
+        )
+        .await
+    }
+
+    /// Move an issue to its team's first "completed" state.
+    pub async fn close_issue(&self, issue: &str) -> Result<CreatedIssue> {
+        let issue = self.resolve_issue(issue).await?;
+        let states = self.team_states(&issue.team.id).await?;
+        let done = states
+            .iter()
+            .find(|s| s.state_type == "completed")
+            .ok_or_else(|| {
+                Error::LinearError("У команды нет статуса типа completed".to_string())
+            })?;
+
+        self.apply_update(
+            &issue.id,
+            IssueUpdateInputPayload {
+                state_id: Some(done.id.clone()),
+                ..Default::default()
+            },
+        )
+        .await
+    }
+
+    async fn apply_update(&self, id: &str, input: IssueUpdateInputPayload) -> Result<CreatedIssue> {
+        let data: IssueUpdateResponse = self
+            .post(ISSUE_UPDATE_MUTATION, IssueUpdateVariables { id, input })
+            .await?;
+        data.issue_update
+            .and_then(|iu| if iu.success { iu.issue } else { None })
+            .ok_or_else(|| Error::LinearError("Linear не подтвердил обновление задачи".to_string()))
+    }
+
+    /// Post a comment on an issue.
+    pub async fn create_comment(&self, issue_id: &str, body: &str) -> Result<()> {
+        if body.trim().is_empty() {
+            return Err(Error::InvalidArgument(
+                "Текст комментария пустой".to_string(),
+            ));
+        }
+
+        let data: CommentCreateResponse = self
+            .post(
+                COMMENT_CREATE_MUTATION,
+                CommentCreateVariables {
+                    input: CommentCreateInputPayload {
+                        issue_id,
+                        body: body.trim(),
+                    },
+                },
+            )
+            .await?;
+
+        if !data.comment_create.is_some_and(|r| r.success) {
+            return Err(Error::LinearError(
+                "Linear не подтвердил создание комментария".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Upload a file to Linear storage and attach it to an issue.
+    ///
+    /// Linear hands out a signed upload URL (`fileUpload`), the file is PUT
+    /// there, and the resulting asset URL is linked with `attachmentCreate`.
+    /// Returns the asset URL.
+    pub async fn attach_file(&self, issue_id: &str, path: &Path) -> Result<String> {
+        let content = tokio::fs::read(path).await?;
+        let filename = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .ok_or_else(|| {
+                Error::InvalidArgument(format!("Некорректный путь файла: {}", path.display()))
+            })?;
+        let content_type = content_type_for(path);
+
+        let data: FileUploadResponse = self
+            .post(
+                FILE_UPLOAD_MUTATION,
+                FileUploadVariables {
+                    content_type,
+                    filename: &filename,
+                    size: content.len() as u64,
+                },
+            )
+            .await?;
+        let upload = data
+            .file_upload
+            .filter(|r| r.success)
+            .and_then(|r| r.upload_file)
+            .ok_or_else(|| {
+                Error::LinearError("Linear не выдал адрес для загрузки файла".to_string())
+            })?;
+
+        let mut request = self
+            .http
+            .put(&upload.upload_url)
+            .header("Content-Type", content_type)
+            .header("Cache-Control", "public, max-age=31536000");
+        for header in &upload.headers {
+            request = request.header(&header.key, &header.value);
+        }
+        let response = request
+            .body(content)
+            .send()
+            .await
+            .map_err(|e| Error::LinearError(format!("Не удалось загрузить файл: {}", e)))?;
+        if !response.status().is_success() {
+            return Err(Error::LinearError(format!(
+                "Загрузка файла вернула HTTP {}",
+                response.status().as_u16()
+            )));
+        }
+
+        let data: AttachmentCreateResponse = self
+            .post(
+                ATTACHMENT_CREATE_MUTATION,
+                AttachmentCreateVariables {
+                    input: AttachmentCreateInputPayload {
+                        issue_id,
+                        title: &filename,
+                        url: &upload.asset_url,
+                    },
+                },
+            )
+            .await?;
+        if !data.attachment_create.is_some_and(|r| r.success) {
+            return Err(Error::LinearError(
+                "Linear не подтвердил добавление вложения".to_string(),
+            ));
+        }
+
+        Ok(upload.asset_url)
+    }
+}
+
+/// MIME type for an uploaded file, by extension
+fn content_type_for(path: &Path) -> &'static str {
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "pdf" => "application/pdf",
+        "json" => "application/json",
+        "txt" | "log" | "md" => "text/plain",
+        "csv" => "text/csv",
+        "zip" => "application/zip",
+        _ => "application/octet-stream",
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -187,6 +413,41 @@ pub struct CreateIssueInput {
     pub label_ids: Vec<String>,
 }
 
+/// Fields to change on an existing issue; `None` leaves a field as is.
+#[derive(Debug, Clone, Default)]
+pub struct IssueChanges {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    /// Workflow state name, e.g. `In Progress`
+    pub state: Option<String>,
+    pub priority: Option<i32>,
+    pub assignee_id: Option<String>,
+}
+
+impl IssueChanges {
+    /// Nothing to change
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none()
+            && self.description.is_none()
+            && self.state.is_none()
+            && self.priority.is_none()
+            && self.assignee_id.is_none()
+    }
+}
+
+/// An existing issue with the team it belongs to.
+#[derive(Debug, Clone, Deserialize)]
+pub struct IssueRef {
+    pub id: String,
+    pub identifier: String,
+    pub team: TeamRef,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TeamRef {
+    pub id: String,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct CreatedIssue {
     pub id: String,
@@ -265,6 +526,146 @@ struct IssueCreateResult {
     issue: Option<CreatedIssue>,
 }
 
+#[derive(Debug, Serialize)]
+struct IssueLookupVariables<'a> {
+    id: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct IssueLookupResponse {
+    issue: Option<IssueRef>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct WorkflowStatesVariables<'a> {
+    team_id: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct WorkflowStatesResponse {
+    #[serde(rename = "workflowStates")]
+    workflow_states: WorkflowStateConnection,
+}
+
+#[derive(Debug, Deserialize)]
+struct WorkflowStateConnection {
+    nodes: Vec<WorkflowState>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WorkflowState {
+    id: String,
+    name: String,
+    #[serde(rename = "type")]
+    state_type: String,
+}
+
+#[derive(Debug, Serialize)]
+struct IssueUpdateVariables<'a> {
+    id: &'a str,
+    input: IssueUpdateInputPayload,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct IssueUpdateInputPayload {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    state_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    priority: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    assignee_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IssueUpdateResponse {
+    #[serde(rename = "issueUpdate")]
+    issue_update: Option<IssueCreateResult>,
+}
+
+#[derive(Debug, Serialize)]
+struct CommentCreateVariables<'a> {
+    input: CommentCreateInputPayload<'a>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CommentCreateInputPayload<'a> {
+    issue_id: &'a str,
+    body: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct CommentCreateResponse {
+    #[serde(rename = "commentCreate")]
+    comment_create: Option<MutationResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MutationResult {
+    success: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FileUploadVariables<'a> {
+    content_type: &'a str,
+    filename: &'a str,
+    size: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileUploadResponse {
+    #[serde(rename = "fileUpload")]
+    file_upload: Option<FileUploadResult>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FileUploadResult {
+    success: bool,
+    upload_file: Option<UploadFile>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UploadFile {
+    upload_url: String,
+    asset_url: String,
+    #[serde(default)]
+    headers: Vec<UploadHeader>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UploadHeader {
+    key: String,
+    value: String,
+}
+
+#[derive(Debug, Serialize)]
+struct AttachmentCreateVariables<'a> {
+    input: AttachmentCreateInputPayload<'a>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AttachmentCreateInputPayload<'a> {
+    issue_id: &'a str,
+    title: &'a str,
+    url: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct AttachmentCreateResponse {
+    #[serde(rename = "attachmentCreate")]
+    attachment_create: Option<MutationResult>,
+}
+
 const TEAM_QUERY: &str = r#"
 query TeamId($key: String!) {
   team: teamForKey(key: $key) {
@@ -289,6 +690,76 @@ mutation IssueCreate($input: IssueCreateInput!) {
 }
 "#;
 
+const ISSUE_LOOKUP_QUERY: &str = r#"
+query IssueLookup($id: String!) {
+  issue(id: $id) {
+    id
+    identifier
+    team {
+      id
+    }
+  }
+}
+"#;
+
+const WORKFLOW_STATES_QUERY: &str = r#"
+query WorkflowStates($teamId: ID!) {
+  workflowStates(filter: { team: { id: { eq: $teamId } } }) {
+    nodes {
+      id
+      name
+      type
+    }
+  }
+}
+"#;
+
+const ISSUE_UPDATE_MUTATION: &str = r#"
+mutation IssueUpdate($id: String!, $input: IssueUpdateInput!) {
+  issueUpdate(id: $id, input: $input) {
+    success
+    issue {
+      id
+      identifier
+      title
+      url
+    }
+  }
+}
+"#;
+
+const COMMENT_CREATE_MUTATION: &str = r#"
+mutation CommentCreate($input: CommentCreateInput!) {
+  commentCreate(input: $input) {
+    success
+  }
+}
+"#;
+
+const FILE_UPLOAD_MUTATION: &str = r#"
+mutation FileUpload($contentType: String!, $filename: String!, $size: Int!) {
+  fileUpload(contentType: $contentType, filename: $filename, size: $size) {
+    success
+    uploadFile {
+      uploadUrl
+      assetUrl
+      headers {
+        key
+        value
+      }
+    }
+  }
+}
+"#;
+
+const ATTACHMENT_CREATE_MUTATION: &str = r#"
+mutation AttachmentCreate($input: AttachmentCreateInput!) {
+  attachmentCreate(input: $input) {
+    success
+  }
+}
+"#;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -767,6 +1238,181 @@ mod tests {
         assert!(debug_str.contains("LinearClient"));
     }
 
+    fn mock_issue_lookup(server: &MockServer) {
+        server.mock(|when, then| {
+            when.method(POST)
+                .path("/graphql")
+                .body_includes("query IssueLookup")
+                .body_includes("\"id\":\"ENG-123\"");
+            then.status(200).json_body(serde_json::json!({
+                "data": {
+                    "issue": { "id": "uuid-1", "identifier": "ENG-123", "team": { "id": "team-eng" } }
+                }
+            }));
+        });
+        server.mock(|when, then| {
+            when.method(POST)
+                .path("/graphql")
+                .body_includes("query WorkflowStates")
+                .body_includes("\"teamId\":\"team-eng\"");
+            then.status(200).json_body(serde_json::json!({
+                "data": {
+                    "workflowStates": {
+                        "nodes": [
+                            { "id": "s-todo", "name": "Todo", "type": "unstarted" },
+                            { "id": "s-wip", "name": "In Progress", "type": "started" },
+                            { "id": "s-done", "name": "Done", "type": "completed" }
+                        ]
+                    }
+                }
+            }));
+        });
+    }
+
+    fn issue_update_response() -> serde_json::Value {
+        serde_json::json!({
+            "data": {
+                "issueUpdate": {
+                    "success": true,
+                    "issue": {
+                        "id": "uuid-1",
+                        "identifier": "ENG-123",
+                        "title": "Renamed",
+                        "url": "https://linear.app/eng/issue/ENG-123"
+                    }
+                }
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn update_issue_resolves_identifier_and_state() {
+        let server = MockServer::start_async().await;
+        mock_issue_lookup(&server);
+        let update_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/graphql")
+                .json_body(serde_json::json!({
+                    "query": ISSUE_UPDATE_MUTATION,
+                    "variables": {
+                        "id": "uuid-1",
+                        "input": { "title": "Renamed", "stateId": "s-wip", "priority": 4 }
+                    }
+                }));
+            then.status(200).json_body(issue_update_response());
+        });
+
+        let client = setup_client(&server);
+        let issue = client
+            .update_issue(
+                "ENG-123",
+                IssueChanges {
+                    title: Some(" Renamed ".into()),
+                    state: Some("in progress".into()),
+                    priority: Some(9),
+                    ..Default::default()
+                },
+            )
+            .await
+            .expect("update");
+
+        assert_eq!(issue.identifier.as_deref(), Some("ENG-123"));
+        update_mock.assert_calls(1);
+
+        let err = client
+            .update_issue(
+                "ENG-123",
+                IssueChanges {
+                    state: Some("Shipped".into()),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap_err();
+        assert!(format!("{err}").contains("Статус 'Shipped' не найден"));
+        update_mock.assert_calls(1);
+    }
+
+    #[tokio::test]
+    async fn close_issue_moves_to_completed_state() {
+        let server = MockServer::start_async().await;
+        mock_issue_lookup(&server);
+        let update_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/graphql")
+                .body_includes("mutation IssueUpdate")
+                .body_includes("\"input\":{\"stateId\":\"s-done\"}");
+            then.status(200).json_body(issue_update_response());
+        });
+
+        let client = setup_client(&server);
+        client.close_issue("ENG-123").await.expect("close");
+        update_mock.assert_calls(1);
+    }
+
+    #[tokio::test]
+    async fn update_issue_requires_a_change() {
+        let client = LinearClient::new("key").expect("client");
+        let err = client
+            .update_issue("ENG-1", IssueChanges::default())
+            .await
+            .unwrap_err();
+
+        assert!(format!("{err}").contains("Не указано ни одного изменения"));
+    }
+
+    #[tokio::test]
+    async fn attach_file_uploads_then_links_asset() {
+        let server = MockServer::start_async().await;
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let path = tmp.path().join("trace.log");
+        std::fs::write(&path, "boom at line 3").unwrap();
+
+        let upload_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/graphql")
+                .body_includes("mutation FileUpload")
+                .body_includes("\"contentType\":\"text/plain\"")
+                .body_includes("\"size\":14");
+            then.status(200).json_body(serde_json::json!({
+                "data": {
+                    "fileUpload": {
+                        "success": true,
+                        "uploadFile": {
+                            "uploadUrl": server.url("/upload/trace.log"),
+                            "assetUrl": "https://uploads.linear.app/trace.log",
+                            "headers": [ { "key": "x-goog-meta", "value": "1" } ]
+                        }
+                    }
+                }
+            }));
+        });
+        let put_mock = server.mock(|when, then| {
+            when.method(PUT)
+                .path("/upload/trace.log")
+                .header("x-goog-meta", "1")
+                .body("boom at line 3");
+            then.status(200);
+        });
+        let attach_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/graphql")
+                .body_includes("mutation AttachmentCreate")
+                .body_includes("https://uploads.linear.app/trace.log");
+            then.status(200).json_body(serde_json::json!({
+                "data": { "attachmentCreate": { "success": true } }
+            }));
+        });
+
+        let client = setup_client(&server);
+        let url = client.attach_file("issue-1", &path).await.expect("attach");
+
+        assert_eq!(url, "https://uploads.linear.app/trace.log");
+        upload_mock.assert_calls(1);
+        put_mock.assert_calls(1);
+        attach_mock.assert_calls(1);
+    }
+
     #[tokio::test]
     async fn create_issue_returns_error_on_failed_issue_create() {
         let server = MockServer::start_async().await;
@@ -0,0 +1,248 @@
+//! Link and domain extraction for spam/marketing analysis
+//!
+//! Finds URLs in message text, normalizes them to domains and aggregates
+//! which senders post which domains. Shortened links can optionally be
+//! expanded to their final destination.
+
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::error::{Error, Result};
+
+/// Timeout for a single shortener HEAD request
+pub const DEFAULT_EXPAND_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Well-known URL shorteners whose links are worth expanding
+pub const SHORTENERS: &[&str] = &[
+    "bit.ly",
+    "clck.ru",
+    "cutt.ly",
+    "goo.gl",
+    "is.gd",
+    "ow.ly",
+    "rebrand.ly",
+    "shorturl.at",
+    "t.co",
+    "tinyurl.com",
+];
+
+static URL_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)\b(?:https?://|www\.)[^\s<>"'()\[\]{}]+"#).expect("valid URL regex")
+});
+
+/// URLs found in `text`, in order of appearance (trailing punctuation stripped)
+pub fn extract_urls(text: &str) -> Vec<String> {
+    URL_RE
+        .find_iter(text)
+        .map(|m| {
+            m.as_str()
+                .trim_end_matches(['.', ',', ';', ':', '!', '?'])
+                .to_string()
+        })
+        .filter(|url| domain_of(url).is_some())
+        .collect()
+}
+
+/// Normalized domain of a URL: lowercase, without scheme, credentials, port and `www.`
+pub fn domain_of(url: &str) -> Option<String> {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let host = rest.split(['/', '?', '#']).next()?;
+    let host = host.rsplit('@').next()?;
+    let host = host.split(':').next()?.trim_end_matches('.').to_lowercase();
+    let host = host.strip_prefix("www.").unwrap_or(&host);
+
+    if host.contains('.') && !host.starts_with('.') {
+        Some(host.to_string())
+    } else {
+        None
+    }
+}
+
+/// Whether the domain belongs to a known URL shortener
+pub fn is_shortener(domain: &str) -> bool {
+    SHORTENERS.contains(&domain)
+}
+
+/// Links pointing to one domain
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DomainStats {
+    pub domain: String,
+    pub count: usize,
+    /// Sender → number of links to this domain
+    pub senders: BTreeMap<String, usize>,
+}
+
+impl DomainStats {
+    /// Senders ordered by how many links they posted
+    pub fn top_senders(&self, n: usize) -> Vec<(&str, usize)> {
+        let mut senders: Vec<(&str, usize)> = self
+            .senders
+            .iter()
+            .map(|(name, count)| (name.as_str(), *count))
+            .collect();
+        senders.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        senders.truncate(n);
+        senders
+    }
+}
+
+/// Domain frequency across a chat's messages
+#[derive(Debug, Clone, Default)]
+pub struct DomainReport {
+    domains: HashMap<String, DomainStats>,
+    total_links: usize,
+}
+
+impl DomainReport {
+    /// Count every link of a message
+    pub fn add_message(&mut self, sender: &str, text: &str) {
+        for url in extract_urls(text) {
+            self.add_url(sender, &url);
+        }
+    }
+
+    /// Count a single link (e.g. an expanded shortener target)
+    pub fn add_url(&mut self, sender: &str, url: &str) {
+        let Some(domain) = domain_of(url) else {
+            return;
+        };
+        let stats = self
+            .domains
+            .entry(domain.clone())
+            .or_insert_with(|| DomainStats {
+                domain,
+                count: 0,
+                senders: BTreeMap::new(),
+            });
+        stats.count += 1;
+        *stats.senders.entry(sender.to_string()).or_insert(0) += 1;
+        self.total_links += 1;
+    }
+
+    pub fn total_links(&self) -> usize {
+        self.total_links
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.domains.is_empty()
+    }
+
+    /// Most linked domains first (ties by name)
+    pub fn top(&self, n: usize) -> Vec<&DomainStats> {
+        let mut domains: Vec<&DomainStats> = self.domains.values().collect();
+        domains.sort_by(|a, b| b.count.cmp(&a.count).then(a.domain.cmp(&b.domain)));
+        domains.truncate(n);
+        domains
+    }
+}
+
+/// HTTP client for [`expand_url`], following redirects with a per-request timeout
+pub fn expand_client(timeout: Duration) -> Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .timeout(timeout)
+        .redirect(reqwest::redirect::Policy::limited(10))
+        .build()
+        .map_err(|e| Error::ConnectionError(e.to_string()))
+}
+
+/// Final destination of a (shortened) link via a HEAD request
+pub async fn expand_url(client: &reqwest::Client, url: &str) -> Result<String> {
+    let target = if url.contains("://") {
+        url.to_string()
+    } else {
+        format!("https://{}", url)
+    };
+    let response = client
+        .head(&target)
+        .send()
+        .await
+        .map_err(|e| Error::ConnectionError(format!("{}: {}", url, e)))?;
+    Ok(response.url().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_multiple_urls_per_message() {
+        let text = "Смотри https://Example.com/a?b=1, и www.rust-lang.org/learn. А ещё (http://bit.ly/xyz)!";
+        assert_eq!(
+            extract_urls(text),
+            vec![
+                "https://Example.com/a?b=1",
+                "www.rust-lang.org/learn",
+                "http://bit.ly/xyz",
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_text_without_links() {
+        assert!(extract_urls("no links here, just example.com mentions").is_empty());
+        assert!(extract_urls("https://localhost/").is_empty());
+    }
+
+    #[test]
+    fn normalizes_domains() {
+        assert_eq!(
+            domain_of("https://WWW.Example.COM/path").as_deref(),
+            Some("example.com")
+        );
+        assert_eq!(
+            domain_of("http://user:pw@shop.example.com:8080/").as_deref(),
+            Some("shop.example.com")
+        );
+        assert_eq!(domain_of("www.t.me/channel").as_deref(), Some("t.me"));
+        assert_eq!(
+            domain_of("https://example.com./x").as_deref(),
+            Some("example.com")
+        );
+        assert_eq!(domain_of("https://intranet/"), None);
+    }
+
+    #[test]
+    fn counts_domains_and_senders() {
+        let mut report = DomainReport::default();
+        report.add_message(
+            "alice",
+            "https://bit.ly/1 https://bit.ly/2 https://example.com",
+        );
+        report.add_message("bob", "see www.example.com/page");
+        report.add_message("bob", "http://bit.ly/3");
+        report.add_message("carol", "nothing to see");
+
+        assert_eq!(report.total_links(), 5);
+        let top = report.top(10);
+        assert_eq!(top[0].domain, "bit.ly");
+        assert_eq!(top[0].count, 3);
+        assert_eq!(top[0].top_senders(5), vec![("alice", 2), ("bob", 1)]);
+        assert_eq!(top[1].domain, "example.com");
+        assert_eq!(top[1].count, 2);
+        assert_eq!(top[1].senders.len(), 2);
+    }
+
+    #[test]
+    fn top_breaks_ties_by_domain_and_limits() {
+        let mut report = DomainReport::default();
+        report.add_message(
+            "a",
+            "https://b.com https://a.com https://c.com https://c.com",
+        );
+
+        let domains: Vec<&str> = report.top(2).iter().map(|d| d.domain.as_str()).collect();
+        assert_eq!(domains, vec!["c.com", "a.com"]);
+    }
+
+    #[test]
+    fn detects_shorteners() {
+        assert!(is_shortener("bit.ly"));
+        assert!(is_shortener(
+            &domain_of("https://www.tinyurl.com/abc").unwrap()
+        ));
+        assert!(!is_shortener("example.com"));
+    }
+}
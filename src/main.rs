@@ -5,10 +5,17 @@
 use clap::{Parser, Subcommand};
 use std::net::SocketAddr;
 use std::path::PathBuf;
-use std::time::Instant;
 use tracing_subscriber::EnvFilter;
 
-use telegram_reader::{commands, metrics};
+use telegram_reader::dry_run::PlanFormat;
+use telegram_reader::engagement::EngagementWeights;
+use telegram_reader::export::CsvOptions;
+use telegram_reader::linear::IssueChanges;
+use telegram_reader::output::JsonEnvelope;
+use telegram_reader::polling::PollingConfig;
+use telegram_reader::quiet_hours::{parse_offset, QuietHours};
+use telegram_reader::scan::no_results_message;
+use telegram_reader::{commands, metrics, session};
 use tracing::warn;
 
 #[derive(Parser)]
@@ -20,6 +27,26 @@ struct Cli {
     #[arg(long, env = "METRICS_ADDR")]
     metrics_addr: Option<String>,
 
+    /// Print one JSON envelope {command, ok, data, error} instead of text (digest, analyze, profanity-stats)
+    #[arg(long, global = true, default_value_t = false)]
+    json: bool,
+
+    /// Run without Telegram/LLM access: read messages from --input and use a stub LLM (analyze)
+    #[arg(long, global = true, default_value_t = false)]
+    no_network: bool,
+
+    /// Exported chat history (JSONL, one message per line) for --no-network
+    #[arg(long, global = true)]
+    input: Option<PathBuf>,
+
+    /// Telegram session to use: <name>.session guarded by <name>.lock (default telegram_session)
+    #[arg(long, global = true, env = "TELEGRAM_SESSION")]
+    session: Option<String>,
+
+    /// Seconds to wait for another run to release the session lock (default: fail at once)
+    #[arg(long, global = true, env = "TELEGRAM_LOCK_TIMEOUT")]
+    lock_timeout: Option<u64>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -43,6 +70,26 @@ enum Commands {
         /// Watch chat in real time and log new messages
         #[arg(long, default_value_t = false)]
         watch: bool,
+
+        /// Watch by polling history instead of subscribing to updates
+        #[arg(long, default_value_t = false)]
+        poll: bool,
+
+        /// Base polling interval in milliseconds (used right after activity)
+        #[arg(long, default_value_t = 1000)]
+        poll_base_ms: u64,
+
+        /// Maximum polling interval in milliseconds for quiet chats
+        #[arg(long, default_value_t = 30000)]
+        poll_max_ms: u64,
+
+        /// Append new messages as JSONL to this file (implies --watch)
+        #[arg(long)]
+        watch_out: Option<PathBuf>,
+
+        /// Rotate the --watch-out file to file.1, file.2, ... past this size (MB)
+        #[arg(long, default_value_t = 10)]
+        watch_out_max_mb: u64,
     },
 
     /// Simple chat export (tg.py equivalent)
@@ -62,9 +109,13 @@ enum Commands {
         #[arg(short, long, default_value = "20")]
         limit: usize,
 
-        /// Filter by chat type: all, users, groups, channels
+        /// Filter: all, users, groups, channels, unread, or folder:<name>
         #[arg(short, long, default_value = "all")]
         filter: String,
+
+        /// Output format: table | json
+        #[arg(long, default_value = "table")]
+        output: String,
     },
 
     /// Get most active chats
@@ -72,6 +123,10 @@ enum Commands {
         /// Number of chats to display
         #[arg(short, long, default_value = "20")]
         limit: usize,
+
+        /// Output format: table | json
+        #[arg(long, default_value = "table")]
+        output: String,
     },
 
     /// Get dialogs with metadata
@@ -84,6 +139,10 @@ enum Commands {
         #[arg(long, default_value = "table")]
         format: String,
 
+        /// Order: recent (last message, default) | unread | name
+        #[arg(long, default_value = "recent")]
+        sort: String,
+
         /// Optional output file to save results
         #[arg(short, long)]
         output: Option<PathBuf>,
@@ -101,6 +160,26 @@ enum Commands {
         /// Maximum number of messages
         #[arg(short, long, default_value = "100")]
         limit: usize,
+
+        /// Also save who reacted with what to this JSON file
+        #[arg(long)]
+        reactors: Option<String>,
+
+        /// Only export messages from this date on (YYYY-MM-DD)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Only export messages up to and including this date (YYYY-MM-DD)
+        #[arg(long)]
+        until: Option<String>,
+
+        /// Output format: markdown | jsonl (one JSON object per message, newest first)
+        #[arg(long, default_value = "markdown")]
+        format: commands::export::ExportFormat,
+
+        /// Resume an interrupted export from `<output>.cursor` (writes newest first, also for markdown)
+        #[arg(long, default_value_t = false)]
+        resume: bool,
     },
 
     /// Delete Zoom messages from a chat
@@ -111,6 +190,18 @@ enum Commands {
         /// Maximum messages to scan
         #[arg(short, long, default_value = "3000")]
         limit: usize,
+
+        /// Preview only: print a summary of planned deletions without changing anything
+        #[arg(long, default_value_t = false)]
+        dry_run: bool,
+
+        /// Print the dry-run summary as JSON
+        #[arg(long, default_value_t = false)]
+        plan_json: bool,
+
+        /// Don't ask to confirm the resolved chat (for scripts)
+        #[arg(short = 'y', long, default_value_t = false)]
+        yes: bool,
     },
 
     /// Analyze chat content with AI (categorization, insights)
@@ -134,7 +225,7 @@ enum Commands {
         #[arg(long, default_value = "30")]
         days: i64,
 
-        /// Output format: json | markdown | both
+        /// Output format: json | markdown | both | csv | none
         #[arg(long, default_value = "both")]
         output_format: String,
 
@@ -142,6 +233,10 @@ enum Commands {
         #[arg(long, default_value = "analysis_results")]
         output_dir: PathBuf,
 
+        /// Also append each result as one JSON line to this file (time series across runs)
+        #[arg(long)]
+        append_to: Option<PathBuf>,
+
         /// Custom prompt file (Markdown)
         #[arg(long)]
         prompt: Option<PathBuf>,
@@ -169,6 +264,46 @@ enum Commands {
         /// Max tokens for LLM response
         #[arg(long, default_value = "2000")]
         max_tokens: u32,
+
+        /// Important sender ids/usernames to always keep (comma-separated, adds to config.yml)
+        #[arg(long, value_delimiter = ',')]
+        important: Vec<String>,
+
+        /// Fail when the LLM output doesn't match the analysis schema
+        #[arg(long, default_value_t = false)]
+        strict: bool,
+
+        /// Only analyze messages newer than the last successful run
+        #[arg(long, default_value_t = false)]
+        since_last: bool,
+
+        /// Engagement score weights, e.g. messages=1,reactions=2,replies=1,recency=0.5,half_life=7
+        #[arg(long)]
+        engagement_weights: Option<String>,
+
+        /// Rank key participants by the local engagement score even if the LLM scores them
+        #[arg(long, default_value_t = false)]
+        local_engagement: bool,
+
+        /// Keep at most N topics in the report, by mentions (0 = all)
+        #[arg(long, default_value_t = 10)]
+        max_topics: usize,
+
+        /// Keep at most N key discussions, by message count (0 = all)
+        #[arg(long, default_value_t = 10)]
+        max_discussions: usize,
+
+        /// Keep at most N key participants, by engagement score (0 = all)
+        #[arg(long, default_value_t = 15)]
+        max_participants: usize,
+
+        /// Output language of free-text fields, e.g. English (category/sentiment stay canonical)
+        #[arg(long)]
+        language: Option<String>,
+
+        /// Attach recent photos to the prompt (claude/gemini; capped count and size)
+        #[arg(long, default_value_t = false)]
+        include_vision: bool,
     },
 
     /// Start AI auto-responder
@@ -176,11 +311,22 @@ enum Commands {
         /// OpenAI model to use
         #[arg(short, long, default_value = "gpt-4o-mini")]
         model: String,
+
+        /// Quiet hours window, e.g. 22:00-08:00 (replies are queued until it ends)
+        #[arg(long)]
+        quiet_hours: Option<String>,
+
+        /// UTC offset of the quiet hours window, e.g. +03:00 (default: QUIET_HOURS_TZ or UTC)
+        #[arg(long)]
+        quiet_tz: Option<String>,
     },
 
     /// Initialize a new session (use only once!)
     InitSession,
 
+    /// Check config.yml and env for problems without connecting (non-zero exit on errors)
+    Validate,
+
     /// Create a Linear issue via GraphQL API
     Linear {
         /// Linear API key (fallback: LINEAR_API_KEY)
@@ -214,12 +360,55 @@ enum Commands {
         /// Label IDs, comma-separated or repeated
         #[arg(long, value_delimiter = ',', env = "LINEAR_LABEL_IDS")]
         labels: Vec<String>,
+
+        /// Comment to post on the issue after creating it
+        #[arg(long)]
+        comment: Option<String>,
+
+        /// File to upload and attach to the issue (repeatable)
+        #[arg(long = "attach-file")]
+        attach_files: Vec<PathBuf>,
+    },
+
+    /// Update or close an existing Linear issue
+    LinearUpdate {
+        /// Issue identifier, e.g. ENG-123
+        issue: String,
+
+        /// Linear API key (fallback: LINEAR_API_KEY)
+        #[arg(long, env = "LINEAR_API_KEY")]
+        api_key: Option<String>,
+
+        /// New title
+        #[arg(short, long)]
+        title: Option<String>,
+
+        /// New description
+        #[arg(short, long)]
+        description: Option<String>,
+
+        /// Workflow state name, e.g. "In Progress"
+        #[arg(long)]
+        state: Option<String>,
+
+        /// Priority 0..4
+        #[arg(long)]
+        priority: Option<i32>,
+
+        /// Assign to user id
+        #[arg(long)]
+        assignee: Option<String>,
+
+        /// Move the issue to the team's completed state
+        #[arg(long)]
+        close: bool,
     },
 
     /// Generate AI-powered chat digest/summary
     Digest {
-        /// Chat name to analyze
-        chat: String,
+        /// Chat name to analyze (comma-separated for a combined report)
+        #[arg(value_delimiter = ',', required = true)]
+        chat: Vec<String>,
 
         /// Period in hours (default: 24)
         #[arg(short = 'H', long, default_value = "24")]
@@ -232,6 +421,30 @@ enum Commands {
         /// OpenAI model to use
         #[arg(short, long, default_value = "gpt-4o-mini")]
         model: String,
+
+        /// Output structured JSON (summary, highlights, action items, decisions)
+        #[arg(long, default_value_t = false)]
+        structured: bool,
+
+        /// Important sender ids/usernames to always keep (comma-separated, adds to config.yml)
+        #[arg(long, value_delimiter = ',')]
+        important: Vec<String>,
+
+        /// Only digest messages newer than the last successful run
+        #[arg(long, default_value_t = false)]
+        since_last: bool,
+
+        /// Also post the digest to this chat (alias, @username or user id), split at 4096 chars
+        #[arg(long)]
+        post_to: Option<String>,
+
+        /// Don't print the digest to stdout (with --post-to)
+        #[arg(long, default_value_t = false)]
+        quiet: bool,
+
+        /// Write the digest in this language, e.g. English (default: the chat's language)
+        #[arg(long)]
+        language: Option<String>,
     },
 
     /// Moderate chat - filter profanity
@@ -246,6 +459,38 @@ enum Commands {
         /// Send warning messages
         #[arg(short, long, default_value = "true")]
         warn: bool,
+
+        /// Extra banned terms, one per line (`#` comments ignored), matched as whole words
+        #[arg(long)]
+        wordlist: Option<PathBuf>,
+
+        /// Never moderate these user ids, comma-separated
+        #[arg(long, value_delimiter = ',')]
+        exempt_user_ids: Vec<i64>,
+
+        /// Never moderate the chat's admins, anonymous ones included (needs permission to list them)
+        #[arg(long, default_value_t = false)]
+        exempt_admins: bool,
+
+        /// Base polling interval in milliseconds (used right after activity)
+        #[arg(long, default_value_t = 1000)]
+        poll_base_ms: u64,
+
+        /// Maximum polling interval in milliseconds for quiet chats
+        #[arg(long, default_value_t = 30000)]
+        poll_max_ms: u64,
+
+        /// Preview only: print a summary of planned deletions and warnings without changing anything
+        #[arg(long, default_value_t = false)]
+        dry_run: bool,
+
+        /// Print the dry-run summary as JSON
+        #[arg(long, default_value_t = false)]
+        plan_json: bool,
+
+        /// Don't ask to confirm the resolved chat (for scripts)
+        #[arg(short = 'y', long, default_value_t = false)]
+        yes: bool,
     },
 
     /// Analyze chat for profanity statistics
@@ -253,9 +498,29 @@ enum Commands {
         /// Chat name to analyze
         chat: String,
 
-        /// Maximum messages to analyze
+        /// Maximum messages to analyze (most recent first)
         #[arg(short, long, default_value = "1000")]
         limit: usize,
+
+        /// Estimate from a random sample of N messages across the whole history instead
+        #[arg(long, conflicts_with = "limit")]
+        sample: Option<usize>,
+
+        /// Seed for --sample (random if omitted; printed so the run can be reproduced)
+        #[arg(long, requires = "sample")]
+        seed: Option<u64>,
+
+        /// Save the per-sender breakdown to a file (.csv for CSV, JSON otherwise)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Prepend a UTF-8 BOM to CSV output (for Excel)
+        #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
+        csv_bom: bool,
+
+        /// CSV delimiter: , | ; | tab
+        #[arg(long, default_value = ",")]
+        csv_delimiter: String,
     },
 
     /// Parse chat for CRM data (contacts, deals, action items)
@@ -274,6 +539,22 @@ enum Commands {
         /// Export contacts to CSV file
         #[arg(long)]
         export_csv: Option<String>,
+
+        /// Merge contacts into this CSV (by phone or username) and rewrite it
+        #[arg(long)]
+        merge_into: Option<PathBuf>,
+
+        /// Export deals (stage, amount, currency) to CSV file
+        #[arg(long)]
+        export_deals_csv: Option<String>,
+
+        /// Prepend a UTF-8 BOM to CSV output (for Excel)
+        #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
+        csv_bom: bool,
+
+        /// CSV delimiter: , | ; | tab
+        #[arg(long, default_value = ",")]
+        csv_delimiter: String,
     },
 
     /// Like (react to) messages from a specific user in a chat
@@ -293,6 +574,26 @@ enum Commands {
         /// Maximum messages to scan
         #[arg(short, long, default_value = "500")]
         limit: usize,
+
+        /// Preview only: print a summary of planned reactions without changing anything
+        #[arg(long, default_value_t = false)]
+        dry_run: bool,
+
+        /// Print the dry-run summary as JSON
+        #[arg(long, default_value_t = false)]
+        plan_json: bool,
+
+        /// Don't ask to confirm the resolved chat (for scripts)
+        #[arg(short = 'y', long, default_value_t = false)]
+        yes: bool,
+
+        /// Only react to messages that already have at least N reactions
+        #[arg(long, default_value_t = 0)]
+        min_reactions: u32,
+
+        /// Stop after sending this many reactions in one run
+        #[arg(long)]
+        max_per_run: Option<usize>,
     },
 
     /// Send reactions to specific messages (ids/links) or latest messages
@@ -301,7 +602,7 @@ enum Commands {
         #[arg(short, long)]
         chat: String,
 
-        /// Reaction emoji (default ❤️)
+        /// Reaction emoji (default ❤️); a weighted list like "❤️:5,🔥:2,👍:1" picks one per message
         #[arg(short, long, default_value = "❤️")]
         emoji: String,
 
@@ -325,13 +626,57 @@ enum Commands {
         #[arg(long, default_value_t = 600)]
         delay_ms: u64,
 
+        /// Remove reactions from the targeted messages instead of adding one
+        #[arg(long, default_value_t = false)]
+        remove: bool,
+
         /// Preview only, no reactions sent
         #[arg(long, default_value_t = false)]
         dry_run: bool,
+
+        /// Print the dry-run summary as JSON
+        #[arg(long, default_value_t = false)]
+        plan_json: bool,
+
+        /// Don't ask to confirm the resolved chat (for scripts)
+        #[arg(short = 'y', long, default_value_t = false)]
+        yes: bool,
     },
 
     /// Send predefined viral questions to multiple chats
-    SendViral,
+    SendViral {
+        /// Max messages per day for the account (default: DAILY_SEND_CAP or 50)
+        #[arg(long)]
+        daily_cap: Option<u32>,
+
+        /// YAML/JSON file with questions (question, chats, optional weight) instead of the built-in set
+        #[arg(long)]
+        questions: Option<PathBuf>,
+
+        /// Template file sent instead of the predefined questions ({{chat_title}}, {{date}}, {{first_name}})
+        #[arg(long)]
+        template: Option<PathBuf>,
+
+        /// Fail on unknown template variables instead of rendering them empty
+        #[arg(long, default_value_t = false)]
+        strict: bool,
+
+        /// Preview only: print a summary of planned messages without changing anything
+        #[arg(long, default_value_t = false)]
+        dry_run: bool,
+
+        /// Print the dry-run summary as JSON
+        #[arg(long, default_value_t = false)]
+        plan_json: bool,
+
+        /// Quiet hours window, e.g. 22:00-08:00 (default: QUIET_HOURS)
+        #[arg(long)]
+        quiet_hours: Option<String>,
+
+        /// UTC offset of the quiet hours window, e.g. +03:00 (default: QUIET_HOURS_TZ or UTC)
+        #[arg(long)]
+        quiet_tz: Option<String>,
+    },
 
     /// N8N service monitor with auto-restart
     N8nMonitor,
@@ -364,6 +709,14 @@ enum Commands {
         #[arg(short, long, value_delimiter = ',')]
         exclude: Vec<String>,
 
+        /// Match keywords, required and exclude as case-insensitive regexes instead of substrings
+        #[arg(long, default_value_t = false)]
+        regex: bool,
+
+        /// Usernames to skip, comma-separated (e.g. admins, bots)
+        #[arg(long, value_delimiter = ',')]
+        exclude_users: Vec<String>,
+
         /// Minimum messages from user
         #[arg(long, default_value = "1")]
         min_messages: usize,
@@ -380,10 +733,128 @@ enum Commands {
         #[arg(long)]
         export_csv: Option<String>,
 
+        /// Prepend a UTF-8 BOM to CSV output (for Excel)
+        #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
+        csv_bom: bool,
+
+        /// CSV delimiter: , | ; | tab
+        #[arg(long, default_value = ",")]
+        csv_delimiter: String,
+
         /// Maximum results to display
         #[arg(long, default_value = "50")]
         top: usize,
     },
+
+    /// Detect days with unusually high or low message activity
+    Anomalies {
+        /// Chat name to analyze
+        chat: String,
+
+        /// Days of history to analyze
+        #[arg(short, long, default_value = "60")]
+        days: i64,
+
+        /// Rolling window size in days
+        #[arg(short, long, default_value = "7")]
+        window: usize,
+
+        /// Threshold in standard deviations
+        #[arg(short, long, default_value = "2.0")]
+        sigma: f64,
+
+        /// Output format: table | json
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
+
+    /// Find clusters of near-identical messages (copypasta, spam waves, bot networks)
+    Duplicates {
+        /// Chat name to scan
+        chat: String,
+
+        /// Number of recent messages to scan
+        #[arg(short, long, default_value = "1000")]
+        limit: usize,
+
+        /// Minimum cosine similarity in embedding mode (0.0-1.0)
+        #[arg(short, long, default_value = "0.92")]
+        threshold: f32,
+
+        /// Comparison mode: hash (normalized text) | embedding (OpenAI)
+        #[arg(long, default_value = "hash")]
+        mode: String,
+
+        /// Output format: table | json
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
+
+    /// Run a Cypher query against the Neo4j message graph (NEO4J_URI/USER/PASSWORD)
+    GraphQuery {
+        /// Cypher statement, read-only unless --allow-write
+        cypher: String,
+
+        /// Output format: table | json
+        #[arg(long, default_value = "table")]
+        format: String,
+
+        /// Allow CREATE/MERGE/DELETE/SET and other write clauses
+        #[arg(long, default_value_t = false)]
+        allow_write: bool,
+    },
+
+    /// Rank recent messages by engagement to find pin candidates
+    PinCandidates {
+        /// Chat name to scan
+        chat: String,
+
+        /// Days of history to scan
+        #[arg(short, long, default_value = "7")]
+        days: i64,
+
+        /// Number of candidates to show
+        #[arg(short, long, default_value = "5")]
+        top: usize,
+
+        /// Pin the top candidates (requires admin rights)
+        #[arg(long, default_value_t = false)]
+        pin: bool,
+
+        /// Preview pinning without changing the chat
+        #[arg(long, default_value_t = false)]
+        dry_run: bool,
+
+        /// Don't ask to confirm the resolved chat (for scripts)
+        #[arg(short = 'y', long, default_value_t = false)]
+        yes: bool,
+    },
+
+    /// Extract meeting/event mentions and export them as ICS and JSON
+    Events {
+        /// Chat name to scan
+        chat: String,
+
+        /// Days of history to scan
+        #[arg(short, long, default_value = "7")]
+        days: i64,
+
+        /// Maximum messages to analyze
+        #[arg(short, long, default_value = "300")]
+        limit: usize,
+
+        /// OpenAI model to use
+        #[arg(short, long, default_value = "gpt-4o-mini")]
+        model: String,
+
+        /// Timezone relative dates and times are read in: UTC, +03:00, UTC+3
+        #[arg(long, default_value = "UTC")]
+        tz: String,
+
+        /// Output path without extension (writes <path>.ics and <path>.json)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
 }
 
 impl Commands {
@@ -399,19 +870,39 @@ impl Commands {
             Commands::Analyze { .. } => "analyze",
             Commands::AutoAnswer { .. } => "autoanswer",
             Commands::InitSession => "init_session",
+            Commands::Validate => "validate",
             Commands::Linear { .. } => "linear",
+            Commands::LinearUpdate { .. } => "linear_update",
             Commands::Digest { .. } => "digest",
             Commands::Moderate { .. } => "moderate",
             Commands::ProfanityStats { .. } => "profanity_stats",
             Commands::Crm { .. } => "crm",
             Commands::Like { .. } => "like",
-            Commands::SendViral => "send_viral",
+            Commands::SendViral { .. } => "send_viral",
             Commands::N8nMonitor => "n8n_monitor",
             Commands::N8nBackup { .. } => "n8n_backup",
             Commands::React { .. } => "react",
             Commands::Hunt { .. } => "hunt",
+            Commands::Anomalies { .. } => "anomalies",
+            Commands::Duplicates { .. } => "duplicates",
+            Commands::GraphQuery { .. } => "graph_query",
+            Commands::PinCandidates { .. } => "pin_candidates",
+            Commands::Events { .. } => "events",
         }
     }
+
+    /// Commands that return a serializable result for `--json`
+    fn supports_json(&self) -> bool {
+        matches!(
+            self,
+            Commands::Digest { .. } | Commands::Analyze { .. } | Commands::ProfanityStats { .. }
+        )
+    }
+
+    /// Commands that can run from `--input` with `--no-network`
+    fn supports_offline(&self) -> bool {
+        matches!(self, Commands::Analyze { .. })
+    }
 }
 
 #[tokio::main]
@@ -419,15 +910,27 @@ async fn main() -> anyhow::Result<()> {
     // Load .env for local development
     let _ = dotenvy::dotenv();
 
-    // Initialize logging
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            EnvFilter::from_default_env().add_directive("telegram_reader=info".parse()?),
-        )
-        .init();
-
     let cli = Cli::parse();
 
+    if let Some(name) = cli.session.as_deref() {
+        session::validate_session_name(name)?;
+        // Session helpers read TELEGRAM_SESSION, so the flag reaches every command
+        std::env::set_var(session::SESSION_ENV, name);
+    }
+    if let Some(secs) = cli.lock_timeout {
+        std::env::set_var(session::LOCK_TIMEOUT_ENV, secs.to_string());
+    }
+
+    // Initialize logging (to stderr with --json so stdout stays parseable)
+    let logging = tracing_subscriber::fmt().with_env_filter(
+        EnvFilter::from_default_env().add_directive("telegram_reader=info".parse()?),
+    );
+    if cli.json {
+        logging.with_writer(std::io::stderr).init();
+    } else {
+        logging.init();
+    }
+
     if let Some(addr) = cli.metrics_addr.as_deref() {
         match addr.parse::<SocketAddr>() {
             Ok(socket) => metrics::spawn_metrics_server(socket),
@@ -436,60 +939,134 @@ async fn main() -> anyhow::Result<()> {
     }
 
     let command_name = cli.command.name();
-    metrics::record_command_start(command_name);
-    let start = Instant::now();
-
-    let result = execute_command(cli.command).await;
-
-    metrics::record_command_result(command_name, start.elapsed(), result.is_ok());
+    let command_metrics = metrics::start_command(command_name);
+
+    let result = if cli.json && !cli.command.supports_json() {
+        Err(anyhow::anyhow!("{} does not support --json", command_name))
+    } else if cli.no_network && !cli.command.supports_offline() {
+        Err(anyhow::anyhow!(
+            "{} needs Telegram access and is not available with --no-network",
+            command_name
+        ))
+    } else if cli.no_network && cli.input.is_none() {
+        Err(anyhow::anyhow!(
+            "--no-network requires --input <messages.jsonl>"
+        ))
+    } else {
+        let offline_input = cli.input.filter(|_| cli.no_network);
+        execute_command(cli.command, cli.json, offline_input).await
+    };
+
+    command_metrics.finish(result.is_ok());
+
+    if !cli.json {
+        return result.map(|_| ());
+    }
 
-    result
+    let envelope = match result {
+        Ok(data) => JsonEnvelope::success(command_name, data.unwrap_or_default()),
+        Err(err) => JsonEnvelope::failure(command_name, format!("{:#}", err)),
+    };
+    println!("{}", envelope.to_json()?);
+    if !envelope.ok {
+        std::process::exit(1);
+    }
+    Ok(())
 }
 
-async fn execute_command(command: Commands) -> anyhow::Result<()> {
+/// Run a command; with `json` supported commands return their result instead of printing it.
+///
+/// `offline_input` is set with `--no-network`; only offline-capable commands get here then.
+async fn execute_command(
+    command: Commands,
+    json: bool,
+    offline_input: Option<PathBuf>,
+) -> anyhow::Result<Option<serde_json::Value>> {
     match command {
         Commands::Read {
             chat,
             limit,
             delete_unengaged,
             watch,
+            poll,
+            poll_base_ms,
+            poll_max_ms,
+            watch_out,
+            watch_out_max_mb,
         } => {
+            let watch = (watch || watch_out.is_some()).then(|| commands::read::WatchOptions {
+                force_poll: poll,
+                polling: PollingConfig::from_millis(poll_base_ms, poll_max_ms),
+                out: watch_out,
+                out_max_bytes: watch_out_max_mb.saturating_mul(1024 * 1024),
+            });
             commands::read::run(&chat, limit, delete_unengaged, watch).await?;
         }
         Commands::Tg { chat, limit } => {
             commands::tg::run(&chat, limit).await?;
         }
-        Commands::ListChats { limit, filter } => {
-            let chat_filter = match filter.to_lowercase().as_str() {
-                "users" | "user" => commands::list_chats::ChatFilter::Users,
-                "groups" | "group" => commands::list_chats::ChatFilter::Groups,
-                "channels" | "channel" => commands::list_chats::ChatFilter::Channels,
-                _ => commands::list_chats::ChatFilter::All,
-            };
-            commands::list_chats::run_with_filter(limit, chat_filter).await?;
+        Commands::ListChats {
+            limit,
+            filter,
+            output,
+        } => {
+            let chat_filter = commands::list_chats::ChatFilter::parse(&filter);
+            let output = commands::list_chats::ChatsOutput::parse(&output)?;
+            commands::list_chats::run_with_filter(limit, chat_filter, output).await?;
         }
-        Commands::ActiveChats { limit } => {
-            commands::active_chats::run(limit).await?;
+        Commands::ActiveChats { limit, output } => {
+            let output = commands::list_chats::ChatsOutput::parse(&output)?;
+            commands::active_chats::run(limit, output).await?;
         }
         Commands::Dialogs {
             limit,
             format,
+            sort,
             output,
         } => {
-            commands::dialogs_run(limit, &format, output).await?;
+            commands::dialogs_run(limit, &format, &sort, output).await?;
         }
         Commands::Export {
             username,
             output,
             limit,
+            since,
+            until,
+            format,
+            reactors,
+            resume,
         } => {
-            commands::export::run(&username, output.as_deref(), limit).await?;
+            let window = commands::export::DateWindow::parse(since.as_deref(), until.as_deref())?;
+            commands::export::run_with_reactors(
+                &username,
+                output.as_deref(),
+                limit,
+                window,
+                format,
+                reactors.as_deref(),
+                resume,
+            )
+            .await?;
         }
-        Commands::DeleteZoom { username, limit } => {
-            commands::delete_zoom::run(&username, limit).await?;
+        Commands::DeleteZoom {
+            username,
+            limit,
+            dry_run,
+            plan_json,
+            yes,
+        } => {
+            let dry_run = PlanFormat::from_flags(dry_run, plan_json);
+            commands::delete_zoom::run(&username, limit, dry_run, yes).await?;
         }
-        Commands::AutoAnswer { model } => {
-            commands::autoanswer::run(&model).await?;
+        Commands::AutoAnswer {
+            model,
+            quiet_hours,
+            quiet_tz,
+        } => {
+            let quiet_hours = quiet_hours
+                .map(|window| QuietHours::from_cli(&window, quiet_tz.as_deref()))
+                .transpose()?;
+            commands::autoanswer::run(&model, quiet_hours).await?;
         }
         Commands::Analyze {
             chat,
@@ -499,6 +1076,7 @@ async fn execute_command(command: Commands) -> anyhow::Result<()> {
             days,
             output_format,
             output_dir,
+            append_to,
             prompt,
             quiet,
             include_media,
@@ -506,7 +1084,21 @@ async fn execute_command(command: Commands) -> anyhow::Result<()> {
             min_length,
             temperature,
             max_tokens,
+            important,
+            strict,
+            since_last,
+            engagement_weights,
+            local_engagement,
+            max_topics,
+            max_discussions,
+            max_participants,
+            language,
+            include_vision,
         } => {
+            let engagement_weights = match engagement_weights {
+                Some(spec) => EngagementWeights::parse(&spec)?,
+                None => Default::default(),
+            };
             let cfg = commands::chat_analyzer::AnalyzerConfig {
                 message_limit: limit,
                 days_back: days,
@@ -520,10 +1112,36 @@ async fn execute_command(command: Commands) -> anyhow::Result<()> {
                 output_format: commands::chat_analyzer::OutputFormat::parse(&output_format),
                 output_dir,
                 prompt_path: prompt,
-                verbose: !quiet,
+                verbose: !quiet && !json,
+                important_senders: important,
+                strict,
+                since_last,
+                engagement_weights,
+                local_engagement,
+                section_limits: commands::chat_analyzer::SectionLimits {
+                    topics: max_topics,
+                    discussions: max_discussions,
+                    participants: max_participants,
+                },
+                append_to,
+                language,
+                include_vision,
             };
 
-            let result = commands::chat_analyzer::run(&chat, cfg).await?;
+            let result = match offline_input {
+                Some(input) => {
+                    let cfg = commands::chat_analyzer::AnalyzerConfig {
+                        llm_provider: commands::chat_analyzer::LlmProvider::Stub,
+                        since_last: false,
+                        ..cfg
+                    };
+                    commands::chat_analyzer::run_offline(&chat, &input, cfg).await?
+                }
+                None => commands::chat_analyzer::run(&chat, cfg).await?,
+            };
+            if json {
+                return Ok(Some(serde_json::to_value(&result)?));
+            }
             println!(
                 "Chat: {}\nCategory: {}\nSentiment: {}\nMessages analyzed: {}",
                 result.chat_name,
@@ -535,6 +1153,9 @@ async fn execute_command(command: Commands) -> anyhow::Result<()> {
         Commands::InitSession => {
             commands::init_session::run().await?;
         }
+        Commands::Validate => {
+            commands::validate::run()?;
+        }
         Commands::Linear {
             api_key,
             team,
@@ -544,6 +1165,8 @@ async fn execute_command(command: Commands) -> anyhow::Result<()> {
             priority,
             assignee,
             labels,
+            comment,
+            attach_files,
         } => {
             commands::linear::run(commands::linear::LinearArgs {
                 api_key,
@@ -554,6 +1177,32 @@ async fn execute_command(command: Commands) -> anyhow::Result<()> {
                 priority,
                 assignee,
                 labels,
+                comment,
+                attach_files,
+            })
+            .await?;
+        }
+        Commands::LinearUpdate {
+            issue,
+            api_key,
+            title,
+            description,
+            state,
+            priority,
+            assignee,
+            close,
+        } => {
+            commands::linear::update(commands::linear::LinearUpdateArgs {
+                api_key,
+                issue,
+                changes: IssueChanges {
+                    title,
+                    description,
+                    state,
+                    priority,
+                    assignee_id: assignee,
+                },
+                close,
             })
             .await?;
         }
@@ -562,37 +1211,121 @@ async fn execute_command(command: Commands) -> anyhow::Result<()> {
             hours,
             limit,
             model,
+            structured,
+            important,
+            since_last,
+            post_to,
+            quiet,
+            language,
         } => {
             let config = commands::digest::DigestConfig {
                 hours,
                 max_messages: limit,
                 model,
+                important_senders: important,
+                since_last,
+                post_to,
+                language,
                 ..Default::default()
             };
-            let digest = commands::digest::run(&chat, config).await?;
-            println!("{}", digest);
+            if (json || structured) && chat.len() > 1 {
+                return Err(anyhow::anyhow!(
+                    "--structured/--json digests take a single chat"
+                ));
+            }
+            if json {
+                let digest = commands::digest::run_structured(&chat[0], config).await?;
+                return Ok(Some(serde_json::to_value(&digest)?));
+            } else if structured {
+                let digest = commands::digest::run_structured(&chat[0], config).await?;
+                if !quiet {
+                    println!("{}", serde_json::to_string_pretty(&digest)?);
+                }
+            } else {
+                let digest = commands::digest::run(&chat, config).await?;
+                if !quiet {
+                    println!("{}", digest);
+                }
+            }
         }
-        Commands::Moderate { chat, delete, warn } => {
+        Commands::Moderate {
+            chat,
+            delete,
+            warn,
+            wordlist,
+            exempt_user_ids,
+            exempt_admins,
+            poll_base_ms,
+            poll_max_ms,
+            dry_run,
+            plan_json,
+            yes,
+        } => {
+            let extra_words = match wordlist {
+                Some(path) => commands::moderate::load_wordlist(&path)?,
+                None => Vec::new(),
+            };
             let config = commands::moderate::ModerateConfig {
                 delete_profanity: delete,
                 send_warning: warn,
+                extra_words,
+                polling: PollingConfig::from_millis(poll_base_ms, poll_max_ms),
+                dry_run: PlanFormat::from_flags(dry_run, plan_json),
+                yes,
+                exempt_user_ids,
+                exempt_admins,
                 ..Default::default()
             };
             commands::moderate::run(&chat, config).await?;
         }
-        Commands::ProfanityStats { chat, limit } => {
-            let stats = commands::moderate::analyze(&chat, limit).await?;
+        Commands::ProfanityStats {
+            chat,
+            limit,
+            sample,
+            seed,
+            output,
+            csv_bom,
+            csv_delimiter,
+        } => {
+            let csv_options = CsvOptions::new(csv_bom, &csv_delimiter)?;
+            let mode = match sample {
+                Some(size) => commands::moderate::StatsMode::Sample {
+                    size,
+                    seed: seed.unwrap_or_else(rand::random),
+                },
+                None => commands::moderate::StatsMode::Recent { limit },
+            };
+            let stats = commands::moderate::analyze(&chat, mode).await?;
+            if let Some(path) = &output {
+                stats.export_per_sender(path, &csv_options)?;
+                eprintln!("📁 Per-sender breakdown saved to {}", path.display());
+            }
+            if json {
+                return Ok(Some(serde_json::to_value(&stats)?));
+            }
             println!("\n📊 Profanity Statistics for '{}'", chat);
             println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+            if let Some(seed) = stats.seed {
+                println!(
+                    "Sampled {} of {} messages (seed {})",
+                    stats.total_messages, stats.population, seed
+                );
+            }
             println!("Total messages analyzed: {}", stats.total_messages);
             println!(
                 "Messages with profanity: {} ({:.1}%)",
                 stats.messages_with_profanity,
                 stats.profanity_rate()
             );
-            println!("\n🏴‍☠️ Top offenders:");
-            for (user, count) in stats.top_offenders(10) {
-                println!("  {} - {} violations", user, count);
+            if stats.messages_with_profanity == 0 {
+                println!("\n{}", no_results_message(&stats.scan));
+            } else {
+                println!("\n🏴‍☠️ Top offenders:");
+                for (user, count) in stats.top_offenders(10) {
+                    println!("  {} - {} violations", user, count);
+                }
+                println!("\n📅 Violations by day (UTC):");
+                print!("{}", stats.daily_table());
             }
         }
         Commands::Crm {
@@ -600,27 +1333,63 @@ async fn execute_command(command: Commands) -> anyhow::Result<()> {
             limit,
             model,
             export_csv,
+            merge_into,
+            export_deals_csv,
+            csv_bom,
+            csv_delimiter,
         } => {
+            let csv_options = CsvOptions::new(csv_bom, &csv_delimiter)?;
             let config = commands::crm::CrmConfig {
                 model,
                 max_messages: limit,
             };
             let extraction = commands::crm::parse_chat(&chat, config).await?;
-            commands::crm::print_extraction(&extraction);
+            if extraction.is_empty() {
+                println!("\n{}", no_results_message(&extraction.scan));
+            } else {
+                commands::crm::print_extraction(&extraction);
+            }
 
             if let Some(csv_path) = export_csv {
-                let csv = commands::crm::export_contacts_csv(&extraction);
+                let csv = commands::crm::export_contacts_csv(&extraction, None, &csv_options)?;
                 std::fs::write(&csv_path, csv)?;
                 println!("\n📁 Contacts exported to {}", csv_path);
             }
+
+            if let Some(path) = merge_into {
+                let csv =
+                    commands::crm::export_contacts_csv(&extraction, Some(&path), &csv_options)?;
+                std::fs::write(&path, csv)?;
+                println!("\n📁 Contacts merged into {}", path.display());
+            }
+
+            if let Some(csv_path) = export_deals_csv {
+                let csv = commands::crm::export_deals_csv(&extraction, &csv_options)?;
+                std::fs::write(&csv_path, csv)?;
+                println!("\n📁 Deals exported to {}", csv_path);
+            }
         }
         Commands::Like {
             chat,
             user,
             emoji,
             limit,
+            dry_run,
+            plan_json,
+            yes,
+            min_reactions,
+            max_per_run,
         } => {
-            commands::like::run(&chat, &user, Some(&emoji), limit).await?;
+            let dry_run = PlanFormat::from_flags(dry_run, plan_json);
+            let config = commands::like::LikeConfig {
+                emoji,
+                limit,
+                yes,
+                min_reactions,
+                max_per_run,
+                ..Default::default()
+            };
+            commands::like::run(&chat, &user, config, dry_run).await?;
         }
         Commands::React {
             chat,
@@ -630,7 +1399,10 @@ async fn execute_command(command: Commands) -> anyhow::Result<()> {
             recent,
             user_id,
             delay_ms,
+            remove,
             dry_run,
+            plan_json,
+            yes,
         } => {
             commands::react::run(commands::react::ReactArgs {
                 chat,
@@ -640,12 +1412,38 @@ async fn execute_command(command: Commands) -> anyhow::Result<()> {
                 recent,
                 user_id,
                 delay_ms,
-                dry_run,
+                remove,
+                dry_run: PlanFormat::from_flags(dry_run, plan_json),
+                yes,
             })
             .await?;
         }
-        Commands::SendViral => {
-            commands::send_viral::run().await?;
+        Commands::SendViral {
+            daily_cap,
+            questions,
+            template,
+            strict,
+            dry_run,
+            plan_json,
+            quiet_hours,
+            quiet_tz,
+        } => {
+            let template = template
+                .map(|path| commands::send_message::MessageText::from_template_file(&path, strict))
+                .transpose()?;
+            let questions = questions
+                .map(|path| commands::send_viral::load_questions(&path))
+                .transpose()?;
+            let options = commands::send_viral::ViralOptions {
+                daily_cap,
+                questions,
+                template,
+                dry_run: PlanFormat::from_flags(dry_run, plan_json),
+                quiet_hours: quiet_hours
+                    .map(|window| QuietHours::from_cli(&window, quiet_tz.as_deref()))
+                    .transpose()?,
+            };
+            commands::send_viral::run(options).await?;
         }
         Commands::N8nMonitor => {
             commands::n8n::run_monitor_cli().await?;
@@ -658,39 +1456,126 @@ async fn execute_command(command: Commands) -> anyhow::Result<()> {
             keywords,
             required,
             exclude,
+            regex,
+            exclude_users,
             min_messages,
             days,
             limit,
             export_csv,
+            csv_bom,
+            csv_delimiter,
             top,
         } => {
+            let csv_options = CsvOptions::new(csv_bom, &csv_delimiter)?;
             let criteria = commands::hunt::HuntCriteria {
                 keywords,
                 required_keywords: required,
                 exclude_keywords: exclude,
+                exclude_users,
                 min_messages,
                 days_back: days,
+                regex,
                 ..Default::default()
             };
 
             let chat_refs: Vec<&str> = chats.iter().map(|s| s.as_str()).collect();
-            let results = if chat_refs.len() == 1 {
+            let report = if chat_refs.len() == 1 {
                 commands::hunt::hunt_users(chat_refs[0], criteria, limit).await?
             } else {
                 commands::hunt::hunt_multiple_chats(&chat_refs, criteria, limit).await?
             };
 
-            commands::hunt::print_results(&results, top);
+            if report.results.is_empty() {
+                println!("\n{}", no_results_message(&report.scan));
+            } else {
+                commands::hunt::print_results(&report.results, top);
+            }
 
             if let Some(csv_path) = export_csv {
-                let csv = commands::hunt::export_csv(&results);
+                let csv = commands::hunt::export_csv(&report.results, &csv_options)?;
                 std::fs::write(&csv_path, csv)?;
                 println!("\n📁 Results exported to {}", csv_path);
             }
         }
+        Commands::Anomalies {
+            chat,
+            days,
+            window,
+            sigma,
+            format,
+        } => {
+            let config = commands::anomalies::AnomalyConfig {
+                days,
+                window,
+                sigma,
+            };
+            commands::anomalies::run(&chat, config, &format).await?;
+        }
+        Commands::Duplicates {
+            chat,
+            limit,
+            threshold,
+            mode,
+            format,
+        } => {
+            let config = commands::duplicates::DuplicatesConfig {
+                limit,
+                threshold,
+                mode: commands::duplicates::DuplicateMode::parse(&mode)?,
+            };
+            commands::duplicates::run(&chat, config, &format).await?;
+        }
+        Commands::GraphQuery {
+            cypher,
+            format,
+            allow_write,
+        } => {
+            commands::graph_query::run(&cypher, &format, allow_write).await?;
+        }
+        Commands::PinCandidates {
+            chat,
+            days,
+            top,
+            pin,
+            dry_run,
+            yes,
+        } => {
+            let config = commands::pin_candidates::PinCandidatesConfig {
+                days,
+                top,
+                pin,
+                dry_run,
+                yes,
+                ..Default::default()
+            };
+            commands::pin_candidates::run(&chat, config).await?;
+        }
+        Commands::Events {
+            chat,
+            days,
+            limit,
+            model,
+            tz,
+            output,
+        } => {
+            let config = commands::events::EventsConfig {
+                days,
+                limit,
+                model,
+                offset: parse_offset(&tz)?,
+            };
+            let report = commands::events::run(&chat, config).await?;
+            if report.events.is_empty() {
+                println!("\n{}", no_results_message(&report.scan));
+            } else {
+                commands::events::print_events(&report.events);
+                let output = output.unwrap_or_else(|| PathBuf::from(format!("{}_events", chat)));
+                commands::events::export(&chat, &report.events, &output)?;
+            }
+        }
     }
 
-    Ok(())
+    Ok(None)
 }
 
 // Commands are in src/commands/ directory
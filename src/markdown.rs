@@ -0,0 +1,381 @@
+//! Telegram formatting entities → Markdown
+//!
+//! `Message::text()` is plain text; bold, links and code live in separate
+//! entity ranges (offsets in UTF-16 code units). [`render_markdown`] merges
+//! them back so exported archives keep the original formatting. Overlapping
+//! ranges are closed and reopened at the boundaries so the markup always
+//! nests, and nothing is rendered inside code.
+
+use grammers_client::types::Message;
+use grammers_tl_types as tl;
+
+/// Formatting kinds that have a Markdown equivalent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntityKind {
+    Bold,
+    Italic,
+    Strike,
+    Code,
+    Pre {
+        language: String,
+    },
+    TextUrl {
+        url: String,
+    },
+    /// `@username` in the text
+    Mention,
+    /// Link to a user without a username
+    MentionName {
+        user_id: i64,
+    },
+}
+
+/// A formatting range as sent by Telegram (UTF-16 offset and length)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntitySpan {
+    pub offset: usize,
+    pub length: usize,
+    pub kind: EntityKind,
+}
+
+impl EntitySpan {
+    pub fn new(offset: usize, length: usize, kind: EntityKind) -> Self {
+        Self {
+            offset,
+            length,
+            kind,
+        }
+    }
+}
+
+/// Entities of a message we can render; underline, spoilers and the like are dropped
+pub fn entities_from_tl(entities: &[tl::enums::MessageEntity]) -> Vec<EntitySpan> {
+    use tl::enums::MessageEntity as E;
+
+    let span = |offset: i32, length: i32, kind| {
+        EntitySpan::new(offset.max(0) as usize, length.max(0) as usize, kind)
+    };
+    entities
+        .iter()
+        .filter_map(|entity| match entity {
+            E::Bold(e) => Some(span(e.offset, e.length, EntityKind::Bold)),
+            E::Italic(e) => Some(span(e.offset, e.length, EntityKind::Italic)),
+            E::Strike(e) => Some(span(e.offset, e.length, EntityKind::Strike)),
+            E::Code(e) => Some(span(e.offset, e.length, EntityKind::Code)),
+            E::Pre(e) => Some(span(
+                e.offset,
+                e.length,
+                EntityKind::Pre {
+                    language: e.language.clone(),
+                },
+            )),
+            E::TextUrl(e) => Some(span(
+                e.offset,
+                e.length,
+                EntityKind::TextUrl { url: e.url.clone() },
+            )),
+            E::Mention(e) => Some(span(e.offset, e.length, EntityKind::Mention)),
+            E::MentionName(e) => Some(span(
+                e.offset,
+                e.length,
+                EntityKind::MentionName { user_id: e.user_id },
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Message text with its formatting entities rendered as Markdown
+pub fn message_markdown(message: &Message) -> String {
+    match message.fmt_entities() {
+        Some(entities) => render_markdown(message.text(), &entities_from_tl(entities)),
+        None => message.text().to_string(),
+    }
+}
+
+/// Range resolved to byte offsets, with its Markdown markers
+#[derive(Debug)]
+struct Span {
+    start: usize,
+    end: usize,
+    open: String,
+    close: String,
+    /// Code/pre: nothing nested inside is rendered
+    verbatim: bool,
+    /// Pre blocks keep their surrounding whitespace
+    block: bool,
+}
+
+/// Render `text` with `entities` as Markdown.
+///
+/// Text outside entities is copied as is. Whitespace at the edges of a range is
+/// moved outside the markers (`**bold **` isn't valid Markdown).
+pub fn render_markdown(text: &str, entities: &[EntitySpan]) -> String {
+    let spans = resolve_spans(text, entities);
+    if spans.is_empty() {
+        return text.to_string();
+    }
+
+    let mut boundaries: Vec<usize> = spans
+        .iter()
+        .flat_map(|s| [s.start, s.end])
+        .chain([0, text.len()])
+        .collect();
+    boundaries.sort_unstable();
+    boundaries.dedup();
+
+    let mut out = String::with_capacity(text.len() + spans.len() * 4);
+    let mut open: Vec<usize> = Vec::new();
+
+    for window in boundaries.windows(2) {
+        let (from, to) = (window[0], window[1]);
+        let active = active_spans(&spans, from, to);
+
+        let common = open.iter().zip(&active).take_while(|(a, b)| a == b).count();
+        for &index in open[common..].iter().rev() {
+            close_span(&mut out, &spans[index]);
+        }
+        open.truncate(common);
+
+        let mut segment = &text[from..to];
+        let opening = &active[common..];
+        if !opening.is_empty() && !opening.iter().any(|&i| spans[i].block) {
+            let trimmed = segment.trim_start();
+            out.push_str(&segment[..segment.len() - trimmed.len()]);
+            if trimmed.is_empty() {
+                // Open them with the next non-blank segment
+                continue;
+            }
+            segment = trimmed;
+        }
+        for &index in opening {
+            out.push_str(&spans[index].open);
+            open.push(index);
+        }
+        out.push_str(segment);
+    }
+
+    for &index in open.iter().rev() {
+        close_span(&mut out, &spans[index]);
+    }
+    out
+}
+
+/// Spans covering `from..to`, outermost first, cut off below the first code span
+fn active_spans(spans: &[Span], from: usize, to: usize) -> Vec<usize> {
+    let mut active: Vec<usize> = (0..spans.len())
+        .filter(|&i| spans[i].start <= from && spans[i].end >= to)
+        .collect();
+    active.sort_by(|&a, &b| {
+        spans[a]
+            .start
+            .cmp(&spans[b].start)
+            .then(spans[b].end.cmp(&spans[a].end))
+            .then(a.cmp(&b))
+    });
+    if let Some(code) = active.iter().position(|&i| spans[i].verbatim) {
+        active.truncate(code + 1);
+    }
+    active
+}
+
+fn close_span(out: &mut String, span: &Span) {
+    if span.block {
+        out.push_str(&span.close);
+        return;
+    }
+    let trailing = out.len() - out.trim_end().len();
+    let whitespace = out.split_off(out.len() - trailing);
+    out.push_str(&span.close);
+    out.push_str(&whitespace);
+}
+
+/// Convert UTF-16 ranges to byte ranges, dropping empty or out-of-range ones
+fn resolve_spans(text: &str, entities: &[EntitySpan]) -> Vec<Span> {
+    // utf16_to_byte[i] is the byte offset of UTF-16 unit i (and one past the end)
+    let mut utf16_to_byte = Vec::with_capacity(text.len() + 1);
+    for (byte, ch) in text.char_indices() {
+        for _ in 0..ch.len_utf16() {
+            utf16_to_byte.push(byte);
+        }
+    }
+    utf16_to_byte.push(text.len());
+    let units = utf16_to_byte.len() - 1;
+
+    entities
+        .iter()
+        .filter(|e| e.length > 0 && e.offset < units)
+        .filter_map(|e| {
+            let start = utf16_to_byte[e.offset];
+            let end = utf16_to_byte[(e.offset + e.length).min(units)];
+            if start >= end {
+                return None;
+            }
+            let covered = &text[start..end];
+            let (open, close) = markers(&e.kind, covered);
+            if covered.trim().is_empty() && !matches!(e.kind, EntityKind::Pre { .. }) {
+                return None;
+            }
+            Some(Span {
+                start,
+                end,
+                open,
+                close,
+                verbatim: matches!(e.kind, EntityKind::Code | EntityKind::Pre { .. }),
+                block: matches!(e.kind, EntityKind::Pre { .. }),
+            })
+        })
+        .collect()
+}
+
+fn markers(kind: &EntityKind, covered: &str) -> (String, String) {
+    let wrap = |marker: &str| (marker.to_string(), marker.to_string());
+    let link = |url: String| ("[".to_string(), format!("]({})", url));
+    match kind {
+        EntityKind::Bold => wrap("**"),
+        EntityKind::Italic => wrap("_"),
+        EntityKind::Strike => wrap("~~"),
+        EntityKind::Code => wrap("`"),
+        EntityKind::Pre { language } => (format!("```{}\n", language), "\n```".to_string()),
+        EntityKind::TextUrl { url } => link(url.clone()),
+        EntityKind::Mention => link(format!(
+            "https://t.me/{}",
+            covered.trim().trim_start_matches('@')
+        )),
+        EntityKind::MentionName { user_id } => link(format!("tg://user?id={}", user_id)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(offset: usize, length: usize, kind: EntityKind) -> EntitySpan {
+        EntitySpan::new(offset, length, kind)
+    }
+
+    fn url(url: &str) -> EntityKind {
+        EntityKind::TextUrl {
+            url: url.to_string(),
+        }
+    }
+
+    #[test]
+    fn plain_text_is_unchanged() {
+        assert_eq!(render_markdown("2*3 = 6_", &[]), "2*3 = 6_");
+    }
+
+    #[test]
+    fn renders_bold_and_links() {
+        let text = "Read the docs now";
+        let entities = [
+            span(0, 4, EntityKind::Bold),
+            span(5, 8, url("https://docs.rs")),
+        ];
+        assert_eq!(
+            render_markdown(text, &entities),
+            "**Read** [the docs](https://docs.rs) now"
+        );
+    }
+
+    #[test]
+    fn renders_mentions_as_links() {
+        let entities = [
+            span(3, 6, EntityKind::Mention),
+            span(14, 3, EntityKind::MentionName { user_id: 42 }),
+        ];
+        assert_eq!(
+            render_markdown("hi @alice and Bob", &entities),
+            "hi [@alice](https://t.me/alice) and [Bob](tg://user?id=42)"
+        );
+    }
+
+    #[test]
+    fn nested_entities_nest() {
+        // Bold over everything, italic inside, link inside the italic part
+        let text = "bold italic link";
+        let entities = [
+            span(0, 16, EntityKind::Bold),
+            span(5, 11, EntityKind::Italic),
+            span(12, 4, url("https://x.io")),
+        ];
+        assert_eq!(
+            render_markdown(text, &entities),
+            "**bold _italic [link](https://x.io)_**"
+        );
+    }
+
+    #[test]
+    fn overlapping_entities_are_split() {
+        // bold "abc def", italic "def ghi"
+        let entities = [span(0, 7, EntityKind::Bold), span(4, 7, EntityKind::Italic)];
+        assert_eq!(
+            render_markdown("abc def ghi", &entities),
+            "**abc _def_** _ghi_"
+        );
+    }
+
+    #[test]
+    fn whitespace_moves_outside_markers() {
+        let entities = [span(0, 6, EntityKind::Bold)];
+        assert_eq!(render_markdown("Hello world", &entities), "**Hello** world");
+        // A range of only whitespace renders nothing
+        assert_eq!(
+            render_markdown("a   b", &[span(1, 3, EntityKind::Italic)]),
+            "a   b"
+        );
+        // Overlap on a single space doesn't leave empty markers behind
+        let entities = [span(0, 6, EntityKind::Bold), span(5, 6, EntityKind::Italic)];
+        assert_eq!(
+            render_markdown("Hello world", &entities),
+            "**Hello** _world_"
+        );
+    }
+
+    #[test]
+    fn nothing_is_rendered_inside_code() {
+        let text = "run cargo test now";
+        let entities = [
+            span(0, 14, EntityKind::Bold),
+            span(4, 10, EntityKind::Code),
+            span(10, 4, EntityKind::Italic),
+        ];
+        assert_eq!(render_markdown(text, &entities), "**run `cargo test`** now");
+    }
+
+    #[test]
+    fn renders_pre_blocks_with_language() {
+        let text = "See:\nfn main() {}";
+        let entities = [span(
+            5,
+            12,
+            EntityKind::Pre {
+                language: "rust".to_string(),
+            },
+        )];
+        assert_eq!(
+            render_markdown(text, &entities),
+            "See:\n```rust\nfn main() {}\n```"
+        );
+    }
+
+    #[test]
+    fn offsets_are_utf16() {
+        // 😀 takes two UTF-16 units, "Привет" one unit per letter but two bytes
+        let text = "😀 Привет мир";
+        let entities = [
+            span(3, 6, EntityKind::Bold),
+            span(10, 3, EntityKind::Italic),
+        ];
+        assert_eq!(render_markdown(text, &entities), "😀 **Привет** _мир_");
+    }
+
+    #[test]
+    fn out_of_range_entities_are_clamped_or_dropped() {
+        let entities = [
+            span(2, 100, EntityKind::Bold),
+            span(50, 2, EntityKind::Italic),
+        ];
+        assert_eq!(render_markdown("a bc", &entities), "a **bc**");
+    }
+}
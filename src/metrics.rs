@@ -4,11 +4,14 @@
 //! - `telegram_reader_command_duration_seconds` (histogram)
 //! - `telegram_reader_command_total` (counter with status)
 //! - `telegram_reader_command_inflight` (gauge)
+//! - `telegram_reader_command_last_success_timestamp_seconds` (gauge, unix time)
+//! - `llm_tokens_total` (counter by provider and kind: prompt | completion)
+//! - `llm_request_duration_seconds` (histogram by provider)
 //! - process metrics via `process` collector
 
 use std::convert::Infallible;
 use std::net::SocketAddr;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use bytes::Bytes;
 use http_body_util::Full;
@@ -20,8 +23,9 @@ use hyper_util::rt::TokioIo;
 use once_cell::sync::Lazy;
 use prometheus::process_collector::ProcessCollector;
 use prometheus::{
-    default_registry, register_histogram_vec, register_int_counter_vec, register_int_gauge_vec,
-    Encoder, HistogramVec, IntCounterVec, IntGaugeVec, TextEncoder,
+    default_registry, register_gauge_vec, register_histogram_vec, register_int_counter_vec,
+    register_int_gauge_vec, Encoder, GaugeVec, HistogramVec, IntCounterVec, IntGaugeVec,
+    TextEncoder,
 };
 use tokio::net::TcpListener;
 use tracing::{error, info, warn};
@@ -32,15 +36,17 @@ static PROCESS_COLLECTOR: Lazy<()> = Lazy::new(|| {
     }
 });
 
+/// Histogram buckets in seconds, 100ms up to 5 minutes, for p50/p95 per label.
+const DURATION_BUCKETS: &[f64] = &[
+    0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0,
+];
+
 static COMMAND_DURATION: Lazy<HistogramVec> = Lazy::new(|| {
-    // Exponential buckets from 50ms up to ~3 minutes.
-    let buckets =
-        prometheus::exponential_buckets(0.05, 2.0, 14).expect("failed to create histogram buckets");
     register_histogram_vec!(
         "telegram_reader_command_duration_seconds",
         "CLI command duration in seconds",
         &["command"],
-        buckets
+        DURATION_BUCKETS.to_vec()
     )
     .expect("failed to register command duration histogram")
 });
@@ -63,12 +69,43 @@ static COMMAND_INFLIGHT: Lazy<IntGaugeVec> = Lazy::new(|| {
     .expect("failed to register inflight gauge")
 });
 
+static COMMAND_LAST_SUCCESS: Lazy<GaugeVec> = Lazy::new(|| {
+    register_gauge_vec!(
+        "telegram_reader_command_last_success_timestamp_seconds",
+        "Unix time of the last successful run per command",
+        &["command"]
+    )
+    .expect("failed to register last success gauge")
+});
+
+static LLM_TOKENS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "llm_tokens_total",
+        "LLM tokens used by provider and kind",
+        &["provider", "kind"]
+    )
+    .expect("failed to register LLM token counter")
+});
+
+static LLM_DURATION: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "llm_request_duration_seconds",
+        "LLM API call duration in seconds by provider",
+        &["provider"],
+        DURATION_BUCKETS.to_vec()
+    )
+    .expect("failed to register LLM duration histogram")
+});
+
 /// Ensure collectors are registered.
 fn init_collectors() {
     Lazy::force(&PROCESS_COLLECTOR);
     Lazy::force(&COMMAND_DURATION);
     Lazy::force(&COMMAND_TOTAL);
     Lazy::force(&COMMAND_INFLIGHT);
+    Lazy::force(&COMMAND_LAST_SUCCESS);
+    Lazy::force(&LLM_TOKENS);
+    Lazy::force(&LLM_DURATION);
 }
 
 /// Increment inflight gauge for a command.
@@ -87,6 +124,70 @@ pub fn record_command_result(command: &'static str, duration: Duration, success:
     COMMAND_TOTAL
         .with_label_values(&[command, if success { "ok" } else { "error" }])
         .inc();
+    if success {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        COMMAND_LAST_SUCCESS
+            .with_label_values(&[command])
+            .set(now.as_secs_f64());
+    }
+}
+
+/// Metrics for one command run.
+///
+/// Created by [`start_command`]; the in-flight gauge goes back down even when
+/// the command bails out early or panics, which then counts as an error.
+#[must_use = "dropping the guard ends the command run"]
+pub struct CommandGuard {
+    command: &'static str,
+    start: Instant,
+    finished: bool,
+}
+
+impl CommandGuard {
+    /// Record the outcome and duration of the run.
+    pub fn finish(mut self, success: bool) {
+        self.finished = true;
+        record_command_result(self.command, self.start.elapsed(), success);
+    }
+}
+
+impl Drop for CommandGuard {
+    fn drop(&mut self) {
+        if !self.finished {
+            record_command_result(self.command, self.start.elapsed(), false);
+        }
+    }
+}
+
+/// Mark a command as in flight until the returned guard is finished or dropped.
+pub fn start_command(command: &'static str) -> CommandGuard {
+    record_command_start(command);
+    CommandGuard {
+        command,
+        start: Instant::now(),
+        finished: false,
+    }
+}
+
+/// Count the prompt and completion tokens of one LLM call.
+pub fn record_token_usage(provider: &'static str, prompt: u32, completion: u32) {
+    init_collectors();
+    LLM_TOKENS
+        .with_label_values(&[provider, "prompt"])
+        .inc_by(u64::from(prompt));
+    LLM_TOKENS
+        .with_label_values(&[provider, "completion"])
+        .inc_by(u64::from(completion));
+}
+
+/// Observe how long one LLM API call took, error replies included.
+pub fn record_llm_duration(provider: &'static str, duration: Duration) {
+    init_collectors();
+    LLM_DURATION
+        .with_label_values(&[provider])
+        .observe(duration.as_secs_f64());
 }
 
 async fn metrics_response() -> Result<Response<Full<Bytes>>, Infallible> {
@@ -186,6 +287,24 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn records_llm_token_usage_by_provider_and_kind() {
+        let provider = "test_provider_tokens";
+
+        record_token_usage(provider, 120, 30);
+        record_token_usage(provider, 80, 20);
+
+        let tokens = |kind| LLM_TOKENS.with_label_values(&[provider, kind]).get();
+        assert_eq!(tokens("prompt"), 200);
+        assert_eq!(tokens("completion"), 50);
+
+        let response = metrics_response().await.expect("metrics response");
+        let body_bytes = response.into_body().collect().await.unwrap().to_bytes();
+        let text = String::from_utf8(body_bytes.to_vec()).unwrap();
+        assert!(text.contains("llm_tokens_total{"));
+        assert!(text.contains(provider));
+    }
+
     #[tokio::test]
     async fn metrics_response_contains_registered_metrics() {
         let cmd = "test_metrics_response";
@@ -241,6 +360,80 @@ mod tests {
         assert!(sum >= 0.5);
     }
 
+    #[test]
+    fn success_sets_last_success_timestamp() {
+        let cmd = "test_last_success";
+
+        start_command(cmd).finish(false);
+        assert_eq!(COMMAND_LAST_SUCCESS.with_label_values(&[cmd]).get(), 0.0);
+
+        let before = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs_f64();
+        start_command(cmd).finish(true);
+        assert!(COMMAND_LAST_SUCCESS.with_label_values(&[cmd]).get() >= before);
+        assert_eq!(COMMAND_INFLIGHT.with_label_values(&[cmd]).get(), 0);
+    }
+
+    #[test]
+    fn dropped_guard_decrements_inflight_and_counts_error() {
+        let cmd = "test_guard_dropped";
+
+        let guard = start_command(cmd);
+        assert_eq!(COMMAND_INFLIGHT.with_label_values(&[cmd]).get(), 1);
+        drop(guard);
+
+        assert_eq!(COMMAND_INFLIGHT.with_label_values(&[cmd]).get(), 0);
+        assert_eq!(COMMAND_TOTAL.with_label_values(&[cmd, "error"]).get(), 1);
+        assert_eq!(COMMAND_TOTAL.with_label_values(&[cmd, "ok"]).get(), 0);
+    }
+
+    #[test]
+    fn guard_releases_inflight_on_panic() {
+        let cmd = "test_guard_panic";
+
+        let result = std::panic::catch_unwind(|| {
+            let _guard = start_command(cmd);
+            panic!("command blew up");
+        });
+
+        assert!(result.is_err());
+        assert_eq!(COMMAND_INFLIGHT.with_label_values(&[cmd]).get(), 0);
+        assert_eq!(COMMAND_TOTAL.with_label_values(&[cmd, "error"]).get(), 1);
+    }
+
+    #[tokio::test]
+    async fn records_llm_duration_by_provider() {
+        let provider = "test_provider_latency";
+
+        record_llm_duration(provider, Duration::from_millis(700));
+        record_llm_duration(provider, Duration::from_secs(40));
+
+        let histogram = LLM_DURATION.with_label_values(&[provider]);
+        assert_eq!(histogram.get_sample_count(), 2);
+        assert!((histogram.get_sample_sum() - 40.7).abs() < 1e-9);
+
+        let response = metrics_response().await.expect("metrics response");
+        let body_bytes = response.into_body().collect().await.unwrap().to_bytes();
+        let text = String::from_utf8(body_bytes.to_vec()).unwrap();
+        assert!(text.contains(&format!(
+            "llm_request_duration_seconds_bucket{{provider=\"{}\",le=\"1\"}} 1",
+            provider
+        )));
+        assert!(text.contains(&format!(
+            "llm_request_duration_seconds_bucket{{provider=\"{}\",le=\"60\"}} 2",
+            provider
+        )));
+    }
+
+    #[test]
+    fn command_duration_uses_bounded_buckets() {
+        assert_eq!(DURATION_BUCKETS.first(), Some(&0.1));
+        assert_eq!(DURATION_BUCKETS.last(), Some(&300.0));
+        assert!(DURATION_BUCKETS.windows(2).all(|w| w[0] < w[1]));
+    }
+
     #[test]
     fn init_collectors_can_be_called_multiple_times() {
         init_collectors();
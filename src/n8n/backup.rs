@@ -1,10 +1,23 @@
 //! N8N Configuration Backup
 //!
 //! Автоматический бэкап конфигураций N8N
+//!
+//! Restoring recreates the archived workflows through the public API: existing
+//! ids are updated, missing ones are created, and workflows that were active
+//! are activated again.
+//!
+//! Every archive gets a `<archive>.sha256` sidecar (`sha256sum` format) so a
+//! truncated archive is caught by [`N8NBackup::verify_backup`] before a restore,
+//! and a `<archive>.info.json` sidecar with the complete [`BackupInfo`].
 
 use chrono::{DateTime, Utc};
-use reqwest::Client;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use reqwest::{Client, StatusCode};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
 use std::env;
 use std::path::{Path, PathBuf};
 use tokio::fs;
@@ -83,6 +96,115 @@ pub struct BackupInfo {
     pub n8n_url: String,
     pub workflows_count: usize,
     pub credentials_count: usize,
+    /// Hex sha256 of the finished archive (only in the `.info.json` sidecar)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size_bytes: Option<u64>,
+}
+
+/// Path of the checksum sidecar for `archive`
+pub fn checksum_path(archive: &Path) -> PathBuf {
+    let mut name = archive.as_os_str().to_os_string();
+    name.push(".sha256");
+    PathBuf::from(name)
+}
+
+/// Path of the [`BackupInfo`] sidecar for `archive`
+pub fn info_path(archive: &Path) -> PathBuf {
+    let mut name = archive.as_os_str().to_os_string();
+    name.push(".info.json");
+    PathBuf::from(name)
+}
+
+/// Hex sha256 and size of a file.
+async fn file_sha256(path: &Path) -> Result<(String, u64)> {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || -> std::io::Result<(String, u64)> {
+        let mut file = std::fs::File::open(&path)?;
+        let mut hasher = Sha256::new();
+        let size = std::io::copy(&mut file, &mut hasher)?;
+        let hex = hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        Ok((hex, size))
+    })
+    .await
+    .map_err(|e| Error::InvalidArgument(format!("Failed to hash archive: {}", e)))?
+    .map_err(Error::from)
+}
+
+/// Workflow fields accepted by the create/update endpoints
+const WORKFLOW_BODY_FIELDS: [&str; 4] = ["nodes", "connections", "settings", "staticData"];
+
+/// How a workflow is restored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestoreAction {
+    Create,
+    Update,
+}
+
+/// Outcome of restoring one workflow.
+#[derive(Debug, Clone)]
+pub struct WorkflowRestore {
+    pub id: String,
+    pub name: String,
+    pub action: RestoreAction,
+    /// The workflow was active when backed up
+    pub activate: bool,
+    /// `None` on success (or in a dry run)
+    pub error: Option<String>,
+}
+
+impl std::fmt::Display for WorkflowRestore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let action = match self.action {
+            RestoreAction::Create => "create",
+            RestoreAction::Update => "update",
+        };
+        let status = if self.error.is_some() { "❌" } else { "✅" };
+        write!(f, "{} {} '{}' ({})", status, action, self.name, self.id)?;
+        if self.activate {
+            write!(f, " + activate")?;
+        }
+        if let Some(ref error) = self.error {
+            write!(f, ": {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+/// Per-workflow summary of a restore.
+#[derive(Debug, Clone, Default)]
+pub struct RestoreReport {
+    pub dry_run: bool,
+    pub workflows: Vec<WorkflowRestore>,
+}
+
+impl RestoreReport {
+    pub fn succeeded(&self) -> usize {
+        self.workflows.iter().filter(|w| w.error.is_none()).count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.workflows.len() - self.succeeded()
+    }
+}
+
+/// Request body for creating or updating `workflow`
+fn workflow_body(workflow: &Workflow) -> serde_json::Value {
+    let mut body = serde_json::Map::new();
+    body.insert("name".to_string(), workflow.name.clone().into());
+    for field in WORKFLOW_BODY_FIELDS {
+        if let Some(value) = workflow.extra.get(field) {
+            body.insert(field.to_string(), value.clone());
+        }
+    }
+    body.entry("settings")
+        .or_insert_with(|| serde_json::json!({}));
+    serde_json::Value::Object(body)
 }
 
 /// N8N backup manager.
@@ -203,12 +325,14 @@ impl N8NBackup {
         }
 
         // 3. Create backup info file
-        let backup_info = BackupInfo {
+        let mut backup_info = BackupInfo {
             timestamp: timestamp.clone(),
             datetime: Utc::now().to_rfc3339(),
             n8n_url: self.config.n8n_url.clone(),
             workflows_count: workflows.len(),
             credentials_count: credentials.len(),
+            sha256: None,
+            size_bytes: None,
         };
         let info_file = backup_path.join("backup_info.json");
         let content = serde_json::to_string_pretty(&backup_info)?;
@@ -221,33 +345,88 @@ impl N8NBackup {
             .join(format!("{}.tar.gz", backup_name));
         self.create_tar_gz(&backup_path, &archive_path).await?;
 
-        info!(path = %archive_path.display(), "✅ Created archive");
+        // 5. Checksum sidecar
+        let (sha256, size_bytes) = file_sha256(&archive_path).await?;
+        let archive_name = archive_path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy();
+        fs::write(
+            checksum_path(&archive_path),
+            format!("{}  {}\n", sha256, archive_name),
+        )
+        .await?;
+        backup_info.sha256 = Some(sha256);
+        backup_info.size_bytes = Some(size_bytes);
+        let content = serde_json::to_string_pretty(&backup_info)?;
+        fs::write(info_path(&archive_path), content).await?;
 
-        // 5. Cleanup temporary directory
+        info!(
+            path = %archive_path.display(),
+            size_bytes,
+            sha256 = backup_info.sha256.as_deref().unwrap_or_default(),
+            "✅ Created archive"
+        );
+
+        // 6. Cleanup temporary directory
         fs::remove_dir_all(&backup_path).await?;
 
         Ok(archive_path)
     }
 
     /// Create tar.gz archive.
+    ///
+    /// Same layout as `tar -czf dest -C <parent> <name>`: every entry sits
+    /// under the backup directory's name.
     async fn create_tar_gz(&self, source: &Path, dest: &Path) -> Result<()> {
-        use std::process::Command;
+        let source = source.to_path_buf();
+        let dest = dest.to_path_buf();
+
+        tokio::task::spawn_blocking(move || -> std::io::Result<()> {
+            let name = source.file_name().unwrap_or_default();
+            let file = std::fs::File::create(&dest)?;
+            let encoder = GzEncoder::new(file, Compression::default());
+            let mut builder = tar::Builder::new(encoder);
+            builder.append_dir_all(name, &source)?;
+            builder.into_inner()?.finish()?;
+            Ok(())
+        })
+        .await
+        .map_err(|e| Error::InvalidArgument(format!("Failed to create archive: {}", e)))?
+        .map_err(|e| Error::InvalidArgument(format!("Failed to create tar archive: {}", e)))
+    }
 
-        let status = Command::new("tar")
-            .arg("-czf")
-            .arg(dest)
-            .arg("-C")
-            .arg(source.parent().unwrap_or(Path::new(".")))
-            .arg(source.file_name().unwrap())
-            .status()
-            .map_err(|e| Error::InvalidArgument(format!("Failed to create archive: {}", e)))?;
+    /// Extract a tar.gz archive into `dest`.
+    async fn extract_tar_gz(&self, archive: &Path, dest: &Path) -> Result<()> {
+        let archive = archive.to_path_buf();
+        let dest = dest.to_path_buf();
 
-        if !status.success() {
-            return Err(Error::InvalidArgument(
-                "Failed to create tar archive".to_string(),
-            ));
-        }
+        tokio::task::spawn_blocking(move || -> std::io::Result<()> {
+            let file = std::fs::File::open(&archive)?;
+            tar::Archive::new(GzDecoder::new(file)).unpack(&dest)
+        })
+        .await
+        .map_err(|e| Error::InvalidArgument(format!("Failed to extract archive: {}", e)))?
+        .map_err(|e| Error::InvalidArgument(format!("Failed to extract tar archive: {}", e)))
+    }
+
+    /// Recompute the archive checksum and compare it with its `.sha256` sidecar.
+    pub async fn verify_backup(&self, path: &Path) -> Result<()> {
+        let sidecar = checksum_path(path);
+        let expected = fs::read_to_string(&sidecar).await.map_err(|e| {
+            Error::InvalidArgument(format!("No checksum for {}: {}", path.display(), e))
+        })?;
+        let expected = expected.split_whitespace().next().unwrap_or_default();
 
+        let (actual, _) = file_sha256(path).await?;
+        if !actual.eq_ignore_ascii_case(expected) {
+            return Err(Error::InvalidArgument(format!(
+                "Checksum mismatch for {}: expected {}, got {}",
+                path.display(),
+                expected,
+                actual
+            )));
+        }
         Ok(())
     }
 
@@ -280,6 +459,8 @@ impl N8NBackup {
             let age_days = (now - *mtime).num_days();
             if age_days > self.config.retention_days as i64 {
                 fs::remove_file(path).await?;
+                fs::remove_file(checksum_path(path)).await.ok();
+                fs::remove_file(info_path(path)).await.ok();
                 info!(
                     file = %path.display(),
                     age_days = age_days,
@@ -296,6 +477,8 @@ impl N8NBackup {
         while backups.len() > self.config.max_backups as usize {
             if let Some((oldest, _)) = backups.first() {
                 fs::remove_file(oldest).await?;
+                fs::remove_file(checksum_path(oldest)).await.ok();
+                fs::remove_file(info_path(oldest)).await.ok();
                 info!(file = %oldest.display(), "🗑️ Removed excess backup");
                 removed_by_count += 1;
                 backups.remove(0);
@@ -331,12 +514,18 @@ impl N8NBackup {
                 let modified: DateTime<Utc> = metadata.modified()?.into();
                 let size_mb = metadata.len() as f64 / (1024.0 * 1024.0);
                 let age_days = (Utc::now() - modified).num_days();
+                let verified = if checksum_path(&path).exists() {
+                    Some(self.verify_backup(&path).await.is_ok())
+                } else {
+                    None
+                };
 
                 backups.push(BackupEntry {
                     path,
                     modified,
                     size_mb,
                     age_days,
+                    verified,
                 });
             }
         }
@@ -346,7 +535,10 @@ impl N8NBackup {
     }
 
     /// Restore N8N configuration from backup.
-    pub async fn restore_backup(&self, backup_file: &Path) -> Result<()> {
+    ///
+    /// With `dry_run` the archive is only read and the report lists what would
+    /// be created or updated.
+    pub async fn restore_backup(&self, backup_file: &Path, dry_run: bool) -> Result<RestoreReport> {
         if !backup_file.exists() {
             return Err(Error::InvalidArgument(format!(
                 "Backup file not found: {}",
@@ -354,25 +546,19 @@ impl N8NBackup {
             )));
         }
 
+        if checksum_path(backup_file).exists() {
+            self.verify_backup(backup_file).await?;
+        }
+
         info!(file = %backup_file.display(), "🔄 Restoring from backup");
 
         let temp_dir = self.config.backup_dir.join("restore_temp");
         fs::create_dir_all(&temp_dir).await?;
 
         // Extract archive
-        let status = std::process::Command::new("tar")
-            .arg("-xzf")
-            .arg(backup_file)
-            .arg("-C")
-            .arg(&temp_dir)
-            .status()
-            .map_err(|e| Error::InvalidArgument(format!("Failed to extract archive: {}", e)))?;
-
-        if !status.success() {
+        if let Err(e) = self.extract_tar_gz(backup_file, &temp_dir).await {
             fs::remove_dir_all(&temp_dir).await.ok();
-            return Err(Error::InvalidArgument(
-                "Failed to extract tar archive".to_string(),
-            ));
+            return Err(e);
         }
 
         // Find extracted directory
@@ -394,21 +580,165 @@ impl N8NBackup {
         let backup_data_dir = backup_data_dir
             .ok_or_else(|| Error::InvalidArgument("No backup data found in archive".to_string()))?;
 
-        // Check workflows file
+        // Read workflows file
         let workflows_file = backup_data_dir.join("workflows.json");
-        if workflows_file.exists() {
-            let content = fs::read_to_string(&workflows_file).await?;
-            let workflows: Vec<Workflow> = serde_json::from_str(&content)?;
-            info!(count = workflows.len(), "🔄 Found workflows in backup");
-            warn!("⚠️ Workflow restoration via API not yet implemented");
-            info!(file = %workflows_file.display(), "ℹ️ You can manually import workflows from this file");
-        }
-
-        info!("✅ Backup extracted successfully");
+        let workflows: Vec<Workflow> = if workflows_file.exists() {
+            let content = fs::read_to_string(&workflows_file).await;
+            let parsed = content
+                .map_err(Error::from)
+                .and_then(|c| serde_json::from_str(&c).map_err(Error::from));
+            match parsed {
+                Ok(workflows) => workflows,
+                Err(e) => {
+                    fs::remove_dir_all(&temp_dir).await.ok();
+                    return Err(e);
+                }
+            }
+        } else {
+            Vec::new()
+        };
+        info!(count = workflows.len(), "🔄 Found workflows in backup");
 
         // Cleanup
         fs::remove_dir_all(&temp_dir).await.ok();
 
+        let existing: HashSet<String> = self
+            .get_workflows()
+            .await?
+            .into_iter()
+            .map(|w| w.id)
+            .collect();
+
+        let mut report = RestoreReport {
+            dry_run,
+            workflows: Vec::with_capacity(workflows.len()),
+        };
+        for workflow in &workflows {
+            let action = if existing.contains(&workflow.id) {
+                RestoreAction::Update
+            } else {
+                RestoreAction::Create
+            };
+            let mut entry = WorkflowRestore {
+                id: workflow.id.clone(),
+                name: workflow.name.clone(),
+                action,
+                activate: workflow.active,
+                error: None,
+            };
+
+            if !dry_run {
+                match self.restore_workflow(workflow, action).await {
+                    Ok(action) => entry.action = action,
+                    Err(e) => entry.error = Some(e.to_string()),
+                }
+            }
+
+            if let Some(ref error) = entry.error {
+                warn!(workflow = %entry.name, error = %error, "❌ Workflow restore failed");
+            } else {
+                info!(workflow = %entry.name, action = ?entry.action, dry_run, "✅ Workflow restored");
+            }
+            report.workflows.push(entry);
+        }
+
+        info!(
+            succeeded = report.succeeded(),
+            failed = report.failed(),
+            dry_run,
+            "✅ Restore finished"
+        );
+        Ok(report)
+    }
+
+    /// Create or update one workflow, then activate it if it was active.
+    ///
+    /// A create that conflicts with an existing workflow falls back to an update.
+    async fn restore_workflow(
+        &self,
+        workflow: &Workflow,
+        action: RestoreAction,
+    ) -> Result<RestoreAction> {
+        let body = workflow_body(workflow);
+
+        let (action, id) = match action {
+            RestoreAction::Update => {
+                self.update_workflow(&workflow.id, &body).await?;
+                (RestoreAction::Update, workflow.id.clone())
+            }
+            RestoreAction::Create => {
+                let url = format!("{}/api/v1/workflows", self.config.n8n_url);
+                let response = self
+                    .http
+                    .post(&url)
+                    .headers(self.get_headers())
+                    .json(&body)
+                    .send()
+                    .await
+                    .map_err(|e| {
+                        Error::InvalidArgument(format!("Failed to create workflow: {}", e))
+                    })?;
+
+                if response.status() == StatusCode::CONFLICT {
+                    self.update_workflow(&workflow.id, &body).await?;
+                    (RestoreAction::Update, workflow.id.clone())
+                } else if response.status().is_success() {
+                    let created: serde_json::Value = response.json().await.unwrap_or_default();
+                    let id = created
+                        .get("id")
+                        .and_then(|v| v.as_str())
+                        .map(str::to_string)
+                        .unwrap_or_else(|| workflow.id.clone());
+                    (RestoreAction::Create, id)
+                } else {
+                    return Err(Error::InvalidArgument(format!(
+                        "Create returned {}",
+                        response.status()
+                    )));
+                }
+            }
+        };
+
+        if workflow.active {
+            let url = format!("{}/api/v1/workflows/{}/activate", self.config.n8n_url, id);
+            let response = self
+                .http
+                .post(&url)
+                .headers(self.get_headers())
+                .send()
+                .await
+                .map_err(|e| {
+                    Error::InvalidArgument(format!("Failed to activate workflow: {}", e))
+                })?;
+            if !response.status().is_success() {
+                return Err(Error::InvalidArgument(format!(
+                    "Activate returned {}",
+                    response.status()
+                )));
+            }
+        }
+
+        Ok(action)
+    }
+
+    /// Replace the workflow with `id`.
+    async fn update_workflow(&self, id: &str, body: &serde_json::Value) -> Result<()> {
+        let url = format!("{}/api/v1/workflows/{}", self.config.n8n_url, id);
+        let response = self
+            .http
+            .put(&url)
+            .headers(self.get_headers())
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| Error::InvalidArgument(format!("Failed to update workflow: {}", e)))?;
+
+        if !response.status().is_success() {
+            return Err(Error::InvalidArgument(format!(
+                "Update returned {}",
+                response.status()
+            )));
+        }
         Ok(())
     }
 }
@@ -420,13 +750,21 @@ pub struct BackupEntry {
     pub modified: DateTime<Utc>,
     pub size_mb: f64,
     pub age_days: i64,
+    /// Checksum matches its sidecar; `None` for archives without one
+    pub verified: Option<bool>,
 }
 
 impl std::fmt::Display for BackupEntry {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let verified = match self.verified {
+            Some(true) => "✓",
+            Some(false) => "✗",
+            None => "?",
+        };
         write!(
             f,
-            "• {} ({:.2} MB, {} days old)",
+            "{} {} ({:.2} MB, {} days old)",
+            verified,
             self.path.file_name().unwrap_or_default().to_string_lossy(),
             self.size_mb,
             self.age_days
@@ -666,6 +1004,18 @@ mod tests {
         assert!(listing.contains("workflows.json"));
         assert!(listing.contains("credentials_meta.json"));
         assert!(listing.contains("backup_info.json"));
+
+        let info: BackupInfo =
+            serde_json::from_str(&std::fs::read_to_string(info_path(&archive_path)).unwrap())
+                .unwrap();
+        assert_eq!(info.workflows_count, 1);
+        assert_eq!(info.credentials_count, 1);
+        let sidecar = std::fs::read_to_string(checksum_path(&archive_path)).unwrap();
+        assert_eq!(info.sha256.as_deref(), sidecar.split_whitespace().next());
+        assert_eq!(
+            info.size_bytes,
+            Some(std::fs::metadata(&archive_path).unwrap().len())
+        );
     }
 
     #[tokio::test]
@@ -697,9 +1047,175 @@ mod tests {
         let backup = N8NBackup::new(cfg).expect("backup");
 
         let archive_path = backup.create_backup().await.unwrap();
-        backup.restore_backup(&archive_path).await.unwrap();
+        let report = backup.restore_backup(&archive_path, true).await.unwrap();
 
         assert!(!tmp.path().join("restore_temp").exists());
+        assert!(report.dry_run);
+        assert_eq!(report.workflows.len(), 1);
+        assert_eq!(report.workflows[0].action, RestoreAction::Update);
+    }
+
+    #[tokio::test]
+    async fn truncated_archives_fail_verification() {
+        let server = MockServer::start_async().await;
+        server.mock(|when, then| {
+            when.method(GET).path("/api/v1/workflows");
+            then.status(200).json_body(json!({
+                "data": [ { "id": "1", "name": "WF", "active": true } ]
+            }));
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/api/v1/credentials");
+            then.status(200).json_body(json!({ "data": [] }));
+        });
+        let tmp = tempdir().expect("tempdir");
+        let backup = backup_for(&server, tmp.path());
+
+        let archive_path = backup.create_backup().await.unwrap();
+        let sidecar = std::fs::read_to_string(checksum_path(&archive_path)).unwrap();
+        let name = archive_path.file_name().unwrap().to_string_lossy();
+        assert!(sidecar.ends_with(&format!("  {}\n", name)));
+        assert_eq!(sidecar.split_whitespace().next().unwrap().len(), 64);
+        backup.verify_backup(&archive_path).await.unwrap();
+
+        let listed = backup.list_backups().await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].verified, Some(true));
+        assert!(listed[0].to_string().starts_with("✓ n8n_backup_"));
+
+        let bytes = std::fs::read(&archive_path).unwrap();
+        std::fs::write(&archive_path, &bytes[..bytes.len() / 2]).unwrap();
+        let err = backup.verify_backup(&archive_path).await.unwrap_err();
+        assert!(err.to_string().contains("Checksum mismatch"));
+        assert_eq!(
+            backup.list_backups().await.unwrap()[0].verified,
+            Some(false)
+        );
+
+        std::fs::remove_file(checksum_path(&archive_path)).unwrap();
+        assert_eq!(backup.list_backups().await.unwrap()[0].verified, None);
+    }
+
+    #[tokio::test]
+    async fn archives_made_by_system_tar_are_still_restorable() {
+        let server = MockServer::start_async().await;
+        server.mock(|when, then| {
+            when.method(GET).path("/api/v1/workflows");
+            then.status(200).json_body(json!({ "data": [] }));
+        });
+        let tmp = tempdir().expect("tempdir");
+        let backup = backup_for(&server, tmp.path());
+
+        let data_dir = tmp.path().join("n8n_backup_20250101_000000");
+        std::fs::create_dir_all(&data_dir).unwrap();
+        std::fs::write(
+            data_dir.join("workflows.json"),
+            r#"[{"id": "7", "name": "Legacy", "active": false}]"#,
+        )
+        .unwrap();
+        let archive_path = tmp.path().join("n8n_backup_20250101_000000.tar.gz");
+        let status = std::process::Command::new("tar")
+            .arg("-czf")
+            .arg(&archive_path)
+            .arg("-C")
+            .arg(tmp.path())
+            .arg("n8n_backup_20250101_000000")
+            .status()
+            .expect("tar create");
+        assert!(status.success());
+
+        let report = backup.restore_backup(&archive_path, true).await.unwrap();
+        assert_eq!(report.workflows.len(), 1);
+        assert_eq!(report.workflows[0].name, "Legacy");
+    }
+
+    #[tokio::test]
+    async fn restore_backup_creates_updates_and_reactivates_workflows() {
+        let server = MockServer::start_async().await;
+        let tmp = tempdir().expect("tempdir");
+        let backup = backup_for(&server, tmp.path());
+
+        let mut list = server.mock(|when, then| {
+            when.method(GET).path("/api/v1/workflows");
+            then.status(200).json_body(json!({
+                "data": [
+                    { "id": "1", "name": "Existing", "active": true,
+                      "nodes": [], "connections": {}, "updatedAt": "2025-01-01" },
+                    { "id": "2", "name": "Deleted", "active": false, "nodes": [] },
+                    { "id": "3", "name": "Clashing", "active": true, "nodes": [] }
+                ]
+            }));
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/api/v1/credentials");
+            then.status(200).json_body(json!({ "data": [] }));
+        });
+        let archive_path = backup.create_backup().await.unwrap();
+
+        // Only workflow 1 is still on the server; 3 was recreated concurrently
+        list.delete();
+        server.mock(|when, then| {
+            when.method(GET).path("/api/v1/workflows");
+            then.status(200).json_body(json!({
+                "data": [ { "id": "1", "name": "Existing", "active": false } ]
+            }));
+        });
+        let update = server.mock(|when, then| {
+            when.method(PUT)
+                .path("/api/v1/workflows/1")
+                .header("X-N8N-API-KEY", "k")
+                .json_body(json!({
+                    "name": "Existing", "nodes": [], "connections": {}, "settings": {}
+                }));
+            then.status(200).json_body(json!({ "id": "1" }));
+        });
+        let create = server.mock(|when, then| {
+            when.method(POST)
+                .path("/api/v1/workflows")
+                .body_includes("\"Deleted\"");
+            then.status(200).json_body(json!({ "id": "20" }));
+        });
+        server.mock(|when, then| {
+            when.method(POST)
+                .path("/api/v1/workflows")
+                .body_includes("\"Clashing\"");
+            then.status(409);
+        });
+        server.mock(|when, then| {
+            when.method(PUT).path("/api/v1/workflows/3");
+            then.status(500);
+        });
+        let activate = server.mock(|when, then| {
+            when.method(POST).path("/api/v1/workflows/1/activate");
+            then.status(200);
+        });
+
+        let dry = backup.restore_backup(&archive_path, true).await.unwrap();
+        let actions: Vec<_> = dry.workflows.iter().map(|w| w.action).collect();
+        assert_eq!(
+            actions,
+            [
+                RestoreAction::Update,
+                RestoreAction::Create,
+                RestoreAction::Create
+            ]
+        );
+        assert_eq!(dry.failed(), 0);
+        update.assert_calls(0);
+        create.assert_calls(0);
+
+        let report = backup.restore_backup(&archive_path, false).await.unwrap();
+        update.assert_calls(1);
+        create.assert_calls(1);
+        activate.assert_calls(1);
+        assert_eq!(report.succeeded(), 2);
+        assert_eq!(report.failed(), 1);
+        assert_eq!(report.workflows[2].action, RestoreAction::Create);
+        assert!(report.workflows[2]
+            .error
+            .as_deref()
+            .unwrap()
+            .contains("Update returned 500"));
     }
 
     #[tokio::test]
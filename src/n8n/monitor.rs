@@ -1,35 +1,74 @@
 //! N8N Service Monitor with Auto-Restart
 //!
 //! Мониторинг N8N с автоматическим перезапуском при недоступности
+//!
+//! One process can watch several instances: `N8N_INSTANCES` holds a JSON list
+//! of `{name, url, api_key, restart_command}`. Each instance has its own
+//! failure counter and restart cooldown, so only the failing one is restarted.
+//!
+//! Alerts go to every configured [`AlertSink`]: Telegram (`TELEGRAM_BOT_TOKEN`
+//! with `N8N_ALERT_CHAT_ID`) and/or a webhook (`N8N_ALERT_WEBHOOK`). Up/down
+//! alerts fire on state changes, with the same cooldown rules as the
+//! `devops_ai_bot` monitor loop.
 
 use chrono::{DateTime, Utc};
 use reqwest::Client;
+use serde::Deserialize;
 use std::env;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::process::Command;
 use tokio::time::sleep;
 use tracing::{error, info, warn};
 
 use crate::{Error, Result};
 
+/// One monitored N8N instance.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct InstanceConfig {
+    pub name: String,
+    pub url: String,
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Falls back to the global `restart_command`
+    #[serde(default)]
+    pub restart_command: Option<String>,
+}
+
 /// Monitor configuration.
 #[derive(Debug, Clone)]
 pub struct MonitorConfig {
+    /// Single-instance setup (`N8N_URL`), used when `instances` is empty
     pub n8n_url: String,
     pub api_key: Option<String>,
     pub check_interval_secs: u64,
+    /// Default restart command for instances without their own
     pub restart_command: String,
     pub telegram_bot_token: Option<String>,
+    /// `N8N_ALERT_CHAT_ID`, falling back to `TELEGRAM_CHAT_ID`
     pub telegram_chat_id: Option<i64>,
+    /// `N8N_ALERT_WEBHOOK`
+    pub alert_webhook: Option<String>,
+    /// Minimum time between repeated "down" alerts for one instance
+    pub alert_cooldown_secs: u64,
     pub max_retries: u32,
     pub timeout_secs: u64,
+    /// Instances from `N8N_INSTANCES`
+    pub instances: Vec<InstanceConfig>,
 }
 
 impl MonitorConfig {
     /// Load configuration from environment variables.
     pub fn from_env() -> Result<Self> {
-        let n8n_url = env::var("N8N_URL")
-            .map_err(|_| Error::InvalidArgument("N8N_URL not set".to_string()))?;
+        let instances = match env::var("N8N_INSTANCES") {
+            Ok(raw) => parse_instances(&raw)?,
+            Err(_) => Vec::new(),
+        };
+
+        let n8n_url = match env::var("N8N_URL") {
+            Ok(url) => url,
+            Err(_) if !instances.is_empty() => String::new(),
+            Err(_) => return Err(Error::InvalidArgument("N8N_URL not set".to_string())),
+        };
 
         let restart_command =
             env::var("N8N_RESTART_COMMAND").unwrap_or_else(|_| "systemctl restart n8n".to_string());
@@ -43,9 +82,17 @@ impl MonitorConfig {
                 .unwrap_or(60),
             restart_command,
             telegram_bot_token: env::var("TELEGRAM_BOT_TOKEN").ok(),
-            telegram_chat_id: env::var("TELEGRAM_CHAT_ID")
+            telegram_chat_id: env::var("N8N_ALERT_CHAT_ID")
+                .or_else(|_| env::var("TELEGRAM_CHAT_ID"))
                 .ok()
                 .and_then(|s| s.parse().ok()),
+            alert_webhook: env::var("N8N_ALERT_WEBHOOK")
+                .ok()
+                .filter(|url| !url.is_empty()),
+            alert_cooldown_secs: env::var("N8N_ALERT_COOLDOWN")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(300),
             max_retries: env::var("MAX_RETRIES")
                 .ok()
                 .and_then(|s| s.parse().ok())
@@ -54,16 +101,172 @@ impl MonitorConfig {
                 .ok()
                 .and_then(|s| s.parse().ok())
                 .unwrap_or(10),
+            instances,
         })
     }
+
+    /// Instances to watch, each with its restart command resolved
+    pub fn resolved_instances(&self) -> Vec<InstanceConfig> {
+        if self.instances.is_empty() {
+            return vec![InstanceConfig {
+                name: "n8n".to_string(),
+                url: self.n8n_url.clone(),
+                api_key: self.api_key.clone(),
+                restart_command: Some(self.restart_command.clone()),
+            }];
+        }
+
+        self.instances
+            .iter()
+            .cloned()
+            .map(|mut instance| {
+                instance
+                    .restart_command
+                    .get_or_insert_with(|| self.restart_command.clone());
+                instance
+            })
+            .collect()
+    }
+
+    /// Configured alert destinations (empty when alerting is off)
+    pub fn alert_sinks(&self) -> Vec<AlertSink> {
+        let mut sinks = Vec::new();
+        if let (Some(bot_token), Some(chat_id)) = (&self.telegram_bot_token, self.telegram_chat_id)
+        {
+            sinks.push(AlertSink::Telegram {
+                bot_token: bot_token.clone(),
+                chat_id,
+            });
+        }
+        if let Some(url) = &self.alert_webhook {
+            sinks.push(AlertSink::Webhook { url: url.clone() });
+        }
+        sinks
+    }
+}
+
+/// Where monitor alerts are delivered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AlertSink {
+    /// Bot API `sendMessage`
+    Telegram { bot_token: String, chat_id: i64 },
+    /// JSON `{"text": ...}` POSTed to the URL
+    Webhook { url: String },
+}
+
+impl AlertSink {
+    /// Deliver one alert.
+    pub async fn send(&self, http: &Client, text: &str) -> Result<()> {
+        let request = match self {
+            AlertSink::Telegram { bot_token, chat_id } => {
+                let url = format!("https://api.telegram.org/bot{}/sendMessage", bot_token);
+                let params = [("chat_id", chat_id.to_string()), ("text", text.to_string())];
+                http.post(url).form(&params)
+            }
+            AlertSink::Webhook { url } => http.post(url).json(&serde_json::json!({ "text": text })),
+        };
+
+        let resp = request
+            .send()
+            .await
+            .map_err(|e| Error::ConnectionError(format!("{}: {}", self.label(), e)))?;
+        if !resp.status().is_success() {
+            return Err(Error::ConnectionError(format!(
+                "{} returned {}",
+                self.label(),
+                resp.status()
+            )));
+        }
+        Ok(())
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            AlertSink::Telegram { .. } => "Telegram alert",
+            AlertSink::Webhook { .. } => "Alert webhook",
+        }
+    }
+}
+
+/// Up/down change worth alerting about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transition {
+    Down,
+    Recovered,
+}
+
+/// Last status and last "down" alert of one instance.
+///
+/// Same rules as the `devops_ai_bot` monitor loop: a failing check alerts
+/// when the instance was not already down, or once the cooldown has passed
+/// since the previous alert; a passing check after a failure always reports
+/// the recovery.
+#[derive(Debug, Clone, Default)]
+pub struct AlertState {
+    last_healthy: Option<bool>,
+    last_alert: Option<Instant>,
+}
+
+impl AlertState {
+    /// Record a check result and return the alert to send, if any.
+    pub fn observe(
+        &mut self,
+        healthy: bool,
+        now: Instant,
+        cooldown: Duration,
+    ) -> Option<Transition> {
+        let prev = self.last_healthy.replace(healthy);
+
+        if healthy {
+            return (prev == Some(false)).then_some(Transition::Recovered);
+        }
+
+        let cooled_down = self
+            .last_alert
+            .is_none_or(|last| now.saturating_duration_since(last) >= cooldown);
+        if prev != Some(false) || cooled_down {
+            self.last_alert = Some(now);
+            Some(Transition::Down)
+        } else {
+            None
+        }
+    }
+}
+
+/// Parse `N8N_INSTANCES`: a JSON list of `{name, url, api_key?, restart_command?}`
+pub fn parse_instances(raw: &str) -> Result<Vec<InstanceConfig>> {
+    let instances: Vec<InstanceConfig> = serde_json::from_str(raw)
+        .map_err(|e| Error::InvalidArgument(format!("Invalid N8N_INSTANCES: {}", e)))?;
+
+    for (i, instance) in instances.iter().enumerate() {
+        if instances[..i]
+            .iter()
+            .any(|other| other.name == instance.name)
+        {
+            return Err(Error::InvalidArgument(format!(
+                "Duplicate N8N instance name '{}'",
+                instance.name
+            )));
+        }
+    }
+    Ok(instances)
+}
+
+/// Health and restart bookkeeping of one instance.
+#[derive(Debug)]
+struct InstanceState {
+    config: InstanceConfig,
+    consecutive_failures: u32,
+    last_restart: Option<DateTime<Utc>>,
+    alerts: AlertState,
 }
 
 /// N8N service monitor.
 pub struct N8NMonitor {
     config: MonitorConfig,
     http: Client,
-    consecutive_failures: u32,
-    last_restart: Option<DateTime<Utc>>,
+    instances: Vec<InstanceState>,
+    sinks: Vec<AlertSink>,
 }
 
 impl N8NMonitor {
@@ -76,11 +279,22 @@ impl N8NMonitor {
             .build()
             .map_err(|e| Error::InvalidArgument(format!("HTTP client error: {}", e)))?;
 
+        let instances = config
+            .resolved_instances()
+            .into_iter()
+            .map(|config| InstanceState {
+                config,
+                consecutive_failures: 0,
+                last_restart: None,
+                alerts: AlertState::default(),
+            })
+            .collect();
+
         Ok(Self {
+            sinks: config.alert_sinks(),
             config,
             http,
-            consecutive_failures: 0,
-            last_restart: None,
+            instances,
         })
     }
 
@@ -90,70 +304,80 @@ impl N8NMonitor {
         Self::new(config)
     }
 
-    /// Send alert to Telegram.
-    async fn send_telegram_alert(&self, message: &str) {
-        let (token, chat_id) = match (
-            &self.config.telegram_bot_token,
-            self.config.telegram_chat_id,
-        ) {
-            (Some(t), Some(c)) => (t, c),
-            _ => return,
-        };
+    /// Send an alert to every configured sink.
+    async fn send_alert(&self, message: &str) {
+        if self.sinks.is_empty() {
+            return;
+        }
 
-        let url = format!("https://api.telegram.org/bot{}/sendMessage", token);
         let text = format!(
             "🚨 N8N Monitor Alert\n\n{}\n\nTime: {}",
             message,
             Utc::now().format("%Y-%m-%d %H:%M:%S")
         );
 
-        let params = [("chat_id", chat_id.to_string()), ("text", text)];
-
-        match self.http.post(&url).form(&params).send().await {
-            Ok(resp) if resp.status().is_success() => {
-                info!(message = message, "Telegram alert sent");
-            }
-            Ok(resp) => {
-                error!(status = %resp.status(), "Failed to send Telegram alert");
-            }
-            Err(e) => {
-                error!(error = %e, "Failed to send Telegram alert");
+        for sink in &self.sinks {
+            match sink.send(&self.http, &text).await {
+                Ok(()) => info!(message = message, "{} sent", sink.label()),
+                Err(e) => error!(error = %e, "Failed to send alert"),
             }
         }
     }
 
-    /// Check if N8N is responding.
+    /// Check if every monitored instance is responding.
     pub async fn check_health(&self) -> bool {
-        let url = format!("{}/healthz", self.config.n8n_url);
+        self.check_all().await.iter().all(|(_, healthy)| *healthy)
+    }
+
+    /// Health of each instance, in configuration order.
+    pub async fn check_all(&self) -> Vec<(String, bool)> {
+        let mut report = Vec::with_capacity(self.instances.len());
+        for state in &self.instances {
+            let healthy = self.check_instance(&state.config).await;
+            report.push((state.config.name.clone(), healthy));
+        }
+        report
+    }
+
+    /// Check if one instance is responding.
+    async fn check_instance(&self, instance: &InstanceConfig) -> bool {
+        let url = format!("{}/healthz", instance.url);
 
         let mut request = self.http.get(&url);
-        if let Some(ref api_key) = self.config.api_key {
+        if let Some(ref api_key) = instance.api_key {
             request = request.header("X-N8N-API-KEY", api_key);
         }
 
         match request.send().await {
             Ok(resp) if resp.status().is_success() => {
-                info!("✅ N8N is healthy");
+                info!(instance = %instance.name, "✅ N8N is healthy");
                 true
             }
             Ok(resp) => {
-                warn!(status = %resp.status(), "❌ N8N returned error status");
+                warn!(
+                    instance = %instance.name,
+                    status = %resp.status(),
+                    "❌ N8N returned error status"
+                );
                 false
             }
             Err(e) => {
-                error!(error = %e, "❌ N8N connection error");
+                error!(instance = %instance.name, error = %e, "❌ N8N connection error");
                 false
             }
         }
     }
 
-    /// Restart N8N service.
-    async fn restart(&mut self) -> bool {
+    /// Restart the N8N instance at `index`.
+    async fn restart(&mut self, index: usize) -> bool {
+        let name = self.instances[index].config.name.clone();
+
         // Check minimum time between restarts (5 minutes)
-        if let Some(last) = self.last_restart {
+        if let Some(last) = self.instances[index].last_restart {
             let since_restart = (Utc::now() - last).num_seconds();
             if since_restart < 300 {
                 warn!(
+                    instance = %name,
                     seconds_since_restart = since_restart,
                     "Skipping restart, too soon since last restart"
                 );
@@ -161,51 +385,56 @@ impl N8NMonitor {
             }
         }
 
-        info!("🔄 Attempting to restart N8N...");
-        self.send_telegram_alert(&format!(
-            "Restarting N8N after {} failed checks",
-            self.consecutive_failures
+        info!(instance = %name, "🔄 Attempting to restart N8N...");
+        self.send_alert(&format!(
+            "[{}] Restarting N8N after {} failed checks",
+            name, self.instances[index].consecutive_failures
         ))
         .await;
 
+        let restart_command = self.instances[index]
+            .config
+            .restart_command
+            .clone()
+            .unwrap_or_else(|| self.config.restart_command.clone());
         let output = Command::new("sh")
             .arg("-c")
-            .arg(&self.config.restart_command)
+            .arg(&restart_command)
             .output()
             .await;
 
         match output {
             Ok(out) if out.status.success() => {
-                info!("✅ N8N restart command executed successfully");
-                self.last_restart = Some(Utc::now());
-                self.send_telegram_alert("✅ N8N restarted successfully")
+                info!(instance = %name, "✅ N8N restart command executed successfully");
+                self.instances[index].last_restart = Some(Utc::now());
+                self.send_alert(&format!("[{}] ✅ N8N restarted successfully", name))
                     .await;
 
                 // Wait 10 seconds before checking
                 sleep(Duration::from_secs(10)).await;
 
                 // Verify service is up
-                let is_healthy = self.check_health().await;
+                let is_healthy = self.check_instance(&self.instances[index].config).await;
                 if is_healthy {
-                    self.consecutive_failures = 0;
+                    self.instances[index].consecutive_failures = 0;
                     true
                 } else {
-                    error!("❌ N8N still unhealthy after restart");
-                    self.send_telegram_alert("⚠️ N8N restarted but still unhealthy")
+                    error!(instance = %name, "❌ N8N still unhealthy after restart");
+                    self.send_alert(&format!("[{}] ⚠️ N8N restarted but still unhealthy", name))
                         .await;
                     false
                 }
             }
             Ok(out) => {
                 let stderr = String::from_utf8_lossy(&out.stderr);
-                error!(stderr = %stderr, "❌ Failed to restart N8N");
-                self.send_telegram_alert(&format!("❌ Failed to restart N8N: {}", stderr))
+                error!(instance = %name, stderr = %stderr, "❌ Failed to restart N8N");
+                self.send_alert(&format!("[{}] ❌ Failed to restart N8N: {}", name, stderr))
                     .await;
                 false
             }
             Err(e) => {
-                error!(error = %e, "❌ Exception during restart");
-                self.send_telegram_alert(&format!("❌ Exception during restart: {}", e))
+                error!(instance = %name, error = %e, "❌ Exception during restart");
+                self.send_alert(&format!("[{}] ❌ Exception during restart: {}", name, e))
                     .await;
                 false
             }
@@ -214,49 +443,75 @@ impl N8NMonitor {
 
     /// Main monitoring loop.
     pub async fn monitor_loop(&mut self) -> Result<()> {
-        info!(
-            url = %self.config.n8n_url,
-            interval = self.config.check_interval_secs,
-            command = %self.config.restart_command,
-            "🚀 Starting N8N monitor"
-        );
+        for state in &self.instances {
+            info!(
+                instance = %state.config.name,
+                url = %state.config.url,
+                interval = self.config.check_interval_secs,
+                command = state.config.restart_command.as_deref().unwrap_or_default(),
+                "🚀 Starting N8N monitor"
+            );
+        }
 
-        self.send_telegram_alert("🚀 N8N Monitor started").await;
+        self.send_alert("🚀 N8N Monitor started").await;
 
         loop {
-            let is_healthy = self.check_health().await;
-
-            if is_healthy {
-                if self.consecutive_failures > 0 {
-                    info!(failures = self.consecutive_failures, "✅ N8N recovered");
-                    self.send_telegram_alert(&format!(
-                        "✅ N8N recovered after {} failures",
-                        self.consecutive_failures
-                    ))
-                    .await;
-                }
-                self.consecutive_failures = 0;
-            } else {
-                self.consecutive_failures += 1;
-                warn!(
-                    failures = self.consecutive_failures,
-                    max = self.config.max_retries,
-                    "⚠️ Consecutive failures"
-                );
-
-                if self.consecutive_failures >= self.config.max_retries {
-                    error!(
-                        retries = self.config.max_retries,
-                        "❌ N8N failed health checks, initiating restart"
-                    );
-                    self.restart().await;
-                }
+            for index in 0..self.instances.len() {
+                self.check_and_heal(index).await;
             }
 
             sleep(Duration::from_secs(self.config.check_interval_secs)).await;
         }
     }
 
+    /// One check of the instance at `index`, restarting it after too many failures.
+    async fn check_and_heal(&mut self, index: usize) {
+        let is_healthy = self.check_instance(&self.instances[index].config).await;
+        let name = self.instances[index].config.name.clone();
+        let cooldown = Duration::from_secs(self.config.alert_cooldown_secs);
+        let transition = self.instances[index]
+            .alerts
+            .observe(is_healthy, Instant::now(), cooldown);
+
+        if is_healthy {
+            let failures = self.instances[index].consecutive_failures;
+            if transition == Some(Transition::Recovered) {
+                info!(instance = %name, failures, "✅ N8N recovered");
+                self.send_alert(&format!(
+                    "[{}] ✅ N8N recovered after {} failures",
+                    name, failures
+                ))
+                .await;
+            }
+            self.instances[index].consecutive_failures = 0;
+        } else {
+            self.instances[index].consecutive_failures += 1;
+            let failures = self.instances[index].consecutive_failures;
+            if transition == Some(Transition::Down) {
+                self.send_alert(&format!(
+                    "[{}] ❌ N8N is down ({} failed checks)",
+                    name, failures
+                ))
+                .await;
+            }
+            warn!(
+                instance = %name,
+                failures,
+                max = self.config.max_retries,
+                "⚠️ Consecutive failures"
+            );
+
+            if failures >= self.config.max_retries {
+                error!(
+                    instance = %name,
+                    retries = self.config.max_retries,
+                    "❌ N8N failed health checks, initiating restart"
+                );
+                self.restart(index).await;
+            }
+        }
+    }
+
     /// Run single health check (for CLI).
     pub async fn run_check(&self) -> bool {
         self.check_health().await
@@ -340,12 +595,76 @@ mod tests {
             restart_command: "true".to_string(),
             telegram_bot_token: None,
             telegram_chat_id: None,
+            alert_webhook: None,
+            alert_cooldown_secs: 300,
             max_retries: 3,
             timeout_secs: 2,
+            instances: Vec::new(),
         };
         N8NMonitor::new(cfg).expect("monitor")
     }
 
+    #[test]
+    fn instances_parse_with_default_restart_command() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        let _guards = [
+            EnvGuard::unset("N8N_URL"),
+            EnvGuard::set(
+                "N8N_INSTANCES",
+                r#"[{"name": "prod", "url": "https://n8n.example.com", "api_key": "k"},
+                    {"name": "dev", "url": "http://localhost:5678", "restart_command": "docker restart n8n-dev"}]"#,
+            ),
+            EnvGuard::unset("N8N_RESTART_COMMAND"),
+        ];
+
+        let cfg = MonitorConfig::from_env().unwrap();
+        let instances = cfg.resolved_instances();
+        assert_eq!(instances.len(), 2);
+        assert_eq!(instances[0].name, "prod");
+        assert_eq!(instances[0].api_key.as_deref(), Some("k"));
+        assert_eq!(
+            instances[0].restart_command.as_deref(),
+            Some("systemctl restart n8n")
+        );
+        assert_eq!(
+            instances[1].restart_command.as_deref(),
+            Some("docker restart n8n-dev")
+        );
+
+        let err = parse_instances(r#"[{"name": "a", "url": "x"}, {"name": "a", "url": "y"}]"#)
+            .unwrap_err();
+        assert!(err.to_string().contains("Duplicate N8N instance name 'a'"));
+    }
+
+    #[tokio::test]
+    async fn check_all_reports_each_instance() {
+        let up = MockServer::start_async().await;
+        up.mock(|when, then| {
+            when.method(GET).path("/healthz");
+            then.status(200);
+        });
+        let down = MockServer::start_async().await;
+        down.mock(|when, then| {
+            when.method(GET).path("/healthz");
+            then.status(503);
+        });
+
+        let mut monitor = monitor_for(&up, None);
+        monitor.config.instances = parse_instances(&format!(
+            r#"[{{"name": "prod", "url": "{}"}}, {{"name": "staging", "url": "{}"}}]"#,
+            up.base_url(),
+            down.base_url()
+        ))
+        .unwrap();
+        let monitor = N8NMonitor::new(monitor.config).unwrap();
+
+        assert_eq!(
+            monitor.check_all().await,
+            vec![("prod".to_string(), true), ("staging".to_string(), false)]
+        );
+        assert!(!monitor.check_health().await);
+    }
+
     #[tokio::test]
     async fn check_health_returns_true_on_success() {
         let server = MockServer::start_async().await;
@@ -375,20 +694,86 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn send_telegram_alert_is_noop_without_credentials() {
+    async fn send_alert_is_noop_without_credentials() {
         let server = MockServer::start_async().await;
         let monitor = monitor_for(&server, None);
-        monitor.send_telegram_alert("test").await;
+        assert!(monitor.sinks.is_empty());
+        monitor.send_alert("test").await;
+    }
+
+    #[tokio::test]
+    async fn alerts_are_posted_to_the_webhook() {
+        let server = MockServer::start_async().await;
+        let hook = server.mock(|when, then| {
+            when.method(POST)
+                .path("/hook")
+                .body_includes("[prod] N8N is down");
+            then.status(200);
+        });
+
+        let mut monitor = monitor_for(&server, None);
+        monitor.config.alert_webhook = Some(format!("{}/hook", server.base_url()));
+        let monitor = N8NMonitor::new(monitor.config).unwrap();
+        assert_eq!(
+            monitor.sinks,
+            vec![AlertSink::Webhook {
+                url: format!("{}/hook", server.base_url())
+            }]
+        );
+
+        monitor.send_alert("[prod] N8N is down").await;
+        hook.assert_calls(1);
+
+        let err = AlertSink::Webhook {
+            url: format!("{}/missing", server.base_url()),
+        }
+        .send(&monitor.http, "x")
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("Alert webhook returned 404"));
+    }
+
+    #[test]
+    fn alerts_fire_on_transitions_with_cooldown() {
+        let start = Instant::now();
+        let cooldown = Duration::from_secs(300);
+        let at = |secs| start + Duration::from_secs(secs);
+        let mut state = AlertState::default();
+
+        assert_eq!(state.observe(true, at(0), cooldown), None);
+        assert_eq!(
+            state.observe(false, at(60), cooldown),
+            Some(Transition::Down)
+        );
+        // Still down: quiet until the cooldown has passed
+        assert_eq!(state.observe(false, at(120), cooldown), None);
+        assert_eq!(state.observe(false, at(359), cooldown), None);
+        assert_eq!(
+            state.observe(false, at(360), cooldown),
+            Some(Transition::Down)
+        );
+        assert_eq!(
+            state.observe(true, at(420), cooldown),
+            Some(Transition::Recovered)
+        );
+        assert_eq!(state.observe(true, at(480), cooldown), None);
+
+        // Down on the very first check counts as a transition
+        let mut state = AlertState::default();
+        assert_eq!(
+            state.observe(false, at(0), cooldown),
+            Some(Transition::Down)
+        );
     }
 
     #[tokio::test]
     async fn restart_is_skipped_when_too_soon_since_last_restart() {
         let server = MockServer::start_async().await;
         let mut monitor = monitor_for(&server, None);
-        monitor.last_restart = Some(Utc::now());
-        monitor.consecutive_failures = 10;
+        monitor.instances[0].last_restart = Some(Utc::now());
+        monitor.instances[0].consecutive_failures = 10;
 
-        let restarted = monitor.restart().await;
+        let restarted = monitor.restart(0).await;
         assert!(!restarted);
     }
 }
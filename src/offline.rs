@@ -0,0 +1,116 @@
+//! Offline input for `--no-network` runs
+//!
+//! Commands that support offline mode read a chat history from a JSONL file
+//! (one message per line, newest or oldest first) instead of fetching it from
+//! Telegram, so pipelines can be exercised in CI and demos without a session.
+
+use std::fs;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+/// One message of an offline history
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OfflineMessage {
+    pub id: i32,
+    pub date: DateTime<Utc>,
+    /// `@username` or display name, as printed by the live commands
+    #[serde(alias = "sender_name")]
+    pub sender: String,
+    #[serde(default)]
+    pub sender_id: Option<i64>,
+    #[serde(default)]
+    pub text: String,
+    #[serde(default)]
+    pub reply_to: Option<i32>,
+    #[serde(default)]
+    pub reactions: i32,
+    #[serde(default)]
+    pub has_media: bool,
+    #[serde(default)]
+    pub is_bot: bool,
+}
+
+impl OfflineMessage {
+    /// Username without `@`, if the sender is given as one
+    pub fn username(&self) -> Option<&str> {
+        self.sender.strip_prefix('@')
+    }
+}
+
+/// Parse a JSONL history; blank lines are skipped, malformed ones are an error
+pub fn parse_jsonl(content: &str) -> Result<Vec<OfflineMessage>> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line).map_err(|e| {
+                Error::SerializationError(format!("Invalid message on line {}: {}", i + 1, e))
+            })
+        })
+        .collect()
+}
+
+/// Load a JSONL history, newest message first (like Telegram history)
+pub fn load_jsonl(path: &Path) -> Result<Vec<OfflineMessage>> {
+    let content = fs::read_to_string(path).map_err(|e| {
+        Error::InvalidArgument(format!(
+            "Cannot read offline input {}: {}",
+            path.display(),
+            e
+        ))
+    })?;
+    let mut messages = parse_jsonl(&content)?;
+    messages.sort_by(|a, b| b.date.cmp(&a.date).then(b.id.cmp(&a.id)));
+    Ok(messages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn parses_minimal_and_full_lines() {
+        let content = r#"
+{"id": 1, "date": "2024-05-01T10:00:00Z", "sender": "@alice", "text": "hi"}
+
+{"id": 2, "date": "2024-05-01T10:05:00Z", "sender": "Bob", "sender_id": 7, "text": "hello", "reply_to": 1, "reactions": 2, "has_media": true, "is_bot": true}
+"#;
+        let messages = parse_jsonl(content).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].username(), Some("alice"));
+        assert_eq!(messages[0].reactions, 0);
+        assert_eq!(messages[1].username(), None);
+        assert_eq!(messages[1].reply_to, Some(1));
+        assert!(messages[1].is_bot);
+    }
+
+    #[test]
+    fn reports_the_bad_line() {
+        let content =
+            "{\"id\": 1, \"date\": \"2024-05-01T10:00:00Z\", \"sender\": \"a\"}\nnot json\n";
+        let err = parse_jsonl(content).unwrap_err().to_string();
+        assert!(err.contains("line 2"), "{}", err);
+    }
+
+    #[test]
+    fn load_orders_newest_first() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("chat.jsonl");
+        fs::write(
+            &path,
+            "{\"id\": 1, \"date\": \"2024-05-01T10:00:00Z\", \"sender\": \"a\"}\n\
+             {\"id\": 2, \"date\": \"2024-05-02T10:00:00Z\", \"sender\": \"b\"}\n",
+        )
+        .unwrap();
+
+        let ids: Vec<i32> = load_jsonl(&path).unwrap().iter().map(|m| m.id).collect();
+        assert_eq!(ids, vec![2, 1]);
+        assert!(load_jsonl(&dir.path().join("missing.jsonl")).is_err());
+    }
+}
@@ -0,0 +1,115 @@
+//! Uniform JSON output for scripting the CLI
+//!
+//! With the global `--json` flag, supported commands print exactly one
+//! envelope `{ "command", "ok", "data", "error" }` to stdout, where `data` is
+//! the command's own result struct.
+
+use std::fmt::Display;
+
+use serde::Serialize;
+
+use crate::error::Result;
+
+/// Result of a command run, as printed with `--json`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JsonEnvelope<T> {
+    pub command: String,
+    pub ok: bool,
+    pub data: Option<T>,
+    pub error: Option<String>,
+}
+
+impl<T> JsonEnvelope<T> {
+    pub fn success(command: &str, data: T) -> Self {
+        Self {
+            command: command.to_string(),
+            ok: true,
+            data: Some(data),
+            error: None,
+        }
+    }
+
+    pub fn failure(command: &str, error: impl Display) -> Self {
+        Self {
+            command: command.to_string(),
+            ok: false,
+            data: None,
+            error: Some(error.to_string()),
+        }
+    }
+}
+
+impl<T: Serialize> JsonEnvelope<T> {
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::digest::Digest;
+    use crate::commands::moderate::ProfanityStats;
+    use serde_json::{json, Value};
+
+    fn parse<T: Serialize>(envelope: &JsonEnvelope<T>) -> Value {
+        serde_json::from_str(&envelope.to_json().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn wraps_digest_result() {
+        let digest = Digest {
+            summary: "Обсудили релиз".to_string(),
+            highlights: vec!["Релиз в пятницу".to_string()],
+            ..Default::default()
+        };
+
+        let value = parse(&JsonEnvelope::success("digest", digest));
+        assert_eq!(
+            value,
+            json!({
+                "command": "digest",
+                "ok": true,
+                "data": {
+                    "summary": "Обсудили релиз",
+                    "highlights": ["Релиз в пятницу"],
+                    "action_items": [],
+                    "decisions": []
+                },
+                "error": null
+            })
+        );
+    }
+
+    #[test]
+    fn wraps_profanity_stats() {
+        let mut stats = ProfanityStats {
+            total_messages: 10,
+            messages_with_profanity: 2,
+            ..Default::default()
+        };
+        stats.offenders.insert("@troll".to_string(), 2);
+
+        let value = parse(&JsonEnvelope::success("profanity_stats", stats));
+        assert_eq!(value["ok"], true);
+        assert_eq!(value["data"]["total_messages"], 10);
+        assert_eq!(value["data"]["offenders"]["@troll"], 2);
+        assert_eq!(value["error"], Value::Null);
+    }
+
+    #[test]
+    fn error_envelope_has_no_data() {
+        let envelope: JsonEnvelope<Digest> =
+            JsonEnvelope::failure("digest", crate::Error::ChatNotFound("chat_x".into()));
+
+        assert_eq!(
+            parse(&envelope),
+            json!({
+                "command": "digest",
+                "ok": false,
+                "data": null,
+                "error": "Chat not found in configuration: chat_x"
+            })
+        );
+    }
+}
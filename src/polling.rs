@@ -0,0 +1,118 @@
+//! Adaptive polling interval for watch loops
+//!
+//! Quiet chats are polled less and less often, busy ones stay at the base
+//! interval: every empty poll multiplies the delay up to `max`, any new
+//! message resets it to `base`.
+
+use std::time::Duration;
+
+/// Polling cadence configuration
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PollingConfig {
+    /// Interval used right after activity
+    pub base: Duration,
+    /// Upper bound for the backed-off interval
+    pub max: Duration,
+    /// Growth factor applied after each empty poll
+    pub multiplier: f64,
+}
+
+impl Default for PollingConfig {
+    fn default() -> Self {
+        Self {
+            base: Duration::from_secs(1),
+            max: Duration::from_secs(30),
+            multiplier: 2.0,
+        }
+    }
+}
+
+impl PollingConfig {
+    /// Build a config from millisecond bounds, keeping `max >= base`
+    pub fn from_millis(base_ms: u64, max_ms: u64) -> Self {
+        let base = Duration::from_millis(base_ms.max(1));
+        Self {
+            base,
+            max: Duration::from_millis(max_ms).max(base),
+            ..Default::default()
+        }
+    }
+
+    /// Next polling interval given the current one and whether the last poll saw new messages
+    pub fn next_interval(&self, current: Duration, had_messages: bool) -> Duration {
+        if had_messages {
+            return self.base;
+        }
+
+        let grown = current.mul_f64(self.multiplier.max(1.0));
+        grown.clamp(self.base, self.max.max(self.base))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> PollingConfig {
+        PollingConfig {
+            base: Duration::from_secs(1),
+            max: Duration::from_secs(10),
+            multiplier: 2.0,
+        }
+    }
+
+    #[test]
+    fn backs_off_when_idle() {
+        let cfg = config();
+        let mut interval = cfg.base;
+        let mut seen = Vec::new();
+        for _ in 0..5 {
+            interval = cfg.next_interval(interval, false);
+            seen.push(interval.as_secs());
+        }
+        assert_eq!(seen, vec![2, 4, 8, 10, 10]);
+    }
+
+    #[test]
+    fn resets_on_activity() {
+        let cfg = config();
+        assert_eq!(
+            cfg.next_interval(Duration::from_secs(8), true),
+            Duration::from_secs(1)
+        );
+    }
+
+    #[test]
+    fn active_chat_stays_at_base() {
+        let cfg = config();
+        assert_eq!(cfg.next_interval(cfg.base, true), cfg.base);
+    }
+
+    #[test]
+    fn never_drops_below_base() {
+        let cfg = config();
+        assert_eq!(
+            cfg.next_interval(Duration::from_millis(10), false),
+            Duration::from_secs(1)
+        );
+    }
+
+    #[test]
+    fn multiplier_below_one_does_not_shrink() {
+        let cfg = PollingConfig {
+            multiplier: 0.5,
+            ..config()
+        };
+        assert_eq!(
+            cfg.next_interval(Duration::from_secs(4), false),
+            Duration::from_secs(4)
+        );
+    }
+
+    #[test]
+    fn from_millis_keeps_max_above_base() {
+        let cfg = PollingConfig::from_millis(5_000, 1_000);
+        assert_eq!(cfg.base, Duration::from_secs(5));
+        assert_eq!(cfg.max, Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,201 @@
+//! Progress reporting for long indexing and embedding jobs
+//!
+//! [`ProgressCounter`] does the processed/total/rate bookkeeping over explicit
+//! `Instant`s so it can be tested without a terminal. [`Progress`] renders it
+//! as an `indicatif` bar when stderr is a tty and as periodic log lines
+//! otherwise (CI, cron, docker logs).
+
+use std::io::IsTerminal;
+use std::time::{Duration, Instant};
+
+use indicatif::{ProgressBar, ProgressStyle};
+use tracing::info;
+
+/// How often non-tty runs log their progress
+pub const LOG_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Processed/total accounting with throughput and ETA
+#[derive(Debug, Clone)]
+pub struct ProgressCounter {
+    pub total: u64,
+    pub processed: u64,
+    started: Instant,
+    last_log: Instant,
+}
+
+impl ProgressCounter {
+    pub fn new(total: u64, now: Instant) -> Self {
+        Self {
+            total,
+            processed: 0,
+            started: now,
+            last_log: now,
+        }
+    }
+
+    /// Count `n` more items; the total grows if the estimate was too low
+    pub fn advance(&mut self, n: u64) {
+        self.processed = self.processed.saturating_add(n);
+        self.total = self.total.max(self.processed);
+    }
+
+    /// Mark the job done, shrinking the total when it stopped early
+    pub fn finish(&mut self) {
+        self.total = self.processed;
+    }
+
+    /// Items per second since the start
+    pub fn rate(&self, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(self.started).as_secs_f64();
+        if elapsed <= 0.0 {
+            return 0.0;
+        }
+        self.processed as f64 / elapsed
+    }
+
+    /// Time left at the current rate (`None` until there is a rate to go by)
+    pub fn eta(&self, now: Instant) -> Option<Duration> {
+        let rate = self.rate(now);
+        if rate <= 0.0 {
+            return None;
+        }
+        let remaining = self.total.saturating_sub(self.processed);
+        Some(Duration::from_secs_f64(remaining as f64 / rate))
+    }
+
+    /// Whether a log line is due, resetting the timer when it is
+    pub fn log_due(&mut self, now: Instant, every: Duration) -> bool {
+        if now.saturating_duration_since(self.last_log) < every {
+            return false;
+        }
+        self.last_log = now;
+        true
+    }
+
+    /// One-line summary, e.g. `120/500 (24.0/s, ETA 16s)`
+    pub fn summary(&self, now: Instant) -> String {
+        let eta = match self.eta(now) {
+            Some(eta) if self.processed < self.total => format!(", ETA {}s", eta.as_secs()),
+            _ => String::new(),
+        };
+        format!(
+            "{}/{} ({:.1}/s{})",
+            self.processed,
+            self.total,
+            self.rate(now),
+            eta
+        )
+    }
+}
+
+/// A progress bar on a terminal, periodic log lines elsewhere
+pub struct Progress {
+    label: String,
+    counter: ProgressCounter,
+    bar: Option<ProgressBar>,
+}
+
+impl Progress {
+    pub fn new(label: impl Into<String>, total: u64) -> Self {
+        let label = label.into();
+        let bar = std::io::stderr().is_terminal().then(|| {
+            let bar = ProgressBar::new(total);
+            let style = ProgressStyle::with_template(
+                "{prefix} [{bar:30}] {pos}/{len} ({per_sec}, ETA {eta})",
+            )
+            .unwrap_or_else(|_| ProgressStyle::default_bar())
+            .progress_chars("=> ");
+            bar.set_style(style);
+            bar.set_prefix(label.clone());
+            bar
+        });
+
+        Self {
+            label,
+            counter: ProgressCounter::new(total, Instant::now()),
+            bar,
+        }
+    }
+
+    pub fn inc(&mut self, n: u64) {
+        self.counter.advance(n);
+        let now = Instant::now();
+        match &self.bar {
+            Some(bar) => {
+                bar.set_length(self.counter.total);
+                bar.set_position(self.counter.processed);
+            }
+            None if self.counter.log_due(now, LOG_INTERVAL) => {
+                info!("{}: {}", self.label, self.counter.summary(now));
+            }
+            None => {}
+        }
+    }
+
+    pub fn finish(&mut self) {
+        self.counter.finish();
+        match &self.bar {
+            Some(bar) => {
+                bar.set_length(self.counter.total);
+                bar.finish();
+            }
+            None => info!(
+                "{}: done, {}",
+                self.label,
+                self.counter.summary(Instant::now())
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_processed_total_and_rate() {
+        let start = Instant::now();
+        let mut counter = ProgressCounter::new(500, start);
+        assert_eq!(counter.rate(start), 0.0);
+        assert_eq!(counter.eta(start), None);
+
+        counter.advance(100);
+        counter.advance(20);
+        let now = start + Duration::from_secs(5);
+        assert_eq!(counter.processed, 120);
+        assert_eq!(counter.rate(now), 24.0);
+        assert_eq!(counter.eta(now), Some(Duration::from_secs_f64(380.0 / 24.0)));
+        assert_eq!(counter.summary(now), "120/500 (24.0/s, ETA 15s)");
+    }
+
+    #[test]
+    fn total_follows_the_actual_count() {
+        let start = Instant::now();
+        let now = start + Duration::from_secs(2);
+
+        // More items than estimated
+        let mut counter = ProgressCounter::new(10, start);
+        counter.advance(15);
+        assert_eq!(counter.total, 15);
+        assert_eq!(counter.summary(now), "15/15 (7.5/s)");
+
+        // Stopped early (e.g. the chat has fewer messages than the limit)
+        let mut counter = ProgressCounter::new(1000, start);
+        counter.advance(40);
+        counter.finish();
+        assert_eq!(counter.total, 40);
+        assert_eq!(counter.eta(now), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn logs_at_most_once_per_interval() {
+        let start = Instant::now();
+        let mut counter = ProgressCounter::new(10, start);
+        let every = Duration::from_secs(10);
+
+        assert!(!counter.log_due(start + Duration::from_secs(3), every));
+        assert!(counter.log_due(start + Duration::from_secs(10), every));
+        assert!(!counter.log_due(start + Duration::from_secs(15), every));
+        assert!(counter.log_due(start + Duration::from_secs(21), every));
+    }
+}
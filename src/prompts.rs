@@ -2,6 +2,7 @@
 //!
 //! Промпты хранятся в каталоге `prompts/` в корне проекта.
 
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 use crate::{Error, Result};
@@ -69,6 +70,42 @@ pub fn prompts_dir() -> PathBuf {
     PathBuf::from("prompts")
 }
 
+/// Подставить переменные `{{name}}` в шаблон.
+///
+/// Пробелы внутри скобок игнорируются (`{{ name }}`). Неизвестная переменная
+/// в строгом режиме — ошибка, иначе подставляется пустая строка.
+pub fn render_template(
+    template: &str,
+    vars: &HashMap<&str, String>,
+    strict: bool,
+) -> Result<String> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+
+        let name = rest[start + 2..start + 2 + len].trim();
+        match vars.get(name) {
+            Some(value) => out.push_str(value),
+            None if strict => {
+                return Err(Error::InvalidArgument(format!(
+                    "Неизвестная переменная шаблона: {{{{{}}}}}",
+                    name
+                )))
+            }
+            None => {}
+        }
+        rest = &rest[start + 2 + len + 2..];
+    }
+
+    out.push_str(rest);
+    Ok(out)
+}
+
 /// Список всех доступных промптов.
 pub fn list_prompts() -> Vec<Prompt> {
     vec![
@@ -155,6 +192,42 @@ mod tests {
         assert!(debug_str.contains("Calculator"));
     }
 
+    fn vars() -> HashMap<&'static str, String> {
+        HashMap::from([
+            ("chat_title", "Rust Chat".to_string()),
+            ("first_name", "Анна".to_string()),
+        ])
+    }
+
+    #[test]
+    fn test_render_template_substitutes_variables() {
+        let rendered = render_template(
+            "Привет, {{first_name}}! Это {{ chat_title }}.",
+            &vars(),
+            true,
+        )
+        .unwrap();
+        assert_eq!(rendered, "Привет, Анна! Это Rust Chat.");
+    }
+
+    #[test]
+    fn test_render_template_missing_variable_lenient() {
+        let rendered = render_template("Дата: {{date}}.", &vars(), false).unwrap();
+        assert_eq!(rendered, "Дата: .");
+    }
+
+    #[test]
+    fn test_render_template_missing_variable_strict() {
+        let err = render_template("Дата: {{date}}", &vars(), true).unwrap_err();
+        assert!(err.to_string().contains("{{date}}"));
+    }
+
+    #[test]
+    fn test_render_template_keeps_unclosed_braces() {
+        let rendered = render_template("{{first_name}} {{oops", &vars(), true).unwrap();
+        assert_eq!(rendered, "Анна {{oops");
+    }
+
     #[test]
     fn test_list_prompts_contains_all_variants() {
         let prompts = list_prompts();
@@ -0,0 +1,252 @@
+//! Quiet hours for outbound messages
+//!
+//! Scheduled sends and (optionally) bot replies shouldn't wake recipients up
+//! at night. A window like `22:00-08:00` in a fixed UTC offset defers outbound
+//! messages until the window closes instead of dropping them.
+
+use chrono::{DateTime, Duration, FixedOffset, NaiveTime, TimeZone, Utc};
+
+use crate::error::{Error, Result};
+
+/// Quiet window in local time of the recipients
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuietHours {
+    /// Window start (inclusive)
+    pub start: NaiveTime,
+    /// Window end (exclusive); earlier than `start` for windows spanning midnight
+    pub end: NaiveTime,
+    pub offset: FixedOffset,
+}
+
+impl QuietHours {
+    pub fn new(start: NaiveTime, end: NaiveTime, offset: FixedOffset) -> Self {
+        Self { start, end, offset }
+    }
+
+    /// Parse `HH:MM-HH:MM` and an optional UTC offset (`+03:00`, `UTC-5`; UTC by default)
+    pub fn parse(window: &str, timezone: Option<&str>) -> Result<Self> {
+        let invalid = || {
+            Error::InvalidArgument(format!(
+                "Invalid quiet hours '{}', expected HH:MM-HH:MM",
+                window
+            ))
+        };
+        let (start, end) = window.split_once('-').ok_or_else(invalid)?;
+        let parse_time =
+            |s: &str| NaiveTime::parse_from_str(s.trim(), "%H:%M").map_err(|_| invalid());
+
+        let offset = match timezone {
+            Some(tz) => parse_offset(tz)?,
+            None => utc_offset(),
+        };
+        Ok(Self::new(parse_time(start)?, parse_time(end)?, offset))
+    }
+
+    /// Quiet hours from `QUIET_HOURS` and `QUIET_HOURS_TZ`, if configured
+    pub fn from_env() -> Result<Option<Self>> {
+        match std::env::var("QUIET_HOURS") {
+            Ok(window) if !window.trim().is_empty() => {
+                let timezone = std::env::var("QUIET_HOURS_TZ").ok();
+                Self::parse(&window, timezone.as_deref()).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Quiet hours from a CLI window, taking the timezone from `QUIET_HOURS_TZ` if not given
+    pub fn from_cli(window: &str, timezone: Option<&str>) -> Result<Self> {
+        let env_tz = std::env::var("QUIET_HOURS_TZ").ok();
+        Self::parse(window, timezone.or(env_tz.as_deref()))
+    }
+
+    /// Whether `now` falls inside the quiet window
+    pub fn is_quiet(&self, now: DateTime<Utc>) -> bool {
+        let time = now.with_timezone(&self.offset).time();
+        if self.start == self.end {
+            false
+        } else if self.start < self.end {
+            time >= self.start && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+
+    /// Earliest moment at or after `now` when sending is allowed
+    pub fn next_allowed(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        if !self.is_quiet(now) {
+            return now;
+        }
+
+        let local = now.with_timezone(&self.offset);
+        let mut date = local.date_naive();
+        // Evening part of a window spanning midnight ends tomorrow
+        if self.start > self.end && local.time() >= self.start {
+            date += Duration::days(1);
+        }
+
+        self.offset
+            .from_local_datetime(&date.and_time(self.end))
+            .single()
+            .map(|end| end.with_timezone(&Utc))
+            .unwrap_or(now)
+    }
+
+    /// How long an outbound message must wait at `now`
+    pub fn delay(&self, now: DateTime<Utc>) -> std::time::Duration {
+        (self.next_allowed(now) - now).to_std().unwrap_or_default()
+    }
+
+    /// Sleep until the quiet window is over (returns immediately outside of it)
+    pub async fn wait(&self) {
+        let delay = self.delay(Utc::now());
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Next allowed time formatted in the window's timezone
+    pub fn format_next_allowed(&self, now: DateTime<Utc>) -> String {
+        self.next_allowed(now)
+            .with_timezone(&self.offset)
+            .format("%d.%m.%Y %H:%M %:z")
+            .to_string()
+    }
+}
+
+fn utc_offset() -> FixedOffset {
+    FixedOffset::east_opt(0).expect("zero offset is valid")
+}
+
+/// Parse a fixed UTC offset: `UTC`, `Z`, `+03:00`, `-0530`, `UTC+3`, `GMT-05:00`
+pub fn parse_offset(timezone: &str) -> Result<FixedOffset> {
+    let invalid = || {
+        Error::InvalidArgument(format!(
+            "Invalid timezone '{}', expected a UTC offset like +03:00",
+            timezone
+        ))
+    };
+
+    let tz = timezone.trim();
+    let tz = tz
+        .strip_prefix("UTC")
+        .or_else(|| tz.strip_prefix("GMT"))
+        .unwrap_or(tz);
+    if tz.is_empty() || tz == "Z" {
+        return Ok(utc_offset());
+    }
+
+    let (sign, rest) = match tz.as_bytes()[0] {
+        b'+' => (1, &tz[1..]),
+        b'-' => (-1, &tz[1..]),
+        _ => return Err(invalid()),
+    };
+    let (hours, minutes) = match rest.split_once(':') {
+        Some((h, m)) => (h, m),
+        None if rest.len() == 4 => rest.split_at(2),
+        None => (rest, "0"),
+    };
+    let hours: i32 = hours.parse().map_err(|_| invalid())?;
+    let minutes: i32 = minutes.parse().map_err(|_| invalid())?;
+    if hours > 14 || minutes >= 60 {
+        return Err(invalid());
+    }
+
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60)).ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 6, day, hour, minute, 0).unwrap()
+    }
+
+    fn night() -> QuietHours {
+        QuietHours::parse("22:00-08:00", None).unwrap()
+    }
+
+    #[test]
+    fn window_spanning_midnight() {
+        let quiet = night();
+        assert!(quiet.is_quiet(utc(1, 22, 0)));
+        assert!(quiet.is_quiet(utc(1, 23, 59)));
+        assert!(quiet.is_quiet(utc(2, 3, 0)));
+        assert!(!quiet.is_quiet(utc(2, 8, 0)));
+        assert!(!quiet.is_quiet(utc(2, 12, 0)));
+        assert!(!quiet.is_quiet(utc(1, 21, 59)));
+    }
+
+    #[test]
+    fn next_allowed_crosses_midnight() {
+        let quiet = night();
+        // Before midnight: wait until tomorrow morning
+        assert_eq!(quiet.next_allowed(utc(1, 23, 0)), utc(2, 8, 0));
+        // After midnight: wait until this morning
+        assert_eq!(quiet.next_allowed(utc(2, 3, 0)), utc(2, 8, 0));
+        // Outside the window: send right away
+        assert_eq!(quiet.next_allowed(utc(2, 9, 0)), utc(2, 9, 0));
+        assert_eq!(
+            quiet.delay(utc(2, 7, 30)),
+            std::time::Duration::from_secs(1800)
+        );
+    }
+
+    #[test]
+    fn same_day_window() {
+        let quiet = QuietHours::parse("13:00-15:00", None).unwrap();
+        assert!(!quiet.is_quiet(utc(1, 12, 59)));
+        assert!(quiet.is_quiet(utc(1, 13, 0)));
+        assert!(!quiet.is_quiet(utc(1, 15, 0)));
+        assert_eq!(quiet.next_allowed(utc(1, 14, 0)), utc(1, 15, 0));
+    }
+
+    #[test]
+    fn timezone_shifts_the_window() {
+        // 22:00-08:00 Moscow time is 19:00-05:00 UTC
+        let quiet = QuietHours::parse("22:00-08:00", Some("+03:00")).unwrap();
+        assert!(quiet.is_quiet(utc(1, 19, 30)));
+        assert!(!quiet.is_quiet(utc(1, 18, 59)));
+        assert!(!quiet.is_quiet(utc(2, 5, 0)));
+        // 23:00 UTC is 02:00 local on the next day, so the window ends 05:00 UTC
+        assert_eq!(quiet.next_allowed(utc(1, 23, 0)), utc(2, 5, 0));
+        // 20:00 UTC is 23:00 local, still the same local day
+        assert_eq!(quiet.next_allowed(utc(1, 20, 0)), utc(2, 5, 0));
+    }
+
+    #[test]
+    fn negative_offset_window() {
+        let quiet = QuietHours::parse("22:00-07:00", Some("UTC-5")).unwrap();
+        // 02:00 UTC is 21:00 local: allowed
+        assert!(!quiet.is_quiet(utc(2, 2, 0)));
+        // 04:00 UTC is 23:00 local: quiet until 07:00 local = 12:00 UTC
+        assert_eq!(quiet.next_allowed(utc(2, 4, 0)), utc(2, 12, 0));
+    }
+
+    #[test]
+    fn empty_window_is_never_quiet() {
+        let quiet = QuietHours::parse("00:00-00:00", None).unwrap();
+        assert!(!quiet.is_quiet(utc(1, 0, 0)));
+        assert_eq!(quiet.next_allowed(utc(1, 0, 0)), utc(1, 0, 0));
+    }
+
+    #[test]
+    fn parses_offsets() {
+        let east = |secs| FixedOffset::east_opt(secs).unwrap();
+        assert_eq!(parse_offset("UTC").unwrap(), east(0));
+        assert_eq!(parse_offset("Z").unwrap(), east(0));
+        assert_eq!(parse_offset("+03:00").unwrap(), east(3 * 3600));
+        assert_eq!(parse_offset("-0530").unwrap(), east(-(5 * 3600 + 30 * 60)));
+        assert_eq!(parse_offset("UTC+3").unwrap(), east(3 * 3600));
+        assert_eq!(parse_offset("GMT-05:00").unwrap(), east(-5 * 3600));
+        assert!(parse_offset("Europe/Moscow").is_err());
+        assert!(parse_offset("+25").is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_windows() {
+        assert!(QuietHours::parse("22:00", None).is_err());
+        assert!(QuietHours::parse("25:00-08:00", None).is_err());
+        assert!(QuietHours::parse("22:00-08:00", Some("nowhere")).is_err());
+    }
+}
@@ -1,7 +1,41 @@
 //! Reaction handling utilities
 
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::Duration;
+
+use grammers_client::types::peer::Peer;
 use grammers_client::types::Message;
+use grammers_client::Client;
 use grammers_tl_types as tl;
+use serde::Serialize;
+use tracing::warn;
+
+use crate::chat::{flood_wait_seconds, input_peer};
+use crate::error::Result;
+
+/// Page size for `messages.getMessageReactionsList`
+const REACTORS_PAGE_SIZE: i32 = 100;
+
+/// How many FLOOD_WAIT errors a single reaction sleeps through before giving up
+pub const MAX_REACTION_FLOOD_RETRIES: u32 = 3;
+
+/// A single user's reaction on a message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Reactor {
+    pub user_id: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    pub name: String,
+    pub emoji: String,
+}
+
+/// Display data for a reacting user, keyed by id in [`user_profiles`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReactorProfile {
+    pub username: Option<String>,
+    pub name: String,
+}
 
 /// Extract reaction count and emoji list from a message's reactions
 pub fn extract_reactions(reactions: Option<&tl::enums::MessageReactions>) -> (i32, String) {
@@ -18,35 +52,341 @@ pub fn extract_reactions(reactions: Option<&tl::enums::MessageReactions>) -> (i3
         let tl::enums::ReactionCount::Count(count) = result;
         total_count += count.count;
 
-        match &count.reaction {
-            tl::enums::Reaction::Emoji(emoji) => emojis.push(emoji.emoticon.clone()),
-            tl::enums::Reaction::CustomEmoji(custom) => {
-                emojis.push(format!("CustomEmoji({})", custom.document_id));
-            }
-            tl::enums::Reaction::Paid => emojis.push("💎".to_string()),
-            tl::enums::Reaction::Empty => {}
+        if let Some(label) = reaction_label(&count.reaction) {
+            emojis.push(label);
         }
     }
 
     (total_count, emojis.join(""))
 }
 
+/// Text form of a reaction (`None` for the empty reaction)
+pub fn reaction_label(reaction: &tl::enums::Reaction) -> Option<String> {
+    match reaction {
+        tl::enums::Reaction::Emoji(emoji) => Some(emoji.emoticon.clone()),
+        tl::enums::Reaction::CustomEmoji(custom) => {
+            Some(format!("CustomEmoji({})", custom.document_id))
+        }
+        tl::enums::Reaction::Paid => Some("💎".to_string()),
+        tl::enums::Reaction::Empty => None,
+    }
+}
+
+/// Key for per-emoji counts: the emoji itself, or the document id of a custom emoji
+fn reaction_key(reaction: &tl::enums::Reaction) -> Option<String> {
+    match reaction {
+        tl::enums::Reaction::CustomEmoji(custom) => Some(custom.document_id.to_string()),
+        other => reaction_label(other),
+    }
+}
+
+/// Per-emoji reaction counts (custom emoji keyed by document id)
+pub fn reaction_counts(reactions: Option<&tl::enums::MessageReactions>) -> HashMap<String, i32> {
+    let mut counts = HashMap::new();
+    let Some(tl::enums::MessageReactions::Reactions(reactions)) = reactions else {
+        return counts;
+    };
+
+    for result in &reactions.results {
+        let tl::enums::ReactionCount::Count(count) = result;
+        if let Some(key) = reaction_key(&count.reaction) {
+            *counts.entry(key).or_insert(0) += count.count;
+        }
+    }
+    counts
+}
+
+/// Per-emoji reaction counts of a message
+pub fn message_reaction_counts(msg: &Message) -> HashMap<String, i32> {
+    match &msg.raw {
+        tl::enums::Message::Message(m) => reaction_counts(m.reactions.as_ref()),
+        _ => HashMap::new(),
+    }
+}
+
+/// The most used reaction (ties go to the smaller key, for stable output)
+pub fn top_reaction(counts: &HashMap<String, i32>) -> Option<(&str, i32)> {
+    counts
+        .iter()
+        .filter(|(_, count)| **count > 0)
+        .max_by(|(a_key, a), (b_key, b)| a.cmp(b).then_with(|| b_key.cmp(a_key)))
+        .map(|(key, count)| (key.as_str(), *count))
+}
+
 /// Count total reactions on a message
 pub fn count_reactions(msg: &Message) -> i32 {
-    // Access raw message reactions
-    let raw = &msg.raw;
-    match raw {
-        grammers_tl_types::enums::Message::Message(m) => {
-            let (count, _) = extract_reactions(m.reactions.as_ref());
-            count
+    message_reaction_counts(msg).values().sum()
+}
+
+/// Emotional tone of a reaction, used to weight engagement
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReactionSentiment {
+    Positive,
+    Neutral,
+    Negative,
+}
+
+const POSITIVE_REACTIONS: &[&str] = &[
+    "👍",
+    "❤",
+    "🔥",
+    "🥰",
+    "👏",
+    "😁",
+    "🎉",
+    "🤩",
+    "🙏",
+    "👌",
+    "😍",
+    "❤‍🔥",
+    "💯",
+    "🤣",
+    "⚡",
+    "🏆",
+    "💎",
+    "🤝",
+    "🫡",
+    "😎",
+];
+const NEGATIVE_REACTIONS: &[&str] = &[
+    "👎", "💩", "🤮", "😡", "🤬", "😢", "😭", "🤡", "🥱", "💔", "😱",
+];
+
+/// Classify a reaction label (as returned by [`reaction_label`])
+pub fn reaction_sentiment(label: &str) -> ReactionSentiment {
+    let normalized = label.trim_end_matches('\u{fe0f}');
+    if POSITIVE_REACTIONS.contains(&normalized) {
+        ReactionSentiment::Positive
+    } else if NEGATIVE_REACTIONS.contains(&normalized) {
+        ReactionSentiment::Negative
+    } else {
+        ReactionSentiment::Neutral
+    }
+}
+
+/// Per-reaction counts of a message's reactions, in Telegram's order
+pub fn reaction_breakdown(reactions: Option<&tl::enums::MessageReactions>) -> Vec<(String, i32)> {
+    let Some(tl::enums::MessageReactions::Reactions(reactions)) = reactions else {
+        return Vec::new();
+    };
+
+    reactions
+        .results
+        .iter()
+        .filter_map(|result| {
+            let tl::enums::ReactionCount::Count(count) = result;
+            reaction_label(&count.reaction).map(|label| (label, count.count))
+        })
+        .collect()
+}
+
+/// Build an id → profile lookup from the users attached to a reactions list
+pub fn user_profiles(users: &[tl::enums::User]) -> HashMap<i64, ReactorProfile> {
+    users
+        .iter()
+        .filter_map(|user| match user {
+            tl::enums::User::User(u) => {
+                let name = [u.first_name.as_deref(), u.last_name.as_deref()]
+                    .into_iter()
+                    .flatten()
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                Some((
+                    u.id,
+                    ReactorProfile {
+                        username: u.username.clone(),
+                        name,
+                    },
+                ))
+            }
+            tl::enums::User::Empty(_) => None,
+        })
+        .collect()
+}
+
+/// Pair each peer reaction with the reacting user's profile
+pub fn assemble_reactors(
+    reactions: &[tl::enums::MessagePeerReaction],
+    profiles: &HashMap<i64, ReactorProfile>,
+) -> Vec<Reactor> {
+    reactions
+        .iter()
+        .filter_map(|reaction| {
+            let tl::enums::MessagePeerReaction::Reaction(reaction) = reaction;
+            let emoji = reaction_label(&reaction.reaction)?;
+            let user_id = match &reaction.peer_id {
+                tl::enums::Peer::User(p) => p.user_id,
+                tl::enums::Peer::Chat(p) => p.chat_id,
+                tl::enums::Peer::Channel(p) => p.channel_id,
+            };
+            let profile = profiles.get(&user_id).cloned().unwrap_or_default();
+            let name = if profile.name.is_empty() {
+                "Unknown".to_string()
+            } else {
+                profile.name
+            };
+
+            Some(Reactor {
+                user_id,
+                username: profile.username,
+                name,
+                emoji,
+            })
+        })
+        .collect()
+}
+
+/// Whether Telegram lets us list who reacted to this message
+pub fn can_list_reactors(msg: &Message) -> bool {
+    match &msg.raw {
+        tl::enums::Message::Message(m) => match &m.reactions {
+            Some(tl::enums::MessageReactions::Reactions(r)) => {
+                r.can_see_list && !r.results.is_empty()
+            }
+            None => false,
+        },
+        _ => false,
+    }
+}
+
+/// Fetch who reacted to a message and with which emoji.
+///
+/// Returns `Ok(None)` when the reactor list isn't accessible (broadcast
+/// channels, hidden lists, missing rights) instead of failing the export.
+/// An error after the first page keeps the reactors fetched so far.
+pub async fn fetch_reactors(
+    client: &Client,
+    chat: &Peer,
+    msg: &Message,
+) -> Result<Option<Vec<Reactor>>> {
+    if !can_list_reactors(msg) {
+        return Ok(None);
+    }
+
+    let peer = input_peer(chat);
+    let reactors = collect_reactor_pages(msg.id(), |offset| {
+        let request = tl::functions::messages::GetMessageReactionsList {
+            peer: peer.clone(),
+            id: msg.id(),
+            reaction: None,
+            offset,
+            limit: REACTORS_PAGE_SIZE,
+        };
+
+        async move {
+            let tl::enums::messages::MessageReactionsList::List(page) =
+                client.invoke(&request).await.map_err(|e| e.to_string())?;
+
+            let profiles = user_profiles(&page.users);
+            Ok(ReactorPage {
+                reactors: assemble_reactors(&page.reactions, &profiles),
+                // An empty page ends the list even if an offset came back
+                next_offset: page.next_offset.filter(|_| !page.reactions.is_empty()),
+            })
+        }
+    })
+    .await;
+
+    Ok(reactors)
+}
+
+/// One page of a reactor list
+struct ReactorPage {
+    reactors: Vec<Reactor>,
+    next_offset: Option<String>,
+}
+
+/// Walk reactor pages until the last one; `None` if even the first page fails
+async fn collect_reactor_pages<F, Fut>(msg_id: i32, mut fetch_page: F) -> Option<Vec<Reactor>>
+where
+    F: FnMut(Option<String>) -> Fut,
+    Fut: Future<Output = std::result::Result<ReactorPage, String>>,
+{
+    let mut reactors = Vec::new();
+    let mut offset: Option<String> = None;
+
+    loop {
+        let first_page = offset.is_none();
+        let page = match fetch_page(offset.take()).await {
+            Ok(page) => page,
+            Err(e) if first_page => {
+                warn!("Reactor list unavailable for message {}: {}", msg_id, e);
+                return None;
+            }
+            Err(e) => {
+                warn!(
+                    "Reactor list for message {} cut short after {} reactors: {}",
+                    msg_id,
+                    reactors.len(),
+                    e
+                );
+                break;
+            }
+        };
+
+        reactors.extend(page.reactors);
+        match page.next_offset {
+            Some(next) => offset = Some(next),
+            None => break,
+        }
+    }
+
+    Some(reactors)
+}
+
+/// FLOOD_WAIT handling shared by the bulk reaction commands
+///
+/// Sleeps for the duration Telegram asks for and retries the same request,
+/// up to `max_retries` times per request. `flood_waits` counts every wait so
+/// command summaries can show it when tuning `delay_ms`.
+#[derive(Debug, Clone)]
+pub struct FloodRetry {
+    pub max_retries: u32,
+    pub flood_waits: usize,
+}
+
+impl Default for FloodRetry {
+    fn default() -> Self {
+        Self {
+            max_retries: MAX_REACTION_FLOOD_RETRIES,
+            flood_waits: 0,
+        }
+    }
+}
+
+impl FloodRetry {
+    /// Run `request`, retrying it after each FLOOD_WAIT within the retry cap
+    pub async fn run<T, F, Fut>(&mut self, mut request: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut retries = 0;
+        loop {
+            let err = match request().await {
+                Ok(value) => return Ok(value),
+                Err(err) => err,
+            };
+            match flood_wait_seconds(&err.to_string()) {
+                Some(secs) if retries < self.max_retries => {
+                    retries += 1;
+                    self.flood_waits += 1;
+                    warn!(
+                        "Flood wait {}s, retrying reaction ({}/{})",
+                        secs, retries, self.max_retries
+                    );
+                    tokio::time::sleep(Duration::from_secs(secs)).await;
+                }
+                _ => return Err(err),
+            }
         }
-        _ => 0,
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::Error;
     use grammers_tl_types as tl;
 
     fn reactions_without_empty() -> tl::enums::MessageReactions {
@@ -82,6 +422,57 @@ mod tests {
         tl::enums::MessageReactions::Reactions(reactions)
     }
 
+    #[test]
+    fn breaks_down_reactions_by_label() {
+        let breakdown = reaction_breakdown(Some(&reactions_without_empty()));
+        assert_eq!(
+            breakdown,
+            vec![
+                ("🔥".to_string(), 2),
+                ("CustomEmoji(42)".to_string(), 3),
+                ("💎".to_string(), 1),
+            ]
+        );
+        assert!(reaction_breakdown(None).is_empty());
+    }
+
+    #[test]
+    fn counts_reactions_per_emoji() {
+        let counts = reaction_counts(Some(&reactions_without_empty()));
+        assert_eq!(
+            counts,
+            HashMap::from([
+                ("🔥".to_string(), 2),
+                ("42".to_string(), 3),
+                ("💎".to_string(), 1),
+            ])
+        );
+        assert_eq!(counts.values().sum::<i32>(), 6);
+        assert!(reaction_counts(None).is_empty());
+    }
+
+    #[test]
+    fn picks_the_most_common_reaction() {
+        let counts = reaction_counts(Some(&reactions_without_empty()));
+        assert_eq!(top_reaction(&counts), Some(("42", 3)));
+
+        let tied = HashMap::from([("🔥".to_string(), 2), ("👍".to_string(), 2)]);
+        assert_eq!(top_reaction(&tied), Some(("👍", 2)));
+        assert_eq!(top_reaction(&HashMap::new()), None);
+    }
+
+    #[test]
+    fn classifies_reaction_sentiment() {
+        assert_eq!(reaction_sentiment("🔥"), ReactionSentiment::Positive);
+        assert_eq!(reaction_sentiment("❤️"), ReactionSentiment::Positive);
+        assert_eq!(reaction_sentiment("👎"), ReactionSentiment::Negative);
+        assert_eq!(reaction_sentiment("🤔"), ReactionSentiment::Neutral);
+        assert_eq!(
+            reaction_sentiment("CustomEmoji(42)"),
+            ReactionSentiment::Neutral
+        );
+    }
+
     #[test]
     fn returns_zero_for_absent_reactions() {
         let (count, emojis) = extract_reactions(None);
@@ -161,4 +552,209 @@ mod tests {
         assert_eq!(count, 4);
         assert!(emojis.is_empty());
     }
+
+    fn peer_reaction(
+        user_id: i64,
+        reaction: tl::enums::Reaction,
+    ) -> tl::enums::MessagePeerReaction {
+        tl::enums::MessagePeerReaction::Reaction(tl::types::MessagePeerReaction {
+            big: false,
+            unread: false,
+            my: false,
+            peer_id: tl::enums::Peer::User(tl::types::PeerUser { user_id }),
+            date: 1_700_000_000,
+            reaction,
+        })
+    }
+
+    fn emoji(emoticon: &str) -> tl::enums::Reaction {
+        tl::enums::Reaction::Emoji(tl::types::ReactionEmoji {
+            emoticon: emoticon.into(),
+        })
+    }
+
+    #[test]
+    fn assembles_reactor_emoji_mapping() {
+        let reactions = vec![
+            peer_reaction(1, emoji("🔥")),
+            peer_reaction(2, emoji("👍")),
+            peer_reaction(1, tl::enums::Reaction::Paid),
+        ];
+        let profiles = HashMap::from([
+            (
+                1,
+                ReactorProfile {
+                    username: Some("alice".into()),
+                    name: "Alice Smith".into(),
+                },
+            ),
+            (
+                2,
+                ReactorProfile {
+                    username: None,
+                    name: "Bob".into(),
+                },
+            ),
+        ]);
+
+        let reactors = assemble_reactors(&reactions, &profiles);
+
+        assert_eq!(reactors.len(), 3);
+        assert_eq!(reactors[0].user_id, 1);
+        assert_eq!(reactors[0].username.as_deref(), Some("alice"));
+        assert_eq!(reactors[0].emoji, "🔥");
+        assert_eq!(reactors[1].name, "Bob");
+        assert_eq!(reactors[1].emoji, "👍");
+        assert_eq!(reactors[2].emoji, "💎");
+    }
+
+    #[test]
+    fn unknown_reactor_gets_placeholder_name() {
+        let reactions = vec![peer_reaction(99, emoji("❤️"))];
+        let reactors = assemble_reactors(&reactions, &HashMap::new());
+
+        assert_eq!(reactors.len(), 1);
+        assert_eq!(reactors[0].user_id, 99);
+        assert_eq!(reactors[0].name, "Unknown");
+        assert!(reactors[0].username.is_none());
+    }
+
+    #[test]
+    fn skips_empty_reactions_in_reactor_list() {
+        let reactions = vec![
+            peer_reaction(1, tl::enums::Reaction::Empty),
+            peer_reaction(2, emoji("👌")),
+        ];
+        let reactors = assemble_reactors(&reactions, &HashMap::new());
+
+        assert_eq!(reactors.len(), 1);
+        assert_eq!(reactors[0].user_id, 2);
+    }
+
+    #[test]
+    fn reactor_serializes_without_missing_username() {
+        let reactor = Reactor {
+            user_id: 5,
+            username: None,
+            name: "Eve".into(),
+            emoji: "🎉".into(),
+        };
+        let json = serde_json::to_value(&reactor).unwrap();
+        assert_eq!(json["user_id"], 5);
+        assert!(json.get("username").is_none());
+    }
+
+    #[tokio::test]
+    async fn flood_retry_repeats_the_request_after_a_wait() {
+        let mut flood = FloodRetry::default();
+        let mut calls = 0;
+        let result = flood
+            .run(|| {
+                calls += 1;
+                let attempt = calls;
+                async move {
+                    if attempt < 3 {
+                        Err(Error::TelegramError(
+                            "rpc error 420: FLOOD_WAIT_0".to_string(),
+                        ))
+                    } else {
+                        Ok(attempt)
+                    }
+                }
+            })
+            .await;
+
+        assert_eq!(result.unwrap(), 3);
+        assert_eq!(flood.flood_waits, 2);
+    }
+
+    #[tokio::test]
+    async fn flood_retry_gives_up_after_the_cap() {
+        let mut flood = FloodRetry {
+            max_retries: 2,
+            ..Default::default()
+        };
+        let mut calls = 0;
+        let result: Result<()> = flood
+            .run(|| {
+                calls += 1;
+                async { Err(Error::TelegramError("FLOOD_WAIT_0".to_string())) }
+            })
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(calls, 3);
+        assert_eq!(flood.flood_waits, 2);
+    }
+
+    #[tokio::test]
+    async fn flood_retry_passes_other_errors_through() {
+        let mut flood = FloodRetry::default();
+        let mut calls = 0;
+        let result: Result<()> = flood
+            .run(|| {
+                calls += 1;
+                async { Err(Error::TelegramError("REACTION_INVALID".to_string())) }
+            })
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+        assert_eq!(flood.flood_waits, 0);
+    }
+
+    fn reactor(user_id: i64) -> Reactor {
+        Reactor {
+            user_id,
+            username: None,
+            name: format!("User {}", user_id),
+            emoji: "👍".to_string(),
+        }
+    }
+
+    /// Scripted pages keyed by the offset they are requested with
+    fn pages(
+        script: Vec<std::result::Result<ReactorPage, String>>,
+    ) -> impl FnMut(Option<String>) -> std::future::Ready<std::result::Result<ReactorPage, String>>
+    {
+        let mut script = script.into_iter();
+        move |_offset| std::future::ready(script.next().expect("unexpected page request"))
+    }
+
+    #[tokio::test]
+    async fn reactor_pages_follow_offsets() {
+        let script = vec![
+            Ok(ReactorPage {
+                reactors: vec![reactor(1), reactor(2)],
+                next_offset: Some("2".to_string()),
+            }),
+            Ok(ReactorPage {
+                reactors: vec![reactor(3)],
+                next_offset: None,
+            }),
+        ];
+
+        let reactors = collect_reactor_pages(7, pages(script)).await.unwrap();
+        assert_eq!(reactors, vec![reactor(1), reactor(2), reactor(3)]);
+    }
+
+    #[tokio::test]
+    async fn reactor_pages_keep_partial_list_on_later_error() {
+        let script = vec![
+            Ok(ReactorPage {
+                reactors: vec![reactor(1), reactor(2)],
+                next_offset: Some("2".to_string()),
+            }),
+            Err("FLOOD_WAIT_30".to_string()),
+        ];
+
+        let reactors = collect_reactor_pages(7, pages(script)).await;
+        assert_eq!(reactors, Some(vec![reactor(1), reactor(2)]));
+    }
+
+    #[tokio::test]
+    async fn reactor_pages_unavailable_when_first_page_fails() {
+        let script = vec![Err("CHAT_ADMIN_REQUIRED".to_string())];
+        assert_eq!(collect_reactor_pages(7, pages(script)).await, None);
+    }
 }
@@ -0,0 +1,404 @@
+//! Bulk username resolution
+//!
+//! Commands that work with lists of usernames (CRM contacts, hunt exclusions)
+//! resolve them through [`resolve_users`]: names are normalized and
+//! deduplicated, answers (including "not found") are cached, lookups go out in
+//! small batches with a pause in between, and FLOOD_WAIT errors are slept
+//! through. A username that can't be resolved ends up in the report instead of
+//! failing the whole command.
+
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::time::Duration;
+
+use grammers_client::types::peer::Peer;
+use grammers_client::Client;
+use tracing::warn;
+
+use crate::chat::{flood_wait_seconds, MAX_FLOOD_RETRIES};
+use crate::error::{Error, Result};
+
+/// Lookups sent before pausing
+pub const DEFAULT_BATCH_SIZE: usize = 20;
+
+/// Pause between batches
+pub const DEFAULT_BATCH_DELAY: Duration = Duration::from_secs(2);
+
+/// Looks up a single username, `Ok(None)` when it doesn't exist.
+///
+/// Implemented for the Telegram client; tests plug in a scripted resolver.
+pub trait UsernameResolver {
+    fn resolve_username(&self, username: &str) -> impl Future<Output = Result<Option<i64>>> + Send;
+}
+
+impl UsernameResolver for Client {
+    async fn resolve_username(&self, username: &str) -> Result<Option<i64>> {
+        let peer = Client::resolve_username(self, username)
+            .await
+            .map_err(|e| Error::TelegramError(e.to_string()))?;
+        Ok(peer.map(|peer| match peer {
+            Peer::User(user) => user.raw.id(),
+            Peer::Channel(channel) => channel.raw.id,
+            Peer::Group(group) => match &group.raw {
+                grammers_tl_types::enums::Chat::Empty(c) => c.id,
+                grammers_tl_types::enums::Chat::Chat(c) => c.id,
+                grammers_tl_types::enums::Chat::Forbidden(c) => c.id,
+                grammers_tl_types::enums::Chat::Channel(c) => c.id,
+                grammers_tl_types::enums::Chat::ChannelForbidden(c) => c.id,
+            },
+        }))
+    }
+}
+
+/// Outcome for one username
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResolvedUser {
+    pub id: Option<i64>,
+    pub resolved: bool,
+}
+
+impl ResolvedUser {
+    fn found(id: i64) -> Self {
+        Self {
+            id: Some(id),
+            resolved: true,
+        }
+    }
+
+    fn missing() -> Self {
+        Self {
+            id: None,
+            resolved: false,
+        }
+    }
+}
+
+/// Answers from earlier lookups, keyed by normalized username.
+///
+/// Reuse one cache across calls (e.g. when scanning several chats) to avoid
+/// asking Telegram about the same name twice.
+#[derive(Debug, Clone, Default)]
+pub struct UserCache {
+    entries: HashMap<String, Option<i64>>,
+}
+
+impl UserCache {
+    pub fn get(&self, username: &str) -> Option<Option<i64>> {
+        self.entries.get(&normalize_username(username)).copied()
+    }
+
+    pub fn insert(&mut self, username: &str, id: Option<i64>) {
+        self.entries.insert(normalize_username(username), id);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Batching knobs for [`resolve_with`]
+#[derive(Debug, Clone, Copy)]
+pub struct ResolveOptions {
+    pub batch_size: usize,
+    pub batch_delay: Duration,
+}
+
+impl Default for ResolveOptions {
+    fn default() -> Self {
+        Self {
+            batch_size: DEFAULT_BATCH_SIZE,
+            batch_delay: DEFAULT_BATCH_DELAY,
+        }
+    }
+}
+
+/// Result of a bulk lookup
+#[derive(Debug, Clone, Default)]
+pub struct ResolveReport {
+    /// Normalized username → outcome
+    pub users: BTreeMap<String, ResolvedUser>,
+    /// Usernames that failed with an error (not just "not found") → error text
+    pub errors: BTreeMap<String, String>,
+    /// Answers served from the cache
+    pub cache_hits: usize,
+    /// Requests actually sent
+    pub lookups: usize,
+}
+
+impl ResolveReport {
+    /// Id of a resolved username
+    pub fn id(&self, username: &str) -> Option<i64> {
+        self.users
+            .get(&normalize_username(username))
+            .and_then(|user| user.id)
+    }
+
+    /// Usernames that couldn't be resolved, sorted
+    pub fn unresolved(&self) -> Vec<&str> {
+        self.users
+            .iter()
+            .filter(|(_, user)| !user.resolved)
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    /// Warn about usernames that couldn't be resolved
+    pub fn print_unresolved(&self) {
+        let unresolved = self.unresolved();
+        if unresolved.is_empty() {
+            return;
+        }
+        eprintln!("⚠️ Не удалось найти {} username(s):", unresolved.len());
+        for name in unresolved {
+            match self.errors.get(name) {
+                Some(error) => eprintln!("   @{} ({})", name, error),
+                None => eprintln!("   @{}", name),
+            }
+        }
+    }
+}
+
+/// `@Name ` → `name`
+pub fn normalize_username(username: &str) -> String {
+    let name = username.trim();
+    name.strip_prefix('@').unwrap_or(name).to_lowercase()
+}
+
+/// Resolve usernames with the Telegram client, reusing `cache`
+pub async fn resolve_users<S: AsRef<str>>(
+    client: &Client,
+    usernames: &[S],
+    cache: &mut UserCache,
+) -> ResolveReport {
+    resolve_with(client, usernames, cache, ResolveOptions::default()).await
+}
+
+/// Resolve usernames with any [`UsernameResolver`]; never fails as a whole
+pub async fn resolve_with<R, S>(
+    resolver: &R,
+    usernames: &[S],
+    cache: &mut UserCache,
+    options: ResolveOptions,
+) -> ResolveReport
+where
+    R: UsernameResolver,
+    S: AsRef<str>,
+{
+    let mut report = ResolveReport::default();
+    let mut pending = Vec::new();
+
+    for username in usernames {
+        let name = normalize_username(username.as_ref());
+        if name.is_empty() || report.users.contains_key(&name) || pending.contains(&name) {
+            continue;
+        }
+        match cache.get(&name) {
+            Some(id) => {
+                report.cache_hits += 1;
+                report.users.insert(name, outcome(id));
+            }
+            None => pending.push(name),
+        }
+    }
+
+    for (i, batch) in pending.chunks(options.batch_size.max(1)).enumerate() {
+        if i > 0 && !options.batch_delay.is_zero() {
+            tokio::time::sleep(options.batch_delay).await;
+        }
+
+        for name in batch {
+            report.lookups += 1;
+            match lookup(resolver, name).await {
+                Ok(id) => {
+                    cache.insert(name, id);
+                    report.users.insert(name.clone(), outcome(id));
+                }
+                Err(e) => {
+                    // Errors are not cached: the next run may succeed
+                    report.errors.insert(name.clone(), e.to_string());
+                    report.users.insert(name.clone(), ResolvedUser::missing());
+                }
+            }
+        }
+    }
+
+    report
+}
+
+fn outcome(id: Option<i64>) -> ResolvedUser {
+    id.map_or_else(ResolvedUser::missing, ResolvedUser::found)
+}
+
+async fn lookup<R: UsernameResolver>(resolver: &R, username: &str) -> Result<Option<i64>> {
+    let mut flood_retries = 0;
+    loop {
+        match resolver.resolve_username(username).await {
+            Err(err) => match flood_wait_seconds(&err.to_string()) {
+                Some(secs) if flood_retries < MAX_FLOOD_RETRIES => {
+                    flood_retries += 1;
+                    warn!(
+                        "Flood wait {}s while resolving @{}, retrying",
+                        secs, username
+                    );
+                    tokio::time::sleep(Duration::from_secs(secs)).await;
+                }
+                _ => return Err(err),
+            },
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Scripted resolver: known names, optional flood waits, call log
+    #[derive(Default)]
+    struct MockResolver {
+        ids: HashMap<String, i64>,
+        broken: Vec<String>,
+        flood_waits: Mutex<usize>,
+        calls: Mutex<Vec<String>>,
+    }
+
+    impl MockResolver {
+        fn new(ids: &[(&str, i64)]) -> Self {
+            Self {
+                ids: ids.iter().map(|(n, id)| (n.to_string(), *id)).collect(),
+                ..Default::default()
+            }
+        }
+
+        fn calls(&self) -> Vec<String> {
+            self.calls.lock().unwrap().clone()
+        }
+    }
+
+    impl UsernameResolver for MockResolver {
+        async fn resolve_username(&self, username: &str) -> Result<Option<i64>> {
+            self.calls.lock().unwrap().push(username.to_string());
+            {
+                let mut flood_waits = self.flood_waits.lock().unwrap();
+                if *flood_waits > 0 {
+                    *flood_waits -= 1;
+                    return Err(Error::TelegramError("rpc error 420: FLOOD_WAIT_0".into()));
+                }
+            }
+            if self.broken.iter().any(|b| b == username) {
+                return Err(Error::TelegramError("USERNAME_INVALID".into()));
+            }
+            Ok(self.ids.get(username).copied())
+        }
+    }
+
+    fn no_delay(batch_size: usize) -> ResolveOptions {
+        ResolveOptions {
+            batch_size,
+            batch_delay: Duration::ZERO,
+        }
+    }
+
+    #[tokio::test]
+    async fn normalizes_and_deduplicates() {
+        let resolver = MockResolver::new(&[("alice", 1)]);
+        let mut cache = UserCache::default();
+
+        let report = resolve_with(
+            &resolver,
+            &["@Alice", "alice", " ALICE ", ""],
+            &mut cache,
+            no_delay(10),
+        )
+        .await;
+
+        assert_eq!(resolver.calls(), vec!["alice"]);
+        assert_eq!(report.id("@alice"), Some(1));
+        assert_eq!(report.users.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn second_call_is_served_from_cache() {
+        let resolver = MockResolver::new(&[("alice", 1), ("bob", 2)]);
+        let mut cache = UserCache::default();
+
+        let first = resolve_with(&resolver, &["alice", "ghost"], &mut cache, no_delay(10)).await;
+        assert_eq!(first.lookups, 2);
+        assert_eq!(first.cache_hits, 0);
+
+        let second = resolve_with(
+            &resolver,
+            &["alice", "ghost", "bob"],
+            &mut cache,
+            no_delay(10),
+        )
+        .await;
+        assert_eq!(second.cache_hits, 2);
+        assert_eq!(second.lookups, 1);
+        assert_eq!(resolver.calls(), vec!["alice", "ghost", "bob"]);
+        // "Not found" is cached too
+        assert_eq!(second.unresolved(), vec!["ghost"]);
+        assert_eq!(cache.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn partial_failure_is_reported_not_fatal() {
+        let mut resolver = MockResolver::new(&[("alice", 1), ("carol", 3)]);
+        resolver.broken.push("bad".to_string());
+        let mut cache = UserCache::default();
+
+        let report = resolve_with(
+            &resolver,
+            &["alice", "bad", "ghost", "carol"],
+            &mut cache,
+            no_delay(2),
+        )
+        .await;
+
+        assert_eq!(report.id("alice"), Some(1));
+        assert_eq!(report.id("carol"), Some(3));
+        assert_eq!(report.unresolved(), vec!["bad", "ghost"]);
+        assert_eq!(
+            report.users["ghost"],
+            ResolvedUser {
+                id: None,
+                resolved: false
+            }
+        );
+        assert!(report.errors["bad"].contains("USERNAME_INVALID"));
+        assert!(!report.errors.contains_key("ghost"));
+        // Errors are retried next time, "not found" is not
+        assert_eq!(cache.get("bad"), None);
+        assert_eq!(cache.get("ghost"), Some(None));
+    }
+
+    #[tokio::test]
+    async fn retries_after_flood_wait() {
+        let resolver = MockResolver::new(&[("alice", 1)]);
+        *resolver.flood_waits.lock().unwrap() = 2;
+        let mut cache = UserCache::default();
+
+        let report = resolve_with(&resolver, &["alice"], &mut cache, no_delay(1)).await;
+
+        assert_eq!(report.id("alice"), Some(1));
+        assert_eq!(resolver.calls().len(), 3);
+        assert!(report.errors.is_empty());
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_repeated_flood_waits() {
+        let resolver = MockResolver::new(&[("alice", 1)]);
+        *resolver.flood_waits.lock().unwrap() = MAX_FLOOD_RETRIES + 1;
+        let mut cache = UserCache::default();
+
+        let report = resolve_with(&resolver, &["alice"], &mut cache, no_delay(1)).await;
+
+        assert_eq!(report.unresolved(), vec!["alice"]);
+        assert!(report.errors["alice"].contains("FLOOD_WAIT"));
+    }
+}
@@ -0,0 +1,179 @@
+//! Size-rotated JSONL capture for live message streams
+//!
+//! [`RollingJsonl`] appends one JSON object per line and flushes after every
+//! write, so a crash loses at most the message being written. When the next
+//! line would push the file past `max_bytes`, it is renamed to `file.1`
+//! (older captures shift to `file.2`, ... up to `keep`) and a fresh file is
+//! started.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::error::{Error, Result};
+
+/// Default rotation threshold
+pub const DEFAULT_MAX_BYTES: u64 = 10 * 1024 * 1024;
+
+/// How many rotated files are kept by default (`file.1` .. `file.5`)
+pub const DEFAULT_KEEP: usize = 5;
+
+/// Append-only JSONL file that rotates by size
+#[derive(Debug)]
+pub struct RollingJsonl {
+    path: PathBuf,
+    max_bytes: u64,
+    keep: usize,
+    file: File,
+    size: u64,
+}
+
+impl RollingJsonl {
+    /// Open `path` for appending, creating parent directories as needed
+    pub fn open(path: impl Into<PathBuf>, max_bytes: u64, keep: usize) -> Result<Self> {
+        let path = path.into();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let file = open_append(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path,
+            max_bytes: max_bytes.max(1),
+            keep: keep.max(1),
+            file,
+            size,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Write `record` as one line and flush it
+    pub fn append(&mut self, record: &impl Serialize) -> Result<()> {
+        let mut line =
+            serde_json::to_vec(record).map_err(|e| Error::SerializationError(e.to_string()))?;
+        line.push(b'\n');
+
+        if self.size > 0 && self.size + line.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+
+        self.file.write_all(&line)?;
+        self.file.flush()?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    /// Shift `file.N-1` -> `file.N` ... `file` -> `file.1` and start a new file
+    fn rotate(&mut self) -> Result<()> {
+        let oldest = rotated_path(&self.path, self.keep);
+        if oldest.exists() {
+            fs::remove_file(&oldest)?;
+        }
+        for n in (1..self.keep).rev() {
+            let from = rotated_path(&self.path, n);
+            if from.exists() {
+                fs::rename(&from, rotated_path(&self.path, n + 1))?;
+            }
+        }
+        fs::rename(&self.path, rotated_path(&self.path, 1))?;
+
+        self.file = open_append(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+/// `file.N` next to `path`
+pub fn rotated_path(path: &Path, n: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", n));
+    PathBuf::from(name)
+}
+
+fn open_append(path: &Path) -> Result<File> {
+    Ok(OpenOptions::new().create(true).append(true).open(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn lines(path: &Path) -> Vec<serde_json::Value> {
+        fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn appends_one_json_object_per_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("capture").join("live.jsonl");
+
+        let mut log = RollingJsonl::open(&path, DEFAULT_MAX_BYTES, DEFAULT_KEEP).unwrap();
+        log.append(&json!({"id": 1, "text": "привет"})).unwrap();
+        log.append(&json!({"id": 2, "text": "line\nbreak"}))
+            .unwrap();
+
+        // Flushed: readable without dropping the writer
+        assert_eq!(
+            lines(&path),
+            vec![
+                json!({"id": 1, "text": "привет"}),
+                json!({"id": 2, "text": "line\nbreak"})
+            ]
+        );
+
+        // Reopening continues the same file
+        drop(log);
+        let mut log = RollingJsonl::open(&path, DEFAULT_MAX_BYTES, DEFAULT_KEEP).unwrap();
+        log.append(&json!({"id": 3})).unwrap();
+        assert_eq!(lines(&path).len(), 3);
+    }
+
+    #[test]
+    fn rotates_by_size_and_keeps_the_newest_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("live.jsonl");
+        // Each record is 9 bytes with the newline: {"id":N}
+        let mut log = RollingJsonl::open(&path, 20, 2).unwrap();
+        for id in 1..=7 {
+            log.append(&json!({ "id": id })).unwrap();
+        }
+
+        // Two records fit per file; file.3 would exceed `keep` and is dropped
+        assert_eq!(lines(&path), vec![json!({"id": 7})]);
+        assert_eq!(
+            lines(&rotated_path(&path, 1)),
+            vec![json!({"id": 5}), json!({"id": 6})]
+        );
+        assert_eq!(
+            lines(&rotated_path(&path, 2)),
+            vec![json!({"id": 3}), json!({"id": 4})]
+        );
+        assert!(!rotated_path(&path, 3).exists());
+    }
+
+    #[test]
+    fn oversized_record_still_goes_into_a_fresh_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("live.jsonl");
+        let mut log = RollingJsonl::open(&path, 5, 3).unwrap();
+        log.append(&json!({"text": "longer than five bytes"}))
+            .unwrap();
+        log.append(&json!({"text": "another"})).unwrap();
+
+        assert_eq!(lines(&path), vec![json!({"text": "another"})]);
+        assert_eq!(
+            lines(&rotated_path(&path, 1)),
+            vec![json!({"text": "longer than five bytes"})]
+        );
+    }
+}
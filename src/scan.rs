@@ -0,0 +1,117 @@
+//! What a command scanned, for an informative "no results" message
+//!
+//! An empty report can mean the chat had nothing in range or that nothing in
+//! it matched. Commands record every fetched message in [`ScanStats`] and use
+//! [`no_results_message`] so users can tell the two apart.
+
+use chrono::{DateTime, Utc};
+
+/// Messages fetched by a command and the period they cover
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScanStats {
+    pub fetched: usize,
+    pub oldest: Option<DateTime<Utc>>,
+    pub newest: Option<DateTime<Utc>>,
+}
+
+impl ScanStats {
+    /// Count one fetched message
+    pub fn record(&mut self, date: DateTime<Utc>) {
+        self.fetched += 1;
+        self.oldest = Some(self.oldest.map_or(date, |d| d.min(date)));
+        self.newest = Some(self.newest.map_or(date, |d| d.max(date)));
+    }
+
+    /// Stats over a list of message dates
+    pub fn from_dates(dates: impl IntoIterator<Item = DateTime<Utc>>) -> Self {
+        let mut scan = Self::default();
+        for date in dates {
+            scan.record(date);
+        }
+        scan
+    }
+
+    /// Combine scans of several chats
+    pub fn merge(&mut self, other: &ScanStats) {
+        self.fetched += other.fetched;
+        self.oldest = match (self.oldest, other.oldest) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        self.newest = self.newest.max(other.newest);
+    }
+
+    /// Days covered by the fetched messages, rounded up (at least 1 once anything was fetched)
+    pub fn days(&self) -> i64 {
+        match (self.oldest, self.newest) {
+            (Some(oldest), Some(newest)) => {
+                let seconds = (newest - oldest).num_seconds();
+                ((seconds + 86_399) / 86_400).max(1)
+            }
+            _ => 0,
+        }
+    }
+}
+
+/// "No results" message telling "nothing matched" apart from "nothing fetched"
+pub fn no_results_message(scan: &ScanStats) -> String {
+    if scan.fetched == 0 {
+        return "No results: no messages were fetched (empty chat or nothing in the requested period)"
+            .to_string();
+    }
+
+    let days = scan.days();
+    format!(
+        "No results: nothing matched (scanned {} message{} over {} day{})",
+        scan.fetched,
+        if scan.fetched == 1 { "" } else { "s" },
+        days,
+        if days == 1 { "" } else { "s" }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, day, hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn zero_fetched_says_nothing_was_fetched() {
+        assert_eq!(
+            no_results_message(&ScanStats::default()),
+            "No results: no messages were fetched (empty chat or nothing in the requested period)"
+        );
+    }
+
+    #[test]
+    fn zero_matches_reports_the_scan() {
+        let scan = ScanStats::from_dates([at(3, 12), at(1, 9), at(2, 18)]);
+        assert_eq!(scan.days(), 3);
+        assert_eq!(
+            no_results_message(&scan),
+            "No results: nothing matched (scanned 3 messages over 3 days)"
+        );
+
+        let single = ScanStats::from_dates([at(1, 9)]);
+        assert_eq!(
+            no_results_message(&single),
+            "No results: nothing matched (scanned 1 message over 1 day)"
+        );
+    }
+
+    #[test]
+    fn merges_scans_of_several_chats() {
+        let mut scan = ScanStats::from_dates([at(2, 0), at(3, 0)]);
+        scan.merge(&ScanStats::default());
+        scan.merge(&ScanStats::from_dates([at(1, 0)]));
+
+        assert_eq!(scan.fetched, 3);
+        assert_eq!(scan.oldest, Some(at(1, 0)));
+        assert_eq!(scan.newest, Some(at(3, 0)));
+        assert_eq!(scan.days(), 2);
+    }
+}
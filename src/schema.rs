@@ -0,0 +1,175 @@
+//! Minimal JSON Schema validation for LLM output
+//!
+//! Supports the subset used by our prompts: `type` (single or list),
+//! `required`, `properties` and `items`. Everything else is ignored.
+
+use serde_json::Value;
+use std::fmt;
+
+/// What is wrong at a given path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ViolationKind {
+    /// Required property is absent
+    Missing,
+    /// Value has an unexpected JSON type
+    WrongType { expected: String, found: String },
+}
+
+/// A single schema violation, e.g. `$.topics[0].mentions: expected integer, found string`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaViolation {
+    pub path: String,
+    pub kind: ViolationKind,
+}
+
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ViolationKind::Missing => write!(f, "{}: missing required field", self.path),
+            ViolationKind::WrongType { expected, found } => {
+                write!(f, "{}: expected {}, found {}", self.path, expected, found)
+            }
+        }
+    }
+}
+
+/// Validate `value` against `schema`, returning every violation found
+pub fn validate(value: &Value, schema: &Value) -> Vec<SchemaViolation> {
+    let mut violations = Vec::new();
+    validate_at(value, schema, "$", &mut violations);
+    violations
+}
+
+fn validate_at(value: &Value, schema: &Value, path: &str, out: &mut Vec<SchemaViolation>) {
+    if let Some(expected) = schema.get("type") {
+        let allowed: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !allowed.is_empty() && !allowed.iter().any(|t| matches_type(value, t)) {
+            out.push(SchemaViolation {
+                path: path.to_string(),
+                kind: ViolationKind::WrongType {
+                    expected: allowed.join("|"),
+                    found: type_name(value).to_string(),
+                },
+            });
+            return;
+        }
+    }
+
+    if let Value::Object(map) = value {
+        if let Some(required) = schema.get("required").and_then(Value::as_array) {
+            for key in required.iter().filter_map(Value::as_str) {
+                if !map.contains_key(key) {
+                    out.push(SchemaViolation {
+                        path: format!("{}.{}", path, key),
+                        kind: ViolationKind::Missing,
+                    });
+                }
+            }
+        }
+
+        if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+            for (key, prop_schema) in properties {
+                if let Some(prop) = map.get(key) {
+                    validate_at(prop, prop_schema, &format!("{}.{}", path, key), out);
+                }
+            }
+        }
+    }
+
+    if let (Value::Array(items), Some(item_schema)) = (value, schema.get("items")) {
+        for (idx, item) in items.iter().enumerate() {
+            validate_at(item, item_schema, &format!("{}[{}]", path, idx), out);
+        }
+    }
+}
+
+fn matches_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["name", "tags"],
+            "properties": {
+                "name": {"type": "string"},
+                "score": {"type": ["number", "null"]},
+                "tags": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["id"],
+                        "properties": {"id": {"type": "integer"}}
+                    }
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn accepts_conformant_value() {
+        let value = json!({"name": "a", "score": 1.5, "tags": [{"id": 1}]});
+        assert!(validate(&value, &schema()).is_empty());
+    }
+
+    #[test]
+    fn reports_missing_and_wrong_types() {
+        let value = json!({"score": "high", "tags": [{"id": "x"}, {}]});
+        let violations = validate(&value, &schema());
+        let messages: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+
+        assert_eq!(
+            messages,
+            vec![
+                "$.name: missing required field",
+                "$.score: expected number|null, found string",
+                "$.tags[0].id: expected integer, found string",
+                "$.tags[1].id: missing required field",
+            ]
+        );
+    }
+
+    #[test]
+    fn wrong_root_type_stops_descent() {
+        let violations = validate(&json!([1, 2]), &schema());
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].path, "$");
+    }
+
+    #[test]
+    fn integer_rejects_floats() {
+        let schema = json!({"type": "integer"});
+        assert!(validate(&json!(3), &schema).is_empty());
+        assert_eq!(validate(&json!(3.5), &schema).len(), 1);
+    }
+}
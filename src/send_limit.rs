@@ -0,0 +1,346 @@
+//! Account-wide daily cap on outbound messages
+//!
+//! Bulk-send commands share one counter persisted on disk, so the total number
+//! of messages sent per day stays below a safe threshold across runs and
+//! commands. The counter resets when the (UTC) date changes.
+//!
+//! Updates re-read the file under an exclusive lock on `<file>.lock`, so two
+//! commands sending at once never overwrite each other's count, and replace it
+//! through a temporary file, so a crash mid-write leaves the old count intact.
+//! Senders claim each message with [`DailySendLimiter::try_reserve`] right
+//! before sending it.
+
+use std::fs::{self, File, OpenOptions};
+use std::path::{Path, PathBuf};
+
+use chrono::{NaiveDate, Utc};
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+/// Default cap on messages per day (overridable via `DAILY_SEND_CAP`)
+pub const DEFAULT_DAILY_SEND_CAP: u32 = 50;
+
+/// Default counter file (overridable via `SEND_LIMIT_FILE`)
+pub const DEFAULT_SEND_LIMIT_FILE: &str = ".send_limit.json";
+
+/// Source of the current date, injectable for tests
+pub trait Clock {
+    fn today(&self) -> NaiveDate;
+}
+
+/// Wall clock in UTC
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn today(&self) -> NaiveDate {
+        Utc::now().date_naive()
+    }
+}
+
+impl<F: Fn() -> NaiveDate> Clock for F {
+    fn today(&self) -> NaiveDate {
+        self()
+    }
+}
+
+/// Persisted counter state
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct SendCounter {
+    date: Option<NaiveDate>,
+    sent: u32,
+    /// Messages left unsent after the cap was hit
+    #[serde(default)]
+    queued: usize,
+}
+
+/// Daily outbound message limiter shared by bulk-send commands
+#[derive(Debug)]
+pub struct DailySendLimiter<C: Clock = SystemClock> {
+    path: PathBuf,
+    cap: u32,
+    clock: C,
+    counter: SendCounter,
+}
+
+impl DailySendLimiter<SystemClock> {
+    /// Limiter using `SEND_LIMIT_FILE`/`DAILY_SEND_CAP`, with an optional cap override
+    pub fn from_env(cap: Option<u32>) -> Result<Self> {
+        let path = std::env::var("SEND_LIMIT_FILE")
+            .unwrap_or_else(|_| DEFAULT_SEND_LIMIT_FILE.to_string());
+        let cap = match cap {
+            Some(cap) => cap,
+            None => match std::env::var("DAILY_SEND_CAP") {
+                Ok(raw) => raw.parse().map_err(|_| {
+                    Error::InvalidArgument(format!("Invalid DAILY_SEND_CAP '{}'", raw))
+                })?,
+                Err(_) => DEFAULT_DAILY_SEND_CAP,
+            },
+        };
+        Self::load(path, cap, SystemClock)
+    }
+}
+
+impl<C: Clock> DailySendLimiter<C> {
+    /// Load the counter from `path` (a missing file means nothing sent yet)
+    pub fn load(path: impl Into<PathBuf>, cap: u32, clock: C) -> Result<Self> {
+        let path = path.into();
+        let counter = read_counter(&path)?;
+
+        Ok(Self {
+            path,
+            cap,
+            clock,
+            counter,
+        })
+    }
+
+    pub fn cap(&self) -> u32 {
+        self.cap
+    }
+
+    /// Messages sent today
+    pub fn sent_today(&self) -> u32 {
+        if self.counter.date == Some(self.clock.today()) {
+            self.counter.sent
+        } else {
+            0
+        }
+    }
+
+    /// Messages that may still be sent today
+    pub fn remaining(&self) -> u32 {
+        self.cap.saturating_sub(self.sent_today())
+    }
+
+    /// Record one sent message and persist the counter
+    pub fn record_sent(&mut self) -> Result<()> {
+        self.update(|counter| counter.sent += 1)
+    }
+
+    /// Claim one send against today's cap.
+    ///
+    /// The check and the increment happen under the lock on the freshly read
+    /// counter, so concurrent runs can't overshoot the cap. Returns `false`
+    /// (recording nothing) once the cap is reached.
+    pub fn try_reserve(&mut self) -> Result<bool> {
+        let cap = self.cap;
+        self.update(|counter| {
+            let free = counter.sent < cap;
+            if free {
+                counter.sent += 1;
+            }
+            free
+        })
+    }
+
+    /// Give back a claim whose send failed
+    pub fn release(&mut self) -> Result<()> {
+        self.update(|counter| counter.sent = counter.sent.saturating_sub(1))
+    }
+
+    /// Record how many messages were left unsent because the cap was hit
+    pub fn record_queued(&mut self, queued: usize) -> Result<()> {
+        self.update(|counter| counter.queued = queued)
+    }
+
+    /// Error returned when the cap is exhausted
+    pub fn cap_reached(&self, queued: usize) -> Error {
+        Error::InvalidArgument(format!(
+            "Daily send cap of {} messages reached; {} message(s) remain queued. Try again tomorrow or raise DAILY_SEND_CAP",
+            self.cap, queued
+        ))
+    }
+
+    fn roll_over(&mut self) {
+        let today = self.clock.today();
+        if self.counter.date != Some(today) {
+            self.counter = SendCounter {
+                date: Some(today),
+                ..Default::default()
+            };
+        }
+    }
+
+    /// Read-modify-write the counter while holding the lock file
+    fn update<T>(&mut self, change: impl FnOnce(&mut SendCounter) -> T) -> Result<T> {
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let lock = self.lock()?;
+
+        self.counter = read_counter(&self.path)?;
+        self.roll_over();
+        let result = change(&mut self.counter);
+
+        let mut tmp_path = self.path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        fs::write(&tmp_path, serde_json::to_string_pretty(&self.counter)?)?;
+        fs::rename(&tmp_path, &self.path)?;
+        lock.unlock()?;
+        Ok(result)
+    }
+
+    fn lock(&self) -> Result<File> {
+        let mut lock_path = self.path.clone().into_os_string();
+        lock_path.push(".lock");
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&lock_path)?;
+        file.lock_exclusive()?;
+        Ok(file)
+    }
+}
+
+/// Counter stored at `path`; a missing file means nothing sent yet
+fn read_counter(path: &Path) -> Result<SendCounter> {
+    if !path.exists() {
+        return Ok(SendCounter::default());
+    }
+    let content = fs::read_to_string(path)?;
+    serde_json::from_str(&content).map_err(|e| {
+        Error::SerializationError(format!("Invalid send counter {}: {}", path.display(), e))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use tempfile::TempDir;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 5, d).unwrap()
+    }
+
+    fn fake_clock(date: NaiveDate) -> (Rc<Cell<NaiveDate>>, impl Fn() -> NaiveDate) {
+        let today = Rc::new(Cell::new(date));
+        let handle = Rc::clone(&today);
+        (today, move || handle.get())
+    }
+
+    #[test]
+    fn enforces_cap_within_a_day() {
+        let dir = TempDir::new().unwrap();
+        let (_, clock) = fake_clock(day(1));
+        let mut limiter = DailySendLimiter::load(dir.path().join("c.json"), 2, clock).unwrap();
+
+        assert_eq!(limiter.remaining(), 2);
+        limiter.record_sent().unwrap();
+        limiter.record_sent().unwrap();
+        assert_eq!(limiter.remaining(), 0);
+        assert_eq!(limiter.sent_today(), 2);
+    }
+
+    #[test]
+    fn resets_across_day_boundary() {
+        let dir = TempDir::new().unwrap();
+        let (today, clock) = fake_clock(day(1));
+        let mut limiter = DailySendLimiter::load(dir.path().join("c.json"), 2, clock).unwrap();
+
+        limiter.record_sent().unwrap();
+        limiter.record_sent().unwrap();
+        assert_eq!(limiter.remaining(), 0);
+
+        today.set(day(2));
+        assert_eq!(limiter.remaining(), 2);
+        limiter.record_sent().unwrap();
+        assert_eq!(limiter.sent_today(), 1);
+        assert_eq!(limiter.remaining(), 1);
+    }
+
+    #[test]
+    fn persists_across_runs() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("nested").join("c.json");
+
+        let (_, clock) = fake_clock(day(3));
+        let mut first = DailySendLimiter::load(&path, 3, clock).unwrap();
+        first.record_sent().unwrap();
+        first.record_sent().unwrap();
+
+        let (_, clock) = fake_clock(day(3));
+        let second = DailySendLimiter::load(&path, 3, clock).unwrap();
+        assert_eq!(second.remaining(), 1);
+
+        let (_, clock) = fake_clock(day(4));
+        let next_day = DailySendLimiter::load(&path, 3, clock).unwrap();
+        assert_eq!(next_day.remaining(), 3);
+    }
+
+    #[test]
+    fn records_queued_messages() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("c.json");
+        let (_, clock) = fake_clock(day(1));
+        let mut limiter = DailySendLimiter::load(&path, 1, clock).unwrap();
+
+        limiter.record_sent().unwrap();
+        limiter.record_queued(4).unwrap();
+
+        let saved: SendCounter = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved.sent, 1);
+        assert_eq!(saved.queued, 4);
+        assert!(limiter
+            .cap_reached(4)
+            .to_string()
+            .contains("4 message(s) remain queued"));
+    }
+
+    #[test]
+    fn concurrent_limiters_do_not_lose_sends() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("c.json");
+
+        // Both load an empty counter before either records anything
+        let (_, clock) = fake_clock(day(1));
+        let mut first = DailySendLimiter::load(&path, 5, clock).unwrap();
+        let (_, clock) = fake_clock(day(1));
+        let mut second = DailySendLimiter::load(&path, 5, clock).unwrap();
+
+        first.record_sent().unwrap();
+        second.record_sent().unwrap();
+        first.record_sent().unwrap();
+
+        let (_, clock) = fake_clock(day(1));
+        let reloaded = DailySendLimiter::load(&path, 5, clock).unwrap();
+        assert_eq!(reloaded.sent_today(), 3);
+        assert_eq!(first.sent_today(), 3);
+    }
+
+    #[test]
+    fn reservations_stop_at_the_cap_across_limiters() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("c.json");
+
+        // Both see an empty counter in memory; the file decides
+        let (_, clock) = fake_clock(day(1));
+        let mut first = DailySendLimiter::load(&path, 3, clock).unwrap();
+        let (_, clock) = fake_clock(day(1));
+        let mut second = DailySendLimiter::load(&path, 3, clock).unwrap();
+
+        assert!(first.try_reserve().unwrap());
+        assert!(second.try_reserve().unwrap());
+        assert!(first.try_reserve().unwrap());
+        assert!(!second.try_reserve().unwrap());
+        assert!(!first.try_reserve().unwrap());
+        assert_eq!(second.sent_today(), 3);
+
+        first.release().unwrap();
+        assert!(second.try_reserve().unwrap());
+        assert!(!dir.path().join("c.json.tmp").exists());
+    }
+
+    #[test]
+    fn corrupt_counter_is_an_error() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("c.json");
+        fs::write(&path, "{").unwrap();
+        assert!(DailySendLimiter::load(&path, 1, SystemClock).is_err());
+    }
+}
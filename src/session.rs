@@ -4,63 +4,177 @@
 //! - File-based session locking to prevent parallel execution
 //! - Session file validation
 //! - Client creation with proper configuration
+//!
+//! The session is picked by name (`--session` / `TELEGRAM_SESSION`, default
+//! `telegram_session`): `<name>.session` holds the auth data and `<name>.lock`
+//! guards it, so different accounts can run side by side.
 
 use std::fs::{File, OpenOptions};
+use std::future::Future;
+use std::io::Write;
 use std::path::Path;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use fs2::FileExt;
+use grammers_client::client::dialogs::DialogIter;
+use grammers_client::client::messages::MessageIter;
 use grammers_client::client::updates::UpdatesLike;
-use grammers_client::Client;
+use grammers_client::types::{Dialog, Message};
+use grammers_client::{Client, InputMessage, InvocationError};
 use grammers_mtsender::{SenderPool, SenderPoolHandle};
+use grammers_session::defs::PeerRef;
 use grammers_session::storages::SqliteSession;
+use grammers_session::Session;
 use tokio::sync::mpsc;
+use tracing::{info, warn};
 
-use crate::config::{Config, LOCK_FILE, SESSION_NAME};
+use crate::config::{Config, SESSION_NAME};
 use crate::error::{Error, Result};
 
+/// Env var selecting the session; `--session` sets it for the whole process
+pub const SESSION_ENV: &str = "TELEGRAM_SESSION";
+
+/// Name of the selected session (`TELEGRAM_SESSION`, default `telegram_session`)
+pub fn session_name() -> String {
+    std::env::var(SESSION_ENV)
+        .ok()
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| SESSION_NAME.to_string())
+}
+
+/// Session names become file names, so keep them to a single path component
+pub fn validate_session_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name != "."
+        && name != ".."
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::InvalidArgument(format!(
+            "session name '{}' may only contain letters, digits, '_', '-' and '.'",
+            name
+        )))
+    }
+}
+
+/// `<session>.session` for the selected session
+pub fn session_file() -> String {
+    format!("{}.session", session_name())
+}
+
+/// `<session>.lock` for the selected session
+pub fn lock_file() -> String {
+    format!("{}.lock", session_name())
+}
+
+/// Env var with the default lock wait in seconds; `--lock-timeout` sets it
+pub const LOCK_TIMEOUT_ENV: &str = "TELEGRAM_LOCK_TIMEOUT";
+
+/// How often `SessionLock::acquire_timeout` re-checks a busy lock
+const LOCK_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// How long `SessionLock::acquire` waits (`TELEGRAM_LOCK_TIMEOUT`, default: not at all)
+pub fn lock_timeout() -> Result<Duration> {
+    match std::env::var(LOCK_TIMEOUT_ENV) {
+        Ok(raw) if !raw.trim().is_empty() => {
+            raw.trim().parse().map(Duration::from_secs).map_err(|_| {
+                Error::InvalidArgument(format!(
+                    "{} must be a number of seconds, got '{}'",
+                    LOCK_TIMEOUT_ENV, raw
+                ))
+            })
+        }
+        _ => Ok(Duration::ZERO),
+    }
+}
+
 /// Session lock guard that ensures exclusive access to the Telegram session.
+///
+/// The guard holds an exclusive `flock` on `<session>.lock`. The kernel drops
+/// it when the owning process exits, so a crashed run never leaves the session
+/// locked; the PID written into the file is informational only.
 pub struct SessionLock {
     lock_file: Option<File>,
+    path: String,
 }
 
 impl SessionLock {
-    /// Acquire an exclusive lock on the session.
-    pub fn acquire() -> Result<Self> {
-        let lock_file = OpenOptions::new()
+    /// Acquire an exclusive lock on the selected session, waiting up to
+    /// [`lock_timeout`] for another process to release it.
+    pub async fn acquire() -> Result<Self> {
+        Self::acquire_timeout(lock_timeout()?).await
+    }
+
+    /// Single attempt that fails at once if the session is busy.
+    pub fn acquire_now() -> Result<Self> {
+        Self::try_lock(&lock_file())?.ok_or_else(session_busy)
+    }
+
+    /// Acquire the lock, waiting up to `timeout` for another process to release it.
+    pub async fn acquire_timeout(timeout: Duration) -> Result<Self> {
+        let path = lock_file();
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            if let Some(lock) = Self::try_lock(&path)? {
+                return Ok(lock);
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(session_busy());
+            }
+            tokio::time::sleep(LOCK_POLL_INTERVAL.min(deadline - now)).await;
+        }
+    }
+
+    /// One locking attempt; `None` when another holder has the lock
+    fn try_lock(path: &str) -> Result<Option<Self>> {
+        // No truncate on open: a busy lock file still names its owner
+        let mut lock_file = OpenOptions::new()
+            .read(true)
             .write(true)
             .create(true)
-            .truncate(true)
-            .open(LOCK_FILE)
+            .truncate(false)
+            .open(path)
             .map_err(|e| Error::LockError(format!("Failed to open lock file: {}", e)))?;
 
-        match lock_file.try_lock_exclusive() {
-            Ok(()) => Ok(Self {
-                lock_file: Some(lock_file),
-            }),
-            Err(_) => {
-                eprintln!(
-                    r#"
-⚠️  ОШИБКА: Telegram сессия уже используется другим скриптом!
-
-Telegram требует последовательного выполнения операций.
-Параллельное использование одной сессии может привести к конфликтам и блокировкам.
+        if lock_file.try_lock_exclusive().is_err() {
+            return Ok(None);
+        }
 
-Подождите, пока завершится другой скрипт, и попробуйте снова.
-"#
-                );
-                Err(Error::SessionLocked)
-            }
+        // The previous holder unlinks the file on release; if we locked that
+        // unlinked inode, retry on the current path instead
+        if !is_same_file(&lock_file, path) {
+            let _ = lock_file.unlock();
+            return Ok(None);
         }
+
+        lock_file
+            .set_len(0)
+            .and_then(|_| write!(lock_file, "{}", std::process::id()))
+            .and_then(|_| lock_file.flush())
+            .map_err(|e| Error::LockError(format!("Failed to write lock file: {}", e)))?;
+
+        Ok(Some(Self {
+            lock_file: Some(lock_file),
+            path: path.to_string(),
+        }))
     }
 
     /// Release the lock manually
     pub fn release(&mut self) {
-        if let Some(ref file) = self.lock_file {
+        if let Some(file) = self.lock_file.take() {
+            // Unlink while still holding the lock, so nobody can lock the path
+            // we are about to remove
+            let _ = std::fs::remove_file(&self.path);
             let _ = file.unlock();
         }
-        self.lock_file = None;
-        let _ = std::fs::remove_file(LOCK_FILE);
     }
 }
 
@@ -70,9 +184,37 @@ impl Drop for SessionLock {
     }
 }
 
+fn session_busy() -> Error {
+    eprintln!(
+        r#"
+⚠️  ОШИБКА: Telegram сессия уже используется другим скриптом!
+
+Telegram требует последовательного выполнения операций.
+Параллельное использование одной сессии может привести к конфликтам и блокировкам.
+
+Подождите, пока завершится другой скрипт, или задайте --lock-timeout.
+"#
+    );
+    Error::SessionLocked
+}
+
+#[cfg(unix)]
+fn is_same_file(file: &File, path: &str) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (file.metadata(), std::fs::metadata(path)) {
+        (Ok(open), Ok(on_disk)) => open.dev() == on_disk.dev() && open.ino() == on_disk.ino(),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn is_same_file(_file: &File, path: &str) -> bool {
+    Path::new(path).exists()
+}
+
 /// Check if the session file exists.
 pub fn check_session_exists() -> Result<()> {
-    let session_file = format!("{}.session", SESSION_NAME);
+    let session_file = session_file();
 
     if !Path::new(&session_file).exists() {
         eprintln!(
@@ -80,10 +222,11 @@ pub fn check_session_exists() -> Result<()> {
 ⚠️  ОШИБКА: Session файл '{}' не найден!
 
 Для создания session файла:
-1. Запустите: cargo run --bin init_session
+1. Запустите: cargo run -- --session {} init-session
 2. Введите код из Telegram
 "#,
-            session_file
+            session_file,
+            session_name()
         );
         return Err(Error::SessionNotFound(session_file));
     }
@@ -93,7 +236,7 @@ pub fn check_session_exists() -> Result<()> {
 
 /// Load an existing session from file.
 pub fn load_session() -> Result<Arc<SqliteSession>> {
-    let session_file = format!("{}.session", SESSION_NAME);
+    let session_file = session_file();
     let session = SqliteSession::open(&session_file)
         .map_err(|e| Error::SessionNotFound(format!("Failed to load session: {}", e)))?;
     Ok(Arc::new(session))
@@ -101,51 +244,232 @@ pub fn load_session() -> Result<Arc<SqliteSession>> {
 
 /// Create a new session (for init_session only).
 pub fn create_session() -> Result<Arc<SqliteSession>> {
-    let session_file = format!("{}.session", SESSION_NAME);
+    let session_file = session_file();
     let session = SqliteSession::open(&session_file)
         .map_err(|e| Error::SessionNotFound(format!("Failed to create session: {}", e)))?;
     Ok(Arc::new(session))
 }
 
-/// Holder for SenderPool components and Client
+/// Bounded reconnect attempts with exponential backoff
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReconnectPolicy {
+    /// Reconnects tried before the error reaches the command
+    pub max_attempts: u32,
+    /// Wait before the first attempt; doubles on each following one
+    pub base_delay: Duration,
+    /// Upper bound for the wait
+    pub max_delay: Duration,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Wait before reconnect `attempt` (1-based)
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.base_delay.saturating_mul(factor).min(self.max_delay)
+    }
+}
+
+/// Errors that mean the connection is gone rather than the request being refused
+pub fn is_connection_error(err: &InvocationError) -> bool {
+    matches!(
+        err,
+        InvocationError::Io(_) | InvocationError::Transport(_) | InvocationError::Dropped
+    )
+}
+
+/// Holder for SenderPool components and Client.
+///
+/// The sender pool dials a datacenter again on the first request after its
+/// connection dropped, so `client` (and every [`Message`] fetched through it)
+/// stays usable for the whole run. Only requests that were in flight when the
+/// connection went away fail; the methods below retry those with backoff.
 pub struct TelegramClient {
     pub client: Client,
     pub handle: SenderPoolHandle,
     session: Arc<SqliteSession>,
     updates: Option<mpsc::UnboundedReceiver<UpdatesLike>>,
-    _runner_handle: tokio::task::JoinHandle<()>,
+    runner_handle: tokio::task::JoinHandle<()>,
+    reconnect_policy: ReconnectPolicy,
 }
 
 impl TelegramClient {
     /// Create a new TelegramClient from session
     pub async fn connect(session: Arc<SqliteSession>) -> Result<Self> {
         let config = Config::new();
-        let pool = SenderPool::new(session.clone(), config.api_id);
-
-        // Create client from pool (need reference to whole pool)
-        let client = Client::new(&pool);
-
-        // Get handle and runner after client is created
-        let SenderPool {
-            runner,
-            updates,
-            handle,
-        } = pool;
-
-        // Spawn the runner in background
-        let runner_handle = tokio::spawn(async move {
-            runner.run().await;
-        });
+        let (client, handle, updates, runner_handle) = start_pool(&session, config.api_id);
 
         Ok(Self {
             client,
             handle,
             session,
             updates: Some(updates),
-            _runner_handle: runner_handle,
+            runner_handle,
+            reconnect_policy: ReconnectPolicy::default(),
         })
     }
 
+    /// Replace the default reconnect policy
+    pub fn with_reconnect_policy(mut self, policy: ReconnectPolicy) -> Self {
+        self.reconnect_policy = policy;
+        self
+    }
+
+    /// Whether the background sender has stopped
+    pub fn is_disconnected(&self) -> bool {
+        self.runner_handle.is_finished()
+    }
+
+    /// Wait until Telegram answers again after a dropped connection.
+    ///
+    /// Retries with backoff per the reconnect policy and logs a warning on
+    /// every attempt. Existing handles and update streams keep working.
+    pub async fn reconnect(&self) -> Result<()> {
+        let mut attempts = 0;
+        self.reconnect_counted(&mut attempts).await
+    }
+
+    /// Reconnect, counting tries in `attempts` so that repeated drops during
+    /// one request share a single `max_attempts` budget
+    async fn reconnect_counted(&self, attempts: &mut u32) -> Result<()> {
+        let policy = self.reconnect_policy;
+        let mut last_error = String::from("no attempts left");
+
+        while *attempts < policy.max_attempts {
+            if self.is_disconnected() {
+                return Err(Error::ConnectionError(
+                    "Telegram sender stopped".to_string(),
+                ));
+            }
+
+            *attempts += 1;
+            let delay = policy.delay(*attempts);
+            warn!(
+                "Telegram connection lost, reconnecting in {:?} (attempt {}/{})",
+                delay, attempts, policy.max_attempts
+            );
+            tokio::time::sleep(delay).await;
+
+            // Drop a half-open connection so the probe dials a fresh one
+            self.handle.disconnect_from_dc(self.session.home_dc_id());
+            match self.client.get_me().await {
+                Ok(_) => {
+                    info!("Reconnected to Telegram");
+                    return Ok(());
+                }
+                Err(e) if is_connection_error(&e) => last_error = e.to_string(),
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Err(Error::ConnectionError(format!(
+            "gave up after {} reconnect attempts: {}",
+            policy.max_attempts, last_error
+        )))
+    }
+
+    /// Reconnect after `err` if it is a connection error and the budget
+    /// allows; any other error is handed back to the caller
+    async fn recover(&self, err: InvocationError, attempts: &mut u32) -> Result<()> {
+        if !is_connection_error(&err) {
+            return Err(err.into());
+        }
+        if *attempts >= self.reconnect_policy.max_attempts {
+            return Err(Error::ConnectionError(format!(
+                "gave up after {} reconnect attempts: {}",
+                attempts, err
+            )));
+        }
+        self.reconnect_counted(attempts).await
+    }
+
+    /// Run a request, reconnecting and retrying it when the connection drops.
+    ///
+    /// Reconnect attempts across all retries count against one
+    /// `max_attempts` budget.
+    pub async fn with_reconnect<T, F, Fut>(&self, mut request: F) -> Result<T>
+    where
+        F: FnMut(Client) -> Fut,
+        Fut: Future<Output = std::result::Result<T, InvocationError>>,
+    {
+        let mut attempts = 0;
+        loop {
+            match request(self.client.clone()).await {
+                Ok(value) => return Ok(value),
+                Err(e) => self.recover(e, &mut attempts).await?,
+            }
+        }
+    }
+
+    /// [`Client::send_message`] that survives a dropped connection
+    pub async fn send_message<C, M>(&self, peer: C, message: M) -> Result<Message>
+    where
+        C: Into<PeerRef>,
+        M: Into<InputMessage>,
+    {
+        let peer = peer.into();
+        let message = message.into();
+        self.with_reconnect(|client| {
+            let message = message.clone();
+            async move { client.send_message(peer, message).await }
+        })
+        .await
+    }
+
+    /// Reply to message `message_id` in `peer`; unlike [`Message::reply`]
+    /// this only needs the ids, so it can be queued and sent later
+    pub async fn reply<C, M>(&self, peer: C, message_id: i32, message: M) -> Result<Message>
+    where
+        C: Into<PeerRef>,
+        M: Into<InputMessage>,
+    {
+        let message = message.into().reply_to(Some(message_id));
+        self.send_message(peer, message).await
+    }
+
+    /// [`Client::delete_messages`] that survives a dropped connection
+    pub async fn delete_messages<C: Into<PeerRef>>(
+        &self,
+        peer: C,
+        message_ids: &[i32],
+    ) -> Result<usize> {
+        let peer = peer.into();
+        self.with_reconnect(|client| async move { client.delete_messages(peer, message_ids).await })
+            .await
+    }
+
+    /// Next message of `iter`, retrying the page fetch when the connection
+    /// drops (a failed fetch leaves the iterator where it was)
+    pub async fn next_message(&self, iter: &mut MessageIter) -> Result<Option<Message>> {
+        let mut attempts = 0;
+        loop {
+            match iter.next().await {
+                Ok(message) => return Ok(message),
+                Err(e) => self.recover(e, &mut attempts).await?,
+            }
+        }
+    }
+
+    /// Next dialog of `iter`, retrying the page fetch like [`Self::next_message`]
+    pub async fn next_dialog(&self, iter: &mut DialogIter) -> Result<Option<Dialog>> {
+        let mut attempts = 0;
+        loop {
+            match iter.next().await {
+                Ok(dialog) => return Ok(dialog),
+                Err(e) => self.recover(e, &mut attempts).await?,
+            }
+        }
+    }
+
     /// Take ownership of the updates receiver to build an UpdateStream.
     /// Returns None if updates were already taken.
     pub fn take_updates(&mut self) -> Option<mpsc::UnboundedReceiver<UpdatesLike>> {
@@ -158,7 +482,9 @@ impl TelegramClient {
     }
 }
 
-// Implement Deref to allow using TelegramClient as &Client
+// Implement Deref to allow using TelegramClient as &Client.
+// `send_message` and `delete_messages` above shadow the Client methods of the
+// same name, so plain calls on a TelegramClient retry on their own.
 impl std::ops::Deref for TelegramClient {
     type Target = Client;
 
@@ -173,13 +499,78 @@ pub fn save_session(_session: &SqliteSession) -> Result<()> {
     Ok(())
 }
 
+/// Build a sender pool for `session` and run it in the background
+fn start_pool(
+    session: &Arc<SqliteSession>,
+    api_id: i32,
+) -> (
+    Client,
+    SenderPoolHandle,
+    mpsc::UnboundedReceiver<UpdatesLike>,
+    tokio::task::JoinHandle<()>,
+) {
+    let pool = SenderPool::new(session.clone(), api_id);
+
+    // Create client from pool (need reference to whole pool)
+    let client = Client::new(&pool);
+
+    // Get handle and runner after client is created
+    let SenderPool {
+        runner,
+        updates,
+        handle,
+    } = pool;
+
+    // Spawn the runner in background
+    let runner_handle = tokio::spawn(async move {
+        runner.run().await;
+    });
+
+    (client, handle, updates, runner_handle)
+}
+
 /// Create and connect a Telegram client with an existing session.
+///
+/// Sends, deletes and history pages fetched through the returned client are
+/// retried across dropped connections; other requests can opt in with
+/// [`TelegramClient::with_reconnect`].
 pub async fn get_client() -> Result<TelegramClient> {
     check_session_exists()?;
     let session = load_session()?;
     TelegramClient::connect(session).await
 }
 
+/// Session lock plus client for bulk sends that may pause for hours.
+///
+/// [`HeldSession::release`] disconnects and unlocks the session so other
+/// scripts can use it during the pause; the next [`HeldSession::client`]
+/// call locks and connects again.
+#[derive(Default)]
+pub struct HeldSession {
+    // The client is dropped (and disconnected) before the lock is released
+    held: Option<(TelegramClient, SessionLock)>,
+}
+
+impl HeldSession {
+    /// Connected client, locking the session and connecting if needed
+    pub async fn client(&mut self) -> Result<&TelegramClient> {
+        if self.held.is_none() {
+            let lock = SessionLock::acquire().await?;
+            let client = get_client().await?;
+            self.held = Some((client, lock));
+        }
+        match &self.held {
+            Some((client, _)) => Ok(client),
+            None => unreachable!("connected above"),
+        }
+    }
+
+    /// Disconnect and unlock the session until the next `client` call
+    pub fn release(&mut self) {
+        self.held = None;
+    }
+}
+
 /// Create a Telegram client for initialization (no session check).
 pub async fn get_client_for_init() -> Result<TelegramClient> {
     let session = create_session()?;
@@ -189,6 +580,7 @@ pub async fn get_client_for_init() -> Result<TelegramClient> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::LOCK_FILE;
     use std::env;
     use std::path::PathBuf;
     use std::process::{self, Command};
@@ -215,13 +607,210 @@ mod tests {
         }
     }
 
+    /// Selects a session via `TELEGRAM_SESSION` for the guard's lifetime
+    struct SessionEnvGuard {
+        original: Option<String>,
+    }
+
+    impl SessionEnvGuard {
+        fn select(name: Option<&str>) -> Self {
+            let original = env::var(SESSION_ENV).ok();
+            match name {
+                Some(name) => env::set_var(SESSION_ENV, name),
+                None => env::remove_var(SESSION_ENV),
+            }
+            Self { original }
+        }
+    }
+
+    impl Drop for SessionEnvGuard {
+        fn drop(&mut self) {
+            match &self.original {
+                Some(value) => env::set_var(SESSION_ENV, value),
+                None => env::remove_var(SESSION_ENV),
+            }
+        }
+    }
+
+    /// PID recorded in a lock file, if it has one
+    fn lock_owner(path: &str) -> Option<u32> {
+        std::fs::read_to_string(path).ok()?.trim().parse().ok()
+    }
+
+    #[test]
+    fn acquire_records_owner_pid() {
+        let _lock = WORKDIR_LOCK.lock().unwrap();
+        let temp = tempdir().expect("tempdir");
+        let _guard = DirGuard::change_to(temp.path());
+
+        let _held = SessionLock::acquire_now().expect("lock");
+        assert_eq!(lock_owner(LOCK_FILE), Some(process::id()));
+    }
+
+    #[test]
+    fn acquire_timeout_gives_up_after_timeout() {
+        let _lock = WORKDIR_LOCK.lock().unwrap();
+        let temp = tempdir().expect("tempdir");
+        let _guard = DirGuard::change_to(temp.path());
+
+        let _held = SessionLock::acquire_now().expect("first lock");
+        let started = Instant::now();
+        let second = tokio_test::block_on(SessionLock::acquire_timeout(Duration::from_millis(300)));
+
+        assert!(matches!(second, Err(Error::SessionLocked)));
+        assert!(started.elapsed() >= Duration::from_millis(300));
+        // The holder's PID survives the failed attempt
+        assert_eq!(lock_owner(LOCK_FILE), Some(process::id()));
+    }
+
+    #[test]
+    fn acquire_timeout_waits_for_release() {
+        let _lock = WORKDIR_LOCK.lock().unwrap();
+        let temp = tempdir().expect("tempdir");
+        let _guard = DirGuard::change_to(temp.path());
+
+        let held = SessionLock::acquire_now().expect("first lock");
+        let releaser = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(300));
+            drop(held);
+        });
+
+        let second = tokio_test::block_on(SessionLock::acquire_timeout(Duration::from_secs(5)));
+        releaser.join().unwrap();
+        assert!(second.is_ok());
+        assert!(PathBuf::from(LOCK_FILE).exists());
+    }
+
+    #[test]
+    fn leftover_lock_file_without_holder_is_taken_over() {
+        let _lock = WORKDIR_LOCK.lock().unwrap();
+        let temp = tempdir().expect("tempdir");
+        let _guard = DirGuard::change_to(temp.path());
+
+        // A crashed run leaves the file behind, but its flock died with it
+        std::fs::write(LOCK_FILE, u32::MAX.to_string()).unwrap();
+
+        let lock = SessionLock::acquire_now().expect("unheld lock file");
+        assert_eq!(lock_owner(LOCK_FILE), Some(process::id()));
+        drop(lock);
+        assert!(!PathBuf::from(LOCK_FILE).exists());
+    }
+
+    #[test]
+    fn lock_timeout_follows_env() {
+        let _lock = WORKDIR_LOCK.lock().unwrap();
+        let original = env::var(LOCK_TIMEOUT_ENV).ok();
+
+        env::remove_var(LOCK_TIMEOUT_ENV);
+        assert_eq!(lock_timeout().unwrap(), Duration::ZERO);
+        env::set_var(LOCK_TIMEOUT_ENV, "30");
+        assert_eq!(lock_timeout().unwrap(), Duration::from_secs(30));
+        env::set_var(LOCK_TIMEOUT_ENV, "soon");
+        assert!(matches!(lock_timeout(), Err(Error::InvalidArgument(_))));
+
+        match original {
+            Some(value) => env::set_var(LOCK_TIMEOUT_ENV, value),
+            None => env::remove_var(LOCK_TIMEOUT_ENV),
+        }
+    }
+
+    #[test]
+    fn reconnect_delay_doubles_up_to_cap() {
+        let policy = ReconnectPolicy {
+            max_attempts: 6,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(10),
+        };
+        let delays: Vec<u64> = (1..=6).map(|a| policy.delay(a).as_secs()).collect();
+        assert_eq!(delays, vec![1, 2, 4, 8, 10, 10]);
+        // Huge attempt numbers saturate instead of overflowing
+        assert_eq!(policy.delay(u32::MAX), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn dropped_and_io_errors_trigger_reconnect() {
+        assert!(is_connection_error(&InvocationError::Dropped));
+        assert!(is_connection_error(&InvocationError::Io(
+            std::io::ErrorKind::ConnectionReset.into()
+        )));
+    }
+
+    #[test]
+    fn session_name_defaults_and_follows_env() {
+        let _lock = WORKDIR_LOCK.lock().unwrap();
+        {
+            let _session = SessionEnvGuard::select(None);
+            assert_eq!(session_name(), SESSION_NAME);
+            assert_eq!(lock_file(), LOCK_FILE);
+        }
+        let _session = SessionEnvGuard::select(Some("work"));
+        assert_eq!(session_name(), "work");
+        assert_eq!(session_file(), "work.session");
+        assert_eq!(lock_file(), "work.lock");
+    }
+
+    #[test]
+    fn session_names_must_be_plain_file_names() {
+        for ok in ["telegram_session", "work-2", "личный.acc"] {
+            assert!(validate_session_name(ok).is_ok(), "{}", ok);
+        }
+        for bad in ["", ".", "..", "../other", "a/b", "a\\b", "with space"] {
+            assert!(
+                matches!(validate_session_name(bad), Err(Error::InvalidArgument(_))),
+                "{}",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn different_sessions_lock_independently() {
+        let _lock = WORKDIR_LOCK.lock().unwrap();
+        let temp = tempdir().expect("tempdir");
+        let _guard = DirGuard::change_to(temp.path());
+
+        let personal = {
+            let _session = SessionEnvGuard::select(Some("personal"));
+            SessionLock::acquire_now().expect("personal lock")
+        };
+        let _session = SessionEnvGuard::select(Some("work"));
+        let mut work = SessionLock::acquire_now().expect("work lock");
+
+        assert!(PathBuf::from("personal.lock").exists());
+        assert!(PathBuf::from("work.lock").exists());
+
+        // Releasing one session keeps the other's lock in place
+        work.release();
+        assert!(!PathBuf::from("work.lock").exists());
+        assert!(PathBuf::from("personal.lock").exists());
+        drop(personal);
+        assert!(!PathBuf::from("personal.lock").exists());
+    }
+
+    #[test]
+    fn check_session_exists_uses_selected_session() {
+        let _lock = WORKDIR_LOCK.lock().unwrap();
+        let temp = tempdir().expect("tempdir");
+        let _guard = DirGuard::change_to(temp.path());
+        let _session = SessionEnvGuard::select(Some("work"));
+
+        File::create(format!("{}.session", SESSION_NAME)).expect("default session");
+        assert!(matches!(
+            check_session_exists(),
+            Err(Error::SessionNotFound(path)) if path == "work.session"
+        ));
+
+        File::create("work.session").expect("work session");
+        check_session_exists().expect("selected session exists");
+    }
+
     #[test]
     fn test_session_lock_creation() {
         let _lock = WORKDIR_LOCK.lock().unwrap();
         let temp = tempdir().expect("tempdir");
         let _guard = DirGuard::change_to(temp.path());
 
-        let result = SessionLock::acquire();
+        let result = SessionLock::acquire_now();
         if let Ok(mut lock) = result {
             lock.release();
         }
@@ -234,7 +823,7 @@ mod tests {
         let temp = tempdir().expect("tempdir");
         let _guard = DirGuard::change_to(temp.path());
 
-        let mut first = SessionLock::acquire().expect("first lock");
+        let mut first = SessionLock::acquire_now().expect("first lock");
 
         let status = Command::new(env::current_exe().expect("current exe"))
             .env("SESSION_LOCK_CHILD", "1")
@@ -248,7 +837,7 @@ mod tests {
 
         first.release();
 
-        let third = SessionLock::acquire();
+        let third = SessionLock::acquire_now();
         assert!(third.is_ok());
     }
 
@@ -262,7 +851,7 @@ mod tests {
             let _ = env::set_current_dir(&dir);
         }
 
-        let result = SessionLock::acquire();
+        let result = SessionLock::acquire_now();
         match result {
             Err(Error::SessionLocked) => process::exit(0),
             Ok(mut lock) => {
@@ -296,7 +885,7 @@ mod tests {
         let temp = tempdir().expect("tempdir");
         let _guard = DirGuard::change_to(temp.path());
 
-        let mut lock = SessionLock::acquire().expect("lock");
+        let mut lock = SessionLock::acquire_now().expect("lock");
         assert!(PathBuf::from(LOCK_FILE).exists());
         lock.release();
         assert!(!PathBuf::from(LOCK_FILE).exists());
@@ -309,7 +898,7 @@ mod tests {
         let _guard = DirGuard::change_to(temp.path());
 
         {
-            let _lock = SessionLock::acquire().expect("lock");
+            let _lock = SessionLock::acquire_now().expect("lock");
             assert!(PathBuf::from(LOCK_FILE).exists());
         }
         // Lock should be released after drop
@@ -322,7 +911,7 @@ mod tests {
         let temp = tempdir().expect("tempdir");
         let _guard = DirGuard::change_to(temp.path());
 
-        let mut lock = SessionLock::acquire().expect("lock");
+        let mut lock = SessionLock::acquire_now().expect("lock");
         lock.release();
         lock.release(); // Should not panic
     }
@@ -374,7 +963,7 @@ mod tests {
         let _guard = DirGuard::change_to(temp.path());
 
         assert!(!PathBuf::from(LOCK_FILE).exists());
-        let mut lock = SessionLock::acquire().expect("lock");
+        let mut lock = SessionLock::acquire_now().expect("lock");
         assert!(PathBuf::from(LOCK_FILE).exists());
         lock.release();
     }
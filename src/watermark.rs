@@ -0,0 +1,309 @@
+//! Per-(command, chat) watermarks for "since last run" processing
+//!
+//! Cron-driven digests and analyses store the newest processed message so the
+//! next run only fetches what arrived afterwards.
+
+use std::fs;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+/// Default directory for watermark files (overridable via `WATERMARK_DIR`)
+pub const DEFAULT_WATERMARK_DIR: &str = ".watermarks";
+
+/// Newest message processed by a previous run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Watermark {
+    pub last_date: DateTime<Utc>,
+    pub last_id: i32,
+}
+
+impl Watermark {
+    /// Advance to the newest of the given `(date, id)` pairs, never moving backwards
+    pub fn advance(
+        current: Option<Watermark>,
+        processed: impl IntoIterator<Item = (DateTime<Utc>, i32)>,
+    ) -> Option<Watermark> {
+        processed
+            .into_iter()
+            .map(|(last_date, last_id)| Watermark { last_date, last_id })
+            .chain(current)
+            .max_by_key(|w| (w.last_id, w.last_date))
+    }
+}
+
+/// Range of messages an incremental run should fetch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FetchBounds {
+    /// Stop at messages older than this (no lower bound when `None`)
+    pub since: Option<DateTime<Utc>>,
+    /// Skip messages with id at or below this (already processed)
+    pub after_id: Option<i32>,
+}
+
+impl FetchBounds {
+    /// Bounds from a watermark, falling back to a fixed window when there is none
+    pub fn new(watermark: Option<&Watermark>, fallback_since: Option<DateTime<Utc>>) -> Self {
+        match watermark {
+            Some(w) => Self {
+                since: Some(w.last_date),
+                after_id: Some(w.last_id),
+            },
+            None => Self {
+                since: fallback_since,
+                after_id: None,
+            },
+        }
+    }
+
+    /// Whether a message is past the lower date bound (iteration can stop otherwise)
+    pub fn in_range(&self, date: DateTime<Utc>) -> bool {
+        self.since.is_none_or(|since| date >= since)
+    }
+
+    /// Whether a message hasn't been processed by a previous run
+    pub fn is_new(&self, id: i32) -> bool {
+        self.after_id.is_none_or(|after| id > after)
+    }
+}
+
+/// File-backed watermark storage, one JSON file per (command, chat)
+#[derive(Debug, Clone)]
+pub struct WatermarkStore {
+    dir: PathBuf,
+}
+
+impl WatermarkStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Store in `WATERMARK_DIR` or [`DEFAULT_WATERMARK_DIR`]
+    pub fn from_env() -> Self {
+        let dir = std::env::var("WATERMARK_DIR").unwrap_or_else(|_| DEFAULT_WATERMARK_DIR.into());
+        Self::new(dir)
+    }
+
+    /// `<command>.<chat>.json` with both parts escaped, so distinct pairs
+    /// never share a file
+    fn path(&self, command: &str, chat: &str) -> PathBuf {
+        self.dir
+            .join(format!("{}.{}.json", escape_key(command), escape_key(chat)))
+    }
+
+    /// Lossy name used before escaping; only read, so older watermarks
+    /// survive the upgrade
+    fn legacy_path(&self, command: &str, chat: &str) -> PathBuf {
+        let key = format!("{}_{}", command, chat);
+        let safe: String = key
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() || c == '-' || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.dir.join(format!("{safe}.json"))
+    }
+
+    /// Load the watermark for a command/chat pair, if any
+    pub fn load(&self, command: &str, chat: &str) -> Result<Option<Watermark>> {
+        let path = [self.path(command, chat), self.legacy_path(command, chat)]
+            .into_iter()
+            .find(|path| path.exists());
+        let Some(path) = path else {
+            return Ok(None);
+        };
+        let content = fs::read_to_string(&path)?;
+        let watermark = serde_json::from_str(&content).map_err(|e| {
+            Error::SerializationError(format!("Invalid watermark {}: {}", path.display(), e))
+        })?;
+        Ok(Some(watermark))
+    }
+
+    /// Persist the watermark for a command/chat pair
+    pub fn save(&self, command: &str, chat: &str, watermark: &Watermark) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let payload = serde_json::to_string_pretty(watermark)?;
+        fs::write(self.path(command, chat), payload)?;
+        Ok(())
+    }
+}
+
+/// Keep ASCII letters, digits and `-`; every other byte becomes `_xx` hex
+fn escape_key(part: &str) -> String {
+    part.bytes()
+        .map(|b| {
+            if b.is_ascii_alphanumeric() || b == b'-' {
+                (b as char).to_string()
+            } else {
+                format!("_{:02x}", b)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+    use tempfile::TempDir;
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, 1, hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn load_missing_returns_none() {
+        let dir = TempDir::new().unwrap();
+        let store = WatermarkStore::new(dir.path());
+        assert_eq!(store.load("digest", "chat").unwrap(), None);
+    }
+
+    #[test]
+    fn save_then_load_round_trips_per_command_and_chat() {
+        let dir = TempDir::new().unwrap();
+        let store = WatermarkStore::new(dir.path().join("nested"));
+        let mark = Watermark {
+            last_date: at(10),
+            last_id: 42,
+        };
+
+        store.save("digest", "@team/chat", &mark).unwrap();
+
+        assert_eq!(store.load("digest", "@team/chat").unwrap(), Some(mark));
+        assert_eq!(store.load("analyze", "@team/chat").unwrap(), None);
+        assert_eq!(store.load("digest", "other").unwrap(), None);
+    }
+
+    #[test]
+    fn save_overwrites_previous_value() {
+        let dir = TempDir::new().unwrap();
+        let store = WatermarkStore::new(dir.path());
+        let first = Watermark {
+            last_date: at(1),
+            last_id: 1,
+        };
+        let second = Watermark {
+            last_date: at(2),
+            last_id: 2,
+        };
+
+        store.save("digest", "chat", &first).unwrap();
+        store.save("digest", "chat", &second).unwrap();
+
+        assert_eq!(store.load("digest", "chat").unwrap(), Some(second));
+    }
+
+    #[test]
+    fn distinct_keys_never_share_a_file() {
+        let dir = TempDir::new().unwrap();
+        let store = WatermarkStore::new(dir.path());
+        let pairs = [
+            ("digest", "chat.a"),
+            ("digest", "chat_a"),
+            ("digest", "chat/a"),
+            ("a_b", "c"),
+            ("a", "b_c"),
+        ];
+
+        for (id, (command, chat)) in pairs.iter().enumerate() {
+            let mark = Watermark {
+                last_date: at(1),
+                last_id: id as i32,
+            };
+            store.save(command, chat, &mark).unwrap();
+        }
+
+        for (id, (command, chat)) in pairs.iter().enumerate() {
+            let mark = store.load(command, chat).unwrap().unwrap();
+            assert_eq!(mark.last_id, id as i32, "{command}/{chat}");
+        }
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), pairs.len());
+    }
+
+    #[test]
+    fn legacy_file_is_read_until_the_next_save() {
+        let dir = TempDir::new().unwrap();
+        let store = WatermarkStore::new(dir.path());
+        let old = Watermark {
+            last_date: at(1),
+            last_id: 7,
+        };
+        fs::write(
+            dir.path().join("digest__team_chat.json"),
+            serde_json::to_string(&old).unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(store.load("digest", "@team/chat").unwrap(), Some(old));
+
+        let new = Watermark {
+            last_date: at(2),
+            last_id: 8,
+        };
+        store.save("digest", "@team/chat", &new).unwrap();
+        assert_eq!(store.load("digest", "@team/chat").unwrap(), Some(new));
+    }
+
+    #[test]
+    fn corrupt_file_is_an_error() {
+        let dir = TempDir::new().unwrap();
+        let store = WatermarkStore::new(dir.path());
+        fs::write(dir.path().join("digest_chat.json"), "not json").unwrap();
+        assert!(store.load("digest", "chat").is_err());
+    }
+
+    #[test]
+    fn advance_picks_newest_and_never_moves_back() {
+        let current = Some(Watermark {
+            last_date: at(5),
+            last_id: 50,
+        });
+
+        let advanced = Watermark::advance(current, vec![(at(6), 51), (at(7), 55)]);
+        assert_eq!(advanced.map(|w| w.last_id), Some(55));
+
+        let stale = Watermark::advance(current, vec![(at(4), 40)]);
+        assert_eq!(stale, current);
+
+        assert_eq!(Watermark::advance(None, Vec::new()), None);
+    }
+
+    #[test]
+    fn bounds_without_watermark_use_fallback_window() {
+        let fallback = at(12) - Duration::hours(24);
+        let bounds = FetchBounds::new(None, Some(fallback));
+
+        assert_eq!(bounds.since, Some(fallback));
+        assert!(bounds.is_new(1));
+        assert!(bounds.in_range(at(12)));
+        assert!(!bounds.in_range(fallback - Duration::seconds(1)));
+    }
+
+    #[test]
+    fn bounds_with_watermark_start_after_last_message() {
+        let mark = Watermark {
+            last_date: at(9),
+            last_id: 100,
+        };
+        let bounds = FetchBounds::new(Some(&mark), Some(at(0)));
+
+        assert_eq!(bounds.since, Some(at(9)));
+        assert!(!bounds.is_new(100));
+        assert!(bounds.is_new(101));
+        assert!(!bounds.in_range(at(8)));
+    }
+
+    #[test]
+    fn bounds_without_window_are_unbounded() {
+        let bounds = FetchBounds::new(None, None);
+        assert!(bounds.in_range(DateTime::<Utc>::MIN_UTC));
+        assert!(bounds.is_new(i32::MIN));
+    }
+}
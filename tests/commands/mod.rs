@@ -1,5 +1,6 @@
 //! Integration tests for commands module
 
 mod test_active_chats;
+mod test_analyze_offline;
 mod test_export;
 mod test_init_session;
@@ -1,12 +1,13 @@
 //! Tests for active_chats command
 
 use telegram_reader::commands::active_chats;
+use telegram_reader::commands::list_chats::ChatsOutput;
 
 #[tokio::test]
 #[ignore] // Requires Telegram connection
 async fn test_active_chats_run() {
     // This is an integration test that requires actual Telegram session
-    let result = active_chats::run(5).await;
+    let result = active_chats::run(5, ChatsOutput::Table).await;
     // Should either succeed or fail with a session error
     assert!(result.is_ok() || result.is_err());
 }
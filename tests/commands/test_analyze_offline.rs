@@ -0,0 +1,149 @@
+//! Tests for `analyze --no-network` (JSONL input + stub LLM)
+
+use std::path::{Path, PathBuf};
+
+use telegram_reader::commands::chat_analyzer::{
+    run_offline, AnalyzerConfig, LlmProvider, OutputFormat, ScoreSource,
+};
+
+fn fixture() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/offline_chat.jsonl")
+}
+
+fn offline_config(output_dir: &Path) -> AnalyzerConfig {
+    AnalyzerConfig {
+        llm_provider: LlmProvider::Stub,
+        model: None,
+        output_format: OutputFormat::Both,
+        output_dir: output_dir.to_path_buf(),
+        verbose: false,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_analyze_runs_offline_from_jsonl() {
+    let dir = tempfile::TempDir::new().unwrap();
+
+    let result = run_offline("release_chat", &fixture(), offline_config(dir.path()))
+        .await
+        .unwrap();
+
+    assert_eq!(result.chat_name, "release_chat");
+    assert_eq!(result.category, "Offline");
+    // "ok" is shorter than the minimum length and the bot message is excluded
+    assert_eq!(result.activity_metrics.total_messages, 4);
+    assert_eq!(result.activity_metrics.active_users, 3);
+    assert_eq!(result.activity_metrics.reactions_count, 4);
+    assert_eq!(
+        result.date_range_start.as_deref(),
+        Some("2024-05-01T09:00:00+00:00")
+    );
+
+    // The stub leaves participants to the local engagement score
+    let names: Vec<&str> = result
+        .key_participants
+        .iter()
+        .map(|p| p.name.as_str())
+        .collect();
+    assert_eq!(names, vec!["@alice", "@carol", "@bob"]);
+    assert!(result
+        .key_participants
+        .iter()
+        .all(|p| p.score_source == ScoreSource::Local));
+
+    let mut written: Vec<String> = std::fs::read_dir(dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    written.sort();
+    assert_eq!(written.len(), 2);
+    assert!(written[0].starts_with("release_chat_") && written[0].ends_with(".json"));
+    assert!(written[1].ends_with(".md"));
+}
+
+#[tokio::test]
+async fn test_analyze_offline_is_deterministic() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let config = || AnalyzerConfig {
+        strict: true,
+        ..offline_config(dir.path())
+    };
+
+    let first = run_offline("release_chat", &fixture(), config())
+        .await
+        .unwrap();
+    let second = run_offline("release_chat", &fixture(), config())
+        .await
+        .unwrap();
+
+    let scores = |r: &telegram_reader::commands::chat_analyzer::ChatAnalysisResult| {
+        r.key_participants
+            .iter()
+            .map(|p| (p.name.clone(), p.engagement_score))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(scores(&first), scores(&second));
+}
+
+#[tokio::test]
+async fn test_analyze_offline_reports_missing_input() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let missing = dir.path().join("missing.jsonl");
+
+    let result = run_offline("release_chat", &missing, offline_config(dir.path())).await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_analyze_offline_explains_empty_result() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let config = AnalyzerConfig {
+        min_message_length: 10_000,
+        ..offline_config(dir.path())
+    };
+
+    let err = run_offline("release_chat", &fixture(), config)
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("nothing matched (scanned 6 messages over 1 day)"),
+        "{}",
+        err
+    );
+}
+
+#[tokio::test]
+async fn test_analyze_offline_appends_results_as_jsonl() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let history = dir.path().join("history/analysis.jsonl");
+    let config = || AnalyzerConfig {
+        output_format: OutputFormat::None,
+        append_to: Some(history.clone()),
+        ..offline_config(dir.path())
+    };
+
+    for _ in 0..3 {
+        run_offline("release_chat", &fixture(), config())
+            .await
+            .unwrap();
+    }
+
+    let content = std::fs::read_to_string(&history).unwrap();
+    let lines: Vec<&str> = content.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(content.ends_with('\n'));
+    for line in lines {
+        let value: serde_json::Value = serde_json::from_str(line).unwrap();
+        assert_eq!(value["chat_name"], "release_chat");
+        assert_eq!(value["activity_metrics"]["total_messages"], 4);
+    }
+
+    // `none` writes no per-run files next to the history
+    let written: Vec<_> = std::fs::read_dir(dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert_eq!(written, vec![std::ffi::OsString::from("history")]);
+}
@@ -136,7 +136,7 @@ fn test_chunker_single_word() {
 #[test]
 fn test_chunk_has_metadata() {
     let chunk = Chunk::new("test text".into(), 0, 2, "my_source");
-    assert!(!chunk.id.is_nil());
+    assert!(!chunk.id.is_empty());
     assert_eq!(chunk.text, "test text");
     assert_eq!(chunk.start, 0);
     assert_eq!(chunk.end, 2);